@@ -0,0 +1,66 @@
+-- Two-factor authentication (TOTP) and token version invalidation
+
+-- Bumping token_version invalidates every JWT issued before the bump
+ALTER TABLE users ADD COLUMN IF NOT EXISTS token_version INTEGER NOT NULL DEFAULT 0;
+
+-- TOTP secret is stored encrypted (JWE); 2FA only applies once totp_enabled is set
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_secret_encrypted TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled BOOLEAN NOT NULL DEFAULT false;
+-- Last accepted time step, used to reject replayed codes
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_last_used_step BIGINT;
+
+-- Single-use backup codes (bcrypt hashed)
+CREATE TABLE IF NOT EXISTS user_backup_codes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(255) NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_backup_codes_user_id ON user_backup_codes(user_id);
+
+-- authenticate_user now also returns the 2FA state and token version
+DROP FUNCTION IF EXISTS authenticate_user(TEXT);
+
+CREATE OR REPLACE FUNCTION authenticate_user(user_email TEXT)
+RETURNS TABLE(
+    id UUID,
+    tenant_id UUID,
+    email TEXT,
+    first_name TEXT,
+    last_name TEXT,
+    role TEXT,
+    active BOOLEAN,
+    created_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ,
+    password_hash TEXT,
+    token_version INTEGER,
+    totp_enabled BOOLEAN
+) 
+SECURITY DEFINER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT 
+        u.id,
+        u.tenant_id,
+        u.email::TEXT,
+        u.first_name::TEXT,
+        u.last_name::TEXT,
+        u.role::TEXT,
+        u.active,
+        u.created_at,
+        u.updated_at,
+        u.password_hash::TEXT,
+        u.token_version,
+        u.totp_enabled
+    FROM users u
+    WHERE u.email = user_email
+    AND u.active = true;
+END;
+$$;
+
+ALTER FUNCTION authenticate_user(TEXT) OWNER TO postgres;
+GRANT EXECUTE ON FUNCTION authenticate_user(TEXT) TO quillspace;
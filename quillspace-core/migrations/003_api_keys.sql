@@ -0,0 +1,48 @@
+-- Tenant-scoped API keys for server-to-server access
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    -- Visible part of the key (qs_<id>), used for lookup and display
+    key_prefix VARCHAR(32) UNIQUE NOT NULL,
+    -- SHA-256 of the full key; the key itself is never stored
+    key_hash VARCHAR(64) NOT NULL,
+    role user_role NOT NULL DEFAULT 'viewer',
+    -- resource:action scopes; empty means the role's full permissions
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_tenant_id ON api_keys(tenant_id);
+
+ALTER TABLE api_keys ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_api_keys ON api_keys;
+CREATE POLICY tenant_isolation_api_keys ON api_keys
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
+
+-- Key lookup happens before the tenant is known, so it bypasses RLS like authenticate_user
+CREATE OR REPLACE FUNCTION authenticate_api_key(prefix TEXT)
+RETURNS SETOF api_keys
+SECURITY DEFINER
+LANGUAGE plpgsql
+SET search_path = public
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT k.*
+    FROM api_keys k
+    WHERE k.key_prefix = prefix;
+END;
+$$;
+
+ALTER FUNCTION authenticate_api_key(TEXT) OWNER TO postgres;
+REVOKE ALL ON FUNCTION authenticate_api_key(TEXT) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION authenticate_api_key(TEXT) TO quillspace;
@@ -0,0 +1,4 @@
+-- Per-tenant fallback template rendered when a page's template no longer exists.
+-- NULL means the platform default from configuration is used.
+
+ALTER TABLE tenants ADD COLUMN IF NOT EXISTS fallback_template VARCHAR(255);
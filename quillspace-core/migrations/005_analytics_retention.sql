@@ -0,0 +1,23 @@
+-- Per-tenant analytics retention. NULL means the platform default from configuration.
+
+ALTER TABLE tenants ADD COLUMN IF NOT EXISTS analytics_retention_days INTEGER
+    CHECK (analytics_retention_days IS NULL OR analytics_retention_days > 0);
+
+-- The purge job runs outside any tenant context, so it bypasses RLS like authenticate_user
+CREATE OR REPLACE FUNCTION analytics_retention_overrides()
+RETURNS TABLE (tenant_id UUID, retention_days INTEGER)
+SECURITY DEFINER
+LANGUAGE plpgsql
+SET search_path = public
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT t.id, t.analytics_retention_days
+    FROM tenants t
+    WHERE t.analytics_retention_days IS NOT NULL;
+END;
+$$;
+
+ALTER FUNCTION analytics_retention_overrides() OWNER TO postgres;
+REVOKE ALL ON FUNCTION analytics_retention_overrides() FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION analytics_retention_overrides() TO quillspace;
@@ -0,0 +1,18 @@
+-- Global subdomain lookup. Subdomains are unique across tenants, so the check has to see
+-- every site; running it as the owner avoids toggling FORCE ROW LEVEL SECURITY on sites.
+
+CREATE OR REPLACE FUNCTION subdomain_taken(p_subdomain TEXT)
+RETURNS BOOLEAN
+SECURITY DEFINER
+STABLE
+LANGUAGE plpgsql
+SET search_path = public
+AS $$
+BEGIN
+    RETURN EXISTS (SELECT 1 FROM sites WHERE subdomain = p_subdomain);
+END;
+$$;
+
+ALTER FUNCTION subdomain_taken(TEXT) OWNER TO postgres;
+REVOKE ALL ON FUNCTION subdomain_taken(TEXT) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION subdomain_taken(TEXT) TO quillspace;
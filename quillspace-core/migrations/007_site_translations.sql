@@ -0,0 +1,25 @@
+-- Per-site translation strings looked up by the `t(key)` template function.
+-- One row per key and locale; the site's default locale is used when a locale is missing.
+
+CREATE TABLE IF NOT EXISTS site_translations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    site_id UUID NOT NULL REFERENCES sites(id) ON DELETE CASCADE,
+    key VARCHAR(255) NOT NULL,
+    locale VARCHAR(35) NOT NULL,
+    value TEXT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE (site_id, key, locale)
+);
+
+CREATE INDEX IF NOT EXISTS idx_site_translations_site_id ON site_translations(site_id);
+
+ALTER TABLE site_translations ENABLE ROW LEVEL SECURITY;
+ALTER TABLE site_translations FORCE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_site_translations ON site_translations;
+CREATE POLICY tenant_isolation_site_translations ON site_translations
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
@@ -0,0 +1,12 @@
+-- Per-locale content variants. Translations of the same post share a translation_group_id,
+-- which is the id of the original post.
+
+ALTER TABLE content ADD COLUMN IF NOT EXISTS locale VARCHAR(35) NOT NULL DEFAULT 'en-US';
+ALTER TABLE content ADD COLUMN IF NOT EXISTS translation_group_id UUID;
+
+UPDATE content SET translation_group_id = id WHERE translation_group_id IS NULL;
+ALTER TABLE content ALTER COLUMN translation_group_id SET NOT NULL;
+
+-- One variant per locale within a group
+CREATE UNIQUE INDEX IF NOT EXISTS idx_content_translation_group_locale ON content(translation_group_id, locale);
+CREATE INDEX IF NOT EXISTS idx_content_tenant_locale ON content(tenant_id, locale);
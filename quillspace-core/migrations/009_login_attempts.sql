@@ -0,0 +1,12 @@
+-- Failed password logins per account and per IP, shared by every API instance.
+-- Keys are `account:<sha256 of email>` or `ip:<address>`; the table holds no tenant
+-- data and is only read before a user is authenticated, so it has no RLS policy.
+
+CREATE TABLE IF NOT EXISTS login_attempts (
+    key VARCHAR(128) PRIMARY KEY,
+    failures INTEGER NOT NULL DEFAULT 0,
+    last_failure_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_attempts_last_failure_at ON login_attempts(last_failure_at);
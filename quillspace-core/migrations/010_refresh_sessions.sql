@@ -0,0 +1,46 @@
+-- Refresh-token sessions: one row per login, listed and revoked by the user.
+-- Only the SHA-256 of the refresh token is stored.
+
+CREATE TABLE IF NOT EXISTS refresh_sessions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) UNIQUE NOT NULL,
+    -- users.token_version at login; bumping it ends the session too
+    token_version INTEGER NOT NULL DEFAULT 0,
+    user_agent TEXT,
+    ip_address VARCHAR(64),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_refresh_sessions_user_id ON refresh_sessions(user_id);
+
+ALTER TABLE refresh_sessions ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_refresh_sessions ON refresh_sessions;
+CREATE POLICY tenant_isolation_refresh_sessions ON refresh_sessions
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
+
+-- Refreshing happens before the tenant is known, so the lookup bypasses RLS like authenticate_api_key
+CREATE OR REPLACE FUNCTION authenticate_refresh_token(hash TEXT)
+RETURNS SETOF refresh_sessions
+SECURITY DEFINER
+LANGUAGE plpgsql
+SET search_path = public
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT s.*
+    FROM refresh_sessions s
+    WHERE s.token_hash = hash;
+END;
+$$;
+
+ALTER FUNCTION authenticate_refresh_token(TEXT) OWNER TO postgres;
+REVOKE ALL ON FUNCTION authenticate_refresh_token(TEXT) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION authenticate_refresh_token(TEXT) TO quillspace;
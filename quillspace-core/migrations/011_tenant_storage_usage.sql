@@ -0,0 +1,29 @@
+-- Running total of asset bytes per tenant, checked against the plan quota on upload.
+-- Kept in step with assets inside the same transaction as every insert and delete.
+
+CREATE TABLE IF NOT EXISTS tenant_storage_usage (
+    tenant_id UUID PRIMARY KEY REFERENCES tenants(id) ON DELETE CASCADE,
+    used_bytes BIGINT NOT NULL DEFAULT 0 CHECK (used_bytes >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE tenant_storage_usage ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_tenant_storage_usage ON tenant_storage_usage;
+CREATE POLICY tenant_isolation_tenant_storage_usage ON tenant_storage_usage
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
+
+-- Start from the assets already stored
+DO $$
+BEGIN
+    IF to_regclass('assets') IS NOT NULL THEN
+        INSERT INTO tenant_storage_usage (tenant_id, used_bytes)
+        SELECT tenant_id, COALESCE(SUM(file_size), 0)
+        FROM assets
+        GROUP BY tenant_id
+        ON CONFLICT (tenant_id) DO UPDATE SET used_bytes = EXCLUDED.used_bytes, updated_at = NOW();
+    END IF;
+END;
+$$;
@@ -0,0 +1,22 @@
+-- Co-authors of content, in byline order. The primary author stays in content.author_id
+-- and always comes first; rows here are the additional authors.
+
+CREATE TABLE IF NOT EXISTS content_authors (
+    content_id UUID NOT NULL REFERENCES content(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    -- 1-based byline position; 0 is the primary author
+    position INTEGER NOT NULL CHECK (position > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (content_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_content_authors_user_id ON content_authors(user_id);
+
+ALTER TABLE content_authors ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_content_authors ON content_authors;
+CREATE POLICY tenant_isolation_content_authors ON content_authors
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
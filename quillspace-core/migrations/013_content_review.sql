@@ -0,0 +1,14 @@
+-- Review workflow: draft -> pending_review -> published. Content is only published
+-- once approved; the latest review is recorded on the content row.
+
+ALTER TABLE content ADD COLUMN IF NOT EXISTS review_decision VARCHAR(20)
+    CHECK (review_decision IN ('approved', 'rejected'));
+ALTER TABLE content ADD COLUMN IF NOT EXISTS reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE content ADD COLUMN IF NOT EXISTS reviewed_at TIMESTAMPTZ;
+ALTER TABLE content ADD COLUMN IF NOT EXISTS review_comment TEXT;
+
+-- Content published before the workflow existed counts as approved, so it can be republished
+UPDATE content SET review_decision = 'approved'
+WHERE review_decision IS NULL AND lower(status) = 'published';
+
+CREATE INDEX IF NOT EXISTS idx_content_tenant_status ON content(tenant_id, status);
@@ -0,0 +1,16 @@
+-- Media library: content hashes to spot re-uploads of identical files, and image
+-- dimensions for variant URLs.
+
+DO $$
+BEGIN
+    IF to_regclass('assets') IS NOT NULL THEN
+        ALTER TABLE assets ADD COLUMN IF NOT EXISTS content_hash VARCHAR(64);
+        ALTER TABLE assets ADD COLUMN IF NOT EXISTS width INTEGER CHECK (width > 0);
+        ALTER TABLE assets ADD COLUMN IF NOT EXISTS height INTEGER CHECK (height > 0);
+
+        CREATE INDEX IF NOT EXISTS idx_assets_tenant_content_hash ON assets(tenant_id, content_hash)
+            WHERE content_hash IS NOT NULL;
+        CREATE INDEX IF NOT EXISTS idx_assets_tenant_created_at ON assets(tenant_id, created_at DESC);
+    END IF;
+END;
+$$;
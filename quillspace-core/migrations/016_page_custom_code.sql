@@ -0,0 +1,11 @@
+-- Per-page custom code: `<style>`/`<script>` elements injected before `</head>` and
+-- `</body>` when the page is served. Scripts need the tenant's `custom_scripts` capability.
+
+DO $$
+BEGIN
+    IF to_regclass('pages') IS NOT NULL THEN
+        ALTER TABLE pages ADD COLUMN IF NOT EXISTS custom_head TEXT;
+        ALTER TABLE pages ADD COLUMN IF NOT EXISTS custom_body TEXT;
+    END IF;
+END;
+$$;
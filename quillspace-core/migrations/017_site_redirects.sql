@@ -0,0 +1,24 @@
+-- Redirect rules per site, checked by the public serving path before a page is looked up.
+-- `source_path` is an exact path or a prefix ending in `*`; exact rules win over prefixes,
+-- and the longest prefix wins among prefixes.
+
+CREATE TABLE IF NOT EXISTS site_redirects (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    site_id UUID NOT NULL REFERENCES sites(id) ON DELETE CASCADE,
+    source_path VARCHAR(2048) NOT NULL,
+    destination VARCHAR(2048) NOT NULL,
+    status_code SMALLINT NOT NULL DEFAULT 301 CHECK (status_code IN (301, 302)),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE (site_id, source_path)
+);
+
+ALTER TABLE site_redirects ENABLE ROW LEVEL SECURITY;
+ALTER TABLE site_redirects FORCE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_site_redirects ON site_redirects;
+CREATE POLICY tenant_isolation_site_redirects ON site_redirects
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
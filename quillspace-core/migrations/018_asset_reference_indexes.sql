@@ -0,0 +1,19 @@
+-- Trigram indexes so batch asset deletion can find pages and content items that
+-- mention an asset (by id, CDN URL or storage path) without scanning every row.
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+DO $$
+BEGIN
+    IF to_regclass('pages') IS NOT NULL THEN
+        CREATE INDEX IF NOT EXISTS idx_pages_puck_data_trgm ON pages USING gin ((puck_data::text) gin_trgm_ops);
+    END IF;
+
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'content' AND column_name = 'body'
+    ) THEN
+        CREATE INDEX IF NOT EXISTS idx_content_body_trgm ON content USING gin (body gin_trgm_ops);
+    END IF;
+END;
+$$;
@@ -0,0 +1,24 @@
+-- Dead-letter support for transactional email jobs. A job that fails for good (three
+-- attempts, or a permanent provider error such as a suppressed address) stays `failed`
+-- with its last error, so admins can review and requeue it.
+
+CREATE TABLE IF NOT EXISTS email_jobs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    booking_id UUID NOT NULL,
+    email_type VARCHAR(64) NOT NULL,
+    recipient_email VARCHAR(320) NOT NULL,
+    template_variables JSONB NOT NULL DEFAULT '{}',
+    scheduled_for TIMESTAMPTZ NOT NULL,
+    sent_at TIMESTAMPTZ,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    retry_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+ALTER TABLE email_jobs ADD COLUMN IF NOT EXISTS last_error TEXT;
+ALTER TABLE email_jobs ADD COLUMN IF NOT EXISTS failure_kind VARCHAR(16)
+    CHECK (failure_kind IN ('retryable', 'permanent'));
+ALTER TABLE email_jobs ADD COLUMN IF NOT EXISTS failed_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_email_jobs_pending ON email_jobs(scheduled_for) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_email_jobs_failed ON email_jobs(failed_at DESC) WHERE status = 'failed';
@@ -0,0 +1,22 @@
+-- Invitations for users created on someone else's behalf (e.g. a tenant's first admin
+-- at bootstrap). The invite email carries the token; only its SHA-256 is stored.
+
+CREATE TABLE IF NOT EXISTS user_invites (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) UNIQUE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    accepted_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_invites_user_id ON user_invites(user_id);
+
+ALTER TABLE user_invites ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_user_invites ON user_invites;
+CREATE POLICY tenant_isolation_user_invites ON user_invites
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
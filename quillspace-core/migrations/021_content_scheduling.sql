@@ -0,0 +1,33 @@
+-- Scheduled publishing. Times are stored in UTC; a user's wall-clock input is read in
+-- their own timezone, or their tenant's (`tenants.settings->>'timezone'`) when unset.
+
+ALTER TABLE content ADD COLUMN IF NOT EXISTS scheduled_publish_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS timezone VARCHAR(64);
+
+CREATE INDEX IF NOT EXISTS idx_content_scheduled_publish
+    ON content(scheduled_publish_at)
+    WHERE scheduled_publish_at IS NOT NULL;
+
+-- The scheduler publishes due content for every tenant, so like subdomain_taken() it runs
+-- as the owner rather than toggling FORCE ROW LEVEL SECURITY on content.
+CREATE OR REPLACE FUNCTION publish_scheduled_content(p_now TIMESTAMPTZ, p_status TEXT)
+RETURNS INTEGER
+SECURITY DEFINER
+VOLATILE
+LANGUAGE plpgsql
+SET search_path = public
+AS $$
+DECLARE
+    published INTEGER;
+BEGIN
+    UPDATE content
+    SET status = p_status, published_at = scheduled_publish_at, scheduled_publish_at = NULL, updated_at = p_now
+    WHERE scheduled_publish_at <= p_now AND review_decision = 'approved';
+    GET DIAGNOSTICS published = ROW_COUNT;
+    RETURN published;
+END;
+$$;
+
+ALTER FUNCTION publish_scheduled_content(TIMESTAMPTZ, TEXT) OWNER TO postgres;
+REVOKE ALL ON FUNCTION publish_scheduled_content(TIMESTAMPTZ, TEXT) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION publish_scheduled_content(TIMESTAMPTZ, TEXT) TO quillspace;
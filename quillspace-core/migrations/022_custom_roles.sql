@@ -0,0 +1,46 @@
+-- Tenant-defined roles. Each grants a set of `resource:action` permissions, enforced by
+-- Casbin alongside the built-in admin/editor/viewer roles. A user with a custom role is
+-- authorized by it alone; without one, their built-in role applies.
+
+CREATE TABLE IF NOT EXISTS custom_roles (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    description TEXT,
+    permissions TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (tenant_id, name)
+);
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS custom_role_id UUID REFERENCES custom_roles(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_users_custom_role_id ON users(custom_role_id) WHERE custom_role_id IS NOT NULL;
+
+ALTER TABLE custom_roles ENABLE ROW LEVEL SECURITY;
+ALTER TABLE custom_roles FORCE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_custom_roles ON custom_roles;
+CREATE POLICY tenant_isolation_custom_roles ON custom_roles
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
+
+-- Every tenant's roles and members, loaded into the authorizer at startup. Like
+-- subdomain_taken() it runs as the owner so tenant isolation stays forced on the table.
+CREATE OR REPLACE FUNCTION custom_role_grants()
+RETURNS TABLE (role_id UUID, tenant_id UUID, permissions TEXT[], user_ids UUID[])
+SECURITY DEFINER
+STABLE
+LANGUAGE sql
+SET search_path = public
+AS $$
+    SELECT r.id, r.tenant_id, r.permissions,
+           COALESCE(array_agg(u.id) FILTER (WHERE u.id IS NOT NULL), '{}')
+    FROM custom_roles r
+    LEFT JOIN users u ON u.custom_role_id = r.id AND u.tenant_id = r.tenant_id
+    GROUP BY r.id;
+$$;
+
+ALTER FUNCTION custom_role_grants() OWNER TO postgres;
+REVOKE ALL ON FUNCTION custom_role_grants() FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION custom_role_grants() TO quillspace;
@@ -0,0 +1,11 @@
+-- Per-site access protection for sites shared before launch: `{"mode": "none"}`,
+-- `{"mode": "password", "password_hash": ...}` or `{"mode": "tokens", "token_hashes": [...]}`.
+-- Protected sites are served only to visitors who unlock them, and left out of sitemaps.
+
+DO $$
+BEGIN
+    IF to_regclass('sites') IS NOT NULL THEN
+        ALTER TABLE sites ADD COLUMN IF NOT EXISTS access_protection JSONB NOT NULL DEFAULT '{"mode": "none"}';
+    END IF;
+END;
+$$;
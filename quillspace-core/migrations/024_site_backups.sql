@@ -0,0 +1,42 @@
+-- Scheduled site backups. Each row indexes one snapshot in the export format, kept in the
+-- object store under `storage_key`. Backups outlive their site, so `site_id` is not a
+-- foreign key: a deleted site can still be restored as a new one.
+
+CREATE TABLE IF NOT EXISTS site_backups (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    site_id UUID NOT NULL,
+    site_name VARCHAR(255) NOT NULL,
+    storage_key TEXT NOT NULL UNIQUE,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+    page_count INTEGER NOT NULL CHECK (page_count >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_site_backups_tenant_site_created
+    ON site_backups(tenant_id, site_id, created_at DESC);
+
+ALTER TABLE site_backups ENABLE ROW LEVEL SECURITY;
+ALTER TABLE site_backups FORCE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_site_backups ON site_backups;
+CREATE POLICY tenant_isolation_site_backups ON site_backups
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
+
+-- Every tenant's sites, for the backup job. Like subdomain_taken() it runs as the owner
+-- so tenant isolation stays forced on sites.
+CREATE OR REPLACE FUNCTION site_backup_targets()
+RETURNS TABLE (tenant_id UUID, site_id UUID)
+SECURITY DEFINER
+STABLE
+LANGUAGE sql
+SET search_path = public
+AS $$
+    SELECT tenant_id, id FROM sites ORDER BY tenant_id, created_at;
+$$;
+
+ALTER FUNCTION site_backup_targets() OWNER TO postgres;
+REVOKE ALL ON FUNCTION site_backup_targets() FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION site_backup_targets() TO quillspace;
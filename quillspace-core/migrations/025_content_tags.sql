@@ -0,0 +1,8 @@
+-- Tags on content, for "related posts". Related posts are found among the tenant's
+-- published content sharing a tag (GIN on tags) or with a similar title (trigram GIN on
+-- title, pg_trgm being enabled by 018_asset_reference_indexes.sql).
+
+ALTER TABLE content ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_content_tags ON content USING gin (tags);
+CREATE INDEX IF NOT EXISTS idx_content_title_trgm ON content USING gin (title gin_trgm_ops);
@@ -0,0 +1,56 @@
+-- Revision history of content. Every insert, and every update that changes the title
+-- or body, records the new text as the next revision of the content, whichever code
+-- path wrote it. Revisions are compared with GET /api/content/:id/revisions/diff.
+
+CREATE TABLE IF NOT EXISTS content_revisions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    content_id UUID NOT NULL REFERENCES content(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    title VARCHAR(500) NOT NULL,
+    body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (content_id, revision)
+);
+
+ALTER TABLE content_revisions ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_content_revisions ON content_revisions;
+CREATE POLICY tenant_isolation_content_revisions ON content_revisions
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
+
+-- Runs as the owner so revisions are recorded even by writers without a tenant
+-- context set, such as background jobs.
+CREATE OR REPLACE FUNCTION record_content_revision()
+RETURNS TRIGGER
+SECURITY DEFINER
+LANGUAGE plpgsql
+SET search_path = public
+AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' AND NEW.title IS NOT DISTINCT FROM OLD.title AND NEW.body IS NOT DISTINCT FROM OLD.body THEN
+        RETURN NEW;
+    END IF;
+
+    INSERT INTO content_revisions (tenant_id, content_id, revision, title, body)
+    SELECT NEW.tenant_id, NEW.id, COALESCE(MAX(revision), 0) + 1, NEW.title, COALESCE(NEW.body, '')
+    FROM content_revisions
+    WHERE content_id = NEW.id;
+    RETURN NEW;
+END;
+$$;
+
+ALTER FUNCTION record_content_revision() OWNER TO postgres;
+REVOKE ALL ON FUNCTION record_content_revision() FROM PUBLIC;
+
+DROP TRIGGER IF EXISTS record_content_revision ON content;
+CREATE TRIGGER record_content_revision AFTER INSERT OR UPDATE OF title, body ON content
+    FOR EACH ROW EXECUTE FUNCTION record_content_revision();
+
+-- Existing content starts its history at its current text
+INSERT INTO content_revisions (tenant_id, content_id, revision, title, body, created_at)
+SELECT c.tenant_id, c.id, 1, c.title, COALESCE(c.body, ''), c.updated_at
+FROM content c
+WHERE NOT EXISTS (SELECT 1 FROM content_revisions r WHERE r.content_id = c.id);
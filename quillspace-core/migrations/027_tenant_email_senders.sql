@@ -0,0 +1,32 @@
+-- Per-tenant email sender. A tenant's mail goes out from its own address only once
+-- the domain is verified: a TXT record proving ownership, SPF allowing the platform's
+-- mail servers and, when a selector is set, a DKIM key. Until then, and for tenants
+-- without a row, mail is sent from the platform default in `email`.
+
+CREATE TABLE IF NOT EXISTS tenant_email_senders (
+    tenant_id UUID PRIMARY KEY REFERENCES tenants(id) ON DELETE CASCADE,
+    from_address VARCHAR(320) NOT NULL,
+    from_name VARCHAR(255),
+    reply_to VARCHAR(320),
+    -- Domain of from_address, the one verified
+    domain VARCHAR(253) NOT NULL,
+    dkim_selector VARCHAR(63),
+    -- Domain the DKIM key is published under when a provider signs for the tenant;
+    -- NULL means the sender domain itself
+    dkim_domain VARCHAR(253),
+    verification_token VARCHAR(64) NOT NULL,
+    verified_at TIMESTAMPTZ,
+    last_checked_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE tenant_email_senders ENABLE ROW LEVEL SECURITY;
+ALTER TABLE tenant_email_senders FORCE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_tenant_email_senders ON tenant_email_senders;
+CREATE POLICY tenant_isolation_tenant_email_senders ON tenant_email_senders
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
@@ -0,0 +1,148 @@
+-- Publishing windows. Pages can be published on a schedule like content, and both can
+-- be given an expiry (`unpublish_at`) after which they go back to unpublished, so an
+-- item can be live only between two times. Each scheduled publish and unpublish is
+-- recorded in publish_schedule_events.
+
+ALTER TABLE content ADD COLUMN IF NOT EXISTS unpublish_at TIMESTAMPTZ;
+ALTER TABLE pages ADD COLUMN IF NOT EXISTS scheduled_publish_at TIMESTAMPTZ;
+ALTER TABLE pages ADD COLUMN IF NOT EXISTS unpublish_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_content_unpublish_at
+    ON content(unpublish_at)
+    WHERE unpublish_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_pages_scheduled_publish
+    ON pages(scheduled_publish_at)
+    WHERE scheduled_publish_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_pages_unpublish_at
+    ON pages(unpublish_at)
+    WHERE unpublish_at IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS publish_schedule_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    target_type VARCHAR(16) NOT NULL CHECK (target_type IN ('content', 'page')),
+    target_id UUID NOT NULL,
+    action VARCHAR(16) NOT NULL CHECK (action IN ('publish', 'unpublish')),
+    -- The time the item was scheduled for; occurred_at is when the scheduler got to it
+    scheduled_for TIMESTAMPTZ NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_publish_schedule_events_target
+    ON publish_schedule_events(tenant_id, target_id, occurred_at DESC);
+
+ALTER TABLE publish_schedule_events ENABLE ROW LEVEL SECURITY;
+ALTER TABLE publish_schedule_events FORCE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS tenant_isolation_publish_schedule_events ON publish_schedule_events;
+CREATE POLICY tenant_isolation_publish_schedule_events ON publish_schedule_events
+    FOR ALL
+    USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+    WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
+
+-- Scheduled content publishing, as in 021_content_scheduling.sql, now recording each publish
+CREATE OR REPLACE FUNCTION publish_scheduled_content(p_now TIMESTAMPTZ, p_status TEXT)
+RETURNS INTEGER
+SECURITY DEFINER
+VOLATILE
+LANGUAGE plpgsql
+SET search_path = public
+AS $$
+DECLARE
+    published INTEGER;
+BEGIN
+    WITH due AS (
+        UPDATE content
+        SET status = p_status, published_at = scheduled_publish_at, scheduled_publish_at = NULL, updated_at = p_now
+        WHERE scheduled_publish_at <= p_now AND review_decision = 'approved'
+        RETURNING tenant_id, id, published_at
+    )
+    INSERT INTO publish_schedule_events (tenant_id, target_type, target_id, action, scheduled_for, occurred_at)
+    SELECT tenant_id, 'content', id, 'publish', published_at, p_now FROM due;
+    GET DIAGNOSTICS published = ROW_COUNT;
+    RETURN published;
+END;
+$$;
+
+-- Publish pages whose time has come, then unpublish pages and content past their expiry,
+-- in that order so a window that has wholly passed still opens and closes. With p_only
+-- set, only that page or content item is considered. Returns what changed.
+CREATE OR REPLACE FUNCTION apply_publishing_windows(p_now TIMESTAMPTZ, p_draft TEXT, p_only UUID DEFAULT NULL)
+RETURNS TABLE (
+    tenant_id UUID,
+    target_type TEXT,
+    target_id UUID,
+    site_id UUID,
+    action TEXT,
+    scheduled_for TIMESTAMPTZ
+)
+SECURITY DEFINER
+VOLATILE
+LANGUAGE plpgsql
+SET search_path = public
+AS $$
+#variable_conflict use_column
+BEGIN
+    -- Pages go live with the HTML rendered when they were scheduled
+    RETURN QUERY
+    WITH due AS (
+        UPDATE pages p
+        SET is_published = true, published_at = p.scheduled_publish_at, scheduled_publish_at = NULL, updated_at = p_now
+        WHERE p.scheduled_publish_at <= p_now
+          AND p.published_html IS NOT NULL
+          AND (p_only IS NULL OR p.id = p_only)
+        RETURNING p.tenant_id, p.id, p.site_id, p.published_at
+    ), recorded AS (
+        INSERT INTO publish_schedule_events (tenant_id, target_type, target_id, action, scheduled_for, occurred_at)
+        SELECT due.tenant_id, 'page', due.id, 'publish', due.published_at, p_now FROM due
+    )
+    SELECT due.tenant_id, 'page'::text, due.id, due.site_id, 'publish'::text, due.published_at FROM due;
+
+    -- A closed window also drops a publish still pending inside it
+    RETURN QUERY
+    WITH expired AS (
+        SELECT p.id, p.is_published AS was_published, p.unpublish_at
+        FROM pages p
+        WHERE p.unpublish_at <= p_now AND (p_only IS NULL OR p.id = p_only)
+        FOR UPDATE
+    ), due AS (
+        UPDATE pages p
+        SET is_published = false, published_at = NULL, scheduled_publish_at = NULL, unpublish_at = NULL, updated_at = p_now
+        FROM expired
+        WHERE p.id = expired.id
+        RETURNING p.tenant_id, p.id, p.site_id, expired.was_published, expired.unpublish_at
+    ), recorded AS (
+        INSERT INTO publish_schedule_events (tenant_id, target_type, target_id, action, scheduled_for, occurred_at)
+        SELECT due.tenant_id, 'page', due.id, 'unpublish', due.unpublish_at, p_now FROM due WHERE due.was_published
+    )
+    SELECT due.tenant_id, 'page'::text, due.id, due.site_id, 'unpublish'::text, due.unpublish_at
+    FROM due WHERE due.was_published;
+
+    -- Status casing differs between writers, as in the review migration
+    RETURN QUERY
+    WITH expired AS (
+        SELECT c.id, lower(c.status) = 'published' AS was_published, c.unpublish_at
+        FROM content c
+        WHERE c.unpublish_at <= p_now AND (p_only IS NULL OR c.id = p_only)
+        FOR UPDATE
+    ), due AS (
+        UPDATE content c
+        SET status = CASE WHEN expired.was_published THEN p_draft ELSE c.status END,
+            published_at = CASE WHEN expired.was_published THEN NULL ELSE c.published_at END,
+            scheduled_publish_at = NULL, unpublish_at = NULL, updated_at = p_now
+        FROM expired
+        WHERE c.id = expired.id
+        RETURNING c.tenant_id, c.id, expired.was_published, expired.unpublish_at
+    ), recorded AS (
+        INSERT INTO publish_schedule_events (tenant_id, target_type, target_id, action, scheduled_for, occurred_at)
+        SELECT due.tenant_id, 'content', due.id, 'unpublish', due.unpublish_at, p_now FROM due WHERE due.was_published
+    )
+    SELECT due.tenant_id, 'content'::text, due.id, NULL::uuid, 'unpublish'::text, due.unpublish_at
+    FROM due WHERE due.was_published;
+END;
+$$;
+
+ALTER FUNCTION publish_scheduled_content(TIMESTAMPTZ, TEXT) OWNER TO postgres;
+ALTER FUNCTION apply_publishing_windows(TIMESTAMPTZ, TEXT, UUID) OWNER TO postgres;
+REVOKE ALL ON FUNCTION apply_publishing_windows(TIMESTAMPTZ, TEXT, UUID) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION apply_publishing_windows(TIMESTAMPTZ, TEXT, UUID) TO quillspace;
@@ -0,0 +1,5 @@
+-- Tenant-wide default theme. It lives in tenant settings under "theme", which sites
+-- inherit and override field by field through their theme_config. Databases set up
+-- from these migrations alone have no settings column yet.
+
+ALTER TABLE tenants ADD COLUMN IF NOT EXISTS settings JSONB NOT NULL DEFAULT '{}';
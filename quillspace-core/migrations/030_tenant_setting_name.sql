@@ -0,0 +1,8 @@
+-- Every tenant isolation policy reads the tenant from quillspace.tenant_id. The
+-- original setup keyed the tenants policy on app.current_tenant_id; the users and
+-- content policies it created are replaced by the RLS setup at startup.
+
+DROP POLICY IF EXISTS tenant_isolation_tenants ON tenants;
+CREATE POLICY tenant_isolation_tenants ON tenants
+    FOR ALL
+    USING (id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
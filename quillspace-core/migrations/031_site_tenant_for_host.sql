@@ -0,0 +1,23 @@
+-- Public site lookup. A request for a subdomain or custom domain carries no tenant, so
+-- the tenant is resolved here, as the owner, before the site is read under RLS.
+-- Subdomains cannot contain dots and custom domains must, so one host matches one column.
+
+CREATE OR REPLACE FUNCTION site_tenant_for_host(p_host TEXT)
+RETURNS UUID
+SECURITY DEFINER
+STABLE
+LANGUAGE plpgsql
+SET search_path = public
+AS $$
+BEGIN
+    RETURN (
+        SELECT tenant_id FROM sites
+        WHERE subdomain = p_host OR custom_domain = p_host
+        LIMIT 1
+    );
+END;
+$$;
+
+ALTER FUNCTION site_tenant_for_host(TEXT) OWNER TO postgres;
+REVOKE ALL ON FUNCTION site_tenant_for_host(TEXT) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION site_tenant_for_host(TEXT) TO quillspace;
@@ -0,0 +1,4 @@
+-- Tenants are deactivated rather than deleted; the tenant routes and services only
+-- see active ones.
+
+ALTER TABLE tenants ADD COLUMN IF NOT EXISTS is_active BOOLEAN NOT NULL DEFAULT true;
@@ -0,0 +1,20 @@
+-- Invites are accepted before the user can log in, so the token lookup bypasses RLS
+-- like authenticate_refresh_token; the acceptance itself runs in the invite's tenant.
+
+CREATE OR REPLACE FUNCTION find_user_invite(hash TEXT)
+RETURNS SETOF user_invites
+SECURITY DEFINER
+LANGUAGE plpgsql
+SET search_path = public
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT i.*
+    FROM user_invites i
+    WHERE i.token_hash = hash;
+END;
+$$;
+
+ALTER FUNCTION find_user_invite(TEXT) OWNER TO postgres;
+REVOKE ALL ON FUNCTION find_user_invite(TEXT) FROM PUBLIC;
+GRANT EXECUTE ON FUNCTION find_user_invite(TEXT) TO quillspace;
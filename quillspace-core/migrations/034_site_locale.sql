@@ -0,0 +1,12 @@
+-- Site locales get their own columns instead of riding along in seo_settings.
+-- `locale` fixes the language every visitor sees; `default_locale` is what a
+-- multilingual site falls back to when the visitor's language has no match.
+
+ALTER TABLE sites ADD COLUMN IF NOT EXISTS locale VARCHAR(35);
+ALTER TABLE sites ADD COLUMN IF NOT EXISTS default_locale VARCHAR(35);
+
+UPDATE sites
+SET locale = NULLIF(TRIM(seo_settings->>'locale'), ''),
+    default_locale = NULLIF(TRIM(seo_settings->>'default_locale'), ''),
+    seo_settings = seo_settings - 'locale' - 'default_locale'
+WHERE seo_settings ?| ARRAY['locale', 'default_locale'];
@@ -0,0 +1,5 @@
+-- Email jobs sent on behalf of a tenant go out from its verified sender domain.
+-- Jobs without a tenant, such as consultation booking emails, use the platform sender.
+
+ALTER TABLE email_jobs ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id) ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS idx_email_jobs_tenant_failed ON email_jobs(tenant_id, failed_at DESC) WHERE status = 'failed';
@@ -0,0 +1,180 @@
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+/// Prefix identifying QuillSpace API keys in an `Authorization: Bearer` header
+pub const API_KEY_PREFIX: &str = "qs_";
+
+const KEY_ID_LEN: usize = 8;
+const KEY_SECRET_LEN: usize = 32;
+
+/// Freshly generated API key. `key` is shown to the caller once and never stored.
+pub struct GeneratedApiKey {
+    pub key: String,
+    pub prefix: String,
+    pub hash: String,
+}
+
+/// Generate a new key of the form `qs_<id>_<secret>`; `qs_<id>` is the visible prefix
+pub fn generate_api_key() -> GeneratedApiKey {
+    let mut rng = rand::thread_rng();
+    let key_id: String = (&mut rng)
+        .sample_iter(&Alphanumeric)
+        .take(KEY_ID_LEN)
+        .map(|c| (c as char).to_ascii_lowercase())
+        .collect();
+    let secret: String = (&mut rng)
+        .sample_iter(&Alphanumeric)
+        .take(KEY_SECRET_LEN)
+        .map(char::from)
+        .collect();
+
+    let key = format!("{}{}_{}", API_KEY_PREFIX, key_id, secret);
+    GeneratedApiKey {
+        prefix: format!("{}{}", API_KEY_PREFIX, key_id),
+        hash: hash_api_key(&key),
+        key,
+    }
+}
+
+/// Whether a bearer token looks like an API key rather than a JWT
+pub fn is_api_key(token: &str) -> bool {
+    token.starts_with(API_KEY_PREFIX)
+}
+
+/// Extract the visible prefix (`qs_<id>`) used to look a key up
+pub fn api_key_prefix(key: &str) -> Option<&str> {
+    let rest = key.strip_prefix(API_KEY_PREFIX)?;
+    let (key_id, secret) = rest.split_once('_')?;
+    if key_id.len() != KEY_ID_LEN || secret.len() != KEY_SECRET_LEN {
+        return None;
+    }
+    Some(&key[..API_KEY_PREFIX.len() + KEY_ID_LEN])
+}
+
+/// Keys are long random strings, so a fast digest is sufficient for storage
+pub fn hash_api_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Stored API key as needed for authentication
+#[derive(Debug, Clone)]
+pub struct ApiKeyRecord {
+    pub key_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKeyRecord {
+    /// Check a presented key against this record (hash, revocation and expiry)
+    pub fn authenticates(&self, key: &str, now: DateTime<Utc>) -> bool {
+        let presented = hash_api_key(key);
+        let hash_matches = presented.len() == self.key_hash.len()
+            && presented
+                .bytes()
+                .zip(self.key_hash.bytes())
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                == 0;
+
+        hash_matches
+            && self.revoked_at.is_none()
+            && self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// Validate a `resource:action` scope string (`*` allowed as the action)
+pub fn is_valid_scope(scope: &str) -> bool {
+    match scope.split_once(':') {
+        Some((resource, action)) => {
+            !resource.is_empty()
+                && !action.is_empty()
+                && resource.chars().all(|c| c.is_ascii_lowercase() || c == '_')
+                && (action == "*" || action.chars().all(|c| c.is_ascii_lowercase() || c == '_'))
+        }
+        None => false,
+    }
+}
+
+/// Check whether a scope list grants `action` on `resource`
+pub fn scope_allows(scopes: &[String], resource: &str, action: &str) -> bool {
+    scopes.iter().any(|scope| match scope.split_once(':') {
+        Some((r, a)) => r == resource && (a == action || a == "*"),
+        None => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn record_for(key: &GeneratedApiKey) -> ApiKeyRecord {
+        ApiKeyRecord {
+            key_hash: key.hash.clone(),
+            scopes: vec!["content:read".to_string(), "pages:*".to_string()],
+            expires_at: None,
+            revoked_at: None,
+        }
+    }
+
+    #[test]
+    fn test_generated_key_format() {
+        let generated = generate_api_key();
+        assert!(is_api_key(&generated.key));
+        assert_eq!(api_key_prefix(&generated.key), Some(generated.prefix.as_str()));
+        assert!(!generated.hash.contains(&generated.key));
+        assert_eq!(api_key_prefix("qs_short_key"), None);
+        assert!(!is_api_key("eyJhbGciOiJIUzI1NiJ9.payload.signature"));
+    }
+
+    #[test]
+    fn test_valid_key_authenticates() {
+        let generated = generate_api_key();
+        let record = record_for(&generated);
+
+        assert!(record.authenticates(&generated.key, Utc::now()));
+        assert!(!record.authenticates(&generate_api_key().key, Utc::now()));
+    }
+
+    #[test]
+    fn test_revoked_key_fails() {
+        let generated = generate_api_key();
+        let record = ApiKeyRecord {
+            revoked_at: Some(Utc::now()),
+            ..record_for(&generated)
+        };
+
+        assert!(!record.authenticates(&generated.key, Utc::now()));
+    }
+
+    #[test]
+    fn test_expired_key_fails() {
+        let generated = generate_api_key();
+        let now = Utc::now();
+        let record = ApiKeyRecord {
+            expires_at: Some(now - Duration::minutes(1)),
+            ..record_for(&generated)
+        };
+
+        assert!(!record.authenticates(&generated.key, now));
+    }
+
+    #[test]
+    fn test_scope_enforcement() {
+        let scopes = record_for(&generate_api_key()).scopes;
+
+        assert!(scope_allows(&scopes, "content", "read"));
+        assert!(!scope_allows(&scopes, "content", "write"));
+        assert!(scope_allows(&scopes, "pages", "publish"));
+        assert!(!scope_allows(&scopes, "users", "read"));
+
+        assert!(is_valid_scope("content:read"));
+        assert!(is_valid_scope("pages:*"));
+        assert!(!is_valid_scope("content"));
+        assert!(!is_valid_scope(":read"));
+    }
+}
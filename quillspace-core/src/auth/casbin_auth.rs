@@ -2,8 +2,9 @@ use casbin::{prelude::*, DefaultModel};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use axum::http::StatusCode;
-use crate::types::UserRole;
+use crate::{auth::jwt_helpers::AuthContext, types::UserRole};
 use tracing::{info, warn};
+use uuid::Uuid;
 
 /// Casbin-based authorization manager
 #[derive(Clone)]
@@ -43,7 +44,7 @@ m = g(r.sub, p.sub) && r.obj == p.obj && r.act == p.act && (r.tenant == p.tenant
         // Define comprehensive permissions for each role with wildcard tenant
         // VIEWER PERMISSIONS (read-only access)
         let viewer_permissions = vec![
-            ("content", "read"), ("sites", "read"), ("pages", "read"), 
+            ("content", "read"), ("content", "submit"), ("content", "comment"), ("sites", "read"), ("pages", "read"), 
             ("templates", "read"), ("assets", "read"), ("analytics", "read")
         ];
         for (resource, action) in viewer_permissions {
@@ -52,7 +53,8 @@ m = g(r.sub, p.sub) && r.obj == p.obj && r.act == p.act && (r.tenant == p.tenant
 
         // EDITOR PERMISSIONS (inherits viewer + content creation/editing)
         let editor_permissions = vec![
-            ("content", "write"), ("content", "update"), ("content", "publish"),
+            ("content", "write"), ("content", "update"), ("content", "publish"), ("content", "approve"),
+            ("content", "read_internal"),
             ("sites", "write"), ("sites", "update"), ("sites", "publish"),
             ("pages", "write"), ("pages", "update"), ("pages", "publish"),
             ("templates", "write"), ("templates", "update"),
@@ -83,14 +85,8 @@ m = g(r.sub, p.sub) && r.obj == p.obj && r.act == p.act && (r.tenant == p.tenant
 
     /// Check if a user has permission to perform an action on a resource within a tenant
     pub async fn enforce(&self, user_role: &UserRole, resource: &str, action: &str, tenant_id: &str) -> anyhow::Result<bool> {
-        let role_str = match user_role {
-            UserRole::Admin => "admin",
-            UserRole::Editor => "editor", 
-            UserRole::Viewer => "viewer",
-        };
-
         let enforcer = self.enforcer.read().await;
-        let result = enforcer.enforce(vec![role_str, resource, action, tenant_id])?;
+        let result = enforcer.enforce(vec![role_subject(user_role), resource, action, tenant_id])?;
         Ok(result)
     }
 
@@ -103,6 +99,84 @@ m = g(r.sub, p.sub) && r.obj == p.obj && r.act == p.act && (r.tenant == p.tenant
         }
     }
 
+    /// Check a permission for an authenticated request.
+    /// Scopes on the token can only narrow the role's permissions, never widen them.
+    /// A user assigned a custom role is authorized by that role instead of their built-in one.
+    pub async fn enforce_context(&self, auth_context: &AuthContext, resource: &str, action: &str) -> anyhow::Result<bool> {
+        if !auth_context.has_scope(resource, action) {
+            return Ok(false);
+        }
+
+        let user = user_subject(&auth_context.user_id);
+        let tenant_id = auth_context.tenant_id.to_string();
+        let enforcer = self.enforcer.read().await;
+        let subject = if enforcer.get_filtered_grouping_policy(0, vec![user.clone()]).is_empty() {
+            role_subject(&auth_context.user_role)
+        } else {
+            user.as_str()
+        };
+        Ok(enforcer.enforce(vec![subject, resource, action, tenant_id.as_str()])?)
+    }
+
+    /// Require permission for an authenticated request, returning 403 if not authorized
+    pub async fn require_context_permission(&self, auth_context: &AuthContext, resource: &str, action: &str) -> std::result::Result<(), StatusCode> {
+        match self.enforce_context(auth_context, resource, action).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(StatusCode::FORBIDDEN),
+            Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
+    /// Replace the permissions of a tenant's custom role. Its policies only match
+    /// requests in that tenant.
+    pub async fn set_custom_role_permissions(
+        &self,
+        tenant_id: &Uuid,
+        role_id: &Uuid,
+        permissions: &[(String, String)],
+    ) -> anyhow::Result<()> {
+        let role = custom_role_subject(role_id);
+        let mut enforcer = self.enforcer.write().await;
+        enforcer.remove_filtered_policy(0, vec![role.clone()]).await?;
+        let policies: Vec<Vec<String>> = permissions
+            .iter()
+            .map(|(resource, action)| vec![role.clone(), resource.clone(), action.clone(), tenant_id.to_string()])
+            .collect();
+        if !policies.is_empty() {
+            enforcer.add_policies(policies).await?;
+        }
+        Ok(())
+    }
+
+    /// Drop a custom role's permissions and its members' assignments
+    pub async fn remove_custom_role(&self, role_id: &Uuid) -> anyhow::Result<()> {
+        let role = custom_role_subject(role_id);
+        let mut enforcer = self.enforcer.write().await;
+        enforcer.remove_filtered_policy(0, vec![role.clone()]).await?;
+        enforcer.remove_filtered_grouping_policy(1, vec![role]).await?;
+        Ok(())
+    }
+
+    /// Give users a custom role, replacing any they had. With `None` they go back to
+    /// their built-in role.
+    pub async fn assign_custom_role(&self, user_ids: &[Uuid], role_id: Option<&Uuid>) -> anyhow::Result<()> {
+        let mut enforcer = self.enforcer.write().await;
+        for user_id in user_ids {
+            enforcer.remove_filtered_grouping_policy(0, vec![user_subject(user_id)]).await?;
+        }
+        if let Some(role_id) = role_id {
+            let role = custom_role_subject(role_id);
+            let groupings: Vec<Vec<String>> = user_ids
+                .iter()
+                .map(|user_id| vec![user_subject(user_id), role.clone()])
+                .collect();
+            if !groupings.is_empty() {
+                enforcer.add_grouping_policies(groupings).await?;
+            }
+        }
+        Ok(())
+    }
+
     /// Add a custom policy (for dynamic permissions)
     pub async fn add_policy(&self, subject: &str, object: &str, action: &str) -> anyhow::Result<bool> {
         let mut enforcer = self.enforcer.write().await;
@@ -119,17 +193,29 @@ m = g(r.sub, p.sub) && r.obj == p.obj && r.act == p.act && (r.tenant == p.tenant
 
     /// Get all policies for a subject
     pub async fn get_permissions_for_user(&self, user_role: &UserRole) -> Vec<Vec<String>> {
-        let role_str = match user_role {
-            UserRole::Admin => "admin",
-            UserRole::Editor => "editor",
-            UserRole::Viewer => "viewer",
-        };
-
         let enforcer = self.enforcer.read().await;
-        enforcer.get_permissions_for_user(role_str, None)
+        enforcer.get_permissions_for_user(role_subject(user_role), None)
+    }
+}
+
+/// Casbin subject of a built-in role
+fn role_subject(user_role: &UserRole) -> &'static str {
+    match user_role {
+        UserRole::Admin => "admin",
+        UserRole::Editor => "editor",
+        UserRole::Viewer => "viewer",
     }
 }
 
+/// Casbin subject of a custom role; ids keep it apart from the built-in roles
+fn custom_role_subject(role_id: &Uuid) -> String {
+    format!("role:{}", role_id)
+}
+
+fn user_subject(user_id: &Uuid) -> String {
+    format!("user:{}", user_id)
+}
+
 /// Resource schemas for authorization
 pub enum Resource {
     Content,
@@ -144,6 +230,22 @@ pub enum Resource {
 }
 
 impl Resource {
+    pub const ALL: [Resource; 9] = [
+        Resource::Content,
+        Resource::Users,
+        Resource::Tenants,
+        Resource::Analytics,
+        Resource::Sites,
+        Resource::Pages,
+        Resource::Templates,
+        Resource::Assets,
+        Resource::Security,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|resource| resource.as_str() == name)
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Resource::Content => "content",
@@ -162,24 +264,52 @@ impl Resource {
 /// Action schemas for authorization
 pub enum Action {
     Read,
+    /// See restricted content fields such as the author and review notes
+    ReadInternal,
     Write,
     Update,
     Delete,
     Publish,
     Archive,
+    Submit,
+    Approve,
+    Comment,
     Configure,
     Admin,
 }
 
 impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::Read,
+        Action::ReadInternal,
+        Action::Write,
+        Action::Update,
+        Action::Delete,
+        Action::Publish,
+        Action::Archive,
+        Action::Submit,
+        Action::Approve,
+        Action::Comment,
+        Action::Configure,
+        Action::Admin,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.as_str() == name)
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Action::Read => "read",
+            Action::ReadInternal => "read_internal",
             Action::Write => "write",
             Action::Update => "update", 
             Action::Delete => "delete",
             Action::Publish => "publish",
             Action::Archive => "archive",
+            Action::Submit => "submit",
+            Action::Approve => "approve",
+            Action::Comment => "comment",
             Action::Configure => "configure",
             Action::Admin => "admin",
         }
@@ -216,4 +346,75 @@ mod tests {
         assert!(auth.enforce(&UserRole::Admin, "templates", "delete", test_tenant).await.expect("Admin templates delete test failed"));
         assert!(!auth.enforce(&UserRole::Viewer, "templates", "write", test_tenant).await.expect("Viewer templates write test failed"));
     }
+
+    #[tokio::test]
+    async fn test_viewer_can_submit_for_review_but_not_approve() {
+        use crate::services::content_review::ReviewAction;
+
+        let auth = CasbinAuthorizer::new().await.expect("Failed to create Casbin authorizer");
+        let test_tenant = "11111111-1111-1111-1111-111111111111";
+        let submit = ReviewAction::Submit.permission();
+        let approve = ReviewAction::Approve.permission();
+
+        assert!(auth.enforce(&UserRole::Viewer, "content", submit, test_tenant).await.expect("Viewer submit test failed"));
+        assert!(!auth.enforce(&UserRole::Viewer, "content", approve, test_tenant).await.expect("Viewer approve test failed"));
+        assert_eq!(ReviewAction::Reject.permission(), approve);
+        assert!(auth.enforce(&UserRole::Editor, "content", approve, test_tenant).await.expect("Editor approve test failed"));
+        assert!(auth.enforce(&UserRole::Admin, "content", approve, test_tenant).await.expect("Admin approve test failed"));
+    }
+
+    #[tokio::test]
+    async fn test_scoped_token_can_list_but_not_create_content() {
+        use crate::auth::{jwt_helpers::extract_auth_context_with_role, JwtManager, TokenOptions, TokenSubject};
+        use axum::http::HeaderMap;
+
+        let auth = CasbinAuthorizer::new().await.expect("Failed to create Casbin authorizer");
+        let jwt_manager = JwtManager::new("test-secret-key-of-at-least-32-bytes", "quillspace");
+        let subject = TokenSubject {
+            user_id: "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa",
+            email: "admin@quillspace.com",
+            first_name: "System",
+            last_name: "Administrator",
+            role: "admin",
+            tenant_id: "11111111-1111-1111-1111-111111111111",
+        };
+        let options = TokenOptions {
+            scopes: Some(vec!["content:read".to_string()]),
+            site_id: Some("44444444-4444-4444-4444-444444444444".to_string()),
+            ttl: Some(chrono::Duration::minutes(15)),
+            ..TokenOptions::default()
+        };
+        let token = jwt_manager.generate_token_for(&subject, &options).expect("Failed to create scoped token");
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", format!("Bearer {}", token).parse().expect("Invalid header"));
+        let context = extract_auth_context_with_role(&headers, &jwt_manager).expect("Scoped token rejected");
+
+        // Listing content is within scope; creating is not, even though the admin role allows it
+        assert_eq!(auth.require_context_permission(&context, "content", "read").await, Ok(()));
+        assert_eq!(auth.require_context_permission(&context, "content", "write").await, Err(StatusCode::FORBIDDEN));
+        assert_eq!(auth.require_context_permission(&context, "users", "read").await, Err(StatusCode::FORBIDDEN));
+
+        let site_id = uuid::Uuid::parse_str("44444444-4444-4444-4444-444444444444").unwrap();
+        assert!(context.allows_site(&site_id));
+        assert!(!context.allows_site(&uuid::Uuid::nil()));
+    }
+
+    #[tokio::test]
+    async fn test_scopes_never_exceed_base_role() {
+        use crate::types::TenantId;
+
+        let auth = CasbinAuthorizer::new().await.expect("Failed to create Casbin authorizer");
+        let context = AuthContext {
+            tenant_id: TenantId::new(),
+            user_id: uuid::Uuid::new_v4(),
+            user_role: UserRole::Viewer,
+            scopes: Some(vec!["content:write".to_string(), "content:read".to_string()]),
+            site_id: None,
+            session_id: None,
+        };
+
+        assert!(auth.enforce_context(&context, "content", "read").await.expect("Scoped read test failed"));
+        assert!(!auth.enforce_context(&context, "content", "write").await.expect("Scoped write test failed"));
+    }
 }
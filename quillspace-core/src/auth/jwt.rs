@@ -1,13 +1,18 @@
 use josekit::{
+    jwk::Jwk,
     jwt::{JwtPayload},
-    jws::{HS256, JwsHeader},
+    jws::{ES256, HS256, RS256, JwsHeader, JwsSigner, JwsVerifier},
     JoseError,
 };
 use serde::{Deserialize, Serialize};
-use chrono::{Duration, Utc};
-use anyhow::anyhow;
+use serde_json::{Map, Value};
+use chrono::{DateTime, Duration, Utc};
+use anyhow::{anyhow, Context};
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::config::{AuthConfig, JwtAlgorithm};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,        // Subject (user ID)
@@ -16,35 +21,179 @@ pub struct Claims {
     pub last_name: String,  // User last name
     pub role: String,       // User role
     pub tenant_id: String,  // Tenant ID
+    pub token_version: i32, // User token version (bumped to revoke issued tokens)
     pub exp: i64,          // Expiration time
     pub iat: i64,          // Issued at
     pub iss: String,       // Issuer
+    pub scopes: Option<Vec<String>>, // Scope restrictions (None = full role permissions)
+    pub site_id: Option<String>,     // Site restriction for scoped tokens
+    pub session_id: Option<String>,  // Refresh session the token was issued for
+}
+
+/// Identity a token is issued for
+pub struct TokenSubject<'a> {
+    pub user_id: &'a str,
+    pub email: &'a str,
+    pub first_name: &'a str,
+    pub last_name: &'a str,
+    pub role: &'a str,
+    pub tenant_id: &'a str,
+}
+
+/// Options for tokens that differ from a default user session token
+#[derive(Debug, Clone, Default)]
+pub struct TokenOptions {
+    /// User token version the token is bound to
+    pub token_version: i32,
+    /// Restrict the token to these `resource:action` scopes
+    pub scopes: Option<Vec<String>>,
+    /// Restrict the token to a single site
+    pub site_id: Option<String>,
+    /// Token lifetime (defaults to 7 days)
+    pub ttl: Option<Duration>,
+    /// Refresh session the token belongs to, so the session can be told apart from others
+    pub session_id: Option<String>,
+}
+
+/// JWK members holding private key material, never published
+const PRIVATE_JWK_MEMBERS: &[&str] = &["d", "p", "q", "dp", "dq", "qi", "oth", "k"];
+
+/// Public keys of the keyset, as served at `/.well-known/jwks.json`
+#[derive(Debug, Clone, Serialize)]
+pub struct Jwks {
+    pub keys: Vec<Map<String, Value>>,
+}
+
+/// A keyset key tokens are verified with
+struct VerificationKey {
+    verifier: Box<dyn JwsVerifier>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// What new tokens are signed with
+enum SigningKey {
+    /// HS256 with the shared secret and no `kid`
+    Secret(Vec<u8>),
+    Keyset { kid: String, signer: Box<dyn JwsSigner> },
 }
 
 pub struct JwtManager {
-    secret: Vec<u8>,
+    signing_key: SigningKey,
+    keys: HashMap<String, VerificationKey>,
+    /// Secret verifying tokens without a `kid`
+    legacy_secret: Option<Vec<u8>>,
+    jwks: Jwks,
     issuer: String,
 }
 
 impl JwtManager {
+    /// Sign and verify with a shared HS256 secret, without key ids
     pub fn new(secret: &str, issuer: &str) -> Self {
         Self {
-            secret: secret.as_bytes().to_vec(),
+            signing_key: SigningKey::Secret(secret.as_bytes().to_vec()),
+            keys: HashMap::new(),
+            legacy_secret: Some(secret.as_bytes().to_vec()),
+            jwks: Jwks { keys: Vec::new() },
             issuer: issuer.to_string(),
         }
     }
 
+    /// Build from `[auth]`: the keyset under `[auth.jwt]` when it names a current key,
+    /// otherwise the `jwt_secret`
+    pub fn from_config(config: &AuthConfig, issuer: &str) -> anyhow::Result<Self> {
+        let keyset = &config.jwt;
+        let Some(current_kid) = &keyset.current_kid else {
+            return Ok(Self::new(&config.jwt_secret, issuer));
+        };
+
+        let mut signer = None;
+        let mut keys = HashMap::new();
+        let mut jwks = Jwks { keys: Vec::new() };
+        for key in &keyset.keys {
+            let jwk = Jwk::from_map(key.jwk.clone())
+                .with_context(|| format!("Invalid JWK for key '{}'", key.kid))?;
+            if key.kid == *current_kid {
+                signer = Some(signer_for(keyset.algorithm, &jwk)
+                    .with_context(|| format!("Key '{}' cannot sign {:?} tokens", key.kid, keyset.algorithm))?);
+            }
+
+            let verifier = match keyset.algorithm {
+                JwtAlgorithm::Hs256 => verifier_for(keyset.algorithm, &jwk),
+                JwtAlgorithm::Rs256 | JwtAlgorithm::Es256 => {
+                    let public = public_jwk(&key.kid, keyset.algorithm, &key.jwk);
+                    let verifier = verifier_for(keyset.algorithm, &Jwk::from_map(public.clone())?);
+                    jwks.keys.push(public);
+                    verifier
+                }
+            }
+            .with_context(|| format!("Key '{}' cannot verify {:?} tokens", key.kid, keyset.algorithm))?;
+
+            if keys.insert(key.kid.clone(), VerificationKey { verifier, expires_at: key.expires_at }).is_some() {
+                return Err(anyhow!("Duplicate JWT key id '{}'", key.kid));
+            }
+        }
+
+        let signer = signer.ok_or_else(|| anyhow!("JWT current_kid '{}' is not in the keyset", current_kid))?;
+
+        Ok(Self {
+            signing_key: SigningKey::Keyset { kid: current_kid.clone(), signer },
+            keys,
+            legacy_secret: keyset.accept_legacy_secret.then(|| config.jwt_secret.as_bytes().to_vec()),
+            jwks,
+            issuer: issuer.to_string(),
+        })
+    }
+
+    /// Public keys for verifying issued tokens; empty for symmetric keys
+    pub fn jwks(&self) -> &Jwks {
+        &self.jwks
+    }
+
+    /// Verify a token's signature with the key named by its `kid`, refusing unknown or retired keys
+    fn decode(&self, token: &str) -> Result<(JwtPayload, JwsHeader), JoseError> {
+        let legacy_verifier = self.legacy_secret.as_ref()
+            .map(|secret| HS256.verifier_from_bytes(secret))
+            .transpose()?;
+        josekit::jwt::decode_with_verifier_selector(token, |header| match header.key_id() {
+            Some(kid) => {
+                let key = self.keys.get(kid)
+                    .ok_or_else(|| JoseError::InvalidJwtFormat(anyhow!("Unknown key id: {}", kid)))?;
+                if key.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+                    return Err(JoseError::InvalidClaim(anyhow!("Key {} has been retired", kid)));
+                }
+                Ok(Some(&*key.verifier))
+            }
+            None => Ok(legacy_verifier.as_ref().map(|verifier| verifier as &dyn JwsVerifier)),
+        })
+    }
+
     pub fn generate_token(&self, user_id: &str, email: &str, first_name: &str, last_name: &str, role: &str, tenant_id: &str) -> Result<String, JoseError> {
+        let subject = TokenSubject { user_id, email, first_name, last_name, role, tenant_id };
+        self.generate_token_for(&subject, &TokenOptions::default())
+    }
+
+    /// Generate a token for a subject with non-default options (token version, scopes, lifetime)
+    pub fn generate_token_for(&self, subject: &TokenSubject, options: &TokenOptions) -> Result<String, JoseError> {
         let now = Utc::now();
-        let exp = now + Duration::hours(24 * 7); // 7 days
+        let exp = now + options.ttl.unwrap_or_else(|| Duration::hours(24 * 7)); // 7 days by default
 
         let mut payload = JwtPayload::new();
-        payload.set_subject(user_id);
-        payload.set_claim("email", Some(serde_json::Value::String(email.to_string())))?;
-        payload.set_claim("first_name", Some(serde_json::Value::String(first_name.to_string())))?;
-        payload.set_claim("last_name", Some(serde_json::Value::String(last_name.to_string())))?;
-        payload.set_claim("role", Some(serde_json::Value::String(role.to_string())))?;
-        payload.set_claim("tenant_id", Some(serde_json::Value::String(tenant_id.to_string())))?;
+        payload.set_subject(subject.user_id);
+        payload.set_claim("email", Some(serde_json::Value::String(subject.email.to_string())))?;
+        payload.set_claim("first_name", Some(serde_json::Value::String(subject.first_name.to_string())))?;
+        payload.set_claim("last_name", Some(serde_json::Value::String(subject.last_name.to_string())))?;
+        payload.set_claim("role", Some(serde_json::Value::String(subject.role.to_string())))?;
+        payload.set_claim("tenant_id", Some(serde_json::Value::String(subject.tenant_id.to_string())))?;
+        payload.set_claim("token_version", Some(serde_json::Value::from(options.token_version)))?;
+        if let Some(scopes) = &options.scopes {
+            payload.set_claim("scopes", Some(serde_json::Value::from(scopes.clone())))?;
+        }
+        if let Some(site_id) = &options.site_id {
+            payload.set_claim("site_id", Some(serde_json::Value::String(site_id.clone())))?;
+        }
+        if let Some(session_id) = &options.session_id {
+            payload.set_claim("sid", Some(serde_json::Value::String(session_id.clone())))?;
+        }
         
         // Convert chrono DateTime to SystemTime
         let exp_system_time = UNIX_EPOCH + std::time::Duration::from_secs(exp.timestamp() as u64);
@@ -54,16 +203,23 @@ impl JwtManager {
         payload.set_issued_at(&now_system_time);
         payload.set_issuer(&self.issuer);
 
-        let header = JwsHeader::new();
-        let signer = HS256.signer_from_bytes(&self.secret)?;
-        let token = josekit::jwt::encode_with_signer(&payload, &header, &signer)?;
+        let mut header = JwsHeader::new();
+        let token = match &self.signing_key {
+            SigningKey::Secret(secret) => {
+                let signer = HS256.signer_from_bytes(secret)?;
+                josekit::jwt::encode_with_signer(&payload, &header, &signer)?
+            }
+            SigningKey::Keyset { kid, signer } => {
+                header.set_key_id(kid);
+                josekit::jwt::encode_with_signer(&payload, &header, &**signer)?
+            }
+        };
 
         Ok(token)
     }
 
     pub fn verify_token(&self, token: &str) -> Result<Claims, JoseError> {
-        let verifier = HS256.verifier_from_bytes(&self.secret)?;
-        let (payload, _header) = josekit::jwt::decode_with_verifier(token, &verifier)?;
+        let (payload, _header) = self.decode(token)?;
 
         let sub = payload.subject()
             .ok_or_else(|| JoseError::InvalidJwtFormat(anyhow!("Missing subject")))?;
@@ -88,11 +244,20 @@ impl JwtManager {
             .and_then(|v| v.as_str())
             .ok_or_else(|| JoseError::InvalidJwtFormat(anyhow!("Missing tenant_id")))?;
 
+        // Tokens issued before versioning was introduced count as version 0
+        let token_version = payload.claim("token_version")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0) as i32;
+
         let exp = payload.expires_at()
             .ok_or_else(|| JoseError::InvalidJwtFormat(anyhow!("Missing expiration")))?
             .duration_since(UNIX_EPOCH)
             .map_err(|_| JoseError::InvalidJwtFormat(anyhow!("Invalid expiration time")))?
             .as_secs() as i64;
+
+        if exp <= Utc::now().timestamp() {
+            return Err(JoseError::InvalidClaim(anyhow!("Token expired")));
+        }
         
         let iat = payload.issued_at()
             .ok_or_else(|| JoseError::InvalidJwtFormat(anyhow!("Missing issued_at")))?
@@ -103,6 +268,25 @@ impl JwtManager {
         let iss = payload.issuer()
             .ok_or_else(|| JoseError::InvalidJwtFormat(anyhow!("Missing issuer")))?;
 
+        let scopes = match payload.claim("scopes") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(serde_json::Value::Array(values)) => Some(
+                values.iter()
+                    .map(|v| v.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| JoseError::InvalidJwtFormat(anyhow!("Invalid scopes")))?
+            ),
+            Some(_) => return Err(JoseError::InvalidJwtFormat(anyhow!("Invalid scopes"))),
+        };
+
+        let site_id = payload.claim("site_id")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+
+        let session_id = payload.claim("sid")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+
         Ok(Claims {
             sub: sub.to_string(),
             email: email.to_string(),
@@ -110,9 +294,13 @@ impl JwtManager {
             last_name: last_name.to_string(),
             role: role.to_string(),
             tenant_id: tenant_id.to_string(),
+            token_version,
             exp,
             iat,
             iss: iss.to_string(),
+            scopes,
+            site_id,
+            session_id,
         })
     }
 
@@ -127,13 +315,48 @@ impl JwtManager {
     }
 }
 
+fn signer_for(algorithm: JwtAlgorithm, jwk: &Jwk) -> Result<Box<dyn JwsSigner>, JoseError> {
+    Ok(match algorithm {
+        JwtAlgorithm::Hs256 => Box::new(HS256.signer_from_jwk(jwk)?),
+        JwtAlgorithm::Rs256 => Box::new(RS256.signer_from_jwk(jwk)?),
+        JwtAlgorithm::Es256 => Box::new(ES256.signer_from_jwk(jwk)?),
+    })
+}
+
+fn verifier_for(algorithm: JwtAlgorithm, jwk: &Jwk) -> Result<Box<dyn JwsVerifier>, JoseError> {
+    Ok(match algorithm {
+        JwtAlgorithm::Hs256 => Box::new(HS256.verifier_from_jwk(jwk)?),
+        JwtAlgorithm::Rs256 => Box::new(RS256.verifier_from_jwk(jwk)?),
+        JwtAlgorithm::Es256 => Box::new(ES256.verifier_from_jwk(jwk)?),
+    })
+}
+
+/// The public half of a JWK, labeled with its `kid` and algorithm for the JWKS
+fn public_jwk(kid: &str, algorithm: JwtAlgorithm, jwk: &Map<String, Value>) -> Map<String, Value> {
+    let mut public: Map<String, Value> = jwk
+        .iter()
+        .filter(|(member, _)| !PRIVATE_JWK_MEMBERS.contains(&member.as_str()))
+        .map(|(member, value)| (member.clone(), value.clone()))
+        .collect();
+    let alg = match algorithm {
+        JwtAlgorithm::Hs256 => "HS256",
+        JwtAlgorithm::Rs256 => "RS256",
+        JwtAlgorithm::Es256 => "ES256",
+    };
+    public.insert("kid".to_string(), Value::String(kid.to_string()));
+    public.insert("alg".to_string(), Value::String(alg.to_string()));
+    public.insert("use".to_string(), Value::String("sig".to_string()));
+    public
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{JwtKeyConfig, JwtKeysetConfig};
 
     #[test]
     fn test_jwt_generation_and_verification() {
-        let jwt_manager = JwtManager::new("test-secret-key", "quillspace");
+        let jwt_manager = JwtManager::new("test-secret-key-of-at-least-32-bytes", "quillspace");
         
         let token = jwt_manager.generate_token(
             "user-123",
@@ -152,12 +375,145 @@ mod tests {
         assert_eq!(claims.last_name, "User");
         assert_eq!(claims.role, "admin");
         assert_eq!(claims.tenant_id, "tenant-456");
+        assert_eq!(claims.token_version, 0);
+        assert_eq!(claims.scopes, None);
         assert_eq!(claims.iss, "quillspace");
     }
 
+    #[test]
+    fn test_token_options() {
+        let jwt_manager = JwtManager::new("test-secret-key-of-at-least-32-bytes", "quillspace");
+        let subject = TokenSubject {
+            user_id: "user-123",
+            email: "test@example.com",
+            first_name: "Test",
+            last_name: "User",
+            role: "admin",
+            tenant_id: "tenant-456",
+        };
+        let options = TokenOptions {
+            token_version: 3,
+            scopes: Some(vec!["content:read".to_string()]),
+            site_id: Some("site-789".to_string()),
+            ttl: Some(Duration::minutes(5)),
+            session_id: Some("session-1".to_string()),
+        };
+
+        let token = jwt_manager.generate_token_for(&subject, &options).expect("Failed to create test token");
+
+        let claims = jwt_manager.verify_token(&token).expect("Failed to verify test token");
+        assert_eq!(claims.token_version, 3);
+        assert_eq!(claims.scopes, Some(vec!["content:read".to_string()]));
+        assert_eq!(claims.site_id, Some("site-789".to_string()));
+        assert_eq!(claims.session_id, Some("session-1".to_string()));
+        assert!(claims.exp - claims.iat <= 300);
+    }
+
+    #[test]
+    fn test_expired_token_rejected() {
+        let jwt_manager = JwtManager::new("test-secret-key-of-at-least-32-bytes", "quillspace");
+        let subject = TokenSubject {
+            user_id: "user-123",
+            email: "test@example.com",
+            first_name: "Test",
+            last_name: "User",
+            role: "admin",
+            tenant_id: "tenant-456",
+        };
+        let options = TokenOptions {
+            ttl: Some(Duration::seconds(-60)),
+            ..TokenOptions::default()
+        };
+
+        let token = jwt_manager.generate_token_for(&subject, &options).expect("Failed to create test token");
+        assert!(jwt_manager.verify_token(&token).is_err());
+    }
+
+    fn es256_key() -> (Map<String, Value>, Map<String, Value>) {
+        let key_pair = ES256.generate_key_pair().expect("Failed to generate key pair");
+        (
+            key_pair.to_jwk_key_pair().as_ref().clone(),
+            key_pair.to_jwk_public_key().as_ref().clone(),
+        )
+    }
+
+    fn keyset_config(current_kid: &str, keys: Vec<JwtKeyConfig>) -> AuthConfig {
+        AuthConfig {
+            jwt_secret: "test-secret-key-of-at-least-32-bytes".to_string(),
+            jwt_expiration: 3600,
+            refresh_token_expiration: 86400,
+            totp_encryption_key: None,
+            jwt: JwtKeysetConfig {
+                algorithm: JwtAlgorithm::Es256,
+                current_kid: Some(current_kid.to_string()),
+                keys,
+                accept_legacy_secret: false,
+            },
+            platform_tenant_id: None,
+        }
+    }
+
+    fn key(kid: &str, jwk: &Map<String, Value>, expires_at: Option<DateTime<Utc>>) -> JwtKeyConfig {
+        JwtKeyConfig { kid: kid.to_string(), jwk: jwk.clone(), expires_at }
+    }
+
+    fn sign(jwt_manager: &JwtManager) -> String {
+        jwt_manager
+            .generate_token("user-123", "test@example.com", "Test", "User", "admin", "tenant-456")
+            .expect("Failed to create test token")
+    }
+
+    #[test]
+    fn test_previous_key_verifies_during_rotation() {
+        let (old_private, old_public) = es256_key();
+        let (new_private, _) = es256_key();
+
+        let before = JwtManager::from_config(&keyset_config("old", vec![key("old", &old_private, None)]), "quillspace")
+            .expect("Failed to build keyset");
+        let old_token = sign(&before);
+
+        // The new key signs; the old one is kept, public part only, until its tokens expire
+        let overlap = keyset_config("new", vec![
+            key("new", &new_private, None),
+            key("old", &old_public, Some(Utc::now() + Duration::days(7))),
+        ]);
+        let during = JwtManager::from_config(&overlap, "quillspace").expect("Failed to build keyset");
+        assert_eq!(during.verify_token(&old_token).expect("Previous key rejected").sub, "user-123");
+        let new_token = sign(&during);
+        assert!(during.verify_token(&new_token).is_ok());
+        assert!(before.verify_token(&new_token).is_err());
+
+        // Once the overlap ends the old key's tokens are refused
+        let after = keyset_config("new", vec![
+            key("new", &new_private, None),
+            key("old", &old_public, Some(Utc::now() - Duration::seconds(1))),
+        ]);
+        let after = JwtManager::from_config(&after, "quillspace").expect("Failed to build keyset");
+        assert!(after.verify_token(&old_token).is_err());
+
+        // Only public parameters are published
+        let kids: Vec<&str> = during.jwks().keys.iter().filter_map(|jwk| jwk["kid"].as_str()).collect();
+        assert_eq!(kids, ["new", "old"]);
+        assert!(during.jwks().keys.iter().all(|jwk| !jwk.contains_key("d")));
+    }
+
+    #[test]
+    fn test_unknown_kid_rejected() {
+        let (private, _) = es256_key();
+        let (other_private, _) = es256_key();
+        let issuer = JwtManager::from_config(&keyset_config("a", vec![key("a", &private, None)]), "quillspace")
+            .expect("Failed to build keyset");
+        let verifier = JwtManager::from_config(&keyset_config("b", vec![key("b", &other_private, None)]), "quillspace")
+            .expect("Failed to build keyset");
+
+        assert!(verifier.verify_token(&sign(&issuer)).is_err());
+        // Nor does a keyset accept unkeyed secret tokens unless told to
+        assert!(verifier.verify_token(&sign(&JwtManager::new("test-secret-key-of-at-least-32-bytes", "quillspace"))).is_err());
+    }
+
     #[test]
     fn test_token_validation() {
-        let jwt_manager = JwtManager::new("test-secret-key", "quillspace");
+        let jwt_manager = JwtManager::new("test-secret-key-of-at-least-32-bytes", "quillspace");
         
         let token = jwt_manager.generate_token(
             "user-123",
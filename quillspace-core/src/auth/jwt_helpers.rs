@@ -8,9 +8,44 @@ pub struct AuthContext {
     pub tenant_id: TenantId,
     pub user_id: Uuid,
     pub user_role: UserRole,
+    /// Scope restrictions carried by API keys and scoped tokens (None = full role permissions)
+    pub scopes: Option<Vec<String>>,
+    /// Site restriction carried by scoped tokens
+    pub site_id: Option<Uuid>,
+    /// Refresh session of a logged-in user's token
+    pub session_id: Option<Uuid>,
 }
 
-/// Extract complete authentication context from JWT token
+impl AuthContext {
+    /// Check the token's scopes allow `action` on `resource`
+    pub fn has_scope(&self, resource: &str, action: &str) -> bool {
+        match &self.scopes {
+            Some(scopes) => crate::auth::api_keys::scope_allows(scopes, resource, action),
+            None => true,
+        }
+    }
+
+    /// Check the token may access the given site
+    pub fn allows_site(&self, site_id: &Uuid) -> bool {
+        self.site_id.is_none_or(|allowed| allowed == *site_id)
+    }
+
+    /// Require a scope, returning 403 if the token is restricted and lacks it
+    pub fn require_scope(&self, resource: &str, action: &str) -> Result<(), StatusCode> {
+        if self.has_scope(resource, action) {
+            Ok(())
+        } else {
+            debug!(resource, action, "Token scopes do not allow this action");
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+/// Extract complete authentication context from JWT token.
+/// API keys (`Bearer qs_...`) are accepted too: `api_key_middleware` exchanges them
+/// for a short-lived JWT carrying the key's tenant, role and scopes before handlers run.
+/// Revoked user tokens never get this far: `token_version_middleware` refuses tokens
+/// older than the user's token version.
 pub fn extract_auth_context_with_role(headers: &HeaderMap, jwt_manager: &crate::auth::jwt::JwtManager) -> Result<AuthContext, StatusCode> {
     let auth_header = headers
         .get("authorization")
@@ -35,44 +70,41 @@ pub fn extract_auth_context_with_role(headers: &HeaderMap, jwt_manager: &crate::
     );
     let user_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
     let user_role = crate::auth::extract_user_role_from_jwt(&claims)?;
+    let site_id = claims.site_id
+        .as_deref()
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let session_id = claims.session_id
+        .as_deref()
+        .map(Uuid::parse_str)
+        .transpose()
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
     
     Ok(AuthContext {
         tenant_id,
         user_id,
         user_role,
+        scopes: claims.scopes,
+        site_id,
+        session_id,
     })
 }
 
-/// Extract tenant and user context from JWT token - UNIVERSAL HELPER (backward compatibility)
+/// Extract tenant and user context from JWT token - UNIVERSAL HELPER (backward compatibility).
+/// Callers only learn the tenant and user, so tokens restricted by scopes or to one site
+/// are refused with 403; handlers that honor restrictions use `extract_auth_context_with_role`.
 pub fn extract_auth_context(headers: &HeaderMap, jwt_manager: &crate::auth::jwt::JwtManager) -> Result<(TenantId, Uuid), StatusCode> {
-    let auth_header = headers
-        .get("authorization")
-        .and_then(|h| match h.to_str() {
-            Ok(s) => Some(s),
-            Err(e) => {
-                error!("Invalid authorization header encoding - potential security issue: {}", e);
-                None
-            }
-        })
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-    
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-    
-    let claims = jwt_manager.verify_token(token)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
-    
-    let tenant_id = TenantId::from_uuid(
-        Uuid::parse_str(&claims.tenant_id).map_err(|_| StatusCode::UNAUTHORIZED)?
-    );
-    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
-    
-    Ok((tenant_id, user_id))
+    let auth_context = extract_auth_context_with_role(headers, jwt_manager)?;
+    if auth_context.scopes.is_some() || auth_context.site_id.is_some() {
+        debug!("Restricted token used on a route that cannot honor its restrictions");
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok((auth_context.tenant_id, auth_context.user_id))
 }
 
 /// Extract only tenant context from JWT token
 pub fn extract_tenant_context(headers: &HeaderMap, jwt_manager: &crate::auth::jwt::JwtManager) -> Result<TenantId, StatusCode> {
-    let (tenant_id, _) = extract_auth_context(headers, jwt_manager)?;
-    Ok(tenant_id)
+    Ok(extract_auth_context_with_role(headers, jwt_manager)?.tenant_id)
 }
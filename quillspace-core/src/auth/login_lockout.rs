@@ -0,0 +1,316 @@
+use crate::config::LoginLockoutConfig;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use deadpool_postgres::Pool;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+
+/// Failed logins recorded under one key (an account or an IP address)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttemptState {
+    pub failures: u32,
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl AttemptState {
+    pub fn is_locked(&self, now: DateTime<Utc>) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+}
+
+/// Where attempt state lives; it must be shared by every API instance
+pub trait LoginAttemptStore: Send + Sync + 'static {
+    fn get(&self, key: &str) -> impl Future<Output = Result<Option<AttemptState>>> + Send;
+
+    /// Replace the state of `key` with `update(current)`, atomically with respect to other callers
+    fn update<F>(&self, key: &str, update: F) -> impl Future<Output = Result<AttemptState>> + Send
+    where
+        F: FnOnce(Option<AttemptState>) -> AttemptState + Send;
+
+    fn clear(&self, key: &str) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Attempt state in the `login_attempts` table
+#[derive(Clone)]
+pub struct PostgresLoginAttemptStore {
+    pool: Pool,
+}
+
+impl PostgresLoginAttemptStore {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+}
+
+impl LoginAttemptStore for PostgresLoginAttemptStore {
+    async fn get(&self, key: &str) -> Result<Option<AttemptState>> {
+        let client = self.pool.get().await.context("Failed to get database connection")?;
+        let row = client
+            .query_opt(
+                "SELECT failures, last_failure_at, locked_until FROM login_attempts WHERE key = $1",
+                &[&key],
+            )
+            .await
+            .context("Failed to read login attempts")?;
+        Ok(row.map(|row| row_to_state(&row)))
+    }
+
+    async fn update<F>(&self, key: &str, update: F) -> Result<AttemptState>
+    where
+        F: FnOnce(Option<AttemptState>) -> AttemptState + Send,
+    {
+        let mut client = self.pool.get().await.context("Failed to get database connection")?;
+        let transaction = client.transaction().await.context("Failed to start transaction")?;
+
+        // Row lock so concurrent failures from several instances are all counted
+        let current = transaction
+            .query_opt(
+                "SELECT failures, last_failure_at, locked_until FROM login_attempts WHERE key = $1 FOR UPDATE",
+                &[&key],
+            )
+            .await
+            .context("Failed to read login attempts")?
+            .map(|row| row_to_state(&row));
+        let next = update(current);
+        let failures = next.failures as i32;
+
+        transaction
+            .execute(
+                r#"
+                INSERT INTO login_attempts (key, failures, last_failure_at, locked_until)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (key) DO UPDATE
+                SET failures = $2, last_failure_at = $3, locked_until = $4
+                "#,
+                &[&key, &failures, &next.last_failure_at, &next.locked_until],
+            )
+            .await
+            .context("Failed to record login attempt")?;
+        transaction.commit().await.context("Failed to commit login attempt")?;
+
+        Ok(next)
+    }
+
+    async fn clear(&self, key: &str) -> Result<()> {
+        let client = self.pool.get().await.context("Failed to get database connection")?;
+        client
+            .execute("DELETE FROM login_attempts WHERE key = $1", &[&key])
+            .await
+            .context("Failed to clear login attempts")?;
+        Ok(())
+    }
+}
+
+fn row_to_state(row: &tokio_postgres::Row) -> AttemptState {
+    let failures: i32 = row.get("failures");
+    AttemptState {
+        failures: failures.max(0) as u32,
+        last_failure_at: row.get("last_failure_at"),
+        locked_until: row.get("locked_until"),
+    }
+}
+
+/// Per-account and per-IP brute-force protection for password login.
+///
+/// Callers must answer a locked login exactly like a wrong password, so the
+/// response never tells an attacker which of the two happened.
+pub struct LoginLockout<S> {
+    store: S,
+    config: LoginLockoutConfig,
+}
+
+impl<S: LoginAttemptStore> LoginLockout<S> {
+    pub fn new(store: S, config: LoginLockoutConfig) -> Self {
+        Self { store, config }
+    }
+
+    /// Whether the account or the IP is currently locked out
+    pub async fn is_locked(&self, email: &str, ip: Option<&str>, now: DateTime<Utc>) -> Result<bool> {
+        if !self.config.enabled {
+            return Ok(false);
+        }
+        for key in keys(email, ip) {
+            if self.store.get(&key).await?.is_some_and(|state| state.is_locked(now)) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Count a failed login against the account and the IP
+    pub async fn record_failure(&self, email: &str, ip: Option<&str>, now: DateTime<Utc>) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        for key in keys(email, ip) {
+            let threshold = if key.starts_with("ip:") {
+                self.config.ip_threshold
+            } else {
+                self.config.account_threshold
+            };
+            self.store
+                .update(&key, |current| next_state(current, now, threshold, &self.config))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Reset the account after a successful login.
+    ///
+    /// The IP counter is left alone: otherwise an attacker could clear it by
+    /// logging into their own account between guesses.
+    pub async fn record_success(&self, email: &str) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        self.store.clear(&account_key(email)).await
+    }
+}
+
+/// State after one more failure: failures inside the window accumulate, and every
+/// failure from the threshold on locks for twice as long as the previous one
+fn next_state(
+    current: Option<AttemptState>,
+    now: DateTime<Utc>,
+    threshold: u32,
+    config: &LoginLockoutConfig,
+) -> AttemptState {
+    let window = Duration::seconds(config.window_secs as i64);
+    let previous = current
+        .filter(|state| state.is_locked(now) || now - state.last_failure_at < window)
+        .map_or(0, |state| state.failures);
+    let failures = previous.saturating_add(1);
+
+    let locked_until = (failures >= threshold.max(1)).then(|| {
+        let doublings = (failures - threshold.max(1)).min(31);
+        let seconds = config.base_lockout_secs.saturating_mul(1u64 << doublings).min(config.max_lockout_secs);
+        now + Duration::seconds(seconds as i64)
+    });
+
+    AttemptState { failures, last_failure_at: now, locked_until }
+}
+
+/// Emails are hashed so the table doesn't collect addresses, including mistyped ones
+fn account_key(email: &str) -> String {
+    let normalized = email.trim().to_lowercase();
+    format!("account:{}", hex::encode(Sha256::digest(normalized.as_bytes())))
+}
+
+fn keys(email: &str, ip: Option<&str>) -> Vec<String> {
+    std::iter::once(account_key(email))
+        .chain(ip.filter(|ip| !ip.is_empty()).map(|ip| format!("ip:{}", ip)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Stand-in for the shared table
+    #[derive(Default)]
+    struct MemoryStore(Mutex<HashMap<String, AttemptState>>);
+
+    impl LoginAttemptStore for MemoryStore {
+        async fn get(&self, key: &str) -> Result<Option<AttemptState>> {
+            Ok(self.0.lock().unwrap().get(key).copied())
+        }
+
+        async fn update<F>(&self, key: &str, update: F) -> Result<AttemptState>
+        where
+            F: FnOnce(Option<AttemptState>) -> AttemptState + Send,
+        {
+            let mut states = self.0.lock().unwrap();
+            let next = update(states.get(key).copied());
+            states.insert(key.to_string(), next);
+            Ok(next)
+        }
+
+        async fn clear(&self, key: &str) -> Result<()> {
+            self.0.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    const EMAIL: &str = "jane@example.com";
+    const IP: Option<&str> = Some("203.0.113.7");
+
+    fn lockout() -> LoginLockout<MemoryStore> {
+        LoginLockout::new(MemoryStore::default(), LoginLockoutConfig::default())
+    }
+
+    /// What the login handler does with a password check; `None` means refused
+    async fn login(lockout: &LoginLockout<MemoryStore>, password_ok: bool, now: DateTime<Utc>) -> Option<()> {
+        if lockout.is_locked(EMAIL, IP, now).await.unwrap() {
+            return None;
+        }
+        if password_ok {
+            lockout.record_success(EMAIL).await.unwrap();
+            Some(())
+        } else {
+            lockout.record_failure(EMAIL, IP, now).await.unwrap();
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_attempts_trigger_lockout() {
+        let lockout = lockout();
+        let now = Utc::now();
+
+        for attempt in 0..4 {
+            login(&lockout, false, now + Duration::seconds(attempt)).await;
+            assert!(!lockout.is_locked(EMAIL, IP, now + Duration::seconds(attempt)).await.unwrap());
+        }
+        login(&lockout, false, now + Duration::seconds(4)).await;
+        assert!(lockout.is_locked(EMAIL, IP, now + Duration::seconds(5)).await.unwrap());
+        // Same account from another IP is locked too
+        assert!(lockout.is_locked(EMAIL, Some("198.51.100.1"), now + Duration::seconds(5)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_correct_password_refused_until_lockout_passes() {
+        let lockout = lockout();
+        let now = Utc::now();
+        for _ in 0..5 {
+            login(&lockout, false, now).await;
+        }
+
+        assert_eq!(login(&lockout, true, now + Duration::seconds(29)).await, None);
+        assert_eq!(login(&lockout, true, now + Duration::seconds(31)).await, Some(()));
+        // Success resets the account counter
+        assert!(lockout.store.get(&account_key(EMAIL)).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_lockout_doubles_up_to_max() {
+        let config = LoginLockoutConfig::default();
+        let now = Utc::now();
+        let mut state = None;
+        let mut lock_seconds = Vec::new();
+        for _ in 0..12 {
+            let next = next_state(state, now, 5, &config);
+            lock_seconds.push(next.locked_until.map(|until| (until - now).num_seconds()));
+            state = Some(next);
+        }
+
+        assert_eq!(&lock_seconds[..4], &[None, None, None, None]);
+        assert_eq!(
+            &lock_seconds[4..],
+            &[Some(30), Some(60), Some(120), Some(240), Some(480), Some(960), Some(1920), Some(3600)]
+        );
+    }
+
+    #[test]
+    fn test_failures_outside_window_forgotten() {
+        let config = LoginLockoutConfig::default();
+        let now = Utc::now();
+        let old = AttemptState { failures: 4, last_failure_at: now - Duration::seconds(901), locked_until: None };
+
+        let next = next_state(Some(old), now, 5, &config);
+        assert_eq!(next.failures, 1);
+        assert_eq!(next.locked_until, None);
+    }
+}
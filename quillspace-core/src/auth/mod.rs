@@ -1,8 +1,14 @@
+pub mod api_keys;
 pub mod jwt;
 pub mod jwt_helpers;
+pub mod login_lockout;
 pub mod permissions;
 pub mod casbin_auth;
+pub mod password_policy;
+pub mod totp;
+pub mod webhooks;
 
-pub use jwt::{JwtManager, Claims};
+pub use jwt::{JwtManager, Claims, TokenOptions, TokenSubject};
 pub use permissions::extract_user_role_from_jwt;
 pub use casbin_auth::{CasbinAuthorizer, Resource, Action};
+pub use password_policy::{validate_password, PasswordPolicyViolation};
@@ -0,0 +1,217 @@
+use crate::config::PasswordPolicy;
+use crate::services::http_client;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::time::Duration;
+use tracing::warn;
+
+/// Individual password rule that can fail validation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordRule {
+    MinLength,
+    Uppercase,
+    Lowercase,
+    Digit,
+    Symbol,
+    Breached,
+}
+
+impl PasswordRule {
+    pub fn description(&self, policy: &PasswordPolicy) -> String {
+        match self {
+            PasswordRule::MinLength => format!("Password must be at least {} characters long", policy.min_length),
+            PasswordRule::Uppercase => "Password must contain an uppercase letter".to_string(),
+            PasswordRule::Lowercase => "Password must contain a lowercase letter".to_string(),
+            PasswordRule::Digit => "Password must contain a digit".to_string(),
+            PasswordRule::Symbol => "Password must contain a symbol".to_string(),
+            PasswordRule::Breached => "Password has appeared in a known data breach".to_string(),
+        }
+    }
+}
+
+/// Failed rule as returned to API clients
+#[derive(Debug, Serialize)]
+pub struct PasswordRuleViolation {
+    pub rule: PasswordRule,
+    pub message: String,
+}
+
+/// Body returned with a 400 when a password does not satisfy the policy
+#[derive(Debug, Serialize)]
+pub struct PasswordPolicyViolation {
+    pub failed_rules: Vec<PasswordRuleViolation>,
+}
+
+impl PasswordPolicyViolation {
+    pub fn new(policy: &PasswordPolicy, rules: Vec<PasswordRule>) -> Self {
+        Self {
+            failed_rules: rules
+                .into_iter()
+                .map(|rule| PasswordRuleViolation {
+                    message: rule.description(policy),
+                    rule,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Check the local (offline) rules of the policy
+pub fn check_password_rules(policy: &PasswordPolicy, password: &str) -> Vec<PasswordRule> {
+    let mut failed = Vec::new();
+
+    if password.chars().count() < policy.min_length {
+        failed.push(PasswordRule::MinLength);
+    }
+    if policy.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+        failed.push(PasswordRule::Uppercase);
+    }
+    if policy.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+        failed.push(PasswordRule::Lowercase);
+    }
+    if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        failed.push(PasswordRule::Digit);
+    }
+    if policy.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+        failed.push(PasswordRule::Symbol);
+    }
+
+    failed
+}
+
+/// Validate a password against the full policy, including the optional breach lookup
+pub async fn validate_password(policy: &PasswordPolicy, password: &str) -> Result<(), Vec<PasswordRule>> {
+    let mut failed = check_password_rules(policy, password);
+
+    if policy.check_breached && is_breached(policy, password).await {
+        failed.push(PasswordRule::Breached);
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(failed)
+    }
+}
+
+/// Look the password up in the HaveIBeenPwned range API.
+/// Only the first five hex characters of the SHA-1 hash leave the process.
+/// Fails open: any lookup error is logged and treated as "not breached".
+async fn is_breached(policy: &PasswordPolicy, password: &str) -> bool {
+    let digest = Sha1::digest(password.as_bytes());
+    let hash: String = digest.iter().map(|b| format!("{:02X}", b)).collect();
+    let (prefix, suffix) = hash.split_at(5);
+
+    let url = format!("{}/range/{}", policy.breached_api_url.trim_end_matches('/'), prefix);
+    let request = http_client::shared_client()
+        .get(&url)
+        .header("Add-Padding", "true")
+        .timeout(Duration::from_millis(policy.breached_timeout_ms));
+    let body = match request.send().await {
+        Ok(response) if response.status().is_success() => match response.text().await {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to read breached-password response, skipping check: {}", e);
+                return false;
+            }
+        },
+        Ok(response) => {
+            warn!("Breached-password service returned {}, skipping check", response.status());
+            return false;
+        }
+        Err(e) => {
+            warn!("Breached-password service unreachable, skipping check: {}", e);
+            return false;
+        }
+    };
+
+    body.lines().any(|line| {
+        let mut parts = line.trim().splitn(2, ':');
+        let candidate = parts.next().unwrap_or_default();
+        let count = parts.next().and_then(|c| c.trim().parse::<u64>().ok()).unwrap_or(0);
+        candidate.eq_ignore_ascii_case(suffix) && count > 0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Path, routing::get, Router};
+    use tokio::net::TcpListener;
+
+    fn strict_policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 12,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: true,
+            check_breached: false,
+            ..PasswordPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_compliant_password_passes() {
+        assert!(check_password_rules(&strict_policy(), "Correct-Horse-42").is_empty());
+    }
+
+    #[test]
+    fn test_each_rule_fails_independently() {
+        let policy = strict_policy();
+        assert_eq!(check_password_rules(&policy, "Short-4a"), vec![PasswordRule::MinLength]);
+        assert_eq!(check_password_rules(&policy, "correct-horse-42"), vec![PasswordRule::Uppercase]);
+        assert_eq!(check_password_rules(&policy, "CORRECT-HORSE-42"), vec![PasswordRule::Lowercase]);
+        assert_eq!(check_password_rules(&policy, "Correct-Horse-xx"), vec![PasswordRule::Digit]);
+        assert_eq!(check_password_rules(&policy, "CorrectHorse42x"), vec![PasswordRule::Symbol]);
+    }
+
+    /// Serve a canned range response containing the hash of "Correct-Horse-42"
+    async fn spawn_breach_service() -> String {
+        let digest = Sha1::digest("Correct-Horse-42".as_bytes());
+        let hash: String = digest.iter().map(|b| format!("{:02X}", b)).collect();
+        let suffix = hash[5..].to_string();
+
+        let app = Router::new().route(
+            "/range/:prefix",
+            get(move |Path(_prefix): Path<String>| {
+                let suffix = suffix.clone();
+                async move { format!("0000000000000000000000000000000000A:0\r\n{}:42\r\n", suffix) }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind test listener");
+        let addr = listener.local_addr().expect("Failed to read listener address");
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.expect("Test breach service failed");
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_breached_password_rejected() {
+        let policy = PasswordPolicy {
+            check_breached: true,
+            breached_api_url: spawn_breach_service().await,
+            ..strict_policy()
+        };
+
+        assert_eq!(
+            validate_password(&policy, "Correct-Horse-42").await,
+            Err(vec![PasswordRule::Breached])
+        );
+        assert!(validate_password(&policy, "Another-Horse-43").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_breach_check_fails_open_when_unreachable() {
+        let policy = PasswordPolicy {
+            check_breached: true,
+            breached_api_url: "http://127.0.0.1:1".to_string(),
+            breached_timeout_ms: 200,
+            ..strict_policy()
+        };
+
+        assert!(validate_password(&policy, "Correct-Horse-42").await.is_ok());
+    }
+}
@@ -0,0 +1,239 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use josekit::{
+    jwe::{Dir, JweHeader},
+    JoseError,
+};
+use rand::{distributions::Alphanumeric, Rng, RngCore};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Number of digits in a generated code
+pub const TOTP_DIGITS: usize = 6;
+/// Length of a time step in seconds
+pub const TOTP_PERIOD: i64 = 30;
+/// Number of steps either side of "now" that are still accepted (clock drift)
+pub const TOTP_SKEW: i64 = 1;
+/// Number of single-use backup codes issued on activation
+pub const BACKUP_CODE_COUNT: usize = 10;
+
+const SECRET_LEN: usize = 20;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Generate a new random shared secret
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_LEN];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// Build the `otpauth://` provisioning URI understood by authenticator apps.
+/// The same string is what clients encode into the enrollment QR code.
+pub fn provisioning_uri(secret: &[u8], issuer: &str, account: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer),
+        percent_encode(account),
+        base32_encode(secret),
+        percent_encode(issuer),
+        TOTP_DIGITS,
+        TOTP_PERIOD,
+    )
+}
+
+/// Time step containing the given instant
+pub fn time_step(now: DateTime<Utc>) -> i64 {
+    now.timestamp().div_euclid(TOTP_PERIOD)
+}
+
+/// Compute the code for a given time step (RFC 6238 / RFC 4226)
+pub fn code_at(secret: &[u8], step: i64) -> String {
+    let mut mac = <Hmac<Sha1> as Mac>::new_from_slice(secret)
+        .expect("HMAC accepts keys of any length");
+    mac.update(&(step as u64).to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = ((digest[offset] as u32 & 0x7f) << 24)
+        | ((digest[offset + 1] as u32) << 16)
+        | ((digest[offset + 2] as u32) << 8)
+        | (digest[offset + 3] as u32);
+
+    format!("{:0width$}", binary % 10u32.pow(TOTP_DIGITS as u32), width = TOTP_DIGITS)
+}
+
+/// Verify a code against the secret, allowing for clock skew.
+///
+/// Returns the matched time step so the caller can persist it; any code from
+/// a step at or before `last_used_step` is rejected to prevent replay.
+pub fn verify_code(secret: &[u8], code: &str, now: DateTime<Utc>, last_used_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != TOTP_DIGITS || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let current = time_step(now);
+    (current - TOTP_SKEW..=current + TOTP_SKEW)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| constant_time_eq(code_at(secret, *step).as_bytes(), code.as_bytes()))
+}
+
+/// Generate human-friendly single-use backup codes (e.g. `7KQ2-M9XD`)
+pub fn generate_backup_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    (0..BACKUP_CODE_COUNT)
+        .map(|_| {
+            let raw: String = (&mut rng)
+                .sample_iter(&Alphanumeric)
+                .take(8)
+                .map(|c| (c as char).to_ascii_uppercase())
+                .collect();
+            format!("{}-{}", &raw[..4], &raw[4..])
+        })
+        .collect()
+}
+
+/// Normalise a backup code as typed by a user before hashing/comparing
+pub fn normalize_backup_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Encrypt a TOTP secret for storage (JWE, direct A256GCM)
+pub fn encrypt_secret(key_material: &str, secret: &[u8]) -> Result<String, JoseError> {
+    let mut header = JweHeader::new();
+    header.set_content_encryption("A256GCM");
+    let encrypter = Dir.encrypter_from_bytes(derive_key(key_material))?;
+    josekit::jwe::serialize_compact(secret, &header, &encrypter)
+}
+
+/// Decrypt a stored TOTP secret
+pub fn decrypt_secret(key_material: &str, encrypted: &str) -> Result<Vec<u8>, JoseError> {
+    let decrypter = Dir.decrypter_from_bytes(derive_key(key_material))?;
+    let (secret, _header) = josekit::jwe::deserialize_compact(encrypted, &decrypter)?;
+    Ok(secret)
+}
+
+/// Derive a dedicated 256-bit encryption key so the raw signing secret is never reused as-is
+fn derive_key(key_material: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"quillspace-totp-secret:");
+    hasher.update(key_material.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Compare without returning early, so timing reveals nothing about where inputs differ
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// RFC 4648 base32 without padding, as expected by authenticator apps
+fn base32_encode(data: &[u8]) -> String {
+    let mut output = String::with_capacity((data.len() * 8).div_ceil(5));
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    output
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        // RFC 6238 appendix B, truncated to six digits
+        assert_eq!(code_at(RFC_SECRET, 59 / TOTP_PERIOD), "287082");
+        assert_eq!(code_at(RFC_SECRET, 1111111109 / TOTP_PERIOD), "081804");
+        assert_eq!(code_at(RFC_SECRET, 1234567890 / TOTP_PERIOD), "005924");
+    }
+
+    #[test]
+    fn test_base32_encoding() {
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_encode(RFC_SECRET), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    }
+
+    #[test]
+    fn test_enroll_verify_login_with_code() {
+        // Enroll: secret is generated, encrypted for storage and exposed as a provisioning URI
+        let secret = generate_secret();
+        let stored = encrypt_secret("test-secret-key", &secret).expect("Failed to encrypt secret");
+        let uri = provisioning_uri(&secret, "QuillSpace", "admin@quillspace.com");
+        assert!(uri.starts_with("otpauth://totp/QuillSpace:admin%40quillspace.com?secret="));
+
+        // Verify: the first code from the authenticator activates 2FA
+        let enrolled_at = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let secret = decrypt_secret("test-secret-key", &stored).expect("Failed to decrypt secret");
+        let activation_code = code_at(&secret, time_step(enrolled_at));
+        let last_used = verify_code(&secret, &activation_code, enrolled_at, None)
+            .expect("Activation code rejected");
+
+        // Login: a fresh code from a later step is accepted
+        let login_at = enrolled_at + chrono::Duration::minutes(5);
+        let login_code = code_at(&secret, time_step(login_at));
+        assert!(verify_code(&secret, &login_code, login_at, Some(last_used)).is_some());
+    }
+
+    #[test]
+    fn test_wrong_code_rejected() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let code = code_at(RFC_SECRET, time_step(now));
+        let wrong = format!("{:06}", (code.parse::<u32>().unwrap() + 1) % 1_000_000);
+
+        assert!(verify_code(RFC_SECRET, &wrong, now, None).is_none());
+        assert!(verify_code(RFC_SECRET, "12345", now, None).is_none());
+        assert!(verify_code(RFC_SECRET, "abcdef", now, None).is_none());
+    }
+
+    #[test]
+    fn test_replayed_code_rejected() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let code = code_at(RFC_SECRET, time_step(now));
+
+        let used_step = verify_code(RFC_SECRET, &code, now, None).expect("First use rejected");
+        assert!(verify_code(RFC_SECRET, &code, now, Some(used_step)).is_none());
+    }
+
+    #[test]
+    fn test_secret_encryption_requires_same_key() {
+        let stored = encrypt_secret("key-one", RFC_SECRET).expect("Failed to encrypt secret");
+        assert_eq!(decrypt_secret("key-one", &stored).expect("Failed to decrypt secret"), RFC_SECRET);
+        assert!(decrypt_secret("key-two", &stored).is_err());
+    }
+
+    #[test]
+    fn test_backup_codes() {
+        let codes = generate_backup_codes();
+        assert_eq!(codes.len(), BACKUP_CODE_COUNT);
+        assert!(codes.iter().all(|code| code.len() == 9 && code.as_bytes()[4] == b'-'));
+        assert_eq!(normalize_backup_code(" 7kq2-m9xd "), "7KQ2M9XD");
+    }
+}
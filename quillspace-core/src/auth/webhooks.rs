@@ -0,0 +1,239 @@
+use crate::auth::totp::constant_time_eq;
+use crate::config::{WebhookProviderConfig, WebhookSignatureScheme};
+use axum::http::{HeaderMap, StatusCode};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Handler run with the raw body once a webhook's signature has been verified
+pub type WebhookHandler =
+    Arc<dyn Fn(Vec<u8>) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>> + Send + Sync>;
+
+/// Check an HMAC-SHA256 signature header against the raw request body.
+///
+/// The header holds the hex digest, optionally prefixed with `sha256=`. Comparison is
+/// constant-time so response timing reveals nothing about the expected signature.
+pub fn verify_hmac_signature(secret: &[u8], header: &str, body: &[u8]) -> bool {
+    let signature = header.trim();
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret) else {
+        return false;
+    };
+    mac.update(body);
+    constant_time_eq(&mac.finalize().into_bytes(), &signature)
+}
+
+/// Check a Stripe `Stripe-Signature` header (`t=<unix time>,v1=<hex>[,v1=...]`).
+///
+/// Stripe signs `"<t>.<body>"`. Any `v1` entry may match, which keeps events valid
+/// while a secret is being rolled; signatures older than `tolerance_secs` are
+/// rejected so a captured request cannot be replayed later.
+pub fn verify_stripe_signature(secret: &[u8], header: &str, body: &[u8], now: i64, tolerance_secs: u64) -> bool {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.push(value),
+            _ => {}
+        }
+    }
+    let Some(timestamp) = timestamp else {
+        return false;
+    };
+    if now.abs_diff(timestamp) > tolerance_secs {
+        return false;
+    }
+
+    let mut signed = format!("{}.", timestamp).into_bytes();
+    signed.extend_from_slice(body);
+    signatures
+        .into_iter()
+        .any(|signature| verify_hmac_signature(secret, signature, &signed))
+}
+
+/// Why an inbound webhook was rejected
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("Unknown webhook provider: {0}")]
+    UnknownProvider(String),
+    #[error("Missing signature header {0}")]
+    MissingSignature(String),
+    #[error("Invalid webhook signature")]
+    InvalidSignature,
+    #[error("Webhook handler failed: {0}")]
+    Handler(#[source] anyhow::Error),
+}
+
+impl WebhookError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            WebhookError::UnknownProvider(_) => StatusCode::NOT_FOUND,
+            WebhookError::MissingSignature(_) | WebhookError::InvalidSignature => StatusCode::UNAUTHORIZED,
+            WebhookError::Handler(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Inbound webhook providers and their handlers. A request only reaches a handler
+/// after its signature has been verified with the provider's configured secret.
+#[derive(Clone, Default)]
+pub struct WebhookRegistry {
+    providers: HashMap<String, WebhookProviderConfig>,
+    handlers: HashMap<String, WebhookHandler>,
+}
+
+impl WebhookRegistry {
+    pub fn new(providers: HashMap<String, WebhookProviderConfig>) -> Self {
+        Self {
+            providers,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Handle webhooks from `provider`; it also needs a `[webhooks.<provider>]` config entry
+    pub fn register<F, Fut>(&mut self, provider: &str, handler: F)
+    where
+        F: Fn(Vec<u8>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let handler: WebhookHandler = Arc::new(move |body| Box::pin(handler(body)));
+        self.handlers.insert(provider.to_string(), handler);
+    }
+
+    /// Verify the request's signature, then run the provider's handler
+    pub async fn dispatch(&self, provider: &str, headers: &HeaderMap, body: Vec<u8>) -> Result<(), WebhookError> {
+        let (Some(config), Some(handler)) = (self.providers.get(provider), self.handlers.get(provider)) else {
+            return Err(WebhookError::UnknownProvider(provider.to_string()));
+        };
+
+        let signature = headers
+            .get(config.signature_header.as_str())
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| WebhookError::MissingSignature(config.signature_header.clone()))?;
+
+        let secret = config.secret.as_bytes();
+        let verified = match config.scheme {
+            WebhookSignatureScheme::Hmac => verify_hmac_signature(secret, signature, &body),
+            WebhookSignatureScheme::Stripe => {
+                let now = chrono::Utc::now().timestamp();
+                verify_stripe_signature(secret, signature, &body, now, config.tolerance_secs)
+            }
+        };
+        if config.secret.is_empty() || !verified {
+            return Err(WebhookError::InvalidSignature);
+        }
+
+        handler(body).await.map_err(WebhookError::Handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const SECRET: &[u8] = b"whsec_test";
+    const BODY: &[u8] = br#"{"event":"payment.succeeded","amount":1200}"#;
+
+    fn sign(body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET).unwrap();
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn test_valid_signature_accepted() {
+        assert!(verify_hmac_signature(SECRET, &sign(BODY), BODY));
+        // Bare hex digest without the prefix
+        assert!(verify_hmac_signature(SECRET, sign(BODY).trim_start_matches("sha256="), BODY));
+    }
+
+    #[test]
+    fn test_tampered_payload_rejected() {
+        let tampered = br#"{"event":"payment.succeeded","amount":9900}"#;
+        assert!(!verify_hmac_signature(SECRET, &sign(BODY), tampered));
+        assert!(!verify_hmac_signature(b"wrong-secret", &sign(BODY), BODY));
+        assert!(!verify_hmac_signature(SECRET, "sha256=not-hex", BODY));
+        assert!(!verify_hmac_signature(SECRET, "", BODY));
+    }
+
+    #[test]
+    fn test_signature_comparison_is_timing_safe() {
+        let expected = hex::decode(sign(BODY).trim_start_matches("sha256=")).unwrap();
+
+        // Mismatches in the first and last byte are both rejected; the comparison folds over
+        // every byte instead of returning at the first difference
+        let mut first = expected.clone();
+        first[0] ^= 1;
+        let mut last = expected.clone();
+        *last.last_mut().unwrap() ^= 1;
+
+        assert!(constant_time_eq(&expected, &expected));
+        assert!(!constant_time_eq(&expected, &first));
+        assert!(!constant_time_eq(&expected, &last));
+        assert!(!constant_time_eq(&expected, &expected[..expected.len() - 1]));
+        assert!(!verify_hmac_signature(SECRET, &hex::encode(&last), BODY));
+    }
+
+    fn stripe_header(timestamp: i64, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET).unwrap();
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(body);
+        format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn test_stripe_signature_verified() {
+        let now = 1_700_000_000;
+        assert!(verify_stripe_signature(SECRET, &stripe_header(now, BODY), BODY, now + 10, 300));
+        // Rolled secrets: any v1 entry may match
+        let rolled = format!("t={},v1={},{}", now, "00".repeat(32), stripe_header(now, BODY).split_once(",").unwrap().1);
+        assert!(verify_stripe_signature(SECRET, &rolled, BODY, now, 300));
+
+        let tampered = br#"{"event":"payment.succeeded","amount":9900}"#;
+        assert!(!verify_stripe_signature(SECRET, &stripe_header(now, BODY), tampered, now, 300));
+        // Replayed outside the tolerance window
+        assert!(!verify_stripe_signature(SECRET, &stripe_header(now, BODY), BODY, now + 301, 300));
+        assert!(!verify_stripe_signature(SECRET, "v1=abcd", BODY, now, 300));
+    }
+
+    #[tokio::test]
+    async fn test_handler_runs_only_after_verification() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = WebhookProviderConfig {
+            secret: String::from_utf8(SECRET.to_vec()).unwrap(),
+            signature_header: "x-signature".to_string(),
+            ..WebhookProviderConfig::default()
+        };
+        let mut registry = WebhookRegistry::new(HashMap::from([("payments".to_string(), provider)]));
+        let counter = calls.clone();
+        registry.register("payments", move |_body| {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-signature", sign(b"tampered").parse().unwrap());
+        let rejected = registry.dispatch("payments", &headers, BODY.to_vec()).await;
+        assert!(matches!(rejected, Err(WebhookError::InvalidSignature)));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        headers.insert("x-signature", sign(BODY).parse().unwrap());
+        registry.dispatch("payments", &headers, BODY.to_vec()).await.expect("Valid webhook rejected");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let unknown = registry.dispatch("wix", &headers, BODY.to_vec()).await;
+        assert!(matches!(unknown, Err(WebhookError::UnknownProvider(_))));
+    }
+}
@@ -0,0 +1,266 @@
+//! Command line of the binary. `serve` runs the server and is what a bare
+//! `quillspace-core` does; the other subcommands are operational tasks for scripting
+//! deployments. All of them load configuration the same way.
+
+use crate::auth::validate_password;
+use crate::config::{AppConfig, ConfigSources, PasswordPolicy};
+use crate::database::migrations::{discover, run_migrations, MigrationReport};
+use crate::database::postgres::setup_rls;
+use crate::services::admin_account::{create_admin, generate_password, CreatedAdmin, NewAdmin};
+use anyhow::Result;
+use clap::{Arg, ArgMatches};
+use deadpool_postgres::Pool;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding the password `create-admin` sets
+pub const ADMIN_PASSWORD_ENV: &str = "QUILLSPACE_ADMIN_PASSWORD";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Serve,
+    Migrate {
+        directory: PathBuf,
+        baseline: Option<String>,
+    },
+    CreateAdmin(CreateAdminArgs),
+    ConfigCheck,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateAdminArgs {
+    pub tenant: String,
+    pub tenant_name: Option<String>,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+fn cli() -> clap::Command {
+    clap::Command::new("quillspace-core")
+        .about("QuillSpace server and maintenance tasks")
+        .subcommand(clap::Command::new("serve").about("Run the HTTP server (the default)"))
+        .subcommand(
+            clap::Command::new("migrate")
+                .about("Apply the SQL migrations not yet applied, then set up row-level security")
+                .arg(
+                    Arg::new("dir")
+                        .long("dir")
+                        .value_name("DIR")
+                        .default_value("migrations")
+                        .help("Directory of the .sql migrations"),
+                )
+                .arg(
+                    Arg::new("baseline")
+                        .long("baseline")
+                        .value_name("VERSION")
+                        .help("Record migrations up to VERSION as applied without running them"),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("create-admin")
+                .about(format!(
+                    "Create an admin user; the password is read from {}, or generated and printed",
+                    ADMIN_PASSWORD_ENV
+                ))
+                .arg(Arg::new("tenant").long("tenant").value_name("SLUG").required(true).help("Tenant slug"))
+                .arg(
+                    Arg::new("tenant-name")
+                        .long("tenant-name")
+                        .value_name("NAME")
+                        .help("Create the tenant with this name if the slug is not taken"),
+                )
+                .arg(Arg::new("email").long("email").value_name("EMAIL").required(true))
+                .arg(Arg::new("first-name").long("first-name").value_name("NAME").required(true))
+                .arg(Arg::new("last-name").long("last-name").value_name("NAME").default_value("")),
+        )
+        .subcommand(
+            clap::Command::new("config")
+                .about("Inspect the configuration")
+                .subcommand_required(true)
+                .subcommand(clap::Command::new("check").about(
+                    "Print the effective configuration, secrets redacted, and where each critical setting came from; \
+                     fails when a setting is invalid",
+                )),
+        )
+}
+
+/// Parse the command line, including the program name
+pub fn parse<I, T>(args: I) -> Result<Command, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let matches = cli().try_get_matches_from(args)?;
+    let string = |matches: &ArgMatches, name: &str| matches.get_one::<String>(name).cloned();
+
+    Ok(match matches.subcommand() {
+        Some(("migrate", matches)) => Command::Migrate {
+            directory: PathBuf::from(string(matches, "dir").unwrap_or_default()),
+            baseline: string(matches, "baseline"),
+        },
+        Some(("create-admin", matches)) => Command::CreateAdmin(CreateAdminArgs {
+            tenant: string(matches, "tenant").unwrap_or_default(),
+            tenant_name: string(matches, "tenant-name"),
+            email: string(matches, "email").unwrap_or_default(),
+            first_name: string(matches, "first-name").unwrap_or_default(),
+            last_name: string(matches, "last-name").unwrap_or_default(),
+        }),
+        Some(("config", _)) => Command::ConfigCheck,
+        _ => Command::Serve,
+    })
+}
+
+/// `migrate`: apply new migrations from `directory`, then row-level security as startup does
+pub async fn migrate(pool: &Pool, directory: &Path, baseline: Option<&str>) -> Result<MigrationReport> {
+    let migrations = discover(directory)?;
+    let report = run_migrations(pool, &migrations, baseline).await?;
+    setup_rls(pool).await?;
+
+    for version in &report.baselined {
+        println!("recorded  {}", version);
+    }
+    for version in &report.applied {
+        println!("applied   {}", version);
+    }
+    println!("{} applied, {} already applied", report.applied.len(), report.already_applied);
+    Ok(report)
+}
+
+/// `create-admin`: `password` is the one from the environment, if set. A chosen
+/// password must satisfy the password policy like any other.
+pub async fn create_admin_user(
+    pool: &Pool,
+    policy: &PasswordPolicy,
+    args: &CreateAdminArgs,
+    password: Option<String>,
+) -> Result<CreatedAdmin> {
+    if let Some(password) = &password {
+        if let Err(failed_rules) = validate_password(policy, password).await {
+            let reasons: Vec<String> = failed_rules.iter().map(|rule| rule.description(policy)).collect();
+            anyhow::bail!("Password does not meet the password policy: {}", reasons.join("; "));
+        }
+    }
+    let generated = password.is_none();
+    let password = password.unwrap_or_else(generate_password);
+    let admin = NewAdmin {
+        tenant_slug: args.tenant.clone(),
+        tenant_name: args.tenant_name.clone(),
+        email: args.email.clone(),
+        first_name: args.first_name.clone(),
+        last_name: args.last_name.clone(),
+        password: password.clone(),
+    };
+    let created = create_admin(pool, &admin).await?;
+
+    if created.tenant_created {
+        println!("created tenant {} ({})", args.tenant, created.tenant_id);
+    }
+    println!("created admin {} ({})", admin.email, created.user_id);
+    if generated {
+        println!("password: {}", password);
+    }
+    Ok(created)
+}
+
+/// What `config check` prints: the redacted configuration, the source of each critical
+/// setting, and every validation issue
+pub fn config_report(config: &AppConfig, sources: &ConfigSources) -> serde_json::Value {
+    serde_json::json!({
+        "config": config.redacted(),
+        "sources": sources,
+        "issues": config.validate(),
+    })
+}
+
+/// `config check`: print the report, failing when any setting is invalid
+pub fn config_check(config: &AppConfig, sources: &ConfigSources) -> Result<()> {
+    let report = config_report(config, sources);
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    let issues = config.validate();
+    if !issues.is_empty() {
+        anyhow::bail!("Invalid configuration: {}", issues.join("; "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::admin_account::AdminAccountError;
+    use crate::types::UserRole;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_serve_is_the_default() {
+        assert_eq!(parse(["quillspace-core"]).unwrap(), Command::Serve);
+        assert_eq!(parse(["quillspace-core", "serve"]).unwrap(), Command::Serve);
+        assert_eq!(
+            parse(["quillspace-core", "migrate", "--baseline", "026_content_revisions"]).unwrap(),
+            Command::Migrate { directory: PathBuf::from("migrations"), baseline: Some("026_content_revisions".to_string()) }
+        );
+        assert!(parse(["quillspace-core", "create-admin", "--tenant", "acme"]).is_err());
+        assert!(parse(["quillspace-core", "reticulate"]).is_err());
+        assert_eq!(parse(["quillspace-core", "config", "check"]).unwrap(), Command::ConfigCheck);
+        assert!(parse(["quillspace-core", "config"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_admin_creates_admin_user() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let command = parse([
+            "quillspace-core",
+            "create-admin",
+            "--tenant",
+            "bronte-press",
+            "--tenant-name",
+            "Brontë Press",
+            "--email",
+            "Charlotte@BrontePress.com",
+            "--first-name",
+            "Charlotte",
+        ])
+        .unwrap();
+        let Command::CreateAdmin(args) = command else {
+            panic!("Expected create-admin, got {:?}", command);
+        };
+
+        let policy = PasswordPolicy::default();
+        let weak = create_admin_user(&app.admin_pool, &policy, &args, Some("correct horse battery".to_string())).await;
+        assert!(weak.unwrap_err().to_string().contains("uppercase"));
+
+        let created = create_admin_user(&app.admin_pool, &policy, &args, Some("Correct horse battery 9".to_string()))
+            .await
+            .unwrap();
+        assert!(created.tenant_created);
+
+        let client = app.admin_pool.get().await.unwrap();
+        let user = client
+            .query_one(
+                "SELECT u.tenant_id, u.email, u.role::text AS role, u.password_hash, t.slug
+                 FROM users u JOIN tenants t ON t.id = u.tenant_id WHERE u.id = $1",
+                &[&created.user_id],
+            )
+            .await
+            .unwrap();
+        assert_eq!(user.get::<_, Uuid>("tenant_id"), *created.tenant_id.as_uuid());
+        assert_eq!(user.get::<_, String>("slug"), "bronte-press");
+        assert_eq!(user.get::<_, String>("email"), "charlotte@brontepress.com");
+        assert_eq!(user.get::<_, String>("role"), UserRole::Admin.to_string());
+        assert!(bcrypt::verify("Correct horse battery 9", user.get::<_, &str>("password_hash")).unwrap());
+
+        // A second admin joins the tenant; the same email again is refused
+        let second = CreateAdminArgs { email: "anne@brontepress.com".to_string(), tenant_name: None, ..args.clone() };
+        let joined = create_admin_user(&app.admin_pool, &policy, &second, None).await.unwrap();
+        assert_eq!((joined.tenant_id, joined.tenant_created), (created.tenant_id, false));
+        let duplicate = create_admin_user(&app.admin_pool, &policy, &args, Some("Correct horse battery 9".to_string())).await;
+        assert!(matches!(duplicate.unwrap_err().downcast_ref(), Some(AdminAccountError::Conflict(_))));
+
+        let unknown = CreateAdminArgs { tenant: "nobody".to_string(), tenant_name: None, ..args };
+        let missing = create_admin_user(&app.admin_pool, &policy, &unknown, None).await;
+        assert!(matches!(missing.unwrap_err().downcast_ref(), Some(AdminAccountError::TenantNotFound(_))));
+    }
+}
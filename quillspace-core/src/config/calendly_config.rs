@@ -91,7 +91,7 @@ pub struct CalendlyApiClient {
 
 impl CalendlyApiClient {
     pub fn new(config: CalendlyConfig) -> Self {
-        let client = reqwest::Client::new();
+        let client = crate::services::http_client::shared_client();
         Self { config, client }
     }
 
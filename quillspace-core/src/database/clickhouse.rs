@@ -1,12 +1,15 @@
 use crate::config::ClickHouseConfig;
 use crate::types::{AnalyticsEvent, TenantId};
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use tracing::{info, warn};
 use uuid::Uuid;
 
 pub use clickhouse::Client;
+use clickhouse::query::Query;
 
 /// Create ClickHouse client with optimized settings
 pub async fn create_client(config: &ClickHouseConfig) -> Result<Client> {
@@ -61,6 +64,7 @@ pub async fn init_analytics_tables(client: &Client) -> Result<()> {
     // Content analytics table
     let create_content_analytics_table = r#"
         CREATE TABLE IF NOT EXISTS content_analytics (
+            event_id UUID DEFAULT generateUUIDv4(),
             content_id UUID,
             tenant_id UUID,
             action String,
@@ -77,6 +81,12 @@ pub async fn init_analytics_tables(client: &Client) -> Result<()> {
 
     client.query(create_content_analytics_table).execute().await?;
 
+    // Tables created before content actions had ids; replay dedupes on them
+    client
+        .query("ALTER TABLE content_analytics ADD COLUMN IF NOT EXISTS event_id UUID DEFAULT generateUUIDv4() FIRST")
+        .execute()
+        .await?;
+
     // User activity aggregations (materialized view)
     let create_user_activity_mv = r#"
         CREATE MATERIALIZED VIEW IF NOT EXISTS user_activity_daily
@@ -119,10 +129,217 @@ pub async fn init_analytics_tables(client: &Client) -> Result<()> {
         warn!("Failed to create content_performance_daily (may already exist): {}", e);
     }
 
+    // Per-tenant daily rollup used by dashboards instead of scanning raw events
+    let create_events_daily_table = r#"
+        CREATE TABLE IF NOT EXISTS events_daily (
+            tenant_id UUID,
+            date Date,
+            total_events SimpleAggregateFunction(sum, UInt64),
+            unique_users AggregateFunction(uniq, Nullable(UUID)),
+            unique_sessions AggregateFunction(uniq, Nullable(String)),
+            page_views SimpleAggregateFunction(sum, UInt64)
+        ) ENGINE = AggregatingMergeTree()
+        PARTITION BY toYYYYMM(date)
+        ORDER BY (tenant_id, date)
+        SETTINGS index_granularity = 8192
+    "#;
+
+    client.query(create_events_daily_table).execute().await?;
+
+    let create_events_daily_mv = format!(
+        "CREATE MATERIALIZED VIEW IF NOT EXISTS events_daily_mv TO events_daily AS {}",
+        EVENTS_DAILY_ROLLUP_SELECT.replace("{filter}", "1 = 1")
+    );
+
+    if let Err(e) = client.query(&create_events_daily_mv).execute().await {
+        warn!("Failed to create events_daily_mv (may already exist): {}", e);
+    }
+
     info!("ClickHouse analytics tables initialized");
     Ok(())
 }
 
+/// Aggregation feeding `events_daily`, shared by the materialized view and backfill
+const EVENTS_DAILY_ROLLUP_SELECT: &str = r#"
+    SELECT
+        tenant_id,
+        toDate(timestamp) AS date,
+        count() AS total_events,
+        uniqState(user_id) AS unique_users,
+        uniqState(session_id) AS unique_sessions,
+        countIf(event_type = 'page_view') AS page_views
+    FROM events
+    WHERE {filter}
+    GROUP BY tenant_id, date
+"#;
+
+/// Tenants an analytics maintenance operation applies to
+#[derive(Debug, Clone, Copy)]
+pub enum TenantScope<'a> {
+    All,
+    Only(&'a Uuid),
+    AllExcept(&'a [Uuid]),
+}
+
+impl TenantScope<'_> {
+    /// SQL predicate for this scope; bind its parameter with [`TenantScope::bind`]
+    fn predicate(&self) -> &'static str {
+        match self {
+            TenantScope::All => "1 = 1",
+            TenantScope::Only(_) => "tenant_id = ?",
+            TenantScope::AllExcept(_) => "NOT has(?, toString(tenant_id))",
+        }
+    }
+
+    fn bind(&self, query: Query) -> Query {
+        match self {
+            TenantScope::All => query,
+            TenantScope::Only(tenant_id) => query.bind(tenant_id),
+            TenantScope::AllExcept(tenant_ids) => {
+                query.bind(tenant_ids.iter().map(Uuid::to_string).collect::<Vec<_>>())
+            }
+        }
+    }
+}
+
+/// Rebuild `events_daily` from raw events for an inclusive date range.
+///
+/// Existing rollup rows in the range are removed first, so the backfill can be
+/// re-run safely. New events keep flowing into the rollup via the materialized view;
+/// avoid backfilling today while events are still being written.
+pub async fn backfill_daily_rollups(client: &Client, scope: TenantScope<'_>, from: NaiveDate, to: NaiveDate) -> Result<()> {
+    info!(?scope, %from, %to, "Backfilling daily analytics rollups");
+
+    let delete = format!(
+        "ALTER TABLE events_daily DELETE WHERE {} AND date >= ? AND date <= ? SETTINGS mutations_sync = 1",
+        scope.predicate()
+    );
+    scope.bind(client.query(&delete))
+        .bind(from)
+        .bind(to)
+        .execute()
+        .await?;
+
+    let filter = format!("{} AND toDate(timestamp) >= ? AND toDate(timestamp) <= ?", scope.predicate());
+    let insert = format!("INSERT INTO events_daily {}", EVENTS_DAILY_ROLLUP_SELECT.replace("{filter}", &filter));
+    scope.bind(client.query(&insert))
+        .bind(from)
+        .bind(to)
+        .execute()
+        .await?;
+
+    info!(?scope, %from, %to, "Daily analytics rollup backfill complete");
+    Ok(())
+}
+
+/// Delete raw analytics older than `cutoff` (exclusive). Rollups are kept.
+///
+/// With `rollup_first`, the rollup for the days about to be purged is rebuilt from
+/// the raw events beforehand so dashboards keep their history.
+pub async fn purge_events_before(client: &Client, scope: TenantScope<'_>, cutoff: NaiveDate, rollup_first: bool) -> Result<()> {
+    if rollup_first {
+        let oldest_query = format!(
+            "SELECT min(date) AS oldest, count() AS events FROM events WHERE {} AND date < ?",
+            scope.predicate()
+        );
+        let oldest = scope.bind(client.query(&oldest_query))
+            .bind(cutoff)
+            .fetch_one::<OldestEventRow>()
+            .await?;
+
+        if oldest.events > 0 {
+            backfill_daily_rollups(client, scope, oldest.oldest, cutoff - chrono::Duration::days(1)).await?;
+        }
+    }
+
+    for table in ["events", "content_analytics"] {
+        let delete = format!(
+            "ALTER TABLE {} DELETE WHERE {} AND date < ? SETTINGS mutations_sync = 1",
+            table,
+            scope.predicate()
+        );
+        scope.bind(client.query(&delete))
+            .bind(cutoff)
+            .execute()
+            .await?;
+    }
+
+    info!(?scope, %cutoff, "Purged analytics events past retention");
+    Ok(())
+}
+
+/// A public page path and how often it was viewed
+#[derive(Debug, Clone, PartialEq, Eq, clickhouse::Row, Deserialize)]
+pub struct PopularPage {
+    pub tenant_id: Uuid,
+    pub page_path: String,
+    pub views: u64,
+}
+
+/// The `limit` most viewed page paths over the last `days`, most viewed first
+pub async fn popular_pages(client: &Client, scope: TenantScope<'_>, days: u32, limit: u64) -> Result<Vec<PopularPage>> {
+    let query = format!(
+        "SELECT tenant_id, JSONExtractString(event_data, 'page_path') AS page_path, count() AS views
+         FROM events
+         WHERE {} AND event_type = 'page_view' AND timestamp >= now() - INTERVAL ? DAY
+         GROUP BY tenant_id, page_path
+         ORDER BY views DESC
+         LIMIT ?",
+        scope.predicate()
+    );
+    Ok(scope.bind(client.query(&query))
+        .bind(days)
+        .bind(limit)
+        .fetch_all::<PopularPage>()
+        .await?)
+}
+
+/// Delete every analytics record for a tenant, raw and aggregated (GDPR erasure)
+pub async fn delete_tenant_analytics(client: &Client, tenant_id: &Uuid) -> Result<()> {
+    for table in [
+        "events",
+        "content_analytics",
+        "events_daily",
+        "user_activity_daily",
+        "content_performance_daily",
+    ] {
+        let delete = format!("ALTER TABLE {} DELETE WHERE tenant_id = ? SETTINGS mutations_sync = 1", table);
+        client.query(&delete).bind(tenant_id).execute().await?;
+    }
+
+    info!(tenant_id = %tenant_id, "Deleted all analytics for tenant");
+    Ok(())
+}
+
+/// Ids of the tenant's rows in `table` (`events` or `content_analytics`) dated
+/// `from`..=`to`
+pub async fn event_ids_between(
+    client: &Client,
+    table: &str,
+    tenant_id: &Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<HashSet<Uuid>> {
+    let query = format!(
+        "SELECT DISTINCT toString(event_id) FROM {} WHERE tenant_id = ? AND date >= ? AND date <= ?",
+        table
+    );
+    let ids = client.query(&query).bind(tenant_id).bind(from).bind(to).fetch_all::<String>().await?;
+    Ok(ids.iter().filter_map(|id| Uuid::parse_str(id).ok()).collect())
+}
+
+/// A row of `content_analytics`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentAction {
+    pub event_id: Uuid,
+    pub tenant_id: Uuid,
+    pub content_id: Uuid,
+    pub action: String,
+    pub user_id: Option<Uuid>,
+    pub timestamp: DateTime<Utc>,
+    pub metadata: serde_json::Value,
+}
+
 /// ClickHouse analytics service
 #[derive(Clone)]
 pub struct AnalyticsService {
@@ -183,21 +400,36 @@ impl AnalyticsService {
         user_id: Option<Uuid>,
         metadata: serde_json::Value,
     ) -> Result<()> {
+        self.insert_content_action(&ContentAction {
+            event_id: Uuid::new_v4(),
+            tenant_id,
+            content_id,
+            action: action.to_string(),
+            user_id,
+            timestamp: Utc::now(),
+            metadata,
+        })
+        .await
+    }
+
+    /// Record a content action with its own id and time
+    pub async fn insert_content_action(&self, action: &ContentAction) -> Result<()> {
         // Use direct query to avoid serialization issues
         let query = r#"
             INSERT INTO content_analytics (
-                content_id, tenant_id, action, user_id, timestamp, metadata
-            ) VALUES (?, ?, ?, ?, ?, ?)
+                event_id, content_id, tenant_id, action, user_id, timestamp, metadata
+            ) VALUES (?, ?, ?, ?, ?, ?, ?)
         "#;
         
         self.client
             .query(query)
-            .bind(content_id)
-            .bind(tenant_id)
-            .bind(action)
-            .bind(user_id)
-            .bind(Utc::now().timestamp_millis() as f64 / 1000.0)
-            .bind(serde_json::to_string(&metadata)?)
+            .bind(action.event_id)
+            .bind(action.content_id)
+            .bind(action.tenant_id)
+            .bind(&action.action)
+            .bind(action.user_id)
+            .bind(action.timestamp.timestamp_millis() as f64 / 1000.0)
+            .bind(serde_json::to_string(&action.metadata)?)
             .execute()
             .await?;
             
@@ -277,16 +509,17 @@ impl AnalyticsService {
         }).collect())
     }
 
-    /// Get user activity timeline
+    /// Get user activity timeline, one bucket per local day in `tz`
     pub async fn get_user_activity(
         &self,
         tenant_id: &TenantId,
         user_id: &Uuid,
         days: u32,
+        tz: Tz,
     ) -> Result<Vec<UserActivity>> {
         let query = r#"
             SELECT
-                toDate(timestamp) as date,
+                toDate(timestamp, ?) as date,
                 event_type,
                 count() as event_count
             FROM events
@@ -297,6 +530,7 @@ impl AnalyticsService {
 
         let results = self.client
             .query(query)
+            .bind(tz.name())
             .bind(tenant_id.as_uuid())
             .bind(user_id)
             .bind(days)
@@ -335,6 +569,12 @@ struct ContentAnalyticsRow {
     metadata: String,
 }
 
+#[derive(clickhouse::Row, Deserialize)]
+struct OldestEventRow {
+    oldest: NaiveDate,
+    events: u64,
+}
+
 #[derive(clickhouse::Row, Deserialize)]
 struct TenantStatsRow {
     total_events: u64,
@@ -387,3 +627,13 @@ pub struct UserActivity {
     pub event_type: String,
     pub event_count: u64,
 }
+
+/// ClickHouse client for tests, if `QUILLSPACE_TEST_CLICKHOUSE_URL` points at a server.
+/// Tests needing a live ClickHouse return early when it is unset.
+#[cfg(test)]
+pub(crate) async fn test_client() -> Option<Client> {
+    let url = std::env::var("QUILLSPACE_TEST_CLICKHOUSE_URL").ok()?;
+    let client = Client::default().with_url(url);
+    init_analytics_tables(&client).await.expect("Failed to initialize test ClickHouse tables");
+    Some(client)
+}
@@ -0,0 +1,174 @@
+//! SQL migrations from the `migrations` directory, applied in file name order.
+//!
+//! Each applied migration is recorded in `schema_migrations` under its file name
+//! without the extension, so running them again only applies new files. A migration
+//! and its record are committed together, so a failed migration leaves no trace and is
+//! retried on the next run.
+
+use anyhow::{Context, Result};
+use deadpool_postgres::Pool;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// A migration file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    /// File name without `.sql`, e.g. `021_content_scheduling`
+    pub version: String,
+    pub path: PathBuf,
+}
+
+/// What a migration run did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub applied: Vec<String>,
+    /// Recorded without running, by a baseline
+    pub baselined: Vec<String>,
+    pub already_applied: usize,
+}
+
+/// The `.sql` files in `directory`, in the order they apply
+pub fn discover(directory: &Path) -> Result<Vec<Migration>> {
+    let entries = std::fs::read_dir(directory)
+        .with_context(|| format!("Failed to read migrations from {}", directory.display()))?;
+
+    let mut migrations = Vec::new();
+    for entry in entries {
+        let path = entry.context("Failed to read migration")?.path();
+        if path.extension().is_some_and(|extension| extension == "sql") {
+            let version = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            migrations.push(Migration { version, path });
+        }
+    }
+    migrations.sort_by(|a, b| a.version.cmp(&b.version));
+    Ok(migrations)
+}
+
+/// Apply the migrations not yet recorded. With a `baseline`, unrecorded migrations up to
+/// and including it are recorded without running, for databases set up before
+/// migrations were tracked.
+pub async fn run_migrations(pool: &Pool, migrations: &[Migration], baseline: Option<&str>) -> Result<MigrationReport> {
+    if let Some(baseline) = baseline {
+        if !migrations.iter().any(|migration| migration.version == baseline) {
+            anyhow::bail!("Baseline {} is not a known migration", baseline);
+        }
+    }
+
+    let mut client = pool.get().await.context("Failed to get database connection")?;
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                 version VARCHAR(255) PRIMARY KEY,
+                 applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+             )",
+        )
+        .await
+        .context("Failed to create schema_migrations")?;
+
+    let mut report = MigrationReport::default();
+    for migration in migrations {
+        let transaction = client.transaction().await.context("Failed to start transaction")?;
+        // Held until commit, so concurrent runs apply each migration once
+        transaction
+            .execute("SELECT pg_advisory_xact_lock(hashtext('schema_migrations'))", &[])
+            .await
+            .context("Failed to lock schema_migrations")?;
+        let recorded = transaction
+            .query_opt("SELECT 1 FROM schema_migrations WHERE version = $1", &[&migration.version])
+            .await
+            .context("Failed to read schema_migrations")?
+            .is_some();
+        if recorded {
+            report.already_applied += 1;
+            continue;
+        }
+
+        let baselined = baseline.is_some_and(|baseline| migration.version.as_str() <= baseline);
+        if !baselined {
+            let sql = std::fs::read_to_string(&migration.path)
+                .with_context(|| format!("Failed to read {}", migration.path.display()))?;
+            transaction
+                .batch_execute(&sql)
+                .await
+                .with_context(|| format!("Migration {} failed", migration.version))?;
+        }
+        transaction
+            .execute("INSERT INTO schema_migrations (version) VALUES ($1)", &[&migration.version])
+            .await
+            .context("Failed to record migration")?;
+        transaction
+            .commit()
+            .await
+            .with_context(|| format!("Failed to commit migration {}", migration.version))?;
+
+        if baselined {
+            info!(version = %migration.version, "Migration recorded as applied");
+            report.baselined.push(migration.version.clone());
+        } else {
+            info!(version = %migration.version, "Migration applied");
+            report.applied.push(migration.version.clone());
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration_dir(files: &[(&str, &str)]) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("quillspace-migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for (name, sql) in files {
+            std::fs::write(directory.join(name), sql).unwrap();
+        }
+        directory
+    }
+
+    #[test]
+    fn test_migrations_discovered_in_order() {
+        let directory = migration_dir(&[("010_b.sql", ""), ("002_a.sql", ""), ("README.md", "")]);
+
+        let versions: Vec<String> = discover(&directory).unwrap().into_iter().map(|migration| migration.version).collect();
+        assert_eq!(versions, vec!["002_a", "010_b"]);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_migrations_applied_once() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let directory = migration_dir(&[
+            ("001_old.sql", "CREATE TABLE cli_old (id INT)"),
+            ("002_books.sql", "CREATE TABLE cli_books (id INT)"),
+            ("003_broken.sql", "CREATE TABLE cli_broken (id INT); SELECT 1 / 0"),
+        ]);
+        let migrations = discover(&directory).unwrap();
+        let pool = &app.admin_pool;
+
+        // 001 predates tracking; the broken migration stops the run and leaves nothing
+        let failed = run_migrations(pool, &migrations, Some("001_old")).await;
+        assert!(failed.is_err());
+        let client = pool.get().await.unwrap();
+        let exists = |table: &'static str| {
+            let client = &client;
+            async move {
+                client.query_one("SELECT to_regclass($1) IS NOT NULL", &[&table]).await.unwrap().get::<_, bool>(0)
+            }
+        };
+        assert!(!exists("cli_old").await);
+        assert!(exists("cli_books").await);
+        assert!(!exists("cli_broken").await);
+
+        std::fs::write(directory.join("003_broken.sql"), "CREATE TABLE cli_broken (id INT)").unwrap();
+        let report = run_migrations(pool, &migrations, None).await.unwrap();
+        assert_eq!(report.applied, vec!["003_broken"]);
+        assert_eq!(report.already_applied, 2);
+
+        let again = run_migrations(pool, &migrations, None).await.unwrap();
+        assert!(again.applied.is_empty());
+        assert_eq!(again.already_applied, 3);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -1,10 +1,9 @@
 pub mod postgres;
 pub mod clickhouse;
-pub mod rls_helper;
+pub mod migrations;
 use anyhow::Result;
-use deadpool_postgres::{Config, Pool, Runtime};
+use deadpool_postgres::Pool;
 use std::sync::Arc;
-use tokio_postgres::NoTls;
 
 /// Database connections container
 #[derive(Clone)]
@@ -15,16 +14,12 @@ pub struct DatabaseConnections {
 
 impl DatabaseConnections {
     /// Create new database connections
-    pub async fn new(postgres_url: &str, clickhouse_config: &crate::config::ClickHouseConfig) -> Result<Self> {
-        tracing::info!("Creating database connection pool with URL: {}", postgres_url);
-        let mut cfg = Config::new();
-        cfg.url = Some(postgres_url.to_string());
-        let postgres_pool = cfg.create_pool(Some(Runtime::Tokio1), NoTls)?;
-        
-        // Test database connection
-        if let Err(e) = postgres_pool.get().await {
-            tracing::error!("Database connection pool test failed: {}", e);
-        }
+    pub async fn new(
+        database_config: &crate::config::DatabaseConfig,
+        clickhouse_config: &crate::config::ClickHouseConfig,
+    ) -> Result<Self> {
+        // Fails startup if no connection can be obtained within the pool timeouts
+        let postgres_pool = postgres::create_pool(&database_config.url, &database_config.pool).await?;
         
         // Create ClickHouse client with proper authentication
         let clickhouse_client = clickhouse::Client::default()
@@ -40,6 +35,24 @@ impl DatabaseConnections {
         })
     }
 
+    /// Connections that are never opened, for tests of code that holds them without
+    /// querying. Any query fails.
+    #[cfg(test)]
+    pub fn unconnected() -> Self {
+        let pool = postgres::build_pool("postgresql://unused@127.0.0.1:1/unused", &Default::default())
+            .expect("Failed to build unconnected pool");
+        Self::with_postgres(pool)
+    }
+
+    /// `pool` with an unconnected ClickHouse, for tests that only use Postgres
+    #[cfg(test)]
+    pub fn with_postgres(pool: Pool) -> Self {
+        Self {
+            postgres: Arc::new(pool),
+            clickhouse: Arc::new(clickhouse::AnalyticsService::new(clickhouse::Client::default())),
+        }
+    }
+
     /// Get PostgreSQL pool
     pub fn postgres(&self) -> &Pool {
         &self.postgres
@@ -1,113 +1,256 @@
-use anyhow::Result;
-use deadpool_postgres::{Config, Pool, Runtime};
-use tokio_postgres::NoTls;
-use tracing::{info, warn};
-
-/// Create PostgreSQL connection pool with optimized settings
-pub async fn create_pool(postgres_url: &str) -> Result<Pool> {
-    info!("Connecting to PostgreSQL database...");
+use crate::{config::DatabasePoolConfig, types::TenantId};
+use anyhow::{Context, Result};
+use deadpool_postgres::{Config, ManagerConfig, Object, Pool, PoolConfig, RecyclingMethod, Runtime, Timeouts, Transaction};
+use std::time::Duration;
+use tokio_postgres::{error::SqlState, NoTls};
+use tracing::{error, info, warn};
 
+/// Build a PostgreSQL connection pool with the configured size and timeouts.
+/// No connection is opened until the pool is first used.
+///
+/// New connections start with the configured `statement_timeout`, and every
+/// checkout of a pooled connection sets it again and clears the tenant set by
+/// [`tenant_client`], so a request cannot leak either setting into the next one.
+pub fn build_pool(postgres_url: &str, settings: &DatabasePoolConfig) -> Result<Pool> {
     let mut cfg = Config::new();
     cfg.url = Some(postgres_url.to_string());
-    
-    // Use default pool configuration
-    let pool = cfg.create_pool(Some(Runtime::Tokio1), NoTls)?;
+    cfg.options = Some(format!("-c statement_timeout={}", settings.statement_timeout_ms));
+    cfg.manager = Some(ManagerConfig {
+        recycling_method: RecyclingMethod::Custom(format!(
+            "SET statement_timeout = {}; RESET quillspace.tenant_id",
+            settings.statement_timeout_ms
+        )),
+    });
+    cfg.pool = Some(PoolConfig {
+        max_size: settings.max_size.max(1),
+        timeouts: Timeouts {
+            wait: Some(Duration::from_millis(settings.wait_timeout_ms)),
+            create: Some(Duration::from_millis(settings.create_timeout_ms)),
+            recycle: Some(Duration::from_millis(settings.recycle_timeout_ms)),
+        },
+        ..PoolConfig::default()
+    });
+
+    cfg.create_pool(Some(Runtime::Tokio1), NoTls)
+        .context("Failed to create PostgreSQL connection pool")
+}
+
+/// Create a PostgreSQL connection pool, failing if the initial test connection
+/// cannot be obtained within the configured timeouts
+pub async fn create_pool(postgres_url: &str, settings: &DatabasePoolConfig) -> Result<Pool> {
+    info!("Connecting to PostgreSQL database...");
+
+    let pool = build_pool(postgres_url, settings)?;
 
     // Test connection
-    let client = pool.get().await?;
+    let client = pool.get().await
+        .context("Failed to obtain initial PostgreSQL connection")?;
     client.query("SELECT 1", &[]).await?;
 
-    info!("PostgreSQL connection pool created successfully");
+    info!("PostgreSQL connection pool created successfully (max size {})", settings.max_size);
     Ok(pool)
 }
 
-/// Setup row-level security for multi-tenant isolation
-/// Note: Most RLS policies are defined in migrations, this ensures they're enabled
-pub async fn setup_rls(pool: &Pool) -> Result<()> {
-    info!("RLS setup disabled for authentication compatibility");
-    return Ok(());
+/// A pooled connection whose session is set to `tenant_id`, so row-level security
+/// shows it that tenant's rows. The setting lasts until the connection goes back to
+/// the pool; a transaction can still set its own with `set_config(..., true)`.
+pub async fn tenant_client(pool: &Pool, tenant_id: &TenantId) -> Result<Object> {
+    let client = pool.get().await.context("Failed to get database connection")?;
+    client
+        .execute("SELECT set_config('quillspace.tenant_id', $1, false)", &[&tenant_id.to_string()])
+        .await
+        .context("Failed to set RLS tenant context")?;
+    Ok(client)
+}
 
-    let client = pool.get().await?;
+/// A transaction on `client` scoped to `tenant_id` for row-level security. The setting is
+/// local to the transaction, so it ends with the commit or rollback.
+pub async fn tenant_transaction<'a>(client: &'a mut Object, tenant_id: &TenantId) -> Result<Transaction<'a>> {
+    let transaction = client.transaction().await.context("Failed to start transaction")?;
+    transaction
+        .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
+        .await
+        .context("Failed to set RLS tenant context")?;
+    Ok(transaction)
+}
+
+/// Whether a query failed because Postgres cancelled it at `statement_timeout`
+pub fn is_statement_timeout(error: &tokio_postgres::Error) -> bool {
+    error.code() == Some(&SqlState::QUERY_CANCELED)
+}
+
+/// Tenant-scoped table protected by a `tenant_isolation_<table>` policy
+#[derive(Debug, Clone, Copy)]
+pub struct RlsTable {
+    pub name: &'static str,
+    /// Apply policies to the table owner too. `users` is exempt because
+    /// authentication looks users up before any tenant context exists.
+    pub force: bool,
+    /// Rows every tenant may read besides its own, such as platform templates.
+    /// Writes stay limited to the tenant's own rows.
+    pub shared_read: Option<&'static str>,
+}
+
+/// Tables that must be isolated by tenant
+pub const TENANT_SCOPED_TABLES: &[RlsTable] = &[
+    RlsTable { name: "sites", force: true, shared_read: None },
+    RlsTable { name: "pages", force: true, shared_read: None },
+    RlsTable { name: "content", force: true, shared_read: None },
+    RlsTable { name: "templates", force: true, shared_read: Some("tenant_id IS NULL OR is_public") },
+    RlsTable { name: "site_translations", force: true, shared_read: None },
+    RlsTable { name: "users", force: false, shared_read: None },
+];
+
+/// Row-level security state of a table as reported by the catalog
+#[derive(Debug, Clone, PartialEq)]
+pub struct RlsStatus {
+    pub table: String,
+    pub rls_enabled: bool,
+    pub force_rls: bool,
+    pub has_policy: bool,
+}
 
-    // Verify RLS is enabled on core tables (these should be set by migrations)
-    let rls_checks = vec![
-        "SELECT schemaname, tablename, rowsecurity FROM pg_tables WHERE tablename IN ('users', 'tenants', 'templates', 'sites', 'pages', 'assets') AND schemaname = 'public'",
-    ];
-
-    for query in rls_checks {
-        let rows = client.query(query, &[]).await?;
-        for row in rows {
-            let table_name: String = row.get("tablename");
-            let rls_enabled: bool = row.get("rowsecurity");
-            if rls_enabled {
-                info!("✓ RLS enabled on table: {}", table_name);
-            } else {
-                warn!("⚠ RLS not enabled on table: {}", table_name);
-            }
+/// Setup row-level security for multi-tenant isolation.
+///
+/// Safe to run on every startup: enabling/forcing RLS is idempotent and each policy
+/// is replaced inside a transaction, so there is no window without one. Afterwards
+/// the catalog is checked and startup fails if any table is not protected.
+pub async fn setup_rls(pool: &Pool) -> Result<()> {
+    let mut client = pool.get().await?;
+
+    for table in TENANT_SCOPED_TABLES {
+        let exists: bool = client
+            .query_one(
+                "SELECT EXISTS (SELECT FROM information_schema.tables WHERE table_schema = 'public' AND table_name = $1)",
+                &[&table.name],
+            )
+            .await?
+            .get(0);
+        if !exists {
+            warn!("⚠ Tenant-scoped table {} does not exist, skipping RLS setup", table.name);
+            continue;
         }
-    }
 
-    // Ensure core tenant isolation policies exist for legacy tables
-    let legacy_policies = vec![
-        // Create policy for users if table exists (using correct setting name)
-        r#"
-        DO $$
-        BEGIN
-            IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'users' AND table_schema = 'public') THEN
-                ALTER TABLE users ENABLE ROW LEVEL SECURITY;
-                DROP POLICY IF EXISTS tenant_isolation_users ON users;
-                CREATE POLICY tenant_isolation_users ON users
-                FOR ALL
-                USING (tenant_id = current_setting('quillspace.tenant_id')::uuid)
-                WITH CHECK (tenant_id = current_setting('quillspace.tenant_id')::uuid);
-            END IF;
-        END $$;
-        "#,
-        
-        // Create policy for tenants if table exists
-        r#"
-        DO $$
-        BEGIN
-            IF EXISTS (SELECT FROM information_schema.tables WHERE table_name = 'tenants' AND table_schema = 'public') THEN
-                ALTER TABLE tenants ENABLE ROW LEVEL SECURITY;
-                DROP POLICY IF EXISTS tenant_isolation_tenants ON tenants;
-                CREATE POLICY tenant_isolation_tenants ON tenants
-                FOR ALL
-                USING (id = current_setting('quillspace.tenant_id')::uuid);
-            END IF;
-        END $$;
-        "#,
-    ];
+        let transaction = client.transaction().await?;
+        transaction
+            .batch_execute(&rls_setup_sql(table))
+            .await
+            .with_context(|| format!("Failed to configure RLS on {}", table.name))?;
+        transaction.commit().await?;
+    }
 
-    for query in legacy_policies {
-        if let Err(e) = client.execute(query, &[]).await {
-            warn!("Failed to execute RLS policy: {}", e);
+    let statuses = rls_statuses(pool).await?;
+    let violations = rls_violations(TENANT_SCOPED_TABLES, &statuses);
+    if !violations.is_empty() {
+        for violation in &violations {
+            error!("✗ {}", violation);
         }
+        anyhow::bail!("Row-level security verification failed: {}", violations.join("; "));
     }
 
+    for status in &statuses {
+        info!("✓ RLS enabled on table: {} (force: {})", status.table, status.force_rls);
+    }
     info!("Row-level security policies configured");
     Ok(())
 }
 
-/// Set tenant context for the current session
-pub async fn set_tenant_context(pool: &Pool, tenant_id: uuid::Uuid) -> Result<()> {
+/// Idempotent statements enabling RLS and (re)creating the isolation policy for a table,
+/// plus a read-only policy for its shared rows when it has them
+fn rls_setup_sql(table: &RlsTable) -> String {
+    let force = if table.force { "FORCE" } else { "NO FORCE" };
+    let shared_read = match table.shared_read {
+        Some(condition) => format!(
+            "CREATE POLICY shared_read_{table} ON {table} FOR SELECT USING ({condition});",
+            table = table.name,
+            condition = condition,
+        ),
+        None => String::new(),
+    };
+    format!(
+        r#"
+        ALTER TABLE {table} ENABLE ROW LEVEL SECURITY;
+        ALTER TABLE {table} {force} ROW LEVEL SECURITY;
+        DROP POLICY IF EXISTS tenant_isolation_{table} ON {table};
+        CREATE POLICY tenant_isolation_{table} ON {table}
+            FOR ALL
+            USING (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid)
+            WITH CHECK (tenant_id = NULLIF(current_setting('quillspace.tenant_id', true), '')::uuid);
+        DROP POLICY IF EXISTS shared_read_{table} ON {table};
+        {shared_read}
+        "#,
+        table = table.name,
+        force = force,
+        shared_read = shared_read,
+    )
+}
+
+/// Read the RLS state of every existing tenant-scoped table
+pub async fn rls_statuses(pool: &Pool) -> Result<Vec<RlsStatus>> {
     let client = pool.get().await?;
-    client.execute(
-        "SELECT set_config('quillspace.tenant_id', $1, true)",
-        &[&tenant_id.to_string()]
-    ).await?;
-    
-    Ok(())
+    let names: Vec<&str> = TENANT_SCOPED_TABLES.iter().map(|table| table.name).collect();
+
+    let rows = client
+        .query(
+            r#"
+            SELECT c.relname::text AS table_name,
+                   c.relrowsecurity AS rls_enabled,
+                   c.relforcerowsecurity AS force_rls,
+                   EXISTS (
+                       SELECT 1 FROM pg_policies p
+                       WHERE p.schemaname = 'public'
+                         AND p.tablename = c.relname
+                         AND p.policyname = 'tenant_isolation_' || c.relname
+                   ) AS has_policy
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = 'public' AND c.relkind = 'r' AND c.relname = ANY($1)
+            ORDER BY c.relname
+            "#,
+            &[&names],
+        )
+        .await
+        .context("Failed to read row-level security state")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| RlsStatus {
+            table: row.get("table_name"),
+            rls_enabled: row.get("rls_enabled"),
+            force_rls: row.get("force_rls"),
+            has_policy: row.get("has_policy"),
+        })
+        .collect())
+}
+
+/// Describe every way the observed state falls short of the expected protection
+pub fn rls_violations(expected: &[RlsTable], statuses: &[RlsStatus]) -> Vec<String> {
+    let mut violations = Vec::new();
+    for table in expected {
+        let Some(status) = statuses.iter().find(|status| status.table == table.name) else {
+            continue;
+        };
+        if !status.rls_enabled {
+            violations.push(format!("RLS is not enabled on {}", table.name));
+        }
+        if table.force && !status.force_rls {
+            violations.push(format!("FORCE ROW LEVEL SECURITY is not set on {}", table.name));
+        }
+        if !status.has_policy {
+            violations.push(format!("Policy tenant_isolation_{} is missing", table.name));
+        }
+    }
+    violations
 }
 
 /// Tenant-aware query helper
 pub struct TenantQuery {
-    tenant_id: uuid::Uuid,
+    tenant_id: TenantId,
 }
 
 impl TenantQuery {
     pub fn new(tenant_id: uuid::Uuid) -> Self {
-        Self { tenant_id }
+        Self { tenant_id: TenantId::from_uuid(tenant_id) }
     }
 
     /// Execute a query with tenant context
@@ -117,13 +260,8 @@ impl TenantQuery {
         query: &str,
         params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
     ) -> Result<u64> {
-        // Set tenant context
-        set_tenant_context(pool, self.tenant_id).await?;
-        
-        // Execute the query
-        let client = pool.get().await?;
-        let result = client.execute(query, params).await?;
-        Ok(result)
+        let client = tenant_client(pool, &self.tenant_id).await?;
+        Ok(client.execute(query, params).await?)
     }
 
     /// Query for multiple rows with tenant context
@@ -133,13 +271,8 @@ impl TenantQuery {
         query: &str,
         params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
     ) -> Result<Vec<tokio_postgres::Row>> {
-        // Set tenant context
-        set_tenant_context(pool, self.tenant_id).await?;
-        
-        // Execute the query
-        let client = pool.get().await?;
-        let result = client.query(query, params).await?;
-        Ok(result)
+        let client = tenant_client(pool, &self.tenant_id).await?;
+        Ok(client.query(query, params).await?)
     }
 
     /// Query for a single row with tenant context
@@ -149,13 +282,8 @@ impl TenantQuery {
         query: &str,
         params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
     ) -> Result<tokio_postgres::Row> {
-        // Set tenant context
-        set_tenant_context(pool, self.tenant_id).await?;
-        
-        // Execute the query
-        let client = pool.get().await?;
-        let result = client.query_one(query, params).await?;
-        Ok(result)
+        let client = tenant_client(pool, &self.tenant_id).await?;
+        Ok(client.query_one(query, params).await?)
     }
 
     /// Query for an optional row with tenant context
@@ -165,12 +293,205 @@ impl TenantQuery {
         query: &str,
         params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
     ) -> Result<Option<tokio_postgres::Row>> {
-        // Set tenant context
-        set_tenant_context(pool, self.tenant_id).await?;
-        
-        // Execute the query
-        let client = pool.get().await?;
-        let result = client.query_opt(query, params).await?;
-        Ok(result)
+        let client = tenant_client(pool, &self.tenant_id).await?;
+        Ok(client.query_opt(query, params).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(table: &str, rls_enabled: bool, force_rls: bool, has_policy: bool) -> RlsStatus {
+        RlsStatus { table: table.to_string(), rls_enabled, force_rls, has_policy }
+    }
+
+    #[test]
+    fn test_rls_violations() {
+        let statuses = vec![
+            status("sites", true, false, true),
+            status("pages", true, true, false),
+            status("content", true, true, true),
+            status("users", true, false, true),
+        ];
+
+        assert_eq!(
+            rls_violations(TENANT_SCOPED_TABLES, &statuses),
+            vec![
+                "FORCE ROW LEVEL SECURITY is not set on sites".to_string(),
+                "Policy tenant_isolation_pages is missing".to_string(),
+            ]
+        );
+    }
+
+    /// Postgres pool for tests, if `QUILLSPACE_TEST_DATABASE_URL` is set.
+    /// It must connect as the application role; superusers always bypass RLS.
+    async fn test_pool() -> Option<Pool> {
+        let url = std::env::var("QUILLSPACE_TEST_DATABASE_URL").ok()?;
+        Some(create_pool(&url, &DatabasePoolConfig::default()).await.expect("Failed to connect to test database"))
+    }
+
+    #[test]
+    fn test_build_pool_applies_settings() {
+        let settings = DatabasePoolConfig { max_size: 3, ..DatabasePoolConfig::default() };
+        let pool = build_pool("postgresql://quillspace@localhost/quillspace", &settings).unwrap();
+        assert_eq!(pool.status().max_size, 3);
+        assert_eq!(pool.timeouts().wait, Some(Duration::from_millis(settings.wait_timeout_ms)));
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_pool_times_out() {
+        let Ok(url) = std::env::var("QUILLSPACE_TEST_DATABASE_URL") else {
+            return;
+        };
+        let settings = DatabasePoolConfig {
+            max_size: 1,
+            wait_timeout_ms: 100,
+            ..DatabasePoolConfig::default()
+        };
+        let pool = create_pool(&url, &settings).await.expect("Failed to connect to test database");
+
+        let _held = pool.get().await.expect("Failed to get first connection");
+        let result = tokio::time::timeout(Duration::from_secs(5), pool.get())
+            .await
+            .expect("Pool wait hung past its timeout");
+        assert!(matches!(
+            result,
+            Err(deadpool_postgres::PoolError::Timeout(deadpool_postgres::TimeoutType::Wait))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_slow_query_cancelled_at_statement_timeout() {
+        let Ok(url) = std::env::var("QUILLSPACE_TEST_DATABASE_URL") else {
+            return;
+        };
+        let settings = DatabasePoolConfig {
+            max_size: 1,
+            statement_timeout_ms: 200,
+            ..DatabasePoolConfig::default()
+        };
+        let pool = create_pool(&url, &settings).await.expect("Failed to connect to test database");
+
+        {
+            let client = pool.get().await.expect("Failed to get connection");
+            let started = std::time::Instant::now();
+            let error = client
+                .simple_query("SELECT pg_sleep(5)")
+                .await
+                .expect_err("Slow query was not cancelled");
+            assert!(is_statement_timeout(&error), "{}", error);
+            assert!(started.elapsed() < Duration::from_secs(2));
+
+            // A request raising its own limit must not leak it to the next checkout
+            client.simple_query("SET statement_timeout = 0").await.expect("Failed to change timeout");
+        }
+
+        let client = pool.get().await.expect("Failed to get connection");
+        let timeout: String = client
+            .query_one("SHOW statement_timeout", &[])
+            .await
+            .expect("Failed to read statement_timeout")
+            .get(0);
+        assert_eq!(timeout, "200ms");
+    }
+
+    #[tokio::test]
+    async fn test_policies_read_one_tenant_setting() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let client = app.admin_pool.get().await.expect("Failed to get connection");
+        let stale: Vec<String> = client
+            .query(
+                "SELECT tablename::text FROM pg_policies
+                 WHERE schemaname = 'public'
+                   AND (qual LIKE '%app.current_tenant_id%' OR with_check LIKE '%app.current_tenant_id%')",
+                &[],
+            )
+            .await
+            .expect("Failed to read policies")
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert!(stale.is_empty(), "Policies still read app.current_tenant_id: {:?}", stale);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_client_scopes_rows_and_public_lookup_finds_site() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let admin = app.admin_pool.get().await.expect("Failed to get connection");
+        let site_id: uuid::Uuid = admin
+            .query_one(
+                "INSERT INTO sites (tenant_id, name, subdomain, custom_domain)
+                 VALUES ($1, 'Lookup', 'lookup-site', 'lookup.example.com') RETURNING id",
+                &[app.tenant_a.id.as_uuid()],
+            )
+            .await
+            .expect("Failed to insert site")
+            .get(0);
+        let pool = app.state.db.postgres();
+        let sites = crate::services::site::SiteService::new(pool.clone());
+
+        // The app role sees a tenant's rows only with that tenant set on the connection
+        assert!(sites.get_site(&app.tenant_a.id, site_id).await.unwrap().is_some());
+        assert!(sites.get_site(&app.tenant_b.id, site_id).await.unwrap().is_none());
+
+        // Public requests carry no tenant; the host resolves it
+        let by_subdomain = sites.get_site_by_subdomain("lookup-site").await.unwrap().expect("Site not found by subdomain");
+        assert_eq!(by_subdomain.id, site_id);
+        let by_domain = sites.get_site_by_domain("lookup.example.com").await.unwrap().expect("Site not found by domain");
+        assert_eq!(by_domain.id, site_id);
+        assert!(sites.get_site_by_subdomain("missing-site").await.unwrap().is_none());
+
+        // The setting does not outlive the checkout
+        for _ in 0..4 {
+            let client = pool.get().await.expect("Failed to get connection");
+            let tenant: Option<String> = client
+                .query_one("SELECT NULLIF(current_setting('quillspace.tenant_id', true), '')", &[])
+                .await
+                .expect("Failed to read tenant setting")
+                .get(0);
+            assert_eq!(tenant, None);
+            let visible: i64 = client.query_one("SELECT COUNT(*) FROM sites", &[]).await.unwrap().get(0);
+            assert_eq!(visible, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_without_tenant_context_sees_no_rows() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+
+        // Running setup twice must succeed and leave every table protected
+        setup_rls(&pool).await.expect("First RLS setup failed");
+        setup_rls(&pool).await.expect("Repeated RLS setup failed");
+
+        let mut client = pool.get().await.expect("Failed to get connection");
+        let tenant_id = uuid::Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap();
+        let transaction = client.transaction().await.expect("Failed to start transaction");
+        transaction
+            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
+            .await
+            .expect("Failed to set tenant context");
+        transaction
+            .execute(
+                "INSERT INTO content (tenant_id, author_id, title)
+                 SELECT tenant_id, id, 'RLS test' FROM users WHERE tenant_id = $1 LIMIT 1",
+                &[&tenant_id],
+            )
+            .await
+            .expect("Failed to insert test content");
+        transaction.commit().await.expect("Failed to commit test content");
+
+        let visible: i64 = client
+            .query_one("SELECT COUNT(*) FROM content", &[])
+            .await
+            .expect("Failed to count content")
+            .get(0);
+        assert_eq!(visible, 0);
     }
 }
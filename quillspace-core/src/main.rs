@@ -1,3 +1,4 @@
+mod cli;
 mod config;
 mod types;
 mod database;
@@ -5,25 +6,35 @@ mod middleware;
 mod routes;
 mod services;
 mod auth;
+#[cfg(test)]
+mod test_harness;
 
 use axum::{
-    extract::State,
-    middleware::from_fn,
+    extract::{FromRef, State},
+    middleware::{from_fn, from_fn_with_state},
     response::IntoResponse,
     routing::get,
     Json, Router,
 };
 use crate::{
-    auth::{JwtManager, CasbinAuthorizer},
+    auth::{JwtManager, CasbinAuthorizer, webhooks::WebhookRegistry},
     config::AppConfig,
     database::DatabaseConnections,
+    middleware::{observability::RequestCounter, rate_limit::TenantRateLimiter},
+    services::{
+        analytics_writer::AnalyticsWriter, billing::BillingService, cache_warm::CacheWarmer, cdn::CdnPurger,
+        connected_websites::{WixConnectionTester, WixSiteCache},
+        custom_roles::CustomRoleService,
+        metrics_registry::{metrics_handler, MetricsRegistry},
+        object_store::{object_store_from_config, ObjectStore}, publish_cache::PublishCache,
+        TemplateEngine,
+    },
 };
 // Removed unused Deserialize import
 use std::{net::SocketAddr, sync::Arc};
-use tokio::sync::Mutex;
 use tokio::net::TcpListener;
 use tower::ServiceBuilder;
-use tracing::{info, warn};
+use tracing::{error, info};
 
 // Enhanced application state with database connections
 #[derive(Clone)]
@@ -33,22 +44,80 @@ pub struct AppState {
     pub jwt_secret: Arc<String>,
     pub jwt_manager: Arc<JwtManager>,
     pub authorizer: Arc<CasbinAuthorizer>,
-    pub request_count: Arc<Mutex<usize>>,
+    pub analytics_writer: AnalyticsWriter,
+    pub webhooks: Arc<WebhookRegistry>,
+    pub publish_cache: Arc<PublishCache>,
+    pub cdn: CdnPurger,
+    pub tenant_rate_limiter: Arc<TenantRateLimiter>,
+    pub request_count: Arc<RequestCounter>,
+    pub metrics: MetricsRegistry,
+    /// Where asset files are kept, chosen by `storage.backend`
+    pub object_store: Arc<dyn ObjectStore>,
+    pub template_engine: Arc<TemplateEngine>,
+    /// Set when `templates.warm.enabled`; warms the caches after a publish
+    pub cache_warmer: Option<Arc<CacheWarmer>>,
+    /// Recent Wix site lookups, shared by website listings
+    pub wix_sites: Arc<WixSiteCache>,
+    /// Recent passed Wix connection tests and each user's test budget
+    pub wix_connection_tests: Arc<WixConnectionTester>,
 }
 
 impl AppState {
     pub async fn new(config: AppConfig) -> anyhow::Result<Self> {
-        let db = DatabaseConnections::new(&config.database.url, &config.clickhouse).await?;
-        let jwt_manager = JwtManager::new(&config.auth.jwt_secret, "quillspace");
-        let authorizer = CasbinAuthorizer::new().await?;
+        services::http_client::configure(config.http_client.clone());
+        let db = DatabaseConnections::new(&config.database, &config.clickhouse).await?;
+        let jwt_manager = JwtManager::from_config(&config.auth, "quillspace")?;
+        let authorizer = Arc::new(CasbinAuthorizer::new().await?);
+        CustomRoleService::new(db.postgres().clone(), authorizer.clone())
+            .load_into_authorizer()
+            .await?;
+        let metrics = MetricsRegistry::from_config(&config.observability);
+        let analytics_writer = AnalyticsWriter::spawn(db.clickhouse().clone(), &config.analytics, metrics.clone());
+        let mut template_engine = TemplateEngine::new(Arc::new(db.clone()))?
+            .with_default_template(config.templates.default_template.clone())
+            .with_minify(config.templates.minify.clone())
+            .with_metrics(metrics.clone());
+        if let Some(dir) = config.dev_template_dir() {
+            template_engine = template_engine.with_dev_templates(dir);
+        }
+        let template_engine = Arc::new(template_engine);
+        let publish_cache = Arc::new(PublishCache::new());
+        let cache_warmer = config.templates.warm.enabled.then(|| {
+            Arc::new(CacheWarmer::new(
+                db.postgres().clone(),
+                db.clickhouse().clone(),
+                template_engine.clone(),
+                publish_cache.clone(),
+                config.templates.minify.clone(),
+                config.templates.warm.clone(),
+            ))
+        });
+        // Providers are configured under [webhooks.<name>]; handlers register here as integrations are added
+        let mut webhooks = WebhookRegistry::new(config.webhooks.clone());
+        let billing = BillingService::new(db.postgres().clone(), config.billing.clone(), config.plans.clone());
+        webhooks.register("stripe", move |body| {
+            let billing = billing.clone();
+            async move { billing.handle_stripe_event(&body).await }
+        });
         
         Ok(Self {
             jwt_secret: Arc::new(config.auth.jwt_secret.clone()),
             jwt_manager: Arc::new(jwt_manager),
-            authorizer: Arc::new(authorizer),
+            authorizer,
+            analytics_writer,
+            webhooks: Arc::new(webhooks),
+            publish_cache,
+            cdn: CdnPurger::new(config.cdn.clone()),
+            object_store: object_store_from_config(&config.storage.backend),
+            template_engine,
+            cache_warmer,
+            tenant_rate_limiter: Arc::new(TenantRateLimiter::new(&config.rate_limit)),
+            wix_sites: Arc::new(WixSiteCache::new(&config.connected_websites)),
+            wix_connection_tests: Arc::new(WixConnectionTester::new(&config.connected_websites)),
             config: Arc::new(config),
             db,
-            request_count: Arc::new(Mutex::new(0)),
+            request_count: Arc::new(RequestCounter::default()),
+            metrics,
         })
     }
 }
@@ -58,36 +127,51 @@ impl AppState {
 struct InfoResponse {
     app_name: String,
     version: String,
-    request_count: usize,
+    request_count: u64,
 }
 
 // Legacy schemas removed - using proper web builder APIs now
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Load configuration
-    let config = AppConfig::from_env().unwrap_or_else(|_| {
-        warn!("Failed to load config from environment, using defaults");
-        AppConfig::default()
-    });
+    // Parse the command line first, so --help and usage errors need no configuration
+    let command = cli::parse(std::env::args_os()).unwrap_or_else(|e| e.exit());
 
     // Initialize tracing with environment filter
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    info!("Starting QuillSpace server with config: {:?}", config.server);
+    // Report on the configuration without refusing it, so a bad one can be inspected
+    if command == cli::Command::ConfigCheck {
+        let (config, sources) = AppConfig::from_env()?;
+        return cli::config_check(&config, &sources);
+    }
+
+    // Load configuration (aborts on bad critical settings in strict mode)
+    let config = AppConfig::load()?;
 
-    // Initialize metrics if enabled
-    if config.observability.metrics_enabled {
-        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new()
-            .build_recorder();
-        if let Err(e) = metrics::set_global_recorder(recorder) {
-            warn!("Failed to set metrics recorder: {}", e);
-        } else {
-            info!("Prometheus metrics enabled on port {}", config.observability.prometheus_port);
+    match command {
+        cli::Command::Serve => serve(config).await,
+        cli::Command::Migrate { directory, baseline } => {
+            let pool = database::postgres::create_pool(&config.database.url, &config.database.pool).await?;
+            cli::migrate(&pool, &directory, baseline.as_deref()).await?;
+            Ok(())
         }
+        cli::Command::CreateAdmin(args) => {
+            let pool = database::postgres::create_pool(&config.database.url, &config.database.pool).await?;
+            let password = std::env::var(cli::ADMIN_PASSWORD_ENV).ok().filter(|password| !password.is_empty());
+            cli::create_admin_user(&pool, &config.password_policy, &args, password).await?;
+            Ok(())
+        }
+        // Handled before the configuration was enforced
+        cli::Command::ConfigCheck => Ok(()),
     }
+}
+
+/// Run the HTTP server until it stops
+async fn serve(config: AppConfig) -> anyhow::Result<()> {
+    info!("Starting QuillSpace server with config: {:?}", config.server);
 
     // Create enhanced app state with database connections
     let state = AppState::new(config.clone()).await?;
@@ -97,7 +181,47 @@ async fn main() -> anyhow::Result<()> {
     database::postgres::setup_rls(state.db.postgres()).await?;
     info!("Row-level security policies configured");
 
+    // Purge analytics past retention in the background
+    services::analytics_retention::AnalyticsRetentionService::new(state.db.clone(), config.analytics.clone())
+        .spawn_purge_task();
+
+    // Snapshot every site into the object store on a schedule
+    if config.backups.enabled {
+        let assets = services::AssetService::new(state.db.postgres().clone(), config.storage.clone(), state.object_store.clone());
+        services::site_backup::SiteBackupService::new(
+            state.db.postgres().clone(),
+            services::site_export::SiteTransferService::new(state.db.postgres().clone(), assets),
+            state.object_store.clone(),
+            config.backups.clone(),
+        )
+        .spawn_backup_task();
+    }
+
+    // Render the most visited pages into the caches without holding up startup
+    if let Some(warmer) = &state.cache_warmer {
+        warmer.clone().spawn_warm_popular();
+    }
+
+    // Publish scheduled content and apply page and content publishing windows as they come due
+    let (publish_cache, template_engine) = (state.publish_cache.clone(), state.template_engine.clone());
+    services::publish_schedule::PublishScheduleService::new(state.db.postgres().clone()).spawn_scheduler(
+        std::time::Duration::from_secs(60),
+        move |changes| {
+            for change in changes {
+                if let Some(site_id) = change.site_id {
+                    publish_cache.invalidate_site(site_id);
+                    template_engine.invalidate_navigation(site_id);
+                }
+            }
+        },
+    );
+
+    // Send transactional email jobs as they come due
+    services::email_automation::EmailAutomationService::new(state.db.postgres().clone(), state.config.email.clone())
+        .spawn_processor(std::time::Duration::from_secs(60));
+
     // Build the enhanced router with comprehensive middleware
+    let metrics = state.metrics.clone();
     let app = create_app(state).await?;
 
     // Define the address to listen on (0.0.0.0 for Docker compatibility)
@@ -106,16 +230,32 @@ async fn main() -> anyhow::Result<()> {
     ], config.server.port));
     
     info!("🚀 QuillSpace server listening on http://{}", addr);
-    info!("📊 Metrics available on http://localhost:{}/metrics", config.observability.prometheus_port);
+    if metrics.is_enabled() {
+        let metrics_addr = SocketAddr::from(([0, 0, 0, 0], config.observability.prometheus_port));
+        let metrics_listener = TcpListener::bind(metrics_addr).await?;
+        info!("📊 Metrics available on http://{}/metrics", metrics_addr);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(metrics_listener, metrics_app(metrics)).await {
+                error!("Metrics server stopped: {}", e);
+            }
+        });
+    }
     info!("📚 API documentation available at http://{}/docs", addr);
 
     // Run the server
     let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Peer addresses let handlers tell trusted proxies apart (see `config.public_url`)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
     
     Ok(())
 }
 
+/// Prometheus scrape endpoint, served from this app's own registry on
+/// `observability.prometheus_port` rather than the public API port
+fn metrics_app(metrics: MetricsRegistry) -> Router {
+    Router::new().route("/metrics", get(metrics_handler)).with_state(metrics)
+}
+
 /// Create the application router with all middleware and routes
 async fn create_app(state: AppState) -> anyhow::Result<Router> {
     let _jwt_secret = state.jwt_secret.clone();
@@ -127,9 +267,13 @@ async fn create_app(state: AppState) -> anyhow::Result<Router> {
         .route("/health", get(health_check))
         .route("/ready", get(readiness_check))
         
+        // Public keys for verifying access tokens
+        .route("/.well-known/jwks.json", get(jwks))
+        
         // Basic routes
         .route("/", get(root))
         .route("/ping", get(ping))
+        .route("/info", get(info))
         
         // API routes
         .nest("/api", routes::create_routes())
@@ -137,9 +281,39 @@ async fn create_app(state: AppState) -> anyhow::Result<Router> {
         // Middleware stack (applied in reverse order)
         .layer(
             ServiceBuilder::new()
-                .layer(from_fn(middleware::observability::metrics_middleware))
-                .layer(from_fn(middleware::observability::cors_middleware))
+                // Outermost, so responses from the layers below that refuse a request (429, 402,
+                // 401, 503) still carry CORS and security headers
                 .layer(from_fn(middleware::observability::security_headers_middleware))
+                .layer(from_fn(middleware::observability::cors_middleware))
+                // Ahead of the rest, so shed requests cost no auth or database work
+                .layer(from_fn_with_state(
+                    middleware::concurrency::ConcurrencyLimit::new(&state.config.server.concurrency),
+                    middleware::concurrency::concurrency_limit_middleware,
+                ))
+                .layer(middleware::compression::compression_layer(&state.config.server.compression))
+                .layer(from_fn_with_state(state.clone(), middleware::auth::token_version_middleware))
+                .layer(from_fn_with_state(state.clone(), middleware::auth::api_key_middleware))
+                .layer(from_fn_with_state(state.clone(), middleware::rate_limit::tenant_rate_limit_middleware))
+                .layer(from_fn_with_state(state.clone(), middleware::billing::subscription_gate_middleware))
+                .layer(from_fn_with_state(
+                    state.jwt_manager.clone(),
+                    middleware::observability::request_span_middleware,
+                ))
+                .layer(from_fn_with_state(
+                    Arc::new(middleware::observability::RequestLogger::new(&state.config.observability.request_log)),
+                    middleware::observability::metrics_middleware,
+                ))
+                .layer(from_fn_with_state(
+                    state.request_count.clone(),
+                    middleware::observability::request_count_middleware,
+                ))
+                // Outside the timeout, so a request that times out has its transaction rolled back
+                .layer(from_fn(middleware::transaction::transaction_middleware))
+                // Innermost, so a 504 still gets CORS and security headers and is counted
+                .layer(from_fn_with_state(
+                    Arc::new(state.config.server.timeout.clone()),
+                    middleware::timeout::request_timeout_middleware,
+                ))
         )
         .with_state(state);
 
@@ -147,6 +321,12 @@ async fn create_app(state: AppState) -> anyhow::Result<Router> {
 }
 
 
+impl FromRef<AppState> for MetricsRegistry {
+    fn from_ref(state: &AppState) -> Self {
+        state.metrics.clone()
+    }
+}
+
 async fn health_check() -> &'static str {
     "OK"
 }
@@ -155,6 +335,11 @@ async fn readiness_check() -> &'static str {
     "Ready"
 }
 
+/// Public keys of the signing keyset, so other services can verify tokens on their own
+async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.jwt_manager.jwks().clone())
+}
+
 // Root route handler
 async fn root() -> &'static str {
     "🚀 Welcome to QuillSpace - High-Performance Multi-Tenant Publishing Platform"
@@ -165,15 +350,11 @@ async fn ping() -> &'static str {
 }
 
 async fn info(State(state): State<AppState>) -> impl IntoResponse {
-    // Increment request count
-    let mut count = state.request_count.lock().await;
-    *count += 1;
-
-    // Create response
+    // Counted by `request_count_middleware`, this request included
     let response = InfoResponse {
         app_name: "QuillSpace Core API".to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
-        request_count: *count,
+        request_count: state.request_count.get(),
     };
 
     Json(response)
@@ -1,10 +1,22 @@
+use crate::{
+    auth::{api_keys::is_api_key, TokenOptions, TokenSubject},
+    database::postgres::tenant_client,
+    services::api_key::ApiKeyService,
+    types::TenantId,
+    AppState,
+};
 use axum::{
-    extract::Request,
-    http::StatusCode,
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, HeaderValue, StatusCode},
     middleware::Next,
     response::Response,
 };
-use tracing::{debug, warn};
+use chrono::Duration;
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+/// Lifetime of the internal token an API key is exchanged for (one request)
+const API_KEY_TOKEN_TTL_MINUTES: i64 = 5;
 
 /// Authentication middleware placeholder
 /// In a production system, this would validate JWT tokens, API keys, etc.
@@ -27,20 +39,123 @@ pub async fn auth_middleware(request: Request, next: Next) -> Result<Response, S
     Ok(next.run(request).await)
 }
 
-/// API key validation middleware
-pub async fn api_key_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
-    // Check for API key in headers
-    if let Some(api_key) = request.headers().get("x-api-key") {
-        if let Ok(key_str) = api_key.to_str() {
-            // In production, validate against database or cache
-            if !key_str.is_empty() {
-                debug!("Valid API key found");
-                return Ok(next.run(request).await);
-            }
+/// API key authentication middleware
+///
+/// Resolves `Authorization: Bearer qs_...` (or `X-API-Key`) keys against the database
+/// and swaps them for a short-lived JWT carrying the key's tenant, role and scopes,
+/// so handlers authenticate API keys and user tokens through the same helpers.
+pub async fn api_key_middleware(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let bearer_key = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .filter(|token| is_api_key(token));
+    let api_key = bearer_key
+        .or_else(|| request.headers().get("x-api-key").and_then(|h| h.to_str().ok()))
+        .map(str::to_string);
+
+    let api_key = match api_key {
+        Some(api_key) => api_key,
+        None => return Ok(next.run(request).await),
+    };
+
+    let service = ApiKeyService::new(state.db.postgres().clone());
+    let key = match service.authenticate(&api_key).await {
+        Ok(Some(key)) => key,
+        Ok(None) => {
+            warn!("Rejected invalid, expired or revoked API key");
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        Err(e) => {
+            error!("API key lookup failed: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
+    };
+
+    let user_id = key.created_by.to_string();
+    let tenant_id = key.tenant_id.to_string();
+    let role = key.role.to_string();
+    let subject = TokenSubject {
+        user_id: &user_id,
+        email: "",
+        first_name: "API key",
+        last_name: &key.name,
+        role: &role,
+        tenant_id: &tenant_id,
+    };
+    let options = TokenOptions {
+        scopes: (!key.scopes.is_empty()).then(|| key.scopes.clone()),
+        ttl: Some(Duration::minutes(API_KEY_TOKEN_TTL_MINUTES)),
+        ..TokenOptions::default()
+    };
+
+    let token = state.jwt_manager.generate_token_for(&subject, &options).map_err(|e| {
+        error!("Failed to issue token for API key: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let header_value = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    debug!(key_prefix = %key.key_prefix, tenant_id = %key.tenant_id, "Authenticated request with API key");
+
+    let headers = request.headers_mut();
+    headers.remove("x-api-key");
+    headers.insert(AUTHORIZATION, header_value);
+
+    Ok(next.run(request).await)
+}
+
+/// Token revocation middleware
+///
+/// Refuses user tokens issued before the user's token version was bumped (logging out
+/// everywhere, disabling 2FA) or whose user was deactivated, so every handler that
+/// authenticates through `extract_auth_context*` only ever sees current tokens.
+/// Must run before `api_key_middleware`: the tokens API keys are exchanged for carry
+/// the key's permissions, not the creator's token version.
+pub async fn token_version_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let claims = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .filter(|token| !is_api_key(token))
+        .and_then(|token| state.jwt_manager.verify_token(token).ok());
+
+    // Invalid tokens fail authentication in the handler
+    let Some(claims) = claims else {
+        return Ok(next.run(request).await);
+    };
+    let (Ok(user_id), Ok(tenant_id)) = (claims.sub.parse::<Uuid>(), claims.tenant_id.parse::<Uuid>()) else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let current_version: Option<i32> = {
+        let client = tenant_client(state.db.postgres(), &TenantId::from_uuid(tenant_id)).await.map_err(|e| {
+            error!("Failed to get database connection: {:#}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        client
+            .query_opt("SELECT token_version FROM users WHERE id = $1 AND active = true", &[&user_id])
+            .await
+            .map_err(|e| {
+                error!("Database error during token version lookup: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .map(|row| row.get("token_version"))
+    };
+
+    if current_version != Some(claims.token_version) {
+        warn!(user_id = %user_id, "Rejected revoked token");
+        return Err(StatusCode::UNAUTHORIZED);
     }
-    
-    // For demo purposes, allow requests without API key
-    debug!("No API key found, allowing request");
     Ok(next.run(request).await)
 }
@@ -0,0 +1,53 @@
+use crate::{
+    auth::jwt_helpers::extract_tenant_context,
+    services::billing::BillingService,
+    AppState,
+};
+use axum::{
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{debug, error};
+
+/// Paths a tenant with an overdue subscription can still write to: signing in and
+/// out, paying, and the provider webhooks that bring the subscription back
+const READ_ONLY_EXEMPT_PREFIXES: &[&str] = &["/api/auth/", "/api/billing/", "/api/webhooks/"];
+
+/// Subscription gate: tenants whose subscription is past due may only read.
+///
+/// Must run after `api_key_middleware`, so API keys are judged by their tenant too.
+/// Requests without a valid token pass through and fail authentication in the handler.
+pub async fn subscription_gate_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let read = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let path = request.uri().path();
+    if read || READ_ONLY_EXEMPT_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+        return Ok(next.run(request).await);
+    }
+    let Ok(tenant_id) = extract_tenant_context(request.headers(), &state.jwt_manager) else {
+        return Ok(next.run(request).await);
+    };
+
+    let billing = BillingService::new(
+        state.db.postgres().clone(),
+        state.config.billing.clone(),
+        state.config.plans.clone(),
+    );
+    match billing.tenant_subscription(&tenant_id).await {
+        Ok(Some(subscription)) if subscription.status.is_read_only() => {
+            debug!(tenant_id = %tenant_id, status = ?subscription.status, "Write refused for overdue subscription");
+            Err(StatusCode::PAYMENT_REQUIRED)
+        }
+        Ok(_) => Ok(next.run(request).await),
+        Err(e) => {
+            // Billing trouble must not take the editor down with it
+            error!(tenant_id = %tenant_id, error = %e, "Subscription lookup failed; allowing request");
+            Ok(next.run(request).await)
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::config::ProxyConfig;
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::HeaderMap,
+};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+/// An address range such as `10.0.0.0/8` or `fd00::/8`; a bare address is a single host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix_len)).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix_len)).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (address, prefix) = match value.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (value.trim(), None),
+        };
+        let network = IpAddr::from_str(address)
+            .map_err(|_| format!("'{}' is not an IP address or CIDR", value))?
+            .to_canonical();
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| format!("'{}' has an invalid prefix length", value))?,
+            None => max_len,
+        };
+        Ok(Self { network, prefix_len })
+    }
+}
+
+impl std::fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+impl From<IpCidr> for String {
+    fn from(cidr: IpCidr) -> Self {
+        cidr.to_string()
+    }
+}
+
+impl TryFrom<String> for IpCidr {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl ProxyConfig {
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|cidr| cidr.contains(ip))
+    }
+}
+
+/// Address of the immediate peer, when the server was started with connect info
+pub fn peer_ip(request: &Request) -> Option<IpAddr> {
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+/// The visitor's address. `X-Forwarded-For` is only read when the immediate peer is a
+/// trusted proxy, and then from the right: each trusted hop vouches for the entry to
+/// its left, so the first untrusted entry is the client. Anything further left was
+/// written by the client and may be forged.
+pub fn client_ip(proxies: &ProxyConfig, headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+    let peer = peer?.to_canonical();
+    if !proxies.is_trusted(peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .collect();
+
+    let mut client = peer;
+    for entry in forwarded.iter().rev() {
+        // A malformed entry cannot be followed further; trust stops at the last good hop
+        let Ok(ip) = IpAddr::from_str(entry) else {
+            break;
+        };
+        client = ip.to_canonical();
+        if !proxies.is_trusted(client) {
+            break;
+        }
+    }
+    Some(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxies() -> ProxyConfig {
+        ProxyConfig {
+            trusted_proxies: vec!["10.0.0.0/8".parse().unwrap(), "fd00::1".parse().unwrap()],
+        }
+    }
+
+    fn forwarded_for(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", value.parse().unwrap());
+        headers
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_direct_connection_uses_peer() {
+        let client = client_ip(&proxies(), &HeaderMap::new(), Some(ip("203.0.113.7")));
+        assert_eq!(client, Some(ip("203.0.113.7")));
+        assert_eq!(client_ip(&proxies(), &HeaderMap::new(), None), None);
+    }
+
+    #[test]
+    fn test_single_trusted_proxy_forwards_client() {
+        let client = client_ip(&proxies(), &forwarded_for("203.0.113.7"), Some(ip("10.1.2.3")));
+        assert_eq!(client, Some(ip("203.0.113.7")));
+
+        // Behind two proxies the right-most untrusted entry is the client, not a forged one to its left
+        let headers = forwarded_for("198.51.100.1, 203.0.113.7, 10.0.0.5");
+        assert_eq!(client_ip(&proxies(), &headers, Some(ip("10.1.2.3"))), Some(ip("203.0.113.7")));
+
+        // A trusted proxy that forwarded nothing is itself the client
+        assert_eq!(client_ip(&proxies(), &HeaderMap::new(), Some(ip("10.1.2.3"))), Some(ip("10.1.2.3")));
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_for_from_untrusted_peer_ignored() {
+        let headers = forwarded_for("10.0.0.1, 192.0.2.50");
+        assert_eq!(client_ip(&proxies(), &headers, Some(ip("198.51.100.4"))), Some(ip("198.51.100.4")));
+
+        let garbage = forwarded_for("not-an-ip");
+        assert_eq!(client_ip(&proxies(), &garbage, Some(ip("10.1.2.3"))), Some(ip("10.1.2.3")));
+    }
+
+    #[test]
+    fn test_cidr_parsing_and_matching() {
+        let cidr: IpCidr = "172.16.0.0/12".parse().unwrap();
+        assert!(cidr.contains(ip("172.31.255.1")));
+        assert!(!cidr.contains(ip("172.32.0.1")));
+        assert!(cidr.contains(ip("::ffff:172.16.0.9")));
+        assert!("0.0.0.0/0".parse::<IpCidr>().unwrap().contains(ip("8.8.8.8")));
+        assert!(proxies().is_trusted(ip("fd00::1")));
+        assert!(!proxies().is_trusted(ip("fd00::2")));
+
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("proxy.internal".parse::<IpCidr>().is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::config::CompressionConfig;
+use axum::{
+    body::HttpBody,
+    http::{header::CONTENT_TYPE, Response},
+};
+use std::sync::Arc;
+use tower_http::compression::{
+    predicate::{And, Predicate, SizeAbove},
+    CompressionLayer,
+};
+
+/// Only compress responses whose media type is on the configured allowlist.
+/// Images and other already-compressed formats are simply never listed.
+#[derive(Debug, Clone)]
+pub struct ContentTypeAllowlist(Arc<Vec<String>>);
+
+impl ContentTypeAllowlist {
+    pub fn new(content_types: &[String]) -> Self {
+        Self(Arc::new(content_types.iter().map(|t| t.trim().to_ascii_lowercase()).collect()))
+    }
+}
+
+impl Predicate for ContentTypeAllowlist {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: HttpBody,
+    {
+        let Some(content_type) = response.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+            return false;
+        };
+        // Ignore parameters such as `; charset=utf-8`
+        let media_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        self.0.contains(&media_type)
+    }
+}
+
+/// Response compression negotiated from `Accept-Encoding`.
+///
+/// Responses smaller than `min_size_bytes` or outside the content-type allowlist
+/// are sent as-is, as are responses that already carry a `Content-Encoding`.
+pub fn compression_layer(config: &CompressionConfig) -> CompressionLayer<And<SizeAbove, ContentTypeAllowlist>> {
+    CompressionLayer::new()
+        .gzip(config.enabled && config.gzip)
+        .br(config.enabled && config.brotli)
+        .compress_when(SizeAbove::new(config.min_size_bytes).and(ContentTypeAllowlist::new(&config.content_types)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Json, Router};
+    use tower::ServiceExt;
+
+    async fn large_json() -> Json<serde_json::Value> {
+        Json(serde_json::json!({ "events": vec!["page_view"; 500] }))
+    }
+
+    async fn small_json() -> Json<serde_json::Value> {
+        Json(serde_json::json!({ "ok": true }))
+    }
+
+    async fn large_image() -> ([(&'static str, &'static str); 1], Vec<u8>) {
+        ([("content-type", "image/png")], vec![0u8; 8192])
+    }
+
+    async fn get_encoding(config: &CompressionConfig, path: &str, accept_encoding: &str) -> Option<String> {
+        let app = Router::new()
+            .route("/large", get(large_json))
+            .route("/small", get(small_json))
+            .route("/image", get(large_image))
+            .layer(compression_layer(config));
+
+        let request = axum::http::Request::builder()
+            .uri(path)
+            .header("accept-encoding", accept_encoding)
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app.oneshot(request).await.expect("Request failed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        response
+            .headers()
+            .get("content-encoding")
+            .map(|v| v.to_str().expect("Invalid content-encoding").to_string())
+    }
+
+    #[tokio::test]
+    async fn test_large_json_compressed_with_gzip() {
+        let encoding = get_encoding(&CompressionConfig::default(), "/large", "gzip").await;
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+    }
+
+    #[tokio::test]
+    async fn test_brotli_preferred_when_advertised() {
+        let encoding = get_encoding(&CompressionConfig::default(), "/large", "gzip;q=0.5, br").await;
+        assert_eq!(encoding.as_deref(), Some("br"));
+    }
+
+    #[tokio::test]
+    async fn test_small_response_left_alone() {
+        assert_eq!(get_encoding(&CompressionConfig::default(), "/small", "gzip").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_images_never_compressed() {
+        assert_eq!(get_encoding(&CompressionConfig::default(), "/image", "gzip, br").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_compression_left_alone() {
+        let config = CompressionConfig { enabled: false, ..CompressionConfig::default() };
+        assert_eq!(get_encoding(&config, "/large", "gzip").await, None);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::config::ConcurrencyConfig;
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+/// In-flight request budget shared by every request
+#[derive(Clone)]
+pub struct ConcurrencyLimit {
+    permits: Arc<Semaphore>,
+    retry_after_secs: u64,
+    exempt_paths: Arc<[String]>,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(config: &ConcurrencyConfig) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(config.max_in_flight)),
+            retry_after_secs: config.retry_after_secs,
+            exempt_paths: config.exempt_paths.clone().into(),
+        }
+    }
+}
+
+/// Load shedding from `[server.concurrency]`: once `max_in_flight` requests are running,
+/// further ones get `503` with `Retry-After` straight away rather than piling up.
+///
+/// Health checks are exempt so a busy node is not also marked down by the load balancer.
+pub async fn concurrency_limit_middleware(
+    State(limit): State<ConcurrencyLimit>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if limit.exempt_paths.iter().any(|path| path == request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let Ok(_permit) = limit.permits.clone().try_acquire_owned() else {
+        warn!(path = %request.uri().path(), "Shedding request: concurrency limit reached");
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, limit.retry_after_secs.to_string())],
+        )
+            .into_response();
+    };
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware::from_fn_with_state, routing::get, Router};
+    use tokio::sync::Notify;
+    use tower::ServiceExt;
+
+    async fn send(app: Router, path: &str) -> Response {
+        let request = axum::http::Request::builder()
+            .uri(path)
+            .body(Body::empty())
+            .expect("Failed to build request");
+        app.oneshot(request).await.expect("Request failed")
+    }
+
+    #[tokio::test]
+    async fn test_requests_beyond_limit_shed_but_health_checks_pass() {
+        let entered = Arc::new(Notify::new());
+        let release = Arc::new(Notify::new());
+        let slow = {
+            let (entered, release) = (entered.clone(), release.clone());
+            move || async move {
+                entered.notify_one();
+                release.notified().await;
+                "done"
+            }
+        };
+        let config = ConcurrencyConfig {
+            max_in_flight: 1,
+            retry_after_secs: 2,
+            exempt_paths: vec!["/health".to_string()],
+        };
+        let app = Router::new()
+            .route("/slow", get(slow))
+            .route("/health", get(|| async { "OK" }))
+            .layer(from_fn_with_state(ConcurrencyLimit::new(&config), concurrency_limit_middleware));
+
+        // Hold the only permit
+        let in_flight = tokio::spawn(send(app.clone(), "/slow"));
+        entered.notified().await;
+
+        let shed = send(app.clone(), "/slow").await;
+        assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(shed.headers()[header::RETRY_AFTER], "2");
+        assert_eq!(send(app.clone(), "/health").await.status(), StatusCode::OK);
+
+        release.notify_one();
+        assert_eq!(in_flight.await.expect("Request task panicked").status(), StatusCode::OK);
+
+        // The permit is returned once the request completes
+        let next = tokio::spawn(send(app.clone(), "/slow"));
+        entered.notified().await;
+        release.notify_one();
+        assert_eq!(next.await.expect("Request task panicked").status(), StatusCode::OK);
+    }
+}
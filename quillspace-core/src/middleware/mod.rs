@@ -1,16 +1,22 @@
 pub mod auth;
+pub mod billing;
+pub mod client_ip;
+pub mod compression;
+pub mod concurrency;
 pub mod tenant;
 pub mod observability;
 pub mod rate_limit;
+pub mod timeout;
+pub mod transaction;
 
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     http::StatusCode,
     middleware::Next,
     response::Response,
 };
-use std::time::Instant;
-use tracing::{info, warn};
+use observability::RequestLogger;
+use std::{sync::Arc, time::Instant};
 use uuid::Uuid;
 
 /// Request ID middleware - adds unique request ID to all requests
@@ -29,35 +35,19 @@ pub async fn request_id_middleware(mut request: Request, next: Next) -> Result<R
     Ok(response)
 }
 
-/// Request timing middleware - logs request duration
-pub async fn timing_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
+/// Request timing middleware - logs request duration through the [`RequestLogger`]
+pub async fn timing_middleware(
+    State(logger): State<Arc<RequestLogger>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
     let start = Instant::now();
     let method = request.method().clone();
     let uri = request.uri().clone();
     
     let response = next.run(request).await;
     
-    let duration = start.elapsed();
-    let status = response.status();
-    
-    if status.is_server_error() {
-        warn!(
-            method = %method,
-            uri = %uri,
-            status = %status,
-            duration_ms = duration.as_millis(),
-            "Request completed with error"
-        );
-    } else {
-        info!(
-            method = %method,
-            uri = %uri,
-            status = %status,
-            duration_ms = duration.as_millis(),
-            "Request completed"
-        );
-    }
-    
+    logger.log(&method, &uri, response.status(), start.elapsed());
     Ok(response)
 }
 
@@ -1,32 +1,151 @@
+use crate::auth::JwtManager;
+use crate::config::RequestLogConfig;
 use axum::{
-    extract::Request,
-    http::{HeaderMap, StatusCode},
+    extract::{Request, State},
+    http::{HeaderMap, Method, StatusCode, Uri},
     middleware::Next,
     response::Response,
 };
-use std::time::Instant;
-use tracing::{error, info, warn};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{error, field, info, info_span, warn, Instrument};
+use uuid::Uuid;
+
+/// Logged in place of a sensitive value
+const REDACTED: &str = "[REDACTED]";
+
+/// Writes the line logged for each request: the URI redacted, and successful requests
+/// sampled per `success_sample_rate`
+#[derive(Debug)]
+pub struct RequestLogger {
+    config: RequestLogConfig,
+    successes: AtomicU64,
+}
+
+impl RequestLogger {
+    pub fn new(config: &RequestLogConfig) -> Self {
+        Self { config: config.clone(), successes: AtomicU64::new(0) }
+    }
+
+    /// Whether to log this successful request; the sampled ones are spread evenly
+    fn sample_success(&self) -> bool {
+        let rate = self.config.success_sample_rate.clamp(0.0, 1.0);
+        let seen = self.successes.fetch_add(1, Ordering::Relaxed) as f64;
+        ((seen + 1.0) * rate).floor() > (seen * rate).floor()
+    }
+
+    /// `uri` safe to log: configured query values, segments after configured path
+    /// segments, and anything that looks like an email address are redacted
+    pub fn redact(&self, uri: &Uri) -> String {
+        let mut after_marker = false;
+        let path = uri
+            .path()
+            .split('/')
+            .map(|segment| {
+                let redact = std::mem::replace(
+                    &mut after_marker,
+                    self.config.redact_path_after.iter().any(|marker| marker.eq_ignore_ascii_case(segment)),
+                );
+                if !segment.is_empty() && (redact || looks_like_email(segment)) {
+                    REDACTED
+                } else {
+                    segment
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let Some(query) = uri.query() else {
+            return path;
+        };
+        let query = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, value))
+                    if looks_like_email(value)
+                        || self.config.redact_query_params.iter().any(|param| param.eq_ignore_ascii_case(name)) =>
+                {
+                    format!("{}={}", name, REDACTED)
+                }
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("{}?{}", path, query)
+    }
 
-/// Metrics middleware - simplified version for compilation
-pub async fn metrics_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
+    /// Log a finished request: server errors as warnings, client errors always, and
+    /// successes when sampled
+    pub fn log(&self, method: &Method, uri: &Uri, status: StatusCode, duration: Duration) {
+        if status.is_server_error() {
+            warn!(
+                method = %method,
+                uri = %self.redact(uri),
+                status = %status,
+                duration_ms = duration.as_millis(),
+                "Request completed with error"
+            );
+        } else if status.is_client_error() || self.sample_success() {
+            info!(
+                method = %method,
+                uri = %self.redact(uri),
+                status = %status,
+                duration_ms = duration.as_millis(),
+                "Request completed"
+            );
+        }
+    }
+}
+
+fn looks_like_email(value: &str) -> bool {
+    value.contains('@') || value.to_ascii_lowercase().contains("%40")
+}
+
+/// Log every request through the [`RequestLogger`]
+pub async fn metrics_middleware(
+    State(logger): State<Arc<RequestLogger>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
     let start = Instant::now();
     let method = request.method().clone();
-    let path = request.uri().path().to_string();
-    
+    let uri = request.uri().clone();
+
     let response = next.run(request).await;
-    
-    let duration = start.elapsed();
-    info!(
-        method = %method,
-        path = %path,
-        status = %response.status(),
-        duration_ms = duration.as_millis(),
-        "Request processed"
-    );
-    
+
+    logger.log(&method, &uri, response.status(), start.elapsed());
     Ok(response)
 }
 
+/// Requests served since startup, shared lock-free by every request
+#[derive(Debug, Default)]
+pub struct RequestCounter(AtomicU64);
+
+impl RequestCounter {
+    pub fn increment(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Count every request that gets past load shedding, whichever handler serves it
+pub async fn request_count_middleware(
+    State(counter): State<Arc<RequestCounter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    counter.increment();
+    next.run(request).await
+}
+
 /// Tracing middleware - adds structured logging spans
 pub async fn tracing_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
     let method = request.method().clone();
@@ -46,9 +165,50 @@ pub async fn tracing_middleware(request: Request, next: Next) -> Result<Response
     Ok(response)
 }
 
+/// Request context span middleware - tags every downstream log line with
+/// `request_id`, `tenant_id` and `user_id`.
+///
+/// Claims are read from the bearer token when one is present and valid;
+/// unauthenticated requests still get a span, just without the tenant/user
+/// fields. Rejecting bad tokens is left to the handlers.
+pub async fn request_span_middleware(
+    State(jwt_manager): State<Arc<JwtManager>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let request_id = request
+        .extensions()
+        .get::<Uuid>()
+        .copied()
+        .or_else(|| {
+            extract_header(request.headers(), "x-request-id")
+                .and_then(|value| Uuid::parse_str(&value).ok())
+        })
+        .unwrap_or_else(Uuid::new_v4);
+    request.extensions_mut().insert(request_id);
+
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        tenant_id = field::Empty,
+        user_id = field::Empty,
+    );
+
+    let token = extract_header(request.headers(), "authorization")
+        .and_then(|value| value.strip_prefix("Bearer ").map(str::to_string));
+    if let Some(token) = token {
+        if let Ok(claims) = jwt_manager.verify_token(&token) {
+            span.record("tenant_id", field::display(&claims.tenant_id));
+            span.record("user_id", field::display(&claims.sub));
+        }
+    }
+
+    next.run(request).instrument(span).await
+}
+
 /// CORS middleware for cross-origin requests
 pub async fn cors_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
-    use axum::http::{Method, HeaderValue};
+    use axum::http::HeaderValue;
     
     // Handle preflight requests
     if request.method() == Method::OPTIONS {
@@ -165,3 +325,184 @@ fn is_uuid(s: &str) -> bool {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware::from_fn_with_state, routing::get, Router};
+    use std::{io, sync::Mutex};
+    use tower::ServiceExt;
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().expect("log buffer poisoned").extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl LogBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().expect("log buffer poisoned")).into_owned()
+        }
+    }
+
+    async fn probe_handler() -> &'static str {
+        info!("probe handler reached");
+        "ok"
+    }
+
+    async fn run_probe(authorization: Option<&str>) -> String {
+        let jwt_manager = Arc::new(JwtManager::new("test-secret-key-of-at-least-32-bytes", "quillspace"));
+        let app = Router::new()
+            .route("/probe", get(probe_handler))
+            .layer(from_fn_with_state(jwt_manager, request_span_middleware));
+
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut builder = axum::http::Request::builder().uri("/probe");
+        if let Some(value) = authorization {
+            builder = builder.header("authorization", value);
+        }
+        let request = builder.body(Body::empty()).expect("Failed to build request");
+
+        let response = app.oneshot(request).await.expect("Request failed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        buffer.contents()
+    }
+
+    /// Log lines of serving `uris` through the request logger
+    async fn run_logged(config: RequestLogConfig, uris: &[&str]) -> String {
+        let app = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/fail", get(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+            .route("/api/pages/preview/:token", get(|| async { "ok" }))
+            .layer(from_fn_with_state(Arc::new(RequestLogger::new(&config)), metrics_middleware));
+
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        for uri in uris {
+            let request = axum::http::Request::builder().uri(*uri).body(Body::empty()).expect("Failed to build request");
+            app.clone().oneshot(request).await.expect("Request failed");
+        }
+        buffer.contents()
+    }
+
+    #[tokio::test]
+    async fn test_preview_token_and_email_are_redacted() {
+        let logs = run_logged(
+            RequestLogConfig::default(),
+            &["/api/pages/preview/eyJwYWdlIjoic2VjcmV0In0?email=ada%40example.com&page=2&token=abc123"],
+        )
+        .await;
+
+        assert!(logs.contains("uri=/api/pages/preview/[REDACTED]?email=[REDACTED]&page=2&token=[REDACTED]"), "{}", logs);
+        assert!(!logs.contains("eyJwYWdlIjoic2VjcmV0In0"));
+        assert!(!logs.contains("ada%40example.com"));
+        assert!(!logs.contains("abc123"));
+    }
+
+    #[test]
+    fn test_redact_leaves_ordinary_uris_alone() {
+        let logger = RequestLogger::new(&RequestLogConfig::default());
+        let uri: Uri = "/api/sites/123/pages?limit=20".parse().unwrap();
+        assert_eq!(logger.redact(&uri), "/api/sites/123/pages?limit=20");
+        let uri: Uri = "/api/users/ada@example.com/preview".parse().unwrap();
+        assert_eq!(logger.redact(&uri), "/api/users/[REDACTED]/preview");
+    }
+
+    #[tokio::test]
+    async fn test_sampling_drops_successes_but_keeps_errors() {
+        let config = RequestLogConfig { success_sample_rate: 0.25, ..RequestLogConfig::default() };
+        let uris: Vec<&str> = std::iter::repeat_n(["/ok", "/ok", "/fail", "/ok"], 5).flatten().collect();
+        let logs = run_logged(config, &uris).await;
+
+        assert_eq!(logs.lines().filter(|line| line.contains("Request completed with error")).count(), 5);
+        assert_eq!(logs.lines().filter(|line| line.contains("Request completed") && line.contains("uri=/ok")).count(), 3);
+        // A missing route is a client error, which sampling never drops
+        let logs = run_logged(RequestLogConfig { success_sample_rate: 0.0, ..RequestLogConfig::default() }, &["/ok", "/missing"]).await;
+        assert!(!logs.contains("uri=/ok"));
+        assert!(logs.contains("uri=/missing"));
+    }
+
+    #[tokio::test]
+    async fn test_request_count_accurate_under_concurrency() {
+        let counter = Arc::new(RequestCounter::default());
+        let app = Router::new()
+            .route("/probe", get(|| async { "ok" }))
+            .layer(from_fn_with_state(counter.clone(), request_count_middleware));
+
+        let requests: Vec<_> = (0..500)
+            .map(|_| {
+                let app = app.clone();
+                tokio::spawn(async move {
+                    let request = axum::http::Request::builder()
+                        .uri("/probe")
+                        .body(Body::empty())
+                        .expect("Failed to build request");
+                    app.oneshot(request).await.expect("Request failed").status()
+                })
+            })
+            .collect();
+        for request in requests {
+            assert_eq!(request.await.expect("Request task panicked"), StatusCode::OK);
+        }
+
+        assert_eq!(counter.get(), 500);
+    }
+
+    #[tokio::test]
+    async fn test_handler_logs_carry_tenant_and_user() {
+        let jwt_manager = JwtManager::new("test-secret-key-of-at-least-32-bytes", "quillspace");
+        let token = jwt_manager.generate_token(
+            "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa",
+            "test@example.com",
+            "Test",
+            "User",
+            "editor",
+            "22222222-2222-2222-2222-222222222222"
+        ).expect("Failed to create test token");
+
+        let logs = run_probe(Some(&format!("Bearer {}", token))).await;
+        let line = logs
+            .lines()
+            .find(|line| line.contains("probe handler reached"))
+            .expect("Handler log line missing");
+
+        assert!(line.contains("request_id="));
+        assert!(line.contains("tenant_id=22222222-2222-2222-2222-222222222222"));
+        assert!(line.contains("user_id=aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa"));
+    }
+
+    #[tokio::test]
+    async fn test_unauthenticated_request_logs_without_user_fields() {
+        let logs = run_probe(None).await;
+        let line = logs
+            .lines()
+            .find(|line| line.contains("probe handler reached"))
+            .expect("Handler log line missing");
+
+        assert!(line.contains("request_id="));
+        assert!(!line.contains("tenant_id="));
+        assert!(!line.contains("user_id="));
+    }
+}
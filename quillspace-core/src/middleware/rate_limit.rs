@@ -1,13 +1,24 @@
+use crate::{
+    config::RateLimitConfig,
+    database::postgres::tenant_client,
+    middleware::client_ip::{client_ip, peer_ip},
+    types::TenantId,
+    AppState,
+};
 use axum::{
-    extract::Request,
-    http::StatusCode,
+    extract::{Request, State},
+    http::{header, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tracing::{debug, error, warn};
+use tracing::{debug, warn};
+use uuid::Uuid;
 
 /// Simple in-memory rate limiter
 /// In production, use Redis or a proper rate limiting service
@@ -28,6 +39,11 @@ impl RateLimiter {
     }
 
     pub fn check_rate_limit(&self, key: &str) -> bool {
+        self.check_rate_limit_with(key, self.max_requests, self.window)
+    }
+
+    /// Check `key` against limits other than the limiter's own
+    pub fn check_rate_limit_with(&self, key: &str, max_requests: usize, window: Duration) -> bool {
         let mut requests = match self.requests.lock() {
             Ok(requests) => requests,
             Err(poisoned) => {
@@ -41,10 +57,10 @@ impl RateLimiter {
         let request_times = requests.entry(key.to_string()).or_insert_with(Vec::new);
         
         // Remove old requests outside the window
-        request_times.retain(|&time| now.duration_since(time) < self.window);
+        request_times.retain(|&time| now.duration_since(time) < window);
         
         // Check if we're under the limit
-        if request_times.len() < self.max_requests {
+        if request_times.len() < max_requests {
             request_times.push(now);
             true
         } else {
@@ -53,36 +69,21 @@ impl RateLimiter {
     }
 }
 
-/// Rate limiting middleware
-pub async fn rate_limit_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
+/// Rate limiting middleware, keyed by the client address (see `client_ip`)
+pub async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
     // Create a simple rate limiter (100 requests per minute)
     static RATE_LIMITER: std::sync::OnceLock<RateLimiter> = std::sync::OnceLock::new();
     let limiter = RATE_LIMITER.get_or_init(|| RateLimiter::new(100, Duration::from_secs(60)));
     
     // Use IP address as the key (in production, consider user ID or API key)
-    let client_ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|h| match h.to_str() {
-            Ok(s) => Some(s),
-            Err(e) => {
-                warn!("Invalid x-forwarded-for header encoding: {}", e);
-                None
-            }
-        })
-        .unwrap_or_else(|| {
-            // Fallback to connection info if available
-            request.headers()
-                .get("x-real-ip")
-                .and_then(|h| match h.to_str() {
-                    Ok(s) => Some(s),
-                    Err(e) => {
-                        warn!("Invalid x-real-ip header encoding: {}", e);
-                        None
-                    }
-                })
-                .unwrap_or("127.0.0.1")
-        });
+    let client_ip = client_ip(&state.config.proxy, request.headers(), peer_ip(&request))
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let client_ip = client_ip.as_str();
     
     if limiter.check_rate_limit(client_ip) {
         debug!("Rate limit check passed for {}", client_ip);
@@ -93,36 +94,228 @@ pub async fn rate_limit_middleware(request: Request, next: Next) -> Result<Respo
     }
 }
 
-/// Per-tenant rate limiting middleware
+/// A tenant's request budget: `max_requests` per `window_secs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantRateLimit {
+    pub max_requests: u32,
+    pub window_secs: u64,
+}
+
+impl TenantRateLimit {
+    /// Longest window a tenant can be given
+    pub const MAX_WINDOW_SECS: u64 = 3600;
+
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        Self {
+            max_requests: config.max_requests,
+            window_secs: config.window_secs,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_requests == 0 {
+            return Err("max_requests must be at least 1".to_string());
+        }
+        if !(1..=Self::MAX_WINDOW_SECS).contains(&self.window_secs) {
+            return Err(format!("window_secs must be between 1 and {}", Self::MAX_WINDOW_SECS));
+        }
+        Ok(())
+    }
+
+    /// The tenant's own limits from `settings.rate_limit`, if it has any
+    pub fn from_tenant_settings(settings: &Value) -> anyhow::Result<Option<Self>> {
+        match settings.get("rate_limit") {
+            Some(Value::Null) | None => Ok(None),
+            Some(rate_limit) => {
+                let limit: Self = serde_json::from_value(rate_limit.clone())
+                    .map_err(|e| anyhow::anyhow!("Invalid rate limit settings: {}", e))?;
+                limit.validate().map_err(|e| anyhow::anyhow!("Invalid rate limit settings: {}", e))?;
+                Ok(Some(limit))
+            }
+        }
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+}
+
+/// Per-tenant request counting, with each tenant's limits cached for a short TTL so
+/// changes made through the admin API apply on every node within that window
+pub struct TenantRateLimiter {
+    requests: RateLimiter,
+    default_limit: TenantRateLimit,
+    cache_ttl: Duration,
+    limits: Mutex<HashMap<Uuid, (TenantRateLimit, Instant)>>,
+}
+
+impl TenantRateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let default_limit = TenantRateLimit::from_config(config);
+        Self {
+            requests: RateLimiter::new(default_limit.max_requests as usize, default_limit.window()),
+            default_limit,
+            cache_ttl: Duration::from_secs(config.settings_cache_ttl_secs),
+            limits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The tenant's limits if cached and still fresh
+    pub fn cached_limit(&self, tenant_id: Uuid) -> Option<TenantRateLimit> {
+        let limits = self.limits.lock().ok()?;
+        limits
+            .get(&tenant_id)
+            .filter(|(_, cached_at)| cached_at.elapsed() < self.cache_ttl)
+            .map(|(limit, _)| *limit)
+    }
+
+    /// Cache the limits read from a tenant's settings, the defaults if it has none
+    pub fn cache_limit(&self, tenant_id: Uuid, settings_limit: Option<TenantRateLimit>) -> TenantRateLimit {
+        let limit = settings_limit.unwrap_or(self.default_limit);
+        if let Ok(mut limits) = self.limits.lock() {
+            limits.insert(tenant_id, (limit, Instant::now()));
+        }
+        limit
+    }
+
+    /// Forget the tenant's cached limits so this node reads them again on the next request
+    pub fn invalidate(&self, tenant_id: Uuid) {
+        if let Ok(mut limits) = self.limits.lock() {
+            limits.remove(&tenant_id);
+        }
+    }
+
+    /// The tenant's limits, read from its settings when the cached copy has expired.
+    /// Falls back to the defaults if the settings cannot be read.
+    pub async fn limit_for(&self, db: &Pool, tenant_id: Uuid) -> TenantRateLimit {
+        if let Some(limit) = self.cached_limit(tenant_id) {
+            return limit;
+        }
+
+        let settings_limit = match load_tenant_settings(db, tenant_id).await {
+            Ok(settings) => TenantRateLimit::from_tenant_settings(&settings).unwrap_or_else(|e| {
+                warn!(tenant_id = %tenant_id, "Ignoring tenant rate limit: {:#}", e);
+                None
+            }),
+            Err(e) => {
+                warn!(tenant_id = %tenant_id, "Failed to load tenant rate limit, using defaults: {:#}", e);
+                None
+            }
+        };
+        self.cache_limit(tenant_id, settings_limit)
+    }
+
+    /// Count a request against the tenant's budget; `false` once it is spent
+    pub fn check(&self, tenant_id: Uuid, limit: TenantRateLimit) -> bool {
+        self.requests
+            .check_rate_limit_with(&tenant_id.to_string(), limit.max_requests as usize, limit.window())
+    }
+}
+
+async fn load_tenant_settings(db: &Pool, tenant_id: Uuid) -> anyhow::Result<Value> {
+    let client = tenant_client(db, &TenantId::from_uuid(tenant_id)).await?;
+    let row = client
+        .query_opt("SELECT settings FROM tenants WHERE id = $1", &[&tenant_id])
+        .await?;
+    Ok(row.map(|row| row.get("settings")).unwrap_or(Value::Null))
+}
+
+/// Per-tenant rate limiting middleware. The tenant comes from the verified bearer token;
+/// unauthenticated requests are not counted here.
 pub async fn tenant_rate_limit_middleware(
+    State(state): State<AppState>,
     request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    // Extract tenant ID from headers or context
+) -> Response {
     let tenant_id = request
         .headers()
-        .get("x-tenant-id")
-        .and_then(|h| match h.to_str() {
-            Ok(s) => Some(s),
-            Err(e) => {
-                error!("Invalid x-tenant-id header encoding - potential security issue: {}", e);
-                None
-            }
-        })
-        .unwrap_or_else(|| {
-            warn!("No tenant ID found in headers, using default");
-            "default"
-        });
-    
-    // Create tenant-specific rate limiter (1000 requests per minute per tenant)
-    static TENANT_LIMITER: std::sync::OnceLock<RateLimiter> = std::sync::OnceLock::new();
-    let limiter = TENANT_LIMITER.get_or_init(|| RateLimiter::new(1000, Duration::from_secs(60)));
-    
-    if limiter.check_rate_limit(tenant_id) {
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_manager.verify_token(token).ok())
+        .and_then(|claims| Uuid::parse_str(&claims.tenant_id).ok());
+    let Some(tenant_id) = tenant_id else {
+        return next.run(request).await;
+    };
+
+    let limiter = &state.tenant_rate_limiter;
+    let limit = limiter.limit_for(state.db.postgres(), tenant_id).await;
+    if limiter.check(tenant_id, limit) {
         debug!("Tenant rate limit check passed for {}", tenant_id);
-        Ok(next.run(request).await)
+        next.run(request).await
     } else {
         warn!("Tenant rate limit exceeded for {}", tenant_id);
-        Err(StatusCode::TOO_MANY_REQUESTS)
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, limit.window_secs.to_string())],
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn limiter() -> TenantRateLimiter {
+        TenantRateLimiter::new(&RateLimitConfig {
+            max_requests: 10,
+            window_secs: 60,
+            settings_cache_ttl_secs: 30,
+        })
+    }
+
+    /// Requests allowed before the first 429
+    fn allowed(limiter: &TenantRateLimiter, tenant_id: Uuid, settings: &Value) -> usize {
+        let settings_limit = TenantRateLimit::from_tenant_settings(settings).expect("Invalid settings");
+        let limit = limiter.cache_limit(tenant_id, settings_limit);
+        (0..100).take_while(|_| limiter.check(tenant_id, limit)).count()
+    }
+
+    #[test]
+    fn test_lowered_tenant_limit_rate_limits_sooner() {
+        let (limiter, busy, other) = (limiter(), Uuid::new_v4(), Uuid::new_v4());
+        let settings = json!({ "capabilities": [] });
+
+        // Settings after PUT /tenants/:id/rate-limit, as the middleware reads them back
+        let lowered = json!({ "capabilities": [], "rate_limit": { "max_requests": 3, "window_secs": 60 } });
+
+        assert_eq!(allowed(&limiter, busy, &lowered), 3);
+        assert_eq!(allowed(&limiter, other, &settings), 10);
+        assert_eq!(TenantRateLimit::from_tenant_settings(&settings).unwrap(), None);
+    }
+
+    #[test]
+    fn test_cached_limit_expires_and_invalidates() {
+        let limiter = TenantRateLimiter::new(&RateLimitConfig {
+            max_requests: 10,
+            window_secs: 60,
+            settings_cache_ttl_secs: 0,
+        });
+        let tenant_id = Uuid::new_v4();
+        limiter.cache_limit(tenant_id, None);
+        assert_eq!(limiter.cached_limit(tenant_id), None);
+
+        let limiter = self::limiter();
+        let lowered = TenantRateLimit { max_requests: 3, window_secs: 60 };
+        assert_eq!(limiter.cache_limit(tenant_id, Some(lowered)), lowered);
+        assert_eq!(limiter.cached_limit(tenant_id), Some(lowered));
+        limiter.invalidate(tenant_id);
+        assert_eq!(limiter.cached_limit(tenant_id), None);
+    }
+
+    #[test]
+    fn test_invalid_tenant_rate_limit_rejected() {
+        for rate_limit in [
+            json!({ "max_requests": 0, "window_secs": 60 }),
+            json!({ "max_requests": 10, "window_secs": 0 }),
+            json!({ "max_requests": 10, "window_secs": 86400 }),
+            json!({ "max_requests": 10, "window_secs": 60, "burst": 5 }),
+            json!("fast"),
+        ] {
+            assert!(TenantRateLimit::from_tenant_settings(&json!({ "rate_limit": rate_limit })).is_err());
+        }
     }
 }
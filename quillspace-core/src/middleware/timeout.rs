@@ -0,0 +1,100 @@
+use crate::config::TimeoutConfig;
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Request deadline from `[server.timeout]`: a handler still running when its route's
+/// limit passes is dropped, cancelling its work, and the client gets `504`.
+///
+/// Work that legitimately takes longer belongs in a background job, not a bigger limit.
+pub async fn request_timeout_middleware(
+    State(config): State<Arc<TimeoutConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let limit = config.limit_for(&path);
+
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            warn!(path = %path, timeout_ms = limit.as_millis() as u64, "Request timed out");
+            StatusCode::GATEWAY_TIMEOUT.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware::from_fn_with_state, routing::get, Router};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::{Duration, Instant};
+    use tower::ServiceExt;
+
+    /// Marks the handler as cancelled when its future is dropped before finishing
+    struct CancelGuard(Arc<AtomicBool>, bool);
+
+    impl Drop for CancelGuard {
+        fn drop(&mut self) {
+            if !self.1 {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn app(config: TimeoutConfig, cancelled: Arc<AtomicBool>) -> Router {
+        let slow = move || {
+            let cancelled = cancelled.clone();
+            async move {
+                let mut guard = CancelGuard(cancelled, false);
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                guard.1 = true;
+                "done"
+            }
+        };
+        Router::new()
+            .route("/slow", get(slow.clone()))
+            .route("/exports/slow", get(slow))
+            .layer(from_fn_with_state(Arc::new(config), request_timeout_middleware))
+    }
+
+    async fn status(app: Router, path: &str) -> StatusCode {
+        let request = axum::http::Request::builder()
+            .uri(path)
+            .body(Body::empty())
+            .expect("Failed to build request");
+        app.oneshot(request).await.expect("Request failed").status()
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_times_out_with_504() {
+        let config = TimeoutConfig { default_ms: 50, routes: HashMap::new() };
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let started = Instant::now();
+        assert_eq!(status(app(config, cancelled.clone()), "/slow").await, StatusCode::GATEWAY_TIMEOUT);
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(50) && elapsed < Duration::from_millis(300), "{:?}", elapsed);
+        assert!(cancelled.load(Ordering::SeqCst), "handler future was not dropped");
+    }
+
+    #[tokio::test]
+    async fn test_route_override_extends_limit() {
+        let config = TimeoutConfig {
+            default_ms: 50,
+            routes: HashMap::from([("/exports/".to_string(), 2_000)]),
+        };
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        assert_eq!(status(app(config.clone(), cancelled.clone()), "/exports/slow").await, StatusCode::OK);
+        assert!(!cancelled.load(Ordering::SeqCst));
+        assert_eq!(status(app(config, cancelled), "/slow").await, StatusCode::GATEWAY_TIMEOUT);
+    }
+}
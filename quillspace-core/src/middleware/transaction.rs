@@ -0,0 +1,216 @@
+//! Request-scoped database transactions.
+//!
+//! [`transaction_middleware`] gives every request an empty slot. A handler that takes a
+//! [`TenantTransaction`] opens a transaction in it, in the caller's tenant, and runs its
+//! writes through [`TenantTransaction::client`]. Once the handler has answered, the
+//! transaction is committed if the response is a success or redirect and rolled back
+//! otherwise, so an error after the first of several related writes leaves none of
+//! them behind. A failed commit turns the response into a `500`.
+//!
+//! Requests whose handlers do not ask for a transaction never take a connection.
+
+use crate::{auth::jwt_helpers::extract_auth_context_with_role, types::TenantId, AppState};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use deadpool_postgres::Object;
+use std::sync::Arc;
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+use tracing::{error, warn};
+
+/// The transaction of one request, if its handler opened one
+#[derive(Clone, Default)]
+struct TransactionSlot(Arc<Mutex<Option<OpenTransaction>>>);
+
+/// A pooled connection inside `BEGIN`. One dropped before it is finished, such as when
+/// the request is cancelled, is rolled back in the background before its connection
+/// goes back to the pool.
+struct OpenTransaction(Option<Object>);
+
+impl OpenTransaction {
+    async fn finish(mut self, commit: bool) -> Result<(), tokio_postgres::Error> {
+        match self.0.take() {
+            Some(client) => client.batch_execute(if commit { "COMMIT" } else { "ROLLBACK" }).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for OpenTransaction {
+    fn drop(&mut self) {
+        let Some(client) = self.0.take() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            // Without a runtime the connection cannot be cleaned, so keep it out of the pool
+            drop(Object::take(client));
+            return;
+        };
+        runtime.spawn(async move {
+            if let Err(e) = client.batch_execute("ROLLBACK").await {
+                warn!("Failed to roll back abandoned request transaction: {}", e);
+                drop(Object::take(client));
+            }
+        });
+    }
+}
+
+/// Commit or roll back the transaction the handler opened, by the response status
+pub async fn transaction_middleware(mut request: Request, next: Next) -> Response {
+    let slot = TransactionSlot::default();
+    request.extensions_mut().insert(slot.clone());
+
+    let response = next.run(request).await;
+
+    let Some(transaction) = slot.0.lock().await.take() else {
+        return response;
+    };
+    let commit = response.status().is_success() || response.status().is_redirection();
+    match transaction.finish(commit).await {
+        Ok(()) => response,
+        Err(e) if commit => {
+            error!("Failed to commit request transaction: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(e) => {
+            warn!("Failed to roll back request transaction: {}", e);
+            response
+        }
+    }
+}
+
+/// A transaction for the request, with row-level security set to the caller's tenant
+pub struct TenantTransaction {
+    slot: TransactionSlot,
+    pub tenant_id: TenantId,
+}
+
+impl TenantTransaction {
+    /// The transaction's connection; hold it only while running queries
+    pub async fn client(&self) -> Result<MappedMutexGuard<'_, Object>, StatusCode> {
+        MutexGuard::try_map(self.slot.0.lock().await, |open| open.as_mut().and_then(|open| open.0.as_mut()))
+            .map_err(|_| {
+                error!("Request transaction used after it finished");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for TenantTransaction {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let auth_context = extract_auth_context_with_role(&parts.headers, &state.jwt_manager)
+            .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        let Some(slot) = parts.extensions.get::<TransactionSlot>().cloned() else {
+            error!("TenantTransaction used on a route without transaction_middleware");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        };
+
+        let mut open = slot.0.lock().await;
+        if open.is_none() {
+            let client = state.db.postgres().get().await.map_err(|e| {
+                error!("Failed to get database connection: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            client.batch_execute("BEGIN").await.map_err(|e| {
+                error!("Failed to start request transaction: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            // From here on, dropping the transaction rolls it back
+            let transaction = OpenTransaction(Some(client));
+            if let Some(client) = &transaction.0 {
+                client
+                    .execute(
+                        "SELECT set_config('quillspace.tenant_id', $1, true)",
+                        &[&auth_context.tenant_id.to_string()],
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to set RLS tenant context: {}", e);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?;
+            }
+            *open = Some(transaction);
+        }
+        drop(open);
+
+        Ok(Self { slot, tenant_id: auth_context.tenant_id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Path, middleware::from_fn, routing::post, Router};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    /// Creates content, then adds `co_author` to it; a co-author who does not exist
+    /// fails the second write
+    async fn create_with_co_author(
+        transaction: TenantTransaction,
+        Path((title, co_author)): Path<(String, Uuid)>,
+    ) -> Result<StatusCode, StatusCode> {
+        let client = transaction.client().await?;
+        let tenant_id = transaction.tenant_id.as_uuid();
+        let author: Uuid = client
+            .query_one("SELECT id FROM users WHERE tenant_id = $1 LIMIT 1", &[tenant_id])
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .get(0);
+        let content_id: Uuid = client
+            .query_one(
+                "INSERT INTO content (tenant_id, author_id, title, slug, body, status, locale, translation_group_id)
+                 VALUES ($1, $2, $3, $3, '', 'Draft', 'en-US', uuid_generate_v4())
+                 RETURNING id",
+                &[tenant_id, &author, &title],
+            )
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .get(0);
+        client
+            .execute(
+                "INSERT INTO content_authors (content_id, user_id, tenant_id, position) VALUES ($1, $2, $3, 1)",
+                &[&content_id, &co_author, tenant_id],
+            )
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        Ok(StatusCode::CREATED)
+    }
+
+    #[tokio::test]
+    async fn test_error_after_first_write_rolls_back_everything() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let router = Router::new()
+            .route("/content/:title/:co_author", post(create_with_co_author))
+            .layer(from_fn(transaction_middleware))
+            .with_state(app.state.clone());
+        let admin = &app.tenant_a.admin;
+        let send = |title: &str, co_author: Uuid| {
+            let request = app.request(axum::http::Method::POST, &format!("/content/{}/{}", title, co_author), admin, None);
+            let router = router.clone();
+            async move { router.oneshot(request).await.expect("Request failed").status() }
+        };
+        let count = |title: &'static str| {
+            let pool = app.admin_pool.clone();
+            async move {
+                let client = pool.get().await.unwrap();
+                client.query_one("SELECT COUNT(*) FROM content WHERE title = $1", &[&title]).await.unwrap().get::<_, i64>(0)
+            }
+        };
+
+        assert_eq!(send("sanditon", Uuid::new_v4()).await, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(count("sanditon").await, 0);
+
+        assert_eq!(send("persuasion", admin.id).await, StatusCode::CREATED);
+        assert_eq!(count("persuasion").await, 1);
+    }
+}
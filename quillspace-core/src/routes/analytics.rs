@@ -1,47 +1,43 @@
 use crate::{
-    auth::jwt_helpers::extract_auth_context_with_role,
-    types::{ApiResponse, AnalyticsEvent, TenantId, UserRole},
+    auth::jwt_helpers::{extract_auth_context, extract_auth_context_with_role},
+    database::postgres::tenant_client,
+    services::{
+        analytics::{run_batch, AnalyticsService, BatchQueryError, BatchQueryResult, NamedAnalyticsQuery, BATCH_TIMEOUT},
+        analytics_events::{load_event_schema, validate_event, CustomEvent},
+        analytics_query::{run_query, AnalyticsQuerySpec},
+        analytics_replay::{replay_tenant, ReplayError, ReplayRange},
+        analytics_retention::AnalyticsRetentionService,
+        plans::PlanCheck,
+        timezone::load_tenant_timezone,
+    },
+    routes::enforce_plan_limit,
+    types::{ApiResponse, TenantId, UserRole},
     AppState,
 };
 use axum::{
-    extract::{Path, Query, Request, State},
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
-    response::IntoResponse,
-    routing::{get, post},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use chrono::Utc;
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
-/// Extract tenant and user context from JWT token
-fn extract_auth_context(headers: &HeaderMap, jwt_manager: &crate::auth::jwt::JwtManager) -> Result<(TenantId, Uuid), StatusCode> {
-    let auth_header = headers
-        .get("authorization")
-        .and_then(|h| match h.to_str() {
-            Ok(s) => Some(s),
-            Err(e) => {
-                error!("Invalid authorization header encoding - potential security issue: {}", e);
-                None
-            }
-        })
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-    
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-    
-    let claims = jwt_manager.verify_token(token)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
-    
-    let tenant_id = TenantId::from_uuid(
-        Uuid::parse_str(&claims.tenant_id).map_err(|_| StatusCode::UNAUTHORIZED)?
-    );
-    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
-    
-    Ok((tenant_id, user_id))
+/// The tenant's timezone, which daily buckets are aligned to
+async fn tenant_timezone(state: &AppState, tenant_id: &TenantId) -> Result<Tz, StatusCode> {
+    let client = tenant_client(state.db.postgres(), tenant_id).await.map_err(|e| {
+        error!("Failed to get database connection: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    load_tenant_timezone(&client, tenant_id.as_uuid()).await.map_err(|e| {
+        error!(tenant_id = %tenant_id, "Failed to load tenant timezone: {:#}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
 }
 
 /// Create analytics routes
@@ -51,59 +47,49 @@ pub fn create_routes() -> Router<AppState> {
         .route("/stats", get(get_tenant_stats))
         .route("/content/top", get(get_top_content))
         .route("/recent-activity", get(get_recent_activity))
-        .route("/users/{user_id}/activity", get(get_user_activity))
+        .route("/users/:user_id/activity", get(get_user_activity))
+        .route("/batch", post(batch_query))
+        .route("/query", post(run_analytics_query))
+        .route("/retention", put(set_retention))
+        .route("/data", delete(delete_tenant_analytics))
+        .route("/replay", post(replay_content_analytics))
 }
 
-/// Record an analytics event
+/// Record a custom event, checked against the tenant's event schema and queued for the
+/// batching writer; rejected events get 422 with the reason
 async fn record_event(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(event_request): Json<RecordEventRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
+    Json(event): Json<CustomEvent>,
+) -> Result<Response, StatusCode> {
     let request_id = Uuid::new_v4();
     let (tenant_id, user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
-    let user_id = Some(user_id);
 
-    let analytics = state.db.clickhouse();
-    
-    let event = AnalyticsEvent {
-        event_id: Uuid::new_v4(),
-        tenant_id: *tenant_id.as_uuid(),
-        user_id,
-        event_type: event_request.event_type,
-        event_data: event_request.event_data,
-        timestamp: Utc::now(),
-        session_id: event_request.session_id,
-        ip_address: event_request.ip_address,
-        user_agent: event_request.user_agent,
-    };
+    let client = tenant_client(state.db.postgres(), &tenant_id).await.map_err(|e| {
+        error!("Failed to get database connection: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let schema = load_event_schema(&client, tenant_id.as_uuid()).await.map_err(|e| {
+        error!(tenant_id = %tenant_id, "Failed to load event schema: {:#}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    match analytics.record_event(&event).await {
-        Ok(_) => {
-            info!(
-                tenant_id = %tenant_id,
-                event_type = %event.event_type,
-                "Analytics event recorded"
-            );
-            
-            let response = ApiResponse::success(
-                RecordEventResponse {
-                    event_id: event.event_id,
-                    recorded_at: event.timestamp,
-                },
-                request_id,
-            );
-            Ok(Json(response))
-        }
-        Err(e) => {
-            error!(
-                tenant_id = %tenant_id,
-                error = %e,
-                "Failed to record analytics event"
-            );
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
+    if let Err(e) = validate_event(&event, schema.as_ref()) {
+        warn!(tenant_id = %tenant_id, event = %event.name, "Rejected custom event: {}", e);
+        let response = ApiResponse::<()>::error(e.to_string(), request_id);
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(response)).into_response());
     }
+
+    let mut event = event.into_analytics_event(*tenant_id.as_uuid(), Some(user_id));
+    event.user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).map(str::to_string);
+    let response = RecordEventResponse {
+        event_id: event.event_id,
+        event_type: event.event_type.clone(),
+        recorded_at: event.timestamp,
+    };
+    state.analytics_writer.record_event(event);
+
+    Ok((StatusCode::ACCEPTED, Json(ApiResponse::success(response, request_id))).into_response())
 }
 
 /// Get tenant analytics statistics
@@ -185,6 +171,7 @@ async fn get_user_activity(
     // Verify admin authorization for user activity analytics
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("analytics", "read")?;
     
     if auth_context.user_role != UserRole::Admin {
         return Err(StatusCode::FORBIDDEN);
@@ -192,10 +179,11 @@ async fn get_user_activity(
     
     let tenant_id = auth_context.tenant_id;
     
+    let tz = tenant_timezone(&state, &tenant_id).await?;
     let analytics = state.db.clickhouse();
     let days = params.days.unwrap_or(7).min(365);
     
-    match analytics.get_user_activity(&tenant_id, &user_id, days).await {
+    match analytics.get_user_activity(&tenant_id, &user_id, days, tz).await {
         Ok(activity) => {
             let response = ApiResponse::success(
                 UserActivityResponse {
@@ -220,20 +208,210 @@ async fn get_user_activity(
     }
 }
 
-// Request/Response schemas
+/// Run several dashboard queries concurrently and return them in one response
+async fn batch_query(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(batch_request): Json<BatchQueryRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    auth_context.require_scope("analytics", "read")?;
 
-#[derive(Debug, Deserialize)]
-struct RecordEventRequest {
-    event_type: String,
-    event_data: serde_json::Value,
-    session_id: Option<String>,
-    ip_address: Option<String>,
-    user_agent: Option<String>,
+    // Per-user queries carry the same admin restriction as the standalone endpoint
+    if auth_context.user_role != UserRole::Admin
+        && batch_request.queries.iter().any(|named| named.query.requires_admin())
+    {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let tenant_id = auth_context.tenant_id;
+    let tz = tenant_timezone(&state, &tenant_id).await?;
+    let analytics = AnalyticsService::new_clickhouse(state.db.clickhouse().clone());
+    let query_tenant_id = tenant_id.clone();
+
+    let results = run_batch(batch_request.queries, BATCH_TIMEOUT, move |query| {
+        let analytics = analytics.clone();
+        let tenant_id = query_tenant_id.clone();
+        async move { analytics.execute_query(&tenant_id, &query, tz).await }
+    })
+    .await;
+
+    match results {
+        Ok(results) => {
+            let response = ApiResponse::success(
+                BatchQueryResponse { tenant_id, results },
+                request_id,
+            );
+            Ok(Json(response))
+        }
+        Err(BatchQueryError::TimedOut) => {
+            error!(tenant_id = %tenant_id, "Batch analytics query timed out");
+            Err(StatusCode::GATEWAY_TIMEOUT)
+        }
+        Err(e) => {
+            info!(tenant_id = %tenant_id, error = %e, "Rejected batch analytics query");
+            Err(StatusCode::BAD_REQUEST)
+        }
+    }
+}
+
+/// Run an ad-hoc query built from the allowed metrics, dimensions and filters, always
+/// scoped to the caller's tenant; invalid queries get 400 with the reason
+async fn run_analytics_query(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Response, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    auth_context.require_scope("analytics", "read")?;
+    let tenant_id = auth_context.tenant_id;
+    let tz = tenant_timezone(&state, &tenant_id).await?;
+
+    let compiled = AnalyticsQuerySpec::parse(body).and_then(|spec| {
+        if spec.requires_admin() && auth_context.user_role != UserRole::Admin {
+            return Ok(None);
+        }
+        spec.compile(tenant_id.as_uuid(), tz).map(Some)
+    });
+    let query = match compiled {
+        Ok(Some(query)) => query,
+        Ok(None) => return Err(StatusCode::FORBIDDEN),
+        Err(e) => {
+            info!(tenant_id = %tenant_id, error = %e, "Rejected analytics query");
+            let response = ApiResponse::<()>::error(e.to_string(), request_id);
+            return Ok((StatusCode::BAD_REQUEST, Json(response)).into_response());
+        }
+    };
+
+    match tokio::time::timeout(BATCH_TIMEOUT, run_query(state.db.clickhouse().client(), &query)).await {
+        Ok(Ok(rows)) => Ok(Json(ApiResponse::success(rows, request_id)).into_response()),
+        Ok(Err(e)) => {
+            error!(tenant_id = %tenant_id, "Analytics query failed: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        Err(_) => {
+            error!(tenant_id = %tenant_id, "Analytics query timed out");
+            Err(StatusCode::GATEWAY_TIMEOUT)
+        }
+    }
+}
+
+/// Set the tenant's analytics retention period (admin only); `null` restores the platform default
+async fn set_retention(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(retention_request): Json<RetentionRequest>,
+) -> Result<Response, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    auth_context.require_scope("analytics", "admin")?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if retention_request
+        .retention_days
+        .is_some_and(|days| !(1..=MAX_RETENTION_DAYS).contains(&days))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let tenant_id = auth_context.tenant_id;
+    let check = PlanCheck::AnalyticsRetention { days: retention_request.retention_days };
+    if let Err(response) = enforce_plan_limit(&state, &tenant_id, check, request_id).await {
+        return Ok(response);
+    }
+    let retention = AnalyticsRetentionService::new(state.db.clone(), state.config.analytics.clone());
+
+    match retention.set_tenant_retention(&tenant_id, retention_request.retention_days).await {
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Ok(true) => {
+            info!(tenant_id = %tenant_id, retention_days = ?retention_request.retention_days, "Analytics retention updated");
+            let response = ApiResponse::success(
+                RetentionResponse {
+                    tenant_id,
+                    retention_days: retention_request.retention_days.unwrap_or(state.config.analytics.retention_days),
+                },
+                request_id,
+            );
+            Ok(Json(response).into_response())
+        }
+        Err(e) => {
+            error!(tenant_id = %tenant_id, error = %e, "Failed to update analytics retention");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Delete all of the tenant's analytics data (admin only, GDPR erasure)
+async fn delete_tenant_analytics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    auth_context.require_scope("analytics", "admin")?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let tenant_id = auth_context.tenant_id;
+    let retention = AnalyticsRetentionService::new(state.db.clone(), state.config.analytics.clone());
+
+    match retention.delete_tenant_data(&tenant_id).await {
+        Ok(()) => {
+            info!(tenant_id = %tenant_id, user_id = %auth_context.user_id, "Tenant analytics deleted");
+            let response = ApiResponse::success(
+                DeleteAnalyticsResponse {
+                    tenant_id,
+                    deleted_at: Utc::now(),
+                },
+                request_id,
+            );
+            Ok(Json(response))
+        }
+        Err(e) => {
+            error!(tenant_id = %tenant_id, error = %e, "Failed to delete tenant analytics");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Rebuild the tenant's content analytics for a date range from Postgres (admin only),
+/// after ClickHouse lost data; rows it still has are left alone
+async fn replay_content_analytics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(range): Json<ReplayRange>,
+) -> Result<Response, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    auth_context.require_scope("analytics", "admin")?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let tenant_id = auth_context.tenant_id;
+    match replay_tenant(state.db.postgres(), state.db.clickhouse(), &tenant_id, range).await {
+        Ok(summary) => Ok(Json(ApiResponse::success(summary, request_id)).into_response()),
+        Err(e @ ReplayError::InvalidRange(_)) => {
+            let response = ApiResponse::<()>::error(e.to_string(), request_id);
+            Ok((StatusCode::BAD_REQUEST, Json(response)).into_response())
+        }
+        Err(e) => {
+            error!(tenant_id = %tenant_id, "Failed to replay content analytics: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
 }
 
+// Request/Response schemas
+
 #[derive(Debug, Serialize)]
 struct RecordEventResponse {
     event_id: Uuid,
+    event_type: String,
     recorded_at: chrono::DateTime<Utc>,
 }
 
@@ -275,6 +453,37 @@ struct UserActivityResponse {
     activity: Vec<crate::database::clickhouse::UserActivity>,
 }
 
+/// Longest retention a tenant can ask for, a hundred years
+const MAX_RETENTION_DAYS: u32 = 36_500;
+
+#[derive(Debug, Deserialize)]
+struct RetentionRequest {
+    retention_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct RetentionResponse {
+    tenant_id: TenantId,
+    retention_days: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct DeleteAnalyticsResponse {
+    tenant_id: TenantId,
+    deleted_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchQueryRequest {
+    queries: Vec<NamedAnalyticsQuery>,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchQueryResponse {
+    tenant_id: TenantId,
+    results: HashMap<String, BatchQueryResult>,
+}
+
 /// Get recent activity for the tenant
 async fn get_recent_activity(
     State(state): State<AppState>,
@@ -313,3 +522,55 @@ async fn get_recent_activity(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test_harness::TestApp;
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_events_checked_against_the_tenants_schema() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = &app.tenant_a.admin;
+        let schema = json!({ "analytics_events": { "strict": true, "events": { "book_preview_opened": {
+            "properties": { "book_id": { "type": "string", "required": true } } } } } });
+        let response = app.send(app.request(Method::PUT, "/api/tenants/current/settings", admin, Some(schema))).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        let unknown = app.post("/api/analytics/events", admin, json!({ "name": "newsletter_opened" })).await;
+        assert_eq!(unknown.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", unknown.body);
+        let missing = app.post("/api/analytics/events", admin, json!({ "name": "book_preview_opened" })).await;
+        assert_eq!(missing.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", missing.body);
+        let event = json!({ "name": "book_preview_opened", "properties": { "book_id": "emma" } });
+        assert_eq!(app.post("/api/analytics/events", admin, event).await.status, StatusCode::ACCEPTED);
+
+        // The schema is the tenant's own
+        let other = app.post("/api/analytics/events", &app.tenant_b.admin, json!({ "name": "newsletter_opened" })).await;
+        assert_eq!(other.status, StatusCode::ACCEPTED, "{}", other.body);
+    }
+
+    #[tokio::test]
+    async fn test_retention_range_checked_and_stored() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = &app.tenant_a.admin;
+        let put = |days: serde_json::Value| app.request(Method::PUT, "/api/analytics/retention", admin, Some(json!({ "retention_days": days })));
+
+        for days in [json!(0), json!(u32::MAX)] {
+            assert_eq!(app.send(put(days)).await.status, StatusCode::BAD_REQUEST);
+        }
+        let response = app.send(put(json!(90))).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        let stored: Option<i32> = app
+            .admin_pool
+            .get()
+            .await
+            .unwrap()
+            .query_one("SELECT analytics_retention_days FROM tenants WHERE id = $1", &[app.tenant_a.id.as_uuid()])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(stored, Some(90));
+    }
+}
@@ -0,0 +1,433 @@
+use crate::{
+    auth::jwt_helpers::extract_auth_context_with_role,
+    config::ObjectStoreKind,
+    services::asset::{
+        AssetListQuery, AssetService, AssetServiceError, AssetType, BatchDeleteRequest, BatchDeleteStatus,
+        CreateAssetRequest, UpdateAssetRequest, UploadAssetRequest, MAX_BATCH_DELETE,
+    },
+    services::object_store::{verify_local_signature, ObjectStoreError},
+    types::{ApiResponse, PaginatedResponse, TenantId},
+    AppState,
+};
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Largest file `POST /api/assets/upload` accepts
+const MAX_UPLOAD_BYTES: usize = 100 * 1024 * 1024;
+
+/// Validity of file links when the caller does not ask for one
+const DEFAULT_FILE_URL_SECS: u64 = 60 * 60;
+const MAX_FILE_URL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Create asset management routes
+pub fn create_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_assets).post(create_asset))
+        .route("/upload", post(upload_asset).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)))
+        .route("/files/*key", get(serve_local_file))
+        .route("/:asset_id/url", get(asset_file_url))
+        .route("/batch-delete", post(batch_delete_assets))
+        .route("/:asset_id", put(update_asset).delete(delete_asset))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListAssetsQuery {
+    page: Option<u32>,
+    limit: Option<u32>,
+    #[serde(rename = "type")]
+    asset_type: Option<AssetType>,
+    q: Option<String>,
+    site_id: Option<Uuid>,
+}
+
+/// Media library: the tenant's assets, filterable by type and searchable by filename
+async fn list_assets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ListAssetsQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "assets", "read").await?;
+    if let Some(site_id) = params.site_id {
+        if !auth_context.allows_site(&site_id) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
+    let limit: u32 = params.limit.unwrap_or(20).clamp(1, 100);
+    let page = params.page.unwrap_or(1).max(1);
+    let query = AssetListQuery {
+        site_id: params.site_id,
+        asset_type: params.asset_type,
+        search: params.q,
+        limit: Some(limit as i64),
+        offset: Some(((page - 1) * limit) as i64),
+        ..AssetListQuery::default()
+    };
+
+    let service = AssetService::new(state.db.postgres().clone(), state.config.storage.clone(), state.object_store.clone());
+    let assets = service.list_assets(&auth_context.tenant_id, query).await.map_err(|e| {
+        error!(error = %e, "Failed to list assets");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let total = assets.total as u64;
+    let paginated = PaginatedResponse {
+        items: assets.items.into_iter().map(|asset| service.library_asset(asset)).collect(),
+        total,
+        page,
+        limit,
+        total_pages: total.div_ceil(limit as u64) as u32,
+    };
+
+    Ok(Json(ApiResponse::success(paginated, request_id)))
+}
+
+/// Record an uploaded asset; refused with 413 once the tenant's storage quota is used up,
+/// and with 409 (carrying the existing asset) when an identical file is already stored
+async fn create_asset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateAssetRequest>,
+) -> Result<Response, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "assets", "write").await?;
+    if let Some(site_id) = request.site_id {
+        if !auth_context.allows_site(&site_id) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
+    let service = AssetService::new(state.db.postgres().clone(), state.config.storage.clone(), state.object_store.clone());
+    match service.create_asset(&auth_context.tenant_id, request).await {
+        Ok(asset) => {
+            info!(asset_id = %asset.id, file_size = asset.file_size, "Asset created");
+            Ok((StatusCode::CREATED, Json(ApiResponse::success(asset, request_id))).into_response())
+        }
+        Err(e) => create_error_response(&service, &auth_context.tenant_id, e, request_id),
+    }
+}
+
+/// Store an uploaded file (the raw request body, typed by `Content-Type`) and record it,
+/// refused like `create_asset` when over quota or a duplicate
+async fn upload_asset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(request): Query<UploadAssetRequest>,
+    body: Bytes,
+) -> Result<Response, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "assets", "write").await?;
+    if let Some(site_id) = request.site_id {
+        if !auth_context.allows_site(&site_id) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+    let mime_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    let service = AssetService::new(state.db.postgres().clone(), state.config.storage.clone(), state.object_store.clone());
+    match service.upload_asset(&auth_context.tenant_id, request, &mime_type, body.to_vec()).await {
+        Ok(asset) => {
+            info!(asset_id = %asset.id, file_size = asset.file_size, "Asset uploaded");
+            Ok((StatusCode::CREATED, Json(ApiResponse::success(asset, request_id))).into_response())
+        }
+        Err(e) => create_error_response(&service, &auth_context.tenant_id, e, request_id),
+    }
+}
+
+fn create_error_response(
+    service: &AssetService,
+    tenant_id: &TenantId,
+    e: AssetServiceError,
+    request_id: Uuid,
+) -> Result<Response, StatusCode> {
+    match e {
+        AssetServiceError::QuotaExceeded(exceeded) => {
+            warn!(
+                tenant_id = %tenant_id,
+                requested_bytes = exceeded.requested_bytes,
+                remaining_bytes = exceeded.usage.remaining_bytes,
+                "Asset upload over storage quota"
+            );
+            let response = ApiResponse {
+                success: false,
+                data: Some(exceeded),
+                error: Some("Storage quota exceeded".to_string()),
+                request_id,
+            };
+            Ok((StatusCode::PAYLOAD_TOO_LARGE, Json(response)).into_response())
+        }
+        AssetServiceError::Duplicate(existing) => {
+            info!(asset_id = %existing.id, "Upload matches an existing asset");
+            let response = ApiResponse {
+                success: false,
+                data: Some(service.library_asset(*existing)),
+                error: Some("Identical file already uploaded".to_string()),
+                request_id,
+            };
+            Ok((StatusCode::CONFLICT, Json(response)).into_response())
+        }
+        AssetServiceError::SiteNotFound => Err(StatusCode::NOT_FOUND),
+        AssetServiceError::InvalidFileSize(_) | AssetServiceError::InvalidContentHash => {
+            Err(StatusCode::BAD_REQUEST)
+        }
+        e => {
+            error!(error = %e, "Failed to create asset");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FileUrlQuery {
+    /// Seconds the link stays valid
+    expires_in: Option<u64>,
+}
+
+/// A time-limited link to the asset's file, for sharing or embedding without credentials
+async fn asset_file_url(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(asset_id): Path<Uuid>,
+    Query(query): Query<FileUrlQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "assets", "read").await?;
+
+    let expires_in = query.expires_in.unwrap_or(DEFAULT_FILE_URL_SECS).clamp(1, MAX_FILE_URL_SECS);
+    let service = AssetService::new(state.db.postgres().clone(), state.config.storage.clone(), state.object_store.clone());
+    match service.asset_file_url(&auth_context.tenant_id, asset_id, Duration::from_secs(expires_in)).await {
+        Ok(Some(url)) => Ok(Json(ApiResponse::success(
+            serde_json::json!({ "url": url, "expires_in": expires_in }),
+            request_id,
+        ))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!(asset_id = %asset_id, error = %e, "Failed to sign asset file URL");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SignedFileQuery {
+    expires: i64,
+    signature: String,
+}
+
+/// Serve a file from the `local` backend to holders of a signed link
+async fn serve_local_file(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(query): Query<SignedFileQuery>,
+) -> Result<Response, StatusCode> {
+    let backend = &state.config.storage.backend;
+    if backend.kind != ObjectStoreKind::Local {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if !verify_local_signature(&backend.signing_secret, &key, query.expires, &query.signature, Utc::now()) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    match state.object_store.get(&key).await {
+        Ok(bytes) => Ok((
+            [
+                (header::CONTENT_TYPE, content_type_for(&key)),
+                // Uploaded HTML or SVG must not run script on the API's origin
+                (header::CONTENT_SECURITY_POLICY, "default-src 'none'; img-src 'self'; style-src 'unsafe-inline'; sandbox"),
+                (header::CACHE_CONTROL, "private, max-age=300"),
+            ],
+            bytes,
+        )
+            .into_response()),
+        Err(ObjectStoreError::NotFound(_) | ObjectStoreError::InvalidKey(_)) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!(error = %e, "Failed to read local asset file");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Content type from the file extension, for files served without their asset record
+fn content_type_for(key: &str) -> &'static str {
+    let extension = key.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        Some("svg") => "image/svg+xml",
+        Some("pdf") => "application/pdf",
+        Some("mp4") => "video/mp4",
+        Some("mp3") => "audio/mpeg",
+        Some("txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Update an asset's alt text, CDN URL or optimization flag
+async fn update_asset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(asset_id): Path<Uuid>,
+    Json(request): Json<UpdateAssetRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "assets", "update").await?;
+
+    let service = AssetService::new(state.db.postgres().clone(), state.config.storage.clone(), state.object_store.clone());
+    match service.update_asset(&auth_context.tenant_id, asset_id, request).await {
+        Ok(Some(asset)) => {
+            info!(asset_id = %asset_id, "Asset updated");
+            Ok(Json(ApiResponse::success(asset, request_id)))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!(asset_id = %asset_id, error = %e, "Failed to update asset");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Delete an asset, freeing its share of the storage quota
+async fn delete_asset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(asset_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "assets", "delete").await?;
+
+    let service = AssetService::new(state.db.postgres().clone(), state.config.storage.clone(), state.object_store.clone());
+    match service.delete_asset(&auth_context.tenant_id, asset_id).await {
+        Ok(true) => {
+            info!(asset_id = %asset_id, "Asset deleted");
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!(asset_id = %asset_id, error = %e, "Failed to delete asset");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Delete several assets at once. Assets still used by a page or content item are
+/// reported as `blocked` with their references unless `force` is set.
+async fn batch_delete_assets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<BatchDeleteRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "assets", "delete").await?;
+    if request.asset_ids.is_empty() || request.asset_ids.len() > MAX_BATCH_DELETE {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let service = AssetService::new(state.db.postgres().clone(), state.config.storage.clone(), state.object_store.clone());
+    let results = service
+        .batch_delete_assets(&auth_context.tenant_id, &request.asset_ids, request.force)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to batch delete assets");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let deleted = results.iter().filter(|result| result.status == BatchDeleteStatus::Deleted).count();
+    let blocked = results.iter().filter(|result| result.status == BatchDeleteStatus::Blocked).count();
+    info!(deleted, blocked, force = request.force, "Assets batch deleted");
+    Ok(Json(ApiResponse::success(results, request_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_harness::{TestApp, TestUser}, types::UserRole};
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_asset_alt_text_updated_by_editors_only() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let viewer = app.add_user(&app.tenant_a.id, UserRole::Viewer).await;
+        let created = app
+            .post(
+                "/api/assets",
+                &editor,
+                json!({
+                    "filename": "cover.png",
+                    "original_filename": "cover.png",
+                    "mime_type": "image/png",
+                    "file_size": 2048,
+                    "storage_path": "tenant-a/cover.png"
+                }),
+            )
+            .await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let uri = format!("/api/assets/{}", created.body["data"]["id"].as_str().unwrap());
+        let update = json!({ "alt_text": "Cover of Jane Eyre" });
+        let put = |uri: &str, user: &TestUser| app.send(app.request(Method::PUT, uri, user, Some(update.clone())));
+
+        let updated = put(&uri, &editor).await;
+        assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+        assert_eq!(updated.body["data"]["alt_text"], "Cover of Jane Eyre");
+        assert_eq!(put(&uri, &viewer).await.status, StatusCode::FORBIDDEN);
+        assert_eq!(put(&uri, &app.tenant_b.admin).await.status, StatusCode::NOT_FOUND);
+        let missing = format!("/api/assets/{}", Uuid::new_v4());
+        assert_eq!(put(&missing, &editor).await.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_library_pages_and_filters_from_the_query() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        for (filename, mime_type) in [("one.png", "image/png"), ("two.png", "image/png"), ("notes.pdf", "application/pdf")] {
+            let created = app
+                .post(
+                    "/api/assets",
+                    &editor,
+                    json!({
+                        "filename": filename,
+                        "original_filename": filename,
+                        "mime_type": mime_type,
+                        "file_size": 1024,
+                        "storage_path": format!("tenant-a/{}", filename)
+                    }),
+                )
+                .await;
+            assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        }
+
+        let images = app.get("/api/assets?type=image&limit=1&page=2", &editor).await;
+        assert_eq!(images.status, StatusCode::OK, "{}", images.body);
+        assert_eq!(images.body["data"]["total"], 2);
+        assert_eq!(images.body["data"]["total_pages"], 2);
+        assert_eq!(images.body["data"]["items"].as_array().unwrap().len(), 1);
+        let documents = app.get("/api/assets?type=document", &editor).await;
+        assert_eq!(documents.body["data"]["items"][0]["filename"], "notes.pdf", "{}", documents.body);
+    }
+}
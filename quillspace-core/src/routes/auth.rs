@@ -1,21 +1,40 @@
 use crate::{
-    types::{ApiResponse, User, UserRole},
-    auth::{JwtManager, Claims},
+    types::{ApiResponse, TenantId, User, UserRole},
+    database::postgres::tenant_client,
+    middleware::client_ip::client_ip,
+    auth::{
+        api_keys::is_valid_scope, jwt_helpers::extract_auth_context_with_role,
+        login_lockout::{LoginLockout, PostgresLoginAttemptStore}, totp, validate_password,
+        JwtManager, Claims, PasswordPolicyViolation, TokenOptions, TokenSubject,
+    },
+    services::api_key::{ApiKeyService, CreateApiKeyRequest},
+    services::session::{Session, SessionClient, SessionService},
+    services::user_import::UserImportService,
     AppState,
 };
 use axum::{
-    extract::{Request, State},
-    http::StatusCode,
-    response::IntoResponse,
-    routing::{get, post},
+    extract::{ConnectInfo, Path, Query, Request, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 // Using Claims from auth::jwt module
 
+/// Issuer shown in authenticator apps
+const TOTP_ISSUER: &str = "QuillSpace";
+
+/// Backup codes are random and single-use, so a lower bcrypt cost keeps login snappy
+const BACKUP_CODE_HASH_COST: u32 = 10;
+
+/// Default and maximum lifetime of scoped (widget) tokens
+const SCOPED_TOKEN_DEFAULT_TTL_SECONDS: i64 = 900;
+const SCOPED_TOKEN_MAX_TTL_SECONDS: i64 = 3600;
 
 /// Create authentication routes
 pub fn create_routes() -> Router<AppState> {
@@ -23,17 +42,56 @@ pub fn create_routes() -> Router<AppState> {
         .route("/login", post(login))
         .route("/refresh", post(refresh_token))
         .route("/logout", post(logout))
+        .route("/invites/accept", post(accept_invite))
         .route("/me", get(get_current_user))
+        .route("/sessions", get(list_sessions))
+        .route("/sessions/revoke-others", post(revoke_other_sessions))
+        .route("/sessions/:session_id", delete(revoke_session))
+        .route("/2fa/enroll", post(enroll_two_factor))
+        .route("/2fa/verify", post(verify_two_factor))
+        .route("/2fa/disable", post(disable_two_factor))
+        .route("/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/api-keys/:key_id", delete(revoke_api_key))
+        .route("/scoped-tokens", post(create_scoped_token))
 }
 
 /// User login
+///
+/// Users with 2FA enabled must also send `totp_code` or `backup_code`;
+/// when neither is present the request fails with 428 so clients can prompt for one.
+/// Repeated failures lock the account and IP out; a locked login gets the same 401
+/// as a wrong password.
 async fn login(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(login_request): Json<LoginRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
     let request_id = Uuid::new_v4(); // Generate request ID
     info!("Login attempt for email: {}", login_request.email);
 
+    let lockout = LoginLockout::new(
+        PostgresLoginAttemptStore::new(state.db.postgres().clone()),
+        state.config.login_lockout.clone(),
+    );
+    let client_ip = client_ip(&state.config.proxy, &headers, Some(peer.ip())).map(|ip| ip.to_string());
+    let now = chrono::Utc::now();
+    // Lockout storage problems must not take login down with them
+    let locked = lockout.is_locked(&login_request.email, client_ip.as_deref(), now).await.unwrap_or_else(|e| {
+        error!("Failed to check login lockout: {}", e);
+        false
+    });
+    if locked {
+        warn!(ip = ?client_ip, "Login refused during lockout: {}", login_request.email);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let (lockout_ref, email, ip) = (&lockout, login_request.email.as_str(), client_ip.as_deref());
+    let record_failure = || async move {
+        if let Err(e) = lockout_ref.record_failure(email, ip, now).await {
+            error!("Failed to record failed login: {}", e);
+        }
+    };
+
     // Authenticate user with email and password
     let client = get_db_client(&state).await?;
 
@@ -56,9 +114,46 @@ async fn login(
             if !bcrypt::verify(&login_request.password, &password_hash)
                 .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
                 error!("Login attempt with invalid password: {}", login_request.email);
+                record_failure().await;
                 return Err(StatusCode::UNAUTHORIZED);
             }
 
+            let token_version: i32 = row.try_get("token_version")
+                .map_err(|e| {
+                    error!("Failed to get token_version from row: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            let totp_enabled: bool = row.try_get("totp_enabled")
+                .map_err(|e| {
+                    error!("Failed to get totp_enabled from row: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+
+            // The user's tenant is known now, so the rest runs under its RLS context
+            let client = get_tenant_db_client(&state, &TenantId::from_uuid(user.tenant_id)).await?;
+
+            if totp_enabled {
+                let verified = verify_second_factor(
+                    &state,
+                    &client,
+                    user.id,
+                    login_request.totp_code.as_deref(),
+                    login_request.backup_code.as_deref(),
+                ).await;
+                if let Err(status) = verified {
+                    warn!(user_id = %user.id, status = %status, "Login rejected at second factor");
+                    // A missing code is a prompt, not a guess
+                    if status == StatusCode::UNAUTHORIZED {
+                        record_failure().await;
+                    }
+                    return Err(status);
+                }
+            }
+
+            if let Err(e) = lockout.record_success(&login_request.email).await {
+                error!("Failed to reset login attempts: {}", e);
+            }
+
             // Fetch tenant information
             let tenant_query = "SELECT * FROM tenants WHERE id = $1";
             let tenant_row = match client.query_one(tenant_query, &[&user.tenant_id]).await {
@@ -71,9 +166,27 @@ async fn login(
 
             let tenant = TenantInfo::from_row(&tenant_row).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+            let session_client = SessionClient {
+                user_agent: headers.get("user-agent").and_then(|h| h.to_str().ok()).map(str::to_string),
+                ip_address: client_ip.clone(),
+            };
+            let session = SessionService::new(state.db.postgres().clone())
+                .create_session(
+                    user.tenant_id,
+                    user.id,
+                    token_version,
+                    &session_client,
+                    chrono::Duration::seconds(state.config.auth.refresh_token_expiration),
+                )
+                .await
+                .map_err(|e| {
+                    error!("Failed to create session: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+
             info!("Creating JWT token for user: {}", user.id);
             
-            let token = generate_jwt_token(&state.jwt_manager, &user)?;
+            let token = generate_jwt_token(&state.jwt_manager, &user, token_version, Some(session.session.id))?;
             
             info!(
                 user_id = %user.id,
@@ -83,6 +196,8 @@ async fn login(
 
             let response_data = LoginResponse {
                 token,
+                refresh_token: session.refresh_token,
+                session_id: session.session.id,
                 user: UserInfo::from_user(&user),
                 tenant,
             };
@@ -91,8 +206,9 @@ async fn login(
             Ok(Json(response))
         }
         Ok(None) => {
-            // Don't reveal whether user exists or not
+            // Don't reveal whether user exists or not; unknown accounts lock out like real ones
             error!("Login attempt with invalid credentials: {}", login_request.email);
+            record_failure().await;
             Err(StatusCode::UNAUTHORIZED)
         }
         Err(e) => {
@@ -102,38 +218,43 @@ async fn login(
     }
 }
 
-/// Refresh JWT token
+/// Exchange a session's refresh token for a new access token.
+/// Revoked or expired sessions, and sessions from before a token version bump, are refused.
 async fn refresh_token(
     State(state): State<AppState>,
     Json(refresh_request): Json<RefreshTokenRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
     let request_id = Uuid::new_v4(); // Generate request ID
 
-    // Validate refresh token and issue new access token
-    match state.jwt_manager.verify_token(&refresh_request.refresh_token) {
-        Ok(old_claims) => {
-            
-            // Verify user still exists and is active
-            let user_id = Uuid::parse_str(&old_claims.sub)
-                .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let session = SessionService::new(state.db.postgres().clone())
+        .use_refresh_token(&refresh_request.refresh_token)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up refresh session: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
 
-            let client = get_db_client(&state).await?;
-            let user = fetch_user_by_id(&client, user_id).await?;
+    // Verify user still exists and is active
+    let client = get_tenant_db_client(&state, &TenantId::from_uuid(session.tenant_id)).await?;
+    let user = fetch_user_by_id(&client, session.user_id).await?;
+    let token_version = fetch_token_version(&client, session.user_id).await?;
+    if session.token_version != token_version {
+        warn!(session_id = %session.id, "Rejected refresh for a session older than the token version");
+        return Err(StatusCode::UNAUTHORIZED);
+    }
 
-            // Generate new JWT token
-            let token = generate_jwt_token(&state.jwt_manager, &user)?;
-            
-            let response_data = RefreshTokenResponse {
-                access_token: token,
-                token_type: "Bearer".to_string(),
-                expires_in: state.config.auth.jwt_expiration,
-            };
+    // Generate new JWT token
+    let token = generate_jwt_token(&state.jwt_manager, &user, token_version, Some(session.id))?;
 
-            let response = ApiResponse::success(response_data, request_id);
-            Ok(Json(response))
-        }
-        Err(_) => Err(StatusCode::UNAUTHORIZED),
-    }
+    let response_data = RefreshTokenResponse {
+        access_token: token,
+        token_type: "Bearer".to_string(),
+        expires_in: state.config.auth.jwt_expiration,
+    };
+
+    let response = ApiResponse::success(response_data, request_id);
+    Ok(Json(response))
 }
 
 /// User logout
@@ -155,24 +276,24 @@ async fn logout(
             }
         });
     
-    let user_id = if let Some(auth_header) = auth_header {
-        if let Some(token) = auth_header.strip_prefix("Bearer ") {
-            // Decode token to get user ID for logging
-            match state.jwt_manager.verify_token(token) {
-                Ok(claims) => {
-                    match Uuid::parse_str(&claims.sub) {
-                        Ok(id) => Some(id),
-                        Err(_) => None,
-                    }
-                }
-                Err(_) => None,
+    let claims = auth_header
+        .and_then(|auth_header| auth_header.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_manager.verify_token(token).ok());
+    let user_id = claims.as_ref().and_then(|claims| Uuid::parse_str(&claims.sub).ok());
+
+    // End the session so its refresh token stops working
+    if let Some(claims) = &claims {
+        let session = (
+            Uuid::parse_str(&claims.tenant_id),
+            claims.session_id.as_deref().map(Uuid::parse_str),
+        );
+        if let (Ok(tenant_id), Some(Ok(session_id)), Some(user_id)) = (session.0, session.1, user_id) {
+            let service = SessionService::new(state.db.postgres().clone());
+            if let Err(e) = service.revoke_session(&TenantId::from_uuid(tenant_id), user_id, session_id).await {
+                error!("Failed to end session on logout: {}", e);
             }
-        } else {
-            None
         }
-    } else {
-        None
-    };
+    }
 
     // Log the logout event
     if let Some(user_id) = user_id {
@@ -228,24 +349,580 @@ async fn get_current_user(
     };
     
     // Get database connection and fetch user
-    let client = get_db_client(&state).await?;
+    let tenant_id: Uuid = claims.tenant_id.parse().map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let client = get_tenant_db_client(&state, &TenantId::from_uuid(tenant_id)).await?;
     let user_id: Uuid = claims.sub.parse().map_err(|_| StatusCode::UNAUTHORIZED)?;
     let user = fetch_user_by_id(&client, user_id).await?;
+    ensure_token_current(&client, &claims).await?;
     
     let response = ApiResponse::success(user, request_id);
     Ok(Json(response))
 }
 
+/// Accept an invite by choosing a password, which must satisfy the password policy.
+/// Each invite can be used once, before it expires; the user then logs in as usual.
+async fn accept_invite(
+    State(state): State<AppState>,
+    Json(accept_request): Json<AcceptInviteRequest>,
+) -> Result<Response, StatusCode> {
+    let request_id = Uuid::new_v4();
+    if let Err(rejection) = enforce_password_policy(&state, &accept_request.password, request_id).await {
+        return Ok(rejection.into_response());
+    }
+
+    let password = accept_request.password;
+    let password_hash = tokio::task::spawn_blocking(move || bcrypt::hash(password, bcrypt::DEFAULT_COST))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| {
+            error!("Failed to hash password: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    match UserImportService::new(state.db.postgres().clone())
+        .accept_invite(&accept_request.token, &password_hash)
+        .await
+    {
+        Ok(Some(user_id)) => {
+            info!(user_id = %user_id, "Invite accepted");
+            let response = ApiResponse::success(AcceptInviteResponse { user_id }, request_id);
+            Ok(Json(response).into_response())
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to accept invite: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Start TOTP enrollment (admin accounts only)
+///
+/// Stores a freshly generated, encrypted secret and returns the provisioning URI.
+/// 2FA only becomes active once a code is confirmed via `/2fa/verify`.
+async fn enroll_two_factor(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let client = tenant_db_client_for(&state, &headers).await?;
+    let user = authenticate_current_user(&state, &client, &headers).await?;
+
+    if user.role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let secret = totp::generate_secret();
+    let encrypted = totp::encrypt_secret(totp_key(&state), &secret).map_err(|e| {
+        error!("Failed to encrypt TOTP secret: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let updated = client.execute(
+        "UPDATE users SET totp_secret_encrypted = $2, totp_last_used_step = NULL WHERE id = $1 AND totp_enabled = false",
+        &[&user.id, &encrypted],
+    ).await.map_err(|e| {
+        error!("Failed to store TOTP secret: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if updated == 0 {
+        // Already enabled: must be disabled before re-enrolling
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let provisioning_uri = totp::provisioning_uri(&secret, TOTP_ISSUER, &user.email);
+    info!(user_id = %user.id, "Started 2FA enrollment");
+
+    let response_data = TwoFactorEnrollResponse {
+        qr_payload: provisioning_uri.clone(),
+        provisioning_uri,
+    };
+
+    Ok(Json(ApiResponse::success(response_data, request_id)))
+}
+
+/// Confirm TOTP enrollment with a code from the authenticator and issue backup codes
+async fn verify_two_factor(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(verify_request): Json<TwoFactorVerifyRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let mut client = tenant_db_client_for(&state, &headers).await?;
+    let user = authenticate_current_user(&state, &client, &headers).await?;
+
+    let row = client.query_one(
+        "SELECT totp_secret_encrypted, totp_enabled, totp_last_used_step FROM users WHERE id = $1",
+        &[&user.id],
+    ).await.map_err(|e| {
+        error!("Failed to load 2FA state: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let encrypted: Option<String> = row.get("totp_secret_encrypted");
+    let enabled: bool = row.get("totp_enabled");
+    let last_used_step: Option<i64> = row.get("totp_last_used_step");
+
+    if enabled {
+        return Err(StatusCode::CONFLICT);
+    }
+    let encrypted = encrypted.ok_or(StatusCode::BAD_REQUEST)?;
+    let secret = totp::decrypt_secret(totp_key(&state), &encrypted).map_err(|e| {
+        error!("Failed to decrypt TOTP secret: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let step = totp::verify_code(&secret, &verify_request.code, chrono::Utc::now(), last_used_step)
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let backup_codes = totp::generate_backup_codes();
+    // bcrypt is deliberately slow; keep it off the async workers
+    let codes = backup_codes.clone();
+    let backup_hashes = tokio::task::spawn_blocking(move || {
+        codes
+            .iter()
+            .map(|code| bcrypt::hash(totp::normalize_backup_code(code), BACKUP_CODE_HASH_COST))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .await
+    .map_err(|e| {
+        error!("Failed to hash backup codes: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let transaction = client.transaction().await.map_err(|e| {
+        error!("Failed to start transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let db_result: Result<(), tokio_postgres::Error> = async {
+        transaction.execute(
+            "UPDATE users SET totp_enabled = true, totp_last_used_step = $2 WHERE id = $1",
+            &[&user.id, &step],
+        ).await?;
+        transaction.execute("DELETE FROM user_backup_codes WHERE user_id = $1", &[&user.id]).await?;
+        for hash in &backup_hashes {
+            transaction.execute(
+                "INSERT INTO user_backup_codes (user_id, code_hash) VALUES ($1, $2)",
+                &[&user.id, hash],
+            ).await?;
+        }
+        Ok(())
+    }.await;
+
+    if let Err(e) = db_result {
+        error!("Failed to activate 2FA: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    transaction.commit().await.map_err(|e| {
+        error!("Failed to commit 2FA activation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!(user_id = %user.id, "2FA enabled");
+
+    let response_data = TwoFactorVerifyResponse {
+        enabled: true,
+        backup_codes,
+    };
+
+    Ok(Json(ApiResponse::success(response_data, request_id)))
+}
+
+/// Disable 2FA. Requires a current TOTP or backup code and revokes all issued tokens.
+async fn disable_two_factor(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(disable_request): Json<TwoFactorDisableRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let mut client = tenant_db_client_for(&state, &headers).await?;
+    let user = authenticate_current_user(&state, &client, &headers).await?;
+
+    let enabled: bool = client.query_one("SELECT totp_enabled FROM users WHERE id = $1", &[&user.id])
+        .await
+        .map_err(|e| {
+            error!("Failed to load 2FA state: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .get("totp_enabled");
+
+    if !enabled {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    verify_second_factor(
+        &state,
+        &client,
+        user.id,
+        disable_request.totp_code.as_deref(),
+        disable_request.backup_code.as_deref(),
+    ).await?;
+
+    let transaction = client.transaction().await.map_err(|e| {
+        error!("Failed to start transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Bumping the token version invalidates every session issued while 2FA was on
+    let db_result: Result<(), tokio_postgres::Error> = async {
+        transaction.execute(
+            "UPDATE users SET totp_enabled = false, totp_secret_encrypted = NULL, totp_last_used_step = NULL, \
+             token_version = token_version + 1 WHERE id = $1",
+            &[&user.id],
+        ).await?;
+        transaction.execute("DELETE FROM user_backup_codes WHERE user_id = $1", &[&user.id]).await?;
+        Ok(())
+    }.await;
+
+    if let Err(e) = db_result {
+        error!("Failed to disable 2FA: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    transaction.commit().await.map_err(|e| {
+        error!("Failed to commit 2FA disable: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!(user_id = %user.id, "2FA disabled, existing tokens revoked");
+
+    let response_data = LogoutResponse {
+        message: "Two-factor authentication disabled; please sign in again".to_string(),
+    };
+
+    Ok(Json(ApiResponse::success(response_data, request_id)))
+}
+
+/// List the tenant's API keys (admin only)
+async fn list_api_keys(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    require_api_key_admin(&auth_context)?;
+
+    let service = ApiKeyService::new(state.db.postgres().clone());
+    match service.list_api_keys(&auth_context.tenant_id).await {
+        Ok(keys) => Ok(Json(ApiResponse::success(keys, request_id))),
+        Err(e) => {
+            error!("Failed to list API keys: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Create an API key (admin only). The full key is returned once in the response.
+async fn create_api_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(create_request): Json<CreateApiKeyRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    require_api_key_admin(&auth_context)?;
+
+    if create_request.name.trim().is_empty()
+        || !create_request.scopes.iter().all(|scope| is_valid_scope(scope))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if create_request.expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let role = create_request.role.clone().unwrap_or(UserRole::Viewer);
+    let service = ApiKeyService::new(state.db.postgres().clone());
+
+    match service.create_api_key(&auth_context.tenant_id, auth_context.user_id, &role, create_request).await {
+        Ok(created) => {
+            info!(
+                key_prefix = %created.api_key.key_prefix,
+                tenant_id = %auth_context.tenant_id,
+                "API key created"
+            );
+            Ok((StatusCode::CREATED, Json(ApiResponse::success(created, request_id))))
+        }
+        Err(e) => {
+            error!("Failed to create API key: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Revoke an API key (admin only)
+async fn revoke_api_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(key_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    require_api_key_admin(&auth_context)?;
+
+    let service = ApiKeyService::new(state.db.postgres().clone());
+    match service.revoke_api_key(&auth_context.tenant_id, key_id).await {
+        Ok(true) => {
+            info!(key_id = %key_id, tenant_id = %auth_context.tenant_id, "API key revoked");
+            let response_data = LogoutResponse {
+                message: "API key revoked".to_string(),
+            };
+            Ok(Json(ApiResponse::success(response_data, request_id)))
+        }
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to revoke API key: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// List active sessions of the caller, or of another user in the tenant for admins
+async fn list_sessions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SessionsQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    let user_id = session_owner(&auth_context, query.user_id, "read")?;
+
+    let service = SessionService::new(state.db.postgres().clone());
+    match service.list_sessions(&auth_context.tenant_id, user_id).await {
+        Ok(sessions) => {
+            let response_data: Vec<SessionInfo> = sessions
+                .into_iter()
+                .map(|session| SessionInfo {
+                    current: auth_context.session_id == Some(session.id),
+                    session,
+                })
+                .collect();
+            Ok(Json(ApiResponse::success(response_data, request_id)))
+        }
+        Err(e) => {
+            error!("Failed to list sessions: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Revoke a session; its refresh token stops working immediately
+async fn revoke_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<SessionsQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    let user_id = session_owner(&auth_context, query.user_id, "write")?;
+
+    let service = SessionService::new(state.db.postgres().clone());
+    match service.revoke_session(&auth_context.tenant_id, user_id, session_id).await {
+        Ok(true) => {
+            info!(session_id = %session_id, user_id = %user_id, revoked_by = %auth_context.user_id, "Session revoked");
+            let response_data = LogoutResponse {
+                message: "Session revoked".to_string(),
+            };
+            Ok(Json(ApiResponse::success(response_data, request_id)))
+        }
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to revoke session: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Revoke every session of the caller except the one making the request
+async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    // API keys and scoped tokens have no session of their own to keep
+    let current = auth_context.session_id.ok_or(StatusCode::FORBIDDEN)?;
+
+    let service = SessionService::new(state.db.postgres().clone());
+    match service.revoke_other_sessions(&auth_context.tenant_id, auth_context.user_id, Some(current)).await {
+        Ok(revoked) => {
+            info!(user_id = %auth_context.user_id, revoked, "Other sessions revoked");
+            Ok(Json(ApiResponse::success(RevokedSessionsResponse { revoked }, request_id)))
+        }
+        Err(e) => {
+            error!("Failed to revoke sessions: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Whose sessions a request may manage: the caller's own, or anyone's in the tenant for admins
+fn session_owner(
+    auth_context: &crate::auth::jwt_helpers::AuthContext,
+    requested: Option<Uuid>,
+    action: &str,
+) -> Result<Uuid, StatusCode> {
+    match requested {
+        Some(user_id) if user_id != auth_context.user_id => {
+            require_api_key_admin(auth_context)?;
+            Ok(user_id)
+        }
+        _ => {
+            auth_context.require_scope("security", action)?;
+            Ok(auth_context.user_id)
+        }
+    }
+}
+
+/// Mint a short-lived, narrowly-scoped token for public/embeddable widgets (admin only).
+/// The token carries the caller's identity, so it can never exceed the caller's own permissions.
+async fn create_scoped_token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(scoped_request): Json<ScopedTokenRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let token = headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let claims = state.jwt_manager.verify_token(token)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    let ttl_seconds = scoped_request.ttl_seconds.unwrap_or(SCOPED_TOKEN_DEFAULT_TTL_SECONDS);
+    if scoped_request.scopes.is_empty() || !(1..=SCOPED_TOKEN_MAX_TTL_SECONDS).contains(&ttl_seconds) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    for scope in &scoped_request.scopes {
+        // Wildcard actions are not allowed for widget tokens; scopes must be explicit
+        let (resource, action) = match scope.split_once(':') {
+            Some((resource, action)) if is_valid_scope(scope) && action != "*" => (resource, action),
+            _ => return Err(StatusCode::BAD_REQUEST),
+        };
+
+        state.authorizer.require_context_permission(&auth_context, resource, action).await?;
+    }
+
+    // Bind the scoped token to the caller's current token version so revocation covers it too
+    let client = get_tenant_db_client(&state, &auth_context.tenant_id).await?;
+    let token_version = ensure_token_current(&client, &claims).await?;
+
+    let subject = TokenSubject {
+        user_id: &claims.sub,
+        email: &claims.email,
+        first_name: &claims.first_name,
+        last_name: &claims.last_name,
+        role: &claims.role,
+        tenant_id: &claims.tenant_id,
+    };
+    let options = TokenOptions {
+        token_version,
+        scopes: Some(scoped_request.scopes.clone()),
+        site_id: scoped_request.site_id.map(|site_id| site_id.to_string()),
+        ttl: Some(chrono::Duration::seconds(ttl_seconds)),
+        session_id: None,
+    };
+
+    let token = state.jwt_manager.generate_token_for(&subject, &options).map_err(|e| {
+        error!("Failed to generate scoped token: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!(
+        tenant_id = %auth_context.tenant_id,
+        scopes = ?scoped_request.scopes,
+        "Scoped token issued"
+    );
+
+    let response_data = ScopedTokenResponse {
+        token,
+        scopes: scoped_request.scopes,
+        site_id: scoped_request.site_id,
+        expires_at: chrono::Utc::now() + chrono::Duration::seconds(ttl_seconds),
+    };
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(response_data, request_id))))
+}
+
+/// Helper function to restrict API key management to unrestricted admin credentials
+fn require_api_key_admin(auth_context: &crate::auth::jwt_helpers::AuthContext) -> Result<(), StatusCode> {
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    auth_context.require_scope("security", "admin")
+}
+
 // Request/Response schemas
 #[derive(Debug, Deserialize)]
 struct LoginRequest {
     email: String,
     password: String,
+    totp_code: Option<String>,
+    backup_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcceptInviteRequest {
+    token: String,
+    password: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AcceptInviteResponse {
+    user_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScopedTokenRequest {
+    scopes: Vec<String>,
+    site_id: Option<Uuid>,
+    ttl_seconds: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ScopedTokenResponse {
+    token: String,
+    scopes: Vec<String>,
+    site_id: Option<Uuid>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct TwoFactorEnrollResponse {
+    provisioning_uri: String,
+    qr_payload: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TwoFactorVerifyRequest {
+    code: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TwoFactorVerifyResponse {
+    enabled: bool,
+    backup_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TwoFactorDisableRequest {
+    totp_code: Option<String>,
+    backup_code: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 struct LoginResponse {
     token: String,
+    refresh_token: String,
+    session_id: Uuid,
     user: UserInfo,
     tenant: TenantInfo,
 }
@@ -296,6 +973,21 @@ async fn get_db_client(state: &AppState) -> Result<deadpool_postgres::Client, St
     })
 }
 
+/// Helper function to get a database connection set to the caller's tenant, so
+/// row-level security shows its users
+async fn get_tenant_db_client(state: &AppState, tenant_id: &TenantId) -> Result<deadpool_postgres::Client, StatusCode> {
+    tenant_client(state.db.postgres(), tenant_id).await.map_err(|e| {
+        error!("Failed to get tenant database connection: {:#}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Helper function to get a database connection set to the tenant of the request's token
+async fn tenant_db_client_for(state: &AppState, headers: &HeaderMap) -> Result<deadpool_postgres::Client, StatusCode> {
+    let auth_context = extract_auth_context_with_role(headers, &state.jwt_manager)?;
+    get_tenant_db_client(state, &auth_context.tenant_id).await
+}
+
 /// Helper function to fetch user by ID
 async fn fetch_user_by_id(client: &deadpool_postgres::Client, user_id: Uuid) -> Result<User, StatusCode> {
     let query = "SELECT * FROM users WHERE id = $1 AND active = true";
@@ -315,18 +1007,228 @@ async fn fetch_user_by_id(client: &deadpool_postgres::Client, user_id: Uuid) ->
     }
 }
 
+/// Helper function to enforce the configured password policy on a password a user
+/// chooses when accepting an invite. Returns 400 listing the failed rules.
+async fn enforce_password_policy(
+    state: &AppState,
+    password: &str,
+    request_id: Uuid,
+) -> Result<(), (StatusCode, Json<ApiResponse<PasswordPolicyViolation>>)> {
+    let policy = &state.config.password_policy;
+
+    validate_password(policy, password).await.map_err(|failed_rules| {
+        warn!(request_id = %request_id, failed_rules = ?failed_rules, "Password rejected by policy");
+        let response = ApiResponse {
+            success: false,
+            data: Some(PasswordPolicyViolation::new(policy, failed_rules)),
+            error: Some("Password does not meet the password policy".to_string()),
+            request_id,
+        };
+        (StatusCode::BAD_REQUEST, Json(response))
+    })
+}
+
+/// Helper function to verify the bearer token and load the user it belongs to,
+/// rejecting tokens revoked by a token version bump
+async fn authenticate_current_user(
+    state: &AppState,
+    client: &deadpool_postgres::Client,
+    headers: &HeaderMap,
+) -> Result<User, StatusCode> {
+    let token = headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let claims = state.jwt_manager.verify_token(token)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let user_id: Uuid = claims.sub.parse().map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    let user = fetch_user_by_id(client, user_id).await?;
+    ensure_token_current(client, &claims).await?;
+    Ok(user)
+}
+
+/// Helper function to reject tokens issued before the user's current token version.
+/// Returns the current version so new tokens can be bound to it.
+async fn ensure_token_current(client: &deadpool_postgres::Client, claims: &Claims) -> Result<i32, StatusCode> {
+    let user_id: Uuid = claims.sub.parse().map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    let current_version = fetch_token_version(client, user_id).await?;
+    if claims.token_version != current_version {
+        warn!(user_id = %user_id, "Rejected revoked token");
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(current_version)
+}
+
+/// Helper function to get an active user's current token version
+async fn fetch_token_version(client: &deadpool_postgres::Client, user_id: Uuid) -> Result<i32, StatusCode> {
+    let row = client
+        .query_opt("SELECT token_version FROM users WHERE id = $1 AND active = true", &[&user_id])
+        .await
+        .map_err(|e| {
+            error!("Database error during token version lookup: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    Ok(row.get("token_version"))
+}
+
+/// Helper function to check a second factor: either a TOTP code or an unused backup code.
+/// Returns 428 when neither was supplied.
+async fn verify_second_factor(
+    state: &AppState,
+    client: &deadpool_postgres::Client,
+    user_id: Uuid,
+    totp_code: Option<&str>,
+    backup_code: Option<&str>,
+) -> Result<(), StatusCode> {
+    if let Some(code) = totp_code {
+        return consume_totp_code(state, client, user_id, code).await;
+    }
+    if let Some(code) = backup_code {
+        return consume_backup_code(client, user_id, code).await;
+    }
+    Err(StatusCode::PRECONDITION_REQUIRED)
+}
+
+/// Helper function to verify a TOTP code and record its time step so it cannot be replayed
+async fn consume_totp_code(
+    state: &AppState,
+    client: &deadpool_postgres::Client,
+    user_id: Uuid,
+    code: &str,
+) -> Result<(), StatusCode> {
+    let row = client
+        .query_opt(
+            "SELECT totp_secret_encrypted, totp_last_used_step FROM users WHERE id = $1 AND totp_enabled = true",
+            &[&user_id],
+        )
+        .await
+        .map_err(|e| {
+            error!("Database error during 2FA lookup: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let encrypted: Option<String> = row.get("totp_secret_encrypted");
+    let last_used_step: Option<i64> = row.get("totp_last_used_step");
+    let encrypted = encrypted.ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let secret = totp::decrypt_secret(totp_key(state), &encrypted).map_err(|e| {
+        error!("Failed to decrypt TOTP secret: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let step = totp::verify_code(&secret, code, chrono::Utc::now(), last_used_step)
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Conditional update so two concurrent requests cannot both use the same step
+    let updated = client
+        .execute(
+            "UPDATE users SET totp_last_used_step = $2 WHERE id = $1 AND (totp_last_used_step IS NULL OR totp_last_used_step < $2)",
+            &[&user_id, &step],
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to record TOTP step: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if updated == 0 {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(())
+}
+
+/// Helper function to check and burn a single-use backup code
+async fn consume_backup_code(
+    client: &deadpool_postgres::Client,
+    user_id: Uuid,
+    code: &str,
+) -> Result<(), StatusCode> {
+    let normalized = totp::normalize_backup_code(code);
+    let rows = client
+        .query(
+            "SELECT id, code_hash FROM user_backup_codes WHERE user_id = $1 AND used_at IS NULL",
+            &[&user_id],
+        )
+        .await
+        .map_err(|e| {
+            error!("Database error during backup code lookup: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // Checked off the async workers, since each bcrypt verify is deliberately slow
+    let codes: Vec<(Uuid, String)> = rows.iter().map(|row| (row.get("id"), row.get("code_hash"))).collect();
+    let matching = tokio::task::spawn_blocking(move || {
+        codes
+            .into_iter()
+            .filter(|(_, code_hash)| bcrypt::verify(&normalized, code_hash).unwrap_or(false))
+            .map(|(code_id, _)| code_id)
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| {
+        error!("Failed to check backup code: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    for code_id in matching {
+        let updated = client
+            .execute(
+                "UPDATE user_backup_codes SET used_at = NOW() WHERE id = $1 AND used_at IS NULL",
+                &[&code_id],
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to consume backup code: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if updated == 1 {
+            info!(user_id = %user_id, "Backup code used for second factor");
+            return Ok(());
+        }
+    }
+
+    Err(StatusCode::UNAUTHORIZED)
+}
+
+/// Helper function to pick the key material used to encrypt TOTP secrets
+fn totp_key(state: &AppState) -> &str {
+    state.config.auth.totp_encryption_key
+        .as_deref()
+        .unwrap_or(&state.config.auth.jwt_secret)
+}
+
 /// Helper function to generate JWT token
-fn generate_jwt_token(jwt_manager: &JwtManager, user: &User) -> Result<String, StatusCode> {
-    let role_str = role_to_string(&user.role);
+fn generate_jwt_token(
+    jwt_manager: &JwtManager,
+    user: &User,
+    token_version: i32,
+    session_id: Option<Uuid>,
+) -> Result<String, StatusCode> {
+    let user_id = user.id.to_string();
+    let tenant_id = user.tenant_id.to_string();
+    let subject = TokenSubject {
+        user_id: &user_id,
+        email: &user.email,
+        first_name: &user.first_name,
+        last_name: &user.last_name,
+        role: role_to_string(&user.role),
+        tenant_id: &tenant_id,
+    };
+    let options = TokenOptions {
+        token_version,
+        session_id: session_id.map(|id| id.to_string()),
+        ..TokenOptions::default()
+    };
     
-    jwt_manager.generate_token(
-        &user.id.to_string(),
-        &user.email,
-        &user.first_name,
-        &user.last_name,
-        role_str,
-        &user.tenant_id.to_string()
-    ).map_err(|e| {
+    jwt_manager.generate_token_for(&subject, &options).map_err(|e| {
         error!("Failed to generate JWT token: {}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })
@@ -363,7 +1265,153 @@ struct RefreshTokenResponse {
 }
 
 
+#[derive(Debug, Deserialize)]
+struct SessionsQuery {
+    user_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionInfo {
+    #[serde(flatten)]
+    session: Session,
+    /// Whether this is the session making the request
+    current: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct RevokedSessionsResponse {
+    revoked: u64,
+}
+
 #[derive(Debug, Serialize)]
 struct LogoutResponse {
     message: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test_harness::TestApp;
+    use axum::http::{header, Method, StatusCode};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_login_token_is_refused_once_revoked() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, crate::types::UserRole::Editor).await;
+        let hash = bcrypt::hash("correct horse battery", 4).unwrap();
+        let admin_client = app.admin_pool.get().await.unwrap();
+        admin_client
+            .execute("UPDATE users SET password_hash = $2 WHERE id = $1", &[&editor.id, &hash])
+            .await
+            .unwrap();
+        let login = |password: &str| {
+            let body = json!({ "email": editor.email, "password": password });
+            let mut request = app.request(Method::POST, "/api/auth/login", &editor, Some(body));
+            request.headers_mut().remove(header::AUTHORIZATION);
+            app.send(request)
+        };
+        let with_token = |token: &str, uri: &str| {
+            let mut request = app.request(Method::GET, uri, &editor, None);
+            request.headers_mut().insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+            app.send(request)
+        };
+
+        assert_eq!(login("wrong horse battery").await.status, StatusCode::UNAUTHORIZED);
+        let logged_in = login("correct horse battery").await;
+        assert_eq!(logged_in.status, StatusCode::OK, "{}", logged_in.body);
+        assert_eq!(logged_in.body["data"]["tenant"]["slug"], "tenant-a");
+        let token = logged_in.body["data"]["token"].as_str().unwrap().to_string();
+        assert_eq!(with_token(&token, "/api/auth/me").await.status, StatusCode::OK);
+        assert_eq!(with_token(&token, "/api/content").await.status, StatusCode::OK);
+
+        // Logging out everywhere and disabling 2FA bump the token version
+        admin_client
+            .execute("UPDATE users SET token_version = token_version + 1 WHERE id = $1", &[&editor.id])
+            .await
+            .unwrap();
+        assert_eq!(with_token(&token, "/api/content").await.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(with_token(&token, "/api/sites").await.status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_invite_accepted_once_with_a_policy_compliant_password() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = &app.tenant_a.admin;
+        let invited = app
+            .post("/api/users", admin, json!({ "email": "emily@bronte.example.com", "name": "Emily Brontë", "role": "Editor" }))
+            .await;
+        assert_eq!(invited.status, StatusCode::CREATED, "{}", invited.body);
+        let admin_client = app.admin_pool.get().await.unwrap();
+        let token: String = admin_client
+            .query_one("SELECT payload->>'invite_token' FROM notifications WHERE kind = 'user_invited'", &[])
+            .await
+            .unwrap()
+            .get(0);
+        let accept = |password: &str| {
+            let mut request = app.request(
+                Method::POST,
+                "/api/auth/invites/accept",
+                admin,
+                Some(json!({ "token": token, "password": password })),
+            );
+            request.headers_mut().remove(header::AUTHORIZATION);
+            app.send(request)
+        };
+
+        let weak = accept("emily").await;
+        assert_eq!(weak.status, StatusCode::BAD_REQUEST);
+        let rules: Vec<&str> = weak.body["data"]["failed_rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["rule"].as_str().unwrap())
+            .collect();
+        assert_eq!(rules, vec!["min_length", "uppercase", "digit"]);
+
+        let accepted = accept("Wuthering Heights 1847").await;
+        assert_eq!(accepted.status, StatusCode::OK, "{}", accepted.body);
+        let password_hash: String = admin_client
+            .query_one("SELECT password_hash FROM users WHERE email = 'emily@bronte.example.com'", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert!(bcrypt::verify("Wuthering Heights 1847", &password_hash).unwrap());
+        assert_eq!(accept("Agnes Grey 1847 again").await.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_scopes_hold_on_every_route() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = &app.tenant_a.admin;
+        let created = app
+            .post("/api/auth/api-keys", admin, json!({ "name": "Feed", "scopes": ["content:read"] }))
+            .await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let key = created.body["data"]["key"].as_str().unwrap().to_string();
+        let site = app.post("/api/sites", admin, json!({ "name": "Keyed" })).await;
+        assert_eq!(site.status, StatusCode::CREATED, "{}", site.body);
+        let site_id = site.body["data"]["id"].as_str().unwrap().to_string();
+        let send = |method: Method, uri: String| {
+            let mut request = app.request(method, &uri, admin, None);
+            request.headers_mut().insert(header::AUTHORIZATION, format!("Bearer {}", key).parse().unwrap());
+            app.send(request)
+        };
+
+        assert_eq!(send(Method::GET, "/api/content".to_string()).await.status, StatusCode::OK);
+        for (method, uri) in [
+            (Method::DELETE, format!("/api/sites/{}", site_id)),
+            (Method::GET, format!("/api/sites/{}/pages", site_id)),
+            (Method::GET, "/api/users".to_string()),
+            (Method::GET, "/api/tenants/current".to_string()),
+            (Method::GET, "/api/templates/cache".to_string()),
+        ] {
+            let response = send(method.clone(), uri.clone()).await;
+            assert_eq!(response.status, StatusCode::FORBIDDEN, "{} {}", method, uri);
+        }
+        assert_eq!(app.get(&format!("/api/sites/{}", site_id), admin).await.status, StatusCode::OK);
+
+        let revoke = format!("/api/auth/api-keys/{}", created.body["data"]["id"].as_str().unwrap());
+        assert_eq!(app.send(app.request(Method::DELETE, &revoke, admin, None)).await.status, StatusCode::OK);
+        assert_eq!(send(Method::GET, "/api/content".to_string()).await.status, StatusCode::UNAUTHORIZED);
+    }
+}
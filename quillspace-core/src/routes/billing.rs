@@ -0,0 +1,140 @@
+use crate::{
+    auth::jwt_helpers::extract_auth_context_with_role,
+    services::billing::{BillingError, BillingService},
+    types::{ApiResponse, UserRole},
+    AppState,
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::post,
+    Json, Router,
+};
+use serde::Deserialize;
+use tracing::{error, info};
+use uuid::Uuid;
+
+/// Create billing routes; subscription changes arrive via `POST /api/webhooks/stripe`
+pub fn create_routes() -> Router<AppState> {
+    Router::new().route("/checkout", post(create_checkout_session))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckoutRequest {
+    /// Plan to subscribe to, e.g. `author`
+    pub plan: String,
+}
+
+/// Start a Stripe Checkout session for the tenant (admin only); the client redirects to its `url`
+async fn create_checkout_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CheckoutRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    auth_context.require_scope("tenants", "configure")?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let billing = BillingService::new(
+        state.db.postgres().clone(),
+        state.config.billing.clone(),
+        state.config.plans.clone(),
+    );
+    match billing.create_checkout_session(&auth_context.tenant_id, &request.plan, None).await {
+        Ok(session) => {
+            info!(tenant_id = %auth_context.tenant_id, plan = %request.plan, session_id = %session.id, "Checkout session started");
+            Ok((StatusCode::CREATED, Json(ApiResponse::success(session, request_id))))
+        }
+        Err(BillingError::UnknownPlan(_)) => Err(StatusCode::BAD_REQUEST),
+        Err(BillingError::NotConfigured) => Err(StatusCode::SERVICE_UNAVAILABLE),
+        Err(e) => {
+            error!(tenant_id = %auth_context.tenant_id, error = %e, "Failed to start checkout session");
+            Err(StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{WebhookProviderConfig, WebhookSignatureScheme},
+        test_harness::TestApp,
+    };
+    use axum::{
+        body::Body,
+        extract::ConnectInfo,
+        http::{Method, Request, StatusCode},
+    };
+    use hmac::{Hmac, Mac};
+    use serde_json::json;
+    use sha2::Sha256;
+    use std::net::SocketAddr;
+
+    const WEBHOOK_SECRET: &str = "whsec_test_secret";
+
+    fn signed_webhook(body: &[u8]) -> Request<Body> {
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut mac = Hmac::<Sha256>::new_from_slice(WEBHOOK_SECRET.as_bytes()).unwrap();
+        mac.update(format!("{}.", timestamp).as_bytes());
+        mac.update(body);
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/webhooks/stripe")
+            .header("stripe-signature", format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes())))
+            .body(Body::from(body.to_vec()))
+            .unwrap();
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 0))));
+        request
+    }
+
+    #[tokio::test]
+    async fn test_past_due_webhook_makes_the_tenant_read_only() {
+        let Some(app) = TestApp::start_with(|config| {
+            config.billing.plan_prices.insert("author".to_string(), "price_author_monthly".to_string());
+            config.webhooks.insert(
+                "stripe".to_string(),
+                WebhookProviderConfig {
+                    secret: WEBHOOK_SECRET.to_string(),
+                    signature_header: "stripe-signature".to_string(),
+                    scheme: WebhookSignatureScheme::Stripe,
+                    ..WebhookProviderConfig::default()
+                },
+            );
+        })
+        .await
+        else {
+            return;
+        };
+        let admin = &app.tenant_a.admin;
+
+        let event = json!({
+            "id": "evt_1Qabc",
+            "type": "customer.subscription.updated",
+            "created": chrono::Utc::now().timestamp(),
+            "data": {
+                "object": {
+                    "id": "sub_1Qxyz",
+                    "customer": "cus_Rabc",
+                    "status": "past_due",
+                    "metadata": { "tenant_id": app.tenant_a.id.to_string() },
+                    "items": { "data": [{ "price": { "id": "price_author_monthly" } }] }
+                }
+            }
+        });
+        let response = app.send(signed_webhook(event.to_string().as_bytes())).await;
+        assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+        let write = json!({ "timezone": "Europe/London" });
+        let response = app.send(app.request(Method::PUT, "/api/tenants/current/settings", admin, Some(write.clone()))).await;
+        assert_eq!(response.status, StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(app.get("/api/tenants/current/settings", admin).await.status, StatusCode::OK);
+
+        let other = &app.tenant_b.admin;
+        let response = app.send(app.request(Method::PUT, "/api/tenants/current/settings", other, Some(write))).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    }
+}
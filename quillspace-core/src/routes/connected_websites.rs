@@ -1,14 +1,23 @@
 use axum::{
-    extract::{Path, Request, State},
-    http::StatusCode,
-    response::Json,
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{get, post, put},
     Router,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use uuid::Uuid;
 use crate::{
-    services::connected_websites::{ConnectedWebsitesService, ConnectedWebsite},
+    auth::jwt_helpers::{extract_auth_context, extract_auth_context_with_role, AuthContext},
+    services::business_info_sync::{
+        BusinessInfoState, BusinessInfoSyncError, BusinessInfoSyncService, ConflictStrategy, SyncDirection, SyncOutcome,
+    },
+    services::connected_websites::{
+        ConnectedWebsitesService, ConnectedWebsite, ConnectionTestError, WebsiteListParams, WebsitePage,
+    },
+    services::wix_api::{BusinessInfo, WixApiClient, WixApiError},
+    types::ApiResponse,
     AppState,
 };
 
@@ -16,60 +25,98 @@ use crate::{
 #[derive(Debug, Serialize)]
 pub struct ConnectedWebsitesResponse {
     pub websites: Vec<ConnectedWebsite>,
+    pub total: i64,
+    pub page: u32,
+    pub per_page: u32,
 }
 
+/// The Wix site the books and author routes work against
+const WIX_SITE_ID: &str = "1e4e0091-f4d5-4a4c-a66a-4d09e7a5b4e9";
+
 pub fn connected_websites_routes() -> Router<AppState> {
     Router::new()
         .route("/test", get(|| async { "CONNECTED WEBSITES ROUTE WORKS!" }))
         .route("/websites", get(get_user_websites))
+        .route("/wix/test-connection", post(test_wix_connection))
         .route("/wix/books", get(get_wix_books_simple))
         .route("/wix/books", post(create_wix_book))
         .route("/wix/books/with-schema", post(create_wix_book_with_proper_types))
         .route("/wix/books/:book_id", put(update_wix_book))
         .route("/wix/author", get(get_wix_author_info))
         .route("/wix/author", put(update_wix_author_info))
+        .route("/wix/sites/:site_id/business-info", get(get_business_info).put(update_business_info))
+        .route("/wix/sites/:site_id/business-info/pull", post(pull_business_info))
+        .route("/wix/sites/:site_id/business-info/push", post(push_business_info))
+}
+
+/// Wix credentials as entered, before they are saved
+#[derive(Debug, Deserialize)]
+pub struct TestConnectionRequest {
+    pub api_key: String,
+    pub account_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncParams {
+    #[serde(default)]
+    pub strategy: ConflictStrategy,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BusinessInfoSyncResponse {
+    pub outcome: SyncOutcome,
+    pub business_info: BusinessInfoState,
+}
+
+/// Test Wix credentials. A pass is reused briefly and each user gets a few tests a
+/// minute; the result tells rejected credentials from Wix being unreachable.
+pub async fn test_wix_connection(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<TestConnectionRequest>,
+) -> Result<Response, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    auth_context.require_scope("sites", "write")?;
+    if request.api_key.trim().is_empty() || request.account_id.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let client = WixApiClient::new(request.api_key, request.account_id);
+    match state.wix_connection_tests.test(auth_context.user_id, &client).await {
+        Ok(result) => Ok(Json(ApiResponse::success(result, request_id)).into_response()),
+        Err(e @ ConnectionTestError::TooManyTests { retry_after_secs }) => Ok((
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after_secs.to_string())],
+            Json(ApiResponse::<()>::error(e.to_string(), request_id)),
+        )
+            .into_response()),
+    }
 }
 
 /// Get QuillSpace-built websites for the authenticated user
 pub async fn get_user_websites(
     State(state): State<AppState>,
+    Query(params): Query<WebsiteListParams>,
     request: Request,
 ) -> Result<Json<ConnectedWebsitesResponse>, StatusCode> {
-    // Extract Authorization header
-    let auth_header = request
-        .headers()
-        .get("authorization")
-        .and_then(|h| h.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-    
-    // Extract token from "Bearer <token>"
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-    
-    // Decode and validate JWT token
-    let claims = match state.jwt_manager.verify_token(token) {
-        Ok(claims) => claims,
-        Err(e) => {
-            tracing::error!("JWT verification failed: {}", e);
-            return Err(StatusCode::UNAUTHORIZED);
-        }
-    };
-    
-    // Get user ID from claims
-    let user_id: Uuid = claims.sub.parse().map_err(|e| {
-        tracing::error!("Failed to parse user ID from JWT: {}", e);
-        StatusCode::UNAUTHORIZED
-    })?;
+    let (_tenant_id, user_id) = extract_auth_context(request.headers(), &state.jwt_manager)?;
     
     tracing::info!("Getting websites for user: {}", user_id);
     
-    let service = ConnectedWebsitesService::new(state.db.clone());
+    let mut service = ConnectedWebsitesService::new(state.db.clone());
+    // Without Wix credentials the listing still works, from what is stored
+    if let (Ok(api_key), Ok(account_id)) = (
+        std::env::var("QUILLSPACE_WIX_API_KEY"),
+        std::env::var("QUILLSPACE_WIX_ACCOUNT_ID"),
+    ) {
+        service = service.with_wix_lookup(state.wix_sites.clone(), Arc::new(WixApiClient::new(api_key, account_id)));
+    }
     
-    match service.get_user_websites(user_id).await {
-        Ok(websites) => {
-            tracing::info!("Found {} websites for user {}", websites.len(), user_id);
-            Ok(Json(ConnectedWebsitesResponse { websites }))
+    match service.get_user_websites(user_id, &params).await {
+        Ok(WebsitePage { websites, total, page, per_page }) => {
+            tracing::info!("Found {} of {} websites for user {}", websites.len(), total, user_id);
+            Ok(Json(ConnectedWebsitesResponse { websites, total, page, per_page }))
         },
         Err(e) => {
             tracing::error!("Failed to get user websites: {}", e);
@@ -79,15 +126,9 @@ pub async fn get_user_websites(
 }
 
 /// Get Wix books - SIMPLE VERSION
-pub async fn get_wix_books_simple() -> Result<Json<serde_json::Value>, StatusCode> {
-    let api_key = std::env::var("QUILLSPACE_WIX_API_KEY")
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let account_id = std::env::var("QUILLSPACE_WIX_ACCOUNT_ID")
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let client = crate::services::wix_api::WixApiClient::new(api_key, account_id);
-    
-    match client.get_collection_items("1e4e0091-f4d5-4a4c-a66a-4d09e7a5b4e9", "Books").await {
+pub async fn get_wix_books_simple(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    let service = ConnectedWebsitesService::new(state.db.clone());
+    match service.get_wix_books(WIX_SITE_ID).await {
         Ok(books) => Ok(Json(books)),
         Err(e) => {
             tracing::error!("Failed to get Wix books: {}", e);
@@ -98,16 +139,11 @@ pub async fn get_wix_books_simple() -> Result<Json<serde_json::Value>, StatusCod
 
 /// Create new book in Wix
 pub async fn create_wix_book(
+    State(state): State<AppState>,
     Json(book_data): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    let api_key = std::env::var("QUILLSPACE_WIX_API_KEY")
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let account_id = std::env::var("QUILLSPACE_WIX_ACCOUNT_ID")
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let client = crate::services::wix_api::WixApiClient::new(api_key, account_id);
-    
-    match client.insert_collection_item("1e4e0091-f4d5-4a4c-a66a-4d09e7a5b4e9", "Books", book_data).await {
+    let service = ConnectedWebsitesService::new(state.db.clone());
+    match service.create_wix_book(WIX_SITE_ID, book_data).await {
         Ok(book) => Ok(Json(book)),
         Err(e) => {
             tracing::error!("Failed to create Wix book: {}", e);
@@ -118,17 +154,12 @@ pub async fn create_wix_book(
 
 /// Update book in Wix
 pub async fn update_wix_book(
+    State(state): State<AppState>,
     Path(book_id): Path<String>,
     Json(book_data): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    let api_key = std::env::var("QUILLSPACE_WIX_API_KEY")
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let account_id = std::env::var("QUILLSPACE_WIX_ACCOUNT_ID")
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let client = crate::services::wix_api::WixApiClient::new(api_key, account_id);
-    
-    match client.update_collection_item("1e4e0091-f4d5-4a4c-a66a-4d09e7a5b4e9", "Books", &book_id, book_data).await {
+    let service = ConnectedWebsitesService::new(state.db.clone());
+    match service.update_wix_book(WIX_SITE_ID, &book_id, book_data).await {
         Ok(book) => Ok(Json(book)),
         Err(e) => {
             tracing::error!("Failed to update Wix book: {}", e);
@@ -138,15 +169,9 @@ pub async fn update_wix_book(
 }
 
 /// Get Wix author info
-pub async fn get_wix_author_info() -> Result<Json<serde_json::Value>, StatusCode> {
-    let api_key = std::env::var("QUILLSPACE_WIX_API_KEY")
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let account_id = std::env::var("QUILLSPACE_WIX_ACCOUNT_ID")
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let client = crate::services::wix_api::WixApiClient::new(api_key, account_id);
-    
-    match client.get_collection_items("1e4e0091-f4d5-4a4c-a66a-4d09e7a5b4e9", "AuthorInfo").await {
+pub async fn get_wix_author_info(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    let service = ConnectedWebsitesService::new(state.db.clone());
+    match service.get_wix_author_info(WIX_SITE_ID).await {
         Ok(author) => Ok(Json(author)),
         Err(e) => {
             tracing::error!("Failed to get Wix author info: {}", e);
@@ -155,44 +180,16 @@ pub async fn get_wix_author_info() -> Result<Json<serde_json::Value>, StatusCode
     }
 }
 
-/// Update Wix author info
+/// Update Wix author info, creating it if the site has none yet
 pub async fn update_wix_author_info(
+    State(state): State<AppState>,
     Json(author_data): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    let api_key = std::env::var("QUILLSPACE_WIX_API_KEY")
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let account_id = std::env::var("QUILLSPACE_WIX_ACCOUNT_ID")
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let client = crate::services::wix_api::WixApiClient::new(api_key, account_id);
-    
-    // Get existing AuthorInfo to update it
-    match client.get_collection_items("1e4e0091-f4d5-4a4c-a66a-4d09e7a5b4e9", "AuthorInfo").await {
-        Ok(existing_data) => {
-            if let Some(items) = existing_data.get("dataItems").and_then(|v| v.as_array()) {
-                if let Some(first_item) = items.first() {
-                    if let Some(item_id) = first_item.get("id").and_then(|v| v.as_str()) {
-                        return match client.update_collection_item("1e4e0091-f4d5-4a4c-a66a-4d09e7a5b4e9", "AuthorInfo", item_id, author_data).await {
-                            Ok(author) => Ok(Json(author)),
-                            Err(e) => {
-                                tracing::error!("Failed to update Wix author info: {}", e);
-                                Err(StatusCode::INTERNAL_SERVER_ERROR)
-                            }
-                        };
-                    }
-                }
-            }
-            // No existing author info, create new one
-            match client.insert_collection_item("1e4e0091-f4d5-4a4c-a66a-4d09e7a5b4e9", "AuthorInfo", author_data).await {
-                Ok(author) => Ok(Json(author)),
-                Err(e) => {
-                    tracing::error!("Failed to create Wix author info: {}", e);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
-            }
-        }
+    let service = ConnectedWebsitesService::new(state.db.clone());
+    match service.update_wix_author_info(WIX_SITE_ID, author_data).await {
+        Ok(author) => Ok(Json(author)),
         Err(e) => {
-            tracing::error!("Failed to get existing Wix author info: {}", e);
+            tracing::error!("Failed to update Wix author info: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
@@ -208,7 +205,7 @@ pub async fn create_wix_book_with_proper_types(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     let client = crate::services::wix_api::WixApiClient::new(api_key, account_id);
-    let site_id = "1e4e0091-f4d5-4a4c-a66a-4d09e7a5b4e9";
+    let site_id = WIX_SITE_ID;
     let collection_id = "Books";
     
     // First, ensure the priceAmount field exists with proper type (since price is already wrong type)
@@ -231,3 +228,107 @@ pub async fn create_wix_book_with_proper_types(
         }
     }
 }
+
+async fn business_info_context(state: &AppState, headers: &HeaderMap, action: &str) -> Result<AuthContext, StatusCode> {
+    let auth_context = extract_auth_context_with_role(headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "sites", action).await?;
+    Ok(auth_context)
+}
+
+fn business_info_error_status(site_id: &str, e: BusinessInfoSyncError) -> StatusCode {
+    match &e {
+        BusinessInfoSyncError::SiteNotFound => StatusCode::NOT_FOUND,
+        BusinessInfoSyncError::Wix(WixApiError::RateLimited { .. }) => StatusCode::SERVICE_UNAVAILABLE,
+        BusinessInfoSyncError::Wix(_) => {
+            tracing::error!(site_id, error = %e, "Wix business info request failed");
+            StatusCode::BAD_GATEWAY
+        }
+        BusinessInfoSyncError::InvalidState(_) | BusinessInfoSyncError::Database(_) => {
+            tracing::error!(site_id, error = %e, "Failed to sync business info");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// The business info QuillSpace holds for a Wix site, with its sync status
+pub async fn get_business_info(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = business_info_context(&state, &headers, "read").await?;
+
+    let service = BusinessInfoSyncService::new(state.db.clone());
+    let business_info = service
+        .get_state(&auth_context.tenant_id, auth_context.user_id, &site_id)
+        .await
+        .map_err(|e| business_info_error_status(&site_id, e))?;
+    Ok(Json(ApiResponse::success(business_info, request_id)))
+}
+
+/// Edit the business info in QuillSpace; Wix is updated on the next push
+pub async fn update_business_info(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<String>,
+    Json(info): Json<BusinessInfo>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = business_info_context(&state, &headers, "update").await?;
+
+    let service = BusinessInfoSyncService::new(state.db.clone());
+    let business_info = service
+        .edit(&auth_context.tenant_id, auth_context.user_id, &site_id, info)
+        .await
+        .map_err(|e| business_info_error_status(&site_id, e))?;
+    Ok(Json(ApiResponse::success(business_info, request_id)))
+}
+
+/// Pull business info from Wix into the website's metadata
+pub async fn pull_business_info(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<String>,
+    Query(params): Query<SyncParams>,
+) -> Result<impl IntoResponse, StatusCode> {
+    sync_business_info(state, headers, site_id, SyncDirection::Pull, params.strategy).await
+}
+
+/// Push QuillSpace's business info edits to Wix
+pub async fn push_business_info(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<String>,
+    Query(params): Query<SyncParams>,
+) -> Result<impl IntoResponse, StatusCode> {
+    sync_business_info(state, headers, site_id, SyncDirection::Push, params.strategy).await
+}
+
+/// Run a sync; a flagged conflict is a `409` carrying both versions
+async fn sync_business_info(
+    state: AppState,
+    headers: HeaderMap,
+    site_id: String,
+    direction: SyncDirection,
+    strategy: ConflictStrategy,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = business_info_context(&state, &headers, "update").await?;
+
+    let api_key = std::env::var("QUILLSPACE_WIX_API_KEY")
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let account_id = std::env::var("QUILLSPACE_WIX_ACCOUNT_ID")
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let wix = WixApiClient::new(api_key, account_id);
+
+    let service = BusinessInfoSyncService::new(state.db.clone());
+    let (business_info, outcome) = service
+        .sync(&auth_context.tenant_id, auth_context.user_id, &site_id, &wix, direction, strategy)
+        .await
+        .map_err(|e| business_info_error_status(&site_id, e))?;
+
+    tracing::info!(site_id = %site_id, outcome = ?outcome, "Business info sync finished");
+    let status = if outcome == SyncOutcome::Conflict { StatusCode::CONFLICT } else { StatusCode::OK };
+    Ok((status, Json(ApiResponse::success(BusinessInfoSyncResponse { outcome, business_info }, request_id))))
+}
@@ -1,53 +1,144 @@
 use crate::{
-    auth::jwt_helpers::{extract_auth_context, extract_auth_context_with_role},
-    types::{ApiResponse, Content, ContentStatus, PaginatedResponse, PaginationParams, UserRole},
+    auth::jwt_helpers::{extract_auth_context, extract_auth_context_with_role, AuthContext},
+    database::postgres::{is_statement_timeout, tenant_client},
+    middleware::transaction::TenantTransaction,
+    services::{
+        analytics_replay::content_event_id,
+        bulk_publish::{BulkItemStatus, BulkPublishRequest},
+        content::{
+            content_status_to_string, query_content_page, status_from_string, write_new_co_authors,
+            ContentAuthorError, ContentFilter, ContentPage, ContentService, ContentTranslationError,
+        },
+        content_comment::{ContentCommentError, ContentCommentService, NewComment},
+        content_fields::ContentFieldAccess,
+        content_related::{normalize_tags, DEFAULT_RELATED_LIMIT},
+        content_review::{ContentReviewError, ReviewAction},
+        content_revision::ContentRevisionService,
+        html_sanitize::load_sanitize_settings,
+        locale::DEFAULT_LOCALE,
+        publish_schedule::{PublishScheduleError, PublishScheduleService, WindowState},
+        request_validation::{Validate, ValidationErrors, MAX_TITLE_LEN},
+        timezone::{load_user_timezone, parse_schedule_input, to_local},
+    },
+    types::{
+        ApiResponse, Content, ContentAuthor, ContentStatus, ContentWithAuthors, PaginatedResponse, Patch,
+        ReviewDecision, TenantId, UserId, UserRole,
+    },
     AppState,
 };
 use axum::{
     extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
-    response::IntoResponse,
-    routing::{get, post},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
     Json, Router,
 };
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use tokio_postgres::{Row, Error as PgError};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 
 /// Helper function to convert a tokio-postgres Row to Content
 fn row_to_content(row: &Row) -> Result<Content, PgError> {
+    let status = status_from_string(&row.try_get::<_, String>("status")?);
+    let review_decision: Option<String> = row.try_get("review_decision")?;
     Ok(Content {
         id: row.try_get("id")?,
         tenant_id: row.try_get("tenant_id")?,
         title: row.try_get("title")?,
         slug: row.try_get("slug")?,
         body: row.try_get("body")?,
-        status: row.try_get("status")?,
+        status,
         author_id: row.try_get("author_id")?,
         published_at: row.try_get("published_at")?,
+        scheduled_publish_at: row.try_get("scheduled_publish_at")?,
+        locale: row.try_get("locale")?,
+        translation_group_id: row.try_get("translation_group_id")?,
+        tags: row.try_get("tags")?,
+        review_decision: review_decision.as_deref().and_then(ReviewDecision::parse),
+        reviewed_by: row.try_get("reviewed_by")?,
+        reviewed_at: row.try_get("reviewed_at")?,
+        review_comment: row.try_get("review_comment")?,
         created_at: row.try_get("created_at")?,
         updated_at: row.try_get("updated_at")?,
     })
 }
 
-/// Helper function to convert ContentStatus to string for database
-fn content_status_to_string(status: &ContentStatus) -> &'static str {
-    match status {
-        ContentStatus::Draft => "draft",
-        ContentStatus::Published => "published",
-        ContentStatus::Archived => "archived",
+/// Queries cancelled at the statement timeout are reported as 503 so clients can retry
+fn query_error_status(error: &PgError) -> StatusCode {
+    if is_statement_timeout(error) {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
     }
 }
 
+/// Which content fields the requester may see. Every response that lists or shows
+/// content to readers goes through this, so they all leave out the same fields.
+async fn field_access(state: &AppState, auth_context: &AuthContext) -> Result<ContentFieldAccess, StatusCode> {
+    ContentFieldAccess::for_context(&state.authorizer, auth_context).await.map_err(|e| {
+        error!("Failed to check content field permissions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+fn visible_content<T: Serialize>(access: ContentFieldAccess, content: &T) -> Result<serde_json::Value, StatusCode> {
+    access.to_value(content).map_err(|e| {
+        error!("Failed to serialize content: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// A byline on its own, filtered like the `authors` of a content response
+async fn visible_authors(
+    state: &AppState,
+    auth_context: &AuthContext,
+    authors: &[ContentAuthor],
+) -> Result<serde_json::Value, StatusCode> {
+    field_access(state, auth_context).await?.authors_to_value(authors).map_err(|e| {
+        error!("Failed to serialize content authors: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// `body` as stored for this author: sanitized unless the tenant trusts them
+async fn sanitized_body(state: &AppState, auth_context: &AuthContext, body: &str) -> Result<String, StatusCode> {
+    let client = tenant_client(state.db.postgres(), &auth_context.tenant_id).await.map_err(|e| {
+        error!("Failed to get database connection: {:#}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let settings = load_sanitize_settings(&client, auth_context.tenant_id.as_uuid()).await.map_err(|e| {
+        error!("Failed to load content sanitization settings: {:#}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(settings.apply(body, &auth_context.user_role, auth_context.user_id))
+}
+
 /// Create content management routes
 pub fn create_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_content).post(create_content))
-        .route("/:content_id", get(get_content).put(update_content).delete(delete_content))
+        .route("/publish", post(bulk_publish_content))
+        .route("/unpublish", post(bulk_unpublish_content))
+        .route("/:content_id", get(get_content).put(update_content).patch(update_content).delete(delete_content))
         .route("/:content_id/publish", post(publish_content))
         .route("/:content_id/archive", post(archive_content))
+        .route("/:content_id/schedule", put(schedule_content).delete(unschedule_content))
+        .route("/:content_id/expiry", put(set_content_expiry).delete(clear_content_expiry))
+        .route("/:content_id/submit", post(submit_for_review))
+        .route("/:content_id/approve", post(approve_content))
+        .route("/:content_id/reject", post(reject_content))
+        .route("/:content_id/related", get(list_related_content))
+        .route("/:content_id/translations", get(list_translations).post(create_translation))
+        .route("/:content_id/authors", get(list_authors).post(add_author))
+        .route("/:content_id/authors/:user_id", delete(remove_author))
+        .route("/:content_id/comments", get(list_comments).post(create_comment))
+        .route("/:content_id/comments/:comment_id", put(update_comment).delete(delete_comment))
+        .route("/:content_id/comments/:comment_id/resolve", post(resolve_comment_thread))
+        .route("/:content_id/revisions", get(list_revisions))
+        .route("/:content_id/revisions/diff", get(diff_revisions))
         .route("/:content_id/analytics", get(get_content_analytics))
 }
 
@@ -57,14 +148,17 @@ async fn list_content(
     headers: HeaderMap,
     Query(params): Query<ListContentQuery>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "read").await?;
+    let tenant_id = auth_context.tenant_id.clone();
     let request_id = Uuid::new_v4();
 
-    let limit: u32 = params.pagination.limit.unwrap_or(20).min(100);
-    let offset: i64 = ((params.pagination.page.unwrap_or(1) - 1) * limit) as i64;
+    let limit: u32 = params.limit.unwrap_or(20).clamp(1, 100);
+    let page = params.page.unwrap_or(1).max(1);
+    let offset: i64 = ((page - 1) * limit) as i64;
 
     // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -72,66 +166,40 @@ async fn list_content(
         }
     };
 
-    // Build query with filters
-    let mut query = "SELECT * FROM content WHERE tenant_id = $1".to_string();
-    let mut params_vec: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![tenant_id.as_uuid()];
-    let mut param_index = 2;
-
-    // Declare status_str outside the conditional block so it lives long enough
-    let status_str;
-    if let Some(status) = &params.status {
-        query.push_str(&format!(" AND status = ${}", param_index));
-        status_str = content_status_to_string(status);
-        params_vec.push(&status_str);
-        param_index += 1;
-    }
-
-    if let Some(author_id) = &params.author_id {
-        query.push_str(&format!(" AND author_id = ${}", param_index));
-        params_vec.push(author_id);
-        param_index += 1;
-    }
-
-    query.push_str(&format!(" ORDER BY created_at DESC LIMIT ${} OFFSET ${}", param_index, param_index + 1));
-    let limit_i64 = limit as i64;
-    params_vec.push(&limit_i64);
-    params_vec.push(&offset);
-
-    // Get total count for pagination
-    let count_query = "SELECT COUNT(*) FROM content WHERE tenant_id = $1";
-    let count_params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![tenant_id.as_uuid()];
-
-    let content_rows = match client.query(query.as_str(), &params_vec).await {
-        Ok(rows) => rows,
-        Err(e) => {
-            error!("Failed to query content: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    };
-    
-    let count_row = match client.query_one(count_query, &count_params).await {
-        Ok(row) => row,
-        Err(e) => {
-            error!("Failed to query content count: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
+    let filter = ContentFilter {
+        status: params.status,
+        author_id: params.author_id,
+        locale: params.locale,
     };
-    
-    let content: Result<Vec<Content>, _> = content_rows.iter().map(row_to_content).collect();
-    let content = match content {
-        Ok(content) => content,
+    let ContentPage { items: content, total } =
+        match query_content_page(&client, &tenant_id, &filter, limit as i64, offset).await {
+            Ok(page) => page,
+            Err(e) => {
+                error!("Failed to query content: {}", e);
+                return Err(query_error_status(&e));
+            }
+        };
+
+    let content_ids: Vec<Uuid> = content.iter().map(|content| content.id).collect();
+    let mut authors = match ContentService::new(state.db.postgres().clone()).authors_for(&tenant_id, &content_ids).await {
+        Ok(authors) => authors,
         Err(e) => {
-            error!("Failed to parse content rows: {}", e);
+            error!("Failed to load content authors: {}", e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
+    let access = field_access(&state, &auth_context).await?;
+    let items = content
+        .into_iter()
+        .map(|content| {
+            visible_content(access, &ContentWithAuthors { authors: authors.remove(&content.id).unwrap_or_default(), content })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let total: u64 = count_row.get::<_, i64>(0) as u64;
-    let page = params.pagination.page.unwrap_or(1);
     let total_pages = ((total + limit as u64 - 1) / limit as u64) as u32;
 
     let paginated = PaginatedResponse {
-        items: content,
+        items,
         total,
         page,
         limit,
@@ -146,35 +214,40 @@ async fn list_content(
 async fn create_content(
     State(state): State<AppState>,
     headers: HeaderMap,
+    transaction: TenantTransaction,
     Json(content_request): Json<CreateContentRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
+) -> Result<Response, StatusCode> {
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
     let request_id = Uuid::new_v4();
 
-    // Verify editor/admin authorization for content creation
-    if !matches!(auth_context.user_role, UserRole::Editor | UserRole::Admin) {
-        return Err(StatusCode::FORBIDDEN);
+    // Verify authorization for content creation (role and token scopes)
+    state.authorizer.require_context_permission(&auth_context, "content", "write").await?;
+
+    if let Err(errors) = content_request.validate() {
+        return Ok(errors.into_response(request_id));
     }
 
     let content_id = Uuid::new_v4();
     let author_id = auth_context.user_id; // Use actual user ID from JWT
     let now = chrono::Utc::now();
+    let locale = content_request.locale.as_deref().unwrap_or(DEFAULT_LOCALE);
 
-    // Get database connection
-    let client = match state.db.postgres().get().await {
-        Ok(client) => client,
-        Err(e) => {
-            error!("Failed to get database connection: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    };
-
-    // Use the status from the request, defaulting to Draft if not provided
+    // Use the status from the request, defaulting to Draft if not provided.
+    // New content has not been reviewed, so it cannot start out published.
     let status = content_request.status.unwrap_or(ContentStatus::Draft);
-    
+    if matches!(status, ContentStatus::Published) {
+        return Err(StatusCode::CONFLICT);
+    }
+    let tags = normalize_tags(&content_request.tags);
+    let status = content_status_to_string(&status);
+    let body = sanitized_body(&state, &auth_context, &content_request.body).await?;
+
+    // The writes are committed with the response, so a failure after the insert leaves
+    // no content behind
+    let client = transaction.client().await?;
     let query = r#"
-        INSERT INTO content (id, tenant_id, title, slug, body, status, author_id, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        INSERT INTO content (id, tenant_id, title, slug, body, status, author_id, locale, translation_group_id, tags, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $1, $9, $10, $11)
         RETURNING *
         "#;
     
@@ -183,9 +256,11 @@ async fn create_content(
         auth_context.tenant_id.as_uuid(),
         &content_request.title,
         &content_request.slug,
-        &content_request.body,
+        &body,
         &status,
         &author_id,
+        &locale,
+        &tags,
         &now,
         &now,
     ];
@@ -200,14 +275,30 @@ async fn create_content(
                 }
             };
 
+            if !content_request.co_authors.is_empty() {
+                match write_new_co_authors(&*client, &auth_context.tenant_id, content_id, author_id, &content_request.co_authors).await {
+                    Ok(()) => {}
+                    Err(ContentAuthorError::Database(e)) => {
+                        error!("Failed to add content co-authors: {:#}", e);
+                        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                    }
+                    Err(e) => {
+                        let mut errors = ValidationErrors::new();
+                        errors.add("co_authors", "invalid_author", e.to_string());
+                        return Ok(errors.into_response(request_id));
+                    }
+                }
+            }
+
             // Record analytics event
-            let _ = state.db.clickhouse().record_content_action(
+            state.analytics_writer.record_content_action(
+                content_event_id(content_id, "create", 0),
                 *auth_context.tenant_id.as_uuid(),
                 content_id,
                 "create",
                 Some(author_id),
                 serde_json::json!({ "title": content_request.title }),
-            ).await;
+            );
 
             info!(
                 content_id = %content_id,
@@ -216,7 +307,7 @@ async fn create_content(
             );
 
             let response = ApiResponse::success(content, request_id);
-            Ok((StatusCode::CREATED, Json(response)))
+            Ok((StatusCode::CREATED, Json(response)).into_response())
         }
         Err(e) => {
             error!("Failed to create content: {}", e);
@@ -234,10 +325,11 @@ async fn get_content(
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
     let request_id = Uuid::new_v4();
-    let tenant_id = auth_context.tenant_id;
+    state.authorizer.require_context_permission(&auth_context, "content", "read").await?;
+    let tenant_id = auth_context.tenant_id.clone();
 
     // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -258,6 +350,17 @@ async fn get_content(
                 }
             };
 
+            let service = ContentService::new(state.db.postgres().clone());
+            let authors = match service.list_authors(&tenant_id, content.id).await {
+                Ok(authors) => authors,
+                Err(e) => {
+                    error!("Failed to load content authors: {}", e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            };
+
+            let access = field_access(&state, &auth_context).await?;
+            let content = visible_content(access, &ContentWithAuthors { content, authors })?;
             let response = ApiResponse::success(content, request_id);
             Ok(Json(response))
         }
@@ -278,10 +381,11 @@ async fn delete_content(
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
     let request_id = Uuid::new_v4();
+    state.authorizer.require_context_permission(&auth_context, "content", "delete").await?;
     let tenant_id = auth_context.tenant_id;
 
     // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -303,13 +407,14 @@ async fn delete_content(
             };
 
             // Record view analytics
-            let _ = state.db.clickhouse().record_content_action(
+            state.analytics_writer.record_content_action(
+                Uuid::new_v4(),
                 *tenant_id.as_uuid(),
                 content_id,
                 "view",
                 Some(auth_context.user_id), // Use actual user ID from JWT
                 serde_json::json!({}),
-            ).await;
+            );
 
             let response = ApiResponse::success(content, request_id);
             Ok(Json(response))
@@ -322,28 +427,30 @@ async fn delete_content(
     }
 }
 
-/// Update content
+/// Update content; fields left out are kept and `null` clears the body
 async fn update_content(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(content_id): Path<Uuid>,
     Json(update_request): Json<UpdateContentRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
+) -> Result<Response, StatusCode> {
     let request_id = Uuid::new_v4();
 
-    // Verify editor/admin authorization for content updates
+    // Needs content:update (editors and admins, or a custom role granting it)
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
     
-    if !matches!(auth_context.user_role, UserRole::Editor | UserRole::Admin) {
-        return Err(StatusCode::FORBIDDEN);
+    state.authorizer.require_context_permission(&auth_context, "content", "update").await?;
+
+    if let Err(errors) = update_request.validate() {
+        return Ok(errors.into_response(request_id));
     }
     
-    let tenant_id = auth_context.tenant_id;
+    let tenant_id = auth_context.tenant_id.clone();
     let now = chrono::Utc::now();
 
     // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -356,14 +463,24 @@ async fn update_content(
         SET title = COALESCE($3, title),
             slug = COALESCE($4, slug),
             body = COALESCE($5, body),
+            tags = COALESCE($7, tags),
+            -- An approval covers the reviewed text only
+            review_decision = CASE WHEN review_decision = 'approved' THEN NULL ELSE review_decision END,
             updated_at = $6
         WHERE id = $1 AND tenant_id = $2
         RETURNING *
         "#;
 
-    let title_ref = update_request.title.as_deref();
-    let slug_ref = update_request.slug.as_deref();
-    let body_ref = update_request.body.as_deref();
+    let tags = update_request.tags();
+    let (title_ref, slug_ref, body_ref) = update_request.column_values().map_err(|reason| {
+        warn!(content_id = %content_id, reason, "Rejected content update");
+        StatusCode::BAD_REQUEST
+    })?;
+    let sanitized = match body_ref {
+        Some(body) => Some(sanitized_body(&state, &auth_context, body).await?),
+        None => None,
+    };
+    let body_ref = sanitized.as_deref();
     
     let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![
         &content_id,
@@ -372,6 +489,7 @@ async fn update_content(
         &slug_ref,
         &body_ref,
         &now,
+        &tags,
     ];
 
     match client.query_opt(query, &params).await {
@@ -384,18 +502,30 @@ async fn update_content(
                 }
             };
 
-            // Record analytics event
-            let _ = state.db.clickhouse().record_content_action(
+            // Record analytics event, under the revision the update saved so replay
+            // does not record it again
+            let revision = match (&title_ref, &body_ref) {
+                (None, None) => None,
+                _ => ContentRevisionService::new(state.db.postgres().clone())
+                    .latest_revision(&tenant_id, content_id)
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!(content_id = %content_id, "Failed to load latest revision: {:#}", e);
+                        None
+                    }),
+            };
+            state.analytics_writer.record_content_action(
+                revision.map_or_else(Uuid::new_v4, |revision| content_event_id(content_id, "update", revision.into())),
                 *tenant_id.as_uuid(),
                 content_id,
                 "update",
                 Some(auth_context.user_id), // Use actual user ID from JWT
                 serde_json::json!({}),
-            ).await;
+            );
 
             info!(content_id = %content_id, "Content updated");
             let response = ApiResponse::success(content, request_id);
-            Ok(Json(response))
+            Ok(Json(response).into_response())
         }
         Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(e) => {
@@ -405,6 +535,78 @@ async fn update_content(
     }
 }
 
+/// Publish many content items at once
+async fn bulk_publish_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<BulkPublishRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    bulk_set_content_published(state, headers, request, true).await
+}
+
+/// Return many content items to draft at once
+async fn bulk_unpublish_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<BulkPublishRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if request.all_drafts {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    bulk_set_content_published(state, headers, request, false).await
+}
+
+async fn bulk_set_content_published(
+    state: AppState,
+    headers: HeaderMap,
+    request: BulkPublishRequest,
+    publish: bool,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+
+    // Same content:publish requirement as publishing a single item
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "publish").await?;
+    let tenant_id = auth_context.tenant_id;
+
+    let publishing = &state.config.publishing;
+    if let Err(reason) = request.validate(publishing.bulk_max_items) {
+        info!("Rejected bulk content publish request: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let strict = request.strict.unwrap_or(publishing.bulk_strict);
+
+    let content_service = ContentService::new(state.db.postgres().clone());
+    match content_service.bulk_set_published(&tenant_id, &request, publish, strict).await {
+        Ok(report) => {
+            let action = if publish { "publish" } else { "unpublish" };
+            let changed = report
+                .items
+                .iter()
+                .filter(|item| matches!(item.status, BulkItemStatus::Published | BulkItemStatus::Unpublished));
+            for item in changed {
+                state.analytics_writer.record_content_action(
+                    Uuid::new_v4(),
+                    *tenant_id.as_uuid(),
+                    item.id,
+                    action,
+                    Some(auth_context.user_id),
+                    serde_json::json!({ "bulk": true }),
+                );
+            }
+
+            info!(tenant_id = %tenant_id, committed = report.committed, "Bulk content {}", action);
+            let response = ApiResponse::success(report, request_id);
+            Ok(Json(response))
+        }
+        Err(e) => {
+            error!("Failed to bulk publish content: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 /// Publish content
 async fn publish_content(
     State(state): State<AppState>,
@@ -413,19 +615,17 @@ async fn publish_content(
 ) -> Result<impl IntoResponse, StatusCode> {
     let request_id = Uuid::new_v4();
 
-    // Verify editor/admin authorization for content publishing
+    // Needs content:publish (editors and admins, or a custom role granting it)
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
     
-    if !matches!(auth_context.user_role, UserRole::Editor | UserRole::Admin) {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    state.authorizer.require_context_permission(&auth_context, "content", "publish").await?;
     
     let tenant_id = auth_context.tenant_id;
     let now = chrono::Utc::now();
 
     // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -433,10 +633,11 @@ async fn publish_content(
         }
     };
 
+    // Only approved content is published directly; anything else goes through review
     let query = r#"
         UPDATE content 
-        SET status = $3, published_at = $4, updated_at = $5
-        WHERE id = $1 AND tenant_id = $2
+        SET status = $3, published_at = $4, scheduled_publish_at = NULL, updated_at = $5
+        WHERE id = $1 AND tenant_id = $2 AND (review_decision = 'approved' OR status = $3)
         RETURNING *
         "#;
 
@@ -461,19 +662,30 @@ async fn publish_content(
             };
 
             // Record analytics event
-            let _ = state.db.clickhouse().record_content_action(
+            state.analytics_writer.record_content_action(
+                content_event_id(content_id, "publish", now.timestamp_millis()),
                 *tenant_id.as_uuid(),
                 content_id,
                 "publish",
                 Some(auth_context.user_id), // Use actual user ID from JWT
                 serde_json::json!({}),
-            ).await;
+            );
 
             info!(content_id = %content_id, "Content published");
             let response = ApiResponse::success(content, request_id);
             Ok(Json(response))
         }
-        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Ok(None) => {
+            let exists = client
+                .query_opt("SELECT 1 FROM content WHERE id = $1 AND tenant_id = $2", &[&content_id, tenant_id.as_uuid()])
+                .await
+                .map_err(|e| {
+                    error!("Failed to look up content: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .is_some();
+            Err(if exists { StatusCode::CONFLICT } else { StatusCode::NOT_FOUND })
+        }
         Err(e) => {
             error!("Failed to publish content: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -481,6 +693,137 @@ async fn publish_content(
     }
 }
 
+/// Schedule content for publishing. `publish_at` without an offset is wall-clock time
+/// in the user's timezone (falling back to the tenant's); it is stored in UTC and
+/// returned in both forms.
+async fn schedule_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+    Json(request): Json<ScheduleRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "publish").await?;
+    let request_id = Uuid::new_v4();
+
+    let tz = user_timezone(&state, &auth_context.tenant_id, &auth_context.user_id).await?;
+    let publish_at = parse_schedule_input(&request.publish_at, tz).map_err(|e| {
+        warn!(content_id = %content_id, "Rejected schedule: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    if publish_at <= chrono::Utc::now() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    set_schedule(state, auth_context.tenant_id, content_id, Some(publish_at), tz, request_id).await
+}
+
+/// Cancel a scheduled publish
+async fn unschedule_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "publish").await?;
+    let request_id = Uuid::new_v4();
+
+    let tz = user_timezone(&state, &auth_context.tenant_id, &auth_context.user_id).await?;
+    set_schedule(state, auth_context.tenant_id, content_id, None, tz, request_id).await
+}
+
+async fn user_timezone(state: &AppState, tenant_id: &TenantId, user_id: &Uuid) -> Result<Tz, StatusCode> {
+    let client = tenant_client(state.db.postgres(), tenant_id).await.map_err(|e| {
+        error!("Failed to get database connection: {:#}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    load_user_timezone(&client, tenant_id.as_uuid(), user_id).await.map_err(|e| {
+        error!("Failed to load user timezone: {:#}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+async fn set_schedule(
+    state: AppState,
+    tenant_id: TenantId,
+    content_id: Uuid,
+    publish_at: Option<chrono::DateTime<chrono::Utc>>,
+    tz: Tz,
+    request_id: Uuid,
+) -> Result<Json<ApiResponse<ScheduledContent>>, StatusCode> {
+    let service = ContentService::new(state.db.postgres().clone());
+    match service.schedule_publish(&tenant_id, content_id, publish_at).await {
+        Ok(content) => {
+            info!(content_id = %content_id, publish_at = ?publish_at, "Content schedule updated");
+            let scheduled = ScheduledContent {
+                scheduled_publish_at_local: content.scheduled_publish_at.map(|at| to_local(at, tz)),
+                timezone: tz.name().to_string(),
+                content,
+            };
+            Ok(Json(ApiResponse::success(scheduled, request_id)))
+        }
+        Err(ContentReviewError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(ContentReviewError::AlreadyPublished) => Err(StatusCode::CONFLICT),
+        Err(e) => {
+            error!("Failed to schedule content: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Unpublish content automatically at `unpublish_at`, read like a schedule's
+/// `publish_at`. An expiry already passed unpublishes it straight away.
+async fn set_content_expiry(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+    Json(request): Json<ExpiryRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "publish").await?;
+    let request_id = Uuid::new_v4();
+
+    let tz = user_timezone(&state, &auth_context.tenant_id, &auth_context.user_id).await?;
+    let unpublish_at = parse_schedule_input(&request.unpublish_at, tz).map_err(|e| {
+        warn!(content_id = %content_id, "Rejected expiry: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    set_expiry(state, auth_context.tenant_id, content_id, Some(unpublish_at), request_id).await
+}
+
+/// Keep content published indefinitely again
+async fn clear_content_expiry(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "publish").await?;
+    set_expiry(state, auth_context.tenant_id, content_id, None, Uuid::new_v4()).await
+}
+
+async fn set_expiry(
+    state: AppState,
+    tenant_id: TenantId,
+    content_id: Uuid,
+    unpublish_at: Option<chrono::DateTime<chrono::Utc>>,
+    request_id: Uuid,
+) -> Result<Json<ApiResponse<WindowState>>, StatusCode> {
+    let service = PublishScheduleService::new(state.db.postgres().clone());
+    match service.set_content_expiry(&tenant_id, content_id, unpublish_at, chrono::Utc::now()).await {
+        Ok(window) => {
+            info!(content_id = %content_id, unpublish_at = ?unpublish_at, "Content expiry updated");
+            Ok(Json(ApiResponse::success(window, request_id)))
+        }
+        Err(PublishScheduleError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(PublishScheduleError::InvalidWindow) => Err(StatusCode::UNPROCESSABLE_ENTITY),
+        Err(e) => {
+            error!("Failed to set content expiry: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 /// Archive content
 async fn archive_content(
     State(state): State<AppState>,
@@ -489,19 +832,17 @@ async fn archive_content(
 ) -> Result<impl IntoResponse, StatusCode> {
     let request_id = Uuid::new_v4();
 
-    // Verify editor/admin authorization for content archiving
+    // Archiving is an update
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
     
-    if !matches!(auth_context.user_role, UserRole::Editor | UserRole::Admin) {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    state.authorizer.require_context_permission(&auth_context, "content", "update").await?;
     
     let tenant_id = auth_context.tenant_id;
     let now = chrono::Utc::now();
 
     // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -546,6 +887,410 @@ async fn archive_content(
     }
 }
 
+/// Submit a draft for review
+async fn submit_for_review(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    review_content(state, headers, content_id, ReviewAction::Submit, None).await
+}
+
+/// Approve content pending review, publishing it
+async fn approve_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+    body: Option<Json<ReviewRequest>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let comment = body.and_then(|Json(request)| request.comment);
+    review_content(state, headers, content_id, ReviewAction::Approve, comment).await
+}
+
+/// Send content pending review back to draft with the reviewer's comments
+async fn reject_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+    Json(request): Json<ReviewRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    review_content(state, headers, content_id, ReviewAction::Reject, request.comment).await
+}
+
+async fn review_content(
+    state: AppState,
+    headers: HeaderMap,
+    content_id: Uuid,
+    action: ReviewAction,
+    comment: Option<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", action.permission()).await?;
+    let request_id = Uuid::new_v4();
+
+    let service = ContentService::new(state.db.postgres().clone());
+    match service.review_content(&auth_context.tenant_id, content_id, auth_context.user_id, action, comment).await {
+        Ok(content) => {
+            let event = match action {
+                ReviewAction::Submit => "submit_for_review",
+                ReviewAction::Approve => "approve",
+                ReviewAction::Reject => "reject",
+            };
+            state.analytics_writer.record_content_action(
+                Uuid::new_v4(),
+                *auth_context.tenant_id.as_uuid(),
+                content_id,
+                event,
+                Some(auth_context.user_id),
+                serde_json::json!({}),
+            );
+
+            info!(content_id = %content_id, action = event, "Content review updated");
+            let content = visible_content(field_access(&state, &auth_context).await?, &content)?;
+            Ok(Json(ApiResponse::success(content, request_id)))
+        }
+        Err(ContentReviewError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(ContentReviewError::CommentRequired) => Err(StatusCode::BAD_REQUEST),
+        Err(
+            ContentReviewError::InvalidTransition { .. }
+            | ContentReviewError::NotApproved
+            | ContentReviewError::AlreadyPublished,
+        ) => Err(StatusCode::CONFLICT),
+        Err(ContentReviewError::Database(e)) => {
+            error!("Failed to review content: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// List the other-locale variants of a piece of content
+async fn list_translations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "read").await?;
+    let request_id = Uuid::new_v4();
+
+    let service = ContentService::new(state.db.postgres().clone());
+    match service.get_translations(&auth_context.tenant_id, content_id).await {
+        Ok(translations) => {
+            let translations = visible_content(field_access(&state, &auth_context).await?, &translations)?;
+            Ok(Json(ApiResponse::success(translations, request_id)))
+        }
+        Err(e) => {
+            error!("Failed to list content translations: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Published posts related to a piece of content by shared tags and title
+async fn list_related_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+    Query(query): Query<RelatedContentQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "read").await?;
+    let request_id = Uuid::new_v4();
+    let limit = query.limit.unwrap_or(DEFAULT_RELATED_LIMIT).min(MAX_RELATED_LIMIT);
+
+    let service = ContentService::new(state.db.postgres().clone());
+    match service.related(&auth_context.tenant_id, content_id, limit).await {
+        Ok(Some(related)) => {
+            let related = visible_content(field_access(&state, &auth_context).await?, &related)?;
+            Ok(Json(ApiResponse::success(related, request_id)))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to find related content: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Create a translation of existing content
+async fn create_translation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+    Json(request): Json<CreateTranslationRequest>,
+) -> Result<Response, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "write").await?;
+    let request_id = Uuid::new_v4();
+    if let Err(errors) = request.validate() {
+        return Ok(errors.into_response(request_id));
+    }
+    let body = sanitized_body(&state, &auth_context, &request.body).await?;
+
+    let service = ContentService::new(state.db.postgres().clone());
+    match service
+        .create_translation(
+            &auth_context.tenant_id,
+            &UserId::from_uuid(auth_context.user_id),
+            content_id,
+            request.title,
+            request.slug,
+            body,
+            &request.locale,
+        )
+        .await
+    {
+        Ok(Some(content)) => {
+            info!(content_id = %content.id, locale = %content.locale, "Content translation created");
+            Ok((StatusCode::CREATED, Json(ApiResponse::success(content, request_id))).into_response())
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(ContentTranslationError::LocaleExists(_)) => Err(StatusCode::CONFLICT),
+        Err(e) => {
+            error!("Failed to create content translation: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// A content item's byline, primary author first
+async fn list_authors(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "read").await?;
+    let request_id = Uuid::new_v4();
+
+    let service = ContentService::new(state.db.postgres().clone());
+    match service.list_authors(&auth_context.tenant_id, content_id).await {
+        Ok(authors) if authors.is_empty() => Err(StatusCode::NOT_FOUND),
+        Ok(authors) => {
+            let authors = visible_authors(&state, &auth_context, &authors).await?;
+            Ok(Json(ApiResponse::success(authors, request_id)))
+        }
+        Err(e) => {
+            error!("Failed to list content authors: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Add a co-author, or move an existing one to a new byline position
+async fn add_author(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+    Json(request): Json<AddAuthorRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "write").await?;
+    let request_id = Uuid::new_v4();
+
+    let service = ContentService::new(state.db.postgres().clone());
+    match service.add_author(&auth_context.tenant_id, content_id, request.user_id, request.position).await {
+        Ok(authors) => {
+            info!(content_id = %content_id, user_id = %request.user_id, "Content co-author added");
+            let authors = visible_authors(&state, &auth_context, &authors).await?;
+            Ok(Json(ApiResponse::success(authors, request_id)))
+        }
+        Err(e) => Err(author_error_status(e)),
+    }
+}
+
+/// Remove a co-author from the byline
+async fn remove_author(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((content_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "write").await?;
+    let request_id = Uuid::new_v4();
+
+    let service = ContentService::new(state.db.postgres().clone());
+    match service.remove_author(&auth_context.tenant_id, content_id, user_id).await {
+        Ok(authors) => {
+            info!(content_id = %content_id, user_id = %user_id, "Content co-author removed");
+            let authors = visible_authors(&state, &auth_context, &authors).await?;
+            Ok(Json(ApiResponse::success(authors, request_id)))
+        }
+        Err(e) => Err(author_error_status(e)),
+    }
+}
+
+fn author_error_status(error: ContentAuthorError) -> StatusCode {
+    match error {
+        ContentAuthorError::ContentNotFound | ContentAuthorError::UserNotFound => StatusCode::NOT_FOUND,
+        // The primary author is changed through author_id, not the co-author list
+        ContentAuthorError::PrimaryAuthor => StatusCode::CONFLICT,
+        ContentAuthorError::Database(e) => {
+            error!("Failed to update content authors: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Review comments on a piece of content
+async fn list_comments(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+    Query(params): Query<ListCommentsQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "read").await?;
+    let request_id = Uuid::new_v4();
+
+    let service = ContentCommentService::new(state.db.postgres().clone());
+    match service.list_comments(&auth_context.tenant_id, content_id, params.include_resolved.unwrap_or(false)).await {
+        Ok(comments) => Ok(Json(ApiResponse::success(comments, request_id))),
+        Err(e) => Err(comment_error_status(e)),
+    }
+}
+
+/// Comment on content, optionally anchored to a text selection or replying to a thread
+async fn create_comment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+    Json(request): Json<NewComment>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "comment").await?;
+    let request_id = Uuid::new_v4();
+
+    let service = ContentCommentService::new(state.db.postgres().clone());
+    match service.create_comment(&auth_context.tenant_id, content_id, auth_context.user_id, request).await {
+        Ok(comment) => {
+            info!(content_id = %content_id, comment_id = %comment.id, "Content comment added");
+            Ok((StatusCode::CREATED, Json(ApiResponse::success(comment, request_id))))
+        }
+        Err(e) => Err(comment_error_status(e)),
+    }
+}
+
+/// Edit one's own comment
+async fn update_comment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((content_id, comment_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<UpdateCommentRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "comment").await?;
+    let request_id = Uuid::new_v4();
+
+    let service = ContentCommentService::new(state.db.postgres().clone());
+    match service
+        .update_comment(&auth_context.tenant_id, content_id, comment_id, auth_context.user_id, request.body)
+        .await
+    {
+        Ok(comment) => Ok(Json(ApiResponse::success(comment, request_id))),
+        Err(e) => Err(comment_error_status(e)),
+    }
+}
+
+/// Delete a comment; admins may delete anyone's
+async fn delete_comment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((content_id, comment_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "comment").await?;
+    let moderator = matches!(auth_context.user_role, UserRole::Admin);
+
+    let service = ContentCommentService::new(state.db.postgres().clone());
+    match service
+        .delete_comment(&auth_context.tenant_id, content_id, comment_id, auth_context.user_id, moderator)
+        .await
+    {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => Err(comment_error_status(e)),
+    }
+}
+
+/// Resolve (or with `{ "resolved": false }` reopen) the thread a comment belongs to
+async fn resolve_comment_thread(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((content_id, comment_id)): Path<(Uuid, Uuid)>,
+    body: Option<Json<ResolveCommentRequest>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "comment").await?;
+    let request_id = Uuid::new_v4();
+    let resolved = body.is_none_or(|Json(request)| request.resolved);
+
+    let service = ContentCommentService::new(state.db.postgres().clone());
+    match service
+        .set_thread_resolved(&auth_context.tenant_id, content_id, comment_id, auth_context.user_id, resolved)
+        .await
+    {
+        Ok(thread) => Ok(Json(ApiResponse::success(thread, request_id))),
+        Err(e) => Err(comment_error_status(e)),
+    }
+}
+
+/// Saved versions of content's title and body, newest first
+async fn list_revisions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "read").await?;
+    let request_id = Uuid::new_v4();
+
+    let service = ContentRevisionService::new(state.db.postgres().clone());
+    match service.list_revisions(&auth_context.tenant_id, content_id).await {
+        Ok(revisions) => Ok(Json(ApiResponse::success(revisions, request_id))),
+        Err(e) => {
+            error!("Failed to list content revisions: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Word-level diff of the title and body between two revisions
+async fn diff_revisions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(content_id): Path<Uuid>,
+    Query(query): Query<RevisionDiffQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "content", "read").await?;
+    let request_id = Uuid::new_v4();
+
+    let service = ContentRevisionService::new(state.db.postgres().clone());
+    match service.diff_revisions(&auth_context.tenant_id, content_id, query.from, query.to).await {
+        Ok(Some(diff)) => Ok(Json(ApiResponse::success(diff, request_id))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to diff content revisions: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn comment_error_status(error: ContentCommentError) -> StatusCode {
+    match error {
+        ContentCommentError::ContentNotFound | ContentCommentError::NotFound => StatusCode::NOT_FOUND,
+        ContentCommentError::Invalid(reason) => {
+            info!("Rejected content comment: {}", reason);
+            StatusCode::BAD_REQUEST
+        }
+        ContentCommentError::NotAuthor => StatusCode::FORBIDDEN,
+        ContentCommentError::Database(e) => {
+            error!("Failed to handle content comment: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
 /// Get content analytics
 async fn get_content_analytics(
     State(state): State<AppState>,
@@ -602,10 +1347,13 @@ async fn get_content_analytics(
 // Request/Response schemas
 #[derive(Debug, Deserialize)]
 struct ListContentQuery {
-    #[serde(flatten)]
-    pagination: PaginationParams,
+    // Not a flattened `PaginationParams`: flattening hands query values over as
+    // strings, so the numbers would fail to parse
+    page: Option<u32>,
+    limit: Option<u32>,
     status: Option<ContentStatus>,
     author_id: Option<Uuid>,
+    locale: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -614,13 +1362,163 @@ struct CreateContentRequest {
     slug: String,
     body: String,
     status: Option<ContentStatus>,
+    locale: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Co-authors after the creator, in byline order
+    #[serde(default)]
+    co_authors: Vec<Uuid>,
+}
+
+impl Validate for CreateContentRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required("title", &self.title);
+        errors.max_length("title", &self.title, MAX_TITLE_LEN);
+        errors.slug("slug", &self.slug);
+        errors.into_result()
+    }
+}
+
+/// Most related posts one request returns
+const MAX_RELATED_LIMIT: usize = 20;
+
+#[derive(Debug, Deserialize)]
+struct RelatedContentQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateTranslationRequest {
+    locale: String,
+    title: String,
+    slug: String,
+    body: String,
+}
+
+impl Validate for CreateTranslationRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required("locale", &self.locale);
+        errors.required("title", &self.title);
+        errors.max_length("title", &self.title, MAX_TITLE_LEN);
+        errors.slug("slug", &self.slug);
+        errors.into_result()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListCommentsQuery {
+    include_resolved: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RevisionDiffQuery {
+    from: i32,
+    to: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateCommentRequest {
+    body: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResolveCommentRequest {
+    resolved: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScheduleRequest {
+    /// RFC 3339, or wall-clock time such as `2026-03-10T09:00` in the user's timezone
+    publish_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExpiryRequest {
+    /// RFC 3339, or wall-clock time in the user's timezone
+    unpublish_at: String,
+}
+
+/// Content with its schedule shown in the user's timezone as well as UTC
+#[derive(Debug, Serialize)]
+struct ScheduledContent {
+    #[serde(flatten)]
+    content: Content,
+    timezone: String,
+    scheduled_publish_at_local: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewRequest {
+    /// Required when rejecting
+    comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddAuthorRequest {
+    user_id: Uuid,
+    /// Byline position, 1 being directly after the primary author; omitted appends
+    position: Option<usize>,
 }
 
+/// Fields left out are kept. `null` clears the body or the tags; title and slug are
+/// required and cannot be cleared.
 #[derive(Debug, Deserialize)]
 struct UpdateContentRequest {
-    title: Option<String>,
-    slug: Option<String>,
-    body: Option<String>,
+    #[serde(default)]
+    title: Patch<String>,
+    #[serde(default)]
+    slug: Patch<String>,
+    #[serde(default)]
+    body: Patch<String>,
+    #[serde(default)]
+    tags: Patch<Vec<String>>,
+}
+
+impl Validate for UpdateContentRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        match &self.title {
+            Patch::Clear => errors.add("title", "required", "title cannot be cleared"),
+            Patch::Set(title) => {
+                errors.required("title", title);
+                errors.max_length("title", title, MAX_TITLE_LEN);
+            }
+            Patch::Keep => {}
+        }
+        match &self.slug {
+            Patch::Clear => errors.add("slug", "required", "slug cannot be cleared"),
+            Patch::Set(slug) => errors.slug("slug", slug),
+            Patch::Keep => {}
+        }
+        errors.into_result()
+    }
+}
+
+/// Title, slug and body columns of an update, `None` keeping the stored value
+type ColumnValues<'a> = (Option<&'a str>, Option<&'a str>, Option<&'a str>);
+
+impl UpdateContentRequest {
+    /// New title, slug and body, `None` keeping the stored one. A cleared body is stored empty.
+    fn column_values(&self) -> Result<ColumnValues<'_>, &'static str> {
+        if self.title == Patch::Clear {
+            return Err("title cannot be cleared");
+        }
+        if self.slug == Patch::Clear {
+            return Err("slug cannot be cleared");
+        }
+        Ok((
+            self.title.value().map(String::as_str),
+            self.slug.value().map(String::as_str),
+            self.body.update().map(|body| body.map_or("", String::as_str)),
+        ))
+    }
+
+    /// New tags, normalized, `None` keeping the stored ones
+    fn tags(&self) -> Option<Vec<String>> {
+        self.tags.update().map(|tags| tags.map_or_else(Vec::new, |tags| normalize_tags(tags)))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -641,3 +1539,384 @@ struct ContentAnalyticsData {
     count: u64,
     unique_users: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_harness::TestApp, types::UserRole};
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_bodies_sanitized_on_create_and_update_unless_trusted() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let body = "<p>Chapter one</p><script>alert(1)</script>";
+
+        let created = app.post("/api/content", &editor, json!({ "title": "Notes", "slug": "notes", "body": body })).await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let stored = created.body["data"]["body"].as_str().expect("content body");
+        assert!(stored.contains("<p>Chapter one</p>") && !stored.contains("<script"), "{}", stored);
+
+        let uri = format!("/api/content/{}", created.body["data"]["id"].as_str().unwrap());
+        let updated = app.send(app.request(Method::PUT, &uri, &editor, Some(json!({ "body": body })))).await;
+        assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+        assert!(!updated.body["data"]["body"].as_str().unwrap().contains("<script"));
+
+        // Authors the tenant trusts store bodies as written
+        app.admin_pool
+            .get()
+            .await
+            .unwrap()
+            .execute(
+                "UPDATE tenants SET settings = jsonb_build_object('content_sanitization', jsonb_build_object('trusted_users', jsonb_build_array($1::text))) WHERE id = $2",
+                &[&editor.id.to_string(), app.tenant_a.id.as_uuid()],
+            )
+            .await
+            .expect("Failed to trust editor");
+        let trusted = app.send(app.request(Method::PUT, &uri, &editor, Some(json!({ "body": body })))).await;
+        assert_eq!(trusted.status, StatusCode::OK, "{}", trusted.body);
+        assert_eq!(trusted.body["data"]["body"], body);
+    }
+
+    #[tokio::test]
+    async fn test_translation_created_and_listed_through_routes() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let created = app.post("/api/content", &editor, json!({ "title": "Hello", "slug": "hello", "body": "Hi" })).await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let uri = format!("/api/content/{}/translations", created.body["data"]["id"].as_str().unwrap());
+
+        let translation = json!({ "locale": "de-DE", "title": "Hallo", "slug": "hallo", "body": "Servus" });
+        let translated = app.post(&uri, &editor, translation.clone()).await;
+        assert_eq!(translated.status, StatusCode::CREATED, "{}", translated.body);
+        assert_eq!(translated.body["data"]["translation_group_id"], created.body["data"]["translation_group_id"]);
+        assert_eq!(app.post(&uri, &editor, translation).await.status, StatusCode::CONFLICT);
+
+        let listed = app.get(&uri, &editor).await;
+        assert_eq!(listed.status, StatusCode::OK, "{}", listed.body);
+        let locales: Vec<&str> = listed.body["data"].as_array().unwrap().iter().map(|c| c["locale"].as_str().unwrap()).collect();
+        assert_eq!(locales, vec!["de-DE"]);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_publish_and_unpublish_content_through_routes() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let reviewer = &app.tenant_a.admin;
+        let mut ids = Vec::new();
+        for slug in ["first", "second", "unreviewed"] {
+            let created = app.post("/api/content", &editor, json!({ "title": slug, "slug": slug, "body": "" })).await;
+            assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+            ids.push(created.body["data"]["id"].as_str().unwrap().to_string());
+        }
+        // Approving publishes the first two
+        for id in &ids[..2] {
+            assert_eq!(app.post(&format!("/api/content/{}/submit", id), &editor, json!({})).await.status, StatusCode::OK);
+            assert_eq!(app.post(&format!("/api/content/{}/approve", id), reviewer, json!({})).await.status, StatusCode::OK);
+        }
+        let statuses = |report: &serde_json::Value| -> Vec<String> {
+            report["data"]["items"].as_array().unwrap().iter().map(|i| i["status"].as_str().unwrap().to_string()).collect()
+        };
+
+        assert_eq!(app.post("/api/content/unpublish", &editor, json!({ "all_drafts": true })).await.status, StatusCode::BAD_REQUEST);
+        let unpublished = app.post("/api/content/unpublish", &editor, json!({ "ids": &ids[..2] })).await;
+        assert_eq!(unpublished.status, StatusCode::OK, "{}", unpublished.body);
+        assert_eq!(statuses(&unpublished.body), vec!["unpublished", "unpublished"]);
+        assert_eq!(app.get(&format!("/api/content/{}", ids[0]), &editor).await.body["data"]["status"], "Draft");
+
+        // Strict: the unreviewed draft holds the approved ones back
+        let strict = app.post("/api/content/publish", &editor, json!({ "all_drafts": true, "strict": true })).await;
+        assert_eq!(strict.status, StatusCode::OK, "{}", strict.body);
+        assert_eq!(strict.body["data"]["committed"], false);
+        assert_eq!(statuses(&strict.body), vec!["rolled_back", "rolled_back", "failed"]);
+
+        let published = app.post("/api/content/publish", &editor, json!({ "all_drafts": true, "strict": false })).await;
+        assert_eq!(published.body["data"]["committed"], true, "{}", published.body);
+        assert_eq!(statuses(&published.body), vec!["published", "published", "failed"]);
+        assert_eq!(app.get(&format!("/api/content/{}", ids[1]), &editor).await.body["data"]["status"], "Published");
+        assert_eq!(app.get(&format!("/api/content/{}", ids[2]), &editor).await.body["data"]["status"], "Draft");
+
+        // Publishing needs content:publish
+        let viewer = app.add_user(&app.tenant_a.id, UserRole::Viewer).await;
+        let response = app.post("/api/content/publish", &viewer, json!({ "ids": [ids[1]] })).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_review_workflow_through_routes() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let reviewer = &app.tenant_a.admin;
+        let created = app.post("/api/content", &editor, json!({ "title": "Draft", "slug": "draft", "body": "" })).await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let uri = |action: &str| format!("/api/content/{}/{}", created.body["data"]["id"].as_str().unwrap(), action);
+
+        // Unreviewed content is not published directly
+        assert_eq!(app.post(&uri("publish"), &editor, json!({})).await.status, StatusCode::CONFLICT);
+        assert_eq!(app.post(&uri("approve"), reviewer, json!({})).await.status, StatusCode::CONFLICT);
+
+        let submitted = app.post(&uri("submit"), &editor, json!({})).await;
+        assert_eq!(submitted.status, StatusCode::OK, "{}", submitted.body);
+        assert_eq!(submitted.body["data"]["status"], "PendingReview");
+        let viewer = app.add_user(&app.tenant_a.id, UserRole::Viewer).await;
+        assert_eq!(app.post(&uri("approve"), &viewer, json!({})).await.status, StatusCode::FORBIDDEN);
+        assert_eq!(app.post(&uri("reject"), reviewer, json!({ "comment": "  " })).await.status, StatusCode::BAD_REQUEST);
+
+        let rejected = app.post(&uri("reject"), reviewer, json!({ "comment": "Needs a source" })).await;
+        assert_eq!(rejected.status, StatusCode::OK, "{}", rejected.body);
+        assert_eq!(rejected.body["data"]["status"], "Draft");
+        assert_eq!(rejected.body["data"]["review_comment"], "Needs a source");
+
+        app.post(&uri("submit"), &editor, json!({})).await;
+        let approved = app.post(&uri("approve"), reviewer, json!({})).await;
+        assert_eq!(approved.status, StatusCode::OK, "{}", approved.body);
+        assert_eq!(approved.body["data"]["status"], "Published");
+        assert_eq!(approved.body["data"]["review_decision"], "approved");
+
+        // Published content cannot be scheduled again
+        let schedule = app.send(app.request(Method::PUT, &uri("schedule"), &editor, Some(json!({ "publish_at": "2099-01-01T09:00:00Z" })))).await;
+        assert_eq!(schedule.status, StatusCode::CONFLICT);
+        let missing = format!("/api/content/{}/approve", uuid::Uuid::new_v4());
+        assert_eq!(app.post(&missing, reviewer, json!({})).await.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_co_authors_managed_through_routes() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let (first, second) = (
+            app.add_user(&app.tenant_a.id, UserRole::Editor).await,
+            app.add_user(&app.tenant_a.id, UserRole::Viewer).await,
+        );
+        let created = app.post("/api/content", &editor, json!({ "title": "Joint", "slug": "joint", "body": "" })).await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let uri = format!("/api/content/{}/authors", created.body["data"]["id"].as_str().unwrap());
+        let byline = |authors: &serde_json::Value| -> Vec<String> {
+            authors["data"].as_array().unwrap().iter().map(|a| a["user_id"].as_str().unwrap().to_string()).collect()
+        };
+
+        assert_eq!(app.post(&uri, &editor, json!({ "user_id": first.id })).await.status, StatusCode::OK);
+        let added = app.post(&uri, &editor, json!({ "user_id": second.id, "position": 1 })).await;
+        assert_eq!(added.status, StatusCode::OK, "{}", added.body);
+        assert_eq!(byline(&added.body), vec![editor.id.to_string(), second.id.to_string(), first.id.to_string()]);
+        assert_eq!(added.body["data"][0]["is_primary"], true);
+
+        // Users of another tenant and the primary author are not co-authors
+        let outsider = app.post(&uri, &editor, json!({ "user_id": app.tenant_b.admin.id })).await;
+        assert_eq!(outsider.status, StatusCode::NOT_FOUND);
+        let primary = app.send(app.request(Method::DELETE, &format!("{}/{}", uri, editor.id), &editor, None)).await;
+        assert_eq!(primary.status, StatusCode::CONFLICT);
+
+        let removed = app.send(app.request(Method::DELETE, &format!("{}/{}", uri, second.id), &editor, None)).await;
+        assert_eq!(removed.status, StatusCode::OK, "{}", removed.body);
+        assert_eq!(byline(&app.get(&uri, &editor).await.body), vec![editor.id.to_string(), first.id.to_string()]);
+        let missing = format!("/api/content/{}/authors", uuid::Uuid::new_v4());
+        assert_eq!(app.get(&missing, &editor).await.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_co_authors_written_with_new_content_or_not_at_all() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let co_author = app.add_user(&app.tenant_a.id, UserRole::Viewer).await;
+        let created = app
+            .post("/api/content", &editor, json!({ "title": "Joint", "slug": "joint", "body": "", "co_authors": [co_author.id] }))
+            .await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let uri = format!("/api/content/{}/authors", created.body["data"]["id"].as_str().unwrap());
+        let authors = app.get(&uri, &editor).await.body;
+        assert_eq!(authors["data"][1]["user_id"], co_author.id.to_string());
+
+        // A co-author from another tenant fails the request and leaves no content behind
+        let rejected = app
+            .post("/api/content", &editor, json!({ "title": "Lost", "slug": "lost", "body": "", "co_authors": [app.tenant_b.admin.id] }))
+            .await;
+        assert_eq!(rejected.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", rejected.body);
+        let client = app.admin_pool.get().await.unwrap();
+        let count: i64 = client.query_one("SELECT COUNT(*) FROM content WHERE slug = 'lost'", &[]).await.unwrap().get(0);
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_comment_threads_through_routes() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let reviewer = &app.tenant_a.admin;
+        let created = app.post("/api/content", &editor, json!({ "title": "Essay", "slug": "essay", "body": "Hello world" })).await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let uri = format!("/api/content/{}/comments", created.body["data"]["id"].as_str().unwrap());
+
+        let bad_anchor = json!({ "body": "Where?", "anchor": { "start": 0, "end": 100 } });
+        assert_eq!(app.post(&uri, reviewer, bad_anchor).await.status, StatusCode::BAD_REQUEST);
+        let comment = app.post(&uri, reviewer, json!({ "body": "Too casual", "anchor": { "start": 0, "end": 5, "quote": "Hello" } })).await;
+        assert_eq!(comment.status, StatusCode::CREATED, "{}", comment.body);
+        let comment_uri = format!("{}/{}", uri, comment.body["data"]["id"].as_str().unwrap());
+        let reply = app.post(&uri, &editor, json!({ "body": "Fixed", "parent_id": comment.body["data"]["id"] })).await;
+        assert_eq!(reply.status, StatusCode::CREATED, "{}", reply.body);
+
+        // Only the author edits a comment
+        let edit = Some(json!({ "body": "Too casual for the intro" }));
+        assert_eq!(app.send(app.request(Method::PUT, &comment_uri, &editor, edit.clone())).await.status, StatusCode::FORBIDDEN);
+        let edited = app.send(app.request(Method::PUT, &comment_uri, reviewer, edit)).await;
+        assert_eq!(edited.status, StatusCode::OK, "{}", edited.body);
+        assert_eq!(edited.body["data"]["body"], "Too casual for the intro");
+
+        // Resolving takes the whole thread out of the default listing
+        let resolved = app.send(app.request(Method::POST, &format!("{}/resolve", comment_uri), &editor, None)).await;
+        assert_eq!(resolved.status, StatusCode::OK, "{}", resolved.body);
+        assert_eq!(app.get(&uri, &editor).await.body["data"], json!([]));
+        assert_eq!(app.get(&format!("{}?include_resolved=true", uri), &editor).await.body["data"].as_array().unwrap().len(), 2);
+        let reopened = app.post(&format!("{}/resolve", comment_uri), &editor, json!({ "resolved": false })).await;
+        assert_eq!(reopened.status, StatusCode::OK);
+        assert_eq!(app.get(&uri, &editor).await.body["data"].as_array().unwrap().len(), 2);
+
+        // Admins moderate anyone's comments
+        let reply_uri = format!("{}/{}", uri, reply.body["data"]["id"].as_str().unwrap());
+        assert_eq!(app.send(app.request(Method::DELETE, &reply_uri, reviewer, None)).await.status, StatusCode::NO_CONTENT);
+        assert_eq!(app.send(app.request(Method::DELETE, &reply_uri, reviewer, None)).await.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_viewer_responses_omit_restricted_fields() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let viewer = app.add_user(&app.tenant_a.id, UserRole::Viewer).await;
+        let created = app.post("/api/content", &editor, json!({ "title": "Memo", "slug": "memo", "body": "" })).await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let uri = format!("/api/content/{}", created.body["data"]["id"].as_str().unwrap());
+
+        let shown = app.get(&uri, &viewer).await;
+        let listed = app.get("/api/content", &viewer).await;
+        let byline = app.get(&format!("{}/authors", uri), &viewer).await;
+        assert_eq!(shown.status, StatusCode::OK, "{}", shown.body);
+        for item in [&shown.body["data"], &listed.body["data"]["items"][0]] {
+            assert_eq!(item["title"], "Memo");
+            assert!(item.get("author_id").is_none(), "{}", item);
+            assert!(item.get("review_decision").is_none(), "{}", item);
+        }
+        assert!(shown.body["data"]["authors"][0].get("user_id").is_none());
+        assert!(byline.body["data"][0].get("user_id").is_none(), "{}", byline.body);
+
+        let full = app.get(&uri, &editor).await;
+        assert_eq!(full.body["data"]["author_id"], editor.id.to_string());
+        assert_eq!(app.get(&format!("{}/authors", uri), &editor).await.body["data"][0]["user_id"], editor.id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_filtered_list_totals_across_pages() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        for slug in ["one", "two", "three"] {
+            let created = app.post("/api/content", &editor, json!({ "title": slug, "slug": slug, "body": "" })).await;
+            assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        }
+        let other = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        app.post("/api/content", &other, json!({ "title": "Other", "slug": "other", "body": "" })).await;
+
+        let page = app.get(&format!("/api/content?author_id={}&limit=2&page=2", editor.id), &editor).await;
+        assert_eq!(page.status, StatusCode::OK, "{}", page.body);
+        assert_eq!(page.body["data"]["total"], 3);
+        assert_eq!(page.body["data"]["total_pages"], 2);
+        assert_eq!(page.body["data"]["items"].as_array().unwrap().len(), 1);
+
+        // A page past the end still reports the filtered total
+        let past_end = app.get(&format!("/api/content?author_id={}&limit=2&page=5", editor.id), &editor).await;
+        assert_eq!(past_end.body["data"]["total"], 3);
+        assert_eq!(past_end.body["data"]["items"], json!([]));
+        let drafts = app.get("/api/content?status=Draft&limit=10", &editor).await;
+        assert_eq!(drafts.body["data"]["total"], 4, "{}", drafts.body);
+    }
+
+    #[tokio::test]
+    async fn test_related_posts_through_routes() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let mut ids = Vec::new();
+        for (slug, tags) in [
+            ("sourdough-basics", json!(["baking", "bread"])),
+            ("rye-loaves", json!(["baking", "bread"])),
+            ("cookie-tips", json!(["baking"])),
+            ("tax-season", json!(["finance"])),
+            ("bread-draft", json!(["baking", "bread"])),
+        ] {
+            let created = app.post("/api/content", &editor, json!({ "title": slug, "slug": slug, "body": "", "tags": tags })).await;
+            assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+            ids.push(uuid::Uuid::parse_str(created.body["data"]["id"].as_str().unwrap()).unwrap());
+        }
+        app.admin_pool
+            .get()
+            .await
+            .unwrap()
+            .execute("UPDATE content SET status = 'Published' WHERE id = ANY($1)", &[&ids[..4].to_vec()])
+            .await
+            .expect("Failed to publish posts");
+
+        let related = app.get(&format!("/api/content/{}/related", ids[0]), &editor).await;
+        assert_eq!(related.status, StatusCode::OK, "{}", related.body);
+        let slugs: Vec<&str> = related.body["data"].as_array().unwrap().iter().map(|r| r["slug"].as_str().unwrap()).collect();
+        // More shared tags rank higher; the post itself, unrelated posts and drafts are left out
+        assert_eq!(slugs, vec!["rye-loaves", "cookie-tips"]);
+
+        let limited = app.get(&format!("/api/content/{}/related?limit=1", ids[0]), &editor).await;
+        assert_eq!(limited.body["data"].as_array().unwrap().len(), 1);
+        let missing = format!("/api/content/{}/related", uuid::Uuid::new_v4());
+        assert_eq!(app.get(&missing, &editor).await.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_revisions_listed_and_diffed_through_routes() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let created = app.post("/api/content", &editor, json!({ "title": "Log", "slug": "log", "body": "<p>Day one was fine</p>" })).await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let uri = format!("/api/content/{}", created.body["data"]["id"].as_str().unwrap());
+        let edit = Some(json!({ "body": "<p>Day one was very fine</p>" }));
+        assert_eq!(app.send(app.request(Method::PUT, &uri, &editor, edit)).await.status, StatusCode::OK);
+
+        let revisions = app.get(&format!("{}/revisions", uri), &editor).await;
+        assert_eq!(revisions.status, StatusCode::OK, "{}", revisions.body);
+        let numbers: Vec<i64> = revisions.body["data"].as_array().unwrap().iter().map(|r| r["revision"].as_i64().unwrap()).collect();
+        assert_eq!(numbers, vec![2, 1]);
+
+        let diff = app.get(&format!("{}/revisions/diff?from=1&to=2", uri), &editor).await;
+        assert_eq!(diff.status, StatusCode::OK, "{}", diff.body);
+        assert_eq!(diff.body["data"]["unchanged"], false);
+        assert_eq!(diff.body["data"]["body"]["words_added"], 1);
+        // Diff text comes back escaped
+        assert!(diff.body["data"]["body"]["html"].as_str().unwrap().contains("&lt;p&gt;"), "{}", diff.body);
+        let missing = app.get(&format!("{}/revisions/diff?from=1&to=3", uri), &editor).await;
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_patch_keeps_clears_and_sets_content_fields() {
+        let Some(app) = TestApp::start().await else { return };
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let created = app
+            .post("/api/content", &editor, json!({ "title": "Notes", "slug": "notes", "body": "Text", "tags": ["draft"] }))
+            .await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let uri = format!("/api/content/{}", created.body["data"]["id"].as_str().unwrap());
+        let patch = |body: serde_json::Value| app.send(app.request(Method::PATCH, &uri, &editor, Some(body)));
+
+        // Absent: kept
+        let renamed = patch(json!({ "title": "Field notes" })).await;
+        assert_eq!(renamed.status, StatusCode::OK, "{}", renamed.body);
+        assert_eq!(renamed.body["data"]["title"], "Field notes");
+        assert_eq!(renamed.body["data"]["body"], "Text");
+        assert_eq!(renamed.body["data"]["tags"], json!(["draft"]));
+
+        // Present with a value: set
+        let tagged = patch(json!({ "tags": ["Travel", "notes"] })).await;
+        assert_eq!(tagged.body["data"]["tags"], json!(["travel", "notes"]));
+
+        // Present as null: cleared
+        let cleared = patch(json!({ "body": null, "tags": null })).await;
+        assert_eq!(cleared.status, StatusCode::OK, "{}", cleared.body);
+        assert_eq!(cleared.body["data"]["body"], "");
+        assert_eq!(cleared.body["data"]["tags"], json!([]));
+        assert_eq!(cleared.body["data"]["title"], "Field notes");
+
+        assert_eq!(patch(json!({ "title": null })).await.status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{
+    auth::jwt_helpers::{extract_auth_context_with_role, AuthContext},
+    services::email_jobs::{EmailJobError, EmailJobService, RetryWindow},
+    services::email_sender::{DnsTxtResolver, EmailSenderError, EmailSenderService, SetEmailSenderRequest},
+    types::{ApiResponse, PaginatedResponse, PaginationParams, UserRole},
+    AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Serialize;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Create email job administration routes
+pub fn create_routes() -> Router<AppState> {
+    Router::new()
+        .route("/jobs/failed", get(list_failed_jobs))
+        .route("/jobs/retry", post(retry_jobs_in_window))
+        .route("/jobs/:job_id/retry", post(retry_job))
+        .route("/sender", get(get_sender).put(set_sender).delete(delete_sender))
+        .route("/sender/verify", post(verify_sender))
+}
+
+#[derive(Debug, Serialize)]
+struct BulkRetryResponse {
+    requeued: u64,
+}
+
+/// Failed jobs carry recipients and bodies and the sender speaks for the whole tenant,
+/// so only the tenant's admins may manage either
+fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<AuthContext, StatusCode> {
+    let auth_context = extract_auth_context_with_role(headers, &state.jwt_manager)?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(auth_context)
+}
+
+fn email_job_error_status(e: EmailJobError) -> StatusCode {
+    match &e {
+        EmailJobError::NotFound => StatusCode::NOT_FOUND,
+        EmailJobError::NotFailed | EmailJobError::Permanent(_) => {
+            warn!(error = %e, "Refused to retry email job");
+            StatusCode::CONFLICT
+        }
+        EmailJobError::InvalidWindow(_) => StatusCode::BAD_REQUEST,
+        EmailJobError::Database(_) => {
+            error!(error = %e, "Failed to manage email jobs");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+fn email_sender_error_status(e: EmailSenderError) -> StatusCode {
+    match &e {
+        EmailSenderError::Invalid(_) => StatusCode::BAD_REQUEST,
+        EmailSenderError::NotConfigured => StatusCode::NOT_FOUND,
+        EmailSenderError::Unverified(_) => StatusCode::CONFLICT,
+        EmailSenderError::Database(_) => {
+            error!(error = %e, "Failed to manage email sender");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Dead letters: failed jobs with their last error, most recent first
+async fn list_failed_jobs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = require_admin(&state, &headers)?;
+
+    let limit: u32 = pagination.limit.unwrap_or(20).clamp(1, 100);
+    let page = pagination.page.unwrap_or(1).max(1);
+    let service = EmailJobService::new(state.db.postgres().clone());
+    let (jobs, total) = service
+        .list_failed(&auth_context.tenant_id, limit as i64, ((page - 1) * limit) as i64)
+        .await
+        .map_err(email_job_error_status)?;
+
+    let total = total as u64;
+    let paginated = PaginatedResponse {
+        items: jobs,
+        total,
+        page,
+        limit,
+        total_pages: total.div_ceil(limit as u64) as u32,
+    };
+    Ok(Json(ApiResponse::success(paginated, request_id)))
+}
+
+/// Requeue one failed job with its attempts reset; `409` if it failed permanently
+async fn retry_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(job_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = require_admin(&state, &headers)?;
+
+    let service = EmailJobService::new(state.db.postgres().clone());
+    let job = service.retry_job(&auth_context.tenant_id, job_id).await.map_err(email_job_error_status)?;
+
+    info!(job_id = %job_id, "Email job requeued");
+    Ok(Json(ApiResponse::success(job, request_id)))
+}
+
+/// Requeue every retryable job that failed within `from`..`to`
+async fn retry_jobs_in_window(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(window): Json<RetryWindow>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = require_admin(&state, &headers)?;
+
+    let service = EmailJobService::new(state.db.postgres().clone());
+    let requeued = service.retry_window(&auth_context.tenant_id, window).await.map_err(email_job_error_status)?;
+
+    info!(from = %window.from, to = %window.to, requeued, "Failed email jobs requeued");
+    Ok(Json(ApiResponse::success(BulkRetryResponse { requeued }, request_id)))
+}
+
+/// The tenant's own sender with its verification state and the token to publish
+async fn get_sender(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = require_admin(&state, &headers)?;
+
+    let service = EmailSenderService::new(state.db.postgres().clone(), state.config.email.clone());
+    let sender = service
+        .get_sender(&auth_context.tenant_id)
+        .await
+        .map_err(email_sender_error_status)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(sender, request_id)))
+}
+
+/// Set the tenant's sender; mail keeps coming from the platform until it is verified
+async fn set_sender(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SetEmailSenderRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = require_admin(&state, &headers)?;
+
+    let service = EmailSenderService::new(state.db.postgres().clone(), state.config.email.clone());
+    let sender = service
+        .set_sender(&auth_context.tenant_id, request)
+        .await
+        .map_err(email_sender_error_status)?;
+
+    info!(tenant_id = %auth_context.tenant_id, domain = %sender.domain, "Email sender set");
+    Ok(Json(ApiResponse::success(sender, request_id)))
+}
+
+/// Go back to sending from the platform address
+async fn delete_sender(State(state): State<AppState>, headers: HeaderMap) -> Result<StatusCode, StatusCode> {
+    let auth_context = require_admin(&state, &headers)?;
+
+    let service = EmailSenderService::new(state.db.postgres().clone(), state.config.email.clone());
+    if service.delete_sender(&auth_context.tenant_id).await.map_err(email_sender_error_status)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Check the sender domain's DNS records; what is missing is in `last_error`
+async fn verify_sender(State(state): State<AppState>, headers: HeaderMap) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = require_admin(&state, &headers)?;
+
+    let resolver = DnsTxtResolver::from_system_conf().map_err(|e| {
+        error!(error = %e, "Failed to create DNS resolver");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let service = EmailSenderService::new(state.db.postgres().clone(), state.config.email.clone());
+    let sender = service
+        .verify_sender(&auth_context.tenant_id, &resolver)
+        .await
+        .map_err(email_sender_error_status)?;
+    Ok(Json(ApiResponse::success(sender, request_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_harness::TestApp;
+    use axum::http::StatusCode;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_failed_jobs_stay_with_their_tenant() {
+        let Some(app) = TestApp::start().await else { return };
+        let job_id = Uuid::new_v4();
+        let client = app.admin_pool.get().await.unwrap();
+        client
+            .execute(
+                "INSERT INTO email_jobs (id, booking_id, email_type, recipient_email, scheduled_for, status,
+                                         retry_count, last_error, failure_kind, failed_at, tenant_id)
+                 VALUES ($1, $2, 'booking_confirmation', 'reader@example.com', NOW(), 'failed',
+                         3, 'provider timed out', 'retryable', NOW(), $3)",
+                &[&job_id, &Uuid::new_v4(), app.tenant_a.id.as_uuid()],
+            )
+            .await
+            .unwrap();
+        let retry_uri = format!("/api/email/jobs/{}/retry", job_id);
+        let window = json!({ "from": Utc::now() - Duration::hours(1), "to": Utc::now() + Duration::hours(1) });
+
+        let other = &app.tenant_b.admin;
+        let listed = app.get("/api/email/jobs/failed", other).await;
+        assert_eq!(listed.status, StatusCode::OK, "{}", listed.body);
+        assert_eq!(listed.body["data"]["total"], 0);
+        assert_eq!(app.post(&retry_uri, other, json!({})).await.status, StatusCode::NOT_FOUND);
+        assert_eq!(app.post("/api/email/jobs/retry", other, window.clone()).await.body["data"]["requeued"], 0);
+
+        let owner = &app.tenant_a.admin;
+        let listed = app.get("/api/email/jobs/failed", owner).await;
+        assert_eq!(listed.body["data"]["items"][0]["id"], job_id.to_string(), "{}", listed.body);
+        let retried = app.post(&retry_uri, owner, json!({})).await;
+        assert_eq!(retried.status, StatusCode::OK, "{}", retried.body);
+        assert_eq!(retried.body["data"]["status"], "pending");
+    }
+}
@@ -1,13 +1,82 @@
+pub mod analytics;
+pub mod assets;
 pub mod auth;
+pub mod billing;
 pub mod connected_websites;
+pub mod content;
+pub mod email;
+pub mod pages;
+pub mod redirects;
+pub mod roles;
+pub mod sites;
+pub mod templates;
+pub mod tenants;
+pub mod translations;
+pub mod users;
+pub mod webhooks;
 // pub mod consultations; // TODO: Fix calendly service dependencies
 
-use axum::Router;
-use crate::AppState;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use tracing::{error, info};
+use uuid::Uuid;
+use crate::{
+    services::plans::{PlanCheck, PlanError, PlanService},
+    types::{ApiResponse, TenantId},
+    AppState,
+};
 
 pub fn create_routes() -> Router<AppState> {
     Router::new()
         .nest("/auth", auth::create_routes())
+        .nest("/analytics", analytics::create_routes())
+        .nest("/assets", assets::create_routes())
+        .nest("/billing", billing::create_routes())
         .nest("/connected-websites", connected_websites::connected_websites_routes())
+        .nest("/content", content::create_routes())
+        .nest("/email", email::create_routes())
+        .merge(pages::pages_router())
+        .nest("/redirects", redirects::create_routes())
+        .nest("/roles", roles::create_routes())
+        .nest("/sites", sites::sites_router())
+        .nest("/templates", templates::templates_router())
+        .nest("/tenants", tenants::create_routes())
+        .nest("/translations", translations::create_routes())
+        .nest("/users", users::create_routes())
+        .nest("/webhooks", webhooks::create_routes())
         // .nest("/consultations", consultations::consultation_routes()) // TODO: Fix calendly service
 }
+
+/// Shared plan gate for create endpoints: `Err` carries the response to return,
+/// a 402 naming the limit that was hit
+pub(crate) async fn enforce_plan_limit(
+    state: &AppState,
+    tenant_id: &TenantId,
+    check: PlanCheck,
+    request_id: Uuid,
+) -> Result<(), Response> {
+    let plans = PlanService::new(state.db.postgres().clone(), state.config.plans.clone());
+    match plans
+        .enforce_plan_limit(tenant_id, check, state.config.analytics.retention_days)
+        .await
+    {
+        Ok(()) => Ok(()),
+        Err(PlanError::LimitExceeded(exceeded)) => {
+            info!(tenant_id = %tenant_id, plan = %exceeded.plan, limit = ?exceeded.limit, "Request refused by plan limit");
+            let response = ApiResponse {
+                success: false,
+                data: Some(exceeded),
+                error: Some("Plan limit reached; upgrade to continue".to_string()),
+                request_id,
+            };
+            Err((StatusCode::PAYMENT_REQUIRED, Json(response)).into_response())
+        }
+        Err(e) => {
+            error!(tenant_id = %tenant_id, error = %e, "Failed to check plan limits");
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
@@ -1,19 +1,42 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{ConnectInfo, Path, Query, State},
     http::{HeaderMap, StatusCode},
-    response::IntoResponse,
-    routing::{delete, get, post, put},
-    Json, Router,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Form, Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use std::net::SocketAddr;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::{
-    auth::jwt_helpers::extract_auth_context,
-    services::page::{CreatePageRequest, PageService, PublishPageRequest, UpdatePageRequest},
-    services::pages::{PageService as PuckPageService, SavePageDraftRequest, SwitchTemplateRequest},
-    types::ApiResponse,
+    auth::jwt_helpers::{extract_auth_context_with_role, AuthContext},
+    auth::login_lockout::{LoginLockout, PostgresLoginAttemptStore},
+    middleware::client_ip::client_ip,
+    routes::enforce_plan_limit,
+    services::page::{CreatePageRequest, Page, PageService, PublishPageRequest, UpdatePageRequest},
+    services::page_custom_code::{validate_custom_code, with_csp_nonce, CustomCodeError},
+    services::pages::{PageService as PuckPageService, PageServiceError, SavePageDraftRequest, SwitchTemplateRequest},
+    services::draft_patch::{DraftPatchRequest, DraftPatchResponse},
+    services::analytics::analytics_consent_granted,
+    services::content::ContentService,
+    services::content_fields::ContentFieldAccess,
+    services::locale,
+    services::bulk_publish::BulkPublishRequest,
+    services::plans::PlanCheck,
+    services::publish_schedule::{PageWindow, PublishScheduleError, PublishScheduleService},
+    services::public_url::{resolve_base_url, RequestOrigin},
+    services::cdn::page_urls,
+    services::redirect::RedirectService,
+    services::request_validation::Validate,
+    services::site::{Site, SiteService},
+    services::site_access::{login_page, AccessDecision},
+    services::sitemap::site_sitemap,
+    services::site_error_pages::{error_page, ErrorPageKind, SiteErrorPages},
+    services::site_validation::{render_for_publish, site_context},
+    types::TenantId,
+    types::{AnalyticsEvent, ApiResponse},
     AppState,
 };
 
@@ -50,6 +73,10 @@ pub struct PageDetailResponse {
     pub meta_description: Option<String>,
     pub meta_keywords: Option<String>,
     pub puck_data: serde_json::Value,
+    /// The stored composition was unreadable and `puck_data` is an empty one in its place
+    pub composition_error: bool,
+    pub custom_head: Option<String>,
+    pub custom_body: Option<String>,
     pub is_published: bool,
     pub published_at: Option<chrono::DateTime<chrono::Utc>>,
     pub sort_order: i32,
@@ -57,6 +84,19 @@ pub struct PageDetailResponse {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Query parameters of a public page
+#[derive(Debug, Deserialize)]
+pub struct PublicPageQuery {
+    /// Unlocks a token-protected site (see `SiteAccess`), for share links
+    pub access_token: Option<String>,
+}
+
+/// Login form of a protected site
+#[derive(Debug, Deserialize)]
+pub struct UnlockSiteForm {
+    pub password: String,
+}
+
 /// Page reorder request
 #[derive(Debug, Deserialize)]
 pub struct ReorderPagesRequest {
@@ -73,14 +113,92 @@ pub fn pages_router() -> Router<AppState> {
     Router::new()
         .route("/sites/:site_id/pages", get(list_pages).post(create_page))
         .route("/sites/:site_id/pages/reorder", post(reorder_pages))
-        .route("/pages/:page_id", get(get_page).put(update_page).delete(delete_page))
+        .route("/sites/:site_id/pages/publish", post(bulk_publish_pages))
+        .route("/sites/:site_id/pages/unpublish", post(bulk_unpublish_pages))
+        .route("/pages/:page_id", get(get_page).put(update_page).patch(update_page).delete(delete_page))
         .route("/pages/:page_id/publish", post(publish_page))
         .route("/pages/:page_id/unpublish", post(unpublish_page))
+        .route("/pages/:page_id/window", put(set_page_window))
         // New Puck/MiniJinja endpoints
-        .route("/pages/:page_id/draft", put(save_page_draft))
+        .route("/pages/:page_id/draft", put(save_page_draft).patch(autosave_page_draft))
         .route("/pages/:page_id/template", put(switch_page_template))
         .route("/pages/:page_id/preview-link", post(generate_preview_link))
         .route("/preview/:token", get(render_preview_page))
+        .route("/public/:subdomain/sitemap.xml", get(render_sitemap))
+        .route("/public/:subdomain/_access", post(unlock_site))
+        .route("/public/:subdomain/posts/*path", get(render_published_post))
+        .route("/public/:subdomain/*path", get(render_published_page))
+}
+
+/// Authorize `action` on the pages of a site
+async fn authorize_site_pages(
+    state: &AppState,
+    headers: &HeaderMap,
+    action: &str,
+    site_id: &Uuid,
+) -> Result<AuthContext, StatusCode> {
+    let auth_context = extract_auth_context_with_role(headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "pages", action).await?;
+    if !auth_context.allows_site(site_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(auth_context)
+}
+
+/// Authorize `action` on a page. A token bound to one site is checked against the
+/// page's site, so it cannot reach pages of the tenant's other sites.
+async fn authorize_page(
+    state: &AppState,
+    headers: &HeaderMap,
+    action: &str,
+    page_id: Uuid,
+) -> Result<AuthContext, StatusCode> {
+    let auth_context = extract_auth_context_with_role(headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "pages", action).await?;
+    if auth_context.site_id.is_some() {
+        let page = PageService::new(state.db.postgres().clone())
+            .get_page(&auth_context.tenant_id, page_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to load page for authorization: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        if !auth_context.allows_site(&page.site_id) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+    Ok(auth_context)
+}
+
+/// Check a page's custom head and body code before saving it. Scripts are refused
+/// with 403 unless the tenant has the custom scripts capability; malformed code is a 400.
+async fn check_custom_code(
+    page_service: &PageService,
+    tenant_id: &TenantId,
+    custom_head: Option<&str>,
+    custom_body: Option<&str>,
+) -> Result<(), StatusCode> {
+    let sources: Vec<&str> = [custom_head, custom_body].into_iter().flatten().collect();
+    if sources.iter().all(|source| source.trim().is_empty()) {
+        return Ok(());
+    }
+
+    let scripts_allowed = page_service.custom_scripts_allowed(tenant_id).await.map_err(|e| {
+        error!("Failed to check custom scripts capability: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    for source in sources {
+        match validate_custom_code(source, scripts_allowed) {
+            Ok(()) => {}
+            Err(CustomCodeError::ScriptsNotAllowed) => return Err(StatusCode::FORBIDDEN),
+            Err(e) => {
+                warn!("Rejected page custom code: {}", e);
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+    }
+    Ok(())
 }
 
 /// List pages for a site
@@ -90,8 +208,7 @@ pub async fn list_pages(
     Path(site_id): Path<Uuid>,
     Query(query): Query<PageListQuery>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_site_pages(&state, &headers, "read", &site_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     let limit = query.limit.unwrap_or(50).min(100);
@@ -134,8 +251,7 @@ pub async fn get_page(
     headers: HeaderMap,
     Path(page_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_page(&state, &headers, "read", page_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     let page_service = PageService::new(state.db.postgres().clone());
@@ -150,6 +266,9 @@ pub async fn get_page(
                 meta_description: page.meta_description,
                 meta_keywords: page.meta_keywords,
                 puck_data: page.puck_data,
+                composition_error: page.composition_error,
+                custom_head: page.custom_head,
+                custom_body: page.custom_body,
                 is_published: page.is_published,
                 published_at: page.published_at,
                 sort_order: page.sort_order,
@@ -174,21 +293,20 @@ pub async fn create_page(
     headers: HeaderMap,
     Path(site_id): Path<Uuid>,
     Json(request): Json<CreatePageRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+) -> Result<Response, StatusCode> {
+    let tenant_id = authorize_site_pages(&state, &headers, "write", &site_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
-    // Validate input
-    if request.title.trim().is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    if let Err(errors) = request.validate() {
+        return Ok(errors.into_response(request_id));
     }
 
-    if request.slug.trim().is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    if let Err(response) = enforce_plan_limit(&state, &tenant_id, PlanCheck::NewPage { site_id }, request_id).await {
+        return Ok(response);
     }
 
     let page_service = PageService::new(state.db.postgres().clone());
+    check_custom_code(&page_service, &tenant_id, request.custom_head.as_deref(), request.custom_body.as_deref()).await?;
 
     match page_service.create_page(&tenant_id, site_id, request).await {
         Ok(page) => {
@@ -202,6 +320,9 @@ pub async fn create_page(
                 meta_description: page.meta_description,
                 meta_keywords: page.meta_keywords,
                 puck_data: page.puck_data,
+                composition_error: page.composition_error,
+                custom_head: page.custom_head,
+                custom_body: page.custom_body,
                 is_published: page.is_published,
                 published_at: page.published_at,
                 sort_order: page.sort_order,
@@ -210,7 +331,7 @@ pub async fn create_page(
             };
 
             let response = ApiResponse::success(response_page, request_id);
-            Ok((StatusCode::CREATED, Json(response)))
+            Ok((StatusCode::CREATED, Json(response)).into_response())
         }
         Err(e) => {
             error!("Failed to create page: {}", e);
@@ -225,21 +346,34 @@ pub async fn create_page(
     }
 }
 
-/// Update page
+/// Update page; fields left out are kept and `null` clears an optional field
 pub async fn update_page(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(page_id): Path<Uuid>,
     Json(request): Json<UpdatePageRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+) -> Result<Response, StatusCode> {
+    let tenant_id = authorize_page(&state, &headers, "update", page_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
+    if let Err(errors) = request.validate() {
+        return Ok(errors.into_response(request_id));
+    }
+
     let page_service = PageService::new(state.db.postgres().clone());
+    check_custom_code(
+        &page_service,
+        &tenant_id,
+        request.custom_head.value().map(String::as_str),
+        request.custom_body.value().map(String::as_str),
+    )
+    .await?;
 
     match page_service.update_page(&tenant_id, page_id, request).await {
         Ok(Some(page)) => {
+            // Title, slug or order may have changed
+            state.template_engine.invalidate_navigation(page.site_id);
+
             let response_page = PageDetailResponse {
                 id: page.id,
                 site_id: page.site_id,
@@ -248,6 +382,9 @@ pub async fn update_page(
                 meta_description: page.meta_description,
                 meta_keywords: page.meta_keywords,
                 puck_data: page.puck_data,
+                composition_error: page.composition_error,
+                custom_head: page.custom_head,
+                custom_body: page.custom_body,
                 is_published: page.is_published,
                 published_at: page.published_at,
                 sort_order: page.sort_order,
@@ -256,7 +393,7 @@ pub async fn update_page(
             };
 
             let response = ApiResponse::success(response_page, request_id);
-            Ok((StatusCode::OK, Json(response)))
+            Ok((StatusCode::OK, Json(response)).into_response())
         }
         Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(e) => {
@@ -272,8 +409,7 @@ pub async fn delete_page(
     headers: HeaderMap,
     Path(page_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_page(&state, &headers, "delete", page_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     let page_service = PageService::new(state.db.postgres().clone());
@@ -299,8 +435,7 @@ pub async fn publish_page(
     Path(page_id): Path<Uuid>,
     Json(request): Json<PublishPageRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_page(&state, &headers, "publish", page_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     let page_service = PageService::new(state.db.postgres().clone());
@@ -308,6 +443,7 @@ pub async fn publish_page(
     match page_service.publish_page(&tenant_id, page_id, request).await {
         Ok(Some(page)) => {
             info!("Published page {} for tenant {}", page_id, tenant_id);
+            invalidate_published_pages(&state, &tenant_id, page.site_id, std::slice::from_ref(&page.slug)).await;
 
             let response_page = PageDetailResponse {
                 id: page.id,
@@ -317,6 +453,9 @@ pub async fn publish_page(
                 meta_description: page.meta_description,
                 meta_keywords: page.meta_keywords,
                 puck_data: page.puck_data,
+                composition_error: page.composition_error,
+                custom_head: page.custom_head,
+                custom_body: page.custom_body,
                 is_published: page.is_published,
                 published_at: page.published_at,
                 sort_order: page.sort_order,
@@ -335,14 +474,74 @@ pub async fn publish_page(
     }
 }
 
+/// Publish a page at `publish_at` with `rendered_html`, unpublish it at `unpublish_at`,
+/// or both so it is live only in between. Times already passed apply at once.
+pub async fn set_page_window(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(page_id): Path<Uuid>,
+    Json(window): Json<PageWindow>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let tenant_id = authorize_page(&state, &headers, "publish", page_id).await?.tenant_id;
+    let request_id = Uuid::new_v4();
+
+    let service = PublishScheduleService::new(state.db.postgres().clone());
+    match service.set_page_window(&tenant_id, page_id, window, chrono::Utc::now()).await {
+        Ok(window) => {
+            info!(page_id = %page_id, publish_at = ?window.publish_at, unpublish_at = ?window.unpublish_at, "Page window updated");
+            for change in &window.applied {
+                if let Some(site_id) = change.site_id {
+                    invalidate_published_pages(&state, &tenant_id, site_id, &[]).await;
+                }
+            }
+            Ok((StatusCode::OK, Json(ApiResponse::success(window, request_id))))
+        }
+        Err(PublishScheduleError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(PublishScheduleError::AlreadyPublished) => Err(StatusCode::CONFLICT),
+        Err(PublishScheduleError::InvalidWindow | PublishScheduleError::RenderedHtmlRequired) => {
+            Err(StatusCode::UNPROCESSABLE_ENTITY)
+        }
+        Err(e) => {
+            error!("Failed to set page window: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Make a publish visible to the next public fetch: drop the site's cached
+/// responses and the tenant's cached templates, warm them again in the background
+/// when configured, then purge the CDN (best effort)
+async fn invalidate_published_pages(state: &AppState, tenant_id: &TenantId, site_id: Uuid, slugs: &[String]) {
+    state.publish_cache.invalidate_site(site_id);
+    state.template_engine.invalidate_tenant_templates(*tenant_id.as_uuid());
+    state.template_engine.invalidate_navigation(site_id);
+    if let Some(warmer) = &state.cache_warmer {
+        warmer.clone().spawn_warm_site(tenant_id.clone(), site_id);
+    }
+
+    if !state.cdn.is_configured() || slugs.is_empty() {
+        return;
+    }
+    match SiteService::new(state.db.postgres().clone()).get_site(tenant_id, site_id).await {
+        Ok(Some(site)) => {
+            let urls = slugs
+                .iter()
+                .flat_map(|slug| page_urls(&site.subdomain, site.custom_domain.as_deref(), slug))
+                .collect();
+            state.cdn.purge(urls).await;
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Skipping CDN purge, failed to load site {}: {}", site_id, e),
+    }
+}
+
 /// Unpublish page
 pub async fn unpublish_page(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(page_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_page(&state, &headers, "publish", page_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     let page_service = PageService::new(state.db.postgres().clone());
@@ -350,6 +549,7 @@ pub async fn unpublish_page(
     match page_service.unpublish_page(&tenant_id, page_id).await {
         Ok(Some(page)) => {
             info!("Unpublished page {} for tenant {}", page_id, tenant_id);
+            invalidate_published_pages(&state, &tenant_id, page.site_id, std::slice::from_ref(&page.slug)).await;
 
             let response_page = PageDetailResponse {
                 id: page.id,
@@ -359,6 +559,9 @@ pub async fn unpublish_page(
                 meta_description: page.meta_description,
                 meta_keywords: page.meta_keywords,
                 puck_data: page.puck_data,
+                composition_error: page.composition_error,
+                custom_head: page.custom_head,
+                custom_body: page.custom_body,
                 is_published: page.is_published,
                 published_at: page.published_at,
                 sort_order: page.sort_order,
@@ -377,6 +580,89 @@ pub async fn unpublish_page(
     }
 }
 
+/// Publish many pages of a site at once, rendering each through the template engine
+pub async fn bulk_publish_pages(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<Uuid>,
+    Json(request): Json<BulkPublishRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    bulk_set_pages_published(state, headers, site_id, request, true).await
+}
+
+/// Unpublish many pages of a site at once
+pub async fn bulk_unpublish_pages(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<Uuid>,
+    Json(request): Json<BulkPublishRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if request.all_drafts {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    bulk_set_pages_published(state, headers, site_id, request, false).await
+}
+
+async fn bulk_set_pages_published(
+    state: AppState,
+    headers: HeaderMap,
+    site_id: Uuid,
+    request: BulkPublishRequest,
+    publish: bool,
+) -> Result<impl IntoResponse, StatusCode> {
+    let tenant_id = authorize_site_pages(&state, &headers, "publish", &site_id).await?.tenant_id;
+    let request_id = Uuid::new_v4();
+
+    let publishing = &state.config.publishing;
+    if let Err(reason) = request.validate(publishing.bulk_max_items) {
+        warn!("Rejected bulk publish request: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let strict = request.strict.unwrap_or(publishing.bulk_strict);
+
+    let site_service = SiteService::new(state.db.postgres().clone());
+    let site = match site_service.get_site(&tenant_id, site_id).await {
+        Ok(Some(site)) => site,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to load site {}: {}", site_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let tenant_theme = site_service.tenant_theme(&tenant_id).await.map_err(|e| {
+        error!("Failed to load tenant theme for site {}: {}", site_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let site_context = site_context(&site, &tenant_theme);
+
+    let page_service = PageService::new(state.db.postgres().clone());
+    let template_engine = &state.template_engine;
+    let site_context = &site_context;
+    let tenant_uuid = *tenant_id.as_uuid();
+    let render = |page: Page| render_for_publish(template_engine, site_context, tenant_uuid, page);
+
+    match page_service.bulk_set_published(&tenant_id, site_id, &request, publish, strict, render).await {
+        Ok((report, slugs)) => {
+            info!(
+                site_id = %site_id,
+                tenant_id = %tenant_id,
+                changed = slugs.len(),
+                committed = report.committed,
+                "Bulk {} pages", if publish { "published" } else { "unpublished" }
+            );
+            if report.committed {
+                invalidate_published_pages(&state, &tenant_id, site_id, &slugs).await;
+            }
+            let response = ApiResponse::success(report, request_id);
+            Ok((StatusCode::OK, Json(response)))
+        }
+        Err(e) => {
+            error!("Failed to bulk publish pages: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 /// Reorder pages within a site
 pub async fn reorder_pages(
     State(state): State<AppState>,
@@ -384,8 +670,7 @@ pub async fn reorder_pages(
     Path(site_id): Path<Uuid>,
     Json(request): Json<ReorderPagesRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_site_pages(&state, &headers, "update", &site_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     let page_service = PageService::new(state.db.postgres().clone());
@@ -399,6 +684,8 @@ pub async fn reorder_pages(
     match page_service.reorder_pages(&tenant_id, site_id, page_orders).await {
         Ok(()) => {
             info!("Reordered pages for site {} tenant {}", site_id, tenant_id);
+            state.template_engine.invalidate_navigation(site_id);
+            state.publish_cache.invalidate_site(site_id);
             let response = ApiResponse::success((), request_id);
             Ok((StatusCode::OK, Json(response)))
         }
@@ -416,19 +703,13 @@ pub async fn save_page_draft(
     Path(page_id): Path<Uuid>,
     Json(request): Json<SavePageDraftRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_page(&state, &headers, "update", page_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     // Create page service with template engine
-    let template_engine = &state.template_engine;
-    let page_service = PageService::new(
-        state.db.postgres().clone(),
-        template_engine.template_cache.clone(),
-        template_engine.render_defaults.clone(),
-    );
+    let page_service = PuckPageService::new(state.db.postgres().clone(), state.template_engine.clone());
 
-    match page_service.save_draft(page_id, tenant_id, request).await {
+    match page_service.save_draft(page_id, *tenant_id.as_uuid(), request).await {
         Ok(page) => {
             info!("Saved draft for page {} tenant {}", page_id, tenant_id);
 
@@ -440,6 +721,9 @@ pub async fn save_page_draft(
                 meta_description: None, // TODO: Extract from composition
                 meta_keywords: None,
                 puck_data: serde_json::to_value(&page.draft_composition).unwrap_or_default(),
+                composition_error: page.composition_error,
+                custom_head: page.custom_head,
+                custom_body: page.custom_body,
                 is_published: page.is_published,
                 published_at: None, // TODO: Add published_at to new Page struct
                 sort_order: 0, // TODO: Add sort_order to new Page struct
@@ -461,6 +745,50 @@ pub async fn save_page_draft(
     }
 }
 
+/// Auto-save a partial draft update, merged by node id into the stored draft
+pub async fn autosave_page_draft(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(page_id): Path<Uuid>,
+    Json(request): Json<DraftPatchRequest>,
+) -> Result<Response, StatusCode> {
+    let tenant_id = authorize_page(&state, &headers, "update", page_id).await?.tenant_id;
+    let request_id = Uuid::new_v4();
+
+    let page_service = PuckPageService::new(state.db.postgres().clone(), state.template_engine.clone());
+
+    match page_service.patch_draft(page_id, *tenant_id.as_uuid(), &request).await {
+        Ok(updated_at) => {
+            let response = ApiResponse::success(DraftPatchResponse { updated_at }, request_id);
+            Ok((StatusCode::OK, Json(response)).into_response())
+        }
+        Err(PageServiceError::DraftConflict(conflict)) => {
+            warn!("Auto-save conflict on page {} nodes {:?}", page_id, conflict.node_ids);
+            let response = ApiResponse {
+                success: false,
+                data: Some(conflict),
+                error: Some("Draft was changed elsewhere; reload before saving".to_string()),
+                request_id,
+            };
+            Ok((StatusCode::CONFLICT, Json(response)).into_response())
+        }
+        Err(PageServiceError::PageNotFound(_)) => Err(StatusCode::NOT_FOUND),
+        Err(PageServiceError::MalformedComposition(_)) => {
+            let response: ApiResponse<()> = ApiResponse {
+                success: false,
+                data: None,
+                error: Some("Stored draft is malformed; save the full draft instead".to_string()),
+                request_id,
+            };
+            Ok((StatusCode::CONFLICT, Json(response)).into_response())
+        }
+        Err(e) => {
+            error!("Failed to auto-save page draft: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 /// Switch page template
 pub async fn switch_page_template(
     State(state): State<AppState>,
@@ -468,18 +796,12 @@ pub async fn switch_page_template(
     Path(page_id): Path<Uuid>,
     Json(request): Json<SwitchTemplateRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_page(&state, &headers, "update", page_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
-    let template_engine = &state.template_engine;
-    let page_service = PageService::new(
-        state.db.postgres().clone(),
-        template_engine.template_cache.clone(),
-        template_engine.render_defaults.clone(),
-    );
+    let page_service = PuckPageService::new(state.db.postgres().clone(), state.template_engine.clone());
 
-    match page_service.switch_template(page_id, tenant_id, request).await {
+    match page_service.switch_template(page_id, *tenant_id.as_uuid(), request).await {
         Ok(page) => {
             info!("Switched template for page {} tenant {}", page_id, tenant_id);
 
@@ -491,6 +813,9 @@ pub async fn switch_page_template(
                 meta_description: None,
                 meta_keywords: None,
                 puck_data: serde_json::to_value(&page.draft_composition).unwrap_or_default(),
+                composition_error: page.composition_error,
+                custom_head: page.custom_head,
+                custom_body: page.custom_body,
                 is_published: page.is_published,
                 published_at: None,
                 sort_order: 0,
@@ -506,6 +831,8 @@ pub async fn switch_page_template(
             match e {
                 crate::services::pages::PageServiceError::PageNotFound(_) => Err(StatusCode::NOT_FOUND),
                 crate::services::pages::PageServiceError::TemplateNotFound(_) => Err(StatusCode::BAD_REQUEST),
+                // The template has no usable default content to seed from
+                crate::services::pages::PageServiceError::CompositionError(_) => Err(StatusCode::BAD_REQUEST),
                 _ => Err(StatusCode::INTERNAL_SERVER_ERROR),
             }
         }
@@ -515,23 +842,39 @@ pub async fn switch_page_template(
 /// Generate preview link for page
 pub async fn generate_preview_link(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Path(page_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_page(&state, &headers, "update", page_id).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
-    let template_engine = &state.template_engine;
-    let page_service = PageService::new(
-        state.db.postgres().clone(),
-        template_engine.template_cache.clone(),
-        template_engine.render_defaults.clone(),
-    );
+    let page_service = PuckPageService::new(state.db.postgres().clone(), state.template_engine.clone());
 
-    let base_url = state.config.base_url.as_deref().unwrap_or("http://localhost:3000");
+    // Preview links open on the site's own domain when it has one
+    let page = match page_service.get_page(page_id, *tenant_id.as_uuid()).await {
+        Ok(page) => page,
+        Err(crate::services::pages::PageServiceError::PageNotFound(_)) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to load page {} for preview link: {}", page_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let custom_domain = match SiteService::new(state.db.postgres().clone())
+        .get_site(&tenant_id, page.site_id)
+        .await
+    {
+        Ok(site) => site.and_then(|site| site.custom_domain),
+        Err(e) => {
+            error!("Failed to load site {} for preview link: {}", page.site_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let public_url = &state.config.public_url;
+    let origin = RequestOrigin::from_request(&state.config.proxy, &headers, Some(peer.ip()));
+    let base_url = resolve_base_url(public_url, custom_domain.as_deref(), &origin);
 
-    match page_service.generate_preview_link(page_id, tenant_id, base_url).await {
+    match page_service.generate_preview_link(page_id, *tenant_id.as_uuid(), &base_url).await {
         Ok(preview_response) => {
             let response = ApiResponse::success(preview_response, request_id);
             Ok((StatusCode::OK, Json(response)))
@@ -551,15 +894,10 @@ pub async fn render_preview_page(
     State(state): State<AppState>,
     Path(token): Path<String>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let template_engine = &state.template_engine;
-    let page_service = PageService::new(
-        state.db.postgres().clone(),
-        template_engine.template_cache.clone(),
-        template_engine.render_defaults.clone(),
-    );
+    let page_service = PuckPageService::new(state.db.postgres().clone(), state.template_engine.clone());
 
     // Parse token to get tenant_id and page_id
-    let (tenant_id, page_id, _expires_at) = match page_service.parse_preview_token(&token) {
+    let (tenant_id, page_id, _expires_at) = match PuckPageService::parse_preview_token(&token) {
         Ok(parsed) => parsed,
         Err(e) => {
             error!("Invalid preview token: {}", e);
@@ -568,7 +906,7 @@ pub async fn render_preview_page(
     };
 
     // Render preview HTML
-    match page_service.render_preview(page_id, tenant_id, None).await {
+    match page_service.render_preview(page_id, tenant_id).await {
         Ok(html) => {
             let headers = [
                 ("content-type", "text/html; charset=utf-8"),
@@ -585,3 +923,703 @@ pub async fn render_preview_page(
         }
     }
 }
+
+/// Serve a published page to site visitors and record the view. The site's redirect
+/// rules are checked first, so a redirect takes precedence over a page at the same path.
+/// Unknown paths and failures get the site's designated 404 or 500 page.
+///
+/// Visitors who have not granted analytics consent (see `ANALYTICS_CONSENT_COOKIE`)
+/// are recorded anonymously: no IP, user agent or raw session id.
+pub async fn render_published_page(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path((subdomain, path)): Path<(String, String)>,
+    Query(query): Query<PublicPageQuery>,
+) -> Result<Response, StatusCode> {
+    let site = published_site(&state, &subdomain).await?;
+
+    // Protected sites are only served to visitors who unlocked them
+    let access = site.access_protection.check(
+        site.id,
+        headers.get("cookie").and_then(|h| h.to_str().ok()),
+        query.access_token.as_deref(),
+        state.jwt_secret.as_bytes(),
+        chrono::Utc::now(),
+    );
+    if access == AccessDecision::Denied {
+        return Ok(site_login_page(&site, false));
+    }
+
+    let slug = path.trim_matches('/').to_string();
+    let page_path = format!("/{}", slug);
+    let redirect = RedirectService::new(state.db.postgres().clone())
+        .find_redirect(&TenantId::from_uuid(site.tenant_id), site.id, &page_path)
+        .await;
+    match redirect {
+        Ok(Some(target)) => {
+            let status = StatusCode::from_u16(target.status_code).unwrap_or(StatusCode::MOVED_PERMANENTLY);
+            return Ok((status, [("location", target.location)]).into_response());
+        }
+        Ok(None) => {}
+        Err(e) => {
+            error!("Failed to load redirects of site {}: {}", site.id, e);
+            return Ok(render_error_page(&state, &site, ErrorPageKind::ServerError).await);
+        }
+    }
+
+    let page_service = PageService::new(state.db.postgres().clone());
+    let cached = state
+        .publish_cache
+        .get_or_load(site.id, &page_path, || async {
+            let page = page_service.get_page_by_slug(&TenantId::from_uuid(site.tenant_id), site.id, &slug).await?;
+            published_page_html(&state, &page_service, &site, page).await
+        })
+        .await;
+    let page = match cached {
+        Ok(Some(page)) => page,
+        Ok(None) => return Ok(render_error_page(&state, &site, ErrorPageKind::NotFound).await),
+        Err(e) => {
+            error!("Failed to load page {} of site {}: {}", slug, site.id, e);
+            return Ok(render_error_page(&state, &site, ErrorPageKind::ServerError).await);
+        }
+    };
+
+    let header = |name: &str| headers.get(name).and_then(|h| h.to_str().ok());
+    let consent_granted = analytics_consent_granted(header("cookie"));
+    let session_id = header("x-session-id").map(str::to_string);
+    let ip_address = client_ip(&state.config.proxy, &headers, Some(peer.ip())).map(|ip| ip.to_string());
+    let user_agent = header("user-agent").map(str::to_string);
+    let not_modified = page.matches(header("if-none-match"));
+    let tenant_id = TenantId::from_uuid(site.tenant_id);
+
+    // Queued on the shared writer, so recording never delays or fails the page itself
+    state.analytics_writer.record_event(AnalyticsEvent::page_view(
+        &tenant_id,
+        None,
+        &page_path,
+        session_id,
+        ip_address,
+        user_agent,
+        !consent_granted,
+    ));
+
+    // Pages with custom code get a fresh CSP nonce per response, so they are never
+    // stored or revalidated: a cached copy would carry a stale nonce
+    if let Some((body, policy)) = with_csp_nonce(&page.body) {
+        let headers = [
+            ("content-type", "text/html; charset=utf-8".to_string()),
+            ("cache-control", "no-store".to_string()),
+            ("content-security-policy", policy),
+        ];
+        return Ok((StatusCode::OK, headers, body).into_response());
+    }
+
+    // Browsers and CDNs revalidate on every request, so a publish shows up immediately.
+    // Unlocked pages of protected sites must not be shared by CDNs.
+    let cache_control = if access == AccessDecision::Granted { "private, no-cache" } else { "public, no-cache" };
+    let headers = [
+        ("content-type", "text/html; charset=utf-8".to_string()),
+        ("cache-control", cache_control.to_string()),
+        ("etag", page.etag),
+    ];
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, headers, String::new()).into_response());
+    }
+    Ok((StatusCode::OK, headers, page.body).into_response())
+}
+
+/// A published post of the site as JSON, in the locale named by the path prefix
+/// (`/posts/de-DE/my-post`), else the visitor's `Accept-Language`, else the site default
+pub async fn render_published_post(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((subdomain, path)): Path<(String, String)>,
+    Query(query): Query<PublicPageQuery>,
+) -> Result<Response, StatusCode> {
+    let site = published_site(&state, &subdomain).await?;
+
+    let access = site.access_protection.check(
+        site.id,
+        headers.get("cookie").and_then(|h| h.to_str().ok()),
+        query.access_token.as_deref(),
+        state.jwt_secret.as_bytes(),
+        chrono::Utc::now(),
+    );
+    if access == AccessDecision::Denied {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let accept_language = headers.get("accept-language").and_then(|h| h.to_str().ok());
+    let content = ContentService::new(state.db.postgres().clone())
+        .resolve_public_content(
+            &TenantId::from_uuid(site.tenant_id),
+            &path,
+            accept_language,
+            &locale::default_locale(site.locale.as_deref(), site.default_locale.as_deref()),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to resolve post {} of site {}: {}", path, site.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let body = ContentFieldAccess::Restricted.to_value(&content).map_err(|e| {
+        error!("Failed to serialize content: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let headers = [("content-language", content.locale.clone()), ("vary", "Accept-Language".to_string())];
+    Ok((headers, Json(ApiResponse::success(body, Uuid::new_v4()))).into_response())
+}
+
+/// A published site by subdomain; 404 for unknown and unpublished sites
+async fn published_site(state: &AppState, subdomain: &str) -> Result<Site, StatusCode> {
+    match SiteService::new(state.db.postgres().clone()).get_site_by_subdomain(subdomain).await {
+        Ok(Some(site)) if site.is_published => Ok(site),
+        Ok(_) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to load site {}: {}", subdomain, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// The password form served with 401 in place of a protected site's pages
+fn site_login_page(site: &Site, failed: bool) -> Response {
+    let action = format!("/api/public/{}/_access", site.subdomain);
+    let headers = [("content-type", "text/html; charset=utf-8"), ("cache-control", "no-store")];
+    (StatusCode::UNAUTHORIZED, headers, login_page(&site.name, &action, failed)).into_response()
+}
+
+/// Check the password (or access token) entered for a protected site; on success the
+/// visitor gets a cookie unlocking the site and is sent to its home page. Repeated
+/// failures lock the site's form and the IP out, like password login.
+pub async fn unlock_site(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(subdomain): Path<String>,
+    Form(form): Form<UnlockSiteForm>,
+) -> Result<Response, StatusCode> {
+    let site = published_site(&state, &subdomain).await?;
+    let home = format!("/api/public/{}/", site.subdomain);
+    if !site.access_protection.is_protected() {
+        return Ok((StatusCode::SEE_OTHER, [("location", home)]).into_response());
+    }
+
+    // The site stands in for the account, so guesses are limited per site and per IP
+    let lockout = LoginLockout::new(
+        PostgresLoginAttemptStore::new(state.db.postgres().clone()),
+        state.config.login_lockout.clone(),
+    );
+    let lockout_key = format!("site:{}", site.id);
+    let ip = client_ip(&state.config.proxy, &headers, Some(peer.ip())).map(|ip| ip.to_string());
+    let now = chrono::Utc::now();
+    let locked = lockout.is_locked(&lockout_key, ip.as_deref(), now).await.unwrap_or_else(|e| {
+        error!("Failed to check site unlock lockout: {}", e);
+        false
+    });
+    if locked {
+        warn!(ip = ?ip, "Unlock refused during lockout for site {}", site.id);
+        return Ok(site_login_page(&site, true));
+    }
+
+    // bcrypt is deliberately slow; keep it off the async workers
+    let access = site.access_protection.clone();
+    let accepted = tokio::task::spawn_blocking(move || access.accepts_secret(&form.password))
+        .await
+        .map_err(|e| {
+            error!("Failed to check password of site {}: {}", site.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if !accepted {
+        info!("Wrong password for protected site {}", site.id);
+        if let Err(e) = lockout.record_failure(&lockout_key, ip.as_deref(), now).await {
+            error!("Failed to record failed site unlock: {}", e);
+        }
+        return Ok(site_login_page(&site, true));
+    }
+    if let Err(e) = lockout.record_success(&lockout_key).await {
+        error!("Failed to reset site unlock lockout: {}", e);
+    }
+
+    let origin = RequestOrigin::from_request(&state.config.proxy, &headers, Some(peer.ip()));
+    let secure = resolve_base_url(&state.config.public_url, site.custom_domain.as_deref(), &origin).starts_with("https://");
+    let cookie = site.access_protection.access_cookie(site.id, state.jwt_secret.as_bytes(), now, secure);
+    Ok((StatusCode::SEE_OTHER, [("location", home), ("set-cookie", cookie)]).into_response())
+}
+
+/// Serve a site's `sitemap.xml`; protected sites have none
+pub async fn render_sitemap(
+    State(state): State<AppState>,
+    Path(subdomain): Path<String>,
+) -> Result<Response, StatusCode> {
+    let site = published_site(&state, &subdomain).await?;
+    let pages = PageService::new(state.db.postgres().clone())
+        .get_published_pages(&TenantId::from_uuid(site.tenant_id), site.id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load pages of site {}: {}", site.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    match site_sitemap(&site, &pages) {
+        Some(sitemap) => {
+            let headers = [("content-type", "application/xml; charset=utf-8"), ("cache-control", "public, no-cache")];
+            Ok((StatusCode::OK, headers, sitemap).into_response())
+        }
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// A page's HTML as served publicly, with its custom code and the site's minification
+/// applied; `None` unless the page is published
+async fn published_page_html(
+    state: &AppState,
+    page_service: &PageService,
+    site: &Site,
+    page: Option<Page>,
+) -> anyhow::Result<Option<String>> {
+    page_service.public_html(site, page, &state.config.templates.minify).await
+}
+
+/// The site's designated error page (see `SiteErrorPages`), or the default one when it
+/// has none or it can't be loaded. Sent with the error's status, and not stored by browsers.
+async fn render_error_page(state: &AppState, site: &Site, kind: ErrorPageKind) -> Response {
+    let designated = SiteErrorPages::from_site_settings(&site.seo_settings)
+        .ok()
+        .and_then(|error_pages| error_pages.page_for(kind));
+
+    let html = match designated {
+        Some(page_id) => {
+            let page_service = PageService::new(state.db.postgres().clone());
+            let loaded = state
+                .publish_cache
+                .get_or_load(site.id, kind.cache_path(), || async {
+                    let page = page_service.get_page(&TenantId::from_uuid(site.tenant_id), page_id).await?;
+                    published_page_html(state, &page_service, site, page).await
+                })
+                .await;
+            match loaded {
+                Ok(page) => page.map(|page| page.body),
+                Err(e) => {
+                    warn!("Failed to load error page {} of site {}: {}", page_id, site.id, e);
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    let page = error_page(kind, html);
+    let status = StatusCode::from_u16(page.status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    match with_csp_nonce(&page.body) {
+        Some((body, policy)) => {
+            let headers = [
+                ("content-type", "text/html; charset=utf-8".to_string()),
+                ("cache-control", "no-store".to_string()),
+                ("content-security-policy", policy),
+            ];
+            (status, headers, body).into_response()
+        }
+        None => {
+            let headers = [("content-type", "text/html; charset=utf-8"), ("cache-control", "no-store")];
+            (status, headers, page.body).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::AnalyticsConfig,
+        services::{
+            analytics_writer::{AnalyticsSink, AnalyticsWrite, AnalyticsWriter},
+            metrics_registry::MetricsRegistry,
+        },
+        test_harness::TestApp,
+    };
+    use axum::{
+        body::Body,
+        extract::ConnectInfo,
+        http::{Method, Request, StatusCode},
+    };
+    use serde_json::json;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    /// Analytics backend that keeps what it is sent
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        writes: Arc<Mutex<Vec<AnalyticsWrite>>>,
+    }
+
+    impl AnalyticsSink for RecordingSink {
+        async fn write(&self, write: AnalyticsWrite) -> anyhow::Result<()> {
+            self.writes.lock().unwrap().push(write);
+            Ok(())
+        }
+    }
+
+    /// A page of a new tenant A site with `draft` as its draft; returns its id and `updated_at`
+    async fn insert_page(app: &TestApp, subdomain: &str, draft: serde_json::Value) -> tokio_postgres::Row {
+        let admin = app.admin_pool.get().await.expect("Failed to get connection");
+        let tenant_id = app.tenant_a.id.as_uuid();
+        let site_id: Uuid = admin
+            .query_one(
+                "INSERT INTO sites (tenant_id, name, subdomain) VALUES ($1, $2, $2) RETURNING id",
+                &[tenant_id, &subdomain],
+            )
+            .await
+            .expect("Failed to insert site")
+            .get(0);
+        admin
+            .query_one(
+                "INSERT INTO pages (tenant_id, site_id, slug, title, draft_composition)
+                 VALUES ($1, $2, 'home', 'Home', $3) RETURNING id, updated_at",
+                &[tenant_id, &site_id, &draft],
+            )
+            .await
+            .expect("Failed to insert page")
+    }
+
+    #[tokio::test]
+    async fn test_autosave_merges_node_and_rejects_stale_base() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = app.admin_pool.get().await.expect("Failed to get connection");
+        let draft = json!({
+            "content": [{ "type": "TextBlock", "props": { "id": "text-1", "text": "Hello" } }],
+            "root": { "props": { "title": "Home" } }
+        });
+        let row = insert_page(&app, "drafts", draft).await;
+        let page_id: Uuid = row.get("id");
+        let loaded_at: chrono::DateTime<chrono::Utc> = row.get("updated_at");
+        let uri = format!("/api/pages/{}/draft", page_id);
+        let patch = |base: chrono::DateTime<chrono::Utc>, text: &str| {
+            json!({ "base_updated_at": base, "nodes": [{ "id": "text-1", "props": { "text": text } }] })
+        };
+
+        let user = &app.tenant_a.admin;
+        let saved = app.send(app.request(Method::PATCH, &uri, user, Some(patch(loaded_at, "Hello again")))).await;
+        assert_eq!(saved.status, StatusCode::OK, "{}", saved.body);
+        let stored: serde_json::Value = admin
+            .query_one("SELECT draft_composition FROM pages WHERE id = $1", &[&page_id])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(stored["content"][0]["props"]["text"], "Hello again");
+
+        // The same node, edited from the copy loaded before that save
+        let stale = app.send(app.request(Method::PATCH, &uri, user, Some(patch(loaded_at, "Hello from elsewhere")))).await;
+        assert_eq!(stale.status, StatusCode::CONFLICT, "{}", stale.body);
+        assert_eq!(stale.body["data"]["node_ids"], json!(["text-1"]));
+
+        // Another tenant's page does not exist for this one
+        let other = app.send(app.request(Method::PATCH, &uri, &app.tenant_b.admin, Some(patch(loaded_at, "Hi")))).await;
+        assert_eq!(other.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_viewers_read_pages_but_cannot_change_them() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = app.admin_pool.get().await.expect("Failed to get connection");
+        let row = insert_page(&app, "viewers", json!({ "content": [], "root": { "props": {} } })).await;
+        let page_id: Uuid = row.get("id");
+        let site_id: Uuid = admin.query_one("SELECT site_id FROM pages WHERE id = $1", &[&page_id]).await.unwrap().get(0);
+        let viewer = app.add_user(&app.tenant_a.id, crate::types::UserRole::Viewer).await;
+        let page_uri = format!("/api/pages/{}", page_id);
+
+        let read = app.get(&page_uri, &viewer).await;
+        assert_eq!(read.status, StatusCode::OK, "{}", read.body);
+        let writes = [
+            (Method::POST, format!("/api/sites/{}/pages", site_id), json!({ "slug": "about", "title": "About" })),
+            (Method::PUT, page_uri.clone(), json!({ "title": "Renamed" })),
+            (Method::DELETE, page_uri.clone(), json!({})),
+            (Method::POST, format!("{}/publish", page_uri), json!({ "rendered_html": "<p>Home</p>" })),
+            (Method::POST, format!("/api/sites/{}/pages/publish", site_id), json!({ "all_drafts": true })),
+            (Method::PATCH, format!("{}/draft", page_uri), json!({ "base_updated_at": row.get::<_, chrono::DateTime<chrono::Utc>>("updated_at"), "nodes": [] })),
+        ];
+        for (method, uri, body) in writes {
+            let response = app.send(app.request(method.clone(), &uri, &viewer, Some(body))).await;
+            assert_eq!(response.status, StatusCode::FORBIDDEN, "{} {}: {}", method, uri, response.body);
+        }
+        let title: String = admin.query_one("SELECT title FROM pages WHERE id = $1", &[&page_id]).await.unwrap().get(0);
+        assert_eq!(title, "Home");
+    }
+
+    #[tokio::test]
+    async fn test_switch_template_seeds_draft_unless_preserved() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = app.admin_pool.get().await.expect("Failed to get connection");
+        let draft = json!({
+            "content": [
+                { "type": "HeroBlock", "props": { "id": "hero-1", "title": "The Lighthouse" } },
+                { "type": "CardBlock", "props": { "id": "card-1", "title": "Old card" } }
+            ],
+            "root": { "props": { "title": "Home" } }
+        });
+        let page_id: Uuid = insert_page(&app, "switching", draft.clone()).await.get("id");
+        let default_schema = json!({
+            "content": [
+                { "type": "HeroBlock", "props": { "title": "Your Book Title" } },
+                { "type": "TextBlock", "props": { "text": "About the book" } }
+            ],
+            "root": { "props": { "layout": "centered" } }
+        });
+        // A platform template, which every tenant sees
+        let template_id: Uuid = admin
+            .query_one(
+                "INSERT INTO templates (tenant_id, name, html_source, default_schema, version)
+                 VALUES (NULL, 'launch', '<html></html>', $1, 2) RETURNING id",
+                &[&default_schema],
+            )
+            .await
+            .expect("Failed to insert template")
+            .get(0);
+        let uri = format!("/api/pages/{}/template", page_id);
+        let user = &app.tenant_a.admin;
+        let stored = || async {
+            let row = admin
+                .query_one("SELECT template_id, draft_composition FROM pages WHERE id = $1", &[&page_id])
+                .await
+                .unwrap();
+            (row.get::<_, Option<Uuid>>(0), row.get::<_, serde_json::Value>(1))
+        };
+
+        let unknown_version = json!({ "template_id": template_id, "template_version": 3 });
+        let refused = app.send(app.request(Method::PUT, &uri, user, Some(unknown_version))).await;
+        assert_eq!(refused.status, StatusCode::BAD_REQUEST);
+
+        // Preserving (the default) only changes the template
+        let preserve = json!({ "template_id": template_id, "template_version": 2 });
+        let switched = app.send(app.request(Method::PUT, &uri, user, Some(preserve))).await;
+        assert_eq!(switched.status, StatusCode::OK, "{}", switched.body);
+        let (stored_template, stored_draft) = stored().await;
+        assert_eq!(stored_template, Some(template_id));
+        assert_eq!(stored_draft["content"], draft["content"]);
+
+        let reseed = json!({ "template_id": template_id, "template_version": 2, "preserve_content": false });
+        let switched = app.send(app.request(Method::PUT, &uri, user, Some(reseed))).await;
+        assert_eq!(switched.status, StatusCode::OK, "{}", switched.body);
+        let (_, seeded) = stored().await;
+        let content = seeded["content"].as_array().expect("seeded content");
+        assert_eq!(content.len(), 2);
+        assert_eq!(content[0]["props"]["id"], "hero-1");
+        assert_eq!(content[0]["props"]["title"], "The Lighthouse");
+        assert_eq!(content[1]["type"], "TextBlock");
+        assert_eq!(content[1]["props"]["text"], "About the book");
+        assert_eq!(seeded["root"]["props"]["layout"], "centered");
+        assert_eq!(switched.body["data"]["puck_data"]["content"], seeded["content"]);
+    }
+
+    #[tokio::test]
+    async fn test_public_page_view_queued_on_analytics_writer() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = app.admin_pool.get().await.expect("Failed to get connection");
+        let page_id: Uuid = insert_page(&app, "published", json!({})).await.get("id");
+        admin
+            .batch_execute(&format!(
+                "UPDATE sites SET is_published = true WHERE subdomain = 'published';
+                 UPDATE pages SET is_published = true, published_html = '<html><body>Hi</body></html>' WHERE id = '{}'",
+                page_id
+            ))
+            .await
+            .expect("Failed to publish page");
+
+        let sink = RecordingSink::default();
+        let mut state = app.state.clone();
+        state.analytics_writer = AnalyticsWriter::spawn(sink.clone(), &AnalyticsConfig::default(), MetricsRegistry::default());
+        let router = crate::create_app(state).await.expect("Failed to build router");
+        let mut request = Request::builder()
+            .uri("/api/public/published/home")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([203, 0, 113, 7], 0))));
+        let response = router.oneshot(request).await.expect("Request failed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let writes = sink.writes.lock().unwrap();
+        let [AnalyticsWrite::Event(view)] = writes.as_slice() else {
+            panic!("expected one page view, got {:?}", writes);
+        };
+        assert_eq!(view.event_type, "page_view");
+        assert_eq!(view.tenant_id, *app.tenant_a.id.as_uuid());
+        assert_eq!(view.event_data["page_path"], "/home");
+        // No consent cookie: recorded anonymously
+        assert_eq!(view.ip_address, None);
+    }
+
+    #[tokio::test]
+    async fn test_site_unlock_locked_out_after_repeated_failures() {
+        let Some(app) = TestApp::start().await else { return };
+        insert_page(&app, "protected", json!({})).await;
+        let site_id: Uuid = app
+            .admin_pool
+            .get()
+            .await
+            .unwrap()
+            .query_one("UPDATE sites SET is_published = true WHERE subdomain = 'protected' RETURNING id", &[])
+            .await
+            .expect("Failed to publish site")
+            .get(0);
+        let access = json!({ "mode": "password", "password": "launch-day" });
+        let uri = format!("/api/sites/{}/access", site_id);
+        let response = app.send(app.request(Method::PUT, &uri, &app.tenant_a.admin, Some(access))).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        let unlock = |password: &str| {
+            let mut request = Request::builder()
+                .method(Method::POST)
+                .uri("/api/public/protected/_access")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(format!("password={}", password)))
+                .unwrap();
+            request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([203, 0, 113, 7], 0))));
+            app.router.clone().oneshot(request)
+        };
+
+        let unlocked = unlock("launch-day").await.unwrap();
+        assert_eq!(unlocked.status(), StatusCode::SEE_OTHER);
+        // Served over plain http here, so the cookie isn't limited to https
+        let cookie = unlocked.headers()["set-cookie"].to_str().unwrap();
+        assert!(cookie.contains("HttpOnly") && !cookie.contains("Secure"), "{}", cookie);
+
+        let threshold = app.state.config.login_lockout.account_threshold;
+        for _ in 0..threshold {
+            assert_eq!(unlock("guess").await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        }
+        // Locked out: even the right password gets the form again
+        assert_eq!(unlock("launch-day").await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_public_post_served_in_prefix_then_accepted_locale() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = app.admin_pool.get().await.expect("Failed to get connection");
+        insert_page(&app, "bilingual", json!({})).await;
+        admin
+            .execute(
+                "WITH posts(slug, status, locale) AS (
+                     VALUES ('hello', 'Published', 'en-US'), ('hallo', 'published', 'de-DE'), ('bonjour', 'Draft', 'fr-FR')
+                 ), grp AS (SELECT uuid_generate_v4() AS id)
+                 INSERT INTO content (tenant_id, author_id, title, slug, body, status, locale, translation_group_id)
+                 SELECT $1, $2, posts.slug, posts.slug, '', posts.status, posts.locale, grp.id FROM posts, grp",
+                &[app.tenant_a.id.as_uuid(), &app.tenant_a.admin.id],
+            )
+            .await
+            .expect("Failed to insert posts");
+        admin
+            .execute("UPDATE sites SET is_published = true WHERE subdomain = 'bilingual'", &[])
+            .await
+            .expect("Failed to publish site");
+
+        let served = |uri: &str, accept_language: Option<&str>| {
+            let mut request = Request::builder().uri(uri);
+            if let Some(accept_language) = accept_language {
+                request = request.header("accept-language", accept_language);
+            }
+            let request = request.body(Body::empty()).unwrap();
+            let router = app.router.clone();
+            async move {
+                let response = router.oneshot(request).await.expect("Request failed");
+                assert_eq!(response.status(), StatusCode::OK);
+                let language = response.headers()["content-language"].to_str().unwrap().to_string();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                assert_eq!(body["data"]["locale"], language.as_str());
+                assert!(body["data"].get("author_id").is_none());
+                (language, body["data"]["slug"].as_str().unwrap().to_string())
+            }
+        };
+
+        // The prefix wins over the header
+        let (language, slug) = served("/api/public/bilingual/posts/de-DE/hello", Some("en-US")).await;
+        assert_eq!((language.as_str(), slug.as_str()), ("de-DE", "hallo"));
+        // Without one, the first accepted language that has a published variant; the draft is skipped
+        let (language, _) = served("/api/public/bilingual/posts/hello", Some("fr-FR, de;q=0.8")).await;
+        assert_eq!(language, "de-DE");
+        // Then the site default
+        let (language, _) = served("/api/public/bilingual/posts/hallo", None).await;
+        assert_eq!(language, "en-US");
+
+        let response = app
+            .router
+            .clone()
+            .oneshot(Request::builder().uri("/api/public/bilingual/posts/missing").body(Body::empty()).unwrap())
+            .await
+            .expect("Request failed");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    async fn page_published(app: &TestApp, page_id: Uuid) -> bool {
+        let admin = app.admin_pool.get().await.expect("Failed to get connection");
+        admin
+            .query_one("SELECT is_published FROM pages WHERE id = $1", &[&page_id])
+            .await
+            .expect("Failed to load page")
+            .get(0)
+    }
+
+    #[tokio::test]
+    async fn test_bulk_publish_and_unpublish_pages_through_routes() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = app.admin_pool.get().await.expect("Failed to get connection");
+        let draft = json!({ "content": [], "root": { "props": { "title": "Home" } } });
+        let page_id: Uuid = insert_page(&app, "bulk", draft).await.get("id");
+        let site_id: Uuid = admin
+            .query_one("SELECT site_id FROM pages WHERE id = $1", &[&page_id])
+            .await
+            .expect("Failed to load page")
+            .get(0);
+        let user = &app.tenant_a.admin;
+        let missing = Uuid::new_v4();
+
+        // Not strict: the page is published and the unknown id reported as failed
+        let uri = format!("/api/sites/{}/pages/publish", site_id);
+        let published = app.post(&uri, user, json!({ "ids": [page_id, missing], "strict": false })).await;
+        assert_eq!(published.status, StatusCode::OK, "{}", published.body);
+        assert_eq!(published.body["data"]["committed"], true);
+        assert_eq!(published.body["data"]["items"][0]["status"], "published");
+        assert_eq!(published.body["data"]["items"][1]["status"], "failed");
+        assert!(page_published(&app, page_id).await);
+
+        let uri = format!("/api/sites/{}/pages/unpublish", site_id);
+        assert_eq!(app.post(&uri, user, json!({ "all_drafts": true })).await.status, StatusCode::BAD_REQUEST);
+        let unpublished = app.post(&uri, user, json!({ "ids": [page_id] })).await;
+        assert_eq!(unpublished.status, StatusCode::OK, "{}", unpublished.body);
+        assert_eq!(unpublished.body["data"]["items"][0]["status"], "unpublished");
+        assert!(!page_published(&app, page_id).await);
+
+        // Another tenant cannot reach the site
+        let other = &app.tenant_b.admin;
+        let response = app.post(&format!("/api/sites/{}/pages/publish", site_id), other, json!({ "all_drafts": true })).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_patch_keeps_clears_and_sets_page_fields() {
+        let Some(app) = TestApp::start().await else { return };
+        let page_id: Uuid = insert_page(&app, "patched", json!({})).await.get("id");
+        let uri = format!("/api/pages/{}", page_id);
+        let user = &app.tenant_a.admin;
+        let patch = |body: serde_json::Value| app.send(app.request(Method::PATCH, &uri, user, Some(body)));
+
+        let set = patch(json!({ "meta_description": "About us", "meta_keywords": "team" })).await;
+        assert_eq!(set.status, StatusCode::OK, "{}", set.body);
+        assert_eq!(set.body["data"]["meta_description"], "About us");
+
+        let kept = patch(json!({ "title": "About" })).await;
+        assert_eq!(kept.status, StatusCode::OK, "{}", kept.body);
+        assert_eq!(kept.body["data"]["title"], "About");
+        assert_eq!(kept.body["data"]["meta_description"], "About us");
+
+        let cleared = patch(json!({ "meta_description": null })).await;
+        assert_eq!(cleared.status, StatusCode::OK, "{}", cleared.body);
+        assert_eq!(cleared.body["data"]["meta_description"], serde_json::Value::Null);
+        assert_eq!(cleared.body["data"]["meta_keywords"], "team");
+    }
+}
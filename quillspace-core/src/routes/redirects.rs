@@ -0,0 +1,126 @@
+use crate::{
+    auth::jwt_helpers::{extract_auth_context_with_role, AuthContext},
+    services::redirect::{RedirectError, RedirectRuleRequest, RedirectService},
+    types::ApiResponse,
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{get, put},
+    Json, Router,
+};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Create redirect rule management routes
+pub fn create_routes() -> Router<AppState> {
+    Router::new()
+        .route("/sites/:site_id", get(list_redirects).post(create_redirect))
+        .route("/sites/:site_id/:redirect_id", put(update_redirect).delete(delete_redirect))
+}
+
+/// Authorize `action` on sites, for this site in particular
+async fn site_context(state: &AppState, headers: &HeaderMap, site_id: &Uuid, action: &str) -> Result<AuthContext, StatusCode> {
+    let auth_context = extract_auth_context_with_role(headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "sites", action).await?;
+    if !auth_context.allows_site(site_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(auth_context)
+}
+
+fn redirect_error_status(site_id: Uuid, e: RedirectError) -> StatusCode {
+    match &e {
+        RedirectError::InvalidSource(_) | RedirectError::InvalidDestination(_) | RedirectError::InvalidStatus(_) => {
+            warn!(site_id = %site_id, error = %e, "Rejected redirect rule");
+            StatusCode::BAD_REQUEST
+        }
+        RedirectError::DuplicateSource => StatusCode::CONFLICT,
+        RedirectError::SiteNotFound => StatusCode::NOT_FOUND,
+        RedirectError::Database(_) => {
+            error!(site_id = %site_id, error = %e, "Failed to manage redirects");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// List a site's redirect rules
+async fn list_redirects(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = site_context(&state, &headers, &site_id, "read").await?;
+
+    let service = RedirectService::new(state.db.postgres().clone());
+    let redirects = service
+        .list_redirects(&auth_context.tenant_id, site_id)
+        .await
+        .map_err(|e| redirect_error_status(site_id, e))?;
+    Ok(Json(ApiResponse::success(redirects, request_id)))
+}
+
+/// Add a redirect rule; `409` when the source path already has one
+async fn create_redirect(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<Uuid>,
+    Json(request): Json<RedirectRuleRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = site_context(&state, &headers, &site_id, "update").await?;
+    let rule = request.validate().map_err(|e| redirect_error_status(site_id, e))?;
+
+    let service = RedirectService::new(state.db.postgres().clone());
+    let redirect = service
+        .create_redirect(&auth_context.tenant_id, site_id, &rule)
+        .await
+        .map_err(|e| redirect_error_status(site_id, e))?;
+
+    info!(site_id = %site_id, source_path = %redirect.source_path, "Redirect created");
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(redirect, request_id))))
+}
+
+/// Replace a redirect rule
+async fn update_redirect(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((site_id, redirect_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<RedirectRuleRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = site_context(&state, &headers, &site_id, "update").await?;
+    let rule = request.validate().map_err(|e| redirect_error_status(site_id, e))?;
+
+    let service = RedirectService::new(state.db.postgres().clone());
+    match service.update_redirect(&auth_context.tenant_id, site_id, redirect_id, &rule).await {
+        Ok(Some(redirect)) => {
+            info!(site_id = %site_id, redirect_id = %redirect_id, "Redirect updated");
+            Ok(Json(ApiResponse::success(redirect, request_id)))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => Err(redirect_error_status(site_id, e)),
+    }
+}
+
+/// Delete a redirect rule
+async fn delete_redirect(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((site_id, redirect_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = site_context(&state, &headers, &site_id, "update").await?;
+
+    let service = RedirectService::new(state.db.postgres().clone());
+    match service.delete_redirect(&auth_context.tenant_id, site_id, redirect_id).await {
+        Ok(true) => {
+            info!(site_id = %site_id, redirect_id = %redirect_id, "Redirect deleted");
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => Err(redirect_error_status(site_id, e)),
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{
+    auth::jwt_helpers::{extract_auth_context_with_role, AuthContext},
+    services::custom_roles::{CreateRoleRequest, CustomRoleError, CustomRoleService},
+    types::{ApiResponse, UserRole},
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{delete, get, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Create custom role management routes
+pub fn create_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_roles).post(create_role))
+        .route("/assignments", put(assign_role))
+        .route("/:role_id", delete(delete_role))
+        .route("/:role_id/permissions", put(set_permissions))
+}
+
+/// Roles are managed by admins of the tenant
+fn admin_context(state: &AppState, headers: &HeaderMap) -> Result<AuthContext, StatusCode> {
+    let auth_context = extract_auth_context_with_role(headers, &state.jwt_manager)?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(auth_context)
+}
+
+fn role_service(state: &AppState) -> CustomRoleService {
+    CustomRoleService::new(state.db.postgres().clone(), state.authorizer.clone())
+}
+
+fn role_error_status(e: CustomRoleError) -> StatusCode {
+    match &e {
+        CustomRoleError::InvalidName(_)
+        | CustomRoleError::UnknownPermission(_)
+        | CustomRoleError::TooManyUsers(_)
+        | CustomRoleError::UsersNotFound(_) => {
+            warn!(error = %e, "Rejected custom role request");
+            StatusCode::BAD_REQUEST
+        }
+        CustomRoleError::NameTaken(_) => StatusCode::CONFLICT,
+        CustomRoleError::NotFound => StatusCode::NOT_FOUND,
+        CustomRoleError::Database(_) => {
+            error!(error = %e, "Failed to manage custom roles");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// List the tenant's custom roles
+async fn list_roles(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = admin_context(&state, &headers)?;
+
+    let roles = role_service(&state)
+        .list_roles(&auth_context.tenant_id)
+        .await
+        .map_err(role_error_status)?;
+    Ok(Json(ApiResponse::success(roles, request_id)))
+}
+
+/// Define a custom role; `409` when the tenant already has one by that name
+async fn create_role(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateRoleRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = admin_context(&state, &headers)?;
+
+    let role = role_service(&state)
+        .create_role(&auth_context.tenant_id, &request)
+        .await
+        .map_err(role_error_status)?;
+
+    info!(tenant_id = %auth_context.tenant_id, role_id = %role.id, name = %role.name, "Custom role created");
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(role, request_id))))
+}
+
+/// Replace a custom role's permissions
+async fn set_permissions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(role_id): Path<Uuid>,
+    Json(request): Json<SetPermissionsRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = admin_context(&state, &headers)?;
+
+    let role = role_service(&state)
+        .set_permissions(&auth_context.tenant_id, role_id, &request.permissions)
+        .await
+        .map_err(role_error_status)?;
+
+    info!(role_id = %role_id, permissions = ?role.permissions, "Custom role permissions updated");
+    Ok(Json(ApiResponse::success(role, request_id)))
+}
+
+/// Delete a custom role; its members fall back to their built-in roles
+async fn delete_role(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(role_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = admin_context(&state, &headers)?;
+
+    role_service(&state)
+        .delete_role(&auth_context.tenant_id, role_id)
+        .await
+        .map_err(role_error_status)?;
+
+    info!(role_id = %role_id, "Custom role deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Assign a custom role to several users at once, or clear theirs with `"role_id": null`
+async fn assign_role(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<AssignRoleRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = admin_context(&state, &headers)?;
+
+    let user_ids = role_service(&state)
+        .assign_role(&auth_context.tenant_id, request.role_id, &request.user_ids)
+        .await
+        .map_err(role_error_status)?;
+
+    info!(role_id = ?request.role_id, users = user_ids.len(), "Custom role assigned");
+    Ok(Json(ApiResponse::success(
+        RoleAssignment { role_id: request.role_id, user_ids },
+        request_id,
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+struct SetPermissionsRequest {
+    /// `resource:action` pairs, e.g. `content:write`
+    permissions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssignRoleRequest {
+    role_id: Option<Uuid>,
+    user_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+struct RoleAssignment {
+    role_id: Option<Uuid>,
+    user_ids: Vec<Uuid>,
+}
@@ -35,10 +35,7 @@ pub async fn get_security_status(
     let request_id = Uuid::new_v4();
 
     // Require admin permission for security status
-    state.authorizer
-        .require_permission(&auth_context.user_role, "security", "admin", &auth_context.tenant_id.to_string())
-        .await
-        .map_err(|_| StatusCode::FORBIDDEN)?;
+    state.authorizer.require_context_permission(&auth_context, "security", "admin").await?;
 
     let rls_service = RlsService::new(state.db.postgres().clone());
     
@@ -65,10 +62,7 @@ pub async fn verify_security(
     let request_id = Uuid::new_v4();
 
     // Require admin permission for security verification
-    state.authorizer
-        .require_permission(&auth_context.user_role, "security", "admin", &auth_context.tenant_id.to_string())
-        .await
-        .map_err(|_| StatusCode::FORBIDDEN)?;
+    state.authorizer.require_context_permission(&auth_context, "security", "admin").await?;
 
     let rls_service = RlsService::new(state.db.postgres().clone());
     let user_id = UserId::from_uuid(auth_context.user_id);
@@ -96,10 +90,7 @@ pub async fn get_isolation_mode(
     let request_id = Uuid::new_v4();
 
     // Require admin permission to view isolation settings
-    state.authorizer
-        .require_permission(&auth_context.user_role, "tenants", "read", &auth_context.tenant_id.to_string())
-        .await
-        .map_err(|_| StatusCode::FORBIDDEN)?;
+    state.authorizer.require_context_permission(&auth_context, "tenants", "read").await?;
 
     let rls_service = RlsService::new(state.db.postgres().clone());
     
@@ -127,10 +118,7 @@ pub async fn set_isolation_mode(
     let request_id = Uuid::new_v4();
 
     // Require admin permission to configure tenant settings
-    state.authorizer
-        .require_permission(&auth_context.user_role, "tenants", "configure", &auth_context.tenant_id.to_string())
-        .await
-        .map_err(|_| StatusCode::FORBIDDEN)?;
+    state.authorizer.require_context_permission(&auth_context, "tenants", "configure").await?;
 
     // Validate isolation mode
     if !["collaborative", "isolated", "role_based"].contains(&request.mode.as_str()) {
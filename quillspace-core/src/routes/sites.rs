@@ -1,18 +1,28 @@
 use axum::{
     extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::{delete, get, post, put},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::{
-    auth::jwt_helpers::{extract_auth_context, extract_auth_context_with_role},
-    services::site::{CreateSiteRequest, SiteService, UpdateSiteRequest},
-    types::ApiResponse,
+    auth::jwt_helpers::{extract_auth_context_with_role, AuthContext},
+    routes::enforce_plan_limit,
+    services::plans::PlanCheck,
+    services::page::{Page, PageService},
+    services::asset::{AssetService, AssetServiceError},
+    services::site::{CreateSiteRequest, Site, SiteError, SiteService, UpdateSiteRequest},
+    services::request_validation::Validate,
+    services::site_access::SiteAccessRequest,
+    services::site_backup::{RestoreTarget, SiteBackupError, SiteBackupService},
+    services::site_export::{SiteExport, SiteTransferError, SiteTransferService},
+    services::site_validation::{render_for_publish, site_context, validate_pages, SiteValidationReport},
+    types::TenantId,
+    types::{ApiResponse, UserRole},
     AppState,
 };
 
@@ -51,10 +61,44 @@ pub struct SiteDetailResponse {
     pub seo_settings: serde_json::Value,
     pub build_status: String,
     pub theme_config: serde_json::Value,
+    pub locale: Option<String>,
+    pub default_locale: Option<String>,
+    /// `none`, `password` or `tokens`; the secrets themselves are never returned
+    pub access_mode: &'static str,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Site publish query parameters
+#[derive(Debug, Deserialize)]
+pub struct PublishSiteQuery {
+    /// Overrides `publishing.require_valid_pages` from the config
+    pub strict: Option<bool>,
+}
+
+/// Site import request
+#[derive(Debug, Deserialize)]
+pub struct ImportSiteRequest {
+    pub export: SiteExport,
+    /// Subdomain for the new site; defaults to the exported one when it is free
+    pub subdomain: Option<String>,
+}
+
+/// Backup list query parameters
+#[derive(Debug, Deserialize)]
+pub struct BackupListQuery {
+    pub site_id: Option<Uuid>,
+}
+
+/// Backup restore request: into the existing site `site_id` when given, otherwise
+/// into a new site
+#[derive(Debug, Deserialize)]
+pub struct RestoreBackupRequest {
+    pub site_id: Option<Uuid>,
+    /// Subdomain for a new site; defaults to the backed-up one when it is free
+    pub subdomain: Option<String>,
+}
+
 /// Subdomain availability check request
 #[derive(Debug, Deserialize)]
 pub struct SubdomainCheckQuery {
@@ -65,26 +109,47 @@ pub fn sites_router() -> Router<AppState> {
     Router::new()
         .route("/", get(list_sites).post(create_site))
         .route("/:site_id", get(get_site).put(update_site).delete(delete_site))
+        .route("/:site_id/validate", post(validate_site))
+        .route("/:site_id/export", get(export_site))
+        .route("/import", post(import_site))
+        .route("/backups", get(list_backups))
+        .route("/backups/:backup_id/restore", post(restore_backup))
         .route("/:site_id/publish", post(publish_site))
         .route("/:site_id/unpublish", post(unpublish_site))
+        .route("/:site_id/access", put(set_site_access))
         .route("/check-subdomain", get(check_subdomain_availability))
 }
 
+/// Authorize `action` on sites: on this site in particular, or across the tenant's sites
+/// when `site_id` is None, which a token bound to one site may not do
+async fn authorize_sites(
+    state: &AppState,
+    headers: &HeaderMap,
+    action: &str,
+    site_id: Option<&Uuid>,
+) -> Result<AuthContext, StatusCode> {
+    let auth_context = extract_auth_context_with_role(headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "sites", action).await?;
+    let allowed = match site_id {
+        Some(site_id) => auth_context.allows_site(site_id),
+        None => auth_context.site_id.is_none(),
+    };
+    if !allowed {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(auth_context)
+}
+
 /// List sites for the authenticated tenant
 pub async fn list_sites(
     State(state): State<AppState>,
     headers: HeaderMap,
     Query(query): Query<SiteListQuery>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
     let request_id = Uuid::new_v4();
 
-    // Casbin authorization check with proper Resource enum
-    state.authorizer
-        .require_permission(&auth_context.user_role, "sites", "read", &auth_context.tenant_id.to_string())
-        .await
-        .map_err(|_| StatusCode::FORBIDDEN)?;
+    state.authorizer.require_context_permission(&auth_context, "sites", "read").await?;
 
     let limit = query.limit.unwrap_or(20).min(100);
     let offset = query.offset.unwrap_or(0);
@@ -104,9 +169,10 @@ pub async fn list_sites(
         }
     };
 
-    // Process the sites
+    // Process the sites; a site-bound token only sees its own
     let response_sites: Vec<SiteResponse> = sites
         .into_iter()
+        .filter(|s| auth_context.allows_site(&s.id))
         .map(|s| SiteResponse {
             id: s.id,
             name: s.name,
@@ -131,8 +197,7 @@ pub async fn get_site(
     headers: HeaderMap,
     Path(site_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_sites(&state, &headers, "read", Some(&site_id)).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     let site_service = SiteService::new(state.db.postgres().clone());
@@ -150,6 +215,9 @@ pub async fn get_site(
                 seo_settings: site.seo_settings,
                 build_status: site.build_status,
                 theme_config: site.theme_config,
+                locale: site.locale,
+                default_locale: site.default_locale,
+                access_mode: site.access_protection.mode(),
                 created_at: site.created_at,
                 updated_at: site.updated_at,
             };
@@ -170,14 +238,21 @@ pub async fn create_site(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(request): Json<CreateSiteRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+) -> Result<Response, StatusCode> {
+    let tenant_id = authorize_sites(&state, &headers, "write", None).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
-    // Validate input
-    if request.name.trim().is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    if let Err(errors) = request.validate() {
+        return Ok(errors.into_response(request_id));
+    }
+
+    if let Err(response) = enforce_plan_limit(&state, &tenant_id, PlanCheck::NewSite, request_id).await {
+        return Ok(response);
+    }
+    if has_custom_domain(&request.custom_domain) {
+        if let Err(response) = enforce_plan_limit(&state, &tenant_id, PlanCheck::CustomDomain, request_id).await {
+            return Ok(response);
+        }
     }
 
     let site_service = SiteService::new(state.db.postgres().clone());
@@ -197,22 +272,32 @@ pub async fn create_site(
                 seo_settings: site.seo_settings,
                 build_status: site.build_status,
                 theme_config: site.theme_config,
+                locale: site.locale,
+                default_locale: site.default_locale,
+                access_mode: site.access_protection.mode(),
                 created_at: site.created_at,
                 updated_at: site.updated_at,
             };
 
             let response = ApiResponse::success(response_site, request_id);
-            Ok((StatusCode::CREATED, Json(response)))
+            Ok((StatusCode::CREATED, Json(response)).into_response())
         }
-        Err(e) => {
-            error!("Failed to create site: {}", e);
-            if e.to_string().contains("already taken") || e.to_string().contains("reserved") {
-                Err(StatusCode::CONFLICT)
-            } else if e.to_string().contains("invalid") || e.to_string().contains("cannot") {
-                Err(StatusCode::BAD_REQUEST)
-            } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
-            }
+        Err(e) => Err(site_error_status(e)),
+    }
+}
+
+fn site_error_status(e: SiteError) -> StatusCode {
+    match &e {
+        SiteError::ReservedSubdomain(_) | SiteError::SubdomainTaken(_) | SiteError::NoFreeSubdomain(_) => {
+            StatusCode::CONFLICT
+        }
+        SiteError::InvalidSubdomain(_) | SiteError::InvalidSettings(_) => {
+            warn!(error = %e, "Rejected site settings");
+            StatusCode::BAD_REQUEST
+        }
+        SiteError::Database(_) => {
+            error!("Failed to save site: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
         }
     }
 }
@@ -223,11 +308,19 @@ pub async fn update_site(
     headers: HeaderMap,
     Path(site_id): Path<Uuid>,
     Json(request): Json<UpdateSiteRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+) -> Result<Response, StatusCode> {
+    let tenant_id = authorize_sites(&state, &headers, "update", Some(&site_id)).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
+    if let Err(errors) = request.validate() {
+        return Ok(errors.into_response(request_id));
+    }
+    if has_custom_domain(&request.custom_domain) {
+        if let Err(response) = enforce_plan_limit(&state, &tenant_id, PlanCheck::CustomDomain, request_id).await {
+            return Ok(response);
+        }
+    }
+
     let site_service = SiteService::new(state.db.postgres().clone());
 
     match site_service.update_site(&tenant_id, site_id, request).await {
@@ -243,18 +336,18 @@ pub async fn update_site(
                 seo_settings: site.seo_settings,
                 build_status: site.build_status,
                 theme_config: site.theme_config,
+                locale: site.locale,
+                default_locale: site.default_locale,
+                access_mode: site.access_protection.mode(),
                 created_at: site.created_at,
                 updated_at: site.updated_at,
             };
 
             let response = ApiResponse::success(response_site, request_id);
-            Ok((StatusCode::OK, Json(response)))
+            Ok((StatusCode::OK, Json(response)).into_response())
         }
         Ok(None) => Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            error!("Failed to update site: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
+        Err(e) => Err(site_error_status(e)),
     }
 }
 
@@ -264,8 +357,7 @@ pub async fn delete_site(
     headers: HeaderMap,
     Path(site_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_sites(&state, &headers, "delete", Some(&site_id)).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     let site_service = SiteService::new(state.db.postgres().clone());
@@ -284,21 +376,100 @@ pub async fn delete_site(
     }
 }
 
-/// Publish site
-pub async fn publish_site(
+/// Render every page of a site the way publishing would
+async fn site_validation_report(
+    state: &AppState,
+    tenant_id: &TenantId,
+    site: &Site,
+) -> anyhow::Result<SiteValidationReport> {
+    let pages = PageService::new(state.db.postgres().clone())
+        .get_site_pages(tenant_id, site.id)
+        .await?;
+
+    let tenant_theme = SiteService::new(state.db.postgres().clone()).tenant_theme(tenant_id).await?;
+
+    let template_engine = &state.template_engine;
+    let site_context = &site_context(site, &tenant_theme);
+    let tenant_uuid = *tenant_id.as_uuid();
+    let render = |page: Page| render_for_publish(template_engine, site_context, tenant_uuid, page);
+    Ok(validate_pages(pages, render).await)
+}
+
+/// Render every page of a site and report the ones that fail
+pub async fn validate_site(
     State(state): State<AppState>,
     headers: HeaderMap,
     Path(site_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_sites(&state, &headers, "read", Some(&site_id)).await?.tenant_id;
+    let request_id = Uuid::new_v4();
+
+    let site = match SiteService::new(state.db.postgres().clone()).get_site(&tenant_id, site_id).await {
+        Ok(Some(site)) => site,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to load site {}: {}", site_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    match site_validation_report(&state, &tenant_id, &site).await {
+        Ok(report) => {
+            info!(site_id = %site_id, checked = report.checked, failed = report.failures.len(), "Validated site");
+            Ok((StatusCode::OK, Json(ApiResponse::success(report, request_id))))
+        }
+        Err(e) => {
+            error!("Failed to validate site {}: {:#}", site_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Publish site. In strict mode the site is only published when every page renders;
+/// otherwise the validation report is returned with 422.
+pub async fn publish_site(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<Uuid>,
+    Query(query): Query<PublishSiteQuery>,
+) -> Result<Response, StatusCode> {
+    let tenant_id = authorize_sites(&state, &headers, "publish", Some(&site_id)).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     let site_service = SiteService::new(state.db.postgres().clone());
 
+    if query.strict.unwrap_or(state.config.publishing.require_valid_pages) {
+        let site = match site_service.get_site(&tenant_id, site_id).await {
+            Ok(Some(site)) => site,
+            Ok(None) => return Err(StatusCode::NOT_FOUND),
+            Err(e) => {
+                error!("Failed to load site {}: {}", site_id, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+        let report = site_validation_report(&state, &tenant_id, &site).await.map_err(|e| {
+            error!("Failed to validate site {} before publishing: {:#}", site_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        if !report.valid {
+            warn!(site_id = %site_id, failed = report.failures.len(), "Refused to publish site with broken pages");
+            let response = ApiResponse {
+                success: false,
+                error: Some(format!("{} of {} pages failed to render", report.failures.len(), report.checked)),
+                data: Some(report),
+                request_id,
+            };
+            return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(response)).into_response());
+        }
+    }
+
     match site_service.publish_site(&tenant_id, site_id).await {
         Ok(Some(site)) => {
             info!("Published site {} for tenant {}", site_id, tenant_id);
+            state.publish_cache.invalidate_site(site_id);
+            if let Some(warmer) = &state.cache_warmer {
+                warmer.clone().spawn_warm_site(tenant_id.clone(), site_id);
+            }
 
             let response_site = SiteDetailResponse {
                 id: site.id,
@@ -311,12 +482,15 @@ pub async fn publish_site(
                 seo_settings: site.seo_settings,
                 build_status: site.build_status,
                 theme_config: site.theme_config,
+                locale: site.locale,
+                default_locale: site.default_locale,
+                access_mode: site.access_protection.mode(),
                 created_at: site.created_at,
                 updated_at: site.updated_at,
             };
 
             let response = ApiResponse::success(response_site, request_id);
-            Ok((StatusCode::OK, Json(response)))
+            Ok((StatusCode::OK, Json(response)).into_response())
         }
         Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(e) => {
@@ -332,8 +506,7 @@ pub async fn unpublish_site(
     headers: HeaderMap,
     Path(site_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let tenant_id = authorize_sites(&state, &headers, "publish", Some(&site_id)).await?.tenant_id;
     let request_id = Uuid::new_v4();
 
     let site_service = SiteService::new(state.db.postgres().clone());
@@ -341,6 +514,7 @@ pub async fn unpublish_site(
     match site_service.unpublish_site(&tenant_id, site_id).await {
         Ok(Some(site)) => {
             info!("Unpublished site {} for tenant {}", site_id, tenant_id);
+            state.publish_cache.invalidate_site(site_id);
 
             let response_site = SiteDetailResponse {
                 id: site.id,
@@ -353,6 +527,9 @@ pub async fn unpublish_site(
                 seo_settings: site.seo_settings,
                 build_status: site.build_status,
                 theme_config: site.theme_config,
+                locale: site.locale,
+                default_locale: site.default_locale,
+                access_mode: site.access_protection.mode(),
                 created_at: site.created_at,
                 updated_at: site.updated_at,
             };
@@ -368,14 +545,70 @@ pub async fn unpublish_site(
     }
 }
 
+/// Protect the published site with a password or access tokens, or make it public again
+pub async fn set_site_access(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<Uuid>,
+    Json(request): Json<SiteAccessRequest>,
+) -> Result<Response, StatusCode> {
+    let auth_context = authorize_sites(&state, &headers, "update", Some(&site_id)).await?;
+    let request_id = Uuid::new_v4();
+
+    let access = match tokio::task::spawn_blocking(move || request.into_access()).await {
+        Ok(Ok(access)) => access,
+        Ok(Err(e)) => {
+            warn!("Rejected access protection for site {}: {}", site_id, e);
+            let response = ApiResponse::<()>::error(e.to_string(), request_id);
+            return Ok((StatusCode::BAD_REQUEST, Json(response)).into_response());
+        }
+        Err(e) => {
+            error!("Failed to hash site access secrets: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let site_service = SiteService::new(state.db.postgres().clone());
+    match site_service.set_access_protection(&auth_context.tenant_id, site_id, &access).await {
+        Ok(Some(site)) => {
+            info!("Set access protection of site {} to {}", site_id, access.mode());
+            state.publish_cache.invalidate_site(site_id);
+
+            let response_site = SiteDetailResponse {
+                id: site.id,
+                name: site.name,
+                description: site.description,
+                template_id: site.template_id,
+                custom_domain: site.custom_domain,
+                subdomain: site.subdomain,
+                is_published: site.is_published,
+                seo_settings: site.seo_settings,
+                build_status: site.build_status,
+                theme_config: site.theme_config,
+                locale: site.locale,
+                default_locale: site.default_locale,
+                access_mode: site.access_protection.mode(),
+                created_at: site.created_at,
+                updated_at: site.updated_at,
+            };
+
+            Ok(Json(ApiResponse::success(response_site, request_id)).into_response())
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to set access protection of site {}: {}", site_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 /// Check subdomain availability
 pub async fn check_subdomain_availability(
     State(state): State<AppState>,
     headers: HeaderMap,
     Query(query): Query<SubdomainCheckQuery>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (_tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    authorize_sites(&state, &headers, "read", None).await?;
     let request_id = Uuid::new_v4();
 
     let site_service = SiteService::new(state.db.postgres().clone());
@@ -397,3 +630,309 @@ pub async fn check_subdomain_availability(
         }
     }
 }
+
+fn transfer_error_status(e: &SiteTransferError) -> StatusCode {
+    match e {
+        SiteTransferError::SiteNotFound => StatusCode::NOT_FOUND,
+        SiteTransferError::UnsupportedVersion(_) | SiteTransferError::Invalid(_) => StatusCode::BAD_REQUEST,
+        SiteTransferError::SubdomainTaken(_) => StatusCode::CONFLICT,
+        SiteTransferError::Asset(AssetServiceError::QuotaExceeded(_)) => StatusCode::PAYLOAD_TOO_LARGE,
+        SiteTransferError::Asset(_) | SiteTransferError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn site_transfer_service(state: &AppState) -> SiteTransferService {
+    let assets = AssetService::new(state.db.postgres().clone(), state.config.storage.clone(), state.object_store.clone());
+    SiteTransferService::new(state.db.postgres().clone(), assets)
+}
+
+/// Export a site with its pages and assets in the versioned transfer format
+pub async fn export_site(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let tenant_id = authorize_sites(&state, &headers, "read", Some(&site_id)).await?.tenant_id;
+    let request_id = Uuid::new_v4();
+
+    match site_transfer_service(&state).export_site(&tenant_id, site_id).await {
+        Ok(export) => {
+            info!(site_id = %site_id, pages = export.pages.len(), assets = export.assets.len(), "Exported site");
+            Ok((StatusCode::OK, Json(ApiResponse::success(export, request_id))))
+        }
+        Err(e) => {
+            let status = transfer_error_status(&e);
+            if status.is_server_error() {
+                error!("Failed to export site {}: {:#}", site_id, e);
+            }
+            Err(status)
+        }
+    }
+}
+
+/// Create a new, unpublished site in the caller's tenant from an export
+pub async fn import_site(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ImportSiteRequest>,
+) -> Result<Response, StatusCode> {
+    let tenant_id = authorize_sites(&state, &headers, "write", None).await?.tenant_id;
+    let request_id = Uuid::new_v4();
+
+    if let Err(response) = enforce_plan_limit(&state, &tenant_id, PlanCheck::NewSite, request_id).await {
+        return Ok(response);
+    }
+
+    match site_transfer_service(&state).import_site(&tenant_id, &request.export, request.subdomain).await {
+        Ok(imported) => {
+            info!(
+                site_id = %imported.site_id,
+                source_site_id = %request.export.site.id,
+                pages = imported.pages,
+                "Imported site for tenant {}", tenant_id
+            );
+            Ok((StatusCode::CREATED, Json(ApiResponse::success(imported, request_id))).into_response())
+        }
+        Err(e) => {
+            let status = transfer_error_status(&e);
+            if status.is_server_error() {
+                error!("Failed to import site: {:#}", e);
+            } else {
+                warn!("Rejected site import: {}", e);
+            }
+            Err(status)
+        }
+    }
+}
+
+fn site_backup_service(state: &AppState) -> SiteBackupService {
+    SiteBackupService::new(
+        state.db.postgres().clone(),
+        site_transfer_service(state),
+        state.object_store.clone(),
+        state.config.backups.clone(),
+    )
+}
+
+/// List the tenant's site backups, newest first (admin only)
+pub async fn list_backups(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BackupListQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = authorize_sites(&state, &headers, "read", None).await?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let request_id = Uuid::new_v4();
+
+    match site_backup_service(&state).list_backups(&auth_context.tenant_id, query.site_id).await {
+        Ok(backups) => Ok((StatusCode::OK, Json(ApiResponse::success(backups, request_id)))),
+        Err(e) => {
+            error!("Failed to list site backups: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Restore a backup into an existing site or a new one (admin only)
+pub async fn restore_backup(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(backup_id): Path<Uuid>,
+    Json(request): Json<RestoreBackupRequest>,
+) -> Result<Response, StatusCode> {
+    let auth_context = authorize_sites(&state, &headers, "write", None).await?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let tenant_id = auth_context.tenant_id;
+    let request_id = Uuid::new_v4();
+
+    let target = match request.site_id {
+        Some(site_id) => RestoreTarget::ExistingSite(site_id),
+        None => {
+            if let Err(response) = enforce_plan_limit(&state, &tenant_id, PlanCheck::NewSite, request_id).await {
+                return Ok(response);
+            }
+            RestoreTarget::NewSite { subdomain: request.subdomain }
+        }
+    };
+
+    match site_backup_service(&state).restore(&tenant_id, backup_id, target).await {
+        Ok(restored) => {
+            info!(backup_id = %backup_id, site_id = %restored.site_id, pages = restored.pages, "Restored site backup");
+            if request.site_id.is_some() {
+                state.publish_cache.invalidate_site(restored.site_id);
+            }
+            Ok((StatusCode::OK, Json(ApiResponse::success(restored, request_id))).into_response())
+        }
+        Err(e) => {
+            let status = match &e {
+                SiteBackupError::NotFound => StatusCode::NOT_FOUND,
+                SiteBackupError::Transfer(e) => transfer_error_status(e),
+                SiteBackupError::Store(_) | SiteBackupError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            if status.is_server_error() {
+                error!("Failed to restore backup {}: {:#}", backup_id, e);
+            } else {
+                warn!("Rejected restore of backup {}: {}", backup_id, e);
+            }
+            Err(status)
+        }
+    }
+}
+
+/// Whether a create/update sets a custom domain (clearing one is always allowed)
+fn has_custom_domain(custom_domain: &Option<String>) -> bool {
+    custom_domain.as_deref().is_some_and(|domain| !domain.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        services::site::{CreateSiteRequest, SiteError, SiteService},
+        test_harness::TestApp,
+        types::{TenantId, UserRole},
+    };
+    use axum::http::{header, Method, StatusCode};
+    use deadpool_postgres::Pool;
+    use serde_json::json;
+    use std::collections::HashSet;
+    use uuid::Uuid;
+
+    async fn insert_site(pool: &Pool, tenant_id: &TenantId, subdomain: &str) -> Uuid {
+        let client = pool.get().await.expect("Failed to get connection");
+        let row = client
+            .query_one(
+                "INSERT INTO sites (tenant_id, name, subdomain) VALUES ($1, $2, $2) RETURNING id",
+                &[tenant_id.as_uuid(), &subdomain],
+            )
+            .await
+            .expect("Failed to insert site");
+        row.get("id")
+    }
+
+    #[tokio::test]
+    async fn test_tenant_cannot_read_another_tenants_sites() {
+        let Some(app) = TestApp::start().await else { return };
+        let site_a = insert_site(&app.admin_pool, &app.tenant_a.id, "site-a").await;
+        let site_b = insert_site(&app.admin_pool, &app.tenant_b.id, "site-b").await;
+        let admin_a = &app.tenant_a.admin;
+        let viewer_a = app.add_user(&app.tenant_a.id, UserRole::Viewer).await;
+
+        let list = app.get("/api/sites", &viewer_a).await;
+        assert_eq!(list.status, StatusCode::OK, "{}", list.body);
+        let ids: Vec<&str> = list.body["data"]
+            .as_array()
+            .expect("site list")
+            .iter()
+            .filter_map(|site| site["id"].as_str())
+            .collect();
+        assert_eq!(ids, vec![site_a.to_string()]);
+
+        let own = app.get(&format!("/api/sites/{}", site_a), admin_a).await;
+        assert_eq!(own.status, StatusCode::OK);
+
+        let other = app.get(&format!("/api/sites/{}", site_b), admin_a).await;
+        assert_eq!(other.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_site_bound_read_token_cannot_manage_sites() {
+        let Some(app) = TestApp::start().await else { return };
+        let site = insert_site(&app.admin_pool, &app.tenant_a.id, "widget-site").await;
+        let other = insert_site(&app.admin_pool, &app.tenant_a.id, "other-site").await;
+        let admin = &app.tenant_a.admin;
+        let minted = app
+            .post("/api/auth/scoped-tokens", admin, json!({ "scopes": ["sites:read"], "site_id": site }))
+            .await;
+        assert_eq!(minted.status, StatusCode::CREATED, "{}", minted.body);
+        let token = minted.body["data"]["token"].as_str().unwrap().to_string();
+        let send = |method: Method, uri: String, body: serde_json::Value| {
+            let mut request = app.request(method, &uri, admin, Some(body));
+            request.headers_mut().insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+            app.send(request)
+        };
+
+        let list = send(Method::GET, "/api/sites".to_string(), json!({})).await;
+        assert_eq!(list.status, StatusCode::OK, "{}", list.body);
+        assert_eq!(list.body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(send(Method::GET, format!("/api/sites/{}", site), json!({})).await.status, StatusCode::OK);
+        assert_eq!(send(Method::GET, format!("/api/sites/{}", other), json!({})).await.status, StatusCode::FORBIDDEN);
+
+        let created = send(Method::POST, "/api/sites".to_string(), json!({ "name": "Widget" })).await;
+        assert_eq!(created.status, StatusCode::FORBIDDEN);
+        let deleted = send(Method::DELETE, format!("/api/sites/{}", site), json!({})).await;
+        assert_eq!(deleted.status, StatusCode::FORBIDDEN);
+        let published = send(Method::POST, format!("/api/sites/{}/publish", site), json!({})).await;
+        assert_eq!(published.status, StatusCode::FORBIDDEN);
+        let access = send(Method::PUT, format!("/api/sites/{}/access", site), json!({ "mode": "none" })).await;
+        assert_eq!(access.status, StatusCode::FORBIDDEN);
+
+        // Routes that cannot honor the restrictions refuse the token outright
+        let page = send(Method::POST, format!("/api/sites/{}/pages", site), json!({ "title": "Widget", "slug": "widget" })).await;
+        assert_eq!(page.status, StatusCode::FORBIDDEN, "{}", page.body);
+    }
+
+    #[tokio::test]
+    async fn test_site_locale_set_and_cleared_through_its_own_fields() {
+        let Some(app) = TestApp::start().await else { return };
+        let site = insert_site(&app.admin_pool, &app.tenant_a.id, "locale-site").await;
+        let admin = &app.tenant_a.admin;
+        let update = |body: serde_json::Value| {
+            let request = app.request(Method::PUT, &format!("/api/sites/{}", site), admin, Some(body));
+            app.send(request)
+        };
+
+        let invalid = update(json!({ "locale": "german please" })).await;
+        assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", invalid.body);
+
+        let set = update(json!({ "locale": "de-DE", "default_locale": "en-GB" })).await;
+        assert_eq!(set.status, StatusCode::OK, "{}", set.body);
+        assert_eq!((set.body["data"]["locale"].as_str(), set.body["data"]["default_locale"].as_str()), (Some("de-DE"), Some("en-GB")));
+        assert_eq!(set.body["data"]["seo_settings"], json!({}));
+
+        let cleared = update(json!({ "locale": "" })).await;
+        assert_eq!(cleared.status, StatusCode::OK, "{}", cleared.body);
+        assert!(cleared.body["data"]["locale"].is_null());
+        assert_eq!(cleared.body["data"]["default_locale"], "en-GB");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_same_named_sites_get_distinct_subdomains() {
+        let Some(app) = TestApp::start().await else { return };
+        let request = |subdomain: Option<&str>| CreateSiteRequest {
+            name: "Brontë Press".to_string(),
+            description: None,
+            template_id: None,
+            custom_domain: None,
+            subdomain: subdomain.map(str::to_string),
+            seo_settings: None,
+            theme_config: None,
+            locale: None,
+            default_locale: None,
+        };
+
+        // Both tenants at once, so a conflicting row is often one RLS hides
+        let creates: Vec<_> = (0..12)
+            .map(|i| {
+                let service = SiteService::new(app.state.db.postgres().clone());
+                let tenant_id = if i % 2 == 0 { app.tenant_a.id.clone() } else { app.tenant_b.id.clone() };
+                let request = request(None);
+                tokio::spawn(async move { service.create_site(&tenant_id, request).await })
+            })
+            .collect();
+        let mut subdomains = HashSet::new();
+        for create in creates {
+            let site = create.await.unwrap().expect("Failed to create site");
+            assert!(subdomains.insert(site.subdomain));
+        }
+        assert!(subdomains.contains("bronte-press"));
+        assert!(subdomains.contains("bronte-press-11"));
+
+        let service = SiteService::new(app.state.db.postgres().clone());
+        let taken = service.create_site(&app.tenant_b.id, request(Some("bronte-press-3"))).await.unwrap_err();
+        assert!(matches!(taken, SiteError::SubdomainTaken(_)), "{}", taken);
+    }
+}
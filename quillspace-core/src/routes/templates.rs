@@ -1,19 +1,24 @@
 use axum::{
     extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
-    response::{Html, IntoResponse},
-    routing::{delete, get, post, put},
+    response::{Html, IntoResponse, Response},
+    routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::{
-    auth::jwt_helpers::extract_auth_context,
-    services::template_engine::{Template, TemplateEngine, SiteContext, PageContext},
-    types::ApiResponse,
+    auth::jwt_helpers::{extract_auth_context, extract_auth_context_with_role},
+    database::postgres::tenant_client,
+    services::render_metrics::TemplateRenderStats,
+    services::request_validation::{Validate, ValidationErrors, MAX_DESCRIPTION_LEN, MAX_NAME_LEN},
+    services::template_source_cache::{TemplateCacheStats, TenantCacheEntries},
+    services::template_engine::{Template, SiteContext, PageContext},
+    services::template_schema::{template_json_schema, validate_default_schema, TemplateSchemaError},
+    types::{ApiResponse, UserRole},
     AppState,
 };
 
@@ -35,6 +40,40 @@ pub struct UpdateTemplateRequest {
     pub default_schema: Option<Value>,
 }
 
+impl Validate for CreateTemplateRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required("name", &self.name);
+        errors.max_length("name", &self.name, MAX_NAME_LEN);
+        errors.required("category", &self.category);
+        errors.max_length("category", &self.category, MAX_NAME_LEN);
+        errors.required("html_source", &self.html_source);
+        if let Some(description) = &self.description {
+            errors.max_length("description", description, MAX_DESCRIPTION_LEN);
+        }
+        if let Err(e) = validate_default_schema(&self.default_schema) {
+            errors.add("default_schema", "invalid_schema", e.to_string());
+        }
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdateTemplateRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if let Some(description) = &self.description {
+            errors.max_length("description", description, MAX_DESCRIPTION_LEN);
+        }
+        if let Some(html_source) = &self.html_source {
+            errors.required("html_source", html_source);
+        }
+        if let Some(Err(e)) = self.default_schema.as_ref().map(validate_default_schema) {
+            errors.add("default_schema", "invalid_schema", e.to_string());
+        }
+        errors.into_result()
+    }
+}
+
 /// Template list query parameters
 #[derive(Debug, Deserialize)]
 pub struct TemplateListQuery {
@@ -78,20 +117,94 @@ pub struct TemplateDetailResponse {
 pub fn templates_router() -> Router<AppState> {
     Router::new()
         .route("/", get(list_templates).post(create_template))
+        .route("/metrics/slowest", get(slowest_templates))
+        .route("/cache", get(template_cache_status).delete(clear_template_cache))
         .route("/:template_id", get(get_template).put(update_template).delete(delete_template))
+        .route("/:template_id/schema", get(get_template_schema))
         .route("/:template_id/render", post(render_template))
         .route("/render-puck", post(render_puck_page))
         .route("/generate-static", post(generate_static_html))
 }
 
+/// Slowest templates query parameters
+#[derive(Debug, Deserialize)]
+pub struct SlowestTemplatesQuery {
+    pub limit: Option<usize>,
+}
+
+/// The tenant's templates with the highest average render time since startup (admin only)
+pub async fn slowest_templates(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SlowestTemplatesQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    auth_context.require_scope("templates", "read")?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let request_id = Uuid::new_v4();
+
+    let limit = query.limit.unwrap_or(20).min(100);
+    let slowest: Vec<TemplateRenderStats> = state
+        .template_engine
+        .render_metrics()
+        .slowest(*auth_context.tenant_id.as_uuid(), limit);
+    Ok(Json(ApiResponse::success(slowest, request_id)))
+}
+
+/// Template cache contents and hit/miss counts
+#[derive(Debug, Serialize)]
+pub struct TemplateCacheResponse {
+    pub stats: TemplateCacheStats,
+    pub tenants: Vec<TenantCacheEntries>,
+}
+
+/// The tenant's cached template names with the cache's hit/miss counts (admin only).
+/// Tenant admins only ever see and clear their own tenant's entries.
+pub async fn template_cache_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    auth_context.require_scope("templates", "read")?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let request_id = Uuid::new_v4();
+
+    let response = TemplateCacheResponse {
+        stats: state.template_engine.template_cache_stats(),
+        tenants: state.template_engine.template_cache_keys(Some(*auth_context.tenant_id.as_uuid())),
+    };
+    Ok(Json(ApiResponse::success(response, request_id)))
+}
+
+/// Clear the tenant's cached templates (admin only)
+pub async fn clear_template_cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    auth_context.require_scope("templates", "delete")?;
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let request_id = Uuid::new_v4();
+
+    let tenant_id = *auth_context.tenant_id.as_uuid();
+    let removed = state.template_engine.clear_template_cache(Some(tenant_id));
+    info!("Cleared {} cached templates for tenant {} (by {})", removed, tenant_id, auth_context.user_id);
+    Ok(Json(ApiResponse::success(serde_json::json!({ "removed": removed }), request_id)))
+}
+
 /// List templates
 pub async fn list_templates(
     State(state): State<AppState>,
     headers: HeaderMap,
     Query(query): Query<TemplateListQuery>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
     let request_id = Uuid::new_v4();
     
     info!("LIST_TEMPLATES: tenant_id={}, query={:?}", tenant_id, query);
@@ -139,8 +252,7 @@ pub async fn get_template(
     headers: HeaderMap,
     Path(template_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
     let request_id = Uuid::new_v4();
 
     // First try to get by ID
@@ -152,14 +264,14 @@ pub async fn get_template(
         WHERE id = $1 AND (tenant_id = $2 OR is_public = true)
     ";
 
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
-    
+
     match client.query_opt(query, &[&template_id, tenant_id.as_uuid()]).await {
         Ok(Some(row)) => {
             let template = Template {
@@ -202,23 +314,50 @@ pub async fn get_template(
     }
 }
 
-/// Create new template
-pub async fn create_template(
+/// Get the JSON Schema of a template's editable component props
+pub async fn get_template_schema(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(request): Json<CreateTemplateRequest>,
+    Path(template_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
     let request_id = Uuid::new_v4();
 
-    // Validate input
-    if request.name.trim().is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    let template = match state.template_engine.get_template_by_id(template_id, *tenant_id.as_uuid()).await {
+        Ok(Some(template)) => template,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to get template: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    match template_json_schema(&template, *tenant_id.as_uuid()) {
+        Ok(schema) => {
+            let response = ApiResponse::success(schema, request_id);
+            Ok((StatusCode::OK, Json(response)))
+        }
+        Err(TemplateSchemaError::NotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            // Stored before default_schema was validated
+            error!("Template {} has an unusable schema: {}", template_id, e);
+            Err(StatusCode::UNPROCESSABLE_ENTITY)
+        }
     }
+}
+
+/// Create new template
+pub async fn create_template(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateTemplateRequest>,
+) -> Result<Response, StatusCode> {
+    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
+    let request_id = Uuid::new_v4();
 
-    if request.html_source.trim().is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    if let Err(errors) = request.validate() {
+        warn!("Rejected template '{}': {:?}", request.name, errors.fields.keys());
+        return Ok(errors.into_response(request_id));
     }
 
     match state.template_engine.create_template(
@@ -245,7 +384,7 @@ pub async fn create_template(
             };
 
             let response = ApiResponse::success(response_template, request_id);
-            Ok((StatusCode::CREATED, Json(response)))
+            Ok((StatusCode::CREATED, Json(response)).into_response())
         }
         Err(e) => {
             error!("Failed to create template: {}", e);
@@ -264,11 +403,15 @@ pub async fn update_template(
     headers: HeaderMap,
     Path(template_id): Path<Uuid>,
     Json(request): Json<UpdateTemplateRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+) -> Result<Response, StatusCode> {
+    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
     let request_id = Uuid::new_v4();
 
+    if let Err(errors) = request.validate() {
+        warn!("Rejected update of template {}: {:?}", template_id, errors.fields.keys());
+        return Ok(errors.into_response(request_id));
+    }
+
     match state.template_engine.update_template(
         template_id,
         tenant_id.into(),
@@ -292,7 +435,7 @@ pub async fn update_template(
             };
 
             let response = ApiResponse::success(response_template, request_id);
-            Ok((StatusCode::OK, Json(response)))
+            Ok((StatusCode::OK, Json(response)).into_response())
         }
         Err(e) => {
             error!("Failed to update template: {}", e);
@@ -313,8 +456,7 @@ pub async fn delete_template(
     headers: HeaderMap,
     Path(template_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
     let request_id = Uuid::new_v4();
 
     match state.template_engine.delete_template(template_id, tenant_id.into()).await {
@@ -347,8 +489,7 @@ pub async fn render_template(
     Path(template_id): Path<Uuid>,
     Json(request): Json<RenderTemplateRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
     let request_id = Uuid::new_v4();
 
     // Get template first
@@ -357,7 +498,7 @@ pub async fn render_template(
         WHERE id = $1 AND (tenant_id = $2 OR is_public = true)
     ";
 
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -393,6 +534,9 @@ pub async fn render_template(
             subdomain: "subdomain".to_string(),
             custom_domain: None,
             seo_settings: serde_json::json!({}),
+            locale: None,
+            default_locale: None,
+            theme: serde_json::json!({}),
         },
         page: PageContext {
             id: Uuid::new_v4(), // This should come from actual page data
@@ -406,6 +550,15 @@ pub async fn render_template(
         puck_data: Some(serde_json::json!({})),
         puck_content: request.puck_content,
         user: None,
+        navigation: Vec::new(),
+        content: None,
+        trusted: Default::default(),
+        locale: crate::services::locale::resolve_locale(
+            None,
+            None,
+            headers.get(axum::http::header::ACCEPT_LANGUAGE).and_then(|value| value.to_str().ok()),
+        ),
+        translations: Default::default(),
     };
 
     // Render the template using the template engine
@@ -451,8 +604,7 @@ pub async fn render_puck_page(
     headers: HeaderMap,
     Json(request): Json<RenderPuckPageRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let (tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
 
     let request_id = uuid::Uuid::new_v4();
     info!("Rendering Puck page for tenant: {}", tenant_id);
@@ -463,6 +615,7 @@ pub async fn render_puck_page(
         &request.site,
         &request.page,
         tenant_id.into(),
+        headers.get(axum::http::header::ACCEPT_LANGUAGE).and_then(|value| value.to_str().ok()),
     ).await {
         Ok(rendered_html) => {
             let response = ApiResponse::success(serde_json::json!({
@@ -485,8 +638,7 @@ pub async fn generate_static_html(
     headers: HeaderMap,
     Json(request): Json<GenerateStaticHtmlRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let (_tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let (_tenant_id, _user_id) = extract_auth_context(&headers, &state.jwt_manager)?;
 
     info!("Generating static HTML for site: {}", request.site.name);
 
@@ -506,3 +658,93 @@ pub async fn generate_static_html(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_harness::TestApp, types::UserRole};
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_template_schema_validated_and_served() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = &app.tenant_a.admin;
+        let schema = json!({
+            "components": { "HeroBlock": { "label": "Hero", "fields": { "title": { "type": "text" } } } },
+            "content": [{ "type": "HeroBlock", "props": { "title": "Your Book Title" } }]
+        });
+        let template = |default_schema: serde_json::Value| {
+            json!({
+                "name": "Author landing",
+                "category": "page",
+                "html_source": "<h1>{{ page.title }}</h1>",
+                "default_schema": default_schema
+            })
+        };
+
+        let unknown_field_type = json!({ "components": { "HeroBlock": { "fields": { "title": { "type": "color" } } } } });
+        let invalid = app.post("/api/templates", admin, template(unknown_field_type)).await;
+        assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", invalid.body);
+        assert!(invalid.body.to_string().contains("default_schema"), "{}", invalid.body);
+
+        let created = app.post("/api/templates", admin, template(schema)).await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        let uri = format!("/api/templates/{}", created.body["data"]["id"].as_str().unwrap());
+        assert_eq!(app.get(&uri, admin).await.status, StatusCode::OK);
+
+        let served = app.get(&format!("{}/schema", uri), admin).await;
+        assert_eq!(served.status, StatusCode::OK, "{}", served.body);
+        assert_eq!(served.body["data"]["$defs"]["HeroBlock"]["title"], "Hero");
+
+        // Updates are checked the same way
+        let update = Some(json!({ "default_schema": { "content": [{ "props": {} }] } }));
+        let rejected = app.send(app.request(Method::PUT, &uri, admin, update)).await;
+        assert_eq!(rejected.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", rejected.body);
+
+        // Its own tenant renders it
+        let render = json!({ "site_context": {}, "page_context": {}, "puck_content": "" });
+        let rendered = app.post(&format!("{}/render", uri), admin, render.clone()).await;
+        assert_eq!(rendered.status, StatusCode::OK, "{}", rendered.body);
+        assert_eq!(rendered.body["data"]["rendered_html"], "<h1>Page Title</h1>");
+
+        // Another tenant's private template is not found
+        let other = &app.tenant_b.admin;
+        assert_eq!(app.post(&format!("{}/render", uri), other, render).await.status, StatusCode::NOT_FOUND);
+        assert_eq!(app.get(&format!("{}/schema", uri), other).await.status, StatusCode::NOT_FOUND);
+        assert_eq!(app.get(&uri, other).await.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_cache_endpoints_scoped_to_the_admins_tenant() {
+        let Some(app) = TestApp::start().await else { return };
+        app.admin_pool
+            .get()
+            .await
+            .unwrap()
+            .execute(
+                "INSERT INTO templates (name, category, html_source, is_public) VALUES ('shared', 'page', '<p>Hi</p>', true)",
+                &[],
+            )
+            .await
+            .expect("Failed to add template");
+        let engine = &app.state.template_engine;
+        for tenant in [&app.tenant_a.id, &app.tenant_b.id] {
+            assert_eq!(engine.warm_templates(*tenant.as_uuid(), &["shared"]).await.unwrap(), 1);
+        }
+        let (admin, editor) = (&app.tenant_a.admin, app.add_user(&app.tenant_a.id, UserRole::Editor).await);
+
+        assert_eq!(app.get("/api/templates/cache", &editor).await.status, StatusCode::FORBIDDEN);
+        let status = app.get("/api/templates/cache", admin).await;
+        assert_eq!(status.status, StatusCode::OK, "{}", status.body);
+        let tenants = status.body["data"]["tenants"].as_array().unwrap();
+        assert_eq!(tenants.len(), 1, "{}", status.body);
+        assert_eq!(tenants[0]["tenant_id"], app.tenant_a.id.to_string());
+
+        let cleared = app.send(app.request(Method::DELETE, "/api/templates/cache", admin, None)).await;
+        assert_eq!(cleared.status, StatusCode::OK, "{}", cleared.body);
+        assert_eq!(cleared.body["data"]["removed"], 1);
+        // The other tenant's entries are untouched
+        assert_eq!(engine.template_cache_keys(None).len(), 1);
+        assert_eq!(engine.template_cache_keys(Some(*app.tenant_b.id.as_uuid())).len(), 1);
+    }
+}
@@ -1,20 +1,66 @@
 use crate::{
-    auth::jwt_helpers::{extract_auth_context, extract_auth_context_with_role},
-    types::{ApiResponse, Tenant, UserRole},
+    auth::jwt_helpers::{extract_auth_context_with_role, AuthContext},
+    database::postgres::tenant_client,
+    middleware::rate_limit::TenantRateLimit,
+    services::analytics_events,
+    services::asset::AssetService,
+    services::html_sanitize,
+    services::public_url::{resolve_base_url, RequestOrigin},
+    services::site_theme,
+    services::slug,
+    services::tenant_bootstrap::{BootstrapTenantRequest, TenantBootstrapError, TenantBootstrapService},
+    services::template_syntax,
+    services::timezone,
+    types::{ApiResponse, Tenant, TenantId, UserRole},
     AppState,
 };
 use axum::{
-    extract::{Path, Query, State},
+    extract::{ConnectInfo, Path, Query, State},
     http::{HeaderMap, StatusCode},
     response::IntoResponse,
-    routing::{get, post, put},
+    routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use tokio_postgres::{Row, Error as PgError};
-use tracing::{error, info};
+use std::net::SocketAddr;
+use tokio_postgres::{error::SqlState, Row, Error as PgError};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// Settings the platform manages rather than the tenant: the plan and its limits, the
+/// billing subscription, and rate limits, which have their own admin endpoint. Settings
+/// writes keep the stored values.
+const MANAGED_SETTINGS: &[&str] = &["plan", "plan_limits", "subscription", "rate_limit"];
+
+/// SQL for a settings update to `$settings_param` that keeps the stored
+/// [`MANAGED_SETTINGS`], which are bound to `$managed_param`
+fn keep_managed_settings(settings_param: usize, managed_param: usize) -> String {
+    format!(
+        "(${settings_param}::jsonb - ${managed_param}::text[]) || COALESCE((SELECT jsonb_object_agg(key, value) FROM jsonb_each(settings) WHERE key = ANY(${managed_param}::text[])), '{{}}'::jsonb)"
+    )
+}
+
+/// Checks every settings write must pass: settings may only name a timezone the scheduler
+/// and analytics can use, hold a custom event schema analytics can apply, and so on
+const SETTINGS_VALIDATORS: &[fn(&serde_json::Value) -> anyhow::Result<()>] = &[
+    |settings| Ok(timezone::validate_settings(settings)?),
+    |settings| Ok(analytics_events::validate_settings(settings)?),
+    |settings| Ok(slug::validate_settings(settings)?),
+    |settings| Ok(template_syntax::validate_settings(settings)?),
+    |settings| Ok(html_sanitize::validate_settings(settings)?),
+    |settings| Ok(site_theme::validate_settings(settings)?),
+];
+
+fn check_settings(settings: &serde_json::Value) -> Result<(), StatusCode> {
+    for validate in SETTINGS_VALIDATORS {
+        validate(settings).map_err(|e| {
+            warn!("Rejected tenant settings: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+    }
+    Ok(())
+}
+
 /// Helper function to convert a tokio-postgres Row to Tenant
 fn row_to_tenant(row: &Row) -> Result<Tenant, PgError> {
     Ok(Tenant {
@@ -28,6 +74,17 @@ fn row_to_tenant(row: &Row) -> Result<Tenant, PgError> {
     })
 }
 
+/// Admins of the configured platform tenant manage every tenant
+fn is_platform_admin(state: &AppState, auth_context: &AuthContext) -> bool {
+    auth_context.user_role == UserRole::Admin
+        && state.config.auth.platform_tenant_id.as_ref() == Some(auth_context.tenant_id.as_uuid())
+}
+
+/// Everyone reaches their own tenant; platform admins reach any
+fn can_access_tenant(state: &AppState, auth_context: &AuthContext, tenant_id: &Uuid) -> bool {
+    auth_context.tenant_id.as_uuid() == tenant_id || is_platform_admin(state, auth_context)
+}
+
 /// Query parameters for listing tenants
 #[derive(Debug, Deserialize)]
 pub struct ListTenantsQuery {
@@ -55,13 +112,16 @@ pub struct UpdateTenantRequest {
 pub fn create_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_tenants).post(create_tenant))
+        .route("/bootstrap", post(bootstrap_tenant))
         .route("/current", get(get_current_tenant))
         .route("/current/settings", get(get_current_tenant_settings).put(update_current_tenant_settings))
+        .route("/current/usage", get(get_current_tenant_usage))
         .route("/:tenant_id", get(get_tenant).put(update_tenant))
         .route("/:tenant_id/settings", get(get_tenant_settings).put(update_tenant_settings))
+        .route("/:tenant_id/rate-limit", get(get_tenant_rate_limit).put(update_tenant_rate_limit))
 }
 
-/// List tenants (admin only)
+/// List the tenants the caller can see, which row-level security limits to their own (admin only)
 async fn list_tenants(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -72,6 +132,7 @@ async fn list_tenants(
     // Verify admin authorization for tenant operations
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "read")?;
     
     if auth_context.user_role != UserRole::Admin {
         return Err(StatusCode::FORBIDDEN);
@@ -80,8 +141,7 @@ async fn list_tenants(
     let limit: u32 = params.limit.unwrap_or(20).min(100);
     let offset: u32 = params.offset.unwrap_or(0);
 
-    // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &auth_context.tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -112,7 +172,7 @@ async fn list_tenants(
     }
 }
 
-/// Create a new tenant (admin only)
+/// Create a new tenant (platform admins only)
 async fn create_tenant(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -120,20 +180,22 @@ async fn create_tenant(
 ) -> Result<impl IntoResponse, StatusCode> {
     let request_id = Uuid::new_v4();
 
-    // Verify admin authorization for tenant creation
+    // Verify platform admin authorization for tenant creation
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "configure")?;
     
-    if auth_context.user_role != UserRole::Admin {
+    if !is_platform_admin(&state, &auth_context) {
         return Err(StatusCode::FORBIDDEN);
     }
 
     let tenant_id = Uuid::new_v4();
     let now = chrono::Utc::now();
     let settings = request.settings.unwrap_or_else(|| serde_json::json!({}));
+    check_settings(&settings)?;
 
-    // Get database connection
-    let client = match state.db.postgres().get().await {
+    // Connected as the new tenant, so its insert passes the tenants policy
+    let client = match tenant_client(state.db.postgres(), &TenantId::from_uuid(tenant_id)).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -166,7 +228,7 @@ async fn create_tenant(
         }
         Err(e) => {
             error!("Failed to create tenant: {}", e);
-            if e.to_string().contains("duplicate key") {
+            if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
                 Err(StatusCode::CONFLICT)
             } else {
                 Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -175,6 +237,59 @@ async fn create_tenant(
     }
 }
 
+fn bootstrap_error_status(e: &TenantBootstrapError) -> StatusCode {
+    match e {
+        TenantBootstrapError::Invalid(_) => StatusCode::BAD_REQUEST,
+        TenantBootstrapError::TemplateNotFound => StatusCode::NOT_FOUND,
+        TenantBootstrapError::Conflict(_) => StatusCode::CONFLICT,
+        TenantBootstrapError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Create a tenant with its first admin (invited by email), a starter site and its
+/// default pages, all or nothing (platform admins only)
+async fn bootstrap_tenant(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<BootstrapTenantRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "configure")?;
+
+    if !is_platform_admin(&state, &auth_context) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // The invite links to the platform app, never to a tenant's domain
+    let public_url = &state.config.public_url;
+    let base_url = resolve_base_url(public_url, None, &RequestOrigin::from_request(&state.config.proxy, &headers, Some(peer.ip())));
+
+    match TenantBootstrapService::new(state.db.postgres().clone()).bootstrap(&request, &base_url).await {
+        Ok(created) => {
+            info!(
+                tenant_id = %created.tenant_id,
+                site_id = %created.site_id,
+                pages = created.page_ids.len(),
+                "Bootstrapped tenant {}", request.tenant_slug
+            );
+            Ok((StatusCode::CREATED, Json(ApiResponse::success(created, request_id))))
+        }
+        Err(e) => {
+            let status = bootstrap_error_status(&e);
+            if status.is_server_error() {
+                error!("Failed to bootstrap tenant: {:#}", e);
+            } else {
+                info!("Rejected tenant bootstrap: {}", e);
+            }
+            Err(status)
+        }
+    }
+}
+
 /// Get current user's tenant
 async fn get_current_tenant(
     State(state): State<AppState>,
@@ -184,6 +299,7 @@ async fn get_current_tenant(
 
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "read")?;
 
     get_tenant_by_id(state, *auth_context.tenant_id.as_uuid(), request_id).await
 }
@@ -199,9 +315,9 @@ async fn get_tenant(
     // Verify user authorization for tenant access
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "read")?;
     
-    // Admin can access any tenant, others can only access their own
-    if auth_context.user_role != UserRole::Admin && auth_context.tenant_id.as_uuid() != &tenant_id {
+    if !can_access_tenant(&state, &auth_context, &tenant_id) {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -214,8 +330,7 @@ async fn get_tenant_by_id(
     tenant_id: Uuid,
     request_id: Uuid,
 ) -> Result<Json<ApiResponse<Tenant>>, StatusCode> {
-    // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &TenantId::from_uuid(tenant_id)).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -258,16 +373,19 @@ async fn update_tenant(
     // Verify user authorization for tenant updates
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "update")?;
     
-    // Admin can update any tenant, others can only update their own
-    if auth_context.user_role != UserRole::Admin && auth_context.tenant_id.as_uuid() != &tenant_id {
+    // Admins update their own tenant, platform admins any
+    if auth_context.user_role != UserRole::Admin || !can_access_tenant(&state, &auth_context, &tenant_id) {
         return Err(StatusCode::FORBIDDEN);
     }
+    if let Some(settings) = &request.settings {
+        check_settings(settings)?;
+    }
 
     let now = chrono::Utc::now();
 
-    // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &TenantId::from_uuid(tenant_id)).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -293,9 +411,10 @@ async fn update_tenant(
     }
 
     if let Some(settings) = &request.settings {
-        param_count += 1;
-        set_clauses.push(format!("settings = ${}", param_count));
+        param_count += 2;
+        set_clauses.push(format!("settings = {}", keep_managed_settings(param_count - 1, param_count)));
         params.push(settings);
+        params.push(&MANAGED_SETTINGS);
     }
 
     if set_clauses.is_empty() {
@@ -315,6 +434,9 @@ async fn update_tenant(
         Ok(Some(row)) => {
             match row_to_tenant(&row) {
                 Ok(tenant) => {
+                    if request.settings.is_some() {
+                        state.template_engine.invalidate_tenant_templates(tenant_id);
+                    }
                     let response = ApiResponse::success(tenant, request_id);
                     Ok(Json(response))
                 }
@@ -341,10 +463,32 @@ async fn get_current_tenant_settings(
 
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "read")?;
 
     get_tenant_settings_by_id(state, *auth_context.tenant_id.as_uuid(), request_id).await
 }
 
+/// Get current tenant's asset storage use and plan quota
+async fn get_current_tenant_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "read")?;
+
+    let service = AssetService::new(state.db.postgres().clone(), state.config.storage.clone(), state.object_store.clone());
+    match service.get_storage_quota_usage(&auth_context.tenant_id).await {
+        Ok(usage) => Ok(Json(ApiResponse::success(usage, request_id))),
+        Err(e) => {
+            error!("Failed to get tenant storage usage: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 /// Get tenant settings
 async fn get_tenant_settings(
     State(state): State<AppState>,
@@ -356,9 +500,9 @@ async fn get_tenant_settings(
     // Verify user authorization for tenant settings access
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "read")?;
     
-    // Admin can access any tenant settings, others can only access their own
-    if auth_context.user_role != UserRole::Admin && auth_context.tenant_id.as_uuid() != &tenant_id {
+    if !can_access_tenant(&state, &auth_context, &tenant_id) {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -371,8 +515,7 @@ async fn get_tenant_settings_by_id(
     tenant_id: Uuid,
     request_id: Uuid,
 ) -> Result<impl IntoResponse, StatusCode> {
-    // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &TenantId::from_uuid(tenant_id)).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -406,6 +549,7 @@ async fn update_current_tenant_settings(
 
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "update")?;
 
     // Only admin or editor can update tenant settings
     if !matches!(auth_context.user_role, UserRole::Admin | UserRole::Editor) {
@@ -427,13 +571,12 @@ async fn update_tenant_settings(
     // Verify user authorization for tenant settings updates
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "update")?;
     
-    // Admin can update any tenant settings, editors can only update their own
-    if auth_context.user_role == UserRole::Admin {
-        // Admin can update any tenant
-    } else if auth_context.user_role == UserRole::Editor && auth_context.tenant_id.as_uuid() == &tenant_id {
-        // Editor can update their own tenant
-    } else {
+    // Admins and editors update their own tenant's settings, platform admins any
+    if !matches!(auth_context.user_role, UserRole::Admin | UserRole::Editor)
+        || !can_access_tenant(&state, &auth_context, &tenant_id)
+    {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -447,10 +590,10 @@ async fn update_tenant_settings_by_id(
     settings: serde_json::Value,
     request_id: Uuid,
 ) -> Result<impl IntoResponse, StatusCode> {
+    check_settings(&settings)?;
     let now = chrono::Utc::now();
 
-    // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &TenantId::from_uuid(tenant_id)).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -458,11 +601,16 @@ async fn update_tenant_settings_by_id(
         }
     };
 
-    let query = "UPDATE tenants SET settings = $2, updated_at = $3 WHERE id = $1 AND is_active = true RETURNING settings";
+    let query = format!(
+        "UPDATE tenants SET settings = {}, updated_at = $4 WHERE id = $1 AND is_active = true RETURNING settings",
+        keep_managed_settings(2, 3)
+    );
 
-    match client.query_opt(query, &[&tenant_id, &settings, &now]).await {
+    match client.query_opt(&query, &[&tenant_id, &settings, &MANAGED_SETTINGS, &now]).await {
         Ok(Some(row)) => {
             let updated_settings: serde_json::Value = row.get("settings");
+            // Cached templates carry the delimiters they were parsed with
+            state.template_engine.invalidate_tenant_templates(tenant_id);
             let response = ApiResponse::success(updated_settings, request_id);
             Ok(Json(response))
         }
@@ -473,3 +621,274 @@ async fn update_tenant_settings_by_id(
         }
     }
 }
+
+/// A tenant's rate limits and whether they are its own or the platform defaults
+#[derive(Debug, Serialize)]
+pub struct TenantRateLimitResponse {
+    pub tenant_id: Uuid,
+    pub max_requests: u32,
+    pub window_secs: u64,
+    pub is_default: bool,
+}
+
+/// Request body for setting a tenant's rate limits; `null` restores the defaults
+#[derive(Debug, Deserialize)]
+pub struct UpdateTenantRateLimitRequest {
+    pub rate_limit: Option<TenantRateLimit>,
+}
+
+fn rate_limit_response(state: &AppState, tenant_id: Uuid, own: Option<TenantRateLimit>) -> TenantRateLimitResponse {
+    let limit = own.unwrap_or_else(|| TenantRateLimit::from_config(&state.config.rate_limit));
+    TenantRateLimitResponse {
+        tenant_id,
+        max_requests: limit.max_requests,
+        window_secs: limit.window_secs,
+        is_default: own.is_none(),
+    }
+}
+
+/// Get a tenant's rate limits (platform admins only)
+async fn get_tenant_rate_limit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(tenant_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "read")?;
+    if !is_platform_admin(&state, &auth_context) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let client = tenant_client(state.db.postgres(), &TenantId::from_uuid(tenant_id)).await.map_err(|e| {
+        error!("Failed to get database connection: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let settings: serde_json::Value = match client
+        .query_opt("SELECT settings FROM tenants WHERE id = $1 AND is_active = true", &[&tenant_id])
+        .await
+    {
+        Ok(Some(row)) => row.get("settings"),
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to get tenant settings: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let own = TenantRateLimit::from_tenant_settings(&settings).unwrap_or_else(|e| {
+        error!(tenant_id = %tenant_id, "Stored rate limit is invalid: {:#}", e);
+        None
+    });
+    Ok(Json(ApiResponse::success(rate_limit_response(&state, tenant_id, own), request_id)))
+}
+
+/// Set a tenant's rate limits (platform admins only). Other nodes pick the change up once their
+/// cached copy expires, after at most `rate_limit.settings_cache_ttl_secs`.
+async fn update_tenant_rate_limit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(tenant_id): Path<Uuid>,
+    Json(request): Json<UpdateTenantRateLimitRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("tenants", "configure")?;
+    if !is_platform_admin(&state, &auth_context) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if let Some(limit) = &request.rate_limit {
+        limit.validate().map_err(|e| {
+            info!(tenant_id = %tenant_id, "Rejected rate limit: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+    }
+
+    let client = tenant_client(state.db.postgres(), &TenantId::from_uuid(tenant_id)).await.map_err(|e| {
+        error!("Failed to get database connection: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    // Only the rate_limit key changes, so concurrent edits to other settings are kept
+    let query = match request.rate_limit {
+        Some(_) => "UPDATE tenants SET settings = jsonb_set(COALESCE(settings, '{}'::jsonb), '{rate_limit}', $2), updated_at = NOW() WHERE id = $1 AND is_active = true",
+        None => "UPDATE tenants SET settings = COALESCE(settings, '{}'::jsonb) - 'rate_limit', updated_at = NOW() WHERE id = $1 AND is_active = true",
+    };
+    let rate_limit = serde_json::json!(request.rate_limit);
+    let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = match request.rate_limit {
+        Some(_) => vec![&tenant_id, &rate_limit],
+        None => vec![&tenant_id],
+    };
+    match client.execute(query, &params).await {
+        Ok(0) => return Err(StatusCode::NOT_FOUND),
+        Ok(_) => {}
+        Err(e) => {
+            error!("Failed to update tenant rate limit: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    state.tenant_rate_limiter.invalidate(tenant_id);
+    info!(tenant_id = %tenant_id, rate_limit = ?request.rate_limit, "Tenant rate limit updated");
+    Ok(Json(ApiResponse::success(rate_limit_response(&state, tenant_id, request.rate_limit), request_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{services::plans::PlanService, test_harness::TestApp, types::UserRole};
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_current_tenant_settings_and_usage() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = &app.tenant_a.admin;
+
+        let current = app.get("/api/tenants/current", admin).await;
+        assert_eq!(current.status, StatusCode::OK, "{}", current.body);
+        assert_eq!(current.body["data"]["slug"], "tenant-a");
+
+        let settings = json!({
+            "slugs": { "reserved": ["shop"] },
+            "template_syntax": { "variable": ["[[", "]]"] }
+        });
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let updated = app.send(app.request(Method::PUT, "/api/tenants/current/settings", &editor, Some(settings.clone()))).await;
+        assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+        assert_eq!(app.get("/api/tenants/current/settings", admin).await.body["data"]["slugs"], settings["slugs"]);
+
+        // Overlapping delimiters are refused
+        let clashing = json!({ "template_syntax": { "variable": ["{%", "%}"] } });
+        let response = app.send(app.request(Method::PUT, "/api/tenants/current/settings", admin, Some(clashing))).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+        let viewer = app.add_user(&app.tenant_a.id, UserRole::Viewer).await;
+        let usage = app.get("/api/tenants/current/usage", &viewer).await;
+        assert_eq!(usage.status, StatusCode::OK, "{}", usage.body);
+        assert_eq!(usage.body["data"]["used_bytes"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_settings_writes_cannot_change_the_plan() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = &app.tenant_a.admin;
+        let plans = PlanService::new(app.state.db.postgres().clone(), app.state.config.plans.clone());
+        let before = plans.tenant_plan(&app.tenant_a.id).await.unwrap();
+
+        let upgrade = json!({ "plan": "enterprise", "plan_limits": { "max_sites": null }, "timezone": "Europe/London" });
+        let response = app.send(app.request(Method::PUT, "/api/tenants/current/settings", admin, Some(upgrade.clone()))).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        let uri = format!("/api/tenants/{}", app.tenant_a.id);
+        let response = app.send(app.request(Method::PUT, &uri, admin, Some(json!({ "settings": upgrade })))).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        assert_eq!(plans.tenant_plan(&app.tenant_a.id).await.unwrap(), before);
+        let settings = app.get("/api/tenants/current/settings", admin).await.body["data"].clone();
+        assert_eq!(settings["timezone"], "Europe/London");
+        assert!(settings.get("plan").is_none() && settings.get("plan_limits").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_set_by_platform_admin_applies_to_the_tenant() {
+        let Some(app) = TestApp::start().await else { return };
+        let platform_tenant = *app.tenant_a.id.as_uuid();
+        let app = app.reconfigure(|config| config.auth.platform_tenant_id = Some(platform_tenant)).await;
+        let tenant_b = &app.tenant_b.admin;
+
+        let uri = format!("/api/tenants/{}/rate-limit", app.tenant_b.id);
+        let limit = Some(json!({ "rate_limit": { "max_requests": 3, "window_secs": 60 } }));
+        let response = app.send(app.request(Method::PUT, &uri, &app.tenant_a.admin, limit)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        // A wholesale settings update by the tenant's own admin keeps the limit
+        let uri = format!("/api/tenants/{}", app.tenant_b.id);
+        let update = Some(json!({ "settings": { "timezone": "Europe/London" } }));
+        let response = app.send(app.request(Method::PUT, &uri, tenant_b, update)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        assert_eq!(response.body["data"]["settings"]["rate_limit"]["max_requests"], 3);
+
+        assert_eq!(app.get("/api/tenants/current", tenant_b).await.status, StatusCode::OK);
+        assert_eq!(app.get("/api/tenants/current", tenant_b).await.status, StatusCode::OK);
+        let limited = app.get("/api/tenants/current", tenant_b).await;
+        assert_eq!(limited.status, StatusCode::TOO_MANY_REQUESTS);
+        // Other tenants keep the platform default
+        assert_eq!(app.get("/api/tenants/current", &app.tenant_a.admin).await.status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_admins_only_reach_their_own_tenant() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = &app.tenant_a.admin;
+        let other = app.tenant_b.id.as_uuid();
+
+        assert_eq!(app.get(&format!("/api/tenants/{}", other), admin).await.status, StatusCode::FORBIDDEN);
+        let response = app.send(app.request(Method::PUT, &format!("/api/tenants/{}/settings", other), admin, Some(json!({})))).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+        assert_eq!(app.get(&format!("/api/tenants/{}/rate-limit", app.tenant_a.id), admin).await.status, StatusCode::FORBIDDEN);
+        let response = app.post("/api/tenants", admin, json!({ "name": "Brontë Press", "slug": "bronte-press" })).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+        let listed = app.get("/api/tenants", admin).await;
+        assert_eq!(listed.status, StatusCode::OK, "{}", listed.body);
+        let slugs: Vec<_> = listed.body["data"].as_array().unwrap().iter().map(|tenant| tenant["slug"].clone()).collect();
+        assert_eq!(slugs, vec![json!("tenant-a")]);
+    }
+
+    #[tokio::test]
+    async fn test_platform_admin_bootstraps_and_manages_tenants() {
+        let Some(app) = TestApp::start().await else { return };
+        let platform_tenant = *app.tenant_a.id.as_uuid();
+        let app = app.reconfigure(|config| config.auth.platform_tenant_id = Some(platform_tenant)).await;
+        let admin = &app.tenant_a.admin;
+        let template_id: uuid::Uuid = app
+            .admin_pool
+            .get()
+            .await
+            .unwrap()
+            .query_one(
+                "INSERT INTO templates (name, html_source, is_public) VALUES ('Author', '<main></main>', true) RETURNING id",
+                &[],
+            )
+            .await
+            .unwrap()
+            .get(0);
+
+        let bootstrap = json!({
+            "tenant_name": "Brontë Press",
+            "tenant_slug": "bronte-press",
+            "admin_email": "charlotte@bronte.example.com",
+            "admin_first_name": "Charlotte",
+            "admin_last_name": "Brontë",
+            "template_id": template_id,
+        });
+        let created = app.post("/api/tenants/bootstrap", admin, bootstrap.clone()).await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        assert_eq!(created.body["data"]["page_ids"].as_array().unwrap().len(), 4);
+        let tenant_id = created.body["data"]["tenant_id"].as_str().unwrap().to_string();
+
+        let response = app.post("/api/tenants/bootstrap", admin, bootstrap).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+
+        let tenant = app.get(&format!("/api/tenants/{}", tenant_id), admin).await;
+        assert_eq!(tenant.body["data"]["slug"], "bronte-press");
+
+        let limited = app
+            .send(app.request(
+                Method::PUT,
+                &format!("/api/tenants/{}/rate-limit", tenant_id),
+                admin,
+                Some(json!({ "rate_limit": { "max_requests": 300, "window_secs": 60 } })),
+            ))
+            .await;
+        assert_eq!(limited.status, StatusCode::OK, "{}", limited.body);
+        let limit = app.get(&format!("/api/tenants/{}/rate-limit", tenant_id), admin).await;
+        assert_eq!((limit.body["data"]["max_requests"].as_u64(), limit.body["data"]["is_default"].as_bool()), (Some(300), Some(false)));
+
+        // Platform admins still need the admin role
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        assert_eq!(app.get(&format!("/api/tenants/{}", tenant_id), &editor).await.status, StatusCode::FORBIDDEN);
+    }
+}
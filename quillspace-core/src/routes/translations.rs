@@ -0,0 +1,134 @@
+use crate::{
+    auth::jwt_helpers::extract_auth_context_with_role,
+    services::translation::{TranslationError, TranslationService},
+    types::ApiResponse,
+    AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{get, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{error, info};
+use uuid::Uuid;
+
+/// Longest translation key accepted (matches the column width)
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Create translation management routes
+pub fn create_routes() -> Router<AppState> {
+    Router::new()
+        .route("/sites/:site_id", get(list_translations))
+        .route("/sites/:site_id/:key", put(upsert_translation).delete(delete_translation))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpsertTranslationRequest {
+    /// Text per locale, e.g. `{"en-US": "Welcome", "de-DE": "Willkommen"}`
+    pub values: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteTranslationQuery {
+    /// Delete only this locale; all locales of the key when absent
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranslationResponse {
+    pub key: String,
+    pub values: HashMap<String, String>,
+}
+
+/// List all translations of a site
+async fn list_translations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(site_id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "sites", "read").await?;
+    if !auth_context.allows_site(&site_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let service = TranslationService::new(state.db.postgres().clone());
+    match service.get_translations(&auth_context.tenant_id, site_id).await {
+        Ok(translations) => Ok(Json(ApiResponse::success(translations, request_id))),
+        Err(e) => {
+            error!(site_id = %site_id, error = %e, "Failed to list translations");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Set a key's text for one or more locales
+async fn upsert_translation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((site_id, key)): Path<(Uuid, String)>,
+    Json(request): Json<UpsertTranslationRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "sites", "update").await?;
+    if !auth_context.allows_site(&site_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if key.trim().is_empty()
+        || key.len() > MAX_KEY_LENGTH
+        || request.values.is_empty()
+        || request.values.keys().any(|locale| locale.trim().is_empty())
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let service = TranslationService::new(state.db.postgres().clone());
+    match service.upsert_translation(&auth_context.tenant_id, site_id, &key, &request.values).await {
+        Ok(()) => {
+            info!(site_id = %site_id, key = %key, locales = request.values.len(), "Translation saved");
+            let response = TranslationResponse { key, values: request.values };
+            Ok(Json(ApiResponse::success(response, request_id)))
+        }
+        Err(TranslationError::SiteNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!(site_id = %site_id, key = %key, error = %e, "Failed to save translation");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Delete a key, in every locale or just one
+async fn delete_translation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((site_id, key)): Path<(Uuid, String)>,
+    Query(query): Query<DeleteTranslationQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)?;
+    state.authorizer.require_context_permission(&auth_context, "sites", "update").await?;
+    if !auth_context.allows_site(&site_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let service = TranslationService::new(state.db.postgres().clone());
+    match service
+        .delete_translation(&auth_context.tenant_id, site_id, &key, query.locale.as_deref())
+        .await
+    {
+        Ok(true) => {
+            info!(site_id = %site_id, key = %key, locale = ?query.locale, "Translation deleted");
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!(site_id = %site_id, key = %key, error = %e, "Failed to delete translation");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -1,18 +1,24 @@
 use crate::{
-    auth::jwt_helpers::{extract_auth_context, extract_auth_context_with_role},
+    auth::jwt_helpers::extract_auth_context_with_role,
+    database::postgres::tenant_client,
+    services::public_url::{resolve_base_url, RequestOrigin},
+    services::request_validation::{Validate, ValidationErrors, MAX_NAME_LEN},
+    services::timezone::parse_timezone,
+    services::user_import::{split_name, UserImportError, UserImportService},
     types::{ApiResponse, User, UserRole},
     AppState,
 };
 use axum::{
-    extract::{Path, Query, State},
+    extract::{ConnectInfo, Path, Query, State},
     http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::{get, post, put},
     Json, Router,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+use std::net::SocketAddr;
 use tokio_postgres::{Row, Error as PgError};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 /// Helper function to convert a tokio-postgres Row to User
@@ -23,27 +29,13 @@ fn row_to_user(row: &Row) -> Result<User, PgError> {
         email: row.try_get("email")?,
         first_name: row.try_get("first_name")?,
         last_name: row.try_get("last_name")?,
-        role: match row.try_get::<_, String>("role")?.as_str() {
-            "admin" => UserRole::Admin,
-            "editor" => UserRole::Editor,
-            "viewer" => UserRole::Viewer,
-            _ => UserRole::Viewer, // Default fallback
-        },
-        is_active: row.try_get("is_active")?,
+        role: row.try_get("role")?,
+        is_active: row.try_get("active")?,
         created_at: row.try_get("created_at")?,
         updated_at: row.try_get("updated_at")?,
     })
 }
 
-/// Convert UserRole to string for database storage
-fn user_role_to_string(role: &UserRole) -> &'static str {
-    match role {
-        UserRole::Admin => "admin",
-        UserRole::Editor => "editor",
-        UserRole::Viewer => "viewer",
-    }
-}
-
 /// Query parameters for listing users
 #[derive(Debug, Deserialize)]
 pub struct ListUsersQuery {
@@ -66,6 +58,34 @@ pub struct UpdateUserRequest {
     pub name: Option<String>,
     pub role: Option<UserRole>,
     pub is_active: Option<bool>,
+    /// IANA timezone scheduling input is read in; empty falls back to the tenant's
+    pub timezone: Option<String>,
+}
+
+impl Validate for CreateUserRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.email("email", &self.email);
+        errors.required("name", &self.name);
+        errors.max_length("name", &self.name, MAX_NAME_LEN);
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdateUserRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if let Some(name) = &self.name {
+            errors.required("name", name);
+            errors.max_length("name", name, MAX_NAME_LEN);
+        }
+        if let Some(timezone) = self.timezone.as_deref().map(str::trim).filter(|name| !name.is_empty()) {
+            if let Err(e) = parse_timezone(timezone) {
+                errors.add("timezone", "invalid_timezone", e.to_string());
+            }
+        }
+        errors.into_result()
+    }
 }
 
 /// Create user management routes
@@ -73,6 +93,7 @@ pub fn create_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_users).post(create_user))
         .route("/current", get(get_current_user))
+        .route("/import", post(import_users))
         .route("/:user_id", get(get_user).put(update_user))
         .route("/:user_id/deactivate", put(deactivate_user))
 }
@@ -88,6 +109,7 @@ async fn list_users(
     // Verify admin authorization for user management
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("users", "read")?;
     
     if auth_context.user_role != UserRole::Admin {
         return Err(StatusCode::FORBIDDEN);
@@ -96,8 +118,7 @@ async fn list_users(
     let limit: u32 = params.limit.unwrap_or(20).min(100);
     let offset: u32 = params.offset.unwrap_or(0);
 
-    // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &auth_context.tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -105,24 +126,15 @@ async fn list_users(
         }
     };
 
-    // Set RLS context
-    if let Err(e) = client.execute(
-        "SELECT set_config('rls.tenant_id', $1, true)",
-        &[&auth_context.tenant_id.to_string()],
-    ).await {
-        error!("Failed to set RLS context: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
-
     // Build query with optional role filter
     let (query, role_param) = if let Some(role) = &params.role {
         (
-            "SELECT * FROM users WHERE role = $3 AND is_active = true ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+            "SELECT * FROM users WHERE role::text = $3 AND active = true ORDER BY created_at DESC LIMIT $1 OFFSET $2",
             Some(role.as_str()),
         )
     } else {
         (
-            "SELECT * FROM users WHERE is_active = true ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+            "SELECT * FROM users WHERE active = true ORDER BY created_at DESC LIMIT $1 OFFSET $2",
             None,
         )
     };
@@ -154,83 +166,89 @@ async fn list_users(
     }
 }
 
-/// Create a new user (admin only)
+/// Create a new user and invite them to set a password (admin only)
 async fn create_user(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Json(request): Json<CreateUserRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
+) -> Result<Response, StatusCode> {
     let request_id = Uuid::new_v4();
 
     // Verify admin authorization for user creation
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("users", "write")?;
     
     if auth_context.user_role != UserRole::Admin {
         return Err(StatusCode::FORBIDDEN);
     }
 
-    let user_id = Uuid::new_v4();
-    let now = chrono::Utc::now();
-    let role_str = user_role_to_string(&request.role);
+    if let Err(errors) = request.validate() {
+        return Ok(errors.into_response(request_id));
+    }
 
-    // Get database connection
-    let client = match state.db.postgres().get().await {
-        Ok(client) => client,
+    // Invites link to the platform app, never to a tenant's domain
+    let base_url = resolve_base_url(&state.config.public_url, None, &RequestOrigin::from_request(&state.config.proxy, &headers, Some(peer.ip())));
+
+    let invited = UserImportService::new(state.db.postgres().clone())
+        .invite(&auth_context.tenant_id, auth_context.user_id, &request.email, &request.name, request.role, &base_url)
+        .await;
+    let user_id = match invited {
+        Ok(Some((user_id, _))) => user_id,
+        Ok(None) => return Err(StatusCode::CONFLICT),
         Err(e) => {
-            error!("Failed to get database connection: {}", e);
+            error!("Failed to create user: {:#}", e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
 
-    // Set RLS context
-    if let Err(e) = client.execute(
-        "SELECT set_config('rls.tenant_id', $1, true)",
-        &[&auth_context.tenant_id.to_string()],
-    ).await {
-        error!("Failed to set RLS context: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    let user = get_user_by_id(state, user_id, auth_context.tenant_id, request_id).await?;
+    info!("Created user {} with ID {}", request.email, user_id);
+    Ok((StatusCode::CREATED, user).into_response())
+}
+
+/// Create users from a CSV body of `email,name,role` rows and invite them (admin only)
+async fn import_users(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<impl IntoResponse, StatusCode> {
+    let request_id = Uuid::new_v4();
+
+    let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("users", "write")?;
+
+    if auth_context.user_role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
     }
 
-    let query = r#"
-        INSERT INTO users (id, tenant_id, email, name, role, is_active, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-        RETURNING *
-        "#;
-
-    match client.query_one(
-        query,
-        &[
-            &user_id,
-            auth_context.tenant_id.as_uuid(),
-            &request.email,
-            &request.name,
-            &role_str,
-            &true,
-            &now,
-            &now,
-        ],
-    ).await {
-        Ok(row) => {
-            match row_to_user(&row) {
-                Ok(user) => {
-                    info!("Created user {} with ID {}", user.email, user.id);
-                    let response = ApiResponse::success(user, request_id);
-                    Ok((StatusCode::CREATED, Json(response)))
-                }
-                Err(e) => {
-                    error!("Failed to parse created user: {}", e);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
-            }
+    // Invites link to the platform app, never to a tenant's domain
+    let base_url = resolve_base_url(&state.config.public_url, None, &RequestOrigin::from_request(&state.config.proxy, &headers, Some(peer.ip())));
+
+    match UserImportService::new(state.db.postgres().clone())
+        .import(&auth_context.tenant_id, auth_context.user_id, &body, &base_url)
+        .await
+    {
+        Ok(report) => {
+            info!(
+                created = report.created,
+                skipped = report.skipped,
+                invalid = report.invalid,
+                "Imported users into tenant {}", auth_context.tenant_id
+            );
+            Ok(Json(ApiResponse::success(report, request_id)))
         }
+        Err(UserImportError::Database(e)) => {
+            error!("Failed to import users: {:#}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        Err(UserImportError::TooManyRows { .. }) => Err(StatusCode::PAYLOAD_TOO_LARGE),
         Err(e) => {
-            error!("Failed to create user: {}", e);
-            if e.to_string().contains("duplicate key") {
-                Err(StatusCode::CONFLICT)
-            } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
-            }
+            warn!("Rejected user import: {}", e);
+            Err(StatusCode::BAD_REQUEST)
         }
     }
 }
@@ -244,6 +262,7 @@ async fn get_current_user(
 
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("users", "read")?;
 
     get_user_by_id(state, auth_context.user_id, auth_context.tenant_id, request_id).await
 }
@@ -259,6 +278,7 @@ async fn get_user(
     // Verify user authorization
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("users", "read")?;
     
     // Admin can view any user in tenant, others can only view themselves
     if auth_context.user_role != UserRole::Admin && auth_context.user_id != user_id {
@@ -275,8 +295,7 @@ async fn get_user_by_id(
     tenant_id: crate::types::TenantId,
     request_id: Uuid,
 ) -> Result<Json<ApiResponse<User>>, StatusCode> {
-    // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -284,16 +303,7 @@ async fn get_user_by_id(
         }
     };
 
-    // Set RLS context
-    if let Err(e) = client.execute(
-        "SELECT set_config('rls.tenant_id', $1, true)",
-        &[&tenant_id.to_string()],
-    ).await {
-        error!("Failed to set RLS context: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
-
-    let query = "SELECT * FROM users WHERE id = $1 AND is_active = true";
+    let query = "SELECT * FROM users WHERE id = $1 AND active = true";
     
     match client.query_opt(query, &[&user_id]).await {
         Ok(Some(row)) => {
@@ -322,12 +332,13 @@ async fn update_user(
     headers: HeaderMap,
     Path(user_id): Path<Uuid>,
     Json(request): Json<UpdateUserRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
+) -> Result<Response, StatusCode> {
     let request_id = Uuid::new_v4();
 
     // Verify user authorization
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("users", "update")?;
     
     // Admin can update any user, others can only update themselves (limited fields)
     let can_update_role = auth_context.user_role == UserRole::Admin;
@@ -342,10 +353,13 @@ async fn update_user(
         return Err(StatusCode::FORBIDDEN);
     }
 
+    if let Err(errors) = request.validate() {
+        return Ok(errors.into_response(request_id));
+    }
+
     let now = chrono::Utc::now();
 
-    // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &auth_context.tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -353,42 +367,45 @@ async fn update_user(
         }
     };
 
-    // Set RLS context
-    if let Err(e) = client.execute(
-        "SELECT set_config('rls.tenant_id', $1, true)",
-        &[&auth_context.tenant_id.to_string()],
-    ).await {
-        error!("Failed to set RLS context: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
-
     // Build dynamic update query
     let mut set_clauses = Vec::new();
     let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&user_id];
     let mut param_count = 1;
 
+    let names;
     if let Some(name) = &request.name {
-        param_count += 1;
-        set_clauses.push(format!("name = ${}", param_count));
-        params.push(name);
+        names = split_name(name);
+        set_clauses.push(format!("first_name = ${}, last_name = ${}", param_count + 1, param_count + 2));
+        param_count += 2;
+        params.push(&names.0);
+        params.push(&names.1);
     }
 
-    let role_str_ref;
     if let Some(role) = &request.role {
-        role_str_ref = user_role_to_string(role);
         param_count += 1;
         set_clauses.push(format!("role = ${}", param_count));
-        params.push(&role_str_ref);
+        params.push(role);
     }
 
     if let Some(is_active) = &request.is_active {
         param_count += 1;
-        set_clauses.push(format!("is_active = ${}", param_count));
+        set_clauses.push(format!("active = ${}", param_count));
         params.push(is_active);
     }
 
+    let timezone_ref;
+    if let Some(name) = &request.timezone {
+        timezone_ref = match name.trim() {
+            "" => None,
+            name => Some(parse_timezone(name).map_err(|_| StatusCode::BAD_REQUEST)?.name()),
+        };
+        param_count += 1;
+        set_clauses.push(format!("timezone = ${}", param_count));
+        params.push(&timezone_ref);
+    }
+
     if set_clauses.is_empty() {
-        return get_user_by_id(state, user_id, auth_context.tenant_id, request_id).await;
+        return get_user_by_id(state, user_id, auth_context.tenant_id, request_id).await.map(IntoResponse::into_response);
     }
 
     param_count += 1;
@@ -396,7 +413,7 @@ async fn update_user(
     params.push(&now);
 
     let query = format!(
-        "UPDATE users SET {} WHERE id = $1 AND is_active = true RETURNING *",
+        "UPDATE users SET {} WHERE id = $1 AND active = true RETURNING *",
         set_clauses.join(", ")
     );
 
@@ -405,7 +422,7 @@ async fn update_user(
             match row_to_user(&row) {
                 Ok(user) => {
                     let response = ApiResponse::success(user, request_id);
-                    Ok(Json(response))
+                    Ok(Json(response).into_response())
                 }
                 Err(e) => {
                     error!("Failed to parse updated user: {}", e);
@@ -432,6 +449,7 @@ async fn deactivate_user(
     // Verify admin authorization for user deactivation
     let auth_context = extract_auth_context_with_role(&headers, &state.jwt_manager)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    auth_context.require_scope("users", "delete")?;
     
     if auth_context.user_role != UserRole::Admin {
         return Err(StatusCode::FORBIDDEN);
@@ -444,8 +462,7 @@ async fn deactivate_user(
 
     let now = chrono::Utc::now();
 
-    // Get database connection
-    let client = match state.db.postgres().get().await {
+    let client = match tenant_client(state.db.postgres(), &auth_context.tenant_id).await {
         Ok(client) => client,
         Err(e) => {
             error!("Failed to get database connection: {}", e);
@@ -453,16 +470,7 @@ async fn deactivate_user(
         }
     };
 
-    // Set RLS context
-    if let Err(e) = client.execute(
-        "SELECT set_config('rls.tenant_id', $1, true)",
-        &[&auth_context.tenant_id.to_string()],
-    ).await {
-        error!("Failed to set RLS context: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
-
-    let query = "UPDATE users SET is_active = false, updated_at = $2 WHERE id = $1 AND is_active = true RETURNING *";
+    let query = "UPDATE users SET active = false, updated_at = $2 WHERE id = $1 AND active = true RETURNING *";
 
     match client.query_opt(query, &[&user_id, &now]).await {
         Ok(Some(row)) => {
@@ -485,3 +493,80 @@ async fn deactivate_user(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_harness::TestApp, types::UserRole};
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_import_route_creates_and_invites_users() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = &app.tenant_a.admin;
+        let csv = "email,name,role\nanne@bronte.example.com,Anne Brontë,editor\nnot-an-email,Emily,\n";
+
+        let (mut parts, _) = app.request(Method::POST, "/api/users/import", admin, None).into_parts();
+        parts.headers.insert(header::CONTENT_TYPE, "text/csv".parse().unwrap());
+        let response = app.send(Request::from_parts(parts, Body::from(csv))).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        assert_eq!(response.body["data"]["created"], 1);
+        assert_eq!(response.body["data"]["invalid"], 1);
+
+        let editor = app.add_user(&app.tenant_a.id, UserRole::Editor).await;
+        let response = app.send(app.request(Method::POST, "/api/users/import", &editor, None)).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_users_are_managed_within_the_tenant() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = &app.tenant_a.admin;
+
+        let created = app
+            .post("/api/users", admin, json!({ "email": "anne@bronte.example.com", "name": "Anne Brontë", "role": "Editor" }))
+            .await;
+        assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+        assert_eq!(created.body["data"]["first_name"], "Anne");
+        assert_eq!(created.body["data"]["role"], "Editor");
+        let user_id = created.body["data"]["id"].as_str().unwrap().to_string();
+
+        let duplicate = app
+            .post("/api/users", admin, json!({ "email": "Anne@bronte.example.com", "name": "Anne", "role": "Viewer" }))
+            .await;
+        assert_eq!(duplicate.status, StatusCode::CONFLICT);
+
+        let updated = app
+            .send(app.request(
+                Method::PUT,
+                &format!("/api/users/{}", user_id),
+                admin,
+                Some(json!({ "name": "Acton Bell", "role": "Viewer" })),
+            ))
+            .await;
+        assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+        assert_eq!((updated.body["data"]["first_name"].as_str(), updated.body["data"]["last_name"].as_str()), (Some("Acton"), Some("Bell")));
+        assert_eq!(updated.body["data"]["role"], "Viewer");
+
+        let listed = app.get("/api/users?role=viewer", admin).await;
+        assert_eq!(listed.status, StatusCode::OK, "{}", listed.body);
+        let ids: Vec<_> = listed.body["data"].as_array().unwrap().iter().map(|user| user["id"].as_str().unwrap().to_string()).collect();
+        assert_eq!(ids, vec![user_id.clone()]);
+
+        // Another tenant's admin can't see or deactivate the user
+        let other = &app.tenant_b.admin;
+        assert_eq!(app.get(&format!("/api/users/{}", user_id), other).await.status, StatusCode::NOT_FOUND);
+        let response = app.send(app.request(Method::PUT, &format!("/api/users/{}/deactivate", user_id), other, None)).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        let response = app.send(app.request(Method::PUT, &format!("/api/users/{}/deactivate", user_id), admin, None)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        assert_eq!(app.get(&format!("/api/users/{}", user_id), admin).await.status, StatusCode::NOT_FOUND);
+
+        let current = app.get("/api/users/current", admin).await;
+        assert_eq!(current.body["data"]["email"], admin.email.as_str());
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{auth::webhooks::WebhookError, AppState};
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use tracing::{error, info, warn};
+
+/// Create inbound webhook routes
+pub fn create_routes() -> Router<AppState> {
+    Router::new().route("/:provider", post(receive_webhook))
+}
+
+/// Receive a webhook; the signature is checked against the raw body before any handler runs
+async fn receive_webhook(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    match state.webhooks.dispatch(&provider, &headers, body.to_vec()).await {
+        Ok(()) => {
+            info!(provider = %provider, "Webhook processed");
+            StatusCode::NO_CONTENT
+        }
+        Err(e @ WebhookError::Handler(_)) => {
+            error!(provider = %provider, error = %e, "Webhook handler failed");
+            e.status_code()
+        }
+        Err(e) => {
+            warn!(provider = %provider, error = %e, "Webhook rejected");
+            e.status_code()
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::types::{TenantId, UserRole};
+use anyhow::Context;
+use deadpool_postgres::Pool;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Serialize;
+use tokio_postgres::error::SqlState;
+use uuid::Uuid;
+
+/// Shortest password accepted for an admin
+pub const MIN_ADMIN_PASSWORD_LEN: usize = 12;
+
+const GENERATED_PASSWORD_LEN: usize = 24;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AdminAccountError {
+    #[error("Invalid admin: {0}")]
+    Invalid(String),
+
+    #[error("Tenant '{0}' does not exist; give a tenant name to create it")]
+    TenantNotFound(String),
+
+    #[error("{0} already exists")]
+    Conflict(String),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// An admin to create, for deployment bootstrap
+#[derive(Debug, Clone)]
+pub struct NewAdmin {
+    pub tenant_slug: String,
+    /// Creates the tenant when no tenant has the slug
+    pub tenant_name: Option<String>,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub password: String,
+}
+
+impl NewAdmin {
+    pub fn validate(&self) -> Result<(), AdminAccountError> {
+        let invalid = |reason: &str| Err(AdminAccountError::Invalid(reason.to_string()));
+
+        if self.tenant_slug.trim().is_empty() {
+            return invalid("a tenant slug is required");
+        }
+        if !self.email.contains('@') {
+            return invalid("the email is not an email address");
+        }
+        if self.first_name.trim().is_empty() {
+            return invalid("a first name is required");
+        }
+        if self.password.chars().count() < MIN_ADMIN_PASSWORD_LEN {
+            return Err(AdminAccountError::Invalid(format!(
+                "the password must be at least {} characters",
+                MIN_ADMIN_PASSWORD_LEN
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The admin created, and the tenant it belongs to
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedAdmin {
+    pub tenant_id: TenantId,
+    pub user_id: Uuid,
+    pub tenant_created: bool,
+}
+
+/// A random password for an admin created without one
+pub fn generate_password() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(GENERATED_PASSWORD_LEN)
+        .map(char::from)
+        .collect()
+}
+
+/// Create an admin who can log in straight away, and the tenant with it if needed.
+///
+/// Finding a tenant by slug reads `tenants` without a tenant context, so this needs the
+/// database owner's connection, as migrations do.
+pub async fn create_admin(pool: &Pool, admin: &NewAdmin) -> Result<CreatedAdmin, AdminAccountError> {
+    admin.validate()?;
+    let password_hash = bcrypt::hash(&admin.password, bcrypt::DEFAULT_COST).context("Failed to hash password")?;
+    let slug = admin.tenant_slug.trim();
+
+    let mut client = pool.get().await.context("Failed to get database connection")?;
+    let transaction = client.transaction().await.context("Failed to start transaction")?;
+
+    let existing: Option<Uuid> = transaction
+        .query_opt("SELECT id FROM tenants WHERE slug = $1", &[&slug])
+        .await
+        .context("Failed to look up tenant")?
+        .map(|row| row.get(0));
+    let (tenant_id, tenant_created) = match (existing, &admin.tenant_name) {
+        (Some(id), _) => (TenantId::from_uuid(id), false),
+        (None, Some(_)) => (TenantId::new(), true),
+        (None, None) => return Err(AdminAccountError::TenantNotFound(slug.to_string())),
+    };
+
+    // Set before creating the tenant, so its insert passes the tenants policy too
+    transaction
+        .execute(
+            "SELECT set_config('quillspace.tenant_id', $1, true)",
+            &[&tenant_id.to_string()],
+        )
+        .await
+        .context("Failed to set RLS tenant context")?;
+
+    if let (true, Some(name)) = (tenant_created, &admin.tenant_name) {
+        transaction
+            .execute(
+                "INSERT INTO tenants (id, name, slug) VALUES ($1, $2, $3)",
+                &[tenant_id.as_uuid(), &name.trim(), &slug],
+            )
+            .await
+            .map_err(|e| conflict_on_duplicate(e, "tenant slug"))?;
+    }
+
+    let user_id: Uuid = transaction
+        .query_one(
+            "INSERT INTO users (tenant_id, email, password_hash, first_name, last_name, role)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id",
+            &[
+                tenant_id.as_uuid(),
+                &admin.email.trim().to_lowercase(),
+                &password_hash,
+                &admin.first_name.trim(),
+                &admin.last_name.trim(),
+                &UserRole::Admin,
+            ],
+        )
+        .await
+        .map_err(|e| conflict_on_duplicate(e, "user with this email"))?
+        .get(0);
+
+    transaction.commit().await.context("Failed to commit admin")?;
+    Ok(CreatedAdmin { tenant_id, user_id, tenant_created })
+}
+
+fn conflict_on_duplicate(e: tokio_postgres::Error, what: &str) -> AdminAccountError {
+    if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+        AdminAccountError::Conflict(what.to_string())
+    } else {
+        AdminAccountError::Database(anyhow::Error::new(e).context(format!("Failed to create {}", what)))
+    }
+}
@@ -1,14 +1,39 @@
 use crate::{
     database::clickhouse::AnalyticsService as ClickHouseAnalyticsService,
+    services::http_client::{self, OutboundHttpError},
+    services::timezone,
     types::{AnalyticsEvent, TenantId},
 };
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tracing::{error, info};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// Cookie carrying the visitor's first-party analytics consent (`granted` or `denied`)
+pub const ANALYTICS_CONSENT_COOKIE: &str = "qs_analytics_consent";
+
+/// Whether a `Cookie` header grants analytics consent. Anything other than an
+/// explicit `granted` counts as no consent.
+pub fn analytics_consent_granted(cookie_header: Option<&str>) -> bool {
+    cookie_header
+        .into_iter()
+        .flat_map(|header| header.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .any(|(name, value)| name == ANALYTICS_CONSENT_COOKIE && value.trim_matches('"') == "granted")
+}
+
+/// Maximum number of sub-queries accepted in a single batch request
+pub const MAX_BATCH_QUERIES: usize = 8;
+
+/// Overall time budget for a batch; sub-queries still running are cancelled
+pub const BATCH_TIMEOUT: Duration = Duration::from_secs(10);
+
 // Tinybird integration structures
 #[derive(Debug, Serialize)]
 struct TinybirdEvent {
@@ -49,14 +74,14 @@ impl AnalyticsService {
     pub fn new_clickhouse(clickhouse_service: ClickHouseAnalyticsService) -> Self {
         Self {
             backend: AnalyticsBackend::ClickHouse(clickhouse_service),
-            http_client: reqwest::Client::new(),
+            http_client: http_client::shared_client(),
         }
     }
 
     pub fn new_tinybird(api_url: String, token: String, datasource: String) -> Self {
         Self {
             backend: AnalyticsBackend::Tinybird { api_url, token, datasource },
-            http_client: reqwest::Client::new(),
+            http_client: http_client::shared_client(),
         }
     }
 
@@ -71,11 +96,11 @@ impl AnalyticsService {
                 tinybird_url,
                 tinybird_token,
             },
-            http_client: reqwest::Client::new(),
+            http_client: http_client::shared_client(),
         }
     }
 
-    /// Record a page view event
+    /// Record a page view event; `anonymize` strips visitor identifiers when consent is missing
     pub async fn record_page_view(
         &self,
         tenant_id: &TenantId,
@@ -84,21 +109,17 @@ impl AnalyticsService {
         session_id: Option<String>,
         ip_address: Option<String>,
         user_agent: Option<String>,
+        anonymize: bool,
     ) -> Result<()> {
-        let event = AnalyticsEvent {
-            event_id: Uuid::new_v4(),
-            tenant_id: *tenant_id.as_uuid(),
+        let event = AnalyticsEvent::page_view(
+            tenant_id,
             user_id,
-            event_type: "page_view".to_string(),
-            event_data: serde_json::json!({
-                "page_path": page_path,
-                "timestamp": Utc::now()
-            }),
-            timestamp: Utc::now(),
+            page_path,
             session_id,
             ip_address,
             user_agent,
-        };
+            anonymize,
+        );
 
         // Route to appropriate backend
         match &self.backend {
@@ -150,7 +171,8 @@ impl AnalyticsService {
             .header("Authorization", format!("Bearer {}", token))
             .json(&tinybird_event)
             .send()
-            .await?;
+            .await
+            .map_err(|e| OutboundHttpError::from_reqwest("Tinybird", e))?;
 
         if !response.status().is_success() {
             anyhow::bail!("Tinybird API error: {}", response.status());
@@ -272,11 +294,12 @@ impl AnalyticsService {
         Ok(())
     }
 
-    /// Get comprehensive dashboard data
+    /// Get comprehensive dashboard data, with days bucketed in the tenant's timezone `tz`
     pub async fn get_dashboard_data(
         &self,
         tenant_id: &TenantId,
         days: u32,
+        tz: Tz,
     ) -> Result<DashboardData> {
         // Only ClickHouse backend supports dashboard data for now
         let (stats, top_content) = match &self.backend {
@@ -298,7 +321,7 @@ impl AnalyticsService {
         };
         
         // Get additional metrics
-        let daily_stats = self.get_daily_stats(tenant_id, days).await?;
+        let daily_stats = self.get_daily_stats(tenant_id, days, tz).await?;
         let user_engagement = self.get_user_engagement_metrics(tenant_id, days).await?;
 
         Ok(DashboardData {
@@ -316,47 +339,163 @@ impl AnalyticsService {
         })
     }
 
-    /// Get daily statistics for charting
-    async fn get_daily_stats(
+    /// Execute a single dashboard query for the tenant, bucketing days in its timezone `tz`
+    pub async fn execute_query(&self, tenant_id: &TenantId, query: &AnalyticsQuery, tz: Tz) -> Result<serde_json::Value> {
+        let clickhouse = match &self.backend {
+            AnalyticsBackend::ClickHouse(service) => service,
+            AnalyticsBackend::Hybrid { clickhouse, .. } => clickhouse,
+            AnalyticsBackend::Tinybird { .. } => {
+                anyhow::bail!("Dashboard queries not available for Tinybird-only backend");
+            }
+        };
+
+        let value = match query {
+            AnalyticsQuery::TenantStats { days } => {
+                serde_json::to_value(clickhouse.get_tenant_stats(tenant_id, capped_days(*days)).await?)?
+            }
+            AnalyticsQuery::TopContent { days, limit } => {
+                let limit = limit.unwrap_or(10).min(100);
+                serde_json::to_value(clickhouse.get_top_content(tenant_id, capped_days(*days), limit).await?)?
+            }
+            AnalyticsQuery::DailyStats { days } => {
+                serde_json::to_value(self.get_daily_stats(tenant_id, capped_days(*days), tz).await?)?
+            }
+            AnalyticsQuery::UserActivity { user_id, days } => {
+                serde_json::to_value(clickhouse.get_user_activity(tenant_id, user_id, capped_days(*days), tz).await?)?
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Get daily statistics for charting, one bucket per local day in `tz`.
+    ///
+    /// Completed days are read from the `events_daily` rollup; today is still
+    /// being written, so it is always computed from raw events. The rollup is
+    /// bucketed by UTC date, so other timezones are computed from raw events.
+    pub async fn get_daily_stats(
         &self,
         tenant_id: &TenantId,
         days: u32,
+        tz: Tz,
+    ) -> Result<Vec<DailyStats>> {
+        if tz != Tz::UTC {
+            return self.get_local_daily_stats(tenant_id, days, tz).await;
+        }
+
+        let rollup_query = r#"
+            SELECT
+                date,
+                sum(total_events) as total_events,
+                uniqMerge(unique_users) as unique_users,
+                uniqMerge(unique_sessions) as unique_sessions,
+                sum(page_views) as page_views
+            FROM events_daily
+            WHERE tenant_id = ? AND date >= today() - ? AND date < today()
+            GROUP BY date
+            ORDER BY date
+        "#;
+
+        let client = self.clickhouse_client("Daily stats")?;
+
+        let mut stats = if days > 0 {
+            client
+                .query(rollup_query)
+                .bind(tenant_id.as_uuid())
+                .bind(days)
+                .fetch_all::<DailyStatsRow>()
+                .await?
+        } else {
+            Vec::new()
+        };
+        stats.extend(self.query_raw_daily_stats(tenant_id, "date = today()", None).await?);
+
+        Ok(stats.into_iter().map(DailyStats::from).collect())
+    }
+
+    /// Daily statistics from raw events, bucketed by local date in `tz`
+    async fn get_local_daily_stats(
+        &self,
+        tenant_id: &TenantId,
+        days: u32,
+        tz: Tz,
     ) -> Result<Vec<DailyStats>> {
         let query = r#"
             SELECT
-                toDate(timestamp) as date,
+                toDate(timestamp, ?) AS date,
                 count() as total_events,
                 uniq(user_id) as unique_users,
                 uniq(session_id) as unique_sessions,
                 countIf(event_type = 'page_view') as page_views
             FROM events
-            WHERE tenant_id = ? AND timestamp >= now() - INTERVAL ? DAY
+            WHERE tenant_id = ? AND timestamp >= fromUnixTimestamp64Milli(?)
             GROUP BY date
             ORDER BY date
         "#;
 
-        let client = match &self.backend {
-            AnalyticsBackend::ClickHouse(service) => service.client(),
-            AnalyticsBackend::Hybrid { clickhouse, .. } => clickhouse.client(),
-            AnalyticsBackend::Tinybird { .. } => {
-                anyhow::bail!("Daily stats not available for Tinybird-only backend");
-            }
-        };
-
-        let results = client
+        let start = timezone::daily_window_start(Utc::now(), days, tz);
+        let rows = self.clickhouse_client("Daily stats")?
             .query(query)
+            .bind(tz.name())
             .bind(tenant_id.as_uuid())
-            .bind(days)
+            .bind(start.timestamp_millis())
             .fetch_all::<DailyStatsRow>()
             .await?;
 
-        Ok(results.into_iter().map(|row| DailyStats {
-            date: row.date,
-            total_events: row.total_events,
-            unique_users: row.unique_users,
-            unique_sessions: row.unique_sessions,
-            page_views: row.page_views,
-        }).collect())
+        Ok(rows.into_iter().map(DailyStats::from).collect())
+    }
+
+    /// Compute daily statistics for the same window directly from raw events
+    pub async fn get_daily_stats_raw(
+        &self,
+        tenant_id: &TenantId,
+        days: u32,
+    ) -> Result<Vec<DailyStats>> {
+        let rows = self.query_raw_daily_stats(tenant_id, "date >= today() - ?", Some(days)).await?;
+        Ok(rows.into_iter().map(DailyStats::from).collect())
+    }
+
+    async fn query_raw_daily_stats(
+        &self,
+        tenant_id: &TenantId,
+        window: &str,
+        days: Option<u32>,
+    ) -> Result<Vec<DailyStatsRow>> {
+        let query = format!(
+            r#"
+            SELECT
+                date,
+                count() as total_events,
+                uniq(user_id) as unique_users,
+                uniq(session_id) as unique_sessions,
+                countIf(event_type = 'page_view') as page_views
+            FROM events
+            WHERE tenant_id = ? AND {}
+            GROUP BY date
+            ORDER BY date
+            "#,
+            window
+        );
+
+        let mut query = self.clickhouse_client("Daily stats")?
+            .query(&query)
+            .bind(tenant_id.as_uuid());
+        if let Some(days) = days {
+            query = query.bind(days);
+        }
+
+        Ok(query.fetch_all::<DailyStatsRow>().await?)
+    }
+
+    /// ClickHouse client backing this service, if the backend has one
+    fn clickhouse_client(&self, feature: &str) -> Result<&clickhouse::Client> {
+        match &self.backend {
+            AnalyticsBackend::ClickHouse(service) => Ok(service.client()),
+            AnalyticsBackend::Hybrid { clickhouse, .. } => Ok(clickhouse.client()),
+            AnalyticsBackend::Tinybird { .. } => {
+                anyhow::bail!("{} not available for Tinybird-only backend", feature);
+            }
+        }
     }
 
     /// Get user engagement metrics
@@ -462,6 +601,120 @@ impl AnalyticsService {
     }
 }
 
+/// Cap a requested window at one year, defaulting to a week
+fn capped_days(days: Option<u32>) -> u32 {
+    days.unwrap_or(7).min(365)
+}
+
+/// A single dashboard query within a batch request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnalyticsQuery {
+    TenantStats { days: Option<u32> },
+    TopContent { days: Option<u32>, limit: Option<u32> },
+    DailyStats { days: Option<u32> },
+    UserActivity { user_id: Uuid, days: Option<u32> },
+}
+
+impl AnalyticsQuery {
+    /// Queries exposing per-user data are restricted to admins
+    pub fn requires_admin(&self) -> bool {
+        matches!(self, AnalyticsQuery::UserActivity { .. })
+    }
+}
+
+/// Query spec named by the client so results can be matched up
+#[derive(Debug, Clone, Deserialize)]
+pub struct NamedAnalyticsQuery {
+    pub name: String,
+    #[serde(flatten)]
+    pub query: AnalyticsQuery,
+}
+
+/// Outcome of one sub-query; a failing query does not fail the whole batch
+#[derive(Debug, Serialize)]
+pub struct BatchQueryResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Batch request errors
+#[derive(Debug, thiserror::Error)]
+pub enum BatchQueryError {
+    #[error("Batch must contain at least one query")]
+    Empty,
+
+    #[error("Batch contains {0} queries, maximum is {max}", max = MAX_BATCH_QUERIES)]
+    TooManyQueries(usize),
+
+    #[error("Duplicate query name: {0}")]
+    DuplicateName(String),
+
+    #[error("Batch timed out")]
+    TimedOut,
+}
+
+/// Check the batch size and that every query has a unique, non-empty name
+pub fn validate_batch(queries: &[NamedAnalyticsQuery]) -> Result<(), BatchQueryError> {
+    if queries.is_empty() {
+        return Err(BatchQueryError::Empty);
+    }
+    if queries.len() > MAX_BATCH_QUERIES {
+        return Err(BatchQueryError::TooManyQueries(queries.len()));
+    }
+
+    let mut names = HashSet::new();
+    for query in queries {
+        if query.name.trim().is_empty() || !names.insert(query.name.as_str()) {
+            return Err(BatchQueryError::DuplicateName(query.name.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Run a validated batch concurrently within `timeout`, keyed by query name
+pub async fn run_batch<F, Fut>(
+    queries: Vec<NamedAnalyticsQuery>,
+    timeout: Duration,
+    execute: F,
+) -> Result<HashMap<String, BatchQueryResult>, BatchQueryError>
+where
+    F: Fn(AnalyticsQuery) -> Fut,
+    Fut: Future<Output = Result<serde_json::Value>> + Send + 'static,
+{
+    validate_batch(&queries)?;
+
+    let mut tasks = JoinSet::new();
+    for NamedAnalyticsQuery { name, query } in queries {
+        let future = execute(query);
+        tasks.spawn(async move { (name, future.await) });
+    }
+
+    let collect = async {
+        let mut results = HashMap::new();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((name, Ok(data))) => {
+                    results.insert(name, BatchQueryResult { data: Some(data), error: None });
+                }
+                Ok((name, Err(e))) => {
+                    warn!(query = %name, error = %e, "Batch analytics query failed");
+                    results.insert(name, BatchQueryResult { data: None, error: Some(e.to_string()) });
+                }
+                Err(e) => error!("Batch analytics task panicked: {}", e),
+            }
+        }
+        results
+    };
+
+    // Dropping the JoinSet on timeout aborts any queries still in flight
+    tokio::time::timeout(timeout, collect)
+        .await
+        .map_err(|_| BatchQueryError::TimedOut)
+}
+
 // Data structures
 #[derive(Debug, Serialize)]
 pub struct DashboardData {
@@ -481,7 +734,7 @@ pub struct OverviewStats {
     pub content_published: u64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, PartialEq)]
 pub struct DailyStats {
     pub date: chrono::NaiveDate,
     pub total_events: u64,
@@ -525,6 +778,18 @@ struct DailyStatsRow {
     page_views: u64,
 }
 
+impl From<DailyStatsRow> for DailyStats {
+    fn from(row: DailyStatsRow) -> Self {
+        DailyStats {
+            date: row.date,
+            total_events: row.total_events,
+            unique_users: row.unique_users,
+            unique_sessions: row.unique_sessions,
+            page_views: row.page_views,
+        }
+    }
+}
+
 #[derive(clickhouse::Row, Deserialize)]
 struct UserEngagementRow {
     avg_session_length: f64,
@@ -540,3 +805,199 @@ struct EventTypeStatsRow {
     unique_users: u64,
     unique_sessions: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::clickhouse::TenantScope;
+    use serde_json::json;
+
+    fn batch(types: &[&str]) -> Vec<NamedAnalyticsQuery> {
+        types
+            .iter()
+            .enumerate()
+            .map(|(i, query_type)| {
+                serde_json::from_value(json!({ "name": format!("q{}", i), "type": query_type, "days": 30 }))
+                    .expect("Invalid query spec")
+            })
+            .collect()
+    }
+
+    /// Stand-in for ClickHouse that echoes the query type back
+    async fn fake_execute(query: AnalyticsQuery) -> Result<serde_json::Value> {
+        Ok(match query {
+            AnalyticsQuery::TenantStats { days } => json!({ "kind": "tenant_stats", "days": days }),
+            AnalyticsQuery::TopContent { .. } => json!({ "kind": "top_content" }),
+            AnalyticsQuery::DailyStats { .. } => json!({ "kind": "daily_stats" }),
+            AnalyticsQuery::UserActivity { .. } => anyhow::bail!("not allowed in this test"),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_batch_returns_all_results() {
+        let queries = batch(&["tenant_stats", "top_content", "daily_stats"]);
+        let results = run_batch(queries, BATCH_TIMEOUT, fake_execute)
+            .await
+            .expect("Batch failed");
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results["q0"].data, Some(json!({ "kind": "tenant_stats", "days": 30 })));
+        assert_eq!(results["q1"].data, Some(json!({ "kind": "top_content" })));
+        assert_eq!(results["q2"].data, Some(json!({ "kind": "daily_stats" })));
+    }
+
+    #[tokio::test]
+    async fn test_batch_over_cap_rejected() {
+        let queries = batch(&["tenant_stats"; MAX_BATCH_QUERIES + 1]);
+        let result = run_batch(queries, BATCH_TIMEOUT, fake_execute).await;
+
+        assert!(matches!(result, Err(BatchQueryError::TooManyQueries(n)) if n == MAX_BATCH_QUERIES + 1));
+    }
+
+    #[tokio::test]
+    async fn test_batch_times_out() {
+        let queries = batch(&["tenant_stats"]);
+        let result = run_batch(queries, Duration::from_millis(20), |_| async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(json!(null))
+        })
+        .await;
+
+        assert!(matches!(result, Err(BatchQueryError::TimedOut)));
+    }
+
+    /// Record `count` events for the tenant on the day `days_ago`
+    async fn record_events(service: &ClickHouseAnalyticsService, tenant_id: &TenantId, days_ago: i64, count: usize) {
+        for i in 0..count {
+            record_event_at(service, tenant_id, Utc::now() - chrono::Duration::days(days_ago), i).await;
+        }
+    }
+
+    async fn record_event_at(
+        service: &ClickHouseAnalyticsService,
+        tenant_id: &TenantId,
+        timestamp: DateTime<Utc>,
+        i: usize,
+    ) {
+        let event = AnalyticsEvent {
+            event_id: Uuid::new_v4(),
+            tenant_id: *tenant_id.as_uuid(),
+            user_id: Some(Uuid::new_v4()),
+            event_type: if i.is_multiple_of(2) { "page_view" } else { "content_create" }.to_string(),
+            event_data: json!({}),
+            timestamp,
+            session_id: Some(format!("session-{}", i % 3)),
+            ip_address: None,
+            user_agent: None,
+        };
+        service.record_event(&event).await.expect("Failed to record test event");
+    }
+
+    #[tokio::test]
+    async fn test_rollup_daily_stats_match_raw() {
+        let Some(client) = crate::database::clickhouse::test_client().await else {
+            return;
+        };
+        let service = ClickHouseAnalyticsService::new(client.clone());
+        let analytics = AnalyticsService::new_clickhouse(service.clone());
+        let tenant_id = TenantId::new();
+
+        for (days_ago, count) in [(0, 4), (1, 3), (3, 5), (6, 2)] {
+            record_events(&service, &tenant_id, days_ago, count).await;
+        }
+
+        let raw = analytics.get_daily_stats_raw(&tenant_id, 7).await.expect("Raw daily stats failed");
+        let rolled_up = analytics.get_daily_stats(&tenant_id, 7, Tz::UTC).await.expect("Rollup daily stats failed");
+        assert_eq!(raw.len(), 4);
+        assert_eq!(rolled_up, raw);
+
+        // Backfilling the same window must not double count
+        let today = Utc::now().date_naive();
+        crate::database::clickhouse::backfill_daily_rollups(
+            &client,
+            TenantScope::Only(tenant_id.as_uuid()),
+            today - chrono::Duration::days(7),
+            today - chrono::Duration::days(1),
+        )
+        .await
+        .expect("Backfill failed");
+        let rolled_up = analytics.get_daily_stats(&tenant_id, 7, Tz::UTC).await.expect("Rollup daily stats failed");
+        assert_eq!(rolled_up, raw);
+    }
+
+    #[tokio::test]
+    async fn test_daily_stats_bucket_in_tenant_timezone() {
+        let Some(client) = crate::database::clickhouse::test_client().await else {
+            return;
+        };
+        let service = ClickHouseAnalyticsService::new(client);
+        let analytics = AnalyticsService::new_clickhouse(service.clone());
+        let tenant_id = TenantId::new();
+        let los_angeles = chrono_tz::America::Los_Angeles;
+
+        // 23:30 yesterday in Los Angeles is the next day in UTC
+        let yesterday = timezone::local_date(Utc::now(), los_angeles) - chrono::Duration::days(1);
+        let late_evening = timezone::local_to_utc(yesterday.and_hms_opt(23, 30, 0).unwrap(), los_angeles)
+            .expect("23:30 always exists");
+        record_event_at(&service, &tenant_id, late_evening, 0).await;
+
+        let local = analytics.get_daily_stats(&tenant_id, 7, los_angeles).await.expect("Local daily stats failed");
+        assert_eq!(local.iter().map(|day| day.date).collect::<Vec<_>>(), vec![yesterday]);
+
+        let utc = analytics.get_daily_stats_raw(&tenant_id, 7).await.expect("Raw daily stats failed");
+        assert_eq!(utc.iter().map(|day| day.date).collect::<Vec<_>>(), vec![late_evening.date_naive()]);
+        assert_ne!(late_evening.date_naive(), yesterday);
+    }
+
+    #[test]
+    fn test_consent_cookie_parsing() {
+        assert!(analytics_consent_granted(Some("theme=dark; qs_analytics_consent=granted")));
+        assert!(!analytics_consent_granted(Some("qs_analytics_consent=denied")));
+        assert!(!analytics_consent_granted(Some("other_consent=granted")));
+        assert!(!analytics_consent_granted(None));
+    }
+
+    fn page_view(consent_granted: bool) -> AnalyticsEvent {
+        AnalyticsEvent::page_view(
+            &TenantId::new(),
+            Some(Uuid::new_v4()),
+            "/about",
+            Some("session-1".to_string()),
+            Some("203.0.113.7".to_string()),
+            Some("Mozilla/5.0".to_string()),
+            !consent_granted,
+        )
+    }
+
+    #[test]
+    fn test_non_consenting_page_view_is_anonymized() {
+        let event = page_view(false);
+
+        assert_eq!(event.event_type, "page_view");
+        assert_eq!(event.event_data["page_path"], "/about");
+        assert_eq!(event.ip_address, None);
+        assert_eq!(event.user_agent, None);
+        assert_eq!(event.user_id, None);
+        let session_id = event.session_id.expect("Hashed session missing");
+        assert_ne!(session_id, "session-1");
+        assert_eq!(session_id.len(), 64);
+    }
+
+    #[test]
+    fn test_consenting_page_view_keeps_full_data() {
+        let event = page_view(true);
+
+        assert_eq!(event.ip_address.as_deref(), Some("203.0.113.7"));
+        assert_eq!(event.user_agent.as_deref(), Some("Mozilla/5.0"));
+        assert_eq!(event.session_id.as_deref(), Some("session-1"));
+        assert!(event.user_id.is_some());
+    }
+
+    #[test]
+    fn test_duplicate_names_rejected() {
+        let mut queries = batch(&["tenant_stats", "top_content"]);
+        queries[1].name = "q0".to_string();
+
+        assert!(matches!(validate_batch(&queries), Err(BatchQueryError::DuplicateName(_))));
+    }
+}
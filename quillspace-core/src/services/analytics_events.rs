@@ -0,0 +1,290 @@
+use crate::types::AnalyticsEvent;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use deadpool_postgres::GenericClient;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Key of the tenant setting holding its custom event schema
+pub const EVENT_SCHEMA_SETTING: &str = "analytics_events";
+
+/// Prefix of the stored event type, keeping custom events apart from built-in ones
+pub const CUSTOM_EVENT_PREFIX: &str = "custom_";
+
+const MAX_EVENT_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CustomEventError {
+    #[error("Invalid event name '{0}': use 1-64 lowercase letters, digits and underscores")]
+    InvalidName(String),
+
+    #[error("Event '{0}' is not in the tenant's event schema")]
+    UnknownEvent(String),
+
+    #[error("Event '{event}' is missing required property '{property}'")]
+    MissingProperty { event: String, property: String },
+
+    #[error("Property '{property}' of event '{event}' must be {expected:?}")]
+    WrongType { event: String, property: String, expected: PropertyType },
+
+    #[error("Invalid event schema: {0}")]
+    InvalidSchema(String),
+}
+
+/// A tenant-defined event, e.g. `book_preview_opened` with the book it was for
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomEvent {
+    pub name: String,
+    #[serde(default)]
+    pub properties: serde_json::Map<String, serde_json::Value>,
+    pub session_id: Option<String>,
+}
+
+impl CustomEvent {
+    /// The analytics event written for it; its properties become the event data
+    pub fn into_analytics_event(self, tenant_id: Uuid, user_id: Option<Uuid>) -> AnalyticsEvent {
+        AnalyticsEvent {
+            event_id: Uuid::new_v4(),
+            tenant_id,
+            user_id,
+            event_type: format!("{}{}", CUSTOM_EVENT_PREFIX, self.name),
+            event_data: serde_json::Value::Object(self.properties),
+            timestamp: Utc::now(),
+            session_id: self.session_id,
+            ip_address: None,
+            user_agent: None,
+        }
+    }
+}
+
+/// JSON type a property must have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyType {
+    String,
+    Number,
+    Boolean,
+    Object,
+    Array,
+}
+
+impl PropertyType {
+    fn matches(self, value: &serde_json::Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Number => value.is_number(),
+            Self::Boolean => value.is_boolean(),
+            Self::Object => value.is_object(),
+            Self::Array => value.is_array(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PropertyDefinition {
+    #[serde(rename = "type")]
+    pub property_type: PropertyType,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventDefinition {
+    #[serde(default)]
+    pub properties: BTreeMap<String, PropertyDefinition>,
+}
+
+/// A tenant's custom event schema, from its `analytics_events` setting:
+///
+/// ```json
+/// { "strict": true, "events": { "book_preview_opened": { "properties": {
+///     "book_id": { "type": "string", "required": true } } } } }
+/// ```
+///
+/// Events it defines must carry their required properties with the declared types;
+/// other properties are recorded as sent. Events it doesn't define are recorded as
+/// sent, or rejected when `strict` is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventSchema {
+    #[serde(default)]
+    pub strict: bool,
+    #[serde(default)]
+    pub events: BTreeMap<String, EventDefinition>,
+}
+
+impl EventSchema {
+    /// The schema in tenant settings, `None` when the tenant has not configured one
+    pub fn from_settings(settings: &serde_json::Value) -> Result<Option<Self>, CustomEventError> {
+        match settings.get(EVENT_SCHEMA_SETTING) {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(value) => {
+                let schema: Self = serde_json::from_value(value.clone())
+                    .map_err(|e| CustomEventError::InvalidSchema(e.to_string()))?;
+                if let Some(name) = schema.events.keys().find(|name| !valid_event_name(name)) {
+                    return Err(CustomEventError::InvalidName(name.clone()));
+                }
+                Ok(Some(schema))
+            }
+        }
+    }
+
+    fn validate(&self, event: &CustomEvent) -> Result<(), CustomEventError> {
+        let Some(definition) = self.events.get(&event.name) else {
+            return if self.strict { Err(CustomEventError::UnknownEvent(event.name.clone())) } else { Ok(()) };
+        };
+
+        for (property, spec) in &definition.properties {
+            match event.properties.get(property) {
+                None | Some(serde_json::Value::Null) if spec.required => {
+                    return Err(CustomEventError::MissingProperty {
+                        event: event.name.clone(),
+                        property: property.clone(),
+                    });
+                }
+                Some(value) if !value.is_null() && !spec.property_type.matches(value) => {
+                    return Err(CustomEventError::WrongType {
+                        event: event.name.clone(),
+                        property: property.clone(),
+                        expected: spec.property_type,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn valid_event_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_EVENT_NAME_LEN
+        && name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+}
+
+/// Check a custom event against the tenant's schema, if it has one
+pub fn validate_event(event: &CustomEvent, schema: Option<&EventSchema>) -> Result<(), CustomEventError> {
+    if !valid_event_name(&event.name) {
+        return Err(CustomEventError::InvalidName(event.name.clone()));
+    }
+    schema.map_or(Ok(()), |schema| schema.validate(event))
+}
+
+/// Reject a settings update whose `analytics_events` is not a valid schema
+pub fn validate_settings(settings: &serde_json::Value) -> Result<(), CustomEventError> {
+    EventSchema::from_settings(settings).map(|_| ())
+}
+
+/// The tenant's custom event schema, read from its settings
+pub async fn load_event_schema(client: &impl GenericClient, tenant_id: &Uuid) -> Result<Option<EventSchema>> {
+    let settings: Option<serde_json::Value> = client
+        .query_opt("SELECT settings FROM tenants WHERE id = $1", &[tenant_id])
+        .await
+        .context("Failed to load tenant settings")?
+        .and_then(|row| row.get(0));
+    match settings {
+        Some(settings) => EventSchema::from_settings(&settings).context("Tenant has an invalid event schema"),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::AnalyticsConfig,
+        services::{
+            analytics_writer::{AnalyticsSink, AnalyticsWrite, AnalyticsWriter},
+            metrics_registry::MetricsRegistry,
+        },
+    };
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Backend that keeps what it is sent
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        writes: Arc<Mutex<Vec<AnalyticsWrite>>>,
+    }
+
+    impl AnalyticsSink for RecordingSink {
+        async fn write(&self, write: AnalyticsWrite) -> Result<()> {
+            self.writes.lock().unwrap().push(write);
+            Ok(())
+        }
+    }
+
+    fn schema() -> EventSchema {
+        let settings = json!({
+            "analytics_events": {
+                "strict": true,
+                "events": {
+                    "book_preview_opened": {
+                        "properties": {
+                            "book_id": { "type": "string", "required": true },
+                            "page": { "type": "number" }
+                        }
+                    }
+                }
+            }
+        });
+        EventSchema::from_settings(&settings).unwrap().unwrap()
+    }
+
+    fn event(name: &str, properties: serde_json::Value) -> CustomEvent {
+        serde_json::from_value(json!({ "name": name, "properties": properties })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_valid_custom_event_is_recorded() {
+        let sink = RecordingSink::default();
+        let writer = AnalyticsWriter::spawn(sink.clone(), &AnalyticsConfig::default(), MetricsRegistry::default());
+        let tenant_id = Uuid::new_v4();
+
+        let opened = event("book_preview_opened", json!({ "book_id": "b-1", "page": 3, "source": "shelf" }));
+        assert_eq!(validate_event(&opened, Some(&schema())), Ok(()));
+        writer.record_event(opened.into_analytics_event(tenant_id, None));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let writes = sink.writes.lock().unwrap();
+        let [AnalyticsWrite::Event(recorded)] = writes.as_slice() else {
+            panic!("expected one event, got {:?}", writes);
+        };
+        assert_eq!(recorded.tenant_id, tenant_id);
+        assert_eq!(recorded.event_type, "custom_book_preview_opened");
+        assert_eq!(recorded.event_data["book_id"], "b-1");
+        assert_eq!(recorded.event_data["source"], "shelf");
+    }
+
+    #[test]
+    fn test_schema_violating_events_rejected() {
+        let schema = schema();
+
+        assert!(matches!(
+            validate_event(&event("book_preview_opened", json!({ "page": 3 })), Some(&schema)),
+            Err(CustomEventError::MissingProperty { property, .. }) if property == "book_id"
+        ));
+        assert!(matches!(
+            validate_event(&event("book_preview_opened", json!({ "book_id": 7 })), Some(&schema)),
+            Err(CustomEventError::WrongType { expected: PropertyType::String, .. })
+        ));
+        assert_eq!(
+            validate_event(&event("newsletter_signup", json!({})), Some(&schema)),
+            Err(CustomEventError::UnknownEvent("newsletter_signup".to_string()))
+        );
+
+        // Without strict, and without a schema, only the name is checked
+        let lenient = EventSchema { strict: false, ..schema };
+        assert_eq!(validate_event(&event("newsletter_signup", json!({})), Some(&lenient)), Ok(()));
+        assert_eq!(validate_event(&event("newsletter_signup", json!({})), None), Ok(()));
+        assert!(matches!(
+            validate_event(&event("Newsletter Signup", json!({})), None),
+            Err(CustomEventError::InvalidName(_))
+        ));
+        assert!(validate_settings(&json!({ "analytics_events": { "events": { "Bad Name": {} } } })).is_err());
+    }
+}
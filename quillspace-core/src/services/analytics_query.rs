@@ -0,0 +1,428 @@
+use crate::services::timezone;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use clickhouse::{query::Query, Client};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Longest date range a query may cover, in days
+pub const MAX_QUERY_DAYS: i64 = 366;
+
+/// Rows returned when a query sets no limit, and the most it may ask for
+pub const DEFAULT_QUERY_ROWS: u32 = 100;
+pub const MAX_QUERY_ROWS: u32 = 1000;
+
+/// Values a single filter may list
+pub const MAX_FILTER_VALUES: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum AnalyticsQueryError {
+    #[error("Invalid query: {0}")]
+    Invalid(String),
+
+    #[error("Query must select at least one metric")]
+    NoMetrics,
+
+    #[error("'{0}' is selected more than once")]
+    Duplicate(&'static str),
+
+    #[error("Date range ends before it starts")]
+    InvalidRange,
+
+    #[error("Date range covers {0} days, maximum is {max}", max = MAX_QUERY_DAYS)]
+    RangeTooLong(i64),
+
+    #[error("Limit must be between 1 and {max}", max = MAX_QUERY_ROWS)]
+    InvalidLimit,
+
+    #[error("Filter on '{0}' must list between 1 and {max} values", max = MAX_FILTER_VALUES)]
+    InvalidFilterValues(&'static str),
+
+    #[error("Invalid user id '{0}'")]
+    InvalidUserId(String),
+}
+
+/// Aggregates a query can select
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Events,
+    UniqueUsers,
+    UniqueSessions,
+    PageViews,
+}
+
+impl Metric {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Events => "events",
+            Self::UniqueUsers => "unique_users",
+            Self::UniqueSessions => "unique_sessions",
+            Self::PageViews => "page_views",
+        }
+    }
+
+    fn expression(self) -> &'static str {
+        match self {
+            Self::Events => "count()",
+            Self::UniqueUsers => "uniq(user_id)",
+            Self::UniqueSessions => "uniq(session_id)",
+            Self::PageViews => "countIf(event_type = 'page_view')",
+        }
+    }
+}
+
+/// Columns a query can group by; time buckets follow the tenant's timezone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dimension {
+    Date,
+    Week,
+    Month,
+    EventType,
+}
+
+impl Dimension {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Date => "date",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::EventType => "event_type",
+        }
+    }
+
+    /// Expression for the column; `?` is the timezone
+    fn expression(self) -> &'static str {
+        match self {
+            Self::Date => "toDate(timestamp, ?)",
+            Self::Week => "toMonday(timestamp, ?)",
+            Self::Month => "toStartOfMonth(timestamp, ?)",
+            Self::EventType => "event_type",
+        }
+    }
+
+    fn takes_timezone(self) -> bool {
+        !matches!(self, Self::EventType)
+    }
+}
+
+/// Columns a query can filter on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterField {
+    EventType,
+    UserId,
+}
+
+impl FilterField {
+    fn name(self) -> &'static str {
+        match self {
+            Self::EventType => "event_type",
+            Self::UserId => "user_id",
+        }
+    }
+
+    /// Column compared against the bound list of values
+    fn column(self) -> &'static str {
+        match self {
+            Self::EventType => "event_type",
+            Self::UserId => "toString(user_id)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    In,
+    NotIn,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Filter {
+    pub field: FilterField,
+    pub op: FilterOp,
+    pub values: Vec<String>,
+}
+
+/// An ad-hoc analytics query over the tenant's events:
+///
+/// ```json
+/// { "metrics": ["events", "unique_users"], "group_by": ["date"],
+///   "filters": [{ "field": "event_type", "op": "in", "values": ["page_view"] }],
+///   "from": "2024-05-01", "to": "2024-05-31", "limit": 100 }
+/// ```
+///
+/// `from` and `to` are inclusive dates in the tenant's timezone. Only the metrics,
+/// dimensions and filter fields above exist; anything else fails to parse.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnalyticsQuerySpec {
+    pub metrics: Vec<Metric>,
+    #[serde(default)]
+    pub group_by: Vec<Dimension>,
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub limit: Option<u32>,
+}
+
+/// A value bound to a `?` placeholder; never spliced into the SQL text
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryParam {
+    Uuid(Uuid),
+    Text(String),
+    Millis(i64),
+    TextList(Vec<String>),
+}
+
+/// SQL for a query and the values for its placeholders, in order
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledQuery {
+    pub sql: String,
+    pub params: Vec<QueryParam>,
+}
+
+impl CompiledQuery {
+    /// A ClickHouse query with every parameter bound
+    pub fn to_query(&self, client: &Client) -> Query {
+        self.params.iter().fold(client.query(&self.sql), |query, param| match param {
+            QueryParam::Uuid(value) => query.bind(value),
+            QueryParam::Text(value) => query.bind(value.as_str()),
+            QueryParam::Millis(value) => query.bind(value),
+            QueryParam::TextList(values) => query.bind(values),
+        })
+    }
+}
+
+impl AnalyticsQuerySpec {
+    /// Parse a request body, reporting unknown metrics, dimensions and fields
+    pub fn parse(body: serde_json::Value) -> Result<Self, AnalyticsQueryError> {
+        serde_json::from_value(body).map_err(|e| AnalyticsQueryError::Invalid(e.to_string()))
+    }
+
+    /// Filtering on users exposes per-user data, restricted to admins
+    pub fn requires_admin(&self) -> bool {
+        self.filters.iter().any(|filter| filter.field == FilterField::UserId)
+    }
+
+    /// Compile to ClickHouse SQL over `tenant_id`'s events only, with days in `tz`
+    pub fn compile(&self, tenant_id: &Uuid, tz: Tz) -> Result<CompiledQuery, AnalyticsQueryError> {
+        self.validate()?;
+
+        let mut params = Vec::new();
+        let mut columns = Vec::new();
+        for dimension in &self.group_by {
+            if dimension.takes_timezone() {
+                params.push(QueryParam::Text(tz.name().to_string()));
+            }
+            columns.push(format!("{} AS {}", dimension.expression(), dimension.name()));
+        }
+        for metric in &self.metrics {
+            columns.push(format!("{} AS {}", metric.expression(), metric.name()));
+        }
+
+        // The tenant comes from the caller, never from the query
+        let mut conditions = vec![
+            "tenant_id = ?".to_string(),
+            "timestamp >= fromUnixTimestamp64Milli(?)".to_string(),
+            "timestamp < fromUnixTimestamp64Milli(?)".to_string(),
+        ];
+        params.push(QueryParam::Uuid(*tenant_id));
+        params.push(QueryParam::Millis(timezone::local_day_start(self.from, tz).timestamp_millis()));
+        params.push(QueryParam::Millis(timezone::local_day_start(self.to.succ_opt().unwrap_or(self.to), tz).timestamp_millis()));
+
+        for filter in &self.filters {
+            let negate = if filter.op == FilterOp::NotIn { "NOT " } else { "" };
+            conditions.push(format!("{}has(?, {})", negate, filter.field.column()));
+            params.push(QueryParam::TextList(filter.values.clone()));
+        }
+
+        let mut sql = format!("SELECT {} FROM events WHERE {}", columns.join(", "), conditions.join(" AND "));
+        if !self.group_by.is_empty() {
+            let names: Vec<_> = self.group_by.iter().map(|dimension| dimension.name()).collect();
+            sql.push_str(&format!(" GROUP BY {0} ORDER BY {0}", names.join(", ")));
+        }
+        sql.push_str(&format!(" LIMIT {}", self.limit.unwrap_or(DEFAULT_QUERY_ROWS)));
+
+        Ok(CompiledQuery { sql, params })
+    }
+
+    fn validate(&self) -> Result<(), AnalyticsQueryError> {
+        if self.metrics.is_empty() {
+            return Err(AnalyticsQueryError::NoMetrics);
+        }
+        if let Some(metric) = first_duplicate(&self.metrics) {
+            return Err(AnalyticsQueryError::Duplicate(metric.name()));
+        }
+        if let Some(dimension) = first_duplicate(&self.group_by) {
+            return Err(AnalyticsQueryError::Duplicate(dimension.name()));
+        }
+
+        let days = (self.to - self.from).num_days() + 1;
+        if days < 1 {
+            return Err(AnalyticsQueryError::InvalidRange);
+        }
+        if days > MAX_QUERY_DAYS {
+            return Err(AnalyticsQueryError::RangeTooLong(days));
+        }
+        if matches!(self.limit, Some(limit) if limit == 0 || limit > MAX_QUERY_ROWS) {
+            return Err(AnalyticsQueryError::InvalidLimit);
+        }
+
+        for filter in &self.filters {
+            if filter.values.is_empty() || filter.values.len() > MAX_FILTER_VALUES {
+                return Err(AnalyticsQueryError::InvalidFilterValues(filter.field.name()));
+            }
+            if filter.field == FilterField::UserId {
+                if let Some(value) = filter.values.iter().find(|value| Uuid::parse_str(value).is_err()) {
+                    return Err(AnalyticsQueryError::InvalidUserId(value.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn first_duplicate<T: PartialEq + Copy>(items: &[T]) -> Option<T> {
+    items.iter().enumerate().find(|(i, item)| items[..*i].contains(item)).map(|(_, item)| *item)
+}
+
+/// Run a compiled query, one JSON object per result row
+pub async fn run_query(client: &Client, query: &CompiledQuery) -> Result<Vec<serde_json::Value>> {
+    let bytes = query
+        .to_query(client)
+        .with_option("output_format_json_quote_64bit_integers", "0")
+        .fetch_bytes("JSONEachRow")
+        .context("Failed to start analytics query")?
+        .collect()
+        .await
+        .context("Analytics query failed")?;
+
+    bytes
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).context("Unreadable analytics query row"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::clickhouse::AnalyticsService as ClickHouseAnalyticsService, types::AnalyticsEvent};
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    fn spec(body: serde_json::Value) -> Result<AnalyticsQuerySpec, AnalyticsQueryError> {
+        AnalyticsQuerySpec::parse(body)
+    }
+
+    fn daily_events(from: NaiveDate, to: NaiveDate) -> AnalyticsQuerySpec {
+        spec(json!({
+            "metrics": ["events", "unique_users"],
+            "group_by": ["date", "event_type"],
+            "filters": [{ "field": "event_type", "op": "in", "values": ["page_view", "content_view"] }],
+            "from": from,
+            "to": to,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sql_injection_and_other_tenants_blocked() {
+        let tenant_id = Uuid::new_v4();
+        let today = Utc::now().date_naive();
+
+        // Only known metrics, dimensions and fields parse; tenant_id is not one of them
+        for body in [
+            json!({ "metrics": ["count(); DROP TABLE events"], "from": today, "to": today }),
+            json!({ "metrics": ["events"], "group_by": ["tenant_id"], "from": today, "to": today }),
+            json!({ "metrics": ["events"], "from": today, "to": today,
+                    "filters": [{ "field": "tenant_id", "op": "in", "values": [Uuid::new_v4()] }] }),
+            json!({ "metrics": ["events"], "from": today, "to": today, "tenant_id": Uuid::new_v4() }),
+            json!({ "metrics": ["events"], "from": today, "to": today, "where": "1 = 1" }),
+        ] {
+            assert!(matches!(spec(body), Err(AnalyticsQueryError::Invalid(_))));
+        }
+
+        // Filter values are bound, and escaped by the client, never spliced into the SQL
+        let injection = "x') OR 1 = 1 OR has(['";
+        let query = spec(json!({
+            "metrics": ["events"],
+            "filters": [{ "field": "event_type", "op": "in", "values": [injection] }],
+            "from": today,
+            "to": today,
+        }))
+        .unwrap()
+        .compile(&tenant_id, Tz::UTC)
+        .unwrap();
+        assert!(!query.sql.contains(injection));
+        assert!(query.sql.contains("WHERE tenant_id = ? AND "));
+        assert_eq!(query.params[0], QueryParam::Uuid(tenant_id));
+        let bound = query.to_query(&Client::default()).sql_display().to_string();
+        assert!(bound.contains(&format!("tenant_id = '{}'", tenant_id)));
+        assert!(bound.contains(r"['x\') OR 1 = 1 OR has([\'']"), "{}", bound);
+
+        assert_eq!(
+            spec(json!({ "metrics": ["events"], "from": today, "to": today,
+                         "filters": [{ "field": "user_id", "op": "in", "values": ["' OR 1=1"] }] }))
+                .unwrap()
+                .compile(&tenant_id, Tz::UTC),
+            Err(AnalyticsQueryError::InvalidUserId("' OR 1=1".to_string()))
+        );
+        assert_eq!(
+            daily_events(today, today - Duration::days(1)).compile(&tenant_id, Tz::UTC),
+            Err(AnalyticsQueryError::InvalidRange)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_returns_expected_aggregates() {
+        let Some(client) = crate::database::clickhouse::test_client().await else {
+            return;
+        };
+        let service = ClickHouseAnalyticsService::new(client.clone());
+        let tenant_id = Uuid::new_v4();
+        let other_tenant_id = Uuid::new_v4();
+        let yesterday = Utc::now() - Duration::days(1);
+        let visitor = Uuid::new_v4();
+
+        let events = [
+            (tenant_id, "page_view", visitor),
+            (tenant_id, "page_view", visitor),
+            (tenant_id, "page_view", Uuid::new_v4()),
+            (tenant_id, "content_view", visitor),
+            (tenant_id, "content_create", visitor),
+            (other_tenant_id, "page_view", Uuid::new_v4()),
+        ];
+        for (tenant_id, event_type, user_id) in events {
+            let event = AnalyticsEvent {
+                event_id: Uuid::new_v4(),
+                tenant_id,
+                user_id: Some(user_id),
+                event_type: event_type.to_string(),
+                event_data: json!({}),
+                timestamp: yesterday,
+                session_id: None,
+                ip_address: None,
+                user_agent: None,
+            };
+            service.record_event(&event).await.expect("Failed to record test event");
+        }
+
+        let day = yesterday.date_naive();
+        let query = daily_events(day, day).compile(&tenant_id, Tz::UTC).unwrap();
+        let rows = run_query(&client, &query).await.expect("Query failed");
+        assert_eq!(
+            rows,
+            vec![
+                json!({ "date": day, "event_type": "content_view", "events": 1, "unique_users": 1 }),
+                json!({ "date": day, "event_type": "page_view", "events": 3, "unique_users": 2 }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! Rebuild a tenant's content analytics in ClickHouse from Postgres, after ClickHouse
+//! lost them in an outage or to corruption.
+//!
+//! Postgres keeps enough history to reconstruct three content actions:
+//!
+//! - `create`, from `content.created_at` and the author;
+//! - `update`, from each later revision in `content_revisions`;
+//! - `publish`, from `content.published_at`, so only the latest publish of each piece.
+//!
+//! Each action gets a `content_analytics` row and a `content_<action>` event. Their ids
+//! are derived from the action itself with [`content_event_id`], the same ids live
+//! writes use, so rows already in ClickHouse are skipped and replaying a range twice
+//! adds nothing.
+
+use crate::database::postgres::tenant_transaction;
+use crate::database::clickhouse::{event_ids_between, AnalyticsService as ClickHouseAnalyticsService, ContentAction};
+use crate::types::{AnalyticsEvent, TenantId};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::HashSet;
+use std::future::Future;
+use tracing::info;
+use uuid::Uuid;
+
+/// Longest range replayed in one request
+pub const MAX_REPLAY_DAYS: i64 = 92;
+
+/// Namespace of content action ids
+const CONTENT_EVENT_NAMESPACE: Uuid = Uuid::from_u128(0x5c1d_93a4_7e0b_4f6e_9a2d_1b8c_4e7f_3a60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("Invalid replay range: {0}")]
+    InvalidRange(String),
+
+    #[error(transparent)]
+    Failed(#[from] anyhow::Error),
+}
+
+/// Id of a content action: the name-based (v5) UUID of the content, the action and
+/// `sequence`, which tells repeated actions apart. It is 0 for `create`, the revision
+/// for `update` and the publish time in milliseconds for `publish`.
+pub fn content_event_id(content_id: Uuid, action: &str, sequence: i64) -> Uuid {
+    let mut hasher = Sha1::new();
+    hasher.update(CONTENT_EVENT_NAMESPACE.as_bytes());
+    hasher.update(format!("{}/{}/{}", content_id, action, sequence).as_bytes());
+    let digest = hasher.finalize();
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_sha1_bytes(bytes).into_uuid()
+}
+
+/// Days to replay, both inclusive, in UTC
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ReplayRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl ReplayRange {
+    pub fn validate(&self, today: NaiveDate) -> Result<(), ReplayError> {
+        if self.to < self.from {
+            return Err(ReplayError::InvalidRange("`to` is before `from`".to_string()));
+        }
+        if self.to > today {
+            return Err(ReplayError::InvalidRange("`to` is in the future".to_string()));
+        }
+        if (self.to - self.from).num_days() >= MAX_REPLAY_DAYS {
+            return Err(ReplayError::InvalidRange(format!("at most {} days at a time", MAX_REPLAY_DAYS)));
+        }
+        Ok(())
+    }
+
+    fn start(&self) -> DateTime<Utc> {
+        self.from.and_hms_opt(0, 0, 0).expect("midnight exists").and_utc()
+    }
+
+    fn end(&self) -> DateTime<Utc> {
+        self.start() + Duration::days((self.to - self.from).num_days() + 1)
+    }
+}
+
+/// What a replay found in Postgres and added to ClickHouse
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReplaySummary {
+    pub actions_found: usize,
+    pub events_inserted: usize,
+    pub content_actions_inserted: usize,
+}
+
+/// Where replayed analytics are written
+pub trait ReplayTarget: Send + Sync {
+    /// Ids already in `table` for the tenant on the days `from`..=`to`
+    fn event_ids(
+        &self,
+        table: &'static str,
+        tenant_id: &Uuid,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Future<Output = Result<HashSet<Uuid>>> + Send;
+
+    fn insert_event(&self, event: &AnalyticsEvent) -> impl Future<Output = Result<()>> + Send;
+
+    fn insert_content_action(&self, action: &ContentAction) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl ReplayTarget for ClickHouseAnalyticsService {
+    async fn event_ids(&self, table: &'static str, tenant_id: &Uuid, from: NaiveDate, to: NaiveDate) -> Result<HashSet<Uuid>> {
+        event_ids_between(self.client(), table, tenant_id, from, to).await
+    }
+
+    async fn insert_event(&self, event: &AnalyticsEvent) -> Result<()> {
+        self.record_event(event).await
+    }
+
+    async fn insert_content_action(&self, action: &ContentAction) -> Result<()> {
+        ClickHouseAnalyticsService::insert_content_action(self, action).await
+    }
+}
+
+/// The `content_<action>` event recorded alongside a content action
+pub fn action_event(action: &ContentAction) -> AnalyticsEvent {
+    AnalyticsEvent {
+        event_id: action.event_id,
+        tenant_id: action.tenant_id,
+        user_id: action.user_id,
+        event_type: format!("content_{}", action.action),
+        event_data: serde_json::json!({
+            "content_id": action.content_id,
+            "interaction_type": action.action,
+            "metadata": action.metadata,
+        }),
+        timestamp: action.timestamp,
+        session_id: None,
+        ip_address: None,
+        user_agent: None,
+    }
+}
+
+/// The tenant's content actions in `range` according to Postgres, oldest first
+pub async fn load_content_actions(pool: &Pool, tenant_id: &TenantId, range: ReplayRange) -> Result<Vec<ContentAction>> {
+    let mut client = pool.get().await.context("Failed to get database connection")?;
+    // content and content_revisions are read under either policy variable
+    let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+    let rows = transaction
+        .query(
+            "SELECT id AS content_id, 'create' AS action, author_id AS user_id, created_at AS at,
+                    0::bigint AS sequence, title
+             FROM content
+             WHERE tenant_id = $1 AND created_at >= $2 AND created_at < $3
+             UNION ALL
+             SELECT id, 'publish', NULL, published_at,
+                    FLOOR(EXTRACT(EPOCH FROM published_at) * 1000)::bigint, title
+             FROM content
+             WHERE tenant_id = $1 AND published_at >= $2 AND published_at < $3
+             UNION ALL
+             SELECT content_id, 'update', NULL, created_at, revision::bigint, title
+             FROM content_revisions
+             WHERE tenant_id = $1 AND revision > 1 AND created_at >= $2 AND created_at < $3
+             ORDER BY at, action",
+            &[tenant_id.as_uuid(), &range.start(), &range.end()],
+        )
+        .await
+        .context("Failed to load content history")?;
+    transaction.commit().await.context("Failed to commit")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let content_id: Uuid = row.get("content_id");
+            let action: String = row.get("action");
+            let sequence: i64 = row.get("sequence");
+            let title: String = row.get("title");
+            ContentAction {
+                event_id: content_event_id(content_id, &action, sequence),
+                tenant_id: *tenant_id.as_uuid(),
+                content_id,
+                user_id: row.get("user_id"),
+                timestamp: row.get("at"),
+                metadata: serde_json::json!({ "title": title, "replayed": true }),
+                action,
+            }
+        })
+        .collect())
+}
+
+/// Write the actions ClickHouse is missing. Existing ids are looked up a day either
+/// side of the range, as a live write may be dated just after its Postgres change.
+pub async fn replay_content_actions<T: ReplayTarget>(
+    target: &T,
+    tenant_id: &TenantId,
+    range: ReplayRange,
+    actions: &[ContentAction],
+) -> Result<ReplaySummary> {
+    let (from, to) = (range.from - Duration::days(1), range.to + Duration::days(1));
+    let existing_events = target.event_ids("events", tenant_id.as_uuid(), from, to).await?;
+    let existing_actions = target.event_ids("content_analytics", tenant_id.as_uuid(), from, to).await?;
+
+    let mut summary = ReplaySummary { actions_found: actions.len(), ..ReplaySummary::default() };
+    for action in actions {
+        if !existing_events.contains(&action.event_id) {
+            target.insert_event(&action_event(action)).await?;
+            summary.events_inserted += 1;
+        }
+        if !existing_actions.contains(&action.event_id) {
+            target.insert_content_action(action).await?;
+            summary.content_actions_inserted += 1;
+        }
+    }
+    Ok(summary)
+}
+
+/// Replay the tenant's content analytics for `range` from Postgres into `target`
+pub async fn replay_tenant<T: ReplayTarget>(
+    pool: &Pool,
+    target: &T,
+    tenant_id: &TenantId,
+    range: ReplayRange,
+) -> Result<ReplaySummary, ReplayError> {
+    range.validate(Utc::now().date_naive())?;
+    let actions = load_content_actions(pool, tenant_id, range).await?;
+    let summary = replay_content_actions(target, tenant_id, range, &actions).await?;
+    info!(
+        tenant_id = %tenant_id,
+        from = %range.from,
+        to = %range.to,
+        found = summary.actions_found,
+        events = summary.events_inserted,
+        content_actions = summary.content_actions_inserted,
+        "Replayed content analytics"
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// ClickHouse stand-in keeping rows in memory
+    #[derive(Default)]
+    struct MemoryTarget {
+        events: Mutex<Vec<AnalyticsEvent>>,
+        actions: Mutex<Vec<ContentAction>>,
+    }
+
+    impl ReplayTarget for MemoryTarget {
+        async fn event_ids(&self, table: &'static str, tenant_id: &Uuid, from: NaiveDate, to: NaiveDate) -> Result<HashSet<Uuid>> {
+            let in_range = |tenant: &Uuid, at: &DateTime<Utc>| tenant == tenant_id && (from..=to).contains(&at.date_naive());
+            Ok(match table {
+                "events" => self.events.lock().unwrap().iter().filter(|e| in_range(&e.tenant_id, &e.timestamp)).map(|e| e.event_id).collect(),
+                _ => self.actions.lock().unwrap().iter().filter(|a| in_range(&a.tenant_id, &a.timestamp)).map(|a| a.event_id).collect(),
+            })
+        }
+
+        async fn insert_event(&self, event: &AnalyticsEvent) -> Result<()> {
+            self.events.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+
+        async fn insert_content_action(&self, action: &ContentAction) -> Result<()> {
+            self.actions.lock().unwrap().push(action.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_event_ids_are_stable_per_action() {
+        let content_id = Uuid::from_u128(42);
+        let created = content_event_id(content_id, "create", 0);
+
+        assert_eq!(created, content_event_id(content_id, "create", 0));
+        assert_eq!(created.get_version_num(), 5);
+        assert_ne!(created, content_event_id(content_id, "update", 0));
+        assert_ne!(content_event_id(content_id, "update", 2), content_event_id(content_id, "update", 3));
+        assert_ne!(created, content_event_id(Uuid::from_u128(43), "create", 0));
+    }
+
+    #[test]
+    fn test_range_validated() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+        let range = |from: (u32, u32), to: (u32, u32)| ReplayRange {
+            from: NaiveDate::from_ymd_opt(2024, from.0, from.1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2024, to.0, to.1).unwrap(),
+        };
+
+        assert!(range((6, 1), (6, 30)).validate(today).is_ok());
+        assert!(range((6, 10), (6, 9)).validate(today).is_err());
+        assert!(range((6, 1), (7, 1)).validate(today).is_err());
+        assert!(range((1, 1), (6, 1)).validate(today).is_err());
+        assert_eq!(range((6, 1), (6, 1)).end() - range((6, 1), (6, 1)).start(), Duration::days(1));
+    }
+
+    #[tokio::test]
+    async fn test_replay_rebuilds_content_analytics_once() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let admin = app.admin_pool.get().await.unwrap();
+        let day = Utc::now().date_naive() - Duration::days(10);
+        let at = |hour: u32| day.and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        let seed = |tenant: &crate::test_harness::TestTenant, title: &str, created_at: DateTime<Utc>| {
+            let (tenant_id, author) = (*tenant.id.as_uuid(), tenant.admin.id);
+            let title = title.to_string();
+            let admin = &admin;
+            async move {
+                let row = admin
+                    .query_one(
+                        "INSERT INTO content (tenant_id, author_id, title, body, created_at, translation_group_id)
+                         VALUES ($1, $2, $3, 'Draft', $4, uuid_generate_v4()) RETURNING id",
+                        &[&tenant_id, &author, &title, &created_at],
+                    )
+                    .await
+                    .expect("Failed to seed content");
+                row.get::<_, Uuid>(0)
+            }
+        };
+
+        // Created, edited and published on the day
+        let chapter = seed(&app.tenant_a, "Chapter One", at(9)).await;
+        admin.execute("UPDATE content SET body = 'Final' WHERE id = $1", &[&chapter]).await.unwrap();
+        admin
+            .execute("UPDATE content_revisions SET created_at = $2 WHERE content_id = $1 AND revision = 2", &[&chapter, &at(10)])
+            .await
+            .unwrap();
+        admin.execute("UPDATE content SET published_at = $2 WHERE id = $1", &[&chapter, &at(11)]).await.unwrap();
+        // Outside the range, and another tenant's
+        seed(&app.tenant_a, "Older", at(9) - Duration::days(5)).await;
+        seed(&app.tenant_b, "Theirs", at(9)).await;
+
+        let pool = app.state.db.postgres().clone();
+        let target = MemoryTarget::default();
+        let tenant_id = &app.tenant_a.id;
+        let range = ReplayRange { from: day, to: day };
+
+        let summary = replay_tenant(&pool, &target, tenant_id, range).await.unwrap();
+        assert_eq!(summary, ReplaySummary { actions_found: 3, events_inserted: 3, content_actions_inserted: 3 });
+
+        let actions = target.actions.lock().unwrap().clone();
+        let recorded: Vec<(Uuid, &str, DateTime<Utc>)> =
+            actions.iter().map(|action| (action.content_id, action.action.as_str(), action.timestamp)).collect();
+        assert_eq!(recorded, vec![(chapter, "create", at(9)), (chapter, "update", at(10)), (chapter, "publish", at(11))]);
+        assert_eq!(actions[0].user_id, Some(app.tenant_a.admin.id));
+        assert_eq!(actions[0].event_id, content_event_id(chapter, "create", 0));
+        let event_types: Vec<String> = target.events.lock().unwrap().iter().map(|event| event.event_type.clone()).collect();
+        assert_eq!(event_types, vec!["content_create", "content_update", "content_publish"]);
+
+        // Running it again finds the same actions and adds nothing
+        let again = replay_tenant(&pool, &target, tenant_id, range).await.unwrap();
+        assert_eq!(again, ReplaySummary { actions_found: 3, events_inserted: 0, content_actions_inserted: 0 });
+        assert_eq!(target.actions.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_replay_into_clickhouse_is_idempotent() {
+        let Some(client) = crate::database::clickhouse::test_client().await else {
+            return;
+        };
+        let service = ClickHouseAnalyticsService::new(client.clone());
+        let tenant_id = TenantId::new();
+        let day = Utc::now().date_naive() - Duration::days(3);
+        let content_id = Uuid::new_v4();
+        let actions: Vec<ContentAction> = [("create", 0), ("update", 2)]
+            .into_iter()
+            .map(|(action, sequence)| ContentAction {
+                event_id: content_event_id(content_id, action, sequence),
+                tenant_id: *tenant_id.as_uuid(),
+                content_id,
+                action: action.to_string(),
+                user_id: None,
+                timestamp: day.and_hms_opt(12, 0, 0).unwrap().and_utc(),
+                metadata: serde_json::json!({ "replayed": true }),
+            })
+            .collect();
+        let range = ReplayRange { from: day, to: day };
+
+        let first = replay_content_actions(&service, &tenant_id, range, &actions).await.unwrap();
+        assert_eq!((first.events_inserted, first.content_actions_inserted), (2, 2));
+        let second = replay_content_actions(&service, &tenant_id, range, &actions).await.unwrap();
+        assert_eq!((second.events_inserted, second.content_actions_inserted), (0, 0));
+
+        let expected: HashSet<Uuid> = actions.iter().map(|action| action.event_id).collect();
+        for table in ["events", "content_analytics"] {
+            let ids = event_ids_between(&client, table, tenant_id.as_uuid(), day, day).await.unwrap();
+            assert_eq!(ids, expected, "{}", table);
+        }
+        let count: u64 = client
+            .query("SELECT count() FROM content_analytics WHERE tenant_id = ?")
+            .bind(tenant_id.as_uuid())
+            .fetch_one()
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::{
+    config::AnalyticsConfig,
+    database::{
+        clickhouse::{delete_tenant_analytics, purge_events_before, Client, TenantScope},
+        postgres::tenant_client,
+        DatabaseConnections,
+    },
+    types::TenantId,
+};
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use uuid::Uuid;
+
+/// Service enforcing analytics retention and per-tenant erasure
+#[derive(Clone)]
+pub struct AnalyticsRetentionService {
+    db: DatabaseConnections,
+    config: AnalyticsConfig,
+}
+
+impl AnalyticsRetentionService {
+    pub fn new(db: DatabaseConnections, config: AnalyticsConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// Purge events past retention for every tenant
+    pub async fn purge_expired(&self) -> Result<()> {
+        let overrides = self.retention_overrides().await?;
+        purge_with_overrides(
+            self.db.clickhouse().client(),
+            &self.config,
+            Utc::now().date_naive(),
+            &overrides,
+        )
+        .await
+    }
+
+    /// Set (or clear, with `None`) a tenant's retention override. Returns false if the
+    /// tenant doesn't exist.
+    pub async fn set_tenant_retention(&self, tenant_id: &TenantId, retention_days: Option<u32>) -> Result<bool> {
+        let retention_days = retention_days
+            .map(i32::try_from)
+            .transpose()
+            .context("Analytics retention out of range")?;
+        let client = tenant_client(self.db.postgres(), tenant_id).await?;
+
+        let rows_affected = client
+            .execute(
+                "UPDATE tenants SET analytics_retention_days = $2, updated_at = NOW() WHERE id = $1",
+                &[tenant_id.as_uuid(), &retention_days],
+            )
+            .await
+            .context("Failed to update tenant analytics retention")?;
+
+        Ok(rows_affected > 0)
+    }
+
+    /// Delete all of a tenant's analytics, raw and aggregated (GDPR erasure)
+    pub async fn delete_tenant_data(&self, tenant_id: &TenantId) -> Result<()> {
+        delete_tenant_analytics(self.db.clickhouse().client(), tenant_id.as_uuid()).await
+    }
+
+    /// Run the purge on the configured interval for the lifetime of the process
+    pub fn spawn_purge_task(self) -> JoinHandle<()> {
+        let period = Duration::from_secs(self.config.purge_interval_hours.max(1) * 3600);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                match self.purge_expired().await {
+                    Ok(()) => info!("Analytics retention purge completed"),
+                    Err(e) => error!("Analytics retention purge failed: {:#}", e),
+                }
+            }
+        })
+    }
+
+    /// Tenants with their own retention period
+    async fn retention_overrides(&self) -> Result<Vec<(Uuid, u32)>> {
+        let client = self.db.postgres().get().await
+            .context("Failed to get database connection")?;
+
+        let rows = client
+            .query("SELECT tenant_id, retention_days FROM analytics_retention_overrides()", &[])
+            .await
+            .context("Failed to load tenant analytics retention")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("tenant_id"), row.get::<_, i32>("retention_days").max(1) as u32))
+            .collect())
+    }
+}
+
+/// First day kept when retaining `retention_days` of history
+pub fn retention_cutoff(today: NaiveDate, retention_days: u32) -> NaiveDate {
+    today - chrono::Duration::days(retention_days as i64)
+}
+
+/// Purge each overridden tenant with its own cutoff, then everyone else with the default
+pub async fn purge_with_overrides(
+    client: &Client,
+    config: &AnalyticsConfig,
+    today: NaiveDate,
+    overrides: &[(Uuid, u32)],
+) -> Result<()> {
+    for (tenant_id, retention_days) in overrides {
+        let cutoff = retention_cutoff(today, *retention_days);
+        purge_events_before(client, TenantScope::Only(tenant_id), cutoff, config.rollup_before_purge).await?;
+    }
+
+    let overridden: Vec<Uuid> = overrides.iter().map(|(tenant_id, _)| *tenant_id).collect();
+    let cutoff = retention_cutoff(today, config.retention_days);
+    purge_events_before(client, TenantScope::AllExcept(&overridden), cutoff, config.rollup_before_purge).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::clickhouse::{test_client, AnalyticsService as ClickHouseAnalyticsService};
+    use crate::types::AnalyticsEvent;
+
+    async fn record_event(service: &ClickHouseAnalyticsService, tenant_id: &TenantId, days_ago: i64) {
+        let event = AnalyticsEvent {
+            event_id: Uuid::new_v4(),
+            tenant_id: *tenant_id.as_uuid(),
+            user_id: Some(Uuid::new_v4()),
+            event_type: "page_view".to_string(),
+            event_data: serde_json::json!({}),
+            timestamp: Utc::now() - chrono::Duration::days(days_ago),
+            session_id: None,
+            ip_address: None,
+            user_agent: None,
+        };
+        service.record_event(&event).await.expect("Failed to record test event");
+    }
+
+    const RAW_EVENTS: &str = "SELECT count() FROM events WHERE tenant_id = ?";
+    const ROLLUP_EVENTS: &str = "SELECT toUInt64(sum(total_events)) FROM events_daily WHERE tenant_id = ?";
+
+    async fn count(client: &Client, query: &str, tenant_id: &TenantId) -> u64 {
+        client
+            .query(query)
+            .bind(tenant_id.as_uuid())
+            .fetch_one::<u64>()
+            .await
+            .expect("Count query failed")
+    }
+
+    #[test]
+    fn test_retention_cutoff() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 31).unwrap();
+        assert_eq!(retention_cutoff(today, 30), NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_purge_removes_expired_events_and_keeps_rollups() {
+        let Some(client) = test_client().await else {
+            return;
+        };
+        let service = ClickHouseAnalyticsService::new(client.clone());
+        let tenant_id = TenantId::new();
+
+        record_event(&service, &tenant_id, 40).await;
+        record_event(&service, &tenant_id, 35).await;
+        record_event(&service, &tenant_id, 2).await;
+
+        let config = AnalyticsConfig {
+            retention_days: 730,
+            ..AnalyticsConfig::default()
+        };
+        let overrides = [(*tenant_id.as_uuid(), 30)];
+        purge_with_overrides(&client, &config, Utc::now().date_naive(), &overrides)
+            .await
+            .expect("Purge failed");
+
+        assert_eq!(count(&client, RAW_EVENTS, &tenant_id).await, 1);
+        assert_eq!(count(&client, ROLLUP_EVENTS, &tenant_id).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_deletion_only_removes_that_tenant() {
+        let Some(client) = test_client().await else {
+            return;
+        };
+        let service = ClickHouseAnalyticsService::new(client.clone());
+        let deleted_tenant = TenantId::new();
+        let other_tenant = TenantId::new();
+
+        record_event(&service, &deleted_tenant, 1).await;
+        record_event(&service, &other_tenant, 1).await;
+
+        delete_tenant_analytics(&client, deleted_tenant.as_uuid())
+            .await
+            .expect("Tenant deletion failed");
+
+        assert_eq!(count(&client, RAW_EVENTS, &deleted_tenant).await, 0);
+        assert_eq!(count(&client, ROLLUP_EVENTS, &deleted_tenant).await, 0);
+        assert_eq!(count(&client, RAW_EVENTS, &other_tenant).await, 1);
+        assert_eq!(count(&client, ROLLUP_EVENTS, &other_tenant).await, 1);
+    }
+}
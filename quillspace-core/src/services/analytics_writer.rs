@@ -0,0 +1,279 @@
+use crate::{
+    config::AnalyticsConfig,
+    database::clickhouse::{AnalyticsService as ClickHouseAnalyticsService, ContentAction},
+    services::metrics_registry::MetricsRegistry,
+    types::AnalyticsEvent,
+};
+use anyhow::Result;
+use chrono::Utc;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// An analytics write queued from the request path
+#[derive(Debug, Clone)]
+pub enum AnalyticsWrite {
+    Event(AnalyticsEvent),
+    ContentAction {
+        event_id: Uuid,
+        tenant_id: Uuid,
+        content_id: Uuid,
+        action: String,
+        user_id: Option<Uuid>,
+        metadata: serde_json::Value,
+    },
+}
+
+/// Backend the background worker flushes writes to
+pub trait AnalyticsSink: Send + Sync + 'static {
+    fn write(&self, write: AnalyticsWrite) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl AnalyticsSink for ClickHouseAnalyticsService {
+    async fn write(&self, write: AnalyticsWrite) -> Result<()> {
+        match write {
+            AnalyticsWrite::Event(event) => self.record_event(&event).await,
+            AnalyticsWrite::ContentAction { event_id, tenant_id, content_id, action, user_id, metadata } => {
+                self.insert_content_action(&ContentAction {
+                    event_id,
+                    tenant_id,
+                    content_id,
+                    action,
+                    user_id,
+                    timestamp: Utc::now(),
+                    metadata,
+                })
+                .await
+            }
+        }
+    }
+}
+
+/// Stops calling a failing backend for a cool-down period after repeated errors
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Whether a call may be attempted; after the cool-down one trial call is let through
+    pub fn allow(&self, now: Instant) -> bool {
+        let state = self.state.lock().unwrap();
+        match state.opened_at {
+            Some(opened_at) => now.duration_since(opened_at) >= self.cooldown,
+            None => true,
+        }
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = BreakerState::default();
+    }
+
+    pub fn record_failure(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.failure_threshold {
+            if state.opened_at.is_none() {
+                warn!("Analytics backend failing, pausing writes for {:?}", self.cooldown);
+            }
+            // A failed trial call restarts the cool-down
+            state.opened_at = Some(now);
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state.lock().unwrap().opened_at.is_some()
+    }
+}
+
+/// Non-blocking analytics recording for the request hot path.
+///
+/// Writes go into a bounded buffer drained by a background task, so a slow or
+/// unavailable analytics backend never delays or fails the primary operation.
+/// When the buffer is full the write is dropped and logged.
+#[derive(Clone)]
+pub struct AnalyticsWriter {
+    sender: mpsc::Sender<AnalyticsWrite>,
+    breaker: Arc<CircuitBreaker>,
+    metrics: MetricsRegistry,
+}
+
+impl AnalyticsWriter {
+    /// Start the background worker flushing to `sink`; dropped and failed writes are
+    /// counted in `metrics`
+    pub fn spawn<S: AnalyticsSink>(sink: S, config: &AnalyticsConfig, metrics: MetricsRegistry) -> Self {
+        let (sender, receiver) = mpsc::channel(config.buffer_capacity.max(1));
+        let breaker = Arc::new(CircuitBreaker::new(
+            config.breaker_failure_threshold,
+            Duration::from_secs(config.breaker_cooldown_secs),
+        ));
+
+        tokio::spawn(run_worker(sink, receiver, breaker.clone(), metrics.clone()));
+
+        Self { sender, breaker, metrics }
+    }
+
+    /// Queue a write without waiting; never fails the caller
+    pub fn record(&self, write: AnalyticsWrite) {
+        match self.sender.try_send(write) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.metrics.record(|| {
+                    metrics::counter!("analytics_writes_dropped_total", "reason" => "buffer_full").increment(1)
+                });
+                warn!("Analytics buffer full, dropping write");
+            }
+            Err(TrySendError::Closed(_)) => {
+                self.metrics.record(|| {
+                    metrics::counter!("analytics_writes_dropped_total", "reason" => "closed").increment(1)
+                });
+                warn!("Analytics writer stopped, dropping write");
+            }
+        }
+    }
+
+    /// Queue an analytics event
+    pub fn record_event(&self, event: AnalyticsEvent) {
+        self.record(AnalyticsWrite::Event(event));
+    }
+
+    /// Queue a content action. Its `event_id` is what analytics replay dedupes on, see
+    /// [`content_event_id`](crate::services::analytics_replay::content_event_id).
+    pub fn record_content_action(
+        &self,
+        event_id: Uuid,
+        tenant_id: Uuid,
+        content_id: Uuid,
+        action: &str,
+        user_id: Option<Uuid>,
+        metadata: serde_json::Value,
+    ) {
+        self.record(AnalyticsWrite::ContentAction {
+            event_id,
+            tenant_id,
+            content_id,
+            action: action.to_string(),
+            user_id,
+            metadata,
+        });
+    }
+
+    /// Whether writes are currently being skipped because the backend is failing
+    pub fn is_degraded(&self) -> bool {
+        self.breaker.is_open()
+    }
+}
+
+async fn run_worker<S: AnalyticsSink>(
+    sink: S,
+    mut receiver: mpsc::Receiver<AnalyticsWrite>,
+    breaker: Arc<CircuitBreaker>,
+    metrics: MetricsRegistry,
+) {
+    while let Some(write) = receiver.recv().await {
+        if !breaker.allow(Instant::now()) {
+            metrics.record(|| {
+                metrics::counter!("analytics_writes_dropped_total", "reason" => "circuit_open").increment(1)
+            });
+            debug!("Analytics circuit open, dropping write");
+            continue;
+        }
+
+        match sink.write(write).await {
+            Ok(()) => breaker.record_success(),
+            Err(e) => {
+                metrics.record(|| metrics::counter!("analytics_writes_failed_total").increment(1));
+                warn!("Failed to record analytics: {:#}", e);
+                breaker.record_failure(Instant::now());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Backend that is down: every write errors after a delay
+    #[derive(Clone, Default)]
+    struct FailingSink {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl AnalyticsSink for FailingSink {
+        async fn write(&self, _write: AnalyticsWrite) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            anyhow::bail!("ClickHouse unavailable")
+        }
+    }
+
+    fn config(buffer_capacity: usize) -> AnalyticsConfig {
+        AnalyticsConfig {
+            buffer_capacity,
+            breaker_failure_threshold: 3,
+            breaker_cooldown_secs: 60,
+            ..AnalyticsConfig::default()
+        }
+    }
+
+    /// Stand-in for a handler: the primary write, then analytics on the side
+    async fn create_content(writer: &AnalyticsWriter) -> Result<Uuid> {
+        let content_id = Uuid::new_v4();
+        writer.record_content_action(Uuid::new_v4(), Uuid::new_v4(), content_id, "create", None, serde_json::json!({}));
+        Ok(content_id)
+    }
+
+    #[tokio::test]
+    async fn test_content_creation_succeeds_when_analytics_fails() {
+        let sink = FailingSink::default();
+        let writer = AnalyticsWriter::spawn(sink.clone(), &config(10), MetricsRegistry::default());
+
+        // More writes than the buffer holds, against a backend that always errors
+        let started = Instant::now();
+        for _ in 0..20 {
+            assert!(create_content(&writer).await.is_ok());
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(writer.is_degraded());
+        assert_eq!(sink.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_circuit_breaker_opens_and_recovers() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(30));
+        let now = Instant::now();
+
+        breaker.record_failure(now);
+        assert!(breaker.allow(now));
+        breaker.record_failure(now);
+        assert!(!breaker.allow(now + Duration::from_secs(10)));
+
+        // Trial call after the cool-down; success closes the breaker
+        assert!(breaker.allow(now + Duration::from_secs(30)));
+        breaker.record_success();
+        assert!(!breaker.is_open());
+    }
+}
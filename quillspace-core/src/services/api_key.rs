@@ -0,0 +1,181 @@
+use crate::auth::api_keys::{api_key_prefix, generate_api_key, ApiKeyRecord};
+use crate::database::postgres::tenant_client;
+use crate::types::{TenantId, UserRole};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// API key metadata (never includes the key itself)
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub created_by: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub role: UserRole,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// API key creation request
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub role: Option<UserRole>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Newly created key; `key` is only ever returned here
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+/// Service for tenant-scoped API keys used for server-to-server access
+pub struct ApiKeyService {
+    db: Pool,
+}
+
+impl ApiKeyService {
+    pub fn new(db: Pool) -> Self {
+        Self { db }
+    }
+
+    /// Create a new key for the tenant
+    pub async fn create_api_key(
+        &self,
+        tenant_id: &TenantId,
+        created_by: Uuid,
+        role: &UserRole,
+        request: CreateApiKeyRequest,
+    ) -> Result<CreatedApiKey> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let generated = generate_api_key();
+
+        let row = client
+            .query_one(
+                "INSERT INTO api_keys (tenant_id, created_by, name, key_prefix, key_hash, role, scopes, expires_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                 RETURNING *",
+                &[
+                    tenant_id.as_uuid(),
+                    &created_by,
+                    &request.name,
+                    &generated.prefix,
+                    &generated.hash,
+                    role,
+                    &request.scopes,
+                    &request.expires_at,
+                ],
+            )
+            .await
+            .context("Failed to create API key")?;
+
+        Ok(CreatedApiKey {
+            api_key: row_to_api_key(&row)?,
+            key: generated.key,
+        })
+    }
+
+    /// List keys for the tenant, newest first
+    pub async fn list_api_keys(&self, tenant_id: &TenantId) -> Result<Vec<ApiKey>> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let rows = client
+            .query(
+                "SELECT * FROM api_keys WHERE tenant_id = $1 ORDER BY created_at DESC",
+                &[tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to list API keys")?;
+
+        rows.iter().map(row_to_api_key).collect()
+    }
+
+    /// Revoke a key. Returns false if no active key matched.
+    pub async fn revoke_api_key(&self, tenant_id: &TenantId, key_id: Uuid) -> Result<bool> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let rows_affected = client
+            .execute(
+                "UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND tenant_id = $2 AND revoked_at IS NULL",
+                &[&key_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to revoke API key")?;
+
+        Ok(rows_affected > 0)
+    }
+
+    /// Resolve a presented key to its stored record, if valid (not revoked or expired)
+    pub async fn authenticate(&self, key: &str) -> Result<Option<ApiKey>> {
+        let prefix = match api_key_prefix(key) {
+            Some(prefix) => prefix,
+            None => return Ok(None),
+        };
+
+        let client = self.db.get().await
+            .context("Failed to get database connection")?;
+
+        let row = client
+            .query_opt("SELECT * FROM authenticate_api_key($1)", &[&prefix])
+            .await
+            .context("Failed to look up API key")?;
+
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let record = ApiKeyRecord {
+            key_hash: row.get("key_hash"),
+            scopes: row.get("scopes"),
+            expires_at: row.get("expires_at"),
+            revoked_at: row.get("revoked_at"),
+        };
+        if !record.authenticates(key, Utc::now()) {
+            return Ok(None);
+        }
+
+        let api_key = row_to_api_key(&row)?;
+        drop(client);
+
+        // The key's tenant is known now, so its usage is recorded under RLS like any write
+        tenant_client(&self.db, &TenantId::from_uuid(api_key.tenant_id))
+            .await?
+            .execute("UPDATE api_keys SET last_used_at = NOW() WHERE id = $1", &[&api_key.id])
+            .await
+            .context("Failed to record API key usage")?;
+
+        Ok(Some(api_key))
+    }
+}
+
+/// Convert database row to ApiKey struct
+fn row_to_api_key(row: &Row) -> Result<ApiKey> {
+    Ok(ApiKey {
+        id: row.get("id"),
+        tenant_id: row.get("tenant_id"),
+        created_by: row.get("created_by"),
+        name: row.get("name"),
+        key_prefix: row.get("key_prefix"),
+        role: row.get("role"),
+        scopes: row.get("scopes"),
+        expires_at: row.get("expires_at"),
+        last_used_at: row.get("last_used_at"),
+        revoked_at: row.get("revoked_at"),
+        created_at: row.get("created_at"),
+    })
+}
@@ -1,8 +1,15 @@
+use crate::database::postgres::{tenant_client, tenant_transaction};
+use crate::config::StorageConfig;
+use crate::services::object_store::{ObjectStore, ObjectStoreError};
 use crate::types::TenantId;
 use anyhow::{Context, Result};
-use deadpool_postgres::Pool;
+use deadpool_postgres::{Pool, Transaction};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio_postgres::Row;
+use tracing::warn;
 use uuid::Uuid;
 
 /// Asset entity representing uploaded files and media
@@ -19,6 +26,11 @@ pub struct Asset {
     pub cdn_url: Option<String>,
     pub alt_text: Option<String>,
     pub is_optimized: bool,
+    /// Hex SHA-256 of the file, used to spot re-uploads
+    pub content_hash: Option<String>,
+    /// Pixel dimensions, for images
+    pub width: Option<i32>,
+    pub height: Option<i32>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -34,6 +46,26 @@ pub struct CreateAssetRequest {
     pub storage_path: String,
     pub cdn_url: Option<String>,
     pub alt_text: Option<String>,
+    /// Hex SHA-256 of the file (see `content_hash`)
+    pub content_hash: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// Store the file even if an identical one is already in the library
+    #[serde(default)]
+    pub allow_duplicate: bool,
+}
+
+/// Details sent alongside an uploaded file's bytes
+#[derive(Debug, Deserialize)]
+pub struct UploadAssetRequest {
+    pub site_id: Option<Uuid>,
+    /// Name the file had on the uploader's machine
+    pub filename: String,
+    pub alt_text: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    #[serde(default)]
+    pub allow_duplicate: bool,
 }
 
 /// Asset update request
@@ -45,55 +77,346 @@ pub struct UpdateAssetRequest {
 }
 
 /// Asset list query parameters
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct AssetListQuery {
     pub site_id: Option<Uuid>,
     pub mime_type_filter: Option<String>, // e.g., "image/", "video/", "application/"
+    #[serde(rename = "type")]
+    pub asset_type: Option<AssetType>,
+    /// Case-insensitive substring of the original filename
+    #[serde(rename = "q")]
+    pub search: Option<String>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
 
+/// One page of assets and how many match in total
+#[derive(Debug)]
+pub struct AssetPage {
+    pub items: Vec<Asset>,
+    pub total: i64,
+}
+
+/// Kinds of media the library can be filtered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetType {
+    Image,
+    Document,
+    Video,
+    Audio,
+}
+
+impl AssetType {
+    /// MIME types of this kind, as SQL `LIKE` patterns
+    pub fn mime_patterns(&self) -> &'static [&'static str] {
+        match self {
+            AssetType::Image => &["image/%"],
+            AssetType::Document => &[
+                "application/pdf",
+                "application/msword",
+                "application/epub+zip",
+                "application/rtf",
+                "application/vnd.openxmlformats-officedocument.%",
+                "application/vnd.oasis.opendocument.%",
+                "text/%",
+            ],
+            AssetType::Video => &["video/%"],
+            AssetType::Audio => &["audio/%"],
+        }
+    }
+
+    /// Whether a MIME type is of this kind, mirroring the SQL filter
+    pub fn matches(&self, mime_type: &str) -> bool {
+        let mime_type = mime_type.to_ascii_lowercase();
+        self.mime_patterns().iter().any(|pattern| match pattern.strip_suffix('%') {
+            Some(prefix) => mime_type.starts_with(prefix),
+            None => mime_type == *pattern,
+        })
+    }
+}
+
+/// A resized copy of an image served by the CDN
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImageVariant {
+    pub name: String,
+    pub width: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    pub url: String,
+}
+
+/// An asset as listed in the media library
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryAsset {
+    #[serde(flatten)]
+    pub asset: Asset,
+    pub variants: Vec<ImageVariant>,
+}
+
+/// Hex SHA-256 of a file's bytes; upload clients send this as `content_hash`
+pub fn content_hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Lower-case a client-supplied hash, refusing anything that isn't hex SHA-256
+pub fn normalize_content_hash(hash: &str) -> Option<String> {
+    let hash = hash.trim().to_ascii_lowercase();
+    (hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then_some(hash)
+}
+
+/// Variant URLs for an image, narrowest first. Images are never scaled up, and
+/// heights follow the original aspect ratio when the dimensions are known.
+pub fn image_variants(asset: &Asset, variants: &std::collections::HashMap<String, u32>) -> Vec<ImageVariant> {
+    let Some(cdn_url) = asset.cdn_url.as_deref().filter(|_| AssetType::Image.matches(&asset.mime_type)) else {
+        return Vec::new();
+    };
+    let separator = if cdn_url.contains('?') { '&' } else { '?' };
+    let original_width = asset.width.and_then(|width| u32::try_from(width).ok());
+    let original_height = asset.height.and_then(|height| u32::try_from(height).ok());
+
+    let mut list: Vec<ImageVariant> = variants
+        .iter()
+        .filter(|(_, width)| !matches!(original_width, Some(original) if **width >= original))
+        .map(|(name, width)| ImageVariant {
+            name: name.clone(),
+            width: *width,
+            height: match (original_width, original_height) {
+                (Some(original), Some(height)) if original > 0 => {
+                    Some(((height as u64 * *width as u64 + original as u64 / 2) / original as u64) as u32)
+                }
+                _ => None,
+            },
+            url: format!("{}{}width={}", cdn_url, separator, width),
+        })
+        .collect();
+    list.sort_by(|a, b| a.width.cmp(&b.width).then_with(|| a.name.cmp(&b.name)));
+    list
+}
+
+/// Escape `%`, `_` and `\` so user input matches literally inside a `LIKE` pattern
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A tenant's storage use against its plan quota, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct StorageUsage {
+    pub used_bytes: i64,
+    pub quota_bytes: i64,
+    pub remaining_bytes: i64,
+}
+
+impl StorageUsage {
+    pub fn new(used_bytes: i64, quota_bytes: i64) -> Self {
+        Self {
+            used_bytes,
+            quota_bytes,
+            remaining_bytes: quota_bytes.saturating_sub(used_bytes).max(0),
+        }
+    }
+
+    /// Usage after storing `bytes` more, or the refusal if that would pass the quota
+    pub fn reserve(&self, bytes: i64) -> std::result::Result<StorageUsage, QuotaExceeded> {
+        if bytes > self.remaining_bytes {
+            return Err(QuotaExceeded { requested_bytes: bytes, usage: *self });
+        }
+        Ok(StorageUsage::new(self.used_bytes + bytes, self.quota_bytes))
+    }
+
+    /// Usage after `bytes` were deleted
+    pub fn release(&self, bytes: i64) -> StorageUsage {
+        StorageUsage::new(self.used_bytes.saturating_sub(bytes).max(0), self.quota_bytes)
+    }
+}
+
+/// Details returned to the client when an upload does not fit the quota
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuotaExceeded {
+    pub requested_bytes: i64,
+    #[serde(flatten)]
+    pub usage: StorageUsage,
+}
+
+/// Most assets one batch delete may name
+pub const MAX_BATCH_DELETE: usize = 100;
+
+/// Batch delete request; referenced assets are kept unless `force` is set
+#[derive(Debug, Deserialize)]
+pub struct BatchDeleteRequest {
+    pub asset_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// A page or content item whose saved data mentions an asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssetReference {
+    /// `page` or `content`
+    pub kind: String,
+    pub id: Uuid,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchDeleteStatus {
+    Deleted,
+    /// Still referenced, and the request did not force deletion
+    Blocked,
+    NotFound,
+}
+
+/// Outcome for one asset of a batch delete
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BatchDeleteResult {
+    pub asset_id: Uuid,
+    pub status: BatchDeleteStatus,
+    pub references: Vec<AssetReference>,
+}
+
+/// What a batch delete will do, decided before anything is deleted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchDeletePlan {
+    pub results: Vec<BatchDeleteResult>,
+    pub delete_ids: Vec<Uuid>,
+    pub freed_bytes: i64,
+}
+
+/// Decide each requested asset's fate from the assets found and their references.
+/// Results follow the request order, and a repeated id is reported once.
+pub fn plan_batch_delete(requested: &[Uuid], found: Vec<(Asset, Vec<AssetReference>)>, force: bool) -> BatchDeletePlan {
+    let mut found: std::collections::HashMap<Uuid, (Asset, Vec<AssetReference>)> =
+        found.into_iter().map(|(asset, references)| (asset.id, (asset, references))).collect();
+    let mut plan = BatchDeletePlan { results: Vec::new(), delete_ids: Vec::new(), freed_bytes: 0 };
+    let mut seen = std::collections::HashSet::new();
+
+    for asset_id in requested {
+        if !seen.insert(*asset_id) {
+            continue;
+        }
+        let Some((asset, references)) = found.remove(asset_id) else {
+            plan.results.push(BatchDeleteResult { asset_id: *asset_id, status: BatchDeleteStatus::NotFound, references: Vec::new() });
+            continue;
+        };
+        let status = if references.is_empty() || force {
+            plan.delete_ids.push(asset.id);
+            plan.freed_bytes += asset.file_size;
+            BatchDeleteStatus::Deleted
+        } else {
+            BatchDeleteStatus::Blocked
+        };
+        plan.results.push(BatchDeleteResult { asset_id: *asset_id, status, references });
+    }
+    plan
+}
+
+/// LIKE patterns matching saved data that mentions the asset: its id, CDN URL or storage path
+pub fn reference_patterns(asset: &Asset) -> Vec<String> {
+    [Some(asset.id.to_string()), asset.cdn_url.clone(), Some(asset.storage_path.clone())]
+        .into_iter()
+        .flatten()
+        .filter(|key| !key.is_empty())
+        .map(|key| format!("%{}%", escape_like(&key)))
+        .collect()
+}
+
+/// Asset service errors
+#[derive(Debug, thiserror::Error)]
+pub enum AssetServiceError {
+    #[error("Site not found or access denied")]
+    SiteNotFound,
+
+    #[error("Invalid file size: {0}")]
+    InvalidFileSize(i64),
+
+    #[error("Invalid content hash")]
+    InvalidContentHash,
+
+    #[error("An identical file is already in the library")]
+    Duplicate(Box<Asset>),
+
+    #[error("Storage quota exceeded: {} bytes requested, {} remaining", .0.requested_bytes, .0.usage.remaining_bytes)]
+    QuotaExceeded(QuotaExceeded),
+
+    #[error(transparent)]
+    Storage(#[from] ObjectStoreError),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
 /// Asset service for managing uploaded files and media
 pub struct AssetService {
     db: Pool,
+    storage: StorageConfig,
+    store: Arc<dyn ObjectStore>,
 }
 
 impl AssetService {
-    pub fn new(db: Pool) -> Self {
-        Self { db }
+    pub fn new(db: Pool, storage: StorageConfig, store: Arc<dyn ObjectStore>) -> Self {
+        Self { db, storage, store }
     }
 
-    /// Create a new asset record
+    /// Create a new asset record, counting its size against the tenant's storage quota.
+    /// A file identical to one already stored is refused unless `allow_duplicate` is set.
     pub async fn create_asset(
         &self,
         tenant_id: &TenantId,
         request: CreateAssetRequest,
-    ) -> Result<Asset> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
+    ) -> Result<Asset, AssetServiceError> {
+        if request.file_size < 0 {
+            return Err(AssetServiceError::InvalidFileSize(request.file_size));
+        }
+        let content_hash = match request.content_hash.as_deref() {
+            Some(hash) => Some(normalize_content_hash(hash).ok_or(AssetServiceError::InvalidContentHash)?),
+            None => None,
+        };
 
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
 
         // Validate site_id if provided
         if let Some(site_id) = request.site_id {
-            let site_exists = client
+            let site_exists = transaction
                 .query_opt("SELECT id FROM sites WHERE id = $1", &[&site_id])
                 .await
                 .context("Failed to verify site existence")?;
 
             if site_exists.is_none() {
-                return Err(anyhow::anyhow!("Site not found or access denied"));
+                return Err(AssetServiceError::SiteNotFound);
             }
         }
 
-        let row = client
+        // Checked under the usage lock, so two concurrent uploads of one file can't both pass
+        let usage = self.lock_storage_usage(&transaction, tenant_id).await?;
+        if let (Some(hash), false) = (&content_hash, request.allow_duplicate) {
+            let existing = transaction
+                .query_opt(
+                    "SELECT * FROM assets WHERE tenant_id = $1 AND content_hash = $2 ORDER BY created_at LIMIT 1",
+                    &[tenant_id.as_uuid(), hash],
+                )
+                .await
+                .context("Failed to check for duplicate assets")?;
+            if let Some(existing) = existing {
+                return Err(AssetServiceError::Duplicate(Box::new(row_to_asset(&existing)?)));
+            }
+        }
+        let usage = usage.reserve(request.file_size).map_err(AssetServiceError::QuotaExceeded)?;
+        write_storage_usage(&transaction, tenant_id, &usage).await?;
+
+        let row = transaction
             .query_one(
-                "INSERT INTO assets (tenant_id, site_id, filename, original_filename, mime_type, file_size, storage_path, cdn_url, alt_text) 
-                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) 
+                "INSERT INTO assets (tenant_id, site_id, filename, original_filename, mime_type, file_size, storage_path, cdn_url, alt_text, content_hash, width, height) 
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) 
                  RETURNING *",
                 &[
                     tenant_id.as_uuid(),
@@ -105,12 +428,81 @@ impl AssetService {
                     &request.storage_path,
                     &request.cdn_url,
                     &request.alt_text,
+                    &content_hash,
+                    &request.width,
+                    &request.height,
                 ],
             )
             .await
             .context("Failed to create asset")?;
+        let asset = row_to_asset(&row)?;
+
+        transaction.commit().await
+            .context("Failed to commit asset")?;
+        Ok(asset)
+    }
+
+    /// Store an uploaded file in the object store and record it as an asset. The
+    /// file is removed again if the record is refused (quota, duplicate, ...).
+    pub async fn upload_asset(
+        &self,
+        tenant_id: &TenantId,
+        request: UploadAssetRequest,
+        mime_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<Asset, AssetServiceError> {
+        if bytes.is_empty() {
+            return Err(AssetServiceError::InvalidFileSize(0));
+        }
+        let file_size = i64::try_from(bytes.len()).map_err(|_| AssetServiceError::InvalidFileSize(i64::MAX))?;
+        let hash = content_hash(&bytes);
+        let filename = storage_filename(&request.filename);
+        let key = format!("{}/{}/{}", tenant_id, Uuid::new_v4(), filename);
+
+        self.store.put(&key, bytes, mime_type).await?;
+
+        let create = CreateAssetRequest {
+            site_id: request.site_id,
+            filename,
+            original_filename: request.filename,
+            mime_type: mime_type.to_string(),
+            file_size,
+            storage_path: key.clone(),
+            cdn_url: None,
+            alt_text: request.alt_text,
+            content_hash: Some(hash),
+            width: request.width,
+            height: request.height,
+            allow_duplicate: request.allow_duplicate,
+        };
+        let created = self.create_asset(tenant_id, create).await;
+        if created.is_err() {
+            self.remove_files(vec![key]).await;
+        }
+        created
+    }
+
+    /// A link to the asset's file that works without credentials until `expires_in` passes
+    pub async fn asset_file_url(
+        &self,
+        tenant_id: &TenantId,
+        asset_id: Uuid,
+        expires_in: Duration,
+    ) -> Result<Option<String>, AssetServiceError> {
+        let Some(asset) = self.get_asset(tenant_id, asset_id).await? else {
+            return Ok(None);
+        };
+        Ok(Some(self.store.signed_url(&asset.storage_path, expires_in)?))
+    }
 
-        Ok(row_to_asset(&row)?)
+    /// Remove files from the object store after their records are gone; a file left
+    /// behind only costs space, so failures are logged rather than returned
+    async fn remove_files(&self, paths: Vec<String>) {
+        for path in paths {
+            if let Err(e) = self.store.delete(&path).await {
+                warn!(storage_path = %path, "Failed to remove asset file: {:#}", e);
+            }
+        }
     }
 
     /// Get asset by ID
@@ -119,14 +511,7 @@ impl AssetService {
         tenant_id: &TenantId,
         asset_id: Uuid,
     ) -> Result<Option<Asset>> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
-
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         let row = client
             .query_opt("SELECT * FROM assets WHERE id = $1", &[&asset_id])
@@ -139,49 +524,67 @@ impl AssetService {
         }
     }
 
-    /// List assets for a tenant
+    /// List a tenant's assets, newest first, with the total matching the filters
     pub async fn list_assets(
         &self,
         tenant_id: &TenantId,
         query: AssetListQuery,
-    ) -> Result<Vec<Asset>> {
+    ) -> Result<AssetPage> {
         let client = self.db.get().await
             .context("Failed to get database connection")?;
 
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
-
-        let limit = query.limit.unwrap_or(50).min(100);
-        let offset = query.offset.unwrap_or(0);
+        let limit = query.limit.unwrap_or(50).clamp(1, 100);
+        let offset = query.offset.unwrap_or(0).max(0);
 
         // Build dynamic query
-        let mut sql = "SELECT * FROM assets WHERE 1=1".to_string();
-        let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![];
-        let mut param_count = 0;
+        let mut filters = "WHERE tenant_id = $1".to_string();
+        let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![tenant_id.as_uuid()];
+        let mut param_count = 1;
 
         if let Some(site_id) = &query.site_id {
             param_count += 1;
-            sql.push_str(&format!(" AND site_id = ${}", param_count));
+            filters.push_str(&format!(" AND site_id = ${}", param_count));
             params.push(site_id);
         }
 
         if let Some(mime_filter) = &query.mime_type_filter {
             param_count += 1;
-            sql.push_str(&format!(" AND mime_type LIKE ${}", param_count));
+            filters.push_str(&format!(" AND mime_type LIKE ${}", param_count));
             params.push(mime_filter);
         }
 
-        sql.push_str(" ORDER BY created_at DESC");
-        
-        param_count += 1;
-        sql.push_str(&format!(" LIMIT ${}", param_count));
+        let mime_patterns: Option<Vec<&str>> = query.asset_type.map(|asset_type| asset_type.mime_patterns().to_vec());
+        if let Some(patterns) = &mime_patterns {
+            param_count += 1;
+            filters.push_str(&format!(" AND lower(mime_type) LIKE ANY(${})", param_count));
+            params.push(patterns);
+        }
+
+        let search = query
+            .search
+            .as_deref()
+            .map(str::trim)
+            .filter(|search| !search.is_empty())
+            .map(|search| format!("%{}%", escape_like(search)));
+        if let Some(search) = &search {
+            param_count += 1;
+            filters.push_str(&format!(" AND original_filename ILIKE ${}", param_count));
+            params.push(search);
+        }
+
+        let total: i64 = client
+            .query_one(&format!("SELECT COUNT(*) FROM assets {}", filters), &params)
+            .await
+            .context("Failed to count assets")?
+            .get(0);
+
+        let sql = format!(
+            "SELECT * FROM assets {} ORDER BY created_at DESC LIMIT ${} OFFSET ${}",
+            filters,
+            param_count + 1,
+            param_count + 2
+        );
         params.push(&limit);
-        
-        param_count += 1;
-        sql.push_str(&format!(" OFFSET ${}", param_count));
         params.push(&offset);
 
         let rows = client
@@ -189,12 +592,20 @@ impl AssetService {
             .await
             .context("Failed to list assets")?;
 
-        let mut assets = Vec::new();
+        let mut items = Vec::new();
         for row in rows {
-            assets.push(row_to_asset(&row)?);
+            items.push(row_to_asset(&row)?);
         }
 
-        Ok(assets)
+        Ok(AssetPage { items, total })
+    }
+
+    /// An asset as shown in the media library, with its image variants
+    pub fn library_asset(&self, asset: Asset) -> LibraryAsset {
+        LibraryAsset {
+            variants: image_variants(&asset, &self.storage.image_variants),
+            asset,
+        }
     }
 
     /// Update asset metadata
@@ -204,19 +615,12 @@ impl AssetService {
         asset_id: Uuid,
         request: UpdateAssetRequest,
     ) -> Result<Option<Asset>> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
-
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         // Build dynamic update query
         let mut set_clauses = Vec::new();
-        let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&asset_id];
-        let mut param_count = 1;
+        let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&asset_id, tenant_id.as_uuid()];
+        let mut param_count = 2;
 
         if let Some(alt_text) = &request.alt_text {
             param_count += 1;
@@ -242,7 +646,7 @@ impl AssetService {
         }
 
         let query = format!(
-            "UPDATE assets SET {}, updated_at = NOW() WHERE id = $1 RETURNING *",
+            "UPDATE assets SET {}, updated_at = NOW() WHERE id = $1 AND tenant_id = $2 RETURNING *",
             set_clauses.join(", ")
         );
 
@@ -257,121 +661,228 @@ impl AssetService {
         }
     }
 
-    /// Delete asset
+    /// Delete asset, returning its size to the tenant's storage quota
     pub async fn delete_asset(
         &self,
         tenant_id: &TenantId,
         asset_id: Uuid,
     ) -> Result<bool> {
-        let client = self.db.get().await
+        let mut client = self.db.get().await
             .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
 
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
-
-        let rows_affected = client
-            .execute("DELETE FROM assets WHERE id = $1", &[&asset_id])
+        let deleted = transaction
+            .query_opt("DELETE FROM assets WHERE id = $1 RETURNING file_size, storage_path", &[&asset_id])
             .await
             .context("Failed to delete asset")?;
-
-        Ok(rows_affected > 0)
-    }
-
-    /// Get assets by site
-    pub async fn get_assets_by_site(
-        &self,
-        tenant_id: &TenantId,
-        site_id: Uuid,
-    ) -> Result<Vec<Asset>> {
-        let query = AssetListQuery {
-            site_id: Some(site_id),
-            mime_type_filter: None,
-            limit: None,
-            offset: None,
+        let Some(deleted) = deleted else {
+            return Ok(false);
         };
 
-        self.list_assets(tenant_id, query).await
-    }
-
-    /// Get image assets only
-    pub async fn get_image_assets(
-        &self,
-        tenant_id: &TenantId,
-        site_id: Option<Uuid>,
-        limit: Option<i64>,
-        offset: Option<i64>,
-    ) -> Result<Vec<Asset>> {
-        let query = AssetListQuery {
-            site_id,
-            mime_type_filter: Some("image/%".to_string()),
-            limit,
-            offset,
-        };
+        let file_size: i64 = deleted.get("file_size");
+        let usage = self.lock_storage_usage(&transaction, tenant_id).await?;
+        write_storage_usage(&transaction, tenant_id, &usage.release(file_size)).await?;
+        let unreferenced = unreferenced_paths(&transaction, vec![deleted.get("storage_path")]).await?;
 
-        self.list_assets(tenant_id, query).await
+        transaction.commit().await
+            .context("Failed to commit asset deletion")?;
+        self.remove_files(unreferenced).await;
+        Ok(true)
     }
 
-    /// Count assets for a tenant
-    pub async fn count_assets(
+    /// Delete several assets in one transaction. Assets still mentioned by a page or
+    /// content item are kept unless `force` is set; every asset gets a result either way.
+    pub async fn batch_delete_assets(
         &self,
         tenant_id: &TenantId,
-        site_id: Option<Uuid>,
-    ) -> Result<i64> {
-        let client = self.db.get().await
+        asset_ids: &[Uuid],
+        force: bool,
+    ) -> Result<Vec<BatchDeleteResult>> {
+        let mut client = self.db.get().await
             .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
 
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
+        let rows = transaction
+            .query(
+                "SELECT * FROM assets WHERE tenant_id = $1 AND id = ANY($2) FOR UPDATE",
+                &[tenant_id.as_uuid(), &asset_ids],
+            )
             .await
-            .context("Failed to set RLS tenant context")?;
+            .context("Failed to load assets")?;
 
-        let query = if let Some(site_id) = site_id {
-            client
-                .query_one("SELECT COUNT(*) FROM assets WHERE site_id = $1", &[&site_id])
-                .await
-        } else {
-            client
-                .query_one("SELECT COUNT(*) FROM assets", &[])
+        let mut found = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let asset = row_to_asset(row)?;
+            let references = find_references(&transaction, tenant_id, &asset).await?;
+            found.push((asset, references));
+        }
+
+        let storage_paths: std::collections::HashMap<Uuid, String> = found
+            .iter()
+            .map(|(asset, _)| (asset.id, asset.storage_path.clone()))
+            .collect();
+        let plan = plan_batch_delete(asset_ids, found, force);
+        let mut unreferenced = Vec::new();
+        if !plan.delete_ids.is_empty() {
+            transaction
+                .execute("DELETE FROM assets WHERE tenant_id = $1 AND id = ANY($2)", &[tenant_id.as_uuid(), &plan.delete_ids])
                 .await
-        };
+                .context("Failed to delete assets")?;
+            let usage = self.lock_storage_usage(&transaction, tenant_id).await?;
+            write_storage_usage(&transaction, tenant_id, &usage.release(plan.freed_bytes)).await?;
+            let deleted_paths = plan.delete_ids.iter().filter_map(|id| storage_paths.get(id).cloned()).collect();
+            unreferenced = unreferenced_paths(&transaction, deleted_paths).await?;
+        }
 
-        let count: i64 = query
-            .context("Failed to count assets")?
-            .get(0);
+        transaction.commit().await
+            .context("Failed to commit asset deletion")?;
+        self.remove_files(unreferenced).await;
+        Ok(plan.results)
+    }
+
+    /// Tracked storage use of a tenant against its plan quota
+    pub async fn get_storage_quota_usage(&self, tenant_id: &TenantId) -> Result<StorageUsage> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
 
-        Ok(count)
+        let quota_bytes = self.quota_bytes(&transaction, tenant_id).await?;
+        let used_bytes: i64 = transaction
+            .query_opt("SELECT used_bytes FROM tenant_storage_usage WHERE tenant_id = $1", &[tenant_id.as_uuid()])
+            .await
+            .context("Failed to read storage usage")?
+            .map_or(0, |row| row.get("used_bytes"));
+
+        transaction.commit().await
+            .context("Failed to commit storage usage read")?;
+        Ok(StorageUsage::new(used_bytes, quota_bytes))
     }
 
-    /// Get total storage usage for a tenant
-    pub async fn get_storage_usage(
+    /// Count `bytes` more against the tenant's quota within the caller's transaction,
+    /// for records created outside `create_asset` (e.g. a site import)
+    pub(crate) async fn reserve_storage(
         &self,
+        transaction: &Transaction<'_>,
         tenant_id: &TenantId,
-    ) -> Result<i64> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
+        bytes: i64,
+    ) -> Result<(), AssetServiceError> {
+        let usage = self.lock_storage_usage(transaction, tenant_id).await?;
+        let usage = usage.reserve(bytes).map_err(AssetServiceError::QuotaExceeded)?;
+        write_storage_usage(transaction, tenant_id, &usage).await?;
+        Ok(())
+    }
 
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
+    /// Quota for the tenant's plan
+    async fn quota_bytes(&self, transaction: &Transaction<'_>, tenant_id: &TenantId) -> Result<i64> {
+        let settings: serde_json::Value = transaction
+            .query_opt("SELECT settings FROM tenants WHERE id = $1", &[tenant_id.as_uuid()])
             .await
-            .context("Failed to set RLS tenant context")?;
+            .context("Failed to read tenant settings")?
+            .map_or(serde_json::Value::Null, |row| row.get("settings"));
+
+        Ok(i64::try_from(self.storage.quota_for(&settings)).unwrap_or(i64::MAX))
+    }
+
+    /// Current usage, with the row locked until the transaction ends so concurrent
+    /// uploads of one tenant are checked against each other's totals
+    async fn lock_storage_usage(&self, transaction: &Transaction<'_>, tenant_id: &TenantId) -> Result<StorageUsage> {
+        let quota_bytes = self.quota_bytes(transaction, tenant_id).await?;
 
-        let total_size: Option<i64> = client
-            .query_one("SELECT COALESCE(SUM(file_size), 0) FROM assets", &[])
+        transaction
+            .execute(
+                "INSERT INTO tenant_storage_usage (tenant_id) VALUES ($1) ON CONFLICT (tenant_id) DO NOTHING",
+                &[tenant_id.as_uuid()],
+            )
             .await
-            .context("Failed to calculate storage usage")?
-            .get(0);
+            .context("Failed to initialize storage usage")?;
+        let used_bytes: i64 = transaction
+            .query_one(
+                "SELECT used_bytes FROM tenant_storage_usage WHERE tenant_id = $1 FOR UPDATE",
+                &[tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to lock storage usage")?
+            .get("used_bytes");
+
+        Ok(StorageUsage::new(used_bytes, quota_bytes))
+    }
+}
+
+/// Pages and content items whose saved data mentions the asset. The LIKE scans are
+/// served by the trigram indexes from migration 018.
+async fn find_references(transaction: &Transaction<'_>, tenant_id: &TenantId, asset: &Asset) -> Result<Vec<AssetReference>> {
+    let patterns = reference_patterns(asset);
+    let rows = transaction
+        .query(
+            "SELECT 'page' AS kind, p.id, p.title
+             FROM pages p JOIN sites s ON s.id = p.site_id
+             WHERE s.tenant_id = $1 AND p.puck_data::text LIKE ANY($2)
+             UNION ALL
+             SELECT 'content' AS kind, c.id, c.title
+             FROM content c
+             WHERE c.tenant_id = $1 AND c.body LIKE ANY($2)
+             LIMIT 20",
+            &[tenant_id.as_uuid(), &patterns],
+        )
+        .await
+        .context("Failed to check asset references")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| AssetReference {
+            kind: row.get("kind"),
+            id: row.get("id"),
+            title: row.get("title"),
+        })
+        .collect())
+}
+
+
+async fn write_storage_usage(transaction: &Transaction<'_>, tenant_id: &TenantId, usage: &StorageUsage) -> Result<()> {
+    transaction
+        .execute(
+            "UPDATE tenant_storage_usage SET used_bytes = $2, updated_at = NOW() WHERE tenant_id = $1",
+            &[tenant_id.as_uuid(), &usage.used_bytes],
+        )
+        .await
+        .context("Failed to update storage usage")?;
+    Ok(())
+}
 
-        Ok(total_size.unwrap_or(0))
+/// Of `paths`, the ones no remaining asset record points at. Imports and duplicate
+/// uploads can share a file, which must outlive all but the last of its records.
+async fn unreferenced_paths(transaction: &Transaction<'_>, mut paths: Vec<String>) -> Result<Vec<String>> {
+    paths.sort();
+    paths.dedup();
+    let referenced: Vec<String> = transaction
+        .query("SELECT DISTINCT storage_path FROM assets WHERE storage_path = ANY($1)", &[&paths])
+        .await
+        .context("Failed to check asset file references")?
+        .iter()
+        .map(|row| row.get("storage_path"))
+        .collect();
+    paths.retain(|path| !referenced.contains(path));
+    Ok(paths)
+}
+
+/// A file name safe to use in an object key: ASCII letters, digits, `.`, `-` and `_`
+pub fn storage_filename(original: &str) -> String {
+    let name = original.rsplit(['/', '\\']).next().unwrap_or_default();
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '-' })
+        .take(100)
+        .collect();
+    let sanitized = sanitized.trim_matches('.');
+    if sanitized.is_empty() {
+        "file".to_string()
+    } else {
+        sanitized.to_string()
     }
 }
 
 /// Convert database row to Asset struct
-fn row_to_asset(row: &Row) -> Result<Asset> {
+pub(crate) fn row_to_asset(row: &Row) -> Result<Asset> {
     Ok(Asset {
         id: row.get("id"),
         tenant_id: row.get("tenant_id"),
@@ -384,7 +895,177 @@ fn row_to_asset(row: &Row) -> Result<Asset> {
         cdn_url: row.get("cdn_url"),
         alt_text: row.get("alt_text"),
         is_optimized: row.get("is_optimized"),
+        content_hash: row.get("content_hash"),
+        width: row.get("width"),
+        height: row.get("height"),
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: i64 = 1024 * 1024;
+
+    #[test]
+    fn test_upload_exceeding_quota_rejected() {
+        let usage = StorageUsage::new(90 * MIB, 100 * MIB);
+        assert_eq!(usage.remaining_bytes, 10 * MIB);
+
+        let refused = usage.reserve(11 * MIB).unwrap_err();
+        assert_eq!(refused.requested_bytes, 11 * MIB);
+        assert_eq!(refused.usage.remaining_bytes, 10 * MIB);
+
+        let full = usage.reserve(10 * MIB).unwrap();
+        assert_eq!(full.remaining_bytes, 0);
+        assert!(full.reserve(1).is_err());
+    }
+
+    #[test]
+    fn test_deleting_asset_frees_quota() {
+        let full = StorageUsage::new(100 * MIB, 100 * MIB);
+        assert!(full.reserve(5 * MIB).is_err());
+
+        let freed = full.release(20 * MIB);
+        assert_eq!(freed.used_bytes, 80 * MIB);
+        assert_eq!(freed.reserve(5 * MIB).unwrap().used_bytes, 85 * MIB);
+        // Never below zero, even if the tracked total drifted
+        assert_eq!(freed.release(200 * MIB).used_bytes, 0);
+    }
+
+    fn asset(mime_type: &str, width: Option<i32>, height: Option<i32>) -> Asset {
+        Asset {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            site_id: None,
+            filename: "cover.jpg".to_string(),
+            original_filename: "Cover.jpg".to_string(),
+            mime_type: mime_type.to_string(),
+            file_size: MIB,
+            storage_path: "tenant/cover.jpg".to_string(),
+            cdn_url: Some("https://cdn.quillspace.com/tenant/cover.jpg".to_string()),
+            alt_text: None,
+            is_optimized: false,
+            content_hash: None,
+            width,
+            height,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_type_filter_selects_matching_assets() {
+        let library = ["image/jpeg", "image/PNG", "application/pdf", "text/markdown", "video/mp4",
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document", "application/zip"];
+        let of_type = |asset_type: AssetType| -> Vec<&str> {
+            library.iter().copied().filter(|mime| asset_type.matches(mime)).collect()
+        };
+
+        assert_eq!(of_type(AssetType::Image), vec!["image/jpeg", "image/PNG"]);
+        assert_eq!(
+            of_type(AssetType::Document),
+            vec!["application/pdf", "text/markdown", "application/vnd.openxmlformats-officedocument.wordprocessingml.document"]
+        );
+        assert_eq!(serde_json::from_str::<AssetType>(r#""document""#).unwrap(), AssetType::Document);
+        assert_eq!(escape_like("50%_off"), "50\\%\\_off");
+    }
+
+    #[test]
+    fn test_identical_file_matches_existing_hash() {
+        let uploaded = content_hash(b"chapter one");
+        let reupload = content_hash(b"chapter one");
+        assert_eq!(uploaded, reupload);
+        assert_ne!(uploaded, content_hash(b"chapter two"));
+
+        // Clients may send upper-case hex; it must match the stored hash
+        assert_eq!(normalize_content_hash(&reupload.to_uppercase()), Some(uploaded));
+        assert_eq!(normalize_content_hash("not-a-hash"), None);
+    }
+
+    #[test]
+    fn test_image_variants_never_upscale() {
+        let variants = StorageConfig::default().image_variants;
+
+        let listed = image_variants(&asset("image/jpeg", Some(1200), Some(800)), &variants);
+        assert_eq!(listed.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(), vec!["thumbnail", "medium"]);
+        assert_eq!(listed[0].height, Some(213));
+        assert_eq!(listed[0].url, "https://cdn.quillspace.com/tenant/cover.jpg?width=320");
+
+        assert_eq!(image_variants(&asset("image/jpeg", None, None), &variants).len(), 3);
+        assert!(image_variants(&asset("application/pdf", None, None), &variants).is_empty());
+    }
+
+    fn page_reference() -> AssetReference {
+        AssetReference { kind: "page".to_string(), id: Uuid::new_v4(), title: "Home".to_string() }
+    }
+
+    #[test]
+    fn test_referenced_asset_blocked_unless_forced() {
+        let (used, unused) = (asset("image/jpeg", None, None), asset("image/png", None, None));
+        let requested = [used.id, unused.id];
+        let found = || vec![(used.clone(), vec![page_reference()]), (unused.clone(), Vec::new())];
+
+        let plan = plan_batch_delete(&requested, found(), false);
+        assert_eq!(plan.results[0].status, BatchDeleteStatus::Blocked);
+        assert_eq!(plan.results[0].references[0].title, "Home");
+        assert_eq!(plan.results[1].status, BatchDeleteStatus::Deleted);
+        assert_eq!(plan.delete_ids, vec![unused.id]);
+
+        let forced = plan_batch_delete(&requested, found(), true);
+        assert!(forced.results.iter().all(|result| result.status == BatchDeleteStatus::Deleted));
+        assert_eq!(forced.delete_ids, vec![used.id, unused.id]);
+        // References are still reported so the caller knows what it broke
+        assert_eq!(forced.results[0].references.len(), 1);
+
+        let missing = Uuid::new_v4();
+        let plan = plan_batch_delete(&[missing, unused.id, unused.id], vec![(unused.clone(), Vec::new())], false);
+        assert_eq!(plan.results.len(), 2);
+        assert_eq!(plan.results[0].status, BatchDeleteStatus::NotFound);
+    }
+
+    #[test]
+    fn test_batch_delete_decrements_storage_usage() {
+        let (first, second) = (asset("image/jpeg", None, None), asset("application/pdf", None, None));
+        let plan = plan_batch_delete(&[first.id, second.id], vec![(first, Vec::new()), (second, Vec::new())], false);
+        assert_eq!(plan.freed_bytes, 2 * MIB);
+
+        let usage = StorageUsage::new(10 * MIB, 100 * MIB).release(plan.freed_bytes);
+        assert_eq!(usage.used_bytes, 8 * MIB);
+        assert_eq!(usage.remaining_bytes, 92 * MIB);
+    }
+
+    #[test]
+    fn test_reference_patterns_match_id_url_and_path() {
+        let asset = asset("image/jpeg", None, None);
+        assert_eq!(
+            reference_patterns(&asset),
+            vec![
+                format!("%{}%", asset.id),
+                "%https://cdn.quillspace.com/tenant/cover.jpg%".to_string(),
+                "%tenant/cover.jpg%".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quota_from_plan_settings() {
+        let mut storage = StorageConfig::default();
+        storage.plan_quotas.insert("author".to_string(), 10 * 1024 * 1024 * 1024);
+
+        assert_eq!(storage.quota_for(&serde_json::json!({ "plan": "author" })), 10 * 1024 * 1024 * 1024);
+        assert_eq!(storage.quota_for(&serde_json::json!({ "plan": "unknown" })), storage.default_quota_bytes);
+        assert_eq!(storage.quota_for(&serde_json::json!({ "plan": "author", "storage_quota_bytes": 5 })), 5);
+    }
+
+    #[test]
+    fn test_storage_filename_is_key_safe() {
+        assert_eq!(storage_filename("Pride & Prejudice.jpg"), "Pride---Prejudice.jpg");
+        assert_eq!(storage_filename("C:\\Users\\jane\\cover.png"), "cover.png");
+        assert_eq!(storage_filename("../../etc/passwd"), "passwd");
+        assert_eq!(storage_filename(".."), "file");
+        assert_eq!(storage_filename("obraz-ąę.png"), "obraz---.png");
+    }
+}
@@ -0,0 +1,406 @@
+use crate::config::{BillingConfig, PlansConfig};
+use crate::database::postgres::tenant_client;
+use crate::services::http_client;
+use crate::types::TenantId;
+use anyhow::{Context, Result};
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Stripe subscription status, as stored in the tenant's `subscription` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    Active,
+    Trialing,
+    PastDue,
+    Unpaid,
+    Canceled,
+    Incomplete,
+    IncompleteExpired,
+    Paused,
+}
+
+impl SubscriptionStatus {
+    /// Payment is overdue: the tenant keeps its data and plan but may only read
+    pub fn is_read_only(self) -> bool {
+        matches!(self, SubscriptionStatus::PastDue | SubscriptionStatus::Unpaid)
+    }
+
+    /// Whether the subscribed plan applies; otherwise the tenant falls back to the default plan
+    pub fn grants_plan(self) -> bool {
+        matches!(
+            self,
+            SubscriptionStatus::Active
+                | SubscriptionStatus::Trialing
+                | SubscriptionStatus::PastDue
+                | SubscriptionStatus::Unpaid
+        )
+    }
+}
+
+/// Subscription details kept in tenant settings under `subscription`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantSubscription {
+    pub id: String,
+    pub customer_id: String,
+    pub status: SubscriptionStatus,
+    /// `created` of the Stripe event last applied, so late deliveries don't roll back newer ones
+    pub event_created: i64,
+}
+
+/// Tenant update derived from a subscription event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionChange {
+    pub tenant_id: Uuid,
+    pub plan: String,
+    pub subscription: TenantSubscription,
+}
+
+/// Started Stripe Checkout session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckoutSession {
+    pub id: String,
+    pub url: String,
+}
+
+/// Billing errors
+#[derive(Debug, thiserror::Error)]
+pub enum BillingError {
+    #[error("Billing is not configured")]
+    NotConfigured,
+
+    #[error("Plan has no Stripe price: {0}")]
+    UnknownPlan(String),
+
+    #[error(transparent)]
+    Stripe(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeEvent {
+    id: String,
+    #[serde(rename = "type")]
+    event_type: String,
+    created: i64,
+    data: StripeEventData,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeEventData {
+    object: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeSubscription {
+    id: String,
+    customer: String,
+    status: SubscriptionStatus,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+    items: StripeList<StripeSubscriptionItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeList<T> {
+    data: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeSubscriptionItem {
+    price: StripePrice,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripePrice {
+    id: String,
+}
+
+/// Work out what a Stripe webhook event means for its tenant.
+///
+/// Only `customer.subscription.created/updated/deleted` are acted on; any other event,
+/// or a subscription not started through our checkout (no `tenant_id` metadata),
+/// yields `None`. A price that maps to no configured plan is an error, so Stripe
+/// retries the event once the configuration is fixed.
+pub fn subscription_change(body: &[u8], billing: &BillingConfig, plans: &PlansConfig) -> Result<Option<SubscriptionChange>> {
+    let event: StripeEvent = serde_json::from_slice(body).context("Invalid Stripe event")?;
+    let deleted = match event.event_type.as_str() {
+        "customer.subscription.created" | "customer.subscription.updated" => false,
+        "customer.subscription.deleted" => true,
+        _ => return Ok(None),
+    };
+
+    let subscription: StripeSubscription =
+        serde_json::from_value(event.data.object).context("Invalid subscription in Stripe event")?;
+    let Some(tenant_id) = subscription.metadata.get("tenant_id").and_then(|id| Uuid::parse_str(id).ok()) else {
+        warn!(event_id = %event.id, subscription_id = %subscription.id, "Stripe subscription without tenant_id metadata ignored");
+        return Ok(None);
+    };
+
+    let plan = if deleted || !subscription.status.grants_plan() {
+        plans.default_plan.clone()
+    } else {
+        let price_id = subscription
+            .items
+            .data
+            .first()
+            .map(|item| item.price.id.as_str())
+            .context("Stripe subscription has no items")?;
+        billing
+            .plan_prices
+            .iter()
+            .find(|(_, price)| price.as_str() == price_id)
+            .map(|(plan, _)| plan.clone())
+            .with_context(|| format!("No plan configured for Stripe price {}", price_id))?
+    };
+
+    Ok(Some(SubscriptionChange {
+        tenant_id,
+        plan,
+        subscription: TenantSubscription {
+            id: subscription.id,
+            customer_id: subscription.customer,
+            status: if deleted { SubscriptionStatus::Canceled } else { subscription.status },
+            event_created: event.created,
+        },
+    }))
+}
+
+/// Stripe subscriptions of tenants: checkout and applying subscription events
+#[derive(Clone)]
+pub struct BillingService {
+    db: Pool,
+    billing: BillingConfig,
+    plans: PlansConfig,
+    http_client: reqwest::Client,
+}
+
+impl BillingService {
+    pub fn new(db: Pool, billing: BillingConfig, plans: PlansConfig) -> Self {
+        let http_client = http_client::client_builder()
+            .timeout(Duration::from_millis(billing.request_timeout_ms))
+            .build()
+            .unwrap_or_default();
+        Self { db, billing, plans, http_client }
+    }
+
+    /// Webhook handler for the `stripe` provider; the signature is already verified
+    pub async fn handle_stripe_event(&self, body: &[u8]) -> Result<()> {
+        let Some(change) = subscription_change(body, &self.billing, &self.plans)? else {
+            return Ok(());
+        };
+        self.apply_subscription_change(&change).await
+    }
+
+    /// Store the tenant's plan and subscription, unless a newer event was already applied
+    pub async fn apply_subscription_change(&self, change: &SubscriptionChange) -> Result<()> {
+        let client = tenant_client(&self.db, &TenantId::from_uuid(change.tenant_id)).await?;
+
+        let subscription = serde_json::to_value(&change.subscription)?;
+        let updated = client
+            .execute(
+                "UPDATE tenants
+                 SET settings = COALESCE(settings, '{}'::jsonb) || jsonb_build_object('plan', $2::text, 'subscription', $3::jsonb),
+                     updated_at = NOW()
+                 WHERE id = $1
+                   AND COALESCE((settings->'subscription'->>'event_created')::bigint, 0) <= $4",
+                &[&change.tenant_id, &change.plan, &subscription, &change.subscription.event_created],
+            )
+            .await
+            .context("Failed to update tenant subscription")?;
+
+        if updated > 0 {
+            info!(
+                tenant_id = %change.tenant_id,
+                plan = %change.plan,
+                status = ?change.subscription.status,
+                "Tenant subscription updated"
+            );
+        } else {
+            warn!(tenant_id = %change.tenant_id, "Stale or unknown-tenant subscription event ignored");
+        }
+        Ok(())
+    }
+
+    /// The tenant's subscription, if it ever subscribed
+    pub async fn tenant_subscription(&self, tenant_id: &TenantId) -> Result<Option<TenantSubscription>> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let subscription: Option<Value> = client
+            .query_opt("SELECT settings->'subscription' AS subscription FROM tenants WHERE id = $1", &[tenant_id.as_uuid()])
+            .await
+            .context("Failed to read tenant subscription")?
+            .and_then(|row| row.get("subscription"));
+
+        Ok(subscription.and_then(|subscription| serde_json::from_value(subscription).ok()))
+    }
+
+    /// Start a Stripe Checkout session subscribing the tenant to `plan`
+    pub async fn create_checkout_session(
+        &self,
+        tenant_id: &TenantId,
+        plan: &str,
+        customer_email: Option<&str>,
+    ) -> Result<CheckoutSession, BillingError> {
+        if self.billing.stripe_secret_key.trim().is_empty() {
+            return Err(BillingError::NotConfigured);
+        }
+        let price_id = self
+            .billing
+            .plan_prices
+            .get(plan)
+            .ok_or_else(|| BillingError::UnknownPlan(plan.to_string()))?;
+
+        let tenant = tenant_id.to_string();
+        let mut form = vec![
+            ("mode", "subscription".to_string()),
+            ("line_items[0][price]", price_id.clone()),
+            ("line_items[0][quantity]", "1".to_string()),
+            ("success_url", self.billing.checkout_success_url.clone()),
+            ("cancel_url", self.billing.checkout_cancel_url.clone()),
+            ("client_reference_id", tenant.clone()),
+            // Copied onto the subscription, so its webhooks name the tenant
+            ("subscription_data[metadata][tenant_id]", tenant),
+        ];
+        // Returning customers keep their Stripe customer and payment methods
+        match self.tenant_subscription(tenant_id).await? {
+            Some(subscription) => form.push(("customer", subscription.customer_id)),
+            None => {
+                if let Some(email) = customer_email {
+                    form.push(("customer_email", email.to_string()));
+                }
+            }
+        }
+
+        let url = format!("{}/v1/checkout/sessions", self.billing.stripe_api_url.trim_end_matches('/'));
+        let response = self
+            .http_client
+            .post(url)
+            .bearer_auth(&self.billing.stripe_secret_key)
+            .form(&form)
+            .send()
+            .await
+            .context("Stripe checkout request failed")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Stripe rejected checkout session ({}): {}", status, body).into());
+        }
+
+        let session = response
+            .json::<CheckoutSession>()
+            .await
+            .context("Invalid Stripe checkout session")?;
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::webhooks::verify_stripe_signature;
+    use hmac::{Hmac, Mac};
+    use serde_json::json;
+    use sha2::Sha256;
+
+    const WEBHOOK_SECRET: &[u8] = b"whsec_test_secret";
+    const TENANT_ID: &str = "11111111-1111-1111-1111-111111111111";
+    const SIGNED_AT: i64 = 1_735_689_600;
+
+    fn billing() -> BillingConfig {
+        let mut billing = BillingConfig::default();
+        billing.plan_prices.insert("author".to_string(), "price_author_monthly".to_string());
+        billing
+    }
+
+    fn subscription_event(event_type: &str, status: &str, created: i64) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "id": "evt_1Qabc",
+            "object": "event",
+            "type": event_type,
+            "created": created,
+            "data": {
+                "object": {
+                    "id": "sub_1Qxyz",
+                    "object": "subscription",
+                    "customer": "cus_Rabc",
+                    "status": status,
+                    "metadata": { "tenant_id": TENANT_ID },
+                    "items": { "object": "list", "data": [{ "id": "si_1", "price": { "id": "price_author_monthly" } }] }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn stripe_signature(body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(WEBHOOK_SECRET).unwrap();
+        mac.update(format!("{}.", SIGNED_AT).as_bytes());
+        mac.update(body);
+        format!("t={},v1={}", SIGNED_AT, hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// What the `stripe` webhook does with a request: verify, then derive the change
+    fn receive(body: &[u8], signature: &str) -> Option<Option<SubscriptionChange>> {
+        if !verify_stripe_signature(WEBHOOK_SECRET, signature, body, SIGNED_AT + 5, 300) {
+            return None;
+        }
+        Some(subscription_change(body, &billing(), &PlansConfig::default()).unwrap())
+    }
+
+    #[test]
+    fn test_signed_subscription_event_updates_plan() {
+        let body = subscription_event("customer.subscription.created", "active", SIGNED_AT);
+        let change = receive(&body, &stripe_signature(&body)).expect("Valid signature rejected").unwrap();
+
+        assert_eq!(change.tenant_id, Uuid::parse_str(TENANT_ID).unwrap());
+        assert_eq!(change.plan, "author");
+        assert_eq!(change.subscription.customer_id, "cus_Rabc");
+        assert_eq!(change.subscription.status, SubscriptionStatus::Active);
+    }
+
+    #[test]
+    fn test_past_due_keeps_plan_read_only_and_deletion_downgrades() {
+        let past_due = subscription_event("customer.subscription.updated", "past_due", SIGNED_AT);
+        let change = receive(&past_due, &stripe_signature(&past_due)).unwrap().unwrap();
+        assert_eq!(change.plan, "author");
+        assert!(change.subscription.status.is_read_only());
+
+        let deleted = subscription_event("customer.subscription.deleted", "canceled", SIGNED_AT);
+        let change = receive(&deleted, &stripe_signature(&deleted)).unwrap().unwrap();
+        assert_eq!(change.plan, "free");
+        assert_eq!(change.subscription.status, SubscriptionStatus::Canceled);
+        assert!(!change.subscription.status.is_read_only());
+    }
+
+    #[test]
+    fn test_tampered_signature_rejected() {
+        let body = subscription_event("customer.subscription.updated", "active", SIGNED_AT);
+        let signature = stripe_signature(&body);
+
+        // Same signature over a body naming another tenant
+        let forged = String::from_utf8(body.clone()).unwrap().replace(TENANT_ID, &Uuid::new_v4().to_string());
+        assert!(receive(forged.as_bytes(), &signature).is_none());
+
+        let mut flipped = signature.clone();
+        let last = flipped.pop().unwrap();
+        flipped.push(if last == '0' { '1' } else { '0' });
+        assert!(receive(&body, &flipped).is_none());
+    }
+
+    #[test]
+    fn test_unrelated_events_and_unknown_prices() {
+        let invoice = serde_json::to_vec(&json!({
+            "id": "evt_2", "type": "invoice.paid", "created": SIGNED_AT, "data": { "object": {} }
+        }))
+        .unwrap();
+        assert!(subscription_change(&invoice, &billing(), &PlansConfig::default()).unwrap().is_none());
+
+        let body = subscription_event("customer.subscription.created", "active", SIGNED_AT);
+        assert!(subscription_change(&body, &BillingConfig::default(), &PlansConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Request body for bulk publish/unpublish of pages or content.
+///
+/// Either list the `ids` or set `all_drafts` to take every unpublished item.
+/// `strict` overrides `publishing.bulk_strict` from the config.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BulkPublishRequest {
+    pub ids: Vec<Uuid>,
+    pub all_drafts: bool,
+    pub strict: Option<bool>,
+}
+
+impl BulkPublishRequest {
+    pub fn validate(&self, max_items: usize) -> Result<(), String> {
+        if self.all_drafts && !self.ids.is_empty() {
+            return Err("Specify either ids or all_drafts, not both".to_string());
+        }
+        if !self.all_drafts && self.ids.is_empty() {
+            return Err("No items selected".to_string());
+        }
+        if self.ids.len() > max_items {
+            return Err(format!("At most {} items can be published at once", max_items));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemStatus {
+    Published,
+    Unpublished,
+    Failed,
+    /// Prepared successfully but not written because another item failed in strict mode
+    RolledBack,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BulkItemResult {
+    pub id: Uuid,
+    pub status: BulkItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BulkPublishReport {
+    /// Whether any change was written
+    pub committed: bool,
+    pub items: Vec<BulkItemResult>,
+}
+
+/// Outcome of preparing every item, before anything is written
+#[derive(Debug)]
+pub struct BulkPlan<T> {
+    /// Items to write, in request order
+    pub writes: Vec<(Uuid, T)>,
+    pub report: BulkPublishReport,
+}
+
+/// Decide what a bulk operation writes.
+///
+/// `prepared` holds each loaded item with its prepared value (e.g. rendered HTML)
+/// or the reason it failed; requested ids that were not loaded count as failures.
+/// In strict mode a single failure means nothing is written; otherwise the
+/// successful items are written and the failures reported alongside them.
+pub fn plan_bulk<T>(
+    requested: Option<&[Uuid]>,
+    prepared: Vec<(Uuid, Result<T, String>)>,
+    strict: bool,
+    done: BulkItemStatus,
+) -> BulkPlan<T> {
+    let loaded: HashSet<Uuid> = prepared.iter().map(|(id, _)| *id).collect();
+    let missing = requested
+        .unwrap_or_default()
+        .iter()
+        .filter(|id| !loaded.contains(id))
+        .map(|id| (*id, Err("Not found".to_string())));
+    let prepared: Vec<(Uuid, Result<T, String>)> = prepared.into_iter().chain(missing).collect();
+
+    let abort = strict && prepared.iter().any(|(_, result)| result.is_err());
+    let mut writes = Vec::new();
+    let mut items = Vec::with_capacity(prepared.len());
+
+    for (id, result) in prepared {
+        match result {
+            Ok(_) if abort => items.push(BulkItemResult {
+                id,
+                status: BulkItemStatus::RolledBack,
+                error: None,
+            }),
+            Ok(value) => {
+                writes.push((id, value));
+                items.push(BulkItemResult { id, status: done, error: None });
+            }
+            Err(error) => items.push(BulkItemResult {
+                id,
+                status: BulkItemStatus::Failed,
+                error: Some(error),
+            }),
+        }
+    }
+
+    BulkPlan {
+        report: BulkPublishReport { committed: !writes.is_empty(), items },
+        writes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(count: usize) -> Vec<Uuid> {
+        (0..count).map(|_| Uuid::new_v4()).collect()
+    }
+
+    fn rendered(ids: &[Uuid], failing: Option<usize>) -> Vec<(Uuid, Result<String, String>)> {
+        ids.iter()
+            .enumerate()
+            .map(|(i, id)| {
+                let result = if Some(i) == failing {
+                    Err("Template 'missing' not found".to_string())
+                } else {
+                    Ok(format!("<p>page {}</p>", i))
+                };
+                (*id, result)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bulk_publish_several_pages() {
+        let page_ids = ids(3);
+        let plan = plan_bulk(Some(&page_ids), rendered(&page_ids, None), true, BulkItemStatus::Published);
+
+        assert!(plan.report.committed);
+        assert_eq!(plan.writes.iter().map(|(id, _)| *id).collect::<Vec<_>>(), page_ids);
+        assert_eq!(plan.writes[2].1, "<p>page 2</p>");
+        assert!(plan.report.items.iter().all(|item| item.status == BulkItemStatus::Published));
+    }
+
+    #[test]
+    fn test_strict_render_failure_rolls_back_everything() {
+        let page_ids = ids(3);
+        let plan = plan_bulk(Some(&page_ids), rendered(&page_ids, Some(1)), true, BulkItemStatus::Published);
+
+        assert!(!plan.report.committed);
+        assert!(plan.writes.is_empty());
+        let statuses: Vec<_> = plan.report.items.iter().map(|item| item.status).collect();
+        assert_eq!(
+            statuses,
+            vec![BulkItemStatus::RolledBack, BulkItemStatus::Failed, BulkItemStatus::RolledBack]
+        );
+        assert_eq!(plan.report.items[1].error.as_deref(), Some("Template 'missing' not found"));
+    }
+
+    #[test]
+    fn test_lenient_render_failure_publishes_the_rest() {
+        let page_ids = ids(3);
+        let plan = plan_bulk(Some(&page_ids), rendered(&page_ids, Some(1)), false, BulkItemStatus::Published);
+
+        assert!(plan.report.committed);
+        assert_eq!(plan.writes.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![page_ids[0], page_ids[2]]);
+        assert_eq!(plan.report.items[1].status, BulkItemStatus::Failed);
+    }
+
+    #[test]
+    fn test_unknown_ids_reported_as_failures() {
+        let page_ids = ids(2);
+        let plan = plan_bulk(Some(&page_ids), rendered(&page_ids[..1], None), false, BulkItemStatus::Unpublished);
+
+        assert_eq!(plan.writes.len(), 1);
+        assert_eq!(
+            plan.report.items[1],
+            BulkItemResult { id: page_ids[1], status: BulkItemStatus::Failed, error: Some("Not found".to_string()) }
+        );
+    }
+
+    #[test]
+    fn test_request_validation() {
+        assert!(BulkPublishRequest { all_drafts: true, ..Default::default() }.validate(10).is_ok());
+        assert!(BulkPublishRequest::default().validate(10).is_err());
+        assert!(BulkPublishRequest { ids: ids(1), all_drafts: true, strict: None }.validate(10).is_err());
+        assert!(BulkPublishRequest { ids: ids(11), ..Default::default() }.validate(10).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use crate::database::DatabaseConnections;
+use crate::services::connected_websites::store_connection_health;
+use crate::services::wix_api::{BusinessInfo, WixApiClient, WixApiError};
+use crate::types::TenantId;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::GenericClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Key of the sync state in a connected website's metadata
+pub const METADATA_KEY: &str = "business_info";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BusinessInfoSyncError {
+    #[error("Connected website not found")]
+    SiteNotFound,
+
+    #[error("Stored business info is invalid: {0}")]
+    InvalidState(String),
+
+    #[error(transparent)]
+    Wix(#[from] WixApiError),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// What to do when both QuillSpace and Wix changed the business info since the last sync
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Record the conflict and change nothing until it is resolved
+    #[default]
+    Flag,
+    /// The side being synced from wins: Wix on a pull, QuillSpace on a push
+    LastWriteWins,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncDirection {
+    Pull,
+    Push,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncOutcome {
+    Pulled,
+    Pushed,
+    /// Pull skipped: QuillSpace has edits Wix has not seen yet
+    LocalChangesPending,
+    Conflict,
+}
+
+/// Wix's version of the business info when it disagreed with an unsynced local edit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusinessInfoConflict {
+    pub wix: BusinessInfo,
+    pub detected_at: DateTime<Utc>,
+}
+
+/// Business info sync state, kept under `business_info` in the website's metadata
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BusinessInfoState {
+    /// QuillSpace's copy, including edits not yet pushed
+    pub info: BusinessInfo,
+    /// What Wix held at the last successful sync
+    pub synced: Option<BusinessInfo>,
+    pub synced_at: Option<DateTime<Utc>>,
+    pub local_updated_at: Option<DateTime<Utc>>,
+    pub conflict: Option<BusinessInfoConflict>,
+}
+
+impl BusinessInfoState {
+    pub fn from_metadata(metadata: &Value) -> Result<Self, BusinessInfoSyncError> {
+        match metadata.get(METADATA_KEY) {
+            Some(Value::Null) | None => Ok(Self::default()),
+            Some(state) => serde_json::from_value(state.clone())
+                .map_err(|e| BusinessInfoSyncError::InvalidState(e.to_string())),
+        }
+    }
+
+    /// Record a QuillSpace-side edit, to be pushed later
+    pub fn edit(&mut self, info: BusinessInfo, now: DateTime<Utc>) {
+        self.info = info;
+        self.local_updated_at = Some(now);
+    }
+
+    /// Whether QuillSpace was edited after the last sync
+    pub fn local_changed(&self) -> bool {
+        match (self.local_updated_at, self.synced_at) {
+            (Some(edited), Some(synced)) => edited > synced,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Whether Wix differs from what it held at the last sync
+    fn remote_changed(&self, remote: &BusinessInfo) -> bool {
+        self.synced.as_ref() != Some(remote)
+    }
+
+    fn in_conflict(&self, remote: &BusinessInfo) -> bool {
+        self.local_changed() && self.remote_changed(remote) && *remote != self.info
+    }
+
+    fn mark_synced(&mut self, info: BusinessInfo, now: DateTime<Utc>) {
+        self.info = info.clone();
+        self.synced = Some(info);
+        self.synced_at = Some(now);
+        self.conflict = None;
+    }
+
+    fn flag_conflict(&mut self, remote: BusinessInfo, now: DateTime<Utc>) -> SyncOutcome {
+        self.conflict = Some(BusinessInfoConflict { wix: remote, detected_at: now });
+        SyncOutcome::Conflict
+    }
+
+    /// Take Wix's business info, unless that would discard local edits
+    pub fn apply_pull(&mut self, remote: BusinessInfo, strategy: ConflictStrategy, now: DateTime<Utc>) -> SyncOutcome {
+        if self.in_conflict(&remote) && strategy == ConflictStrategy::Flag {
+            return self.flag_conflict(remote, now);
+        }
+        if self.local_changed() && !self.remote_changed(&remote) && strategy == ConflictStrategy::Flag {
+            return SyncOutcome::LocalChangesPending;
+        }
+        self.mark_synced(remote, now);
+        SyncOutcome::Pulled
+    }
+}
+
+/// Pull Wix's business info into `state`
+pub async fn pull(
+    client: &WixApiClient,
+    site_id: &str,
+    state: &mut BusinessInfoState,
+    strategy: ConflictStrategy,
+) -> Result<SyncOutcome, BusinessInfoSyncError> {
+    let remote = client.get_business_info(site_id).await?;
+    Ok(state.apply_pull(remote, strategy, Utc::now()))
+}
+
+/// Push QuillSpace's business info to Wix. Wix is read first so a change made there since
+/// the last sync is flagged rather than overwritten, unless `strategy` says otherwise.
+pub async fn push(
+    client: &WixApiClient,
+    site_id: &str,
+    state: &mut BusinessInfoState,
+    strategy: ConflictStrategy,
+) -> Result<SyncOutcome, BusinessInfoSyncError> {
+    let remote = client.get_business_info(site_id).await?;
+    if state.in_conflict(&remote) && strategy == ConflictStrategy::Flag {
+        return Ok(state.flag_conflict(remote, Utc::now()));
+    }
+
+    let updated = client.update_business_info(site_id, &state.info).await?;
+    state.mark_synced(updated, Utc::now());
+    Ok(SyncOutcome::Pushed)
+}
+
+/// Business info sync for the Wix sites QuillSpace manages, stored in `user_wix_sites.metadata`
+pub struct BusinessInfoSyncService {
+    db: DatabaseConnections,
+}
+
+impl BusinessInfoSyncService {
+    pub fn new(db: DatabaseConnections) -> Self {
+        Self { db }
+    }
+
+    /// The site's business info sync state
+    pub async fn get_state(&self, tenant_id: &TenantId, user_id: Uuid, site_id: &str) -> Result<BusinessInfoState, BusinessInfoSyncError> {
+        let client = self.db.postgres().get().await
+            .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
+        let row = client
+            .query_opt(
+                "SELECT metadata FROM user_wix_sites WHERE wix_site_id = $1 AND tenant_id = $2 AND user_id = $3",
+                &[&site_id, tenant_id.as_uuid(), &user_id],
+            )
+            .await
+            .map_err(anyhow::Error::from)?
+            .ok_or(BusinessInfoSyncError::SiteNotFound)?;
+        BusinessInfoState::from_metadata(&row.get(0))
+    }
+
+    /// Record a QuillSpace-side edit of the business info
+    pub async fn edit(&self, tenant_id: &TenantId, user_id: Uuid, site_id: &str, info: BusinessInfo) -> Result<BusinessInfoState, BusinessInfoSyncError> {
+        let mut client = self.db.postgres().get().await
+            .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
+        let transaction = client.transaction().await
+            .map_err(|e| anyhow::anyhow!("Failed to start transaction: {}", e))?;
+
+        let (id, mut state) = load_for_update(&transaction, tenant_id, user_id, site_id).await?;
+        state.edit(info, Utc::now());
+        save(&transaction, id, &state).await?;
+
+        transaction.commit().await.map_err(anyhow::Error::from)?;
+        Ok(state)
+    }
+
+    /// Pull from or push to Wix and save the resulting state. The row stays locked while
+    /// Wix is called so a concurrent edit is not lost.
+    pub async fn sync(
+        &self,
+        tenant_id: &TenantId,
+        user_id: Uuid,
+        site_id: &str,
+        wix: &WixApiClient,
+        direction: SyncDirection,
+        strategy: ConflictStrategy,
+    ) -> Result<(BusinessInfoState, SyncOutcome), BusinessInfoSyncError> {
+        let mut client = self.db.postgres().get().await
+            .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
+        let transaction = client.transaction().await
+            .map_err(|e| anyhow::anyhow!("Failed to start transaction: {}", e))?;
+
+        let (id, mut state) = load_for_update(&transaction, tenant_id, user_id, site_id).await?;
+        let result = match direction {
+            SyncDirection::Pull => pull(wix, site_id, &mut state, strategy).await,
+            SyncDirection::Push => push(wix, site_id, &mut state, strategy).await,
+        };
+        // The row is locked here, so the connection health goes in this transaction too
+        let outcome = match result {
+            Ok(outcome) => {
+                store_connection_health(&transaction, site_id, None).await?;
+                save(&transaction, id, &state).await?;
+                outcome
+            }
+            Err(BusinessInfoSyncError::Wix(e)) => {
+                store_connection_health(&transaction, site_id, Some(&e)).await?;
+                transaction.commit().await.map_err(anyhow::Error::from)?;
+                return Err(e.into());
+            }
+            Err(e) => return Err(e),
+        };
+
+        transaction.commit().await.map_err(anyhow::Error::from)?;
+        Ok((state, outcome))
+    }
+}
+
+async fn load_for_update(
+    client: &impl GenericClient,
+    tenant_id: &TenantId,
+    user_id: Uuid,
+    site_id: &str,
+) -> Result<(Uuid, BusinessInfoState), BusinessInfoSyncError> {
+    let row = client
+        .query_opt(
+            "SELECT id, metadata FROM user_wix_sites
+             WHERE wix_site_id = $1 AND tenant_id = $2 AND user_id = $3
+             FOR UPDATE",
+            &[&site_id, tenant_id.as_uuid(), &user_id],
+        )
+        .await
+        .map_err(anyhow::Error::from)?
+        .ok_or(BusinessInfoSyncError::SiteNotFound)?;
+    Ok((row.get(0), BusinessInfoState::from_metadata(&row.get(1))?))
+}
+
+async fn save(client: &impl GenericClient, id: Uuid, state: &BusinessInfoState) -> Result<(), BusinessInfoSyncError> {
+    let state = serde_json::to_value(state).map_err(anyhow::Error::from)?;
+    client
+        .execute(
+            "UPDATE user_wix_sites
+             SET metadata = jsonb_set(COALESCE(metadata, '{}'::jsonb), '{business_info}', $2), updated_at = NOW()
+             WHERE id = $1",
+            &[&id, &state],
+        )
+        .await
+        .map_err(anyhow::Error::from)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Json, Router};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    /// A fake `/site-properties/v4/properties` holding `properties`, recording every PATCH body
+    async fn mock_wix(properties: Value) -> (WixApiClient, Arc<Mutex<Value>>, Arc<Mutex<Vec<Value>>>) {
+        let stored = Arc::new(Mutex::new(properties));
+        let patches = Arc::new(Mutex::new(Vec::new()));
+        let (get_stored, patch_stored, recorded) = (stored.clone(), stored.clone(), patches.clone());
+        let app = Router::new().route(
+            "/site-properties/v4/properties",
+            get(move || {
+                let stored = get_stored.clone();
+                async move { Json(json!({ "properties": *stored.lock().unwrap() })) }
+            })
+            .patch(move |Json(body): Json<Value>| {
+                let (stored, recorded) = (patch_stored.clone(), recorded.clone());
+                async move {
+                    recorded.lock().unwrap().push(body.clone());
+                    let mut properties = stored.lock().unwrap();
+                    for path in body["fields"]["paths"].as_array().unwrap() {
+                        let path = path.as_str().unwrap();
+                        properties[path] = body["properties"][path].clone();
+                    }
+                    Json(json!({ "properties": *properties }))
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind mock Wix");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let client = WixApiClient::new("test-key".to_string(), "account".to_string()).with_base_url(base_url);
+        (client, stored, patches)
+    }
+
+    fn bookshop() -> Value {
+        json!({
+            "businessName": "Inkwell Books",
+            "address": { "formatted": "12 Quill Lane, Bath", "city": "Bath", "country": "GB" },
+            "businessSchedule": { "periods": [{ "openDay": "MONDAY", "openTime": "09:00", "closeDay": "MONDAY", "closeTime": "17:00" }] },
+            "email": "hello@inkwell.example"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_pull_populates_metadata() {
+        let (wix, _, _) = mock_wix(bookshop()).await;
+        let metadata = json!({ "service_type": "managed" });
+        let mut state = BusinessInfoState::from_metadata(&metadata).unwrap();
+
+        let outcome = pull(&wix, "site", &mut state, ConflictStrategy::Flag).await.expect("Pull failed");
+        assert_eq!(outcome, SyncOutcome::Pulled);
+        assert_eq!(state.info.business_name.as_deref(), Some("Inkwell Books"));
+        assert_eq!(state.info.address.as_ref().unwrap()["city"], "Bath");
+        assert_eq!(state.info.business_schedule.as_ref().unwrap()["periods"][0]["openTime"], "09:00");
+        assert_eq!(state.synced.as_ref(), Some(&state.info));
+        assert!(!state.local_changed());
+
+        // Round-trips through the website's metadata
+        let mut metadata = metadata;
+        metadata[METADATA_KEY] = serde_json::to_value(&state).unwrap();
+        assert_eq!(BusinessInfoState::from_metadata(&metadata).unwrap(), state);
+    }
+
+    #[tokio::test]
+    async fn test_push_updates_wix() {
+        let (wix, stored, patches) = mock_wix(bookshop()).await;
+        let mut state = BusinessInfoState::default();
+        pull(&wix, "site", &mut state, ConflictStrategy::Flag).await.expect("Pull failed");
+
+        let mut edited = state.info.clone();
+        edited.business_name = Some("Inkwell Books & Coffee".to_string());
+        state.edit(edited, Utc::now());
+        assert!(state.local_changed());
+
+        let outcome = push(&wix, "site", &mut state, ConflictStrategy::Flag).await.expect("Push failed");
+        assert_eq!(outcome, SyncOutcome::Pushed);
+        assert_eq!(stored.lock().unwrap()["businessName"], "Inkwell Books & Coffee");
+        // Properties QuillSpace does not manage are left alone
+        assert_eq!(stored.lock().unwrap()["email"], "hello@inkwell.example");
+        assert_eq!(patches.lock().unwrap()[0]["fields"]["paths"], json!(["businessName", "address", "businessSchedule"]));
+        assert!(!state.local_changed());
+        assert!(state.conflict.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_edits_flagged_unless_last_write_wins() {
+        let (wix, stored, patches) = mock_wix(bookshop()).await;
+        let mut state = BusinessInfoState::default();
+        pull(&wix, "site", &mut state, ConflictStrategy::Flag).await.expect("Pull failed");
+
+        let mut edited = state.info.clone();
+        edited.business_name = Some("Inkwell".to_string());
+        state.edit(edited, Utc::now());
+        stored.lock().unwrap()["businessName"] = json!("Inkwell Bookshop");
+
+        let outcome = push(&wix, "site", &mut state, ConflictStrategy::Flag).await.expect("Push failed");
+        assert_eq!(outcome, SyncOutcome::Conflict);
+        assert!(patches.lock().unwrap().is_empty());
+        assert_eq!(state.conflict.as_ref().unwrap().wix.business_name.as_deref(), Some("Inkwell Bookshop"));
+        assert_eq!(state.info.business_name.as_deref(), Some("Inkwell"));
+
+        let outcome = pull(&wix, "site", &mut state, ConflictStrategy::LastWriteWins).await.expect("Pull failed");
+        assert_eq!(outcome, SyncOutcome::Pulled);
+        assert_eq!(state.info.business_name.as_deref(), Some("Inkwell Bookshop"));
+        assert!(state.conflict.is_none());
+    }
+}
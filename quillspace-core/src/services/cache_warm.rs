@@ -0,0 +1,286 @@
+//! Pre-warming of the template and published-page caches, so the first visitors
+//! after a deploy or publish don't wait on the database.
+//!
+//! The pages warmed are the most viewed ones over `templates.warm.lookback_days`,
+//! by page views in ClickHouse. Warming runs in a background task and is bounded by
+//! `max_pages` (at startup) and `pages_per_site` (after a publish).
+
+use crate::config::{CacheWarmConfig, HtmlMinifyConfig};
+use crate::database::clickhouse::{popular_pages, AnalyticsService, PopularPage, TenantScope};
+use crate::services::page::{Page, PageService};
+use crate::services::publish_cache::PublishCache;
+use crate::services::site::{Site, SiteService};
+use crate::services::template_engine::{TemplateEngine, PUCK_BASE_TEMPLATE};
+use crate::types::TenantId;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::ops::AddAssign;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Sites of a tenant looked at when warming its popular pages
+const MAX_SITES_PER_TENANT: i64 = 50;
+
+/// What a warming pass put in the caches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WarmReport {
+    pub templates: usize,
+    pub pages: usize,
+    pub failed: usize,
+}
+
+impl AddAssign for WarmReport {
+    fn add_assign(&mut self, other: Self) {
+        self.templates += other.templates;
+        self.pages += other.pages;
+        self.failed += other.failed;
+    }
+}
+
+/// The site's pages to warm, most viewed first: those named by `popular_paths` in
+/// that order, then the rest by their menu order, `limit` in all
+pub fn pages_to_warm(pages: Vec<Page>, popular_paths: &[String], limit: usize) -> Vec<Page> {
+    let rank = |page: &Page| {
+        popular_paths
+            .iter()
+            .position(|path| path.trim_matches('/') == page.slug)
+            .unwrap_or(usize::MAX)
+    };
+    let mut pages: Vec<Page> = pages.into_iter().filter(|page| page.is_published).collect();
+    pages.sort_by_key(|page| (rank(page), page.sort_order));
+    pages.truncate(limit);
+    pages
+}
+
+/// Load each page's public HTML into `cache` under its served path, skipping pages
+/// already cached. A page that fails to load is logged and counted, not fatal.
+pub async fn warm_published_pages<F, Fut>(cache: &PublishCache, site_id: Uuid, pages: Vec<Page>, load: F) -> WarmReport
+where
+    F: Fn(Page) -> Fut,
+    Fut: Future<Output = anyhow::Result<Option<String>>>,
+{
+    let mut report = WarmReport::default();
+    for page in pages {
+        let path = format!("/{}", page.slug);
+        match cache.get_or_load(site_id, &path, || load(page)).await {
+            Ok(Some(_)) => report.pages += 1,
+            Ok(None) => {}
+            Err(e) => {
+                warn!(site_id = %site_id, path = %path, "Failed to warm page: {:#}", e);
+                report.failed += 1;
+            }
+        }
+    }
+    report
+}
+
+pub struct CacheWarmer {
+    db: deadpool_postgres::Pool,
+    analytics: AnalyticsService,
+    template_engine: Arc<TemplateEngine>,
+    publish_cache: Arc<PublishCache>,
+    minify: HtmlMinifyConfig,
+    config: CacheWarmConfig,
+}
+
+impl CacheWarmer {
+    pub fn new(
+        db: deadpool_postgres::Pool,
+        analytics: AnalyticsService,
+        template_engine: Arc<TemplateEngine>,
+        publish_cache: Arc<PublishCache>,
+        minify: HtmlMinifyConfig,
+        config: CacheWarmConfig,
+    ) -> Self {
+        Self { db, analytics, template_engine, publish_cache, minify, config }
+    }
+
+    /// Warm the most viewed pages across every tenant, at most `max_pages`
+    pub async fn warm_popular(&self) -> anyhow::Result<WarmReport> {
+        let popular = popular_pages(
+            self.analytics.client(),
+            TenantScope::All,
+            self.config.lookback_days,
+            self.config.max_pages as u64,
+        )
+        .await?;
+
+        let mut by_tenant: BTreeMap<Uuid, Vec<String>> = BTreeMap::new();
+        for PopularPage { tenant_id, page_path, .. } in popular {
+            by_tenant.entry(tenant_id).or_default().push(page_path);
+        }
+
+        let mut report = WarmReport::default();
+        for (tenant_id, paths) in by_tenant {
+            let tenant_id = TenantId::from_uuid(tenant_id);
+            match self.warm_tenant_paths(&tenant_id, &paths).await {
+                Ok(warmed) => report += warmed,
+                Err(e) => {
+                    warn!(tenant_id = %tenant_id, "Failed to warm popular pages: {:#}", e);
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Warm a site's most viewed pages after it is published, at most `pages_per_site`.
+    /// Without page views (analytics down, or a new site) its first pages are warmed.
+    pub async fn warm_site(&self, tenant_id: &TenantId, site_id: Uuid) -> anyhow::Result<WarmReport> {
+        let Some(site) = SiteService::new(self.db.clone()).get_site(tenant_id, site_id).await? else {
+            return Ok(WarmReport::default());
+        };
+        if !site.is_published {
+            return Ok(WarmReport::default());
+        }
+
+        let popular_paths: Vec<String> = popular_pages(
+            self.analytics.client(),
+            TenantScope::Only(tenant_id.as_uuid()),
+            self.config.lookback_days,
+            self.config.pages_per_site as u64,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            warn!(site_id = %site_id, "Failed to read popular pages, warming the first pages: {:#}", e);
+            Vec::new()
+        })
+        .into_iter()
+        .map(|popular| popular.page_path)
+        .collect();
+
+        let pages = PageService::new(self.db.clone()).get_site_pages(tenant_id, site_id).await?;
+        let pages = pages_to_warm(pages, &popular_paths, self.config.pages_per_site);
+        Ok(self.warm_site_pages(&site, pages).await)
+    }
+
+    /// Run [`Self::warm_popular`] in the background
+    pub fn spawn_warm_popular(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            match self.warm_popular().await {
+                Ok(report) => info!(?report, "Warmed caches with popular pages"),
+                Err(e) => warn!("Failed to warm caches: {:#}", e),
+            }
+        })
+    }
+
+    /// Run [`Self::warm_site`] in the background
+    pub fn spawn_warm_site(self: Arc<Self>, tenant_id: TenantId, site_id: Uuid) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            match self.warm_site(&tenant_id, site_id).await {
+                Ok(report) => info!(site_id = %site_id, ?report, "Warmed caches for published site"),
+                Err(e) => warn!(site_id = %site_id, "Failed to warm caches for published site: {:#}", e),
+            }
+        })
+    }
+
+    /// Warm the pages named by `paths` on each of the tenant's published sites
+    async fn warm_tenant_paths(&self, tenant_id: &TenantId, paths: &[String]) -> anyhow::Result<WarmReport> {
+        let page_service = PageService::new(self.db.clone());
+        let sites = SiteService::new(self.db.clone()).list_sites(tenant_id, MAX_SITES_PER_TENANT, 0).await?;
+
+        let mut report = WarmReport::default();
+        for site in sites.into_iter().filter(|site| site.is_published) {
+            let pages: Vec<Page> = page_service
+                .get_site_pages(tenant_id, site.id)
+                .await?
+                .into_iter()
+                .filter(|page| paths.iter().any(|path| path.trim_matches('/') == page.slug))
+                .collect();
+            if !pages.is_empty() {
+                let limit = pages.len();
+                report += self.warm_site_pages(&site, pages_to_warm(pages, paths, limit)).await;
+            }
+        }
+        Ok(report)
+    }
+
+    async fn warm_site_pages(&self, site: &Site, pages: Vec<Page>) -> WarmReport {
+        let mut report = WarmReport::default();
+        match self.template_engine.warm_templates(site.tenant_id, &[PUCK_BASE_TEMPLATE]).await {
+            Ok(found) => report.templates += found,
+            Err(e) => {
+                warn!(tenant_id = %site.tenant_id, "Failed to warm templates: {:#}", e);
+                report.failed += 1;
+            }
+        }
+
+        let page_service = PageService::new(self.db.clone());
+        report += warm_published_pages(&self.publish_cache, site.id, pages, |page| {
+            page_service.public_html(site, Some(page), &self.minify)
+        })
+        .await;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn page(site_id: Uuid, slug: &str, sort_order: i32) -> Page {
+        let now = Utc::now();
+        Page {
+            id: Uuid::new_v4(),
+            site_id,
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            meta_description: None,
+            meta_keywords: None,
+            puck_data: serde_json::json!({}),
+            composition_error: false,
+            custom_head: None,
+            custom_body: None,
+            is_published: true,
+            published_html: Some(format!("<h1>{}</h1>", slug)),
+            published_at: Some(now),
+            sort_order,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_most_viewed_pages_chosen_first() {
+        let site_id = Uuid::new_v4();
+        let mut draft = page(site_id, "draft", 0);
+        draft.is_published = false;
+        let pages = vec![page(site_id, "home", 0), page(site_id, "about", 1), page(site_id, "books", 2), draft];
+        let popular = vec!["/books".to_string(), "/missing".to_string(), "/about".to_string()];
+
+        let slugs = |pages: Vec<Page>| pages.into_iter().map(|page| page.slug).collect::<Vec<_>>();
+        assert_eq!(slugs(pages_to_warm(pages.clone(), &popular, 10)), vec!["books", "about", "home"]);
+        assert_eq!(slugs(pages_to_warm(pages.clone(), &popular, 2)), vec!["books", "about"]);
+        assert_eq!(slugs(pages_to_warm(pages, &[], 2)), vec!["home", "about"]);
+    }
+
+    #[tokio::test]
+    async fn test_warmed_pages_served_from_cache() {
+        let cache = PublishCache::new();
+        let site_id = Uuid::new_v4();
+        let loads = AtomicUsize::new(0);
+        let load = |page: Page| {
+            loads.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(page.published_html) }
+        };
+
+        let pages = vec![page(site_id, "home", 0), page(site_id, "books", 1)];
+        let report = warm_published_pages(&cache, site_id, pages.clone(), load).await;
+        assert_eq!(report, WarmReport { templates: 0, pages: 2, failed: 0 });
+        assert_eq!(cache.get(site_id, "/books").map(|page| page.body), Some("<h1>books</h1>".to_string()));
+
+        // A visitor's request finds the page without loading it again
+        let served = cache
+            .get_or_load(site_id, "/home", || async { anyhow::Ok(Some("<h1>reloaded</h1>".to_string())) })
+            .await
+            .unwrap();
+        assert_eq!(served.map(|page| page.body), Some("<h1>home</h1>".to_string()));
+
+        warm_published_pages(&cache, site_id, pages, load).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+}
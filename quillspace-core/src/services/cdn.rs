@@ -0,0 +1,62 @@
+use crate::config::CdnConfig;
+use crate::services::http_client;
+use crate::services::public_url::PLATFORM_SITE_DOMAIN;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Best-effort CDN cache purge after publishing.
+///
+/// The purge endpoint receives `{"files": [...urls]}` with a bearer token, which is
+/// the shape used by Cloudflare's purge API. Failures are logged and never fail the
+/// publish itself; the CDN copy then expires on its own.
+#[derive(Clone)]
+pub struct CdnPurger {
+    config: CdnConfig,
+    http_client: reqwest::Client,
+}
+
+impl CdnPurger {
+    pub fn new(config: CdnConfig) -> Self {
+        let http_client = http_client::client_builder()
+            .timeout(Duration::from_millis(config.purge_timeout_ms))
+            .build()
+            .unwrap_or_default();
+        Self { config, http_client }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        !self.config.purge_url.trim().is_empty()
+    }
+
+    /// Purge the given public URLs, logging rather than returning any failure
+    pub async fn purge(&self, urls: Vec<String>) {
+        if !self.is_configured() || urls.is_empty() {
+            return;
+        }
+
+        let result = self
+            .http_client
+            .post(&self.config.purge_url)
+            .bearer_auth(&self.config.api_token)
+            .json(&serde_json::json!({ "files": urls }))
+            .send()
+            .await;
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                info!(count = urls.len(), "CDN cache purged");
+            }
+            Ok(response) => warn!(status = %response.status(), "CDN purge rejected"),
+            Err(e) => warn!("CDN purge failed: {}", e),
+        }
+    }
+}
+
+/// Public URLs a page is served under: its subdomain and, if set, the custom domain
+pub fn page_urls(subdomain: &str, custom_domain: Option<&str>, slug: &str) -> Vec<String> {
+    let path = slug.trim_start_matches('/');
+    std::iter::once(format!("{}.{}", subdomain, PLATFORM_SITE_DOMAIN))
+        .chain(custom_domain.filter(|domain| !domain.is_empty()).map(str::to_string))
+        .map(|host| format!("https://{}/{}", host, path))
+        .collect()
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Source of the current time, so services that schedule things can be tested at a fixed instant
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Source of new record ids
+pub trait IdGenerator: Send + Sync {
+    fn new_id(&self) -> Uuid;
+}
+
+/// The system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Random v4 ids
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn new_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// A clock that stays where it is set
+#[cfg(test)]
+#[derive(Debug)]
+pub struct FixedClock(std::sync::Mutex<DateTime<Utc>>);
+
+#[cfg(test)]
+impl FixedClock {
+    pub fn at(now: DateTime<Utc>) -> Self {
+        Self(std::sync::Mutex::new(now))
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+}
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// Ids `00000000-0000-0000-0000-000000000001`, `...0002` and so on
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct SequentialIds(std::sync::atomic::AtomicU64);
+
+#[cfg(test)]
+impl SequentialIds {
+    /// The id returned by the `n`th call, counting from 1
+    pub fn nth(n: u64) -> Uuid {
+        Uuid::from_u128(u128::from(n))
+    }
+}
+
+#[cfg(test)]
+impl IdGenerator for SequentialIds {
+    fn new_id(&self) -> Uuid {
+        Self::nth(self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_fixed_clock_and_sequential_ids() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap();
+        let clock = FixedClock::at(start);
+        assert_eq!((clock.now(), clock.now()), (start, start));
+        clock.advance(chrono::Duration::hours(2));
+        assert_eq!(clock.now(), Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap());
+
+        let ids = SequentialIds::default();
+        assert_eq!(ids.new_id().to_string(), "00000000-0000-0000-0000-000000000001");
+        assert_eq!(ids.new_id(), SequentialIds::nth(2));
+        assert_ne!(RandomIds.new_id(), RandomIds.new_id());
+    }
+}
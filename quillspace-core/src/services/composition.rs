@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, Map};
 use chrono::Datelike;
+use std::collections::HashMap;
+use tracing::warn;
+use uuid::Uuid;
 
 /// Puck composition structure from the editor
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,6 +11,9 @@ pub struct PuckComposition {
     pub version: Option<u32>,
     pub content: Vec<PuckBlock>,
     pub root: PuckRoot,
+    /// Last write time of each node (by node id, `root` for the root props), kept for auto-save conflict detection
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub node_updated_at: HashMap<String, chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +28,30 @@ pub struct PuckRoot {
     pub props: Map<String, Value>,
 }
 
+impl PuckComposition {
+    /// A composition with no blocks, standing in for one that cannot be read
+    pub fn empty() -> Self {
+        Self {
+            version: None,
+            content: Vec::new(),
+            root: PuckRoot { props: Map::new() },
+            node_updated_at: HashMap::new(),
+        }
+    }
+}
+
+/// A page's stored draft, or an empty one with `true` when it is missing or does not
+/// parse, so a legacy or corrupted row still loads. The page is logged for repair.
+pub fn read_stored_composition(page_id: Uuid, stored: Option<Value>) -> (PuckComposition, bool) {
+    let error = match stored.map(serde_json::from_value::<PuckComposition>) {
+        Some(Ok(composition)) => return (composition, false),
+        Some(Err(e)) => e.to_string(),
+        None => "no composition stored".to_string(),
+    };
+    warn!(page_id = %page_id, "Page has a malformed composition, returning an empty one: {}", error);
+    (PuckComposition::empty(), true)
+}
+
 /// Structured context for MiniJinja rendering
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenderContext {
@@ -325,6 +355,67 @@ impl ToTitleCase for str {
     }
 }
 
+/// Default content a template ships in its `default_schema`, in Puck data shape
+#[derive(Debug, Deserialize)]
+struct DefaultContent {
+    content: Vec<PuckBlock>,
+    #[serde(default)]
+    root: Option<PuckRoot>,
+}
+
+/// Build a new draft for a template from its `default_schema`
+/// (`{"content": [{"type": ..., "props": {...}}], "root": {"props": {...}}}`).
+///
+/// Each default node takes over the props of the first unused existing block of
+/// the same type, keeping that block's id; default nodes without a match stay as
+/// placeholders. Existing blocks that fit nowhere in the new layout are dropped.
+pub fn seed_from_default_schema(
+    default_schema: &Value,
+    existing: &PuckComposition,
+) -> Result<PuckComposition, CompositionError> {
+    if default_schema.get("content").is_none() {
+        return Err(CompositionError::MissingRequiredField("default_schema.content".to_string()));
+    }
+    let defaults: DefaultContent = serde_json::from_value(default_schema.clone())?;
+
+    let mut used = vec![false; existing.content.len()];
+    let content = defaults
+        .content
+        .into_iter()
+        .map(|default_block| {
+            let matched = existing
+                .content
+                .iter()
+                .enumerate()
+                .position(|(i, block)| !used[i] && block.block_type == default_block.block_type);
+
+            let mut props = default_block.props;
+            match matched {
+                Some(i) => {
+                    used[i] = true;
+                    props.extend(existing.content[i].props.clone());
+                }
+                None => {
+                    let id = format!("{}-{}", default_block.block_type, uuid::Uuid::new_v4());
+                    props.insert("id".to_string(), Value::String(id));
+                }
+            }
+            PuckBlock { block_type: default_block.block_type, props }
+        })
+        .collect();
+
+    // Page-level props such as the title belong to the page, not the layout
+    let mut root_props = defaults.root.map(|root| root.props).unwrap_or_default();
+    root_props.extend(existing.root.props.clone());
+
+    Ok(PuckComposition {
+        version: existing.version,
+        content,
+        root: PuckRoot { props: root_props },
+        node_updated_at: HashMap::new(),
+    })
+}
+
 /// Error types for composition transformation
 #[derive(Debug, thiserror::Error)]
 pub enum CompositionError {
@@ -346,6 +437,21 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_malformed_stored_composition_reads_as_empty() {
+        let page_id = Uuid::new_v4();
+        let stored = json!({ "content": [{ "type": "Hero", "props": { "title": "Hi" } }], "root": { "props": {} } });
+        let (composition, malformed) = read_stored_composition(page_id, Some(stored));
+        assert!(!malformed);
+        assert_eq!(composition.content[0].block_type, "Hero");
+
+        for stored in [Some(json!("{\"content\": [")), Some(json!({ "content": "Hero" })), None] {
+            let (composition, malformed) = read_stored_composition(page_id, stored);
+            assert!(malformed);
+            assert!(composition.content.is_empty());
+        }
+    }
+
     #[test]
     fn test_composition_to_context() {
         let composition = PuckComposition {
@@ -376,6 +482,7 @@ mod tests {
                     map
                 },
             },
+            node_updated_at: HashMap::new(),
         };
 
         let defaults = RenderDefaults::default();
@@ -388,6 +495,74 @@ mod tests {
         assert_eq!(context.content[1].block_type, "TextBlock");
     }
 
+    fn existing_draft() -> PuckComposition {
+        serde_json::from_value(json!({
+            "version": 1,
+            "content": [
+                { "type": "TextBlock", "props": { "id": "text-1", "children": "My story so far." } },
+                { "type": "HeroBlock", "props": { "id": "hero-1", "title": "The Lighthouse" } },
+                { "type": "CardBlock", "props": { "id": "card-1", "title": "Reviews" } }
+            ],
+            "root": { "props": { "title": "About the Author" } }
+        }))
+        .unwrap()
+    }
+
+    fn default_schema() -> Value {
+        json!({
+            "content": [
+                { "type": "HeroBlock", "props": { "title": "Your Book Title", "subtitle": "A tagline" } },
+                { "type": "ImageBlock", "props": { "src": "/placeholder.png", "alt": "Cover" } },
+                { "type": "TextBlock", "props": { "children": "Tell readers about yourself." } }
+            ],
+            "root": { "props": { "title": "Home", "layout": "centered" } }
+        })
+    }
+
+    #[test]
+    fn test_seeding_produces_template_default_nodes() {
+        let empty = PuckComposition {
+            version: Some(1),
+            content: Vec::new(),
+            root: PuckRoot { props: Map::new() },
+            node_updated_at: HashMap::new(),
+        };
+        let seeded = seed_from_default_schema(&default_schema(), &empty).unwrap();
+
+        let types: Vec<_> = seeded.content.iter().map(|block| block.block_type.as_str()).collect();
+        assert_eq!(types, vec!["HeroBlock", "ImageBlock", "TextBlock"]);
+        assert_eq!(seeded.content[0].props.get("title"), Some(&json!("Your Book Title")));
+        assert_eq!(seeded.root.props.get("layout"), Some(&json!("centered")));
+        // Placeholders get distinct node ids for the editor
+        let ids: std::collections::HashSet<_> =
+            seeded.content.iter().map(|block| block.props.get("id").cloned().unwrap()).collect();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    fn test_seeding_carries_over_matching_content() {
+        let seeded = seed_from_default_schema(&default_schema(), &existing_draft()).unwrap();
+
+        let hero = &seeded.content[0].props;
+        assert_eq!(hero.get("id"), Some(&json!("hero-1")));
+        assert_eq!(hero.get("title"), Some(&json!("The Lighthouse")));
+        assert_eq!(hero.get("subtitle"), Some(&json!("A tagline")));
+        assert_eq!(seeded.content[1].props.get("src"), Some(&json!("/placeholder.png")));
+        assert_eq!(seeded.content[2].props.get("children"), Some(&json!("My story so far.")));
+        // No CardBlock slot in the new layout
+        assert!(seeded.content.iter().all(|block| block.block_type != "CardBlock"));
+        assert_eq!(seeded.root.props.get("title"), Some(&json!("About the Author")));
+        assert_eq!(seeded.root.props.get("layout"), Some(&json!("centered")));
+    }
+
+    #[test]
+    fn test_seeding_requires_default_content() {
+        assert!(matches!(
+            seed_from_default_schema(&json!({ "fields": {} }), &existing_draft()),
+            Err(CompositionError::MissingRequiredField(_))
+        ));
+    }
+
     #[test]
     fn test_validate_hero_block() {
         let mut props = Map::new();
@@ -1,9 +1,22 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
+use crate::config::ConnectedWebsitesConfig;
 use crate::database::DatabaseConnections;
-use crate::services::wix_api::WixApiClient;
+use crate::middleware::rate_limit::RateLimiter;
+use crate::services::object_store::BoxFuture;
+use crate::services::wix_api::{WixApiClient, WixApiError};
 use anyhow::Result;
+use deadpool_postgres::GenericClient;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_postgres::Row;
+
+/// Largest page of websites one listing returns
+pub const MAX_WEBSITES_PER_PAGE: u32 = 100;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConnectedWebsite {
@@ -23,13 +36,13 @@ pub struct ConnectedWebsite {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum BuilderType {
     Wix,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ConnectionStatus {
     Active,
@@ -37,13 +50,332 @@ pub enum ConnectionStatus {
     Error,
 }
 
+impl ConnectionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionStatus::Active => "active",
+            ConnectionStatus::Inactive => "inactive",
+            ConnectionStatus::Error => "error",
+        }
+    }
+
+    /// Status after a failed Wix call. Rejected credentials and a missing site break the
+    /// connection; rate limits, outages and rejected requests leave it up.
+    pub fn after_wix_error(error: &WixApiError) -> Self {
+        match error {
+            WixApiError::Unauthorized(_) => ConnectionStatus::Error,
+            WixApiError::NotFound(_) => ConnectionStatus::Inactive,
+            WixApiError::RateLimited { .. }
+            | WixApiError::Validation { .. }
+            | WixApiError::Server { .. }
+            | WixApiError::Timeout
+            | WixApiError::Other { .. }
+            | WixApiError::InvalidResponse(_)
+            | WixApiError::Http(_) => ConnectionStatus::Active,
+        }
+    }
+}
+
+/// Outcome of the last Wix call for a site, kept under `connection` in its metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionHealth {
+    pub status: ConnectionStatus,
+    pub sync_error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl ConnectionHealth {
+    pub fn from_result(error: Option<&WixApiError>, checked_at: DateTime<Utc>) -> Self {
+        match error {
+            None => Self { status: ConnectionStatus::Active, sync_error: None, checked_at },
+            Some(error) => Self {
+                status: ConnectionStatus::after_wix_error(error),
+                sync_error: Some(error.to_string()),
+                checked_at,
+            },
+        }
+    }
+}
+
+/// Store the outcome of a Wix call in the site's metadata
+pub async fn store_connection_health(client: &impl GenericClient, site_id: &str, error: Option<&WixApiError>) -> Result<()> {
+    store_health(client, site_id, &ConnectionHealth::from_result(error, Utc::now())).await
+}
+
+async fn store_health(client: &impl GenericClient, site_id: &str, health: &ConnectionHealth) -> Result<()> {
+    let health = serde_json::to_value(health)?;
+    client
+        .execute(
+            "UPDATE user_wix_sites
+             SET metadata = jsonb_set(COALESCE(metadata, '{}'::jsonb), '{connection}', $2)
+             WHERE wix_site_id = $1",
+            &[&site_id, &health],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Record the outcome of a Wix call against the site. Failing to record it is logged, not
+/// returned, so it never hides the Wix result.
+pub async fn record_connection_health(db: &DatabaseConnections, site_id: &str, error: Option<&WixApiError>) {
+    let result = match db.postgres().get().await {
+        Ok(client) => store_connection_health(&client, site_id, error).await,
+        Err(e) => Err(anyhow::anyhow!("Failed to get database connection: {}", e)),
+    };
+    if let Err(e) = result {
+        tracing::warn!(site_id, error = %e, "Failed to record Wix connection health");
+    }
+}
+
+/// Looks up a Wix site's properties
+pub trait WixSiteLookup: Send + Sync {
+    fn site_properties<'a>(&'a self, site_id: &'a str) -> BoxFuture<'a, Result<serde_json::Value, WixApiError>>;
+}
+
+impl WixSiteLookup for WixApiClient {
+    fn site_properties<'a>(&'a self, site_id: &'a str) -> BoxFuture<'a, Result<serde_json::Value, WixApiError>> {
+        Box::pin(self.get_site_properties(site_id))
+    }
+}
+
+/// What looking a site up on Wix found
+#[derive(Debug, Clone)]
+pub struct WixSiteDetails {
+    /// The site properties, `None` if the lookup failed
+    pub properties: Option<serde_json::Value>,
+    pub health: ConnectionHealth,
+    /// Reused from an earlier lookup rather than fetched for this one
+    pub cached: bool,
+}
+
+/// Wix site lookups for website listings: at most `wix_lookup_concurrency` in flight
+/// per listing, and each result reused for `wix_lookup_cache_ttl_secs`
+pub struct WixSiteCache {
+    concurrency: usize,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (WixSiteDetails, Instant)>>,
+}
+
+impl WixSiteCache {
+    pub fn new(config: &ConnectedWebsitesConfig) -> Self {
+        Self {
+            concurrency: config.wix_lookup_concurrency.max(1),
+            ttl: Duration::from_secs(config.wix_lookup_cache_ttl_secs),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The site's details if looked up within the TTL
+    fn cached(&self, site_id: &str) -> Option<WixSiteDetails> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(site_id)
+            .filter(|(_, looked_up_at)| looked_up_at.elapsed() < self.ttl)
+            .map(|(details, _)| WixSiteDetails { cached: true, ..details.clone() })
+    }
+
+    /// Details of each site, from the cache or looked up concurrently on Wix
+    pub async fn lookup_all(&self, lookup: Arc<dyn WixSiteLookup>, site_ids: &[String]) -> HashMap<String, WixSiteDetails> {
+        let mut found = HashMap::new();
+        let mut pending = Vec::new();
+        for site_id in site_ids {
+            match self.cached(site_id) {
+                Some(details) => {
+                    found.insert(site_id.clone(), details);
+                }
+                None if !pending.contains(site_id) => pending.push(site_id.clone()),
+                None => {}
+            }
+        }
+
+        let permits = Arc::new(Semaphore::new(self.concurrency));
+        let mut lookups = JoinSet::new();
+        for site_id in pending {
+            let (lookup, permits) = (Arc::clone(&lookup), Arc::clone(&permits));
+            lookups.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                let result = lookup.site_properties(&site_id).await;
+                let health = ConnectionHealth::from_result(result.as_ref().err(), Utc::now());
+                (site_id, WixSiteDetails { properties: result.ok(), health, cached: false })
+            });
+        }
+        let mut fetched = Vec::new();
+        while let Some(joined) = lookups.join_next().await {
+            match joined {
+                Ok(entry) => fetched.push(entry),
+                Err(e) => tracing::warn!(error = %e, "Wix site lookup task failed"),
+            }
+        }
+
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|_, (_, looked_up_at)| looked_up_at.elapsed() < self.ttl);
+            let now = Instant::now();
+            for (site_id, details) in &fetched {
+                entries.insert(site_id.clone(), (details.clone(), now));
+            }
+        }
+        found.extend(fetched);
+        found
+    }
+}
+
+/// The account-level check behind a Wix connection test
+pub trait WixConnectionCheck: Send + Sync {
+    /// Identifies the credentials checked, without containing them
+    fn credentials_key(&self) -> String;
+    fn check_connection(&self) -> BoxFuture<'_, Result<(), WixApiError>>;
+}
+
+impl WixConnectionCheck for WixApiClient {
+    fn credentials_key(&self) -> String {
+        WixApiClient::credentials_key(self)
+    }
+
+    fn check_connection(&self) -> BoxFuture<'_, Result<(), WixApiError>> {
+        Box::pin(self.test_connection())
+    }
+}
+
+/// What testing Wix credentials found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ConnectionTestResult {
+    Connected {
+        /// Passed recently for the same credentials, so Wix was not asked again
+        cached: bool,
+    },
+    /// Wix rejected the credentials
+    AuthFailed { message: String },
+    /// Wix could not be reached or did not answer in time
+    NetworkFailed { message: String },
+    /// Wix answered with some other error, e.g. its own rate limit or a server error
+    WixError { message: String },
+}
+
+impl ConnectionTestResult {
+    fn from_result(result: Result<(), WixApiError>) -> Self {
+        match result {
+            Ok(()) => ConnectionTestResult::Connected { cached: false },
+            Err(WixApiError::Unauthorized(message)) => ConnectionTestResult::AuthFailed { message },
+            Err(e @ (WixApiError::Timeout | WixApiError::Http(_))) => {
+                ConnectionTestResult::NetworkFailed { message: e.to_string() }
+            }
+            Err(e) => ConnectionTestResult::WixError { message: e.to_string() },
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectionTestError {
+    #[error("Too many connection tests, try again in {retry_after_secs} seconds")]
+    TooManyTests { retry_after_secs: u64 },
+}
+
+/// Wix connection tests, as made while credentials are being entered: a pass is reused
+/// for `connection_test_cache_ttl_secs`, and each user can make
+/// `connection_tests_per_window` tests that reach Wix per `connection_test_window_secs`
+pub struct WixConnectionTester {
+    ttl: Duration,
+    window: Duration,
+    limiter: RateLimiter,
+    passed: Mutex<HashMap<String, Instant>>,
+}
+
+impl WixConnectionTester {
+    pub fn new(config: &ConnectedWebsitesConfig) -> Self {
+        let window = Duration::from_secs(config.connection_test_window_secs);
+        Self {
+            ttl: Duration::from_secs(config.connection_test_cache_ttl_secs),
+            window,
+            limiter: RateLimiter::new(config.connection_tests_per_window.max(1), window),
+            passed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Test the credentials `check` holds on behalf of `user_id`. Failures are not
+    /// cached, so corrected credentials are checked at once.
+    pub async fn test(&self, user_id: Uuid, check: &dyn WixConnectionCheck) -> Result<ConnectionTestResult, ConnectionTestError> {
+        let key = check.credentials_key();
+        let passed_recently = self
+            .passed
+            .lock()
+            .map(|passed| passed.get(&key).is_some_and(|at| at.elapsed() < self.ttl))
+            .unwrap_or(false);
+        if passed_recently {
+            return Ok(ConnectionTestResult::Connected { cached: true });
+        }
+
+        if !self.limiter.check_rate_limit(&user_id.to_string()) {
+            return Err(ConnectionTestError::TooManyTests { retry_after_secs: self.window.as_secs().max(1) });
+        }
+        let result = ConnectionTestResult::from_result(check.check_connection().await);
+        if let Ok(mut passed) = self.passed.lock() {
+            passed.retain(|_, at| at.elapsed() < self.ttl);
+            if result == (ConnectionTestResult::Connected { cached: false }) {
+                passed.insert(key, Instant::now());
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Which of a user's websites to list
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WebsiteListParams {
+    /// 1-based
+    pub page: u32,
+    /// Capped at [`MAX_WEBSITES_PER_PAGE`]
+    pub per_page: u32,
+    pub builder_type: Option<BuilderType>,
+    /// Matched against the status last recorded for the site
+    pub status: Option<ConnectionStatus>,
+}
+
+impl Default for WebsiteListParams {
+    fn default() -> Self {
+        Self { page: 1, per_page: 20, builder_type: None, status: None }
+    }
+}
+
+impl WebsiteListParams {
+    pub fn per_page(&self) -> u32 {
+        self.per_page.clamp(1, MAX_WEBSITES_PER_PAGE)
+    }
+
+    pub fn page(&self) -> u32 {
+        self.page.max(1)
+    }
+
+    fn offset(&self) -> i64 {
+        (self.page() as i64 - 1) * self.per_page() as i64
+    }
+}
+
+/// One page of a user's websites
+#[derive(Debug, Serialize)]
+pub struct WebsitePage {
+    pub websites: Vec<ConnectedWebsite>,
+    /// Websites matching the filters, across all pages
+    pub total: i64,
+    pub page: u32,
+    pub per_page: u32,
+}
+
 pub struct ConnectedWebsitesService {
     db: DatabaseConnections,
+    wix_sites: Option<(Arc<WixSiteCache>, Arc<dyn WixSiteLookup>)>,
 }
 
 impl ConnectedWebsitesService {
     pub fn new(db: DatabaseConnections) -> Self {
-        Self { db }
+        Self { db, wix_sites: None }
+    }
+
+    /// Look listed sites up on Wix through `lookup`, reusing recent results from `cache`
+    pub fn with_wix_lookup(mut self, cache: Arc<WixSiteCache>, lookup: Arc<dyn WixSiteLookup>) -> Self {
+        self.wix_sites = Some((cache, lookup));
+        self
     }
 
     /// Get Wix books for a specific site
@@ -54,7 +386,9 @@ impl ConnectedWebsitesService {
             .map_err(|_| anyhow::anyhow!("QUILLSPACE_WIX_ACCOUNT_ID not configured"))?;
 
         let client = WixApiClient::new(api_key, account_id);
-        client.get_collection_items(site_id, "Books").await
+        let result = client.get_collection_items(site_id, "Books").await;
+        record_connection_health(&self.db, site_id, result.as_ref().err()).await;
+        Ok(result?)
     }
 
     /// Create a new book in Wix site
@@ -65,7 +399,9 @@ impl ConnectedWebsitesService {
             .map_err(|_| anyhow::anyhow!("QUILLSPACE_WIX_ACCOUNT_ID not configured"))?;
 
         let client = WixApiClient::new(api_key, account_id);
-        client.insert_collection_item(site_id, "Books", book_data).await
+        let result = client.insert_collection_item(site_id, "Books", book_data).await;
+        record_connection_health(&self.db, site_id, result.as_ref().err()).await;
+        Ok(result?)
     }
 
     /// Update a book in Wix site
@@ -76,7 +412,9 @@ impl ConnectedWebsitesService {
             .map_err(|_| anyhow::anyhow!("QUILLSPACE_WIX_ACCOUNT_ID not configured"))?;
 
         let client = WixApiClient::new(api_key, account_id);
-        client.update_collection_item(site_id, "Books", book_id, book_data).await
+        let result = client.update_collection_item(site_id, "Books", book_id, book_data).await;
+        record_connection_health(&self.db, site_id, result.as_ref().err()).await;
+        Ok(result?)
     }
 
     /// Get author info from Wix site
@@ -87,7 +425,9 @@ impl ConnectedWebsitesService {
             .map_err(|_| anyhow::anyhow!("QUILLSPACE_WIX_ACCOUNT_ID not configured"))?;
 
         let client = WixApiClient::new(api_key, account_id);
-        client.get_collection_items(site_id, "AuthorInfo").await
+        let result = client.get_collection_items(site_id, "AuthorInfo").await;
+        record_connection_health(&self.db, site_id, result.as_ref().err()).await;
+        Ok(result?)
     }
 
     /// Update author info in Wix site
@@ -100,85 +440,488 @@ impl ConnectedWebsitesService {
         let client = WixApiClient::new(api_key, account_id);
         
         // Get existing AuthorInfo to update it
-        match client.get_collection_items(site_id, "AuthorInfo").await {
+        let result = match client.get_collection_items(site_id, "AuthorInfo").await {
             Ok(existing_data) => {
-                if let Some(items) = existing_data.get("dataItems").and_then(|v| v.as_array()) {
-                    if let Some(first_item) = items.first() {
-                        if let Some(item_id) = first_item.get("id").and_then(|v| v.as_str()) {
-                            return client.update_collection_item(site_id, "AuthorInfo", item_id, author_data).await;
-                        }
-                    }
+                let existing_id = existing_data
+                    .get("dataItems")
+                    .and_then(|v| v.as_array())
+                    .and_then(|items| items.first())
+                    .and_then(|item| item.get("id"))
+                    .and_then(|v| v.as_str());
+                match existing_id {
+                    Some(item_id) => client.update_collection_item(site_id, "AuthorInfo", item_id, author_data).await,
+                    // No existing author info, create new one
+                    None => client.insert_collection_item(site_id, "AuthorInfo", author_data).await,
                 }
-                // No existing author info, create new one
-                client.insert_collection_item(site_id, "AuthorInfo", author_data).await
             }
+            // Credentials or the site itself are the problem; creating would fail the same way
+            Err(e @ (WixApiError::Unauthorized(_) | WixApiError::NotFound(_))) => Err(e),
             Err(_) => {
                 // Create new if can't get existing
                 client.insert_collection_item(site_id, "AuthorInfo", author_data).await
             }
-        }
+        };
+        record_connection_health(&self.db, site_id, result.as_ref().err()).await;
+        Ok(result?)
     }
 
-    /// Get websites built by QuillSpace for a user
-    pub async fn get_user_websites(&self, user_id: Uuid) -> Result<Vec<ConnectedWebsite>> {
+    /// A page of the user's websites, newest first: those QuillSpace built for them and
+    /// those they connected themselves, each physical site once (see [`merge_websites`])
+    pub async fn get_user_websites(&self, user_id: Uuid, params: &WebsiteListParams) -> Result<WebsitePage> {
+        let (page, per_page) = (params.page(), params.per_page());
+        // Wix is the only builder QuillSpace manages sites on
+        if params.builder_type.is_some_and(|builder_type| builder_type != BuilderType::Wix) {
+            return Ok(WebsitePage { websites: Vec::new(), total: 0, page, per_page });
+        }
+
         let client = self.db.postgres().get().await
             .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
-            
-        let query = "
-            SELECT uws.wix_site_id, uws.tenant_id, uws.display_name, 
-                   uws.custom_domain, uws.project_status, uws.service_type, 
-                   uws.client_can_edit, uws.metadata, uws.created_at, uws.updated_at
-            FROM user_wix_sites uws
-            WHERE uws.user_id = $1 
-            AND uws.project_status IN ('review', 'active')
-            AND uws.client_can_edit = TRUE
-            ORDER BY uws.created_at DESC
+
+        // Both sources joined on the Wix site id. Status as the listing reports it: paused
+        // projects are inactive, live ones carry the health of their last Wix call, and
+        // connected-only sites keep their own
+        let websites = "
+            WITH built AS (
+                SELECT * FROM user_wix_sites
+                WHERE user_id = $1
+                AND project_status IN ('review', 'active')
+                AND client_can_edit = TRUE
+            ), connected AS (
+                SELECT * FROM connected_websites
+                WHERE user_id = $1 AND builder_type = 'wix'
+            ), websites AS (
+                SELECT b.wix_site_id, b.tenant_id AS b_tenant_id, b.display_name, b.custom_domain,
+                       b.project_status, b.service_type, b.metadata AS b_metadata,
+                       b.created_at AS b_created_at, b.updated_at AS b_updated_at,
+                       c.id AS c_id, c.tenant_id AS c_tenant_id, c.external_site_id, c.name AS c_name,
+                       c.url AS c_url, c.domain AS c_domain, c.status AS c_status, c.last_sync AS c_last_sync,
+                       c.metadata AS c_metadata, c.created_at AS c_created_at, c.updated_at AS c_updated_at,
+                       COALESCE(b.wix_site_id, c.external_site_id) AS site_id,
+                       COALESCE(b.created_at, c.created_at) AS listed_at,
+                       CASE WHEN b.wix_site_id IS NULL THEN c.status
+                            WHEN b.project_status <> 'active' THEN 'inactive'
+                            ELSE COALESCE(b.metadata->'connection'->>'status', 'active')
+                       END AS connection_status
+                FROM built b
+                FULL OUTER JOIN connected c ON c.external_site_id = b.wix_site_id
+            )
         ";
+        let status = params.status.map(|status| status.as_str());
+        let total: i64 = client
+            .query_one(
+                &format!("{websites} SELECT COUNT(*) FROM websites WHERE ($2::text IS NULL OR connection_status = $2)"),
+                &[&user_id, &status],
+            )
+            .await?
+            .get(0);
+        let rows = client
+            .query(
+                &format!(
+                    "{websites}
+                     SELECT * FROM websites
+                     WHERE ($2::text IS NULL OR connection_status = $2)
+                     ORDER BY listed_at DESC, site_id
+                     LIMIT $3 OFFSET $4"
+                ),
+                &[&user_id, &status, &(per_page as i64), &params.offset()],
+            )
+            .await?;
 
-        let rows = client.query(query, &[&user_id]).await?;
-        let mut websites = Vec::new();
-
-        for row in rows {
-            let wix_site_id: String = row.get(0);
-            let tenant_id: Uuid = row.get(1);
-            let display_name: Option<String> = row.get(2);
-            let custom_domain: Option<String> = row.get(3);
-            let project_status: String = row.get(4);
-            let service_type: String = row.get(5);
-            let metadata: serde_json::Value = row.get(7);
-            let created_at: DateTime<Utc> = row.get(8);
-            let updated_at: DateTime<Utc> = row.get(9);
-
-            websites.push(ConnectedWebsite {
-                id: Uuid::new_v4(),
-                tenant_id,
-                user_id,
-                builder_type: BuilderType::Wix,
-                external_site_id: wix_site_id.clone(),
-                name: display_name.unwrap_or_else(|| format!("Wix Site {}", &wix_site_id[..8])),
-                url: custom_domain.clone().map(|d| format!("https://{}", d)),
-                domain: custom_domain,
-                status: if project_status == "active" { 
-                    ConnectionStatus::Active 
-                } else { 
-                    ConnectionStatus::Inactive 
-                },
-                last_sync: Some(Utc::now()),
-                sync_error: None,
-                metadata: serde_json::json!({
-                    "wix_site_id": wix_site_id,
-                    "service_type": service_type,
-                    "project_status": project_status,
-                    "managed_by_quillspace": true,
-                    "original_metadata": metadata
-                }),
-                created_at,
-                updated_at,
-            });
+        let mut details = HashMap::new();
+        if let Some((cache, lookup)) = &self.wix_sites {
+            let site_ids: Vec<String> = rows.iter().map(|row| row.get("site_id")).collect();
+            details = cache.lookup_all(Arc::clone(lookup), &site_ids).await;
+            for (site_id, found) in details.iter().filter(|(_, found)| !found.cached) {
+                if let Err(e) = store_health(&client, site_id, &found.health).await {
+                    tracing::warn!(site_id, error = %e, "Failed to record Wix connection health");
+                }
+            }
         }
 
-        Ok(websites)
+        let websites = rows
+            .iter()
+            .filter_map(|row| {
+                let site_details = details.remove(&row.get::<_, String>("site_id"));
+                let built = built_website(row, user_id, site_details);
+                let connected = connected_website(row, user_id);
+                match (built, connected) {
+                    (Some(built), Some(connected)) => Some(merge_websites(built, connected)),
+                    (built, connected) => built.or(connected),
+                }
+            })
+            .collect();
+
+        Ok(WebsitePage { websites, total, page, per_page })
+    }
+}
+
+/// The QuillSpace-built half of a listing row, if the site has one
+fn built_website(row: &Row, user_id: Uuid, details: Option<WixSiteDetails>) -> Option<ConnectedWebsite> {
+    let wix_site_id: String = row.get::<_, Option<String>>("wix_site_id")?;
+    let display_name: Option<String> = row.get("display_name");
+    let custom_domain: Option<String> = row.get("custom_domain");
+    let project_status: String = row.get("project_status");
+    let service_type: String = row.get("service_type");
+    let metadata: serde_json::Value = row.get("b_metadata");
+    let properties = details.as_ref().and_then(|found| found.properties.clone());
+    let health: Option<ConnectionHealth> = match details {
+        Some(found) => Some(found.health),
+        None => metadata
+            .get("connection")
+            .and_then(|health| serde_json::from_value(health.clone()).ok()),
+    };
+    let name = display_name
+        .or_else(|| {
+            properties
+                .as_ref()
+                .and_then(|properties| properties.pointer("/properties/siteDisplayName"))
+                .and_then(|name| name.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| format!("Wix Site {}", wix_site_id.get(..8).unwrap_or(&wix_site_id)));
+
+    Some(ConnectedWebsite {
+        id: Uuid::new_v4(),
+        tenant_id: row.get("b_tenant_id"),
+        user_id,
+        builder_type: BuilderType::Wix,
+        external_site_id: wix_site_id.clone(),
+        name,
+        url: custom_domain.clone().map(|d| format!("https://{}", d)),
+        domain: custom_domain,
+        status: match &health {
+            _ if project_status != "active" => ConnectionStatus::Inactive,
+            Some(health) => health.status,
+            None => ConnectionStatus::Active,
+        },
+        last_sync: health.as_ref().map(|health| health.checked_at),
+        sync_error: health.and_then(|health| health.sync_error),
+        metadata: serde_json::json!({
+            "wix_site_id": wix_site_id,
+            "service_type": service_type,
+            "project_status": project_status,
+            "managed_by_quillspace": true,
+            "site_properties": properties,
+            "original_metadata": metadata
+        }),
+        created_at: row.get("b_created_at"),
+        updated_at: row.get("b_updated_at"),
+    })
+}
+
+/// The manually connected half of a listing row, if the site has one
+fn connected_website(row: &Row, user_id: Uuid) -> Option<ConnectedWebsite> {
+    let id: Uuid = row.get::<_, Option<Uuid>>("c_id")?;
+    let status: String = row.get("c_status");
+    Some(ConnectedWebsite {
+        id,
+        tenant_id: row.get("c_tenant_id"),
+        user_id,
+        builder_type: BuilderType::Wix,
+        external_site_id: row.get("external_site_id"),
+        name: row.get("c_name"),
+        url: row.get("c_url"),
+        domain: row.get("c_domain"),
+        status: match status.as_str() {
+            "active" => ConnectionStatus::Active,
+            "error" => ConnectionStatus::Error,
+            _ => ConnectionStatus::Inactive,
+        },
+        last_sync: row.get("c_last_sync"),
+        sync_error: None,
+        metadata: row.get::<_, Option<serde_json::Value>>("c_metadata").unwrap_or_default(),
+        created_at: row.get("c_created_at"),
+        updated_at: row.get("c_updated_at"),
+    })
+}
+
+/// One entry for a site that is both QuillSpace-built and manually connected.
+///
+/// The built record wins: QuillSpace manages the site, so its name, status and health
+/// are current. The connected record supplies its stable `id`, and the `url`, `domain`
+/// and `last_sync` the built record lacks. Metadata keys from both are kept, the built
+/// record's winning on conflict.
+pub fn merge_websites(built: ConnectedWebsite, connected: ConnectedWebsite) -> ConnectedWebsite {
+    let mut metadata = match connected.metadata {
+        serde_json::Value::Object(fields) => fields,
+        _ => serde_json::Map::new(),
+    };
+    if let serde_json::Value::Object(fields) = built.metadata {
+        metadata.extend(fields);
+    }
+    ConnectedWebsite {
+        id: connected.id,
+        url: built.url.or(connected.url),
+        domain: built.domain.or(connected.domain),
+        last_sync: built.last_sync.or(connected.last_sync),
+        metadata: serde_json::Value::Object(metadata),
+        created_at: built.created_at.min(connected.created_at),
+        updated_at: built.updated_at.max(connected.updated_at),
+        ..built
     }
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn test_wix_errors_set_connection_status() {
+        let now = Utc::now();
+        let cases = [
+            (WixApiError::Unauthorized("Invalid API key".to_string()), ConnectionStatus::Error),
+            (WixApiError::NotFound("Site not found".to_string()), ConnectionStatus::Inactive),
+            (WixApiError::RateLimited { retry_after_secs: Some(10) }, ConnectionStatus::Active),
+            (WixApiError::Server { status: 503, message: "Unavailable".to_string() }, ConnectionStatus::Active),
+            (WixApiError::Validation { code: None, message: "Invalid filter".to_string() }, ConnectionStatus::Active),
+        ];
+        for (error, status) in cases {
+            let health = ConnectionHealth::from_result(Some(&error), now);
+            assert_eq!(health.status, status, "{:?}", error);
+            assert_eq!(health.sync_error, Some(error.to_string()));
+        }
+
+        let healthy = ConnectionHealth::from_result(None, now);
+        assert_eq!((healthy.status, healthy.sync_error), (ConnectionStatus::Active, None));
+    }
+
+    /// Counts lookups and the most in flight at once
+    #[derive(Default)]
+    struct CountingLookup {
+        calls: std::sync::atomic::AtomicUsize,
+        in_flight: std::sync::atomic::AtomicUsize,
+        max_in_flight: std::sync::atomic::AtomicUsize,
+    }
+
+    impl WixSiteLookup for CountingLookup {
+        fn site_properties<'a>(&'a self, site_id: &'a str) -> BoxFuture<'a, Result<serde_json::Value, WixApiError>> {
+            use std::sync::atomic::Ordering::SeqCst;
+            Box::pin(async move {
+                self.calls.fetch_add(1, SeqCst);
+                let now = self.in_flight.fetch_add(1, SeqCst) + 1;
+                self.max_in_flight.fetch_max(now, SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                self.in_flight.fetch_sub(1, SeqCst);
+                if site_id == "missing" {
+                    return Err(WixApiError::NotFound("Site not found".to_string()));
+                }
+                Ok(serde_json::json!({ "properties": { "siteDisplayName": site_id } }))
+            })
+        }
+    }
+
+    /// A fake Wix site list accepting only `good-key`, counting the requests it gets
+    async fn mock_site_list() -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use axum::{http::{HeaderMap, StatusCode}, routing::post, Json, Router};
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counted = calls.clone();
+        let handler = move |headers: HeaderMap| {
+            let counted = counted.clone();
+            async move {
+                counted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                if headers.get("authorization").is_some_and(|key| key == "good-key") {
+                    (StatusCode::OK, Json(serde_json::json!({ "sites": [] })))
+                } else {
+                    (StatusCode::UNAUTHORIZED, Json(serde_json::json!({ "message": "Invalid API key" })))
+                }
+            }
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind mock Wix");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().route("/site-list/v2/sites/query", post(handler));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (base_url, calls)
+    }
+
+    #[tokio::test]
+    async fn test_repeated_connection_tests_reach_wix_once() {
+        use std::sync::atomic::Ordering::SeqCst;
+        let (base_url, calls) = mock_site_list().await;
+        let tester = WixConnectionTester::new(&ConnectedWebsitesConfig::default());
+        let client = WixApiClient::new("good-key".to_string(), "account".to_string()).with_base_url(&base_url);
+        let user_id = Uuid::new_v4();
+
+        assert_eq!(tester.test(user_id, &client).await.unwrap(), ConnectionTestResult::Connected { cached: false });
+        for _ in 0..10 {
+            assert_eq!(tester.test(user_id, &client).await.unwrap(), ConnectionTestResult::Connected { cached: true });
+        }
+        assert_eq!(calls.load(SeqCst), 1);
+
+        // Failures are not cached but are rate limited per user
+        let bad = WixApiClient::new("bad-key".to_string(), "account".to_string()).with_base_url(&base_url);
+        for _ in 1..5 {
+            assert!(matches!(tester.test(user_id, &bad).await.unwrap(), ConnectionTestResult::AuthFailed { .. }));
+        }
+        assert!(matches!(tester.test(user_id, &bad).await, Err(ConnectionTestError::TooManyTests { .. })));
+        assert!(matches!(tester.test(Uuid::new_v4(), &bad).await, Ok(ConnectionTestResult::AuthFailed { .. })));
+        assert_eq!(calls.load(SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn test_connection_test_tells_auth_from_network_failure() {
+        let (base_url, _) = mock_site_list().await;
+        let tester = WixConnectionTester::new(&ConnectedWebsitesConfig::default());
+        let user_id = Uuid::new_v4();
+
+        let rejected = WixApiClient::new("bad-key".to_string(), "account".to_string()).with_base_url(&base_url);
+        assert_eq!(
+            tester.test(user_id, &rejected).await.unwrap(),
+            ConnectionTestResult::AuthFailed { message: "Invalid API key".to_string() }
+        );
+
+        // Nothing listens on a port just released
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let unreachable = WixApiClient::new("good-key".to_string(), "account".to_string()).with_base_url(closed);
+        assert!(matches!(tester.test(user_id, &unreachable).await.unwrap(), ConnectionTestResult::NetworkFailed { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_wix_lookups_run_concurrently_up_to_the_limit_and_are_cached() {
+        use std::sync::atomic::Ordering::SeqCst;
+        let config = ConnectedWebsitesConfig { wix_lookup_concurrency: 3, wix_lookup_cache_ttl_secs: 60, ..Default::default() };
+        let cache = WixSiteCache::new(&config);
+        let lookup = Arc::new(CountingLookup::default());
+        let mut site_ids: Vec<String> = (0..10).map(|i| format!("site-{}", i)).collect();
+        site_ids.push("missing".to_string());
+
+        let found = cache.lookup_all(lookup.clone(), &site_ids).await;
+        assert_eq!(found.len(), 11);
+        assert_eq!(lookup.calls.load(SeqCst), 11);
+        assert_eq!(lookup.max_in_flight.load(SeqCst), 3);
+        assert_eq!(found["missing"].health.status, ConnectionStatus::Inactive);
+        assert!(found["missing"].properties.is_none());
+
+        // A second listing within the TTL asks Wix nothing
+        let found = cache.lookup_all(lookup.clone(), &site_ids[..4]).await;
+        assert!(found.values().all(|details| details.cached));
+        assert_eq!(lookup.calls.load(SeqCst), 11);
+
+        let expired = WixSiteCache::new(&ConnectedWebsitesConfig { wix_lookup_cache_ttl_secs: 0, ..config });
+        expired.lookup_all(lookup.clone(), &site_ids[..2]).await;
+        expired.lookup_all(lookup.clone(), &site_ids[..2]).await;
+        assert_eq!(lookup.calls.load(SeqCst), 15);
+    }
+
+    #[tokio::test]
+    async fn test_user_websites_are_paginated_and_filtered() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let admin = app.admin_pool.get().await.unwrap();
+        let user = &app.tenant_a.admin;
+        let seed = |name: &'static str, project_status: &'static str, metadata: serde_json::Value, day: u32| {
+            let admin = &admin;
+            async move {
+                admin
+                    .execute(
+                        "INSERT INTO user_wix_sites (wix_site_id, tenant_id, user_id, display_name, project_status, metadata, created_at)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                        &[
+                            &format!("wix-{}", name.to_lowercase()),
+                            user.tenant_id.as_uuid(),
+                            &user.id,
+                            &name,
+                            &project_status,
+                            &metadata,
+                            &chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 4, day, 9, 0, 0).unwrap(),
+                        ],
+                    )
+                    .await
+                    .expect("Failed to seed site");
+            }
+        };
+        let broken = serde_json::json!({ "connection": { "status": "error", "sync_error": "Invalid API key", "checked_at": Utc::now() } });
+        seed("Emma", "active", serde_json::json!({}), 1).await;
+        seed("Persuasion", "active", serde_json::json!({}), 2).await;
+        seed("Sanditon", "review", serde_json::json!({}), 3).await;
+        seed("Mansfield", "active", broken, 4).await;
+        seed("Northanger", "active", serde_json::json!({}), 5).await;
+        seed("Archived", "archived", serde_json::json!({}), 6).await;
+
+        let service = ConnectedWebsitesService::new(app.state.db.clone());
+        let list = |params: WebsiteListParams| {
+            let service = &service;
+            async move {
+                let page = service.get_user_websites(user.id, &params).await.unwrap();
+                (page.total, page.websites.into_iter().map(|website| website.name).collect::<Vec<_>>())
+            }
+        };
+        let params = |page: u32, per_page: u32, status: Option<ConnectionStatus>| WebsiteListParams {
+            page,
+            per_page,
+            builder_type: None,
+            status,
+        };
+
+        assert_eq!(list(params(1, 2, None)).await, (5, vec!["Northanger".to_string(), "Mansfield".to_string()]));
+        assert_eq!(list(params(3, 2, None)).await, (5, vec!["Emma".to_string()]));
+        assert_eq!(list(params(4, 2, None)).await, (5, vec![]));
+        assert_eq!(list(params(1, 1000, None)).await.1.len(), 5);
+        assert_eq!(list(params(1, 20, Some(ConnectionStatus::Error))).await, (1, vec!["Mansfield".to_string()]));
+        assert_eq!(list(params(1, 20, Some(ConnectionStatus::Inactive))).await, (1, vec!["Sanditon".to_string()]));
+        assert_eq!(list(params(1, 20, Some(ConnectionStatus::Active))).await.0, 3);
+        assert_eq!(
+            list(WebsiteListParams { builder_type: Some(BuilderType::Wix), ..Default::default() }).await.0,
+            5
+        );
+        assert_eq!(params(1, 1000, None).per_page(), MAX_WEBSITES_PER_PAGE);
+    }
+
+    #[tokio::test]
+    async fn test_site_built_and_connected_is_listed_once() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let admin = app.admin_pool.get().await.unwrap();
+        let user = &app.tenant_a.admin;
+        for (site_id, name) in [("wix-emma", "Emma"), ("wix-persuasion", "Persuasion")] {
+            admin
+                .execute(
+                    "INSERT INTO user_wix_sites (wix_site_id, tenant_id, user_id, display_name, metadata)
+                     VALUES ($1, $2, $3, $4, '{\"plan\": \"managed\"}')",
+                    &[&site_id, user.tenant_id.as_uuid(), &user.id, &name],
+                )
+                .await
+                .expect("Failed to seed built site");
+        }
+        let connected_id: Uuid = admin
+            .query_one(
+                "INSERT INTO connected_websites (tenant_id, user_id, builder_type, external_site_id, name, url, domain, metadata)
+                 VALUES ($1, $2, 'wix', 'wix-emma', 'My Emma site', 'https://emma.wixsite.com/emma', 'emma.wixsite.com',
+                         '{\"built_by\": \"quillspace_team\", \"managed_by_quillspace\": false}')
+                 RETURNING id",
+                &[user.tenant_id.as_uuid(), &user.id],
+            )
+            .await
+            .expect("Failed to seed connected site")
+            .get(0);
+        admin
+            .execute(
+                "INSERT INTO connected_websites (tenant_id, user_id, builder_type, external_site_id, name, status)
+                 VALUES ($1, $2, 'wix', 'wix-sanditon', 'Sanditon', 'error')",
+                &[user.tenant_id.as_uuid(), &user.id],
+            )
+            .await
+            .expect("Failed to seed connected site");
+
+        let service = ConnectedWebsitesService::new(app.state.db.clone());
+        let page = service.get_user_websites(user.id, &WebsiteListParams::default()).await.unwrap();
+        assert_eq!(page.total, 3);
+        let mut names: Vec<&str> = page.websites.iter().map(|website| website.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["Emma", "Persuasion", "Sanditon"]);
+
+        let emma = page.websites.iter().find(|website| website.external_site_id == "wix-emma").unwrap();
+        assert_eq!(emma.id, connected_id);
+        assert_eq!(emma.url.as_deref(), Some("https://emma.wixsite.com/emma"));
+        assert_eq!(emma.metadata["built_by"], "quillspace_team");
+        assert_eq!(emma.metadata["managed_by_quillspace"], true);
+        assert_eq!(emma.metadata["original_metadata"]["plan"], "managed");
+
+        let errored = WebsiteListParams { status: Some(ConnectionStatus::Error), ..Default::default() };
+        let page = service.get_user_websites(user.id, &errored).await.unwrap();
+        assert_eq!(page.websites.iter().map(|website| website.name.as_str()).collect::<Vec<_>>(), vec!["Sanditon"]);
+    }
 }
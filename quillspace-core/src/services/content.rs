@@ -1,19 +1,54 @@
-use crate::types::{Content, ContentStatus, TenantId, UserId};
-use anyhow::Result;
+use crate::database::postgres::tenant_client;
+use crate::services::bulk_publish::{plan_bulk, BulkItemStatus, BulkPublishReport, BulkPublishRequest};
+use crate::services::content_related::{rank_related, RelatedContent};
+use crate::services::content_review::{ensure_publishable, review_transition, ContentReviewError, ReviewAction};
+use crate::services::locale;
+use crate::types::{Content, ContentAuthor, ContentStatus, ReviewDecision, TenantId, UserId};
+use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use deadpool_postgres::Pool;
+use deadpool_postgres::{GenericClient, Pool};
+use std::collections::HashMap;
 use tokio_postgres::{Row, Error as PgError};
 use uuid::Uuid;
 
+/// Errors from changing a piece of content's byline
+#[derive(Debug, thiserror::Error)]
+pub enum ContentAuthorError {
+    #[error("Content not found")]
+    ContentNotFound,
+
+    #[error("User not found in tenant")]
+    UserNotFound,
+
+    #[error("The primary author is set by author_id")]
+    PrimaryAuthor,
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// Errors from translating a piece of content
+#[derive(Debug, thiserror::Error)]
+pub enum ContentTranslationError {
+    #[error("Translation already exists for locale '{0}'")]
+    LocaleExists(String),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// Place `user_id` among the co-authors at byline `position` (1 is right after the
+/// primary author; `None` or past the end appends). Re-adding moves the author.
+pub fn insert_co_author(co_authors: &mut Vec<Uuid>, user_id: Uuid, position: Option<usize>) {
+    co_authors.retain(|id| *id != user_id);
+    let index = position.map_or(co_authors.len(), |position| position.max(1) - 1).min(co_authors.len());
+    co_authors.insert(index, user_id);
+}
+
 /// Helper function to convert a tokio-postgres Row to Content
 fn row_to_content(row: &Row) -> Result<Content, PgError> {
-    let status_str: String = row.try_get("status")?;
-    let status = match status_str.as_str() {
-        "Draft" => ContentStatus::Draft,
-        "Published" => ContentStatus::Published,
-        "Archived" => ContentStatus::Archived,
-        _ => ContentStatus::Draft,
-    };
+    let status = status_from_string(&row.try_get::<_, String>("status")?);
+    let review_decision: Option<String> = row.try_get("review_decision")?;
 
     Ok(Content {
         id: row.try_get("id")?,
@@ -24,20 +59,100 @@ fn row_to_content(row: &Row) -> Result<Content, PgError> {
         status,
         author_id: row.try_get("author_id")?,
         published_at: row.try_get("published_at")?,
+        scheduled_publish_at: row.try_get("scheduled_publish_at")?,
+        locale: row.try_get("locale")?,
+        translation_group_id: row.try_get("translation_group_id")?,
+        tags: row.try_get("tags")?,
+        review_decision: review_decision.as_deref().and_then(ReviewDecision::parse),
+        reviewed_by: row.try_get("reviewed_by")?,
+        reviewed_at: row.try_get("reviewed_at")?,
+        review_comment: row.try_get("review_comment")?,
         created_at: row.try_get("created_at")?,
         updated_at: row.try_get("updated_at")?,
     })
 }
 
+pub(crate) fn status_from_string(status: &str) -> ContentStatus {
+    match status {
+        "Draft" => ContentStatus::Draft,
+        "PendingReview" => ContentStatus::PendingReview,
+        "Published" => ContentStatus::Published,
+        "Archived" => ContentStatus::Archived,
+        _ => ContentStatus::Draft,
+    }
+}
+
 /// Helper function to convert ContentStatus to string for database
-fn content_status_to_string(status: &ContentStatus) -> &'static str {
+pub(crate) fn content_status_to_string(status: &ContentStatus) -> &'static str {
     match status {
         ContentStatus::Draft => "Draft",
+        ContentStatus::PendingReview => "PendingReview",
         ContentStatus::Published => "Published",
         ContentStatus::Archived => "Archived",
     }
 }
 
+/// Filters for listing content; each one left as `None` matches everything
+#[derive(Debug, Clone, Default)]
+pub struct ContentFilter {
+    pub status: Option<ContentStatus>,
+    /// Matches co-authored content as well as content the user is primary author of
+    pub author_id: Option<Uuid>,
+    pub locale: Option<String>,
+}
+
+/// One page of listed content and how many items match the filter across all pages
+#[derive(Debug)]
+pub struct ContentPage {
+    pub items: Vec<Content>,
+    pub total: u64,
+}
+
+const CONTENT_FILTER: &str = "tenant_id = $1
+    AND ($2::text IS NULL OR lower(status) = lower($2))
+    AND ($3::uuid IS NULL OR author_id = $3
+         OR EXISTS (SELECT 1 FROM content_authors ca WHERE ca.content_id = content.id AND ca.user_id = $3))
+    AND ($4::text IS NULL OR locale = $4)";
+
+/// List a page of a tenant's content, newest first. The total comes back with the
+/// rows through a window count, so a page costs one round trip; only a page past
+/// the end, which has no rows to carry it, needs a separate count.
+pub async fn query_content_page(
+    client: &impl GenericClient,
+    tenant_id: &TenantId,
+    filter: &ContentFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<ContentPage, PgError> {
+    let status = filter.status.as_ref().map(content_status_to_string);
+    let params: [&(dyn tokio_postgres::types::ToSql + Sync); 4] =
+        [tenant_id.as_uuid(), &status, &filter.author_id, &filter.locale];
+
+    let query = format!(
+        "SELECT *, count(*) OVER () AS total_count FROM content WHERE {}
+         ORDER BY created_at DESC LIMIT $5 OFFSET $6",
+        CONTENT_FILTER
+    );
+    let page_params: [&(dyn tokio_postgres::types::ToSql + Sync); 6] =
+        [params[0], params[1], params[2], params[3], &limit, &offset];
+    let rows = client.query(query.as_str(), &page_params).await?;
+
+    let total = match rows.first() {
+        Some(row) => row.try_get::<_, i64>("total_count")?,
+        None if offset > 0 => client
+            .query_one(format!("SELECT COUNT(*) FROM content WHERE {}", CONTENT_FILTER).as_str(), &params)
+            .await?
+            .try_get(0)?,
+        None => 0,
+    };
+    let items = rows.iter().map(row_to_content).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ContentPage { items, total: total as u64 })
+}
+
+/// Posts considered per related-posts lookup, the best matches by the database's reckoning
+const RELATED_CANDIDATE_LIMIT: i64 = 200;
+
 /// Content management service
 #[derive(Clone)]
 pub struct ContentService {
@@ -49,7 +164,7 @@ impl ContentService {
         Self { db }
     }
 
-    /// Create new content
+    /// Create new content in `locale`; it starts its own translation group
     pub async fn create_content(
         &self,
         tenant_id: &TenantId,
@@ -57,16 +172,116 @@ impl ContentService {
         title: String,
         slug: String,
         body: String,
+        locale: &str,
     ) -> Result<Content> {
         let content_id = Uuid::new_v4();
+        self.insert_content(tenant_id, author_id, content_id, content_id, title, slug, body, locale).await
+    }
+
+    /// Create a translation of existing content in another locale
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_translation(
+        &self,
+        tenant_id: &TenantId,
+        author_id: &UserId,
+        source_content_id: Uuid,
+        title: String,
+        slug: String,
+        body: String,
+        locale: &str,
+    ) -> Result<Option<Content>, ContentTranslationError> {
+        let Some(source) = self.get_content(tenant_id, source_content_id).await? else {
+            return Ok(None);
+        };
+
+        let variants = self.get_translations(tenant_id, source_content_id).await?;
+        if source.locale == locale || variants.iter().any(|variant| variant.locale == locale) {
+            return Err(ContentTranslationError::LocaleExists(locale.to_string()));
+        }
+
+        let content = self
+            .insert_content(tenant_id, author_id, Uuid::new_v4(), source.translation_group_id, title, slug, body, locale)
+            .await?;
+        Ok(Some(content))
+    }
+
+    /// Other-locale variants of a piece of content
+    pub async fn get_translations(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+    ) -> Result<Vec<Content>> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let query = r#"
+            SELECT sibling.* FROM content original
+            JOIN content sibling ON sibling.translation_group_id = original.translation_group_id
+                AND sibling.tenant_id = original.tenant_id
+            WHERE original.id = $1 AND original.tenant_id = $2 AND sibling.id <> original.id
+            ORDER BY sibling.locale
+            "#;
+        let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&content_id, tenant_id.as_uuid()];
+
+        let rows = client.query(query, &params).await?;
+        let content: Result<Vec<Content>, _> = rows.iter().map(row_to_content).collect();
+
+        Ok(content?)
+    }
+
+    /// Resolve the published variant to serve for a public path such as `/de-DE/my-post`.
+    /// The locale comes from the URL prefix, then `Accept-Language`, then `default_locale`.
+    pub async fn resolve_public_content(
+        &self,
+        tenant_id: &TenantId,
+        path: &str,
+        accept_language: Option<&str>,
+        default_locale: &str,
+    ) -> Result<Option<Content>> {
+        let (path_locale, rest) = locale::split_locale_prefix(path);
+        let slug = rest.trim_matches('/');
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        // Every published variant in the group of any post with this slug
+        let query = r#"
+            SELECT variant.* FROM content variant
+            WHERE variant.tenant_id = $1 AND lower(variant.status) = 'published'
+              AND variant.translation_group_id IN (
+                  SELECT translation_group_id FROM content WHERE tenant_id = $1 AND slug = $2
+              )
+            "#;
+        let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![tenant_id.as_uuid(), &slug];
+
+        let rows = client.query(query, &params).await?;
+        let variants: Vec<Content> = rows.iter().map(row_to_content).collect::<Result<_, _>>()?;
+
+        let mut preferred: Vec<String> = path_locale.map(str::to_string).into_iter().collect();
+        if path_locale.is_none() {
+            preferred.extend(accept_language.map(locale::accepted_languages).unwrap_or_default());
+        }
+
+        Ok(pick_variant(&variants, &preferred, default_locale).cloned())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_content(
+        &self,
+        tenant_id: &TenantId,
+        author_id: &UserId,
+        content_id: Uuid,
+        translation_group_id: Uuid,
+        title: String,
+        slug: String,
+        body: String,
+        locale: &str,
+    ) -> Result<Content> {
         let now = chrono::Utc::now();
 
         // Get database connection
-        let client = self.db.get().await?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         let query = r#"
-            INSERT INTO content (id, tenant_id, title, slug, body, status, author_id, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            INSERT INTO content (id, tenant_id, title, slug, body, status, author_id, locale, translation_group_id, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
             RETURNING *
             "#;
 
@@ -79,6 +294,8 @@ impl ContentService {
             &body,
             &status_str,
             author_id.as_uuid(),
+            &locale,
+            &translation_group_id,
             &now,
             &now,
         ];
@@ -95,7 +312,7 @@ impl ContentService {
         tenant_id: &TenantId,
         content_id: Uuid,
     ) -> Result<Option<Content>> {
-        let client = self.db.get().await?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         let query = "SELECT * FROM content WHERE id = $1 AND tenant_id = $2";
         let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&content_id, tenant_id.as_uuid()];
@@ -119,13 +336,15 @@ impl ContentService {
         body: Option<String>,
     ) -> Result<Option<Content>> {
         let now = chrono::Utc::now();
-        let client = self.db.get().await?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         let query = r#"
             UPDATE content 
             SET title = COALESCE($3, title),
                 slug = COALESCE($4, slug),
                 body = COALESCE($5, body),
+                -- An approval covers the reviewed text only
+                review_decision = CASE WHEN review_decision = 'approved' THEN NULL ELSE review_decision END,
                 updated_at = $6
             WHERE id = $1 AND tenant_id = $2
             RETURNING *
@@ -153,19 +372,20 @@ impl ContentService {
         }
     }
 
-    /// Publish content
+    /// Publish content. Only approved content can be published.
     pub async fn publish_content(
         &self,
         tenant_id: &TenantId,
         content_id: Uuid,
-    ) -> Result<Option<Content>> {
+    ) -> Result<Option<Content>, ContentReviewError> {
         let now = chrono::Utc::now();
-        let client = self.db.get().await?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
+        // Conditional so a concurrent edit clearing the approval wins
         let query = r#"
             UPDATE content 
-            SET status = $3, published_at = $4, updated_at = $5
-            WHERE id = $1 AND tenant_id = $2
+            SET status = $3, published_at = $4, scheduled_publish_at = NULL, updated_at = $5
+            WHERE id = $1 AND tenant_id = $2 AND (review_decision = 'approved' OR status = $3)
             RETURNING *
             "#;
 
@@ -178,33 +398,226 @@ impl ContentService {
             &now,
         ];
 
-        match client.query_opt(query, &params).await? {
-            Some(row) => {
-                let content = row_to_content(&row)?;
-                Ok(Some(content))
-            }
-            None => Ok(None),
+        match client.query_opt(query, &params).await.context("Failed to publish content")? {
+            Some(row) => Ok(Some(row_to_content(&row).context("Failed to read content")?)),
+            None => match self.get_content(tenant_id, content_id).await? {
+                Some(content) => {
+                    ensure_publishable(&content.status, content.review_decision)?;
+                    Ok(Some(content))
+                }
+                None => Ok(None),
+            },
         }
     }
 
-    /// List content for tenant
+    /// Schedule content to be published at `publish_at` (UTC), or with `None` cancel
+    /// its schedule. Scheduled content is still only published once approved.
+    pub async fn schedule_publish(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        publish_at: Option<DateTime<Utc>>,
+    ) -> Result<Content, ContentReviewError> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        // Status casing differs between writers, as in the review migration
+        let row = client
+            .query_opt(
+                "UPDATE content SET scheduled_publish_at = $3, updated_at = NOW()
+                 WHERE id = $1 AND tenant_id = $2 AND lower(status) <> 'published'
+                 RETURNING *",
+                &[&content_id, tenant_id.as_uuid(), &publish_at],
+            )
+            .await
+            .context("Failed to schedule content")?;
+
+        match row {
+            Some(row) => Ok(row_to_content(&row).context("Failed to read content")?),
+            None => match self.get_content(tenant_id, content_id).await? {
+                Some(_) => Err(ContentReviewError::AlreadyPublished),
+                None => Err(ContentReviewError::NotFound),
+            },
+        }
+    }
+
+    /// Publish approved content whose scheduled time has come, across all tenants.
+    /// Returns how many items were published.
+    pub async fn publish_due(&self, now: DateTime<Utc>) -> Result<u64> {
+        let client = self.db.get().await
+            .context("Failed to get database connection")?;
+
+        // Runs across tenants, so it goes through the owner-run publish_scheduled_content()
+        let published = content_status_to_string(&ContentStatus::Published);
+        let count: i32 = client
+            .query_one("SELECT publish_scheduled_content($1, $2)", &[&now, &published])
+            .await
+            .context("Failed to publish scheduled content")?
+            .get(0);
+
+        Ok(count as u64)
+    }
+
+    /// Move content through review: submit a draft, or approve (publishing it) or
+    /// reject pending content. Approving and rejecting record the reviewer.
+    ///
+    /// Approved content scheduled for later stays a draft until its time comes.
+    pub async fn review_content(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        reviewer_id: Uuid,
+        action: ReviewAction,
+        comment: Option<String>,
+    ) -> Result<Content, ContentReviewError> {
+        let comment = comment.map(|comment| comment.trim().to_string()).filter(|comment| !comment.is_empty());
+        if action == ReviewAction::Reject && comment.is_none() {
+            return Err(ContentReviewError::CommentRequired);
+        }
+
+        let mut client = tenant_client(&self.db, tenant_id).await?;
+        let transaction = client.transaction().await
+            .context("Failed to start transaction")?;
+
+        let status: String = transaction
+            .query_opt(
+                "SELECT status FROM content WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+                &[&content_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to read content")?
+            .ok_or(ContentReviewError::NotFound)?
+            .get(0);
+        let (next_status, decision) = review_transition(&status_from_string(&status), action)?;
+
+        let now = Utc::now();
+        let status_str = content_status_to_string(&next_status);
+        let decision_str = decision.map(|decision| decision.as_str());
+        let draft_str = content_status_to_string(&ContentStatus::Draft);
+        let row = if action == ReviewAction::Submit {
+            // A fresh submission starts a new review
+            transaction
+                .query_one(
+                    "UPDATE content SET status = $3, review_decision = NULL, review_comment = NULL, updated_at = $4
+                     WHERE id = $1 AND tenant_id = $2
+                     RETURNING *",
+                    &[&content_id, tenant_id.as_uuid(), &status_str, &now],
+                )
+                .await
+        } else {
+            transaction
+                .query_one(
+                    "UPDATE content
+                     SET status = CASE WHEN $4 = 'approved' AND scheduled_publish_at > $6 THEN $8 ELSE $3 END,
+                         review_decision = $4, reviewed_by = $5, reviewed_at = $6, review_comment = $7,
+                         published_at = CASE WHEN $4 = 'approved' AND COALESCE(scheduled_publish_at <= $6, true)
+                                             THEN $6 ELSE published_at END,
+                         scheduled_publish_at = CASE WHEN $4 = 'approved' AND scheduled_publish_at <= $6
+                                                     THEN NULL ELSE scheduled_publish_at END,
+                         updated_at = $6
+                     WHERE id = $1 AND tenant_id = $2
+                     RETURNING *",
+                    &[&content_id, tenant_id.as_uuid(), &status_str, &decision_str, &reviewer_id, &now, &comment, &draft_str],
+                )
+                .await
+        }
+        .context("Failed to update review")?;
+        transaction.commit().await
+            .context("Failed to commit review")?;
+
+        Ok(row_to_content(&row).context("Failed to read content")?)
+    }
+
+    /// Publish or unpublish (back to draft) many content items in one transaction.
+    /// With `strict`, an unknown id leaves every item untouched.
+    pub async fn bulk_set_published(
+        &self,
+        tenant_id: &TenantId,
+        request: &BulkPublishRequest,
+        publish: bool,
+        strict: bool,
+    ) -> Result<BulkPublishReport> {
+        let mut client = tenant_client(&self.db, tenant_id).await?;
+        let transaction = client.transaction().await?;
+
+        let draft = content_status_to_string(&ContentStatus::Draft);
+        let rows = if request.all_drafts {
+            transaction
+                .query(
+                    "SELECT id, status, review_decision FROM content WHERE tenant_id = $1 AND status = $2 ORDER BY created_at ASC FOR UPDATE",
+                    &[tenant_id.as_uuid(), &draft],
+                )
+                .await?
+        } else {
+            transaction
+                .query(
+                    "SELECT id, status, review_decision FROM content WHERE tenant_id = $1 AND id = ANY($2) FOR UPDATE",
+                    &[tenant_id.as_uuid(), &request.ids],
+                )
+                .await?
+        };
+
+        let mut prepared: Vec<(Uuid, Result<(), String>)> = rows
+            .iter()
+            .map(|row| {
+                let status = status_from_string(row.get("status"));
+                let decision = row.get::<_, Option<&str>>("review_decision").and_then(ReviewDecision::parse);
+                // Bulk publishing must not skip review either
+                let ready = if publish { ensure_publishable(&status, decision).map_err(|e| e.to_string()) } else { Ok(()) };
+                (row.get("id"), ready)
+            })
+            .collect();
+        if !request.all_drafts {
+            prepared.sort_by_key(|(id, _)| request.ids.iter().position(|requested| requested == id));
+        }
+
+        let requested = (!request.all_drafts).then_some(request.ids.as_slice());
+        let done = if publish { BulkItemStatus::Published } else { BulkItemStatus::Unpublished };
+        let plan = plan_bulk(requested, prepared, strict, done);
+
+        if plan.writes.is_empty() {
+            transaction.rollback().await?;
+            return Ok(plan.report);
+        }
+
+        let now = Utc::now();
+        let (status, published_at) = if publish {
+            (content_status_to_string(&ContentStatus::Published), Some(now))
+        } else {
+            (draft, None)
+        };
+        let ids: Vec<Uuid> = plan.writes.iter().map(|(id, _)| *id).collect();
+        transaction
+            .execute(
+                "UPDATE content SET status = $3, published_at = $4, scheduled_publish_at = NULL, updated_at = $5
+                 WHERE tenant_id = $1 AND id = ANY($2)",
+                &[tenant_id.as_uuid(), &ids, &status, &published_at, &now],
+            )
+            .await?;
+        transaction.commit().await?;
+
+        Ok(plan.report)
+    }
+
+    /// List content for tenant, optionally only one locale
     pub async fn list_content(
         &self,
         tenant_id: &TenantId,
+        locale: Option<&str>,
         limit: i64,
         offset: i64,
     ) -> Result<Vec<Content>> {
-        let client = self.db.get().await?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         let query = r#"
             SELECT * FROM content 
-            WHERE tenant_id = $1 
+            WHERE tenant_id = $1 AND ($2::text IS NULL OR locale = $2)
             ORDER BY created_at DESC 
-            LIMIT $2 OFFSET $3
+            LIMIT $3 OFFSET $4
             "#;
 
         let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![
             tenant_id.as_uuid(),
+            &locale,
             &limit,
             &offset,
         ];
@@ -215,13 +628,175 @@ impl ContentService {
         Ok(content?)
     }
 
+    /// Published posts most related to `content_id` by shared tags and title, in its
+    /// locale and the same tenant, most related first. `None` if the post doesn't exist.
+    pub async fn related(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        limit: usize,
+    ) -> Result<Option<Vec<RelatedContent>>> {
+        let Some(current) = self.get_content(tenant_id, content_id).await? else {
+            return Ok(None);
+        };
+        if limit == 0 {
+            return Ok(Some(Vec::new()));
+        }
+
+        // The tag overlap and title trigram conditions use the indexes from
+        // 025_content_tags.sql; the candidates are ranked in Rust
+        let client = tenant_client(&self.db, tenant_id).await?;
+        let rows = client
+            .query(
+                "SELECT * FROM content
+                 WHERE tenant_id = $1 AND id <> $2 AND translation_group_id <> $3 AND locale = $4
+                   AND lower(status) = 'published'
+                   AND (tags && $5 OR title % $6)
+                 ORDER BY cardinality(ARRAY(SELECT unnest(tags) INTERSECT SELECT unnest($5::text[]))) DESC,
+                          similarity(title, $6) DESC
+                 LIMIT $7",
+                &[
+                    tenant_id.as_uuid(),
+                    &current.id,
+                    &current.translation_group_id,
+                    &current.locale,
+                    &current.tags,
+                    &current.title,
+                    &RELATED_CANDIDATE_LIMIT,
+                ],
+            )
+            .await
+            .context("Failed to find related content")?;
+        let candidates = rows.iter().map(row_to_content).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Some(rank_related(&current, candidates, limit)))
+    }
+
+    /// Bylines of several pieces of content at once, primary author first
+    pub async fn authors_for(
+        &self,
+        tenant_id: &TenantId,
+        content_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<ContentAuthor>>> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+        load_authors(&client, tenant_id, content_ids).await
+    }
+
+    /// Byline of one piece of content, primary author first
+    pub async fn list_authors(&self, tenant_id: &TenantId, content_id: Uuid) -> Result<Vec<ContentAuthor>> {
+        Ok(self.authors_for(tenant_id, &[content_id]).await?.remove(&content_id).unwrap_or_default())
+    }
+
+    /// Add a co-author at byline `position` (or last), moving them if already listed.
+    /// Returns the new byline.
+    pub async fn add_author(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        user_id: Uuid,
+        position: Option<usize>,
+    ) -> Result<Vec<ContentAuthor>, ContentAuthorError> {
+        let mut client = tenant_client(&self.db, tenant_id).await?;
+        let transaction = client.transaction().await
+            .context("Failed to start transaction")?;
+
+        // Locking the content row serializes concurrent byline changes
+        let primary: Uuid = transaction
+            .query_opt(
+                "SELECT author_id FROM content WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+                &[&content_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to read content")?
+            .ok_or(ContentAuthorError::ContentNotFound)?
+            .get(0);
+        if primary == user_id {
+            return Err(ContentAuthorError::PrimaryAuthor);
+        }
+
+        let in_tenant = transaction
+            .query_opt("SELECT 1 FROM users WHERE id = $1 AND tenant_id = $2", &[&user_id, tenant_id.as_uuid()])
+            .await
+            .context("Failed to look up user")?
+            .is_some();
+        if !in_tenant {
+            return Err(ContentAuthorError::UserNotFound);
+        }
+
+        let mut co_authors: Vec<Uuid> = transaction
+            .query(
+                "SELECT user_id FROM content_authors WHERE content_id = $1 AND tenant_id = $2 ORDER BY position",
+                &[&content_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to read co-authors")?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        insert_co_author(&mut co_authors, user_id, position);
+        write_co_authors(&transaction, tenant_id, content_id, &co_authors).await?;
+
+        let mut authors = load_authors(&transaction, tenant_id, &[content_id]).await?;
+        transaction.commit().await
+            .context("Failed to commit co-authors")?;
+
+        Ok(authors.remove(&content_id).unwrap_or_default())
+    }
+
+    /// Remove a co-author; the others close up. Returns the new byline.
+    pub async fn remove_author(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<ContentAuthor>, ContentAuthorError> {
+        let mut client = tenant_client(&self.db, tenant_id).await?;
+        let transaction = client.transaction().await
+            .context("Failed to start transaction")?;
+
+        let primary: Uuid = transaction
+            .query_opt(
+                "SELECT author_id FROM content WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+                &[&content_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to read content")?
+            .ok_or(ContentAuthorError::ContentNotFound)?
+            .get(0);
+        if primary == user_id {
+            return Err(ContentAuthorError::PrimaryAuthor);
+        }
+
+        let mut co_authors: Vec<Uuid> = transaction
+            .query(
+                "SELECT user_id FROM content_authors WHERE content_id = $1 AND tenant_id = $2 ORDER BY position",
+                &[&content_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to read co-authors")?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        if !co_authors.contains(&user_id) {
+            return Err(ContentAuthorError::UserNotFound);
+        }
+        co_authors.retain(|id| *id != user_id);
+        write_co_authors(&transaction, tenant_id, content_id, &co_authors).await?;
+
+        let mut authors = load_authors(&transaction, tenant_id, &[content_id]).await?;
+        transaction.commit().await
+            .context("Failed to commit co-authors")?;
+
+        Ok(authors.remove(&content_id).unwrap_or_default())
+    }
+
     /// Delete content
     pub async fn delete_content(
         &self,
         tenant_id: &TenantId,
         content_id: Uuid,
     ) -> Result<bool> {
-        let client = self.db.get().await?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         let query = "DELETE FROM content WHERE id = $1 AND tenant_id = $2";
         let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&content_id, tenant_id.as_uuid()];
@@ -231,3 +806,304 @@ impl ContentService {
         Ok(rows_affected > 0)
     }
 }
+
+/// Give new content its co-authors in byline order, on the caller's connection so they
+/// are written together with the content. Each must be a user of the tenant other
+/// than the primary author; repeats are dropped.
+pub(crate) async fn write_new_co_authors(
+    client: &impl GenericClient,
+    tenant_id: &TenantId,
+    content_id: Uuid,
+    primary: Uuid,
+    co_authors: &[Uuid],
+) -> Result<(), ContentAuthorError> {
+    let mut byline: Vec<Uuid> = Vec::new();
+    for user_id in co_authors {
+        if !byline.contains(user_id) {
+            byline.push(*user_id);
+        }
+    }
+    if byline.contains(&primary) {
+        return Err(ContentAuthorError::PrimaryAuthor);
+    }
+
+    let found: i64 = client
+        .query_one(
+            "SELECT COUNT(*) FROM users WHERE id = ANY($1) AND tenant_id = $2",
+            &[&byline, tenant_id.as_uuid()],
+        )
+        .await
+        .context("Failed to look up co-authors")?
+        .get(0);
+    if found as usize != byline.len() {
+        return Err(ContentAuthorError::UserNotFound);
+    }
+
+    write_co_authors(client, tenant_id, content_id, &byline).await?;
+    Ok(())
+}
+
+/// Replace a content item's co-authors with `co_authors`, numbered from 1 in order
+async fn write_co_authors(
+    client: &impl GenericClient,
+    tenant_id: &TenantId,
+    content_id: Uuid,
+    co_authors: &[Uuid],
+) -> Result<()> {
+    client
+        .execute("DELETE FROM content_authors WHERE content_id = $1 AND tenant_id = $2", &[&content_id, tenant_id.as_uuid()])
+        .await
+        .context("Failed to clear co-authors")?;
+    client
+        .execute(
+            "INSERT INTO content_authors (content_id, user_id, tenant_id, position)
+             SELECT $1, author.user_id, $2, author.position::int
+             FROM unnest($3::uuid[]) WITH ORDINALITY AS author(user_id, position)",
+            &[&content_id, tenant_id.as_uuid(), &co_authors],
+        )
+        .await
+        .context("Failed to write co-authors")?;
+    Ok(())
+}
+
+/// Primary author (position 0) plus co-authors of each content item, in byline order
+async fn load_authors(
+    client: &impl GenericClient,
+    tenant_id: &TenantId,
+    content_ids: &[Uuid],
+) -> Result<HashMap<Uuid, Vec<ContentAuthor>>> {
+    let rows = client
+        .query(
+            r#"
+            SELECT c.id AS content_id, u.id AS user_id, u.first_name, u.last_name, 0 AS position, TRUE AS is_primary
+            FROM content c JOIN users u ON u.id = c.author_id
+            WHERE c.tenant_id = $1 AND c.id = ANY($2)
+            UNION ALL
+            SELECT ca.content_id, u.id, u.first_name, u.last_name, ca.position, FALSE
+            FROM content_authors ca JOIN users u ON u.id = ca.user_id
+            WHERE ca.tenant_id = $1 AND ca.content_id = ANY($2)
+            ORDER BY content_id, position
+            "#,
+            &[tenant_id.as_uuid(), &content_ids],
+        )
+        .await
+        .context("Failed to load content authors")?;
+
+    let mut authors: HashMap<Uuid, Vec<ContentAuthor>> = HashMap::new();
+    for row in rows {
+        authors.entry(row.get("content_id")).or_default().push(ContentAuthor {
+            user_id: row.get("user_id"),
+            first_name: row.get("first_name"),
+            last_name: row.get("last_name"),
+            position: row.get("position"),
+            is_primary: row.get("is_primary"),
+        });
+    }
+    Ok(authors)
+}
+
+/// Pick the variant for the first preferred locale that has one (exact tag, then same
+/// language), then the default locale, then the oldest variant
+pub fn pick_variant<'a>(variants: &'a [Content], preferred: &[String], default_locale: &str) -> Option<&'a Content> {
+    preferred
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(default_locale))
+        .find_map(|wanted| {
+            variants
+                .iter()
+                .find(|variant| variant.locale.eq_ignore_ascii_case(wanted))
+                .or_else(|| variants.iter().find(|variant| locale::same_language(&variant.locale, wanted)))
+        })
+        .or_else(|| variants.iter().min_by_key(|variant| variant.created_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TestApp;
+    use crate::types::UserRole;
+
+    fn variant(locale: &str, group: Uuid) -> Content {
+        let now = Utc::now();
+        Content {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            title: format!("Post ({})", locale),
+            slug: "my-post".to_string(),
+            body: String::new(),
+            status: ContentStatus::Published,
+            author_id: Uuid::new_v4(),
+            published_at: Some(now),
+            scheduled_publish_at: None,
+            locale: locale.to_string(),
+            translation_group_id: group,
+            tags: Vec::new(),
+            review_decision: Some(ReviewDecision::Approved),
+            reviewed_by: None,
+            reviewed_at: None,
+            review_comment: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_pick_variant_for_request() {
+        let group = Uuid::new_v4();
+        let variants = vec![variant("en-US", group), variant("de-DE", group)];
+        let accept = |header: &str| locale::accepted_languages(header);
+
+        // URL prefix (passed first) and Accept-Language
+        assert_eq!(pick_variant(&variants, &["de-DE".to_string()], "en-US").unwrap().locale, "de-DE");
+        assert_eq!(pick_variant(&variants, &accept("fr-FR, de;q=0.8"), "en-US").unwrap().locale, "de-DE");
+        // Nothing acceptable: default locale
+        assert_eq!(pick_variant(&variants, &accept("ja"), "en-US").unwrap().locale, "en-US");
+        assert!(pick_variant(&[], &accept("de"), "en-US").is_none());
+    }
+
+    #[test]
+    fn test_co_authors_ordered_by_position() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut co_authors = Vec::new();
+
+        insert_co_author(&mut co_authors, a, None);
+        insert_co_author(&mut co_authors, b, None);
+        assert_eq!(co_authors, vec![a, b]);
+
+        // Position 1 is directly after the primary author
+        insert_co_author(&mut co_authors, c, Some(1));
+        assert_eq!(co_authors, vec![c, a, b]);
+
+        // Re-adding moves rather than duplicates; out-of-range positions append
+        insert_co_author(&mut co_authors, c, Some(10));
+        assert_eq!(co_authors, vec![a, b, c]);
+        insert_co_author(&mut co_authors, b, Some(0));
+        assert_eq!(co_authors, vec![b, a, c]);
+    }
+
+    #[tokio::test]
+    async fn test_co_author_added_after_primary() {
+        let Some(app) = TestApp::start().await else {
+            return;
+        };
+        let tenant_id = app.tenant_a.id.clone();
+        let primary = app.tenant_a.admin.id;
+        let co_author = app.add_user(&tenant_id, UserRole::Editor).await.id;
+        let service = ContentService::new(app.state.db.postgres().clone());
+
+        let content = service
+            .create_content(&tenant_id, &UserId::from_uuid(primary), "Duet".into(), format!("duet-{}", Uuid::new_v4()), "".into(), "en-US")
+            .await
+            .expect("Failed to create content");
+
+        let byline = service.add_author(&tenant_id, content.id, co_author, None).await.expect("Failed to add co-author");
+        assert_eq!(byline.iter().map(|author| author.user_id).collect::<Vec<_>>(), vec![primary, co_author]);
+        assert_eq!(byline.iter().map(|author| author.position).collect::<Vec<_>>(), vec![0, 1]);
+        assert!(byline[0].is_primary && !byline[1].is_primary);
+
+        assert!(matches!(
+            service.add_author(&tenant_id, content.id, primary, None).await,
+            Err(ContentAuthorError::PrimaryAuthor)
+        ));
+        assert!(matches!(
+            service.add_author(&tenant_id, content.id, Uuid::new_v4(), None).await,
+            Err(ContentAuthorError::UserNotFound)
+        ));
+
+        let byline = service.remove_author(&tenant_id, content.id, co_author).await.expect("Failed to remove co-author");
+        assert_eq!(byline.len(), 1);
+        service.delete_content(&tenant_id, content.id).await.expect("Failed to clean up");
+    }
+
+    #[tokio::test]
+    async fn test_translation_created_and_listed_by_locale() {
+        let Some(app) = TestApp::start().await else {
+            return;
+        };
+        let tenant_id = app.tenant_a.id.clone();
+        let author_id = UserId::from_uuid(app.tenant_a.admin.id);
+        let service = ContentService::new(app.state.db.postgres().clone());
+
+        let slug = format!("post-{}", Uuid::new_v4());
+        let original = service
+            .create_content(&tenant_id, &author_id, "Hello".into(), slug.clone(), "Hi".into(), "en-US")
+            .await
+            .expect("Failed to create content");
+        let translation = service
+            .create_translation(&tenant_id, &author_id, original.id, "Hallo".into(), slug.clone(), "Hallo".into(), "de-DE")
+            .await
+            .expect("Failed to create translation")
+            .expect("Original content missing");
+
+        assert_eq!(translation.translation_group_id, original.id);
+        assert!(matches!(
+            service
+                .create_translation(&tenant_id, &author_id, original.id, "Hallo".into(), slug, "Hallo".into(), "de-DE")
+                .await,
+            Err(ContentTranslationError::LocaleExists(_))
+        ));
+
+        let german = service.list_content(&tenant_id, Some("de-DE"), 100, 0).await.expect("Failed to list content");
+        assert!(german.iter().any(|content| content.id == translation.id));
+        assert!(german.iter().all(|content| content.locale == "de-DE"));
+
+        let siblings = service.get_translations(&tenant_id, original.id).await.expect("Failed to get translations");
+        assert_eq!(siblings.iter().map(|content| content.id).collect::<Vec<_>>(), vec![translation.id]);
+    }
+
+    #[tokio::test]
+    async fn test_page_total_matches_separate_count() {
+        let Some(app) = TestApp::start().await else {
+            return;
+        };
+        let tenant_id = app.tenant_a.id.clone();
+        let author_id = app.tenant_a.admin.id;
+        let admin = app.admin_pool.get().await.unwrap();
+        let client = tenant_client(app.state.db.postgres(), &tenant_id).await.expect("Failed to get connection");
+        let service = ContentService::new(app.state.db.postgres().clone());
+
+        let mut created = Vec::new();
+        for locale in ["en-US", "en-US", "de-DE"] {
+            let content = service
+                .create_content(&tenant_id, &UserId::from_uuid(author_id), "Count".into(), format!("count-{}", Uuid::new_v4()), "".into(), locale)
+                .await
+                .expect("Failed to create content");
+            created.push(content.id);
+        }
+
+        let filter = ContentFilter {
+            status: Some(ContentStatus::Draft),
+            author_id: Some(author_id),
+            locale: Some("en-US".to_string()),
+        };
+        let separate: i64 = admin
+            .query_one(
+                "SELECT COUNT(*) FROM content WHERE tenant_id = $1 AND lower(status) = 'draft' AND locale = 'en-US'
+                 AND (author_id = $2 OR EXISTS (SELECT 1 FROM content_authors ca WHERE ca.content_id = content.id AND ca.user_id = $2))",
+                &[tenant_id.as_uuid(), &author_id],
+            )
+            .await
+            .expect("Failed to count content")
+            .get(0);
+        assert!(separate >= 2);
+
+        // The total covers every matching row, not just the page
+        let page = query_content_page(&client, &tenant_id, &filter, 1, 0).await.expect("Failed to list content");
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.total, separate as u64);
+        assert!(page.items.iter().all(|content| content.locale == "en-US"));
+
+        // Past the last page there are no rows, but the total is still known
+        let past_end = query_content_page(&client, &tenant_id, &filter, 10, separate).await.expect("Failed to list content");
+        assert!(past_end.items.is_empty());
+        assert_eq!(past_end.total, separate as u64);
+
+        let everything = query_content_page(&client, &tenant_id, &ContentFilter::default(), 1, 0).await.expect("Failed to list content");
+        assert!(everything.total > page.total);
+
+        for id in created {
+            service.delete_content(&tenant_id, id).await.expect("Failed to clean up");
+        }
+    }
+}
@@ -0,0 +1,418 @@
+use crate::database::postgres::tenant_client;
+use crate::services::notification::{enqueue_notification, CONTENT_COMMENT_ADDED};
+use crate::types::TenantId;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// Longest comment body accepted, in characters
+pub const MAX_COMMENT_CHARS: usize = 10_000;
+
+/// The text a comment refers to, as character offsets into the content body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommentAnchor {
+    pub start: usize,
+    pub end: usize,
+    /// The selected text when the comment was made, so the client can re-find it after edits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote: Option<String>,
+}
+
+/// A review comment on content
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentComment {
+    pub id: Uuid,
+    pub content_id: Uuid,
+    pub author_id: Uuid,
+    /// First comment of the thread this one replies to
+    pub parent_id: Option<Uuid>,
+    pub anchor: Option<CommentAnchor>,
+    pub body: String,
+    pub resolved: bool,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A new comment or reply
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewComment {
+    pub body: String,
+    pub anchor: Option<CommentAnchor>,
+    pub parent_id: Option<Uuid>,
+}
+
+/// Comment errors
+#[derive(Debug, thiserror::Error)]
+pub enum ContentCommentError {
+    #[error("Content not found")]
+    ContentNotFound,
+
+    #[error("Comment not found")]
+    NotFound,
+
+    #[error("Invalid comment: {0}")]
+    Invalid(String),
+
+    #[error("Only the comment's author can change it")]
+    NotAuthor,
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// Check a comment body and its anchor against the content body it annotates
+pub fn validate_comment(body: &str, anchor: Option<&CommentAnchor>, content_body: &str) -> Result<(), ContentCommentError> {
+    let length = body.trim().chars().count();
+    if length == 0 {
+        return Err(ContentCommentError::Invalid("body must not be empty".to_string()));
+    }
+    if length > MAX_COMMENT_CHARS {
+        return Err(ContentCommentError::Invalid(format!("body exceeds {} characters", MAX_COMMENT_CHARS)));
+    }
+    if let Some(anchor) = anchor {
+        if anchor.start >= anchor.end || anchor.end > content_body.chars().count() {
+            return Err(ContentCommentError::Invalid("anchor must select text within the content".to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Service for review comments. Every query is scoped to the tenant.
+pub struct ContentCommentService {
+    db: Pool,
+}
+
+impl ContentCommentService {
+    pub fn new(db: Pool) -> Self {
+        Self { db }
+    }
+
+    /// Comments on a piece of content, oldest first; resolved threads only if asked for
+    pub async fn list_comments(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        include_resolved: bool,
+    ) -> Result<Vec<ContentComment>, ContentCommentError> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let rows = client
+            .query(
+                "SELECT * FROM content_comments
+                 WHERE tenant_id = $1 AND content_id = $2 AND ($3 OR NOT resolved)
+                 ORDER BY created_at",
+                &[tenant_id.as_uuid(), &content_id, &include_resolved],
+            )
+            .await
+            .context("Failed to list comments")?;
+
+        Ok(rows.iter().map(row_to_comment).collect())
+    }
+
+    /// Add a comment or reply, and notify the content's author unless they wrote it
+    pub async fn create_comment(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        author_id: Uuid,
+        comment: NewComment,
+    ) -> Result<ContentComment, ContentCommentError> {
+        let mut client = tenant_client(&self.db, tenant_id).await?;
+        let transaction = client.transaction().await
+            .context("Failed to start transaction")?;
+
+        let content = transaction
+            .query_opt(
+                "SELECT author_id, title, body FROM content WHERE id = $1 AND tenant_id = $2",
+                &[&content_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to read content")?
+            .ok_or(ContentCommentError::ContentNotFound)?;
+        let content_author: Uuid = content.get("author_id");
+        let content_body: Option<String> = content.get("body");
+        validate_comment(&comment.body, comment.anchor.as_ref(), content_body.as_deref().unwrap_or_default())?;
+
+        // Replies join the parent's thread; threads are one level deep
+        let parent_id = match comment.parent_id {
+            Some(parent_id) => {
+                let thread: Uuid = transaction
+                    .query_opt(
+                        "SELECT COALESCE(parent_id, id) FROM content_comments WHERE id = $1 AND tenant_id = $2 AND content_id = $3",
+                        &[&parent_id, tenant_id.as_uuid(), &content_id],
+                    )
+                    .await
+                    .context("Failed to read parent comment")?
+                    .ok_or(ContentCommentError::NotFound)?
+                    .get(0);
+                Some(thread)
+            }
+            None => None,
+        };
+
+        let anchor = comment
+            .anchor
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .context("Failed to encode anchor")?;
+        let row = transaction
+            .query_one(
+                "INSERT INTO content_comments (tenant_id, content_id, author_id, parent_id, anchor, body)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 RETURNING *",
+                &[tenant_id.as_uuid(), &content_id, &author_id, &parent_id, &anchor, &comment.body.trim()],
+            )
+            .await
+            .context("Failed to create comment")?;
+        let created = row_to_comment(&row);
+
+        if content_author != author_id {
+            let title: String = content.get("title");
+            let payload = serde_json::json!({
+                "content_id": content_id,
+                "content_title": title,
+                "comment_id": created.id,
+                "commenter_id": author_id,
+                "excerpt": created.body.chars().take(200).collect::<String>(),
+            });
+            enqueue_notification(&transaction, tenant_id, content_author, CONTENT_COMMENT_ADDED, &payload).await?;
+        }
+
+        transaction.commit().await
+            .context("Failed to commit comment")?;
+        Ok(created)
+    }
+
+    /// Edit a comment's body; only its author may
+    pub async fn update_comment(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        comment_id: Uuid,
+        editor_id: Uuid,
+        body: String,
+    ) -> Result<ContentComment, ContentCommentError> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let existing = self.get_comment(&client, tenant_id, content_id, comment_id).await?;
+        if existing.author_id != editor_id {
+            return Err(ContentCommentError::NotAuthor);
+        }
+        // The anchor was checked when the comment was made; only the body changes
+        validate_comment(&body, None, "")?;
+
+        let row = client
+            .query_one(
+                "UPDATE content_comments SET body = $3, updated_at = NOW()
+                 WHERE id = $1 AND tenant_id = $2
+                 RETURNING *",
+                &[&comment_id, tenant_id.as_uuid(), &body.trim()],
+            )
+            .await
+            .context("Failed to update comment")?;
+
+        Ok(row_to_comment(&row))
+    }
+
+    /// Delete a comment (and its replies, if it starts a thread). `moderator`
+    /// lets admins remove other people's comments.
+    pub async fn delete_comment(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        comment_id: Uuid,
+        user_id: Uuid,
+        moderator: bool,
+    ) -> Result<(), ContentCommentError> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let existing = self.get_comment(&client, tenant_id, content_id, comment_id).await?;
+        if existing.author_id != user_id && !moderator {
+            return Err(ContentCommentError::NotAuthor);
+        }
+
+        client
+            .execute("DELETE FROM content_comments WHERE id = $1 AND tenant_id = $2", &[&comment_id, tenant_id.as_uuid()])
+            .await
+            .context("Failed to delete comment")?;
+        Ok(())
+    }
+
+    /// Resolve or reopen the thread `comment_id` belongs to. Returns the thread's comments.
+    pub async fn set_thread_resolved(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        comment_id: Uuid,
+        user_id: Uuid,
+        resolved: bool,
+    ) -> Result<Vec<ContentComment>, ContentCommentError> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let existing = self.get_comment(&client, tenant_id, content_id, comment_id).await?;
+        let thread_id = existing.parent_id.unwrap_or(existing.id);
+
+        let rows = client
+            .query(
+                "UPDATE content_comments
+                 SET resolved = $3,
+                     resolved_by = CASE WHEN $3::boolean THEN $4::uuid END,
+                     resolved_at = CASE WHEN $3::boolean THEN NOW() END,
+                     updated_at = NOW()
+                 WHERE tenant_id = $1 AND (id = $2 OR parent_id = $2)
+                 RETURNING *",
+                &[tenant_id.as_uuid(), &thread_id, &resolved, &user_id],
+            )
+            .await
+            .context("Failed to resolve thread")?;
+
+        let mut thread: Vec<ContentComment> = rows.iter().map(row_to_comment).collect();
+        thread.sort_by_key(|comment| comment.created_at);
+        Ok(thread)
+    }
+
+    async fn get_comment(
+        &self,
+        client: &deadpool_postgres::Client,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        comment_id: Uuid,
+    ) -> Result<ContentComment, ContentCommentError> {
+        client
+            .query_opt(
+                "SELECT * FROM content_comments WHERE id = $1 AND tenant_id = $2 AND content_id = $3",
+                &[&comment_id, tenant_id.as_uuid(), &content_id],
+            )
+            .await
+            .context("Failed to read comment")?
+            .map(|row| row_to_comment(&row))
+            .ok_or(ContentCommentError::NotFound)
+    }
+}
+
+/// Convert database row to ContentComment struct
+fn row_to_comment(row: &Row) -> ContentComment {
+    let anchor: Option<serde_json::Value> = row.get("anchor");
+    ContentComment {
+        id: row.get("id"),
+        content_id: row.get("content_id"),
+        author_id: row.get("author_id"),
+        parent_id: row.get("parent_id"),
+        anchor: anchor.and_then(|anchor| serde_json::from_value(anchor).ok()),
+        body: row.get("body"),
+        resolved: row.get("resolved"),
+        resolved_by: row.get("resolved_by"),
+        resolved_at: row.get("resolved_at"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::postgres::create_pool;
+
+    #[test]
+    fn test_anchor_must_select_text_in_content() {
+        let anchor = |start, end| CommentAnchor { start, end, quote: None };
+        let body = "It is a truth universally acknowledged";
+
+        assert!(validate_comment("Lovely opening", Some(&anchor(0, 10)), body).is_ok());
+        assert!(validate_comment("General note", None, body).is_ok());
+        assert!(matches!(validate_comment("  ", None, body), Err(ContentCommentError::Invalid(_))));
+        assert!(matches!(validate_comment("Empty", Some(&anchor(5, 5)), body), Err(ContentCommentError::Invalid(_))));
+        assert!(matches!(validate_comment("Past end", Some(&anchor(0, 100)), body), Err(ContentCommentError::Invalid(_))));
+    }
+
+    #[tokio::test]
+    async fn test_comment_created_resolved_and_isolated_by_tenant() {
+        let Ok(url) = std::env::var("QUILLSPACE_TEST_DATABASE_URL") else {
+            return;
+        };
+        let pool = create_pool(&url, &Default::default()).await.expect("Failed to connect to test database");
+        let client = pool.get().await.expect("Failed to get connection");
+        let tenant_id = TenantId::from_uuid(Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap());
+        let users: Vec<Uuid> = client
+            .query("SELECT id FROM users WHERE tenant_id = $1 ORDER BY email LIMIT 2", &[tenant_id.as_uuid()])
+            .await
+            .expect("Seed users missing")
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        let (author, reviewer) = (users[0], users[1]);
+        let content_id: Uuid = client
+            .query_one(
+                "INSERT INTO content (tenant_id, author_id, title, slug, body, status, locale, translation_group_id)
+                 VALUES ($1, $2, 'Chapter One', $3, 'It is a truth universally acknowledged', 'Draft', 'en-US', uuid_generate_v4())
+                 RETURNING id",
+                &[tenant_id.as_uuid(), &author, &format!("chapter-{}", Uuid::new_v4())],
+            )
+            .await
+            .expect("Failed to create content")
+            .get(0);
+        let service = ContentCommentService::new(pool.clone());
+
+        let comment = service
+            .create_comment(
+                &tenant_id,
+                content_id,
+                reviewer,
+                NewComment {
+                    body: "Strong opening line".to_string(),
+                    anchor: Some(CommentAnchor { start: 0, end: 16, quote: Some("It is a truth un".to_string()) }),
+                    parent_id: None,
+                },
+            )
+            .await
+            .expect("Failed to create comment");
+        let reply = service
+            .create_comment(
+                &tenant_id,
+                content_id,
+                author,
+                NewComment { body: "Thanks!".to_string(), anchor: None, parent_id: Some(comment.id) },
+            )
+            .await
+            .expect("Failed to reply");
+        assert_eq!(reply.parent_id, Some(comment.id));
+
+        // The reviewer's comment notified the author; the author's own reply did not
+        let notified: i64 = client
+            .query_one(
+                "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND kind = $2 AND payload->>'content_id' = $3",
+                &[&author, &CONTENT_COMMENT_ADDED, &content_id.to_string()],
+            )
+            .await
+            .expect("Failed to count notifications")
+            .get(0);
+        assert_eq!(notified, 1);
+
+        // Resolving the reply resolves the whole thread
+        let thread = service.set_thread_resolved(&tenant_id, content_id, reply.id, reviewer, true).await.expect("Failed to resolve");
+        assert_eq!(thread.len(), 2);
+        assert!(thread.iter().all(|comment| comment.resolved && comment.resolved_by == Some(reviewer)));
+        assert!(service.list_comments(&tenant_id, content_id, false).await.unwrap().is_empty());
+        assert_eq!(service.list_comments(&tenant_id, content_id, true).await.unwrap().len(), 2);
+
+        // Another tenant can neither see nor touch the comments
+        let other_tenant = TenantId::new();
+        assert!(service.list_comments(&other_tenant, content_id, true).await.unwrap().is_empty());
+        assert!(matches!(
+            service.set_thread_resolved(&other_tenant, content_id, comment.id, reviewer, false).await,
+            Err(ContentCommentError::NotFound)
+        ));
+        assert!(matches!(
+            service.delete_comment(&other_tenant, content_id, comment.id, reviewer, true).await,
+            Err(ContentCommentError::NotFound)
+        ));
+
+        client.execute("DELETE FROM content WHERE id = $1", &[&content_id]).await.expect("Failed to clean up");
+    }
+}
@@ -0,0 +1,144 @@
+//! Word-level diffs between two versions of a text, for comparing content revisions.
+//!
+//! Text is split into words and the whitespace between them, and the two token lists
+//! are compared with Myers' algorithm. The common start and end of the texts are
+//! skipped before comparing, so an edit in a long body costs little; a comparison that
+//! still runs past [`DIFF_TIMEOUT`] settles for a coarser, but still correct, diff.
+//!
+//! All text in the output is HTML-escaped, so clients can insert it into markup as is.
+
+use minijinja::HtmlEscape;
+use serde::Serialize;
+use similar::{Algorithm, DiffTag, TextDiff};
+use std::time::Duration;
+
+/// Longest a single comparison may take before falling back to a coarser diff
+pub const DIFF_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A run of text that is the same, added, removed or changed between the versions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum DiffSegment {
+    Equal { text: String },
+    Insert { text: String },
+    Delete { text: String },
+    /// Words of the old version replaced by other words in the new one
+    Replace { old: String, new: String },
+}
+
+/// Differences between two versions of a text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextDiffResult {
+    pub segments: Vec<DiffSegment>,
+    /// The new version with removed text in `<del>` and added text in `<ins>`
+    pub html: String,
+    pub words_added: usize,
+    pub words_removed: usize,
+}
+
+impl TextDiffResult {
+    pub fn is_unchanged(&self) -> bool {
+        self.segments.iter().all(|segment| matches!(segment, DiffSegment::Equal { .. }))
+    }
+}
+
+/// Compare `old` and `new` word by word
+pub fn diff_words(old: &str, new: &str) -> TextDiffResult {
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Myers)
+        .timeout(DIFF_TIMEOUT)
+        .diff_words(old, new);
+    let (old_tokens, new_tokens) = (diff.old_slices(), diff.new_slices());
+
+    let mut result = TextDiffResult { segments: Vec::new(), html: String::new(), words_added: 0, words_removed: 0 };
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        let removed = &old_tokens[old_range];
+        let added = &new_tokens[new_range];
+        if tag != DiffTag::Equal {
+            result.words_removed += count_words(removed);
+            result.words_added += count_words(added);
+        }
+
+        let segment = match tag {
+            DiffTag::Equal => DiffSegment::Equal { text: escape(removed) },
+            DiffTag::Insert => DiffSegment::Insert { text: escape(added) },
+            DiffTag::Delete => DiffSegment::Delete { text: escape(removed) },
+            DiffTag::Replace => DiffSegment::Replace { old: escape(removed), new: escape(added) },
+        };
+        match &segment {
+            DiffSegment::Equal { text } => result.html.push_str(text),
+            DiffSegment::Insert { text } => result.html.push_str(&format!("<ins>{}</ins>", text)),
+            DiffSegment::Delete { text } => result.html.push_str(&format!("<del>{}</del>", text)),
+            DiffSegment::Replace { old, new } => result.html.push_str(&format!("<del>{}</del><ins>{}</ins>", old, new)),
+        }
+        result.segments.push(segment);
+    }
+    result
+}
+
+fn count_words(tokens: &[&str]) -> usize {
+    tokens.iter().filter(|token| !token.trim().is_empty()).count()
+}
+
+fn escape(tokens: &[&str]) -> String {
+    HtmlEscape(&tokens.concat()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(diff: &TextDiffResult) -> Vec<&DiffSegment> {
+        diff.segments.iter().filter(|segment| !matches!(segment, DiffSegment::Equal { .. })).collect()
+    }
+
+    #[test]
+    fn test_inserted_words_classified_as_insert() {
+        let diff = diff_words("It is a truth acknowledged", "It is a truth universally acknowledged");
+
+        assert_eq!(changes(&diff), vec![&DiffSegment::Insert { text: "universally ".to_string() }]);
+        assert_eq!((diff.words_added, diff.words_removed), (1, 0));
+        assert_eq!(diff.html, "It is a truth <ins>universally </ins>acknowledged");
+    }
+
+    #[test]
+    fn test_deleted_words_classified_as_delete() {
+        let diff = diff_words("a single man in possession of a good fortune", "a single man of a good fortune");
+
+        assert_eq!(changes(&diff), vec![&DiffSegment::Delete { text: "in possession ".to_string() }]);
+        assert_eq!((diff.words_added, diff.words_removed), (0, 2));
+    }
+
+    #[test]
+    fn test_changed_words_classified_as_replace() {
+        let diff = diff_words("must be in want of a wife", "must be in need of a wife");
+
+        assert_eq!(
+            changes(&diff),
+            vec![&DiffSegment::Replace { old: "want".to_string(), new: "need".to_string() }]
+        );
+        assert_eq!(diff.html, "must be in <del>want</del><ins>need</ins> of a wife");
+        assert!(diff_words("unchanged text", "unchanged text").is_unchanged());
+    }
+
+    #[test]
+    fn test_output_is_escaped() {
+        let diff = diff_words("<p>Hello</p>", "<p>Hello <script>alert(1)</script></p>");
+
+        assert!(!diff.html.contains("<script>"));
+        assert!(!diff.html.contains("<p>"));
+        assert!(diff.html.contains("<ins>"));
+    }
+
+    #[test]
+    fn test_large_bodies_with_small_edits() {
+        let paragraph = "It is a truth universally acknowledged, that a single man in possession of a good fortune. ";
+        let old = paragraph.repeat(2_000);
+        let new = format!("{}Chapter two begins. {}", paragraph.repeat(1_000), paragraph.repeat(1_000));
+
+        let diff = diff_words(&old, &new);
+        assert_eq!(changes(&diff), vec![&DiffSegment::Insert { text: "Chapter two begins. ".to_string() }]);
+        assert_eq!(diff.words_added, 3);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::auth::{casbin_auth::CasbinAuthorizer, jwt_helpers::AuthContext};
+use crate::types::ContentAuthor;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Permission needed to see the restricted content fields
+pub const READ_INTERNAL_ACTION: &str = "read_internal";
+
+/// Content fields left out of responses for requesters without `content:read_internal`
+pub const RESTRICTED_FIELDS: &[&str] = &["author_id", "reviewed_by", "reviewed_at", "review_decision", "review_comment"];
+
+/// Byline fields left out for the same requesters; author names stay visible
+pub const RESTRICTED_AUTHOR_FIELDS: &[&str] = &["user_id"];
+
+/// Which content fields a requester may see
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentFieldAccess {
+    Full,
+    Restricted,
+}
+
+impl ContentFieldAccess {
+    /// Field access for an authenticated request, from its role (or custom role) and token scopes
+    pub async fn for_context(authorizer: &CasbinAuthorizer, auth_context: &AuthContext) -> anyhow::Result<Self> {
+        let full = authorizer.enforce_context(auth_context, "content", READ_INTERNAL_ACTION).await?;
+        Ok(if full { Self::Full } else { Self::Restricted })
+    }
+
+    /// Serialize content, or a list of it, with the fields this access does not cover removed
+    pub fn to_value<T: Serialize>(self, content: &T) -> serde_json::Result<Value> {
+        let mut value = serde_json::to_value(content)?;
+        if self == Self::Restricted {
+            redact(&mut value);
+        }
+        Ok(value)
+    }
+
+    /// Serialize a byline on its own, with the author fields this access does not cover removed
+    pub fn authors_to_value(self, authors: &[ContentAuthor]) -> serde_json::Result<Value> {
+        let mut value = serde_json::to_value(authors)?;
+        if self == Self::Restricted {
+            redact_authors(&mut value);
+        }
+        Ok(value)
+    }
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        Value::Object(fields) => {
+            for field in RESTRICTED_FIELDS {
+                fields.remove(*field);
+            }
+            if let Some(authors) = fields.get_mut("authors") {
+                redact_authors(authors);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn redact_authors(authors: &mut Value) {
+    if let Value::Array(authors) = authors {
+        for author in authors.iter_mut().filter_map(Value::as_object_mut) {
+            for field in RESTRICTED_AUTHOR_FIELDS {
+                author.remove(*field);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Content, ContentStatus, ContentWithAuthors, ReviewDecision, TenantId, UserRole};
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn context(tenant_id: &TenantId, user_role: UserRole) -> AuthContext {
+        AuthContext {
+            tenant_id: tenant_id.clone(),
+            user_id: Uuid::new_v4(),
+            user_role,
+            scopes: None,
+            site_id: None,
+            session_id: None,
+        }
+    }
+
+    fn reviewed_content(tenant_id: &TenantId) -> ContentWithAuthors {
+        let id = Uuid::new_v4();
+        let author_id = Uuid::new_v4();
+        ContentWithAuthors {
+            content: Content {
+                id,
+                tenant_id: *tenant_id.as_uuid(),
+                title: "Launch notes".to_string(),
+                slug: "launch-notes".to_string(),
+                body: "Body".to_string(),
+                status: ContentStatus::Draft,
+                author_id,
+                published_at: None,
+                scheduled_publish_at: None,
+                locale: "en-US".to_string(),
+                translation_group_id: id,
+                tags: Vec::new(),
+                review_decision: Some(ReviewDecision::Rejected),
+                reviewed_by: Some(Uuid::new_v4()),
+                reviewed_at: Some(Utc::now()),
+                review_comment: Some("Needs sources".to_string()),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            authors: vec![ContentAuthor {
+                user_id: author_id,
+                first_name: "Ada".to_string(),
+                last_name: "Lovelace".to_string(),
+                position: 0,
+                is_primary: true,
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_viewer_does_not_see_restricted_fields() {
+        let authorizer = CasbinAuthorizer::new().await.expect("Failed to create Casbin authorizer");
+        let tenant_id = TenantId::new();
+        let content = reviewed_content(&tenant_id);
+
+        let viewer = ContentFieldAccess::for_context(&authorizer, &context(&tenant_id, UserRole::Viewer)).await.unwrap();
+        let editor = ContentFieldAccess::for_context(&authorizer, &context(&tenant_id, UserRole::Editor)).await.unwrap();
+        assert_eq!(viewer, ContentFieldAccess::Restricted);
+        assert_eq!(editor, ContentFieldAccess::Full);
+
+        // Detail and list responses are filtered the same way
+        let detail = viewer.to_value(&content).unwrap();
+        let list = viewer.to_value(&vec![content.clone()]).unwrap();
+        for item in [&detail, &list[0]] {
+            for field in RESTRICTED_FIELDS {
+                assert!(item.get(*field).is_none(), "viewer saw {}", field);
+            }
+            assert_eq!(item["title"], "Launch notes");
+            assert_eq!(item["authors"][0]["first_name"], "Ada");
+            assert!(item["authors"][0].get("user_id").is_none());
+        }
+
+        let full = editor.to_value(&content).unwrap();
+        for field in RESTRICTED_FIELDS {
+            assert!(full.get(*field).is_some(), "editor missed {}", field);
+        }
+        assert_eq!(full["review_comment"], "Needs sources");
+        assert_eq!(full["authors"][0]["user_id"], content.content.author_id.to_string());
+
+        // A byline listed on its own too
+        let byline = viewer.authors_to_value(&content.authors).unwrap();
+        assert_eq!(byline[0]["last_name"], "Lovelace");
+        assert!(byline[0].get("user_id").is_none());
+        assert!(editor.authors_to_value(&content.authors).unwrap()[0].get("user_id").is_some());
+    }
+}
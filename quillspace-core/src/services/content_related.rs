@@ -0,0 +1,167 @@
+use crate::types::Content;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Most tags a piece of content keeps; later ones are dropped
+pub const MAX_TAGS: usize = 20;
+
+/// Longest tag kept, in characters
+pub const MAX_TAG_LEN: usize = 50;
+
+/// Related posts returned when the request does not say
+pub const DEFAULT_RELATED_LIMIT: usize = 5;
+
+/// Weight of a fully similar title. Below one, so a single shared tag always
+/// outranks a post that is only similar by title.
+const TITLE_WEIGHT: f64 = 0.5;
+
+/// Title words shorter than this ("a", "of", "on") say nothing about the topic
+const MIN_TITLE_WORD_LEN: usize = 3;
+
+/// Another post and how related it is; higher is more related
+#[derive(Debug, Clone, Serialize)]
+pub struct RelatedContent {
+    #[serde(flatten)]
+    pub content: Content,
+    pub score: f64,
+}
+
+/// Tags as stored: trimmed, lowercase, inner whitespace collapsed, without blanks or
+/// repeats, at most [`MAX_TAGS`] of at most [`MAX_TAG_LEN`] characters
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags.iter()
+        .map(|tag| tag.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase())
+        .map(|tag| tag.chars().take(MAX_TAG_LEN).collect::<String>())
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.clone()))
+        .take(MAX_TAGS)
+        .collect()
+}
+
+/// Overlap of the significant words of two titles, from 0 (none) to 1 (the same words)
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    let words = |title: &str| -> HashSet<String> {
+        title
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.chars().count() >= MIN_TITLE_WORD_LEN)
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// One point per shared tag plus up to [`TITLE_WEIGHT`] for title similarity
+pub fn relatedness(current: &Content, other: &Content) -> f64 {
+    let tags: HashSet<&str> = current.tags.iter().map(String::as_str).collect();
+    let shared = other.tags.iter().filter(|tag| tags.contains(tag.as_str())).count();
+    shared as f64 + TITLE_WEIGHT * title_similarity(&current.title, &other.title)
+}
+
+/// The `limit` candidates most related to `current`, most related first and newer
+/// first among equals. The current post and its translations are never included,
+/// nor are candidates with nothing in common with it.
+pub fn rank_related(current: &Content, candidates: Vec<Content>, limit: usize) -> Vec<RelatedContent> {
+    let mut related: Vec<RelatedContent> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.id != current.id && candidate.translation_group_id != current.translation_group_id)
+        .map(|candidate| RelatedContent { score: relatedness(current, &candidate), content: candidate })
+        .filter(|related| related.score > 0.0)
+        .collect();
+    related.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.content.published_at.cmp(&a.content.published_at))
+    });
+    related.truncate(limit);
+    related
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ContentStatus;
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    fn post(title: &str, tags: &[&str]) -> Content {
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        Content {
+            id,
+            tenant_id: Uuid::nil(),
+            title: title.to_string(),
+            slug: title.to_lowercase().replace(' ', "-"),
+            body: String::new(),
+            status: ContentStatus::Published,
+            author_id: Uuid::new_v4(),
+            published_at: Some(now),
+            scheduled_publish_at: None,
+            locale: "en-US".to_string(),
+            translation_group_id: id,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            review_decision: None,
+            reviewed_by: None,
+            reviewed_at: None,
+            review_comment: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_posts_sharing_tags_rank_above_unrelated_ones() {
+        let current = post("Writing a regency romance", &["regency", "romance", "craft"]);
+        let both_tags = post("Dialogue in period novels", &["regency", "romance"]);
+        let one_tag = post("Plotting with index cards", &["craft"]);
+        let title_only = post("Regency romance reading list", &[]);
+        let unrelated = post("Our new office", &["news"]);
+        let mut older_one_tag = post("Revising the first draft", &["craft"]);
+        older_one_tag.published_at = one_tag.published_at.map(|at| at - Duration::days(3));
+
+        let candidates = vec![
+            unrelated.clone(),
+            title_only.clone(),
+            older_one_tag.clone(),
+            one_tag.clone(),
+            both_tags.clone(),
+            current.clone(),
+        ];
+        let related = rank_related(&current, candidates, 10);
+
+        let ids: Vec<Uuid> = related.iter().map(|related| related.content.id).collect();
+        assert_eq!(ids, vec![both_tags.id, one_tag.id, older_one_tag.id, title_only.id]);
+        assert!(!ids.contains(&current.id));
+        assert!(!ids.contains(&unrelated.id));
+
+        assert_eq!(rank_related(&current, vec![both_tags.clone(), one_tag], 1).len(), 1);
+    }
+
+    #[test]
+    fn test_translations_of_the_current_post_excluded() {
+        let current = post("Writing a regency romance", &["regency"]);
+        let mut translation = post("Écrire une romance Régence", &["regency"]);
+        translation.translation_group_id = current.translation_group_id;
+
+        assert!(rank_related(&current, vec![translation, current.clone()], 5).is_empty());
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = ["  Regency ", "regency", "", "Historical   Fiction", &"x".repeat(80)]
+            .iter()
+            .map(|tag| tag.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            normalize_tags(&tags),
+            vec!["regency".to_string(), "historical fiction".to_string(), "x".repeat(MAX_TAG_LEN)]
+        );
+        let many: Vec<String> = (0..30).map(|i| format!("tag{}", i)).collect();
+        assert_eq!(normalize_tags(&many).len(), MAX_TAGS);
+    }
+}
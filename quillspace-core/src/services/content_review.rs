@@ -0,0 +1,110 @@
+use crate::types::{ContentStatus, ReviewDecision};
+
+/// A step in the review workflow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewAction {
+    Submit,
+    Approve,
+    Reject,
+}
+
+impl ReviewAction {
+    /// Casbin action on `content` required to take this step
+    pub fn permission(&self) -> &'static str {
+        match self {
+            ReviewAction::Submit => "submit",
+            ReviewAction::Approve | ReviewAction::Reject => "approve",
+        }
+    }
+}
+
+/// Review workflow errors
+#[derive(Debug, thiserror::Error)]
+pub enum ContentReviewError {
+    #[error("Content not found")]
+    NotFound,
+
+    #[error("Cannot {action:?} content that is {status:?}")]
+    InvalidTransition { status: ContentStatus, action: ReviewAction },
+
+    #[error("A comment is required when rejecting content")]
+    CommentRequired,
+
+    #[error("Content must be approved before it is published")]
+    NotApproved,
+
+    #[error("Content is already published")]
+    AlreadyPublished,
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// Status and decision after `action`, or why it is not allowed from `status`.
+///
+/// Drafts are submitted for review; approving publishes, rejecting returns the
+/// content to draft so the author can revise and resubmit.
+pub fn review_transition(
+    status: &ContentStatus,
+    action: ReviewAction,
+) -> Result<(ContentStatus, Option<ReviewDecision>), ContentReviewError> {
+    match (status, action) {
+        (ContentStatus::Draft, ReviewAction::Submit) => Ok((ContentStatus::PendingReview, None)),
+        (ContentStatus::PendingReview, ReviewAction::Approve) => {
+            Ok((ContentStatus::Published, Some(ReviewDecision::Approved)))
+        }
+        (ContentStatus::PendingReview, ReviewAction::Reject) => {
+            Ok((ContentStatus::Draft, Some(ReviewDecision::Rejected)))
+        }
+        (status, action) => Err(ContentReviewError::InvalidTransition { status: status.clone(), action }),
+    }
+}
+
+/// Whether content may be published directly. Only approved content can be, so
+/// publishing never skips review; content already published is left alone.
+pub fn ensure_publishable(status: &ContentStatus, decision: Option<ReviewDecision>) -> Result<(), ContentReviewError> {
+    match (status, decision) {
+        (ContentStatus::Published, _) | (_, Some(ReviewDecision::Approved)) => Ok(()),
+        _ => Err(ContentReviewError::NotApproved),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review_workflow_transitions() {
+        let (status, decision) = review_transition(&ContentStatus::Draft, ReviewAction::Submit).unwrap();
+        assert!(matches!(status, ContentStatus::PendingReview));
+        assert_eq!(decision, None);
+
+        let (status, decision) = review_transition(&status, ReviewAction::Reject).unwrap();
+        assert!(matches!(status, ContentStatus::Draft));
+        assert_eq!(decision, Some(ReviewDecision::Rejected));
+
+        let (status, _) = review_transition(&status, ReviewAction::Submit).unwrap();
+        let (status, decision) = review_transition(&status, ReviewAction::Approve).unwrap();
+        assert!(matches!(status, ContentStatus::Published));
+        assert_eq!(decision, Some(ReviewDecision::Approved));
+
+        // Drafts cannot be approved without going through review
+        assert!(matches!(
+            review_transition(&ContentStatus::Draft, ReviewAction::Approve),
+            Err(ContentReviewError::InvalidTransition { .. })
+        ));
+    }
+
+    #[test]
+    fn test_publish_blocked_until_approved() {
+        assert!(matches!(ensure_publishable(&ContentStatus::Draft, None), Err(ContentReviewError::NotApproved)));
+        assert!(matches!(ensure_publishable(&ContentStatus::PendingReview, None), Err(ContentReviewError::NotApproved)));
+        assert!(matches!(
+            ensure_publishable(&ContentStatus::Draft, Some(ReviewDecision::Rejected)),
+            Err(ContentReviewError::NotApproved)
+        ));
+
+        // Approved content taken back to draft can be republished without another review
+        assert!(ensure_publishable(&ContentStatus::Draft, Some(ReviewDecision::Approved)).is_ok());
+    }
+}
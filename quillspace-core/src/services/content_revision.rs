@@ -0,0 +1,187 @@
+use crate::database::postgres::tenant_transaction;
+use crate::services::content_diff::{diff_words, TextDiffResult};
+use crate::types::TenantId;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// A saved version of content's title and body. Revisions are recorded by the
+/// `record_content_revision` trigger from 026_content_revisions.sql.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentRevision {
+    pub id: Uuid,
+    pub content_id: Uuid,
+    /// 1 for the first version, counting up with each change
+    pub revision: i32,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What changed from revision `from` to revision `to`
+#[derive(Debug, Clone, Serialize)]
+pub struct RevisionDiff {
+    pub content_id: Uuid,
+    pub from: i32,
+    pub to: i32,
+    pub title: TextDiffResult,
+    pub body: TextDiffResult,
+    /// Neither the title nor the body differs, as when an edit reverted an earlier one
+    pub unchanged: bool,
+}
+
+impl RevisionDiff {
+    pub fn between(from: &ContentRevision, to: &ContentRevision) -> Self {
+        let title = diff_words(&from.title, &to.title);
+        let body = diff_words(&from.body, &to.body);
+        Self {
+            content_id: to.content_id,
+            from: from.revision,
+            to: to.revision,
+            unchanged: title.is_unchanged() && body.is_unchanged(),
+            title,
+            body,
+        }
+    }
+}
+
+/// Service for content revision history. Every query is scoped to the tenant.
+pub struct ContentRevisionService {
+    db: Pool,
+}
+
+impl ContentRevisionService {
+    pub fn new(db: Pool) -> Self {
+        Self { db }
+    }
+
+    /// Revisions of a piece of content, newest first
+    pub async fn list_revisions(&self, tenant_id: &TenantId, content_id: Uuid) -> Result<Vec<ContentRevision>> {
+        self.query(
+            tenant_id,
+            "SELECT * FROM content_revisions WHERE tenant_id = $1 AND content_id = $2 ORDER BY revision DESC",
+            &[tenant_id.as_uuid(), &content_id],
+        )
+        .await
+        .context("Failed to list content revisions")
+    }
+
+    /// The newest revision number, `None` for content without history
+    pub async fn latest_revision(&self, tenant_id: &TenantId, content_id: Uuid) -> Result<Option<i32>> {
+        let revisions = self
+            .query(
+                tenant_id,
+                "SELECT * FROM content_revisions WHERE tenant_id = $1 AND content_id = $2
+                 ORDER BY revision DESC LIMIT 1",
+                &[tenant_id.as_uuid(), &content_id],
+            )
+            .await
+            .context("Failed to load latest content revision")?;
+        Ok(revisions.first().map(|revision| revision.revision))
+    }
+
+    /// The diff between two revisions, or `None` if either does not exist
+    pub async fn diff_revisions(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        from: i32,
+        to: i32,
+    ) -> Result<Option<RevisionDiff>> {
+        let revisions = self
+            .query(
+                tenant_id,
+                "SELECT * FROM content_revisions
+                 WHERE tenant_id = $1 AND content_id = $2 AND revision IN ($3, $4)",
+                &[tenant_id.as_uuid(), &content_id, &from, &to],
+            )
+            .await
+            .context("Failed to load content revisions")?;
+
+        let find = |revision: i32| revisions.iter().find(|candidate| candidate.revision == revision);
+        let (Some(from), Some(to)) = (find(from), find(to)) else {
+            return Ok(None);
+        };
+        // Diffing a long body is CPU-bound, so keep it off the async workers
+        let (from, to) = (from.clone(), to.clone());
+        let diff = tokio::task::spawn_blocking(move || RevisionDiff::between(&from, &to))
+            .await
+            .context("Content diff task failed")?;
+        Ok(Some(diff))
+    }
+
+    /// Run a revision query with the tenant's RLS context set
+    async fn query(
+        &self,
+        tenant_id: &TenantId,
+        sql: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<ContentRevision>> {
+        let mut client = self.db.get().await.context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let rows = transaction.query(sql, params).await?;
+        transaction.commit().await.context("Failed to commit")?;
+        Ok(rows.iter().map(row_to_revision).collect())
+    }
+}
+
+fn row_to_revision(row: &Row) -> ContentRevision {
+    ContentRevision {
+        id: row.get("id"),
+        content_id: row.get("content_id"),
+        revision: row.get("revision"),
+        title: row.get("title"),
+        body: row.get("body"),
+        created_at: row.get("created_at"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::content_diff::DiffSegment;
+    use crate::test_harness::TestApp;
+
+    #[tokio::test]
+    async fn test_edits_recorded_as_revisions_and_diffed() {
+        let Some(app) = TestApp::start().await else { return };
+        let admin = app.admin_pool.get().await.expect("Failed to get connection");
+        let tenant_id = app.tenant_a.id.clone();
+        let content_id: Uuid = admin
+            .query_one(
+                "INSERT INTO content (tenant_id, author_id, title, slug, body, status, locale, translation_group_id)
+                 VALUES ($1, $2, 'Chapter One', 'chapter-one', 'It is a truth acknowledged', 'Draft', 'en-US', uuid_generate_v4())
+                 RETURNING id",
+                &[tenant_id.as_uuid(), &app.tenant_a.admin.id],
+            )
+            .await
+            .expect("Failed to create content")
+            .get(0);
+        for update in [
+            "UPDATE content SET status = 'Published' WHERE id = $1",
+            "UPDATE content SET body = 'It is a truth universally acknowledged' WHERE id = $1",
+            "UPDATE content SET body = 'It is a truth acknowledged' WHERE id = $1",
+        ] {
+            admin.execute(update, &[&content_id]).await.expect("Failed to edit");
+        }
+        let service = ContentRevisionService::new(app.state.db.postgres().clone());
+
+        // Publishing left the text alone, so only the edits made revisions
+        let revisions = service.list_revisions(&tenant_id, content_id).await.unwrap();
+        assert_eq!(revisions.iter().map(|revision| revision.revision).collect::<Vec<_>>(), vec![3, 2, 1]);
+
+        let diff = service.diff_revisions(&tenant_id, content_id, 1, 2).await.unwrap().expect("Revisions missing");
+        assert!(diff.title.is_unchanged());
+        assert!(!diff.unchanged);
+        assert!(diff.body.segments.contains(&DiffSegment::Insert { text: "universally ".to_string() }));
+        // The last edit reverted the first
+        assert!(service.diff_revisions(&tenant_id, content_id, 1, 3).await.unwrap().expect("Revisions missing").unchanged);
+
+        assert!(service.diff_revisions(&tenant_id, content_id, 1, 4).await.unwrap().is_none());
+        assert!(service.diff_revisions(&app.tenant_b.id, content_id, 1, 2).await.unwrap().is_none());
+    }
+}
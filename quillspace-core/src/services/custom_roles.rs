@@ -0,0 +1,425 @@
+use crate::database::postgres::tenant_transaction;
+use crate::auth::{Action, CasbinAuthorizer, Resource};
+use crate::types::TenantId;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tokio_postgres::{error::SqlState, Row};
+use tracing::info;
+use uuid::Uuid;
+
+/// Longest custom role name accepted
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Most users assigned in one request
+pub const MAX_ASSIGNMENT_BATCH: usize = 500;
+
+/// Names reserved by the built-in roles
+const BUILT_IN_ROLES: [&str; 3] = ["admin", "editor", "viewer"];
+
+/// A tenant-defined role and the `resource:action` permissions it grants
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CustomRole {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Define a new custom role
+#[derive(Debug, Deserialize)]
+pub struct CreateRoleRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// Custom role errors
+#[derive(Debug, thiserror::Error)]
+pub enum CustomRoleError {
+    #[error("Role not found")]
+    NotFound,
+
+    #[error("Invalid role name: {0}")]
+    InvalidName(&'static str),
+
+    #[error("A role named '{0}' already exists")]
+    NameTaken(String),
+
+    #[error("Unknown permission '{0}'; expected resource:action")]
+    UnknownPermission(String),
+
+    #[error("Assign at most {max} users at once, got {0}", max = MAX_ASSIGNMENT_BATCH)]
+    TooManyUsers(usize),
+
+    #[error("Users not found in tenant: {0:?}")]
+    UsersNotFound(Vec<Uuid>),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// Trim a role name and check it does not pass for a built-in role
+pub fn validate_role_name(name: &str) -> Result<String, CustomRoleError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(CustomRoleError::InvalidName("must be 1 to 100 characters"));
+    }
+    if BUILT_IN_ROLES.iter().any(|built_in| built_in.eq_ignore_ascii_case(name)) {
+        return Err(CustomRoleError::InvalidName("is a built-in role"));
+    }
+    Ok(name.to_string())
+}
+
+/// Check every permission names a known resource and action. Returns them
+/// deduplicated and sorted, ready to store.
+pub fn parse_permissions(permissions: &[String]) -> Result<Vec<(String, String)>, CustomRoleError> {
+    let mut parsed = BTreeSet::new();
+    for permission in permissions {
+        let (resource, action) = permission
+            .trim()
+            .split_once(':')
+            .and_then(|(resource, action)| Some((Resource::parse(resource)?, Action::parse(action)?)))
+            .ok_or_else(|| CustomRoleError::UnknownPermission(permission.clone()))?;
+        parsed.insert((resource.as_str().to_string(), action.as_str().to_string()));
+    }
+    Ok(parsed.into_iter().collect())
+}
+
+fn permission_strings(permissions: &[(String, String)]) -> Vec<String> {
+    permissions.iter().map(|(resource, action)| format!("{}:{}", resource, action)).collect()
+}
+
+/// Service managing a tenant's custom roles, kept in Postgres and mirrored into Casbin
+/// once each change is committed
+#[derive(Clone)]
+pub struct CustomRoleService {
+    db: Pool,
+    authorizer: Arc<CasbinAuthorizer>,
+}
+
+impl CustomRoleService {
+    pub fn new(db: Pool, authorizer: Arc<CasbinAuthorizer>) -> Self {
+        Self { db, authorizer }
+    }
+
+    /// Load every tenant's roles and assignments into the authorizer; run at startup
+    pub async fn load_into_authorizer(&self) -> anyhow::Result<()> {
+        let client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let rows = client
+            .query("SELECT role_id, tenant_id, permissions, user_ids FROM custom_role_grants()", &[])
+            .await
+            .context("Failed to load custom roles")?;
+
+        for row in &rows {
+            let role_id: Uuid = row.get("role_id");
+            let tenant_id: Uuid = row.get("tenant_id");
+            let stored: Vec<String> = row.get("permissions");
+            let user_ids: Vec<Uuid> = row.get("user_ids");
+
+            // Permissions stored by an older build may name actions this one dropped
+            let permissions: Vec<(String, String)> = stored
+                .iter()
+                .filter_map(|permission| parse_permissions(std::slice::from_ref(permission)).ok())
+                .flatten()
+                .collect();
+            self.authorizer.set_custom_role_permissions(&tenant_id, &role_id, &permissions).await?;
+            self.authorizer.assign_custom_role(&user_ids, Some(&role_id)).await?;
+        }
+
+        info!(roles = rows.len(), "Custom roles loaded");
+        Ok(())
+    }
+
+    /// The tenant's custom roles, by name
+    pub async fn list_roles(&self, tenant_id: &TenantId) -> Result<Vec<CustomRole>, CustomRoleError> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let rows = transaction
+            .query("SELECT * FROM custom_roles WHERE tenant_id = $1 ORDER BY name", &[tenant_id.as_uuid()])
+            .await
+            .context("Failed to list custom roles")?;
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+
+        Ok(rows.iter().map(row_to_role).collect())
+    }
+
+    /// Define a role; names are unique within the tenant
+    pub async fn create_role(&self, tenant_id: &TenantId, request: &CreateRoleRequest) -> Result<CustomRole, CustomRoleError> {
+        let name = validate_role_name(&request.name)?;
+        let permissions = parse_permissions(&request.permissions)?;
+        let description = request.description.as_deref().map(str::trim).filter(|d| !d.is_empty());
+
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let row = transaction
+            .query_one(
+                "INSERT INTO custom_roles (tenant_id, name, description, permissions)
+                 VALUES ($1, $2, $3, $4)
+                 RETURNING *",
+                &[tenant_id.as_uuid(), &name, &description, &permission_strings(&permissions)],
+            )
+            .await
+            .map_err(|e| {
+                if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+                    CustomRoleError::NameTaken(name.clone())
+                } else {
+                    anyhow::Error::new(e).context("Failed to create custom role").into()
+                }
+            })?;
+        let role = row_to_role(&row);
+
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+        self.authorizer.set_custom_role_permissions(tenant_id.as_uuid(), &role.id, &permissions).await?;
+        Ok(role)
+    }
+
+    /// Replace a role's permissions; its members are affected straight away
+    pub async fn set_permissions(
+        &self,
+        tenant_id: &TenantId,
+        role_id: Uuid,
+        permissions: &[String],
+    ) -> Result<CustomRole, CustomRoleError> {
+        let permissions = parse_permissions(permissions)?;
+
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let row = transaction
+            .query_opt(
+                "UPDATE custom_roles SET permissions = $3, updated_at = NOW()
+                 WHERE id = $1 AND tenant_id = $2
+                 RETURNING *",
+                &[&role_id, tenant_id.as_uuid(), &permission_strings(&permissions)],
+            )
+            .await
+            .context("Failed to update custom role")?
+            .ok_or(CustomRoleError::NotFound)?;
+
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+        self.authorizer.set_custom_role_permissions(tenant_id.as_uuid(), &role_id, &permissions).await?;
+        Ok(row_to_role(&row))
+    }
+
+    /// Delete a role; its members go back to their built-in roles
+    pub async fn delete_role(&self, tenant_id: &TenantId, role_id: Uuid) -> Result<(), CustomRoleError> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let deleted = transaction
+            .execute("DELETE FROM custom_roles WHERE id = $1 AND tenant_id = $2", &[&role_id, tenant_id.as_uuid()])
+            .await
+            .context("Failed to delete custom role")?;
+        if deleted == 0 {
+            return Err(CustomRoleError::NotFound);
+        }
+
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+        self.authorizer.remove_custom_role(&role_id).await?;
+        Ok(())
+    }
+
+    /// Assign a role to several users at once, or with `None` clear theirs. Every user
+    /// must be in the tenant, otherwise nobody is changed.
+    pub async fn assign_role(
+        &self,
+        tenant_id: &TenantId,
+        role_id: Option<Uuid>,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<Uuid>, CustomRoleError> {
+        if user_ids.len() > MAX_ASSIGNMENT_BATCH {
+            return Err(CustomRoleError::TooManyUsers(user_ids.len()));
+        }
+        let user_ids: Vec<Uuid> = user_ids.iter().copied().collect::<BTreeSet<_>>().into_iter().collect();
+
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        if let Some(role_id) = role_id {
+            transaction
+                .query_opt("SELECT id FROM custom_roles WHERE id = $1 AND tenant_id = $2", &[&role_id, tenant_id.as_uuid()])
+                .await
+                .context("Failed to look up custom role")?
+                .ok_or(CustomRoleError::NotFound)?;
+        }
+
+        let updated: Vec<Uuid> = transaction
+            .query(
+                "UPDATE users SET custom_role_id = $3, updated_at = NOW()
+                 WHERE tenant_id = $1 AND id = ANY($2)
+                 RETURNING id",
+                &[tenant_id.as_uuid(), &user_ids, &role_id],
+            )
+            .await
+            .context("Failed to assign custom role")?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        if updated.len() != user_ids.len() {
+            let missing = user_ids.into_iter().filter(|id| !updated.contains(id)).collect();
+            return Err(CustomRoleError::UsersNotFound(missing));
+        }
+
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+        self.authorizer.assign_custom_role(&user_ids, role_id.as_ref()).await?;
+        Ok(user_ids)
+    }
+}
+
+
+fn row_to_role(row: &Row) -> CustomRole {
+    CustomRole {
+        id: row.get("id"),
+        tenant_id: row.get("tenant_id"),
+        name: row.get("name"),
+        description: row.get("description"),
+        permissions: row.get("permissions"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt_helpers::AuthContext;
+    use crate::types::UserRole;
+    use axum::http::StatusCode;
+
+    fn context(tenant_id: &TenantId, user_id: Uuid, user_role: UserRole) -> AuthContext {
+        AuthContext {
+            tenant_id: tenant_id.clone(),
+            user_id,
+            user_role,
+            scopes: None,
+            site_id: None,
+            session_id: None,
+        }
+    }
+
+    #[test]
+    fn test_permissions_are_validated() {
+        let parsed = parse_permissions(&["content:write".to_string(), " content:read".to_string(), "content:write".to_string()])
+            .expect("Valid permissions rejected");
+        assert_eq!(permission_strings(&parsed), vec!["content:read", "content:write"]);
+
+        assert!(matches!(parse_permissions(&["content:fly".to_string()]), Err(CustomRoleError::UnknownPermission(_))));
+        assert!(matches!(parse_permissions(&["content".to_string()]), Err(CustomRoleError::UnknownPermission(_))));
+
+        assert_eq!(validate_role_name("  Contributor ").unwrap(), "Contributor");
+        assert!(validate_role_name("Editor").is_err());
+        assert!(validate_role_name(" ").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_contributor_can_create_but_not_publish() {
+        let authorizer = CasbinAuthorizer::new().await.expect("Failed to create Casbin authorizer");
+        let tenant_id = TenantId::new();
+        let contributor_role = Uuid::new_v4();
+        let permissions = parse_permissions(&["content:read".to_string(), "content:write".to_string()]).unwrap();
+        authorizer
+            .set_custom_role_permissions(tenant_id.as_uuid(), &contributor_role, &permissions)
+            .await
+            .expect("Failed to define role");
+
+        // Both users are editors by built-in role; only one is a contributor
+        let contributor = context(&tenant_id, Uuid::new_v4(), UserRole::Editor);
+        let editor = context(&tenant_id, Uuid::new_v4(), UserRole::Editor);
+        authorizer
+            .assign_custom_role(&[contributor.user_id], Some(&contributor_role))
+            .await
+            .expect("Failed to assign role");
+
+        assert_eq!(authorizer.require_context_permission(&contributor, "content", "write").await, Ok(()));
+        assert_eq!(
+            authorizer.require_context_permission(&contributor, "content", "publish").await,
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(authorizer.require_context_permission(&editor, "content", "publish").await, Ok(()));
+
+        // The role only applies in the tenant that defined it
+        let elsewhere = context(&TenantId::new(), contributor.user_id, UserRole::Editor);
+        assert_eq!(
+            authorizer.require_context_permission(&elsewhere, "content", "write").await,
+            Err(StatusCode::FORBIDDEN)
+        );
+
+        // Unassigned, the built-in role applies again
+        authorizer.assign_custom_role(&[contributor.user_id], None).await.expect("Failed to unassign role");
+        assert_eq!(authorizer.require_context_permission(&contributor, "content", "publish").await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_role_assignment_persists() {
+        let Ok(url) = std::env::var("QUILLSPACE_TEST_DATABASE_URL") else {
+            return;
+        };
+        let pool = crate::database::postgres::create_pool(&url, &Default::default())
+            .await
+            .expect("Failed to connect to test database");
+        let client = pool.get().await.expect("Failed to get connection");
+        let tenant_id = TenantId::from_uuid(Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap());
+        let user_id: Uuid = client
+            .query_one("SELECT id FROM users WHERE tenant_id = $1 ORDER BY email LIMIT 1", &[tenant_id.as_uuid()])
+            .await
+            .expect("Seed users missing")
+            .get(0);
+
+        let authorizer = Arc::new(CasbinAuthorizer::new().await.expect("Failed to create Casbin authorizer"));
+        let service = CustomRoleService::new(pool.clone(), authorizer);
+        let role = service
+            .create_role(
+                &tenant_id,
+                &CreateRoleRequest {
+                    name: format!("Contributor {}", Uuid::new_v4()),
+                    description: Some("Writes drafts".to_string()),
+                    permissions: vec!["content:write".to_string(), "content:read".to_string()],
+                },
+            )
+            .await
+            .expect("Failed to create role");
+        assert_eq!(role.permissions, vec!["content:read", "content:write"]);
+
+        // One unknown user leaves everyone unassigned
+        let missing = Uuid::new_v4();
+        let result = service.assign_role(&tenant_id, Some(role.id), &[user_id, missing]).await;
+        assert!(matches!(result, Err(CustomRoleError::UsersNotFound(ids)) if ids == vec![missing]));
+        service.assign_role(&tenant_id, Some(role.id), &[user_id]).await.expect("Failed to assign role");
+
+        // A fresh authorizer picks the assignment up from the database
+        let reloaded = Arc::new(CasbinAuthorizer::new().await.expect("Failed to create Casbin authorizer"));
+        CustomRoleService::new(pool.clone(), reloaded.clone())
+            .load_into_authorizer()
+            .await
+            .expect("Failed to load roles");
+        let member = context(&tenant_id, user_id, UserRole::Editor);
+        assert_eq!(reloaded.require_context_permission(&member, "content", "write").await, Ok(()));
+        assert_eq!(
+            reloaded.require_context_permission(&member, "content", "publish").await,
+            Err(StatusCode::FORBIDDEN)
+        );
+
+        service.delete_role(&tenant_id, role.id).await.expect("Failed to clean up");
+    }
+}
@@ -0,0 +1,105 @@
+//! Templates loaded from disk for theme development, enabled by `dev.enabled`.
+//!
+//! A template `name` is read from `<dir>/<name>.html` (category `page`) or
+//! `<dir>/<name>.txt` (category `text`). The file's modification time and size are
+//! checked on every load, so saving it is picked up by the next render without going
+//! through the API. Names without a file fall through to the database as usual.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::info;
+
+/// File extensions tried for a template name, with the category each implies
+const EXTENSIONS: &[(&str, &str)] = &[("html", "page"), ("txt", "text")];
+
+/// A template read from disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevTemplate {
+    pub html_source: String,
+    pub category: String,
+}
+
+#[derive(Debug, Clone)]
+struct LoadedFile {
+    modified: SystemTime,
+    len: u64,
+    template: DevTemplate,
+}
+
+pub struct DevTemplateLoader {
+    dir: PathBuf,
+    loaded: Mutex<HashMap<PathBuf, LoadedFile>>,
+}
+
+impl DevTemplateLoader {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The template's current contents, or `None` if there is no file for it. Names that
+    /// would leave the directory never match a file.
+    pub fn load(&self, name: &str) -> std::io::Result<Option<DevTemplate>> {
+        let Some(relative) = safe_relative_path(name) else {
+            return Ok(None);
+        };
+
+        for (extension, category) in EXTENSIONS {
+            let path = self.dir.join(&relative).with_extension(extension);
+            let metadata = match std::fs::metadata(&path) {
+                Ok(metadata) if metadata.is_file() => metadata,
+                Ok(_) => continue,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            let modified = metadata.modified()?;
+
+            let mut loaded = self.loaded.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(file) = loaded.get(&path) {
+                if file.modified == modified && file.len == metadata.len() {
+                    return Ok(Some(file.template.clone()));
+                }
+            }
+
+            let template = DevTemplate {
+                html_source: std::fs::read_to_string(&path)?,
+                category: category.to_string(),
+            };
+            info!(template = name, path = %path.display(), "Loaded template from disk");
+            loaded.insert(path, LoadedFile { modified, len: metadata.len(), template: template.clone() });
+            return Ok(Some(template));
+        }
+        Ok(None)
+    }
+}
+
+/// `name` as a path below the template directory, or `None` if it is absolute or
+/// climbs out with `..`
+fn safe_relative_path(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    let normal = !name.is_empty()
+        && !name.contains('\\')
+        && path.components().all(|component| matches!(component, Component::Normal(_)));
+    normal.then(|| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_cannot_leave_the_directory() {
+        assert_eq!(safe_relative_path("themes/classic"), Some(PathBuf::from("themes/classic")));
+        for name in ["", "../secrets", "/etc/passwd", "themes/../../x", "themes\\x"] {
+            assert_eq!(safe_relative_path(name), None, "{}", name);
+        }
+    }
+}
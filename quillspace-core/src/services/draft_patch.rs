@@ -0,0 +1,240 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::services::composition::{PuckBlock, PuckComposition};
+
+/// Node id addressing the composition's root props
+pub const ROOT_NODE_ID: &str = "root";
+
+/// Partial draft update sent by the editor's auto-save.
+///
+/// Only the nodes that changed are sent. Nodes are addressed by the `id` prop Puck
+/// assigns to every block, or `root` for the page-level props.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DraftPatchRequest {
+    /// `updated_at` of the draft as last loaded or saved by this client
+    pub base_updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub nodes: Vec<NodePatch>,
+    /// Ids of blocks deleted in the editor
+    #[serde(default)]
+    pub removed: Vec<String>,
+}
+
+/// Changed props of one node; a `null` value removes the prop
+#[derive(Debug, Clone, Deserialize)]
+pub struct NodePatch {
+    pub id: String,
+    /// Required for blocks that are not in the draft yet
+    #[serde(rename = "type", default)]
+    pub block_type: Option<String>,
+    #[serde(default)]
+    pub props: Map<String, Value>,
+    /// Position of a new block; appended when absent
+    #[serde(default)]
+    pub index: Option<usize>,
+}
+
+/// Returned when the patch touches nodes another session changed since `base_updated_at`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DraftConflict {
+    pub node_ids: Vec<String>,
+}
+
+/// Result of a successful auto-save
+#[derive(Debug, Clone, Serialize)]
+pub struct DraftPatchResponse {
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Merge `patch` into `draft`, stamping every touched node with `now`.
+///
+/// Edits to different nodes merge regardless of how stale the client is. The whole
+/// patch is rejected, leaving `draft` unchanged, if any node it touches was written
+/// after `base_updated_at` or no longer exists.
+pub fn merge_draft_patch(
+    draft: &mut PuckComposition,
+    patch: &DraftPatchRequest,
+    now: DateTime<Utc>,
+) -> Result<(), DraftConflict> {
+    let mut conflicts: Vec<String> = Vec::new();
+    let touched = patch.nodes.iter().map(|node| node.id.as_str()).chain(patch.removed.iter().map(String::as_str));
+    for id in touched {
+        let changed_since_base = draft.node_updated_at.get(id).is_some_and(|written| *written > patch.base_updated_at);
+        if changed_since_base && !conflicts.iter().any(|c| c == id) {
+            conflicts.push(id.to_string());
+        }
+    }
+    for node in &patch.nodes {
+        let missing = node.id != ROOT_NODE_ID && node.block_type.is_none() && block_index(draft, &node.id).is_none();
+        if missing && !conflicts.contains(&node.id) {
+            conflicts.push(node.id.clone());
+        }
+    }
+    if !conflicts.is_empty() {
+        return Err(DraftConflict { node_ids: conflicts });
+    }
+
+    for node in &patch.nodes {
+        if node.id == ROOT_NODE_ID {
+            merge_props(&mut draft.root.props, &node.props);
+        } else if let Some(index) = block_index(draft, &node.id) {
+            let block = &mut draft.content[index];
+            if let Some(block_type) = &node.block_type {
+                block.block_type = block_type.clone();
+            }
+            merge_props(&mut block.props, &node.props);
+        } else {
+            let mut props = Map::new();
+            merge_props(&mut props, &node.props);
+            props.insert("id".to_string(), Value::String(node.id.clone()));
+            let block = PuckBlock { block_type: node.block_type.clone().unwrap_or_default(), props };
+            let index = node.index.unwrap_or(draft.content.len()).min(draft.content.len());
+            draft.content.insert(index, block);
+        }
+        draft.node_updated_at.insert(node.id.clone(), now);
+    }
+    for id in &patch.removed {
+        draft.content.retain(|block| block_id(block) != Some(id.as_str()));
+        draft.node_updated_at.insert(id.clone(), now);
+    }
+
+    Ok(())
+}
+
+/// Stamp every node of a fully saved draft, so older auto-saves touching them conflict
+pub fn stamp_all_nodes(draft: &mut PuckComposition, now: DateTime<Utc>) {
+    let ids: Vec<String> = draft.content.iter().filter_map(block_id).map(str::to_string).collect();
+    for id in ids.into_iter().chain(std::iter::once(ROOT_NODE_ID.to_string())) {
+        draft.node_updated_at.insert(id, now);
+    }
+}
+
+fn block_id(block: &PuckBlock) -> Option<&str> {
+    block.props.get("id").and_then(Value::as_str)
+}
+
+fn block_index(draft: &PuckComposition, id: &str) -> Option<usize> {
+    draft.content.iter().position(|block| block_id(block) == Some(id))
+}
+
+fn merge_props(props: &mut Map<String, Value>, changes: &Map<String, Value>) {
+    for (key, value) in changes {
+        // The id addresses the node and cannot be changed through a patch
+        if key == "id" {
+            continue;
+        }
+        if value.is_null() {
+            props.remove(key);
+        } else {
+            props.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::composition::PuckRoot;
+    use chrono::Duration;
+    use serde_json::json;
+
+    fn block(id: &str, block_type: &str, props: Value) -> PuckBlock {
+        let mut props = props.as_object().cloned().unwrap_or_default();
+        props.insert("id".to_string(), json!(id));
+        PuckBlock { block_type: block_type.to_string(), props }
+    }
+
+    fn saved_draft(saved_at: DateTime<Utc>) -> PuckComposition {
+        let mut draft = PuckComposition {
+            version: Some(1),
+            content: vec![
+                block("hero-1", "HeroBlock", json!({ "title": "Chapter One", "subtitle": "A beginning" })),
+                block("text-1", "TextBlock", json!({ "children": "It was a dark and stormy night." })),
+            ],
+            root: PuckRoot { props: json!({ "title": "My Novel" }).as_object().cloned().unwrap() },
+            node_updated_at: Default::default(),
+        };
+        stamp_all_nodes(&mut draft, saved_at);
+        draft
+    }
+
+    fn patch(base_updated_at: DateTime<Utc>, nodes: Value) -> DraftPatchRequest {
+        serde_json::from_value(json!({ "base_updated_at": base_updated_at, "nodes": nodes })).unwrap()
+    }
+
+    #[test]
+    fn test_partial_node_update_merged_into_draft() {
+        let saved_at = Utc::now();
+        let now = saved_at + Duration::seconds(5);
+        let mut draft = saved_draft(saved_at);
+
+        let update = patch(saved_at, json!([
+            { "id": "hero-1", "props": { "title": "Chapter 1", "subtitle": null } },
+            { "id": "quote-1", "type": "TextBlock", "props": { "children": "Quote" }, "index": 1 },
+        ]));
+        merge_draft_patch(&mut draft, &update, now).unwrap();
+
+        let hero = &draft.content[0].props;
+        assert_eq!(hero.get("title"), Some(&json!("Chapter 1")));
+        assert_eq!(hero.get("subtitle"), None);
+        assert_eq!(hero.get("id"), Some(&json!("hero-1")));
+        // Untouched nodes are kept as stored
+        assert_eq!(draft.content[1].props.get("id"), Some(&json!("quote-1")));
+        assert_eq!(draft.content[2].props.get("children"), Some(&json!("It was a dark and stormy night.")));
+        assert_eq!(draft.root.props.get("title"), Some(&json!("My Novel")));
+        assert_eq!(draft.node_updated_at["hero-1"], now);
+        assert_eq!(draft.node_updated_at["text-1"], saved_at);
+    }
+
+    #[test]
+    fn test_concurrent_edits_to_different_nodes_both_kept() {
+        let saved_at = Utc::now();
+        let mut draft = saved_draft(saved_at);
+
+        let first = patch(saved_at, json!([{ "id": "hero-1", "props": { "title": "Tab A" } }]));
+        merge_draft_patch(&mut draft, &first, saved_at + Duration::seconds(1)).unwrap();
+        let second = patch(saved_at, json!([{ "id": "text-1", "props": { "children": "Tab B" } }]));
+        merge_draft_patch(&mut draft, &second, saved_at + Duration::seconds(2)).unwrap();
+
+        assert_eq!(draft.content[0].props.get("title"), Some(&json!("Tab A")));
+        assert_eq!(draft.content[1].props.get("children"), Some(&json!("Tab B")));
+    }
+
+    #[test]
+    fn test_concurrent_conflicting_update_detected() {
+        let saved_at = Utc::now();
+        let mut draft = saved_draft(saved_at);
+
+        let first = patch(saved_at, json!([{ "id": "hero-1", "props": { "title": "Tab A" } }]));
+        merge_draft_patch(&mut draft, &first, saved_at + Duration::seconds(1)).unwrap();
+
+        // A second tab still based on the original save edits the same node
+        let stale = patch(saved_at, json!([
+            { "id": "text-1", "props": { "children": "Tab B" } },
+            { "id": "hero-1", "props": { "title": "Tab B" } },
+        ]));
+        let before = draft.clone();
+        let conflict = merge_draft_patch(&mut draft, &stale, saved_at + Duration::seconds(2)).unwrap_err();
+
+        assert_eq!(conflict.node_ids, vec!["hero-1".to_string()]);
+        assert_eq!(draft.content[0].props, before.content[0].props);
+        assert_eq!(draft.content[1].props, before.content[1].props);
+    }
+
+    #[test]
+    fn test_update_to_removed_node_conflicts() {
+        let saved_at = Utc::now();
+        let mut draft = saved_draft(saved_at);
+
+        let removal: DraftPatchRequest =
+            serde_json::from_value(json!({ "base_updated_at": saved_at, "removed": ["text-1"] })).unwrap();
+        merge_draft_patch(&mut draft, &removal, saved_at + Duration::seconds(1)).unwrap();
+        assert_eq!(draft.content.len(), 1);
+
+        let stale = patch(saved_at, json!([{ "id": "text-1", "props": { "children": "Edited" } }]));
+        let conflict = merge_draft_patch(&mut draft, &stale, saved_at + Duration::seconds(2)).unwrap_err();
+        assert_eq!(conflict.node_ids, vec!["text-1".to_string()]);
+    }
+}
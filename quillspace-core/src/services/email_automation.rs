@@ -1,8 +1,14 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, Duration};
 use uuid::Uuid;
-use crate::database::Database;
+use deadpool_postgres::Pool;
+use crate::config::EmailConfig;
+use crate::services::clock::{Clock, IdGenerator, RandomIds, SystemClock};
+use crate::services::email_sender::{send_as, EmailProvider, EmailSenderService, LogEmailProvider, OutboundEmail};
+use crate::services::email_jobs::{classify_failure, MAX_ATTEMPTS};
+use crate::types::TenantId;
 use anyhow::Result;
+use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EmailTemplate {
@@ -29,10 +35,43 @@ pub enum EmailType {
     ProjectCompletion,
 }
 
+impl EmailType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailType::BookingConfirmation => "booking_confirmation",
+            EmailType::PreConsultationReminder => "pre_consultation_reminder",
+            EmailType::PostConsultationFollowup => "post_consultation_followup",
+            EmailType::ProjectBriefReminder => "project_brief_reminder",
+            EmailType::ProposalSent => "proposal_sent",
+            EmailType::ProposalAccepted => "proposal_accepted",
+            EmailType::ProjectKickoff => "project_kickoff",
+            EmailType::ProjectUpdate => "project_update",
+            EmailType::ProjectCompletion => "project_completion",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "booking_confirmation" => Some(EmailType::BookingConfirmation),
+            "pre_consultation_reminder" => Some(EmailType::PreConsultationReminder),
+            "post_consultation_followup" => Some(EmailType::PostConsultationFollowup),
+            "project_brief_reminder" => Some(EmailType::ProjectBriefReminder),
+            "proposal_sent" => Some(EmailType::ProposalSent),
+            "proposal_accepted" => Some(EmailType::ProposalAccepted),
+            "project_kickoff" => Some(EmailType::ProjectKickoff),
+            "project_update" => Some(EmailType::ProjectUpdate),
+            "project_completion" => Some(EmailType::ProjectCompletion),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EmailJob {
     pub id: Uuid,
     pub booking_id: Uuid,
+    /// Tenant the email is sent for, from its own sender once verified; `None` for platform mail
+    pub tenant_id: Option<Uuid>,
     pub email_type: EmailType,
     pub recipient_email: String,
     pub template_variables: serde_json::Value,
@@ -52,59 +91,66 @@ pub enum EmailStatus {
     Cancelled,
 }
 
+impl EmailStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailStatus::Pending => "pending",
+            EmailStatus::Sent => "sent",
+            EmailStatus::Failed => "failed",
+            EmailStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(EmailStatus::Pending),
+            "sent" => Some(EmailStatus::Sent),
+            "failed" => Some(EmailStatus::Failed),
+            "cancelled" => Some(EmailStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
 pub struct EmailAutomationService {
-    db: Database,
+    db: Pool,
+    email: EmailConfig,
+    senders: EmailSenderService,
+    provider: Arc<dyn EmailProvider>,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
 }
 
 impl EmailAutomationService {
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(db: Pool, email: EmailConfig) -> Self {
+        Self::with_clock(db, email, Arc::new(SystemClock), Arc::new(RandomIds))
+    }
+
+    pub fn with_clock(db: Pool, email: EmailConfig, clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Self {
+        Self {
+            senders: EmailSenderService::new(db.clone(), email.clone()),
+            db,
+            email,
+            provider: Arc::new(LogEmailProvider),
+            clock,
+            ids,
+        }
+    }
+
+    /// Deliver through `provider` instead of logging each email
+    #[cfg(test)]
+    pub fn with_provider(mut self, provider: Arc<dyn EmailProvider>) -> Self {
+        self.provider = provider;
+        self
     }
 
     /// Trigger email sequence for new booking
     pub async fn trigger_booking_sequence(&self, booking_id: Uuid) -> Result<()> {
         let booking = self.get_booking_details(booking_id).await?;
-        
-        // 1. Immediate confirmation email
-        self.schedule_email(
-            booking_id,
-            EmailType::BookingConfirmation,
-            &booking.guest_email,
-            serde_json::json!({
-                "event_name": booking.event_name,
-                "scheduled_at": booking.scheduled_at,
-                "consultation_url": format!("/consultations/{}", booking_id),
-                "brief_url": format!("/consultations/{}/brief", booking_id)
-            }),
-            Utc::now(),
-        ).await?;
-
-        // 2. Project brief reminder (2 hours after booking)
-        self.schedule_email(
-            booking_id,
-            EmailType::ProjectBriefReminder,
-            &booking.guest_email,
-            serde_json::json!({
-                "event_name": booking.event_name,
-                "scheduled_at": booking.scheduled_at,
-                "brief_url": format!("/consultations/{}/brief", booking_id)
-            }),
-            Utc::now() + Duration::hours(2),
-        ).await?;
 
-        // 3. Pre-consultation reminder (24 hours before)
-        self.schedule_email(
-            booking_id,
-            EmailType::PreConsultationReminder,
-            &booking.guest_email,
-            serde_json::json!({
-                "event_name": booking.event_name,
-                "scheduled_at": booking.scheduled_at,
-                "preparation_checklist": self.get_preparation_checklist(),
-                "zoom_link": "TBD" // Would come from Calendly
-            }),
-            booking.scheduled_at - Duration::hours(24),
-        ).await?;
+        for (email_type, variables, scheduled_for) in booking_sequence(booking_id, &booking, self.clock.now()) {
+            self.schedule_email(booking_id, email_type, &booking.guest_email, variables, scheduled_for).await?;
+        }
 
         Ok(())
     }
@@ -118,67 +164,83 @@ impl EmailAutomationService {
         variables: serde_json::Value,
         scheduled_for: DateTime<Utc>,
     ) -> Result<()> {
-        let email_job = EmailJob {
-            id: Uuid::new_v4(),
+        let email_job = new_email_job(
+            self.clock.as_ref(),
+            self.ids.as_ref(),
             booking_id,
             email_type,
-            recipient_email: recipient.to_string(),
-            template_variables: variables,
+            recipient,
+            variables,
             scheduled_for,
-            sent_at: None,
-            status: EmailStatus::Pending,
-            retry_count: 0,
-            created_at: Utc::now(),
-        };
+        );
 
         let query = "
             INSERT INTO email_jobs (
                 id, booking_id, email_type, recipient_email, template_variables,
-                scheduled_for, sent_at, status, retry_count, created_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                scheduled_for, sent_at, status, retry_count, created_at, tenant_id
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
         ";
 
-        self.db.execute(query, &[
+        self.db.get().await?.execute(query, &[
             &email_job.id,
             &email_job.booking_id,
-            &email_job.email_type,
+            &email_job.email_type.as_str(),
             &email_job.recipient_email,
             &email_job.template_variables,
             &email_job.scheduled_for,
             &email_job.sent_at,
-            &email_job.status,
+            &email_job.status.as_str(),
             &email_job.retry_count,
             &email_job.created_at,
+            &email_job.tenant_id,
         ]).await?;
 
         Ok(())
     }
 
+    /// Send due email jobs every `period`
+    pub fn spawn_processor(self, period: std::time::Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.process_pending_emails().await {
+                    tracing::error!("Email job processing failed: {:#}", e);
+                }
+            }
+        })
+    }
+
     /// Process pending emails (called by cron job)
     pub async fn process_pending_emails(&self) -> Result<()> {
         let query = "
             SELECT id, booking_id, email_type, recipient_email, template_variables,
-                   scheduled_for, sent_at, status, retry_count, created_at
+                   scheduled_for, sent_at, status, retry_count, created_at, tenant_id
             FROM email_jobs 
             WHERE status = 'pending' 
-            AND scheduled_for <= NOW()
-            AND retry_count < 3
+            AND scheduled_for <= $2
+            AND retry_count < $1
             ORDER BY scheduled_for ASC
             LIMIT 50
         ";
 
-        let rows = self.db.query(query, &[]).await?;
+        let rows = self.db.get().await?.query(query, &[&MAX_ATTEMPTS, &self.clock.now()]).await?;
 
         for row in rows {
+            let email_type: String = row.get(2);
+            let status: String = row.get(7);
             let email_job = EmailJob {
                 id: row.get(0),
                 booking_id: row.get(1),
-                email_type: row.get(2),
+                tenant_id: row.get(10),
+                email_type: EmailType::parse(&email_type)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown email type: {}", email_type))?,
                 recipient_email: row.get(3),
                 template_variables: row.get(4),
                 scheduled_for: row.get(5),
                 sent_at: row.get(6),
-                status: row.get(7),
+                status: EmailStatus::parse(&status)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown email status: {}", status))?,
                 retry_count: row.get(8),
                 created_at: row.get(9),
             };
@@ -189,7 +251,7 @@ impl EmailAutomationService {
                 }
                 Err(e) => {
                     tracing::error!("Failed to send email {}: {}", email_job.id, e);
-                    self.increment_retry_count(email_job.id).await?;
+                    self.record_failure(email_job.id, &e.to_string()).await?;
                 }
             }
         }
@@ -206,16 +268,21 @@ impl EmailAutomationService {
         let html_content = self.replace_variables(&template.html_content, &job.template_variables);
         let text_content = self.replace_variables(&template.text_content, &job.template_variables);
 
-        // TODO: Integrate with actual email service (SendGrid, AWS SES, etc.)
-        tracing::info!(
-            "Sending email: {} to {} with subject: {}",
-            job.email_type,
-            job.recipient_email,
-            subject
-        );
-
-        // Mock email sending for now
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        let email = OutboundEmail {
+            to: job.recipient_email.clone(),
+            subject,
+            html: html_content,
+            text: text_content,
+        };
+        match job.tenant_id {
+            Some(tenant_id) => {
+                self.senders.send(self.provider.as_ref(), &TenantId::from_uuid(tenant_id), &email).await?;
+            }
+            None => {
+                send_as(self.provider.as_ref(), &self.email, None, &email).await?;
+            }
+        }
+        tracing::info!("Sent {} email {} to {}", job.email_type.as_str(), job.id, job.recipient_email);
 
         Ok(())
     }
@@ -226,7 +293,7 @@ impl EmailAutomationService {
         // In production, these would be stored in database
         Ok(match email_type {
             EmailType::BookingConfirmation => EmailTemplate {
-                id: Uuid::new_v4(),
+                id: self.ids.new_id(),
                 name: "Booking Confirmation".to_string(),
                 subject: "🎉 Your QuillSpace consultation is confirmed!".to_string(),
                 html_content: self.get_booking_confirmation_template(),
@@ -235,7 +302,7 @@ impl EmailAutomationService {
                 variables: vec!["event_name".to_string(), "scheduled_at".to_string()],
             },
             EmailType::ProjectBriefReminder => EmailTemplate {
-                id: Uuid::new_v4(),
+                id: self.ids.new_id(),
                 name: "Project Brief Reminder".to_string(),
                 subject: "📝 Complete your project brief for maximum consultation value".to_string(),
                 html_content: self.get_brief_reminder_template(),
@@ -244,7 +311,7 @@ impl EmailAutomationService {
                 variables: vec!["brief_url".to_string()],
             },
             EmailType::PreConsultationReminder => EmailTemplate {
-                id: Uuid::new_v4(),
+                id: self.ids.new_id(),
                 name: "Pre-consultation Reminder".to_string(),
                 subject: "⏰ Your QuillSpace consultation is tomorrow!".to_string(),
                 html_content: self.get_pre_consultation_template(),
@@ -284,19 +351,24 @@ impl EmailAutomationService {
             SET status = 'sent', sent_at = NOW() 
             WHERE id = $1
         ";
-        self.db.execute(query, &[&email_id]).await?;
+        self.db.get().await?.execute(query, &[&email_id]).await?;
         Ok(())
     }
 
-    /// Increment retry count
-    async fn increment_retry_count(&self, email_id: Uuid) -> Result<()> {
+    /// Record a failed attempt. Permanent failures and the last allowed attempt move the job
+    /// to the dead-letter queue with its error.
+    async fn record_failure(&self, email_id: Uuid, error: &str) -> Result<()> {
+        let failure_kind = classify_failure(error).as_str();
         let query = "
             UPDATE email_jobs 
             SET retry_count = retry_count + 1,
-                status = CASE WHEN retry_count >= 2 THEN 'failed' ELSE 'pending' END
+                last_error = $2,
+                failure_kind = $3,
+                status = CASE WHEN $3 = 'permanent' OR retry_count + 1 >= $4 THEN 'failed' ELSE 'pending' END,
+                failed_at = CASE WHEN $3 = 'permanent' OR retry_count + 1 >= $4 THEN NOW() END
             WHERE id = $1
         ";
-        self.db.execute(query, &[&email_id]).await?;
+        self.db.get().await?.execute(query, &[&email_id, &error, &failure_kind, &MAX_ATTEMPTS]).await?;
         Ok(())
     }
 
@@ -308,7 +380,7 @@ impl EmailAutomationService {
             WHERE id = $1
         ";
         
-        let row = self.db.query_one(query, &[&booking_id]).await?;
+        let row = self.db.get().await?.query_one(query, &[&booking_id]).await?;
         
         Ok(BookingDetails {
             event_name: row.get(0),
@@ -318,7 +390,7 @@ impl EmailAutomationService {
     }
 
     /// Get preparation checklist
-    fn get_preparation_checklist(&self) -> Vec<String> {
+    fn get_preparation_checklist() -> Vec<String> {
         vec![
             "Author bio and headshot".to_string(),
             "Book covers and descriptions".to_string(),
@@ -441,3 +513,161 @@ struct BookingDetails {
     scheduled_at: DateTime<Utc>,
     guest_email: String,
 }
+
+/// The emails a new booking triggers and when each is due: confirmation now, the brief
+/// reminder two hours later and the pre-consultation reminder a day before the call
+fn booking_sequence(
+    booking_id: Uuid,
+    booking: &BookingDetails,
+    now: DateTime<Utc>,
+) -> Vec<(EmailType, serde_json::Value, DateTime<Utc>)> {
+    vec![
+        (
+            EmailType::BookingConfirmation,
+            serde_json::json!({
+                "event_name": booking.event_name,
+                "scheduled_at": booking.scheduled_at,
+                "consultation_url": format!("/consultations/{}", booking_id),
+                "brief_url": format!("/consultations/{}/brief", booking_id)
+            }),
+            now,
+        ),
+        (
+            EmailType::ProjectBriefReminder,
+            serde_json::json!({
+                "event_name": booking.event_name,
+                "scheduled_at": booking.scheduled_at,
+                "brief_url": format!("/consultations/{}/brief", booking_id)
+            }),
+            now + Duration::hours(2),
+        ),
+        (
+            EmailType::PreConsultationReminder,
+            serde_json::json!({
+                "event_name": booking.event_name,
+                "scheduled_at": booking.scheduled_at,
+                "preparation_checklist": EmailAutomationService::get_preparation_checklist(),
+                "zoom_link": "TBD" // Would come from Calendly
+            }),
+            booking.scheduled_at - Duration::hours(24),
+        ),
+    ]
+}
+
+/// A pending email job, stamped with the clock's time and a new id
+fn new_email_job(
+    clock: &dyn Clock,
+    ids: &dyn IdGenerator,
+    booking_id: Uuid,
+    email_type: EmailType,
+    recipient: &str,
+    variables: serde_json::Value,
+    scheduled_for: DateTime<Utc>,
+) -> EmailJob {
+    EmailJob {
+        id: ids.new_id(),
+        booking_id,
+        tenant_id: None,
+        email_type,
+        recipient_email: recipient.to_string(),
+        template_variables: variables,
+        scheduled_for,
+        sent_at: None,
+        status: EmailStatus::Pending,
+        retry_count: 0,
+        created_at: clock.now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::clock::{FixedClock, SequentialIds};
+    use crate::services::email_sender::MemoryEmailProvider;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_booking_sequence_scheduled_from_clock() {
+        let clock = FixedClock::at(Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap());
+        let ids = SequentialIds::default();
+        let booking_id = Uuid::from_u128(42);
+        let booking = BookingDetails {
+            event_name: "Author website consultation".to_string(),
+            scheduled_at: Utc.with_ymd_and_hms(2024, 3, 5, 15, 0, 0).unwrap(),
+            guest_email: "author@example.com".to_string(),
+        };
+
+        let jobs: Vec<EmailJob> = booking_sequence(booking_id, &booking, clock.now())
+            .into_iter()
+            .map(|(email_type, variables, scheduled_for)| {
+                new_email_job(&clock, &ids, booking_id, email_type, &booking.guest_email, variables, scheduled_for)
+            })
+            .collect();
+
+        let scheduled: Vec<_> = jobs.iter().map(|job| job.scheduled_for).collect();
+        assert_eq!(
+            scheduled,
+            vec![
+                Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 3, 4, 15, 0, 0).unwrap(),
+            ]
+        );
+        let job_ids: Vec<_> = jobs.iter().map(|job| job.id).collect();
+        assert_eq!(job_ids, vec![SequentialIds::nth(1), SequentialIds::nth(2), SequentialIds::nth(3)]);
+        assert!(jobs.iter().all(|job| job.created_at == clock.now() && job.booking_id == booking_id));
+        assert_eq!(jobs[0].template_variables["brief_url"], "/consultations/00000000-0000-0000-0000-00000000002a/brief");
+    }
+
+    #[tokio::test]
+    async fn test_pending_emails_sent_once_due_from_their_sender() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap();
+        let admin = app.admin_pool.get().await.unwrap();
+        admin
+            .execute(
+                "INSERT INTO tenant_email_senders (tenant_id, from_address, domain, verification_token, verified_at)
+                 VALUES ($1, 'news@brontepress.com', 'brontepress.com', 'token', NOW())",
+                &[app.tenant_a.id.as_uuid()],
+            )
+            .await
+            .unwrap();
+        let jobs = [(1u128, None, now), (2, Some(app.tenant_a.id.as_uuid()), now), (3, None, now + Duration::hours(2))];
+        for (id, tenant_id, scheduled_for) in jobs {
+            admin
+                .execute(
+                    "INSERT INTO email_jobs (id, booking_id, tenant_id, email_type, recipient_email, scheduled_for)
+                     VALUES ($1, $1, $2, 'booking_confirmation', 'author@example.com', $3)",
+                    &[&Uuid::from_u128(id), &tenant_id, &scheduled_for],
+                )
+                .await
+                .unwrap();
+        }
+
+        let provider = Arc::new(MemoryEmailProvider::default());
+        let service = EmailAutomationService::with_clock(
+            app.state.db.postgres().clone(),
+            EmailConfig::default(),
+            Arc::new(FixedClock::at(now)),
+            Arc::new(SequentialIds::default()),
+        )
+        .with_provider(provider.clone());
+        service.process_pending_emails().await.unwrap();
+
+        let statuses: Vec<String> = admin
+            .query("SELECT status FROM email_jobs ORDER BY id", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(statuses, vec!["sent", "sent", "pending"]);
+        let mut senders: Vec<String> = provider.sent().into_iter().map(|(sender, _)| sender.from_address).collect();
+        senders.sort();
+        assert_eq!(senders, vec!["news@brontepress.com", "no-reply@quillspace.com"]);
+        assert!(provider.sent().iter().all(|(_, email)| email.to == "author@example.com"
+            && email.subject.contains("consultation is confirmed")));
+    }
+}
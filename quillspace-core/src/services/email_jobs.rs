@@ -0,0 +1,293 @@
+use crate::types::TenantId;
+use chrono::{DateTime, Duration, Utc};
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// Attempts before a retryable failure is given up on and dead-lettered
+pub const MAX_ATTEMPTS: i32 = 3;
+
+/// Longest window a bulk retry may cover
+pub const MAX_RETRY_WINDOW_DAYS: i64 = 31;
+
+/// Provider errors meaning the message can never be delivered to this address
+const PERMANENT_FAILURE_MARKERS: &[&str] = &[
+    "suppressed",
+    "suppression list",
+    "unsubscribed",
+    "hard bounce",
+    "invalid recipient",
+    "recipient address rejected",
+    "mailbox does not exist",
+    "complaint",
+];
+
+/// Whether sending again can succeed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// Provider outage, timeout or throttling
+    Retryable,
+    /// Suppressed, bounced or invalid address
+    Permanent,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::Retryable => "retryable",
+            FailureKind::Permanent => "permanent",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "retryable" => Some(FailureKind::Retryable),
+            "permanent" => Some(FailureKind::Permanent),
+            _ => None,
+        }
+    }
+}
+
+/// Classify a send error by the provider's message
+pub fn classify_failure(error: &str) -> FailureKind {
+    let error = error.to_lowercase();
+    if PERMANENT_FAILURE_MARKERS.iter().any(|marker| error.contains(marker)) {
+        FailureKind::Permanent
+    } else {
+        FailureKind::Retryable
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EmailJobError {
+    #[error("Email job not found")]
+    NotFound,
+
+    #[error("Only failed email jobs can be retried")]
+    NotFailed,
+
+    #[error("The recipient cannot receive email: {0}")]
+    Permanent(String),
+
+    #[error("Invalid retry window: {0}")]
+    InvalidWindow(&'static str),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// A dead-lettered email job
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedEmailJob {
+    pub id: Uuid,
+    pub booking_id: Uuid,
+    pub email_type: String,
+    pub recipient_email: String,
+    pub status: String,
+    pub retry_count: i32,
+    pub last_error: Option<String>,
+    pub failure_kind: Option<FailureKind>,
+    pub failed_at: Option<DateTime<Utc>>,
+    pub scheduled_for: DateTime<Utc>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl FailedEmailJob {
+    fn from_row(row: &Row) -> Self {
+        let failure_kind: Option<String> = row.get("failure_kind");
+        Self {
+            id: row.get("id"),
+            booking_id: row.get("booking_id"),
+            email_type: row.get("email_type"),
+            recipient_email: row.get("recipient_email"),
+            status: row.get("status"),
+            retry_count: row.get("retry_count"),
+            last_error: row.get("last_error"),
+            failure_kind: failure_kind.as_deref().and_then(FailureKind::parse),
+            failed_at: row.get("failed_at"),
+            scheduled_for: row.get("scheduled_for"),
+            created_at: row.get("created_at"),
+        }
+    }
+
+    /// Only failed jobs whose failure was not permanent go back on the queue
+    pub fn check_retryable(&self) -> Result<(), EmailJobError> {
+        if self.status != "failed" {
+            return Err(EmailJobError::NotFailed);
+        }
+        if self.failure_kind == Some(FailureKind::Permanent) {
+            let reason = self.last_error.clone().unwrap_or_else(|| "permanent failure".to_string());
+            return Err(EmailJobError::Permanent(reason));
+        }
+        Ok(())
+    }
+}
+
+/// Jobs that failed within `[from, to)` are requeued by a bulk retry
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct RetryWindow {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl RetryWindow {
+    pub fn validate(&self) -> Result<(), EmailJobError> {
+        if self.from >= self.to {
+            return Err(EmailJobError::InvalidWindow("`from` must be before `to`"));
+        }
+        if self.to - self.from > Duration::days(MAX_RETRY_WINDOW_DAYS) {
+            return Err(EmailJobError::InvalidWindow("window is longer than 31 days"));
+        }
+        Ok(())
+    }
+}
+
+const JOB_COLUMNS: &str = "id, booking_id, email_type, recipient_email, status, retry_count,
+    last_error, failure_kind, failed_at, scheduled_for, created_at";
+
+/// Dead-letter queue of email jobs: review failures and requeue the retryable ones
+pub struct EmailJobService {
+    pool: Pool,
+}
+
+impl EmailJobService {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    /// The tenant's failed jobs, most recent failure first, and how many there are in total
+    pub async fn list_failed(
+        &self,
+        tenant_id: &TenantId,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<FailedEmailJob>, i64), EmailJobError> {
+        let client = self.pool.get().await
+            .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
+
+        let query = format!(
+            "SELECT {} FROM email_jobs WHERE tenant_id = $1 AND status = 'failed'
+             ORDER BY failed_at DESC NULLS LAST, created_at DESC
+             LIMIT $2 OFFSET $3",
+            JOB_COLUMNS
+        );
+        let rows = client
+            .query(&query, &[tenant_id.as_uuid(), &limit, &offset])
+            .await
+            .map_err(anyhow::Error::from)?;
+        let total: i64 = client
+            .query_one(
+                "SELECT COUNT(*) FROM email_jobs WHERE tenant_id = $1 AND status = 'failed'",
+                &[tenant_id.as_uuid()],
+            )
+            .await
+            .map_err(anyhow::Error::from)?
+            .get(0);
+
+        Ok((rows.iter().map(FailedEmailJob::from_row).collect(), total))
+    }
+
+    /// Put one of the tenant's failed jobs back on the queue with a fresh set of attempts
+    pub async fn retry_job(&self, tenant_id: &TenantId, job_id: Uuid) -> Result<FailedEmailJob, EmailJobError> {
+        let mut client = self.pool.get().await
+            .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
+        let transaction = client.transaction().await
+            .map_err(|e| anyhow::anyhow!("Failed to start transaction: {}", e))?;
+
+        let query = format!("SELECT {} FROM email_jobs WHERE id = $1 AND tenant_id = $2 FOR UPDATE", JOB_COLUMNS);
+        let job = transaction
+            .query_opt(&query, &[&job_id, tenant_id.as_uuid()])
+            .await
+            .map_err(anyhow::Error::from)?
+            .map(|row| FailedEmailJob::from_row(&row))
+            .ok_or(EmailJobError::NotFound)?;
+        job.check_retryable()?;
+
+        let query = format!(
+            "UPDATE email_jobs
+             SET status = 'pending', retry_count = 0, failure_kind = NULL, failed_at = NULL, scheduled_for = NOW()
+             WHERE id = $1
+             RETURNING {}",
+            JOB_COLUMNS
+        );
+        let row = transaction.query_one(&query, &[&job_id]).await.map_err(anyhow::Error::from)?;
+        transaction.commit().await.map_err(anyhow::Error::from)?;
+
+        Ok(FailedEmailJob::from_row(&row))
+    }
+
+    /// Requeue every retryable job of the tenant that failed within the window; returns how many
+    pub async fn retry_window(&self, tenant_id: &TenantId, window: RetryWindow) -> Result<u64, EmailJobError> {
+        window.validate()?;
+        let client = self.pool.get().await
+            .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
+
+        let requeued = client
+            .execute(
+                "UPDATE email_jobs
+                 SET status = 'pending', retry_count = 0, failure_kind = NULL, failed_at = NULL, scheduled_for = NOW()
+                 WHERE tenant_id = $1
+                   AND status = 'failed'
+                   AND failure_kind IS DISTINCT FROM 'permanent'
+                   AND failed_at >= $2 AND failed_at < $3",
+                &[tenant_id.as_uuid(), &window.from, &window.to],
+            )
+            .await
+            .map_err(anyhow::Error::from)?;
+        Ok(requeued)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed_job(last_error: &str) -> FailedEmailJob {
+        FailedEmailJob {
+            id: Uuid::new_v4(),
+            booking_id: Uuid::new_v4(),
+            email_type: "booking_confirmation".to_string(),
+            recipient_email: "author@example.com".to_string(),
+            status: "failed".to_string(),
+            retry_count: MAX_ATTEMPTS,
+            last_error: Some(last_error.to_string()),
+            failure_kind: Some(classify_failure(last_error)),
+            failed_at: Some(Utc::now()),
+            scheduled_for: Utc::now(),
+            created_at: Some(Utc::now()),
+        }
+    }
+
+    #[test]
+    fn test_failed_job_can_be_requeued() {
+        let job = failed_job("503 Service Unavailable: provider timed out");
+        assert_eq!(job.failure_kind, Some(FailureKind::Retryable));
+        assert!(job.check_retryable().is_ok());
+
+        // Jobs that are still queued or already sent are not dead letters
+        for status in ["pending", "sent"] {
+            let job = FailedEmailJob { status: status.to_string(), ..failed_job("timeout") };
+            assert!(matches!(job.check_retryable(), Err(EmailJobError::NotFailed)));
+        }
+    }
+
+    #[test]
+    fn test_permanently_failed_job_not_retryable() {
+        let job = failed_job("550 Recipient address is on the Suppression List");
+        assert_eq!(job.failure_kind, Some(FailureKind::Permanent));
+        assert!(matches!(job.check_retryable(), Err(EmailJobError::Permanent(reason)) if reason.contains("Suppression")));
+
+        assert_eq!(classify_failure("Hard bounce: mailbox does not exist"), FailureKind::Permanent);
+        assert_eq!(classify_failure("429 Too Many Requests"), FailureKind::Retryable);
+    }
+
+    #[test]
+    fn test_retry_window_validated() {
+        let now = Utc::now();
+        assert!(RetryWindow { from: now - Duration::hours(6), to: now }.validate().is_ok());
+        assert!(RetryWindow { from: now, to: now }.validate().is_err());
+        assert!(RetryWindow { from: now - Duration::days(40), to: now }.validate().is_err());
+    }
+}
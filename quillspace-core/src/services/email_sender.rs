@@ -0,0 +1,645 @@
+//! Who outbound email is from: the platform default in `email`, or a tenant's own
+//! address once its domain is verified.
+//!
+//! Verifying a sender domain checks three DNS records:
+//!
+//! - `_quillspace.<domain>` TXT holding `quillspace-verification=<token>`, proving the
+//!   tenant controls the domain;
+//! - the domain's SPF record including `email.spf_include`, so receivers accept mail
+//!   the platform sends as it;
+//! - with a DKIM selector, `<selector>._domainkey.<dkim domain>` holding a DKIM key.
+//!
+//! Changing the address's domain or the DKIM settings needs a new verification. Mail
+//! for a tenant whose sender is not verified goes out from the platform default.
+
+use crate::database::postgres::tenant_transaction;
+use crate::config::EmailConfig;
+use crate::services::object_store::BoxFuture;
+use crate::types::TenantId;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use hickory_resolver::error::ResolveErrorKind;
+use hickory_resolver::TokioAsyncResolver;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use tracing::{info, warn};
+
+/// Name of the ownership record, below the sender domain
+pub const VERIFICATION_RECORD: &str = "_quillspace";
+
+/// Prefix of the ownership record's value, followed by the token
+pub const VERIFICATION_PREFIX: &str = "quillspace-verification=";
+
+const TOKEN_LEN: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EmailSenderError {
+    #[error("Invalid email sender: {0}")]
+    Invalid(String),
+
+    #[error("No email sender configured")]
+    NotConfigured,
+
+    #[error("Sender domain '{0}' is not verified")]
+    Unverified(String),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// Key a provider signs a tenant's mail with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DkimKey {
+    pub selector: String,
+    pub domain: String,
+}
+
+/// The sender an email goes out as
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SenderIdentity {
+    pub from_address: String,
+    pub from_name: Option<String>,
+    pub reply_to: Option<String>,
+    /// `None` leaves signing to the provider's own domain
+    pub dkim: Option<DkimKey>,
+}
+
+impl SenderIdentity {
+    pub fn platform(config: &EmailConfig) -> Self {
+        Self {
+            from_address: config.from_address.clone(),
+            from_name: Some(config.from_name.clone()).filter(|name| !name.is_empty()),
+            reply_to: config.reply_to.clone(),
+            dkim: None,
+        }
+    }
+}
+
+/// A message to deliver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboundEmail {
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// Delivers email as the given sender
+pub trait EmailProvider: Send + Sync {
+    fn send<'a>(&'a self, sender: &'a SenderIdentity, email: &'a OutboundEmail) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Logs each email instead of delivering it, until a delivery service is set up
+#[derive(Debug, Default)]
+pub struct LogEmailProvider;
+
+impl EmailProvider for LogEmailProvider {
+    fn send<'a>(&'a self, sender: &'a SenderIdentity, email: &'a OutboundEmail) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            info!(
+                from = %sender.from_address,
+                to = %email.to,
+                subject = %email.subject,
+                dkim_selector = sender.dkim.as_ref().map(|dkim| dkim.selector.as_str()),
+                "Sending email"
+            );
+            Ok(())
+        })
+    }
+}
+
+/// Keeps sent emails in memory, for tests
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryEmailProvider {
+    sent: std::sync::Mutex<Vec<(SenderIdentity, OutboundEmail)>>,
+}
+
+#[cfg(test)]
+impl MemoryEmailProvider {
+    pub fn sent(&self) -> Vec<(SenderIdentity, OutboundEmail)> {
+        self.sent.lock().map(|sent| sent.clone()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+impl EmailProvider for MemoryEmailProvider {
+    fn send<'a>(&'a self, sender: &'a SenderIdentity, email: &'a OutboundEmail) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let mut sent = self.sent.lock().map_err(|_| anyhow::anyhow!("Email outbox lock poisoned"))?;
+            sent.push((sender.clone(), email.clone()));
+            Ok(())
+        })
+    }
+}
+
+/// Looks up TXT records
+pub trait TxtResolver: Send + Sync {
+    /// The TXT values at `name`; none if the name or its records do not exist
+    fn txt<'a>(&'a self, name: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<String>>>;
+}
+
+/// TXT lookups through the system's DNS resolver
+pub struct DnsTxtResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl DnsTxtResolver {
+    pub fn from_system_conf() -> anyhow::Result<Self> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf().context("Failed to read the DNS configuration")?;
+        Ok(Self { resolver })
+    }
+}
+
+impl TxtResolver for DnsTxtResolver {
+    fn txt<'a>(&'a self, name: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<String>>> {
+        Box::pin(async move {
+            match self.resolver.txt_lookup(name).await {
+                Ok(lookup) => Ok(lookup
+                    .iter()
+                    .map(|txt| txt.txt_data().iter().map(|part| String::from_utf8_lossy(part)).collect())
+                    .collect()),
+                Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => Ok(Vec::new()),
+                Err(e) => Err(anyhow::Error::new(e).context(format!("Failed to look up TXT records of {}", name))),
+            }
+        })
+    }
+}
+
+/// A tenant's own sender
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantEmailSender {
+    pub from_address: String,
+    pub from_name: Option<String>,
+    pub reply_to: Option<String>,
+    pub domain: String,
+    pub dkim_selector: Option<String>,
+    pub dkim_domain: Option<String>,
+    /// Value for the `_quillspace.<domain>` TXT record
+    pub verification_token: String,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    /// What the last verification found missing
+    pub last_error: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TenantEmailSender {
+    /// The identity to send as; refused until the domain is verified
+    pub fn identity(&self) -> Result<SenderIdentity, EmailSenderError> {
+        if self.verified_at.is_none() {
+            return Err(EmailSenderError::Unverified(self.domain.clone()));
+        }
+        Ok(SenderIdentity {
+            from_address: self.from_address.clone(),
+            from_name: self.from_name.clone(),
+            reply_to: self.reply_to.clone(),
+            dkim: self.dkim_selector.clone().map(|selector| DkimKey {
+                selector,
+                domain: self.dkim_domain().to_string(),
+            }),
+        })
+    }
+
+    /// Domain the DKIM key is published under
+    pub fn dkim_domain(&self) -> &str {
+        self.dkim_domain.as_deref().unwrap_or(&self.domain)
+    }
+}
+
+/// A tenant's requested sender
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetEmailSenderRequest {
+    pub from_address: String,
+    pub from_name: Option<String>,
+    pub reply_to: Option<String>,
+    pub dkim_selector: Option<String>,
+    pub dkim_domain: Option<String>,
+}
+
+impl SetEmailSenderRequest {
+    /// The request with addresses and domains trimmed and lowercased, or why it is invalid
+    pub fn normalized(self) -> Result<Self, EmailSenderError> {
+        let from_address = normalize_address(&self.from_address)?;
+        let reply_to = self.reply_to.filter(|address| !address.trim().is_empty()).map(|address| normalize_address(&address)).transpose()?;
+        let dkim_selector = self.dkim_selector.map(|selector| selector.trim().to_lowercase()).filter(|selector| !selector.is_empty());
+        if let Some(selector) = &dkim_selector {
+            if !is_dns_name(selector) {
+                return Err(EmailSenderError::Invalid(format!("'{}' is not a valid DKIM selector", selector)));
+            }
+        }
+        let dkim_domain = self.dkim_domain.map(|domain| domain.trim().to_lowercase()).filter(|domain| !domain.is_empty());
+        if let Some(domain) = &dkim_domain {
+            if dkim_selector.is_none() || !is_dns_name(domain) {
+                return Err(EmailSenderError::Invalid("a DKIM domain needs a selector and must be a domain name".to_string()));
+            }
+        }
+        Ok(Self {
+            from_address,
+            from_name: self.from_name.map(|name| name.trim().to_string()).filter(|name| !name.is_empty()),
+            reply_to,
+            dkim_selector,
+            dkim_domain,
+        })
+    }
+}
+
+/// `address` trimmed and lowercased, if it looks like a mailbox on a domain name
+fn normalize_address(address: &str) -> Result<String, EmailSenderError> {
+    let address = address.trim().to_lowercase();
+    let valid = match address.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty() && !local.chars().any(|c| c.is_whitespace() || c == '<' || c == '>') && is_dns_name(domain)
+        }
+        None => false,
+    };
+    if valid {
+        Ok(address)
+    } else {
+        Err(EmailSenderError::Invalid(format!("'{}' is not a valid email address", address)))
+    }
+}
+
+/// Dot-separated labels of ASCII letters, digits, hyphens and underscores
+fn is_dns_name(name: &str) -> bool {
+    name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+/// The sender for a tenant's mail: its own once verified, the platform default otherwise
+pub fn sender_identity(config: &EmailConfig, sender: Option<&TenantEmailSender>) -> SenderIdentity {
+    match sender.map(TenantEmailSender::identity) {
+        Some(Ok(identity)) => identity,
+        Some(Err(e)) => {
+            warn!("{}, sending from the platform address", e);
+            SenderIdentity::platform(config)
+        }
+        None => SenderIdentity::platform(config),
+    }
+}
+
+/// What is missing from the sender's DNS records; empty when the domain is verified
+pub async fn check_sender_domain(
+    resolver: &dyn TxtResolver,
+    config: &EmailConfig,
+    sender: &TenantEmailSender,
+) -> anyhow::Result<Vec<String>> {
+    let mut problems = Vec::new();
+
+    let ownership_name = format!("{}.{}", VERIFICATION_RECORD, sender.domain);
+    let expected = format!("{}{}", VERIFICATION_PREFIX, sender.verification_token);
+    if !resolver.txt(&ownership_name).await?.iter().any(|value| value.trim() == expected) {
+        problems.push(format!("TXT record {} must be \"{}\"", ownership_name, expected));
+    }
+
+    let include = format!("include:{}", config.spf_include);
+    let spf = resolver.txt(&sender.domain).await?;
+    match spf.iter().find(|value| value.trim_start().to_lowercase().starts_with("v=spf1")) {
+        Some(record) if record.split_whitespace().any(|term| term.eq_ignore_ascii_case(&include)) => {}
+        Some(_) => problems.push(format!("SPF record of {} must contain {}", sender.domain, include)),
+        None => problems.push(format!("{} has no SPF record; add \"v=spf1 {} ~all\"", sender.domain, include)),
+    }
+
+    if let Some(selector) = &sender.dkim_selector {
+        let dkim_name = format!("{}._domainkey.{}", selector, sender.dkim_domain());
+        let has_key = resolver.txt(&dkim_name).await?.iter().any(|value| {
+            value.split(';').any(|tag| tag.trim().strip_prefix("p=").is_some_and(|key| !key.trim().is_empty()))
+        });
+        if !has_key {
+            problems.push(format!("{} has no DKIM key", dkim_name));
+        }
+    }
+    Ok(problems)
+}
+
+/// Send `email` as the tenant's sender, or the platform's when it has no verified one
+pub async fn send_as(
+    provider: &dyn EmailProvider,
+    config: &EmailConfig,
+    sender: Option<&TenantEmailSender>,
+    email: &OutboundEmail,
+) -> anyhow::Result<SenderIdentity> {
+    let identity = sender_identity(config, sender);
+    provider.send(&identity, email).await?;
+    Ok(identity)
+}
+
+/// Service for tenant email senders. Every query runs with the tenant's RLS context.
+pub struct EmailSenderService {
+    db: Pool,
+    config: EmailConfig,
+}
+
+impl EmailSenderService {
+    pub fn new(db: Pool, config: EmailConfig) -> Self {
+        Self { db, config }
+    }
+
+    pub async fn get_sender(&self, tenant_id: &TenantId) -> Result<Option<TenantEmailSender>, EmailSenderError> {
+        let mut client = self.db.get().await.context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let row = transaction
+            .query_opt("SELECT * FROM tenant_email_senders WHERE tenant_id = $1", &[tenant_id.as_uuid()])
+            .await
+            .context("Failed to load email sender")?;
+        transaction.commit().await.context("Failed to commit")?;
+        Ok(row.as_ref().map(row_to_sender))
+    }
+
+    /// Set the tenant's sender. A new domain or DKIM setting needs verifying again.
+    pub async fn set_sender(
+        &self,
+        tenant_id: &TenantId,
+        request: SetEmailSenderRequest,
+    ) -> Result<TenantEmailSender, EmailSenderError> {
+        let request = request.normalized()?;
+        let domain = request.from_address.rsplit_once('@').map(|(_, domain)| domain.to_string()).unwrap_or_default();
+        let token: String = rand::thread_rng().sample_iter(&Alphanumeric).take(TOKEN_LEN).map(char::from).collect();
+
+        let mut client = self.db.get().await.context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let row = transaction
+            .query_one(
+                "INSERT INTO tenant_email_senders
+                     (tenant_id, from_address, from_name, reply_to, domain, dkim_selector, dkim_domain, verification_token)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                 ON CONFLICT (tenant_id) DO UPDATE SET
+                     from_address = EXCLUDED.from_address,
+                     from_name = EXCLUDED.from_name,
+                     reply_to = EXCLUDED.reply_to,
+                     domain = EXCLUDED.domain,
+                     dkim_selector = EXCLUDED.dkim_selector,
+                     dkim_domain = EXCLUDED.dkim_domain,
+                     verified_at = CASE
+                         WHEN (tenant_email_senders.domain, tenant_email_senders.dkim_selector, tenant_email_senders.dkim_domain)
+                              IS NOT DISTINCT FROM (EXCLUDED.domain, EXCLUDED.dkim_selector, EXCLUDED.dkim_domain)
+                         THEN tenant_email_senders.verified_at
+                     END,
+                     verification_token = CASE
+                         WHEN tenant_email_senders.domain = EXCLUDED.domain THEN tenant_email_senders.verification_token
+                         ELSE EXCLUDED.verification_token
+                     END,
+                     updated_at = NOW()
+                 RETURNING *",
+                &[
+                    tenant_id.as_uuid(),
+                    &request.from_address,
+                    &request.from_name,
+                    &request.reply_to,
+                    &domain,
+                    &request.dkim_selector,
+                    &request.dkim_domain,
+                    &token,
+                ],
+            )
+            .await
+            .context("Failed to save email sender")?;
+        transaction.commit().await.context("Failed to commit")?;
+        Ok(row_to_sender(&row))
+    }
+
+    /// Stop sending from the tenant's own address; returns whether it had one
+    pub async fn delete_sender(&self, tenant_id: &TenantId) -> Result<bool, EmailSenderError> {
+        let mut client = self.db.get().await.context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let deleted = transaction
+            .execute("DELETE FROM tenant_email_senders WHERE tenant_id = $1", &[tenant_id.as_uuid()])
+            .await
+            .context("Failed to delete email sender")?;
+        transaction.commit().await.context("Failed to commit")?;
+        Ok(deleted > 0)
+    }
+
+    /// Check the sender's DNS records and record the outcome. A sender that fails the
+    /// check is unverified until it passes again.
+    pub async fn verify_sender(
+        &self,
+        tenant_id: &TenantId,
+        resolver: &dyn TxtResolver,
+    ) -> Result<TenantEmailSender, EmailSenderError> {
+        let sender = self.get_sender(tenant_id).await?.ok_or(EmailSenderError::NotConfigured)?;
+        let problems = check_sender_domain(resolver, &self.config, &sender).await?;
+        let last_error = (!problems.is_empty()).then(|| problems.join("; "));
+
+        let mut client = self.db.get().await.context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        // Only if the sender checked is still the one saved
+        let row = transaction
+            .query_opt(
+                "UPDATE tenant_email_senders
+                 SET verified_at = CASE WHEN $2::text IS NULL THEN COALESCE(verified_at, NOW()) END,
+                     last_error = $2,
+                     last_checked_at = NOW()
+                 WHERE tenant_id = $1 AND updated_at = $3
+                 RETURNING *",
+                &[tenant_id.as_uuid(), &last_error, &sender.updated_at],
+            )
+            .await
+            .context("Failed to record email sender verification")?;
+        transaction.commit().await.context("Failed to commit")?;
+
+        let sender = row.as_ref().map(row_to_sender).unwrap_or(sender);
+        match &sender.last_error {
+            None => info!(tenant_id = %tenant_id, domain = %sender.domain, "Email sender domain verified"),
+            Some(error) => warn!(tenant_id = %tenant_id, domain = %sender.domain, error, "Email sender domain not verified"),
+        }
+        Ok(sender)
+    }
+
+    /// Send an email for the tenant, from its own address when verified
+    pub async fn send(
+        &self,
+        provider: &dyn EmailProvider,
+        tenant_id: &TenantId,
+        email: &OutboundEmail,
+    ) -> Result<SenderIdentity, EmailSenderError> {
+        let sender = self.get_sender(tenant_id).await?;
+        Ok(send_as(provider, &self.config, sender.as_ref(), email).await?)
+    }
+}
+
+
+fn row_to_sender(row: &Row) -> TenantEmailSender {
+    TenantEmailSender {
+        from_address: row.get("from_address"),
+        from_name: row.get("from_name"),
+        reply_to: row.get("reply_to"),
+        domain: row.get("domain"),
+        dkim_selector: row.get("dkim_selector"),
+        dkim_domain: row.get("dkim_domain"),
+        verification_token: row.get("verification_token"),
+        verified_at: row.get("verified_at"),
+        last_checked_at: row.get("last_checked_at"),
+        last_error: row.get("last_error"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// TXT records from a fixed zone
+    struct StaticZone(HashMap<String, Vec<String>>);
+
+    fn zone(records: &[(&str, &[&str])]) -> StaticZone {
+        StaticZone(
+            records
+                .iter()
+                .map(|(name, values)| (name.to_string(), values.iter().map(|value| value.to_string()).collect()))
+                .collect(),
+        )
+    }
+
+    impl TxtResolver for StaticZone {
+        fn txt<'a>(&'a self, name: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<String>>> {
+            let values = self.0.get(name).cloned().unwrap_or_default();
+            Box::pin(async move { Ok(values) })
+        }
+    }
+
+    fn sender(verified: bool) -> TenantEmailSender {
+        TenantEmailSender {
+            from_address: "news@brontepress.com".to_string(),
+            from_name: Some("Brontë Press".to_string()),
+            reply_to: Some("editors@brontepress.com".to_string()),
+            domain: "brontepress.com".to_string(),
+            dkim_selector: Some("qs1".to_string()),
+            dkim_domain: None,
+            verification_token: "token123".to_string(),
+            verified_at: verified.then(Utc::now),
+            last_checked_at: None,
+            last_error: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn email() -> OutboundEmail {
+        OutboundEmail {
+            to: "reader@example.com".to_string(),
+            subject: "New release".to_string(),
+            html: "<p>Out now</p>".to_string(),
+            text: "Out now".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verified_tenant_sends_from_its_domain() {
+        let provider = MemoryEmailProvider::default();
+        let config = EmailConfig::default();
+
+        let identity = send_as(&provider, &config, Some(&sender(true)), &email()).await.unwrap();
+        assert_eq!(identity.from_address, "news@brontepress.com");
+        assert_eq!(identity.reply_to.as_deref(), Some("editors@brontepress.com"));
+        assert_eq!(identity.dkim, Some(DkimKey { selector: "qs1".to_string(), domain: "brontepress.com".to_string() }));
+
+        send_as(&provider, &config, None, &email()).await.unwrap();
+        let from: Vec<String> = provider.sent().into_iter().map(|(sender, _)| sender.from_address).collect();
+        assert_eq!(from, vec!["news@brontepress.com".to_string(), config.from_address.clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_unverified_domain_not_used() {
+        let provider = MemoryEmailProvider::default();
+        let config = EmailConfig::default();
+        let unverified = sender(false);
+
+        assert!(matches!(unverified.identity(), Err(EmailSenderError::Unverified(domain)) if domain == "brontepress.com"));
+        let identity = send_as(&provider, &config, Some(&unverified), &email()).await.unwrap();
+        assert_eq!(identity, SenderIdentity::platform(&config));
+        assert_eq!(provider.sent()[0].0.from_address, "no-reply@quillspace.com");
+    }
+
+    #[tokio::test]
+    async fn test_domain_check_reports_missing_records() {
+        let config = EmailConfig::default();
+        let complete = zone(&[
+            ("_quillspace.brontepress.com", &["quillspace-verification=token123"]),
+            ("brontepress.com", &["google-site-verification=abc", "v=spf1 include:_spf.quillspace.com ~all"]),
+            ("qs1._domainkey.brontepress.com", &["v=DKIM1; k=rsa; p=MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQC"]),
+        ]);
+        assert!(check_sender_domain(&complete, &config, &sender(false)).await.unwrap().is_empty());
+
+        let incomplete = zone(&[
+            ("_quillspace.brontepress.com", &["quillspace-verification=other-token"]),
+            ("brontepress.com", &["v=spf1 include:_spf.google.com ~all"]),
+            ("qs1._domainkey.brontepress.com", &["v=DKIM1; p="]),
+        ]);
+        let problems = check_sender_domain(&incomplete, &config, &sender(false)).await.unwrap();
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems[1].contains("include:_spf.quillspace.com"));
+    }
+
+    #[tokio::test]
+    async fn test_sender_used_once_verified() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let service = EmailSenderService::new(app.state.db.postgres().clone(), EmailConfig::default());
+        let provider = MemoryEmailProvider::default();
+        let tenant = &app.tenant_a.id;
+        let request = |from_address: &str| SetEmailSenderRequest {
+            from_address: from_address.to_string(),
+            from_name: Some("Brontë Press".to_string()),
+            reply_to: None,
+            dkim_selector: None,
+            dkim_domain: None,
+        };
+
+        let sender = service.set_sender(tenant, request("news@brontepress.com")).await.unwrap();
+        assert_eq!(service.send(&provider, tenant, &email()).await.unwrap().from_address, "no-reply@quillspace.com");
+
+        let record = format!("{}{}", VERIFICATION_PREFIX, sender.verification_token);
+        let records = zone(&[
+            ("_quillspace.brontepress.com", &[&record]),
+            ("brontepress.com", &["v=spf1 include:_spf.quillspace.com -all"]),
+        ]);
+        let verified = service.verify_sender(tenant, &records).await.unwrap();
+        assert!(verified.verified_at.is_some(), "{:?}", verified.last_error);
+        assert_eq!(service.send(&provider, tenant, &email()).await.unwrap().from_address, "news@brontepress.com");
+
+        // Other tenants neither see the sender nor send as it
+        assert!(service.get_sender(&app.tenant_b.id).await.unwrap().is_none());
+        assert_eq!(
+            service.send(&provider, &app.tenant_b.id, &email()).await.unwrap().from_address,
+            "no-reply@quillspace.com"
+        );
+
+        // A new address on the same domain stays verified; a new domain does not
+        assert!(service.set_sender(tenant, request("hello@brontepress.com")).await.unwrap().verified_at.is_some());
+        let moved = service.set_sender(tenant, request("news@elsewhere.org")).await.unwrap();
+        assert!(moved.verified_at.is_none());
+        assert_ne!(moved.verification_token, sender.verification_token);
+        assert_eq!(service.send(&provider, tenant, &email()).await.unwrap().from_address, "no-reply@quillspace.com");
+    }
+
+    #[test]
+    fn test_sender_request_normalized() {
+        let request = |from_address: &str, dkim_domain: Option<&str>| SetEmailSenderRequest {
+            from_address: from_address.to_string(),
+            from_name: Some("  ".to_string()),
+            reply_to: Some("".to_string()),
+            dkim_selector: Some(" QS1 ".to_string()),
+            dkim_domain: dkim_domain.map(str::to_string),
+        };
+
+        let normalized = request(" News@BrontePress.com ", Some("Mail.Provider.net")).normalized().unwrap();
+        assert_eq!(normalized.from_address, "news@brontepress.com");
+        assert_eq!(normalized.dkim_selector.as_deref(), Some("qs1"));
+        assert_eq!(normalized.dkim_domain.as_deref(), Some("mail.provider.net"));
+        assert_eq!((normalized.from_name, normalized.reply_to), (None, None));
+
+        for invalid in ["news", "@brontepress.com", "news@", "news@bronte press.com", "news@-bronte.com"] {
+            assert!(matches!(request(invalid, None).normalized(), Err(EmailSenderError::Invalid(_))), "{}", invalid);
+        }
+    }
+}
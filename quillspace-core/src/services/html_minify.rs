@@ -0,0 +1,234 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::HtmlMinifyConfig;
+use crate::services::site_analytics::inject_into_head;
+
+/// Elements whose content is copied verbatim: whitespace is significant in
+/// `<pre>`/`<textarea>`, and scripts and styles are not HTML text at all
+const RAW_ELEMENTS: &[&str] = &["pre", "textarea", "script", "style"];
+
+/// The optional `minify` object in a site's settings, e.g.
+/// `{"enabled": true, "critical_css": "body{margin:0}"}`. Unset fields follow the global config.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SiteMinifySettings {
+    pub enabled: Option<bool>,
+    /// Above-the-fold CSS inlined into `<head>` when critical CSS inlining is enabled
+    pub critical_css: Option<String>,
+}
+
+impl SiteMinifySettings {
+    /// Read the minify settings of a site; absent or malformed means use the global config
+    pub fn from_site_settings(site_settings: &Value) -> Self {
+        site_settings
+            .get("minify")
+            .and_then(|minify| serde_json::from_value(minify.clone()).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Apply the global and per-site minification settings to rendered HTML
+pub fn minify_for_site(html: String, config: &HtmlMinifyConfig, site_settings: &Value) -> String {
+    let site = SiteMinifySettings::from_site_settings(site_settings);
+    if !site.enabled.unwrap_or(config.enabled) {
+        return html;
+    }
+
+    let mut html = minify_html(&html, config.strip_comments);
+    if config.inline_critical_css {
+        if let Some(css) = site.critical_css.as_deref().filter(|css| !css.trim().is_empty()) {
+            // A stray `</style` would end the element early and let the rest through as markup
+            let css = css.replace("</", "<\\/");
+            html = inject_into_head(html, &format!("<style>{}</style>", css.trim()));
+        }
+    }
+    html
+}
+
+/// Collapse runs of whitespace in text to a single space and optionally drop comments.
+///
+/// Tags (including attribute values) and the content of `<pre>`, `<textarea>`,
+/// `<script>` and `<style>` are left untouched, as are conditional comments.
+pub fn minify_html(html: &str, strip_comments: bool) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(ch) = rest.chars().next() {
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").map_or(rest.len(), |i| i + 3);
+            if !strip_comments || rest.starts_with("<!--[if") || rest.starts_with("<!--<![endif]") {
+                out.push_str(&rest[..end]);
+            }
+            rest = &rest[end..];
+        } else if ch == '<' {
+            let tag_end = tag_end(rest);
+            out.push_str(&rest[..tag_end]);
+            let tag = &rest[..tag_end];
+            rest = &rest[tag_end..];
+
+            if let Some(element) = raw_element(tag) {
+                let content_end = closing_tag_start(rest, element);
+                out.push_str(&rest[..content_end]);
+                rest = &rest[content_end..];
+            }
+        } else if ch.is_ascii_whitespace() {
+            let run_end = rest.find(|c: char| !c.is_ascii_whitespace()).unwrap_or(rest.len());
+            // Stripped comments can leave two runs back to back
+            if !out.is_empty() && !out.ends_with(' ') && run_end < rest.len() {
+                out.push(' ');
+            }
+            rest = &rest[run_end..];
+        } else {
+            let text_end = rest.find(|c: char| c == '<' || c.is_ascii_whitespace()).unwrap_or(rest.len());
+            out.push_str(&rest[..text_end]);
+            rest = &rest[text_end..];
+        }
+    }
+
+    out
+}
+
+/// Byte offset just past the `>` closing the tag at the start of `html`, skipping quoted attribute values
+fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in html.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return i + 1,
+            (None, _) => {}
+        }
+    }
+    html.len()
+}
+
+/// The raw-text element opened by `tag`, if any
+fn raw_element(tag: &str) -> Option<&'static str> {
+    let name: String = tag[1..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if tag.ends_with("/>") {
+        return None;
+    }
+    RAW_ELEMENTS.iter().copied().find(|element| *element == name)
+}
+
+/// Offset of the `</element` closing tag in `html`, or the end of input if it is never closed
+fn closing_tag_start(html: &str, element: &str) -> usize {
+    let lower = html.to_ascii_lowercase();
+    let closing = format!("</{}", element);
+    lower
+        .match_indices(&closing)
+        .map(|(i, _)| i)
+        .find(|&i| {
+            lower[i + closing.len()..]
+                .chars()
+                .next()
+                .is_none_or(|c| c == '>' || c.is_ascii_whitespace() || c == '/')
+        })
+        .unwrap_or(html.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+    <head>
+        <!-- page metadata -->
+        <title>My   Novel</title>
+        <!--[if IE]><link rel="stylesheet" href="ie.css"><![endif]-->
+    </head>
+    <body>
+        <p class="lead  intro">
+            Chapter    one
+        </p>
+        <pre>
+  indented
+      code
+</pre>
+        <textarea name="notes">  keep
+   this  </textarea>
+        <script>
+            // a comment that must stay
+            var text = "a   b";
+        </script>
+    </body>
+</html>
+"#;
+
+    fn enabled() -> HtmlMinifyConfig {
+        HtmlMinifyConfig { enabled: true, ..HtmlMinifyConfig::default() }
+    }
+
+    /// Text content outside raw elements, with whitespace normalized as a browser would render it
+    fn visible_text(html: &str) -> String {
+        let mut text = String::new();
+        let mut in_tag = false;
+        for c in html.chars() {
+            match c {
+                '<' => in_tag = true,
+                '>' => in_tag = false,
+                c if !in_tag => text.push(c),
+                _ => {}
+            }
+        }
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn test_minified_output_is_smaller() {
+        let minified = minify_html(PAGE, true);
+
+        assert!(minified.len() < PAGE.len());
+        assert!(!minified.contains("page metadata"));
+        assert!(minified.contains("<title>My Novel</title>"));
+        assert!(minified.contains("<p class=\"lead  intro\"> Chapter one </p>"));
+    }
+
+    #[test]
+    fn test_minified_output_is_equivalent() {
+        let minified = minify_html(PAGE, false);
+
+        assert_eq!(visible_text(&minified), visible_text(PAGE));
+        // Minifying again changes nothing
+        assert_eq!(minify_html(&minified, false), minified);
+    }
+
+    #[test]
+    fn test_whitespace_sensitive_elements_preserved() {
+        let minified = minify_html(PAGE, true);
+
+        assert!(minified.contains("<pre>\n  indented\n      code\n</pre>"));
+        assert!(minified.contains("<textarea name=\"notes\">  keep\n   this  </textarea>"));
+        assert!(minified.contains("            // a comment that must stay\n            var text = \"a   b\";\n        </script>"));
+        assert!(minified.contains(r#"<!--[if IE]><link rel="stylesheet" href="ie.css"><![endif]-->"#));
+    }
+
+    #[test]
+    fn test_site_settings_toggle_minification() {
+        let disabled = HtmlMinifyConfig::default();
+        assert_eq!(minify_for_site(PAGE.to_string(), &disabled, &json!({})), PAGE);
+
+        let site_on = json!({ "minify": { "enabled": true } });
+        assert!(minify_for_site(PAGE.to_string(), &disabled, &site_on).len() < PAGE.len());
+
+        let site_off = json!({ "minify": { "enabled": false } });
+        assert_eq!(minify_for_site(PAGE.to_string(), &enabled(), &site_off), PAGE);
+    }
+
+    #[test]
+    fn test_critical_css_inlined_into_head() {
+        let config = HtmlMinifyConfig { inline_critical_css: true, ..enabled() };
+        let settings = json!({ "minify": { "critical_css": "body { margin: 0 }</style><script>" } });
+        let html = minify_for_site(PAGE.to_string(), &config, &settings);
+
+        assert!(html.contains("<style>body { margin: 0 }<\\/style><script></style>\n</head>"));
+    }
+}
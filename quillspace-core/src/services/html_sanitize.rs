@@ -0,0 +1,482 @@
+//! Allowlist sanitizing of content bodies before they are stored.
+//!
+//! Formatting elements are kept with a small set of attributes each; other elements
+//! are unwrapped, keeping their text, except those in [`DROPPED_ELEMENTS`], which go
+//! with everything inside them. Event handlers and `style` are never allowed, and a
+//! link or image whose URL has a scheme other than http(s), mailto or tel loses it.
+//! The output is always well formed: text is escaped and open elements are closed.
+//!
+//! Tenants can exempt trusted authors under `settings.content_sanitization`:
+//!
+//! ```json
+//! { "trusted_roles": ["Admin"], "trusted_users": ["6f1c…"] }
+//! ```
+//!
+//! Sanitized HTML reaches templates as [`SanitizedHtml`], which auto-escaping leaves
+//! alone, so a template can output a body without `|safe` or turning escaping off.
+
+use crate::types::UserRole;
+use anyhow::{Context, Result};
+use deadpool_postgres::GenericClient;
+use serde::{Deserialize, Serialize, Serializer};
+use uuid::Uuid;
+
+/// Key of the sanitization settings in tenant settings
+pub const SANITIZE_SETTING: &str = "content_sanitization";
+
+const ALLOWED_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "blockquote", "br", "caption", "cite", "code", "dd", "del", "div", "dl", "dt", "em",
+    "figcaption", "figure", "h1", "h2", "h3", "h4", "h5", "h6", "hr", "i", "img", "ins", "kbd", "li", "mark",
+    "ol", "p", "pre", "q", "s", "small", "span", "strong", "sub", "sup", "table", "tbody", "td", "tfoot", "th",
+    "thead", "tr", "u", "ul",
+];
+
+const VOID_ELEMENTS: &[&str] = &["br", "hr", "img"];
+
+/// Elements removed together with their content
+pub const DROPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "iframe", "frame", "frameset", "object", "embed", "applet", "noscript", "noembed",
+    "template", "textarea", "select", "svg", "math", "title", "xmp", "plaintext",
+];
+
+/// Attributes allowed on every allowed element
+const GLOBAL_ATTRIBUTES: &[&str] = &["class", "dir", "lang", "title"];
+
+const ELEMENT_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["href", "rel"]),
+    ("blockquote", &["cite"]),
+    ("del", &["cite"]),
+    ("img", &["src", "alt", "width", "height"]),
+    ("ins", &["cite"]),
+    ("ol", &["start", "reversed"]),
+    ("q", &["cite"]),
+    ("td", &["colspan", "rowspan"]),
+    ("th", &["colspan", "rowspan", "scope"]),
+];
+
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "cite"];
+
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto", "tel"];
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SanitizeError {
+    #[error("Invalid content sanitization settings: {0}")]
+    InvalidSettings(String),
+}
+
+/// Who may store bodies without sanitizing; by default nobody
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SanitizeSettings {
+    pub trusted_roles: Vec<UserRole>,
+    pub trusted_users: Vec<Uuid>,
+}
+
+impl SanitizeSettings {
+    /// The settings in tenant `settings`, or the defaults when it has none
+    pub fn from_settings(settings: &serde_json::Value) -> Result<Self, SanitizeError> {
+        match settings.get(SANITIZE_SETTING) {
+            None | Some(serde_json::Value::Null) => Ok(Self::default()),
+            Some(value) if !value.is_object() => Err(SanitizeError::InvalidSettings("expected an object".to_string())),
+            Some(value) => {
+                serde_json::from_value(value.clone()).map_err(|e| SanitizeError::InvalidSettings(e.to_string()))
+            }
+        }
+    }
+
+    /// Whether an author's bodies are stored as written
+    pub fn trusts(&self, role: &UserRole, user_id: Uuid) -> bool {
+        self.trusted_roles.contains(role) || self.trusted_users.contains(&user_id)
+    }
+
+    /// `body` as it should be stored for this author
+    pub fn apply(&self, body: &str, role: &UserRole, user_id: Uuid) -> String {
+        if self.trusts(role, user_id) {
+            body.to_string()
+        } else {
+            sanitize_html(body)
+        }
+    }
+}
+
+/// Reject a settings update whose `content_sanitization` is malformed
+pub fn validate_settings(settings: &serde_json::Value) -> Result<(), SanitizeError> {
+    SanitizeSettings::from_settings(settings).map(|_| ())
+}
+
+/// The tenant's sanitization settings, read from its settings
+pub async fn load_sanitize_settings(client: &impl GenericClient, tenant_id: &Uuid) -> Result<SanitizeSettings> {
+    let settings: Option<serde_json::Value> = client
+        .query_opt("SELECT settings FROM tenants WHERE id = $1", &[tenant_id])
+        .await
+        .context("Failed to load tenant settings")?
+        .and_then(|row| row.get(0));
+    match settings {
+        Some(settings) => {
+            SanitizeSettings::from_settings(&settings).context("Tenant has invalid content sanitization settings")
+        }
+        None => Ok(SanitizeSettings::default()),
+    }
+}
+
+/// HTML that has been through [`sanitize_html`], output by templates without escaping.
+///
+/// It can only be made by sanitizing, so markup that skipped the allowlist (a trusted
+/// author's body included) cannot be passed to a template as safe by mistake.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SanitizedHtml(String);
+
+impl SanitizedHtml {
+    pub fn new(html: &str) -> Self {
+        Self(sanitize_html(html))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A safe string in a template context, a plain string anywhere else
+impl Serialize for SanitizedHtml {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        minijinja::Value::from_safe_string(self.0.clone()).serialize(serializer)
+    }
+}
+
+/// A tag as written, before the allowlist is applied
+struct Tag<'a> {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    attributes: Vec<(String, Option<&'a str>)>,
+}
+
+/// Keep the allowed markup of `html` and escape everything else
+pub fn sanitize_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut open: Vec<String> = Vec::new();
+    let mut rest = html;
+
+    while let Some(lt) = rest.find('<') {
+        push_text(&mut out, &rest[..lt]);
+        rest = &rest[lt..];
+
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |i| &rest[i + 3..]);
+            continue;
+        }
+        let next = rest[1..].chars().next();
+        if matches!(next, Some('!' | '?')) {
+            // Doctypes and processing instructions
+            rest = rest.find('>').map_or("", |i| &rest[i + 1..]);
+            continue;
+        }
+        let starts_tag = match next {
+            Some('/') => rest[2..].starts_with(|c: char| c.is_ascii_alphabetic()),
+            Some(c) => c.is_ascii_alphabetic(),
+            None => false,
+        };
+        if !starts_tag {
+            out.push_str("&lt;");
+            rest = &rest[1..];
+            continue;
+        }
+
+        // A tag cut off by the end of the input is dropped, as browsers do
+        let Some((tag, after)) = parse_tag(rest) else {
+            rest = "";
+            break;
+        };
+        rest = after;
+
+        if tag.closing {
+            if let Some(depth) = open.iter().rposition(|name| *name == tag.name) {
+                for name in open.drain(depth..).rev() {
+                    push_end_tag(&mut out, &name);
+                }
+            }
+        } else if DROPPED_ELEMENTS.contains(&tag.name.as_str()) {
+            if !tag.self_closing {
+                rest = skip_element(rest, &tag.name);
+            }
+        } else if ALLOWED_ELEMENTS.contains(&tag.name.as_str()) {
+            push_start_tag(&mut out, &tag);
+            if !VOID_ELEMENTS.contains(&tag.name.as_str()) {
+                open.push(tag.name);
+            }
+        }
+    }
+    push_text(&mut out, rest);
+
+    for name in open.iter().rev() {
+        push_end_tag(&mut out, name);
+    }
+    out
+}
+
+/// Parse the tag at the start of `input`, returning it and the input after it
+fn parse_tag(input: &str) -> Option<(Tag<'_>, &str)> {
+    let closing = input[1..].starts_with('/');
+    let mut rest = if closing { &input[2..] } else { &input[1..] };
+
+    let name_end = rest.find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>').unwrap_or(rest.len());
+    let name = rest[..name_end].to_ascii_lowercase();
+    rest = &rest[name_end..];
+
+    let mut attributes = Vec::new();
+    let mut self_closing = false;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        match rest.chars().next()? {
+            '>' => return Some((Tag { name, closing, self_closing, attributes }, &rest[1..])),
+            '/' => {
+                self_closing = true;
+                rest = &rest[1..];
+                continue;
+            }
+            _ => self_closing = false,
+        }
+
+        // The first character always belongs to the name, so a stray `=` or quote is consumed
+        let name_end = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| c.is_ascii_whitespace() || matches!(c, '/' | '>' | '='))
+            .map_or(rest.len(), |(i, _)| i);
+        let attribute = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start_matches(|c: char| c.is_ascii_whitespace());
+
+        let mut value = None;
+        if let Some(after_equals) = rest.strip_prefix('=') {
+            rest = after_equals.trim_start_matches(|c: char| c.is_ascii_whitespace());
+            match rest.chars().next()? {
+                quote @ ('"' | '\'') => {
+                    let end = rest[1..].find(quote)? + 1;
+                    value = Some(&rest[1..end]);
+                    rest = &rest[end + 1..];
+                }
+                _ => {
+                    let end = rest.find(|c: char| c.is_ascii_whitespace() || c == '>').unwrap_or(rest.len());
+                    value = Some(&rest[..end]);
+                    rest = &rest[end..];
+                }
+            }
+        }
+        attributes.push((attribute, value));
+    }
+}
+
+/// The input after the closing tag of `element`, or nothing if it is never closed
+fn skip_element<'a>(input: &'a str, element: &str) -> &'a str {
+    let closing = format!("</{}", element);
+    let lowercase = input.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(i) = lowercase[from..].find(&closing) {
+        let start = from + i;
+        let after = start + closing.len();
+        // `</scriptx>` does not close a script
+        if lowercase[after..].starts_with(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>') {
+            return input[after..].find('>').map_or("", |end| &input[after + end + 1..]);
+        }
+        from = after;
+    }
+    ""
+}
+
+fn push_start_tag(out: &mut String, tag: &Tag<'_>) {
+    let element_attributes = ELEMENT_ATTRIBUTES
+        .iter()
+        .find(|(element, _)| *element == tag.name)
+        .map_or(&[][..], |(_, attributes)| *attributes);
+
+    out.push('<');
+    out.push_str(&tag.name);
+    let mut written: Vec<&str> = Vec::new();
+    for (name, value) in &tag.attributes {
+        let name = name.as_str();
+        if written.contains(&name) || !(GLOBAL_ATTRIBUTES.contains(&name) || element_attributes.contains(&name)) {
+            continue;
+        }
+        let value = decode_entities(value.unwrap_or(""));
+        if URL_ATTRIBUTES.contains(&name) && !safe_url(&value) {
+            continue;
+        }
+        written.push(name);
+        out.push(' ');
+        out.push_str(name);
+        out.push_str("=\"");
+        for c in value.chars() {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '"' => out.push_str("&quot;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+    out.push('>');
+}
+
+fn push_end_tag(out: &mut String, name: &str) {
+    out.push_str("</");
+    out.push_str(name);
+    out.push('>');
+}
+
+/// Text between tags; `&` is kept where it starts a character reference
+fn push_text(out: &mut String, text: &str) {
+    for (i, c) in text.char_indices() {
+        match c {
+            '&' if !starts_with_reference(&text[i..]) => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn starts_with_reference(text: &str) -> bool {
+    let Some(end) = text.find(';') else {
+        return false;
+    };
+    let reference = &text[1..end];
+    if let Some(number) = reference.strip_prefix('#') {
+        match number.strip_prefix(['x', 'X']) {
+            Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+            None => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+        }
+    } else {
+        !reference.is_empty() && reference.chars().all(|c| c.is_ascii_alphanumeric())
+    }
+}
+
+/// Decode the character references an attribute value can use to hide a URL scheme
+fn decode_entities(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let end = rest.find(';').filter(|_| starts_with_reference(rest));
+        let character = end.and_then(|end| {
+            let reference = &rest[1..end];
+            match reference.strip_prefix('#') {
+                Some(number) => {
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => number.parse().ok(),
+                    };
+                    Some(code.and_then(char::from_u32).unwrap_or(char::REPLACEMENT_CHARACTER))
+                }
+                None => match reference.to_ascii_lowercase().as_str() {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some('\u{a0}'),
+                    "colon" => Some(':'),
+                    "tab" => Some('\t'),
+                    "newline" => Some('\n'),
+                    _ => None,
+                },
+            }
+        });
+        match (character, end) {
+            (Some(character), Some(end)) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Relative URLs and the allowed schemes. Browsers ignore whitespace and control
+/// characters inside a scheme, so they are ignored here too.
+fn safe_url(url: &str) -> bool {
+    let compact: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match compact.find([':', '/', '?', '#']) {
+        Some(i) if compact[i..].starts_with(':') => URL_SCHEMES.contains(&&compact[..i]),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scripts_and_event_handlers_stripped() {
+        let body = r#"<p>Hello<script>alert(document.cookie)</script></p><img src="x" onerror="alert(1)"><IMG SRC=/cover.png OnError=alert(1)>"#;
+        assert_eq!(sanitize_html(body), r#"<p>Hello</p><img src="x"><img src="/cover.png">"#);
+
+        let hidden = [
+            r#"<a href="javascript:alert(1)">link</a>"#,
+            r#"<a href="JaVaScRiPt&#58;alert(1)">link</a>"#,
+            "<a href=\"java\tscript:alert(1)\">link</a>",
+            r#"<a href="&#x6a;avascript&colon;alert(1)">link</a>"#,
+        ];
+        for body in hidden {
+            assert_eq!(sanitize_html(body), "<a>link</a>", "{}", body);
+        }
+
+        assert_eq!(sanitize_html("<svg><script>alert(1)</script></svg>after"), "after");
+        assert_eq!(sanitize_html("<style>body{display:none}</style><p style=\"color:red\">x</p>"), "<p>x</p>");
+        assert_eq!(sanitize_html("<scr<script>ipt>alert(1)</script>"), "ipt&gt;alert(1)");
+        assert_eq!(sanitize_html("<b>unclosed <script>alert(1)"), "<b>unclosed </b>");
+        assert_eq!(sanitize_html("<p title=\"x\" onclick"), "");
+    }
+
+    #[test]
+    fn test_allowed_formatting_survives() {
+        let body = concat!(
+            r#"<h2 class="lead">Chapter one</h2><p>It was a <strong>dark</strong> and <em>stormy</em> night.<br>"#,
+            r#"See <a href="https://example.com/a?b=1&amp;c=2" rel="nofollow">the map</a>, "#,
+            r#"<a href="/chapters/2#top">next</a> or <a href="mailto:ed@example.com">write</a>.</p>"#,
+            r#"<ul><li>one</li><li>two</li></ul><blockquote cite="https://example.com">Quote</blockquote>"#,
+            r#"<pre><code>let x = 1 &lt; 2;</code></pre><img src="/cover.png" alt="Cover" width="200">"#,
+            r#"<table><tr><td colspan="2">cell</td></tr></table>"#,
+        );
+        assert_eq!(sanitize_html(body), body);
+
+        // Unknown elements are unwrapped; loose text is escaped and elements closed
+        assert_eq!(sanitize_html("<font color=red>Tom & Jerry</font> 1 < 2"), "Tom &amp; Jerry 1 &lt; 2");
+        assert_eq!(sanitize_html("<p><em>open"), "<p><em>open</em></p>");
+        assert_eq!(sanitize_html("<p>a</em>b</p>"), "<p>ab</p>");
+        assert_eq!(sanitize_html("<!-- note --><p>x</p>"), "<p>x</p>");
+    }
+
+    #[test]
+    fn test_trusted_authors_skip_sanitizing() {
+        let settings = SanitizeSettings::from_settings(&json!({
+            SANITIZE_SETTING: { "trusted_roles": ["Admin"], "trusted_users": ["6f1c3a6e-0c4e-4b8e-9a55-2b0c6c1d7e10"] }
+        }))
+        .unwrap();
+        let embed = "<p>Listen</p><iframe src=\"https://player.example.com/1\"></iframe>";
+        let trusted_editor = Uuid::parse_str("6f1c3a6e-0c4e-4b8e-9a55-2b0c6c1d7e10").unwrap();
+
+        assert_eq!(settings.apply(embed, &UserRole::Admin, Uuid::new_v4()), embed);
+        assert_eq!(settings.apply(embed, &UserRole::Editor, trusted_editor), embed);
+        assert_eq!(settings.apply(embed, &UserRole::Editor, Uuid::new_v4()), "<p>Listen</p>");
+        assert_eq!(SanitizeSettings::from_settings(&json!({})).unwrap(), SanitizeSettings::default());
+
+        assert!(validate_settings(&json!({ SANITIZE_SETTING: null })).is_ok());
+        for setting in [json!({ "trusted_roles": ["owner"] }), json!({ "trusted": true }), json!([])] {
+            assert!(validate_settings(&json!({ SANITIZE_SETTING: setting })).is_err(), "{}", setting);
+        }
+    }
+}
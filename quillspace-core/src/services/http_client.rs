@@ -0,0 +1,101 @@
+//! Outbound HTTP to external APIs (Wix, Tinybird, S3, Stripe, ...).
+//!
+//! Every client is built from [`client_builder`], so none waits unboundedly on a hung
+//! service: connects give up after `http_client.connect_timeout_ms` and whole requests
+//! after `http_client.request_timeout_ms`. Integrations without a timeout of their own
+//! share one pooled client, [`shared_client`], rather than building one per call.
+
+use crate::config::HttpClientConfig;
+use reqwest::{Client, ClientBuilder};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+static CONFIG: OnceLock<HttpClientConfig> = OnceLock::new();
+static SHARED: OnceLock<Client> = OnceLock::new();
+
+/// A call to an external API that failed, with timeouts kept apart from other failures
+#[derive(Debug, thiserror::Error)]
+pub enum OutboundHttpError {
+    #[error("{service} did not respond in time")]
+    Timeout { service: &'static str },
+
+    #[error("{service} request failed: {source}")]
+    Http {
+        service: &'static str,
+        #[source]
+        source: reqwest::Error,
+    },
+}
+
+impl OutboundHttpError {
+    pub fn from_reqwest(service: &'static str, error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            OutboundHttpError::Timeout { service }
+        } else {
+            OutboundHttpError::Http { service, source: error }
+        }
+    }
+}
+
+/// Use `config` for the clients built from now on. Called once at startup; later calls
+/// are ignored.
+pub fn configure(config: HttpClientConfig) {
+    let _ = CONFIG.set(config);
+}
+
+fn config() -> &'static HttpClientConfig {
+    CONFIG.get_or_init(HttpClientConfig::default)
+}
+
+/// A builder with the timeouts and pooling of `config`
+pub fn builder_from(config: &HttpClientConfig) -> ClientBuilder {
+    Client::builder()
+        .connect_timeout(Duration::from_millis(config.connect_timeout_ms))
+        .timeout(Duration::from_millis(config.request_timeout_ms))
+        .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_secs))
+}
+
+/// A builder with the configured timeouts; set `timeout` on it to use a different
+/// request timeout
+pub fn client_builder() -> ClientBuilder {
+    builder_from(config())
+}
+
+/// The client shared by integrations using the configured timeouts
+pub fn shared_client() -> Client {
+    SHARED.get_or_init(|| client_builder().build().unwrap_or_default()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use std::time::Instant;
+
+    /// A server that answers after `delay`
+    async fn slow_server(delay: Duration) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind slow server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().fallback(get(move || async move {
+            tokio::time::sleep(delay).await;
+            "{}"
+        }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        base_url
+    }
+
+    #[tokio::test]
+    async fn test_request_aborted_at_configured_timeout() {
+        let base_url = slow_server(Duration::from_secs(5)).await;
+        let config = HttpClientConfig { request_timeout_ms: 200, ..HttpClientConfig::default() };
+        let client = builder_from(&config).build().unwrap();
+
+        let started = Instant::now();
+        let error = client.get(&base_url).send().await.expect_err("Slow server answered in time");
+        assert!(started.elapsed() < Duration::from_secs(2), "{:?}", started.elapsed());
+        assert!(matches!(
+            OutboundHttpError::from_reqwest("Slow API", error),
+            OutboundHttpError::Timeout { service: "Slow API" }
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+use fixed_decimal::{FixedDecimal, Sign};
+use icu_decimal::FixedDecimalFormatter;
+use icu_experimental::dimension::provider::currency::{CurrencyEssentialsV1Marker, PatternSelection, PlaceholderValue};
+use icu_experimental::provider::Baked as CurrencyData;
+use icu_locid::Locale as IcuLocale;
+use icu_provider::{DataLocale, DataPayload, DataProvider, DataRequest, DataResponse};
+use tinystr::TinyAsciiStr;
+
+/// Locale used when neither the site nor the visitor specifies one
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Pick the locale to render with: the site's fixed `locale`, then the visitor's
+/// preferred `Accept-Language` tag, then the site's default locale
+pub fn resolve_locale(site_locale: Option<&str>, site_default_locale: Option<&str>, accept_language: Option<&str>) -> String {
+    configured(site_locale)
+        .or_else(|| accept_language.and_then(preferred_language))
+        .unwrap_or_else(|| default_locale(site_locale, site_default_locale))
+}
+
+/// Locale a multilingual site falls back to: its `default_locale`, then its `locale`, then [`DEFAULT_LOCALE`]
+pub fn default_locale(site_locale: Option<&str>, site_default_locale: Option<&str>) -> String {
+    configured(site_default_locale)
+        .or_else(|| configured(site_locale))
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+fn configured(locale: Option<&str>) -> Option<String> {
+    locale.map(str::trim).filter(|locale| !locale.is_empty()).map(str::to_string)
+}
+
+/// Whether `tag` is a well-formed BCP 47 locale such as `de-AT`
+pub fn is_locale_tag(tag: &str) -> bool {
+    tag.parse::<IcuLocale>().is_ok()
+}
+
+/// Highest-weighted tag in an `Accept-Language` header, ignoring `*`
+fn preferred_language(header: &str) -> Option<String> {
+    accepted_languages(header).into_iter().next()
+}
+
+/// Tags in an `Accept-Language` header, most preferred first, ignoring `*` and `q=0`
+pub fn accepted_languages(header: &str) -> Vec<String> {
+    let mut tags: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.trim().split(';');
+            let tag = parts.next()?.trim();
+            let weight = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!tag.is_empty() && tag != "*" && weight > 0.0).then(|| (tag.to_string(), weight))
+        })
+        .collect();
+    // Stable sort keeps header order for equal weights
+    tags.sort_by(|a, b| b.1.total_cmp(&a.1));
+    tags.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// Split a leading locale segment off a public path: `/de-DE/my-post` → (`Some("de-DE")`, `/my-post`).
+/// Only segments shaped like a language tag (`xx` or `xx-YY`) count as a prefix.
+pub fn split_locale_prefix(path: &str) -> (Option<&str>, &str) {
+    let trimmed = path.trim_start_matches('/');
+    let segment = trimmed.split('/').next().unwrap_or("");
+
+    let mut parts = segment.split('-');
+    let language = parts.next().unwrap_or("");
+    let region = parts.next();
+    let is_locale = language.len() == 2
+        && language.chars().all(|c| c.is_ascii_lowercase())
+        && region.is_none_or(|region| region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic()))
+        && parts.next().is_none();
+
+    if is_locale {
+        (Some(segment), &trimmed[segment.len()..])
+    } else {
+        (None, path)
+    }
+}
+
+/// Whether two locale tags name the same language (`de-AT` matches `de-DE` and `de`)
+pub fn same_language(a: &str, b: &str) -> bool {
+    language(a) == language(b)
+}
+
+/// Primary language subtag, e.g. `de` for `de-AT`
+fn language(locale: &str) -> String {
+    locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or(locale)
+        .to_ascii_lowercase()
+}
+
+/// CLDR data locale for a tag, falling back to its language and then English
+fn data_locale(locale: &str) -> DataLocale {
+    locale
+        .replace('_', "-")
+        .parse::<IcuLocale>()
+        .or_else(|_| language(locale).parse::<IcuLocale>())
+        .unwrap_or(icu_locid::locale!("en"))
+        .into()
+}
+
+/// `value` rounded half away from zero to `decimals` places, keeping trailing zeros
+fn fixed_decimal(value: f64, decimals: usize) -> FixedDecimal {
+    let scale = 10f64.powi(decimals as i32);
+    let scaled = (value.abs() * scale).round() as u64;
+    let sign = if value < 0.0 && scaled > 0 { Sign::Negative } else { Sign::None };
+    FixedDecimal::from(scaled)
+        .multiplied_pow10(-(decimals as i16))
+        .padded_end(-(decimals as i16))
+        .with_sign(sign)
+}
+
+/// Format a number with the locale's grouping and decimal separators
+pub fn format_number(value: f64, decimals: usize, locale: &str) -> String {
+    let decimal = fixed_decimal(value, decimals);
+    match FixedDecimalFormatter::try_new(&data_locale(locale), Default::default()) {
+        Ok(formatter) => formatter.format_to_string(&decimal),
+        Err(_) => decimal.to_string(),
+    }
+}
+
+/// Minor units of a currency per ISO 4217; most use two
+fn currency_decimals(code: &str) -> usize {
+    match code {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX" | "VND"
+        | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+/// Format an amount of money for a locale with its CLDR currency pattern and symbol,
+/// e.g. `€1,234.50` in `en-US` or `1.234,50 €` in `de-DE`
+pub fn format_currency(value: f64, currency_code: &str, locale: &str) -> String {
+    let code = currency_code.to_ascii_uppercase();
+    let decimals = currency_decimals(&code);
+    let amount = format_number(value.abs(), decimals, locale);
+    let sign = if fixed_decimal(value, decimals).sign() == Sign::Negative { "-" } else { "" };
+
+    let request = DataRequest { locale: &data_locale(locale), metadata: Default::default() };
+    let essentials: Option<DataPayload<CurrencyEssentialsV1Marker>> =
+        DataProvider::<CurrencyEssentialsV1Marker>::load(&CurrencyData, request)
+            .and_then(DataResponse::take_payload)
+            .ok();
+    let Some(essentials) = essentials else {
+        return format!("{}{}\u{a0}{}", sign, amount, code);
+    };
+    let essentials = essentials.get();
+
+    let config = TinyAsciiStr::<3>::from_str(&code)
+        .ok()
+        .and_then(|tiny| essentials.pattern_config_map.get_copied(&tiny.to_unvalidated()))
+        .unwrap_or(essentials.default_pattern_config);
+    let symbol = match config.short_placeholder_value {
+        Some(PlaceholderValue::Index(index)) => essentials.placeholders.get(index.into()),
+        Some(PlaceholderValue::ISO) | None => None,
+    }
+    .unwrap_or(code.as_str());
+    let pattern = match config.short_pattern_selection {
+        PatternSelection::Standard => essentials.standard_pattern.as_ref(),
+        PatternSelection::StandardAlphaNextToNumber => essentials.standard_alpha_next_to_number_pattern.as_ref(),
+    };
+
+    match pattern {
+        Some(pattern) => format!("{}{}", sign, pattern.interpolate_to_string((amount.as_str(), symbol))),
+        None => format!("{}{}\u{a0}{}", sign, amount, symbol),
+    }
+}
+
+/// Locale for month and weekday names in formatted dates
+pub fn date_locale(locale: &str) -> chrono::Locale {
+    let posix = locale.replace('-', "_");
+    let language = language(locale);
+    [posix, format!("{}_{}", language, language.to_ascii_uppercase())]
+        .iter()
+        .find_map(|name| chrono::Locale::try_from(name.as_str()).ok())
+        .unwrap_or(chrono::Locale::en_US)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_format_per_locale() {
+        assert_eq!(format_number(1234567.891, 2, "en-US"), "1,234,567.89");
+        assert_eq!(format_number(1234567.891, 2, "de-DE"), "1.234.567,89");
+        assert_eq!(format_number(-0.5, 0, "en"), "-1");
+        assert_eq!(format_number(42.0, 0, "xx-unknown"), "42");
+    }
+
+    #[test]
+    fn test_currency_per_locale() {
+        assert_eq!(format_currency(1234.5, "EUR", "en-US"), "€1,234.50");
+        assert_eq!(format_currency(1234.5, "EUR", "de-DE"), "1.234,50\u{a0}€");
+        assert_eq!(format_currency(1234.5, "JPY", "ja"), "￥1,235");
+        assert_eq!(format_currency(1234.5, "EUR", "fr-FR"), "1\u{202f}234,50\u{a0}€");
+        assert_eq!(format_currency(-9.5, "USD", "en-GB"), "-US$9.50");
+        assert_eq!(format_currency(12.0, "KWD", "en"), "KWD\u{a0}12.000");
+    }
+
+    #[test]
+    fn test_resolve_locale_order() {
+        assert_eq!(resolve_locale(Some("fr-FR"), None, Some("de-DE")), "fr-FR");
+        assert_eq!(resolve_locale(None, None, Some("en;q=0.5, de-DE, *;q=0.1")), "de-DE");
+        assert_eq!(resolve_locale(Some(" "), None, None), DEFAULT_LOCALE);
+        assert_eq!(resolve_locale(None, Some("es-ES"), None), "es-ES");
+        assert_eq!(default_locale(Some("fr-FR"), None), "fr-FR");
+        assert!(is_locale_tag("de-AT") && !is_locale_tag("german please"));
+    }
+
+    #[test]
+    fn test_locale_prefix() {
+        assert_eq!(split_locale_prefix("/de-DE/my-post"), (Some("de-DE"), "/my-post"));
+        assert_eq!(split_locale_prefix("/fr/my-post"), (Some("fr"), "/my-post"));
+        assert_eq!(split_locale_prefix("/my-post"), (None, "/my-post"));
+        assert_eq!(split_locale_prefix("/blog/my-post"), (None, "/blog/my-post"));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::config::ObservabilityConfig;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle, PrometheusRecorder};
+use std::fmt;
+use std::sync::Arc;
+
+/// The Prometheus registry an app instance records into.
+///
+/// Nothing is installed globally, so every app (and every test) can have its own.
+/// Code that emits metrics does so inside `record`. Without a registry, metrics go to
+/// whatever recorder is current, which outside tests means they are dropped.
+#[derive(Clone, Default)]
+pub struct MetricsRegistry {
+    prometheus: Option<Arc<Prometheus>>,
+}
+
+struct Prometheus {
+    recorder: PrometheusRecorder,
+    handle: PrometheusHandle,
+}
+
+impl MetricsRegistry {
+    /// A new, empty Prometheus registry
+    pub fn prometheus() -> Self {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        Self {
+            prometheus: Some(Arc::new(Prometheus { recorder, handle })),
+        }
+    }
+
+    /// A Prometheus registry when `metrics_enabled`, else none
+    pub fn from_config(config: &ObservabilityConfig) -> Self {
+        if config.metrics_enabled {
+            Self::prometheus()
+        } else {
+            Self::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.prometheus.is_some()
+    }
+
+    /// Run `f`, sending any metrics it emits to this registry
+    pub fn record<T>(&self, f: impl FnOnce() -> T) -> T {
+        match &self.prometheus {
+            Some(prometheus) => metrics::with_local_recorder(&prometheus.recorder, f),
+            None => f(),
+        }
+    }
+
+    /// Everything recorded so far, in the Prometheus text format
+    pub fn render(&self) -> Option<String> {
+        self.prometheus.as_ref().map(|prometheus| {
+            // Without the exporter's background task, histograms are only compacted here
+            prometheus.handle.run_upkeep();
+            prometheus.handle.render()
+        })
+    }
+}
+
+impl fmt::Debug for MetricsRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetricsRegistry")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+/// `GET /metrics`: the app's registry for Prometheus to scrape; `404` when metrics are off
+pub async fn metrics_handler(State(registry): State<MetricsRegistry>) -> Response {
+    match registry.render() {
+        Some(body) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            body,
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    /// A minimal app: `/hit` bumps a counter, `/metrics` serves the registry
+    fn app(registry: MetricsRegistry) -> Router {
+        async fn hit(State(registry): State<MetricsRegistry>) -> &'static str {
+            registry.record(|| metrics::counter!("test_hits_total").increment(1));
+            "ok"
+        }
+
+        Router::new()
+            .route("/hit", get(hit))
+            .route("/metrics", get(metrics_handler))
+            .with_state(registry)
+    }
+
+    async fn get_body(app: &Router, path: &str) -> (StatusCode, String) {
+        let request = axum::http::Request::builder()
+            .uri(path)
+            .body(Body::empty())
+            .expect("Failed to build request");
+        let response = app.clone().oneshot(request).await.expect("Request failed");
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("Failed to read body");
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[tokio::test]
+    async fn test_app_instances_have_independent_registries() {
+        let first = app(MetricsRegistry::prometheus());
+        let second = app(MetricsRegistry::prometheus());
+
+        for _ in 0..3 {
+            get_body(&first, "/hit").await;
+        }
+        get_body(&second, "/hit").await;
+
+        let (status, first_metrics) = get_body(&first, "/metrics").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(first_metrics.contains("test_hits_total 3"), "{}", first_metrics);
+
+        let (_, second_metrics) = get_body(&second, "/metrics").await;
+        assert!(second_metrics.contains("test_hits_total 1"), "{}", second_metrics);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_absent_when_disabled() {
+        let app = app(MetricsRegistry::default());
+        assert_eq!(get_body(&app, "/hit").await.0, StatusCode::OK);
+        assert_eq!(get_body(&app, "/metrics").await.0, StatusCode::NOT_FOUND);
+    }
+}
@@ -1,17 +1,80 @@
+pub mod admin_account;
 pub mod analytics;
+pub mod analytics_events;
+pub mod analytics_query;
+pub mod analytics_replay;
+pub mod analytics_retention;
+pub mod analytics_writer;
+pub mod api_key;
 pub mod asset;
+pub mod billing;
+pub mod bulk_publish;
+pub mod cache_warm;
+pub mod clock;
+pub mod cdn;
 pub mod composition;
 pub mod content;
+pub mod content_comment;
+pub mod content_diff;
+pub mod content_fields;
+pub mod content_related;
+pub mod content_review;
+pub mod content_revision;
+pub mod custom_roles;
+pub mod dev_templates;
+pub mod draft_patch;
+pub mod email_automation;
+pub mod email_jobs;
+pub mod email_sender;
+pub mod html_minify;
+pub mod html_sanitize;
+pub mod http_client;
+pub mod locale;
+pub mod metrics_registry;
+pub mod notification;
+pub mod object_store;
 pub mod page;
+pub mod page_custom_code;
 pub mod pages;
+pub mod plans;
+// Not wired into any route yet: proposals, projects and team assignment have no storage,
+// so only its tests build it
+#[cfg(test)]
+pub mod project_kickoff;
+pub mod public_url;
+pub mod publish_cache;
+pub mod publish_schedule;
+pub mod redirect;
+pub mod render_metrics;
+pub mod request_validation;
 pub mod site;
+pub mod site_access;
+pub mod site_analytics;
+pub mod site_backup;
+pub mod site_error_pages;
+pub mod site_export;
+pub mod site_theme;
+pub mod site_validation;
+pub mod sitemap;
+pub mod slug;
+pub mod subdomain;
 pub mod rls;
+pub mod session;
 pub mod template_cache;
+pub mod template_data;
 pub mod template_engine;
+pub mod template_schema;
+pub mod template_source_cache;
+pub mod template_syntax;
 pub mod tenant;
+pub mod tenant_bootstrap;
+pub mod timezone;
+pub mod translation;
 pub mod user;
+pub mod user_import;
 pub mod wix_api;
 pub mod connected_websites;
+pub mod business_info_sync;
 
 // Re-export commonly used services
 pub use template_engine::TemplateEngine;
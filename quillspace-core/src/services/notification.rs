@@ -0,0 +1,31 @@
+use crate::types::TenantId;
+use anyhow::{Context, Result};
+use deadpool_postgres::GenericClient;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Someone commented on content the user authored
+pub const CONTENT_COMMENT_ADDED: &str = "content_comment_added";
+
+/// The user was invited to a tenant and must choose a password
+pub const USER_INVITED: &str = "user_invited";
+
+/// Queue a notification for a user. The email worker delivers rows without
+/// `emailed_at`; pass a transaction so the notification commits with its cause.
+pub async fn enqueue_notification(
+    client: &impl GenericClient,
+    tenant_id: &TenantId,
+    user_id: Uuid,
+    kind: &str,
+    payload: &Value,
+) -> Result<Uuid> {
+    let id: Uuid = client
+        .query_one(
+            "INSERT INTO notifications (tenant_id, user_id, kind, payload) VALUES ($1, $2, $3, $4) RETURNING id",
+            &[tenant_id.as_uuid(), &user_id, &kind, payload],
+        )
+        .await
+        .context("Failed to queue notification")?
+        .get(0);
+    Ok(id)
+}
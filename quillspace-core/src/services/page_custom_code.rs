@@ -0,0 +1,491 @@
+use rand::RngCore;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::services::site_analytics::inject_into_head;
+
+/// Stands in for the CSP nonce in stored and cached HTML; `with_csp_nonce` swaps
+/// in a fresh value for every response
+pub const CSP_NONCE_PLACEHOLDER: &str = "__QUILLSPACE_CSP_NONCE__";
+
+/// Tenant capability (in `settings.capabilities`) that allows custom JavaScript on pages
+pub const CUSTOM_SCRIPTS_CAPABILITY: &str = "custom_scripts";
+
+/// Why a page's custom code was refused
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CustomCodeError {
+    #[error("Custom code may only contain <style> and <script> elements")]
+    UnsupportedMarkup,
+
+    #[error("Unsupported attribute '{0}'")]
+    UnsupportedAttribute(String),
+
+    #[error("Unclosed <{0}> element")]
+    Unclosed(&'static str),
+
+    #[error("Invalid CSS: {0}")]
+    InvalidCss(&'static str),
+
+    #[error("Invalid script: {0}")]
+    InvalidScript(&'static str),
+
+    #[error("Custom JavaScript is not enabled for this tenant")]
+    ScriptsNotAllowed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CustomElement {
+    Style {
+        media: Option<String>,
+        css: String,
+    },
+    Script {
+        /// External script, always `https://`
+        src: Option<String>,
+        module: bool,
+        is_async: bool,
+        defer: bool,
+        code: String,
+    },
+}
+
+/// A page's `custom_head` or `custom_body`: a sequence of `<style>` and `<script>`
+/// elements, optionally separated by whitespace and comments. Anything else is refused.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CustomCode {
+    elements: Vec<CustomElement>,
+}
+
+impl CustomCode {
+    pub fn parse(source: &str) -> Result<Self, CustomCodeError> {
+        let mut elements = Vec::new();
+        let mut rest = source;
+
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let end = comment.find("-->").ok_or(CustomCodeError::UnsupportedMarkup)?;
+                rest = &comment[end + 3..];
+                continue;
+            }
+
+            let (name, attributes, after_tag) = parse_open_tag(rest)?;
+            let closing = format!("</{}", name);
+            let end = after_tag
+                .to_ascii_lowercase()
+                .find(&closing)
+                .ok_or(CustomCodeError::Unclosed(name))?;
+            let body = &after_tag[..end];
+            rest = after_tag[end + closing.len()..]
+                .trim_start()
+                .strip_prefix('>')
+                .ok_or(CustomCodeError::Unclosed(name))?;
+
+            elements.push(match name {
+                "style" => parse_style(attributes, body)?,
+                _ => parse_script(attributes, body)?,
+            });
+        }
+
+        Ok(Self { elements })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn has_scripts(&self) -> bool {
+        self.elements.iter().any(|element| matches!(element, CustomElement::Script { .. }))
+    }
+
+    fn without_scripts(mut self) -> Self {
+        self.elements.retain(|element| matches!(element, CustomElement::Style { .. }));
+        self
+    }
+
+    /// Markup for the page, every element carrying the nonce placeholder
+    fn to_html(&self) -> String {
+        let nonce = format!(r#" nonce="{}""#, CSP_NONCE_PLACEHOLDER);
+        let mut html = String::new();
+        for element in &self.elements {
+            match element {
+                CustomElement::Style { media, css } => {
+                    html.push_str("<style");
+                    html.push_str(&nonce);
+                    if let Some(media) = media {
+                        html.push_str(&format!(r#" media="{}""#, escape_attribute(media)));
+                    }
+                    html.push('>');
+                    html.push_str(css);
+                    html.push_str("</style>");
+                }
+                CustomElement::Script { src, module, is_async, defer, code } => {
+                    html.push_str("<script");
+                    html.push_str(&nonce);
+                    if let Some(src) = src {
+                        html.push_str(&format!(r#" src="{}""#, escape_attribute(src)));
+                    }
+                    if *module {
+                        html.push_str(r#" type="module""#);
+                    }
+                    if *is_async {
+                        html.push_str(" async");
+                    }
+                    if *defer {
+                        html.push_str(" defer");
+                    }
+                    html.push('>');
+                    html.push_str(code);
+                    html.push_str("</script>");
+                }
+            }
+            html.push('\n');
+        }
+        html
+    }
+}
+
+/// Check custom code before it is saved on a page
+pub fn validate_custom_code(source: &str, scripts_allowed: bool) -> Result<(), CustomCodeError> {
+    let code = CustomCode::parse(source)?;
+    if code.has_scripts() && !scripts_allowed {
+        return Err(CustomCodeError::ScriptsNotAllowed);
+    }
+    Ok(())
+}
+
+/// Whether a tenant's settings grant the custom scripts capability, e.g.
+/// `{"capabilities": ["custom_scripts"]}`. Without it, JavaScript is blocked.
+pub fn scripts_allowed(tenant_settings: &Value) -> bool {
+    tenant_settings
+        .get("capabilities")
+        .and_then(Value::as_array)
+        .is_some_and(|capabilities| capabilities.iter().any(|c| c.as_str() == Some(CUSTOM_SCRIPTS_CAPABILITY)))
+}
+
+/// Add a page's custom code to its HTML: `custom_head` before `</head>`, `custom_body`
+/// before `</body>`. Scripts are dropped unless the tenant may use them, and code
+/// that no longer validates is skipped rather than failing the page.
+///
+/// When anything is injected, every script on the page gets the nonce placeholder so
+/// the template's own scripts keep running under the page's CSP.
+pub fn inject_custom_code(
+    html: String,
+    custom_head: Option<&str>,
+    custom_body: Option<&str>,
+    scripts_allowed: bool,
+) -> String {
+    let prepare = |source: Option<&str>| -> CustomCode {
+        let Some(source) = source.filter(|source| !source.trim().is_empty()) else {
+            return CustomCode::default();
+        };
+        match CustomCode::parse(source) {
+            Ok(code) if code.has_scripts() && !scripts_allowed => {
+                warn!("Dropping custom scripts from page, tenant lacks the {} capability", CUSTOM_SCRIPTS_CAPABILITY);
+                code.without_scripts()
+            }
+            Ok(code) => code,
+            Err(e) => {
+                warn!("Skipping invalid custom page code: {}", e);
+                CustomCode::default()
+            }
+        }
+    };
+    let head = prepare(custom_head);
+    let body = prepare(custom_body);
+    if head.is_empty() && body.is_empty() {
+        return html;
+    }
+
+    let html = nonce_scripts(&html);
+    let html = inject_into_head(html, head.to_html().trim_end());
+    inject_before_body_end(html, body.to_html().trim_end())
+}
+
+/// Fill in a fresh nonce for one response. Returns the body and its
+/// `Content-Security-Policy`, or `None` for pages without custom code.
+pub fn with_csp_nonce(html: &str) -> Option<(String, String)> {
+    if !html.contains(CSP_NONCE_PLACEHOLDER) {
+        return None;
+    }
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let nonce = hex::encode(bytes);
+    Some((html.replace(CSP_NONCE_PLACEHOLDER, &nonce), content_security_policy(&nonce)))
+}
+
+/// Only scripts carrying the nonce (and what they load) may run
+pub fn content_security_policy(nonce: &str) -> String {
+    format!("script-src 'nonce-{}' 'strict-dynamic'; object-src 'none'; base-uri 'self'", nonce)
+}
+
+/// Insert `snippet` right before the last closing `</body>` tag, or at the end without one
+fn inject_before_body_end(mut html: String, snippet: &str) -> String {
+    if snippet.is_empty() {
+        return html;
+    }
+    let index = html.to_ascii_lowercase().rfind("</body>").unwrap_or(html.len());
+    html.insert_str(index, &format!("{}\n", snippet));
+    html
+}
+
+/// Give every `<script>` tag without a nonce the placeholder
+fn nonce_scripts(html: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let mut out = String::with_capacity(html.len());
+    let mut copied = 0;
+
+    for (index, _) in lower.match_indices("<script") {
+        let after = index + "<script".len();
+        let is_tag = lower[after..].starts_with(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/');
+        let tag_end = lower[after..].find('>').map_or(lower.len(), |end| after + end);
+        if !is_tag || lower[after..tag_end].contains("nonce=") {
+            continue;
+        }
+        out.push_str(&html[copied..after]);
+        out.push_str(&format!(r#" nonce="{}""#, CSP_NONCE_PLACEHOLDER));
+        copied = after;
+    }
+    out.push_str(&html[copied..]);
+    out
+}
+
+/// A tag's attributes in source order, `None` for a bare attribute such as `async`
+type Attributes = Vec<(String, Option<String>)>;
+
+/// Name, attributes and the text after an opening `<style>` or `<script>` tag
+fn parse_open_tag(source: &str) -> Result<(&'static str, Attributes, &str), CustomCodeError> {
+    let lower = source.to_ascii_lowercase();
+    let name = ["style", "script"]
+        .into_iter()
+        .find(|name| {
+            lower
+                .strip_prefix('<')
+                .and_then(|tag| tag.strip_prefix(*name))
+                .is_some_and(|after| after.starts_with(|c: char| c.is_ascii_whitespace() || c == '>'))
+        })
+        .ok_or(CustomCodeError::UnsupportedMarkup)?;
+
+    let mut rest = &source[name.len() + 1..];
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix('>') {
+            return Ok((name, attributes, after));
+        }
+        let name_end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+            .unwrap_or(rest.len());
+        if name_end == 0 {
+            return Err(CustomCodeError::Unclosed(name));
+        }
+        let attribute = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(value) => {
+                let value = value.trim_start();
+                let (parsed, remaining) = match value.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = value[1..].find(quote).ok_or(CustomCodeError::Unclosed(name))?;
+                        (&value[1..end + 1], &value[end + 2..])
+                    }
+                    _ => {
+                        let end = value
+                            .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                            .unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+                rest = remaining;
+                Some(parsed.to_string())
+            }
+            None => None,
+        };
+        attributes.push((attribute, value));
+    }
+}
+
+fn parse_style(attributes: Attributes, css: &str) -> Result<CustomElement, CustomCodeError> {
+    let mut media = None;
+    for (name, value) in attributes {
+        match (name.as_str(), value) {
+            ("media", Some(value)) => media = Some(value),
+            _ => return Err(CustomCodeError::UnsupportedAttribute(name)),
+        }
+    }
+    validate_css(css)?;
+    Ok(CustomElement::Style { media, css: css.trim().to_string() })
+}
+
+fn parse_script(attributes: Attributes, code: &str) -> Result<CustomElement, CustomCodeError> {
+    let (mut src, mut module, mut is_async, mut defer) = (None, false, false, false);
+    for (name, value) in attributes {
+        match (name.as_str(), value.as_deref()) {
+            ("src", Some(url)) => {
+                let valid = url.starts_with("https://")
+                    && url.len() > "https://".len()
+                    && !url.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>'));
+                if !valid {
+                    return Err(CustomCodeError::InvalidScript("src must be an https URL"));
+                }
+                src = Some(url.to_string());
+            }
+            ("type", Some("module")) => module = true,
+            ("type", Some("text/javascript")) => {}
+            ("async", None) => is_async = true,
+            ("defer", None) => defer = true,
+            _ => return Err(CustomCodeError::UnsupportedAttribute(name)),
+        }
+    }
+
+    // These switch the HTML parser into states where `</script>` no longer ends the element
+    let lower = code.to_ascii_lowercase();
+    if lower.contains("<!--") || lower.contains("<script") {
+        return Err(CustomCodeError::InvalidScript("must not contain '<!--' or '<script'"));
+    }
+    if src.is_some() && !code.trim().is_empty() {
+        return Err(CustomCodeError::InvalidScript("a script with src must be empty"));
+    }
+    Ok(CustomElement::Script { src, module, is_async, defer, code: code.trim().to_string() })
+}
+
+/// Tokenize a stylesheet far enough to know it is well formed: comments and strings
+/// are terminated, brackets balance, and nothing can load script or other stylesheets
+fn validate_css(css: &str) -> Result<(), CustomCodeError> {
+    let lower = css.to_ascii_lowercase();
+    if lower.contains("</") || lower.contains("<!--") {
+        return Err(CustomCodeError::InvalidCss("must not contain markup"));
+    }
+    for forbidden in ["@import", "javascript:", "expression(", "-moz-binding", "behavior:"] {
+        if lower.contains(forbidden) {
+            return Err(CustomCodeError::InvalidCss("uses a feature that is not allowed"));
+        }
+    }
+
+    let mut brackets = Vec::new();
+    let mut chars = css.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.as_str().starts_with('*') => {
+                let comment = &chars.as_str()[1..];
+                let end = comment.find("*/").ok_or(CustomCodeError::InvalidCss("unterminated comment"))?;
+                chars = comment[end + 2..].chars();
+            }
+            '"' | '\'' => loop {
+                match chars.next() {
+                    Some('\\') => {
+                        chars.next();
+                    }
+                    Some('\n') | None => return Err(CustomCodeError::InvalidCss("unterminated string")),
+                    Some(quote) if quote == c => break,
+                    Some(_) => {}
+                }
+            },
+            '\\' => {
+                chars.next();
+            }
+            '{' | '(' | '[' => brackets.push(c),
+            '}' | ')' | ']' => {
+                let open = match c {
+                    '}' => '{',
+                    ')' => '(',
+                    _ => '[',
+                };
+                if brackets.pop() != Some(open) {
+                    return Err(CustomCodeError::InvalidCss("unbalanced brackets"));
+                }
+            }
+            _ => {}
+        }
+    }
+    if !brackets.is_empty() {
+        return Err(CustomCodeError::InvalidCss("unbalanced brackets"));
+    }
+    Ok(())
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PAGE: &str = "<html><head><title>Post</title><script src=\"/app.js\"></script></head><body><main></main></body></html>";
+
+    #[test]
+    fn test_css_injected_into_head() {
+        let html = inject_custom_code(
+            PAGE.to_string(),
+            Some("<style media=\"screen\">h1 { color: #c00; content: \"}\"; }</style>"),
+            None,
+            false,
+        );
+
+        let style = format!(
+            r#"<style nonce="{}" media="screen">h1 {{ color: #c00; content: "}}"; }}</style>"#,
+            CSP_NONCE_PLACEHOLDER
+        );
+        assert!(html.find(&style).unwrap() < html.find("</head>").unwrap());
+        // The template's own script can still run under the page's CSP
+        assert!(html.contains(&format!(r#"<script nonce="{}" src="/app.js">"#, CSP_NONCE_PLACEHOLDER)));
+
+        for css in ["h1 { color: red", "a { background: url(javascript:alert(1)) }", "</style><script>"] {
+            assert!(matches!(
+                CustomCode::parse(&format!("<style>{}</style>", css)),
+                Err(CustomCodeError::InvalidCss(_) | CustomCodeError::Unclosed(_))
+            ));
+        }
+        assert_eq!(CustomCode::parse("<div>hi</div>"), Err(CustomCodeError::UnsupportedMarkup));
+    }
+
+    #[test]
+    fn test_js_blocked_without_capability() {
+        let script = "<script>document.body.dataset.ready = '1';</script>";
+        let tenant = json!({ "plan": "publisher" });
+        assert!(!scripts_allowed(&tenant));
+        assert_eq!(validate_custom_code(script, scripts_allowed(&tenant)), Err(CustomCodeError::ScriptsNotAllowed));
+
+        // Code saved before the capability was withdrawn is dropped at render
+        let html = inject_custom_code(PAGE.to_string(), None, Some(script), false);
+        assert_eq!(html, PAGE);
+
+        let privileged = json!({ "capabilities": ["custom_scripts"] });
+        assert!(validate_custom_code(script, scripts_allowed(&privileged)).is_ok());
+        let html = inject_custom_code(PAGE.to_string(), None, Some(script), true);
+        assert!(html.find("dataset.ready").unwrap() < html.find("</body>").unwrap());
+    }
+
+    #[test]
+    fn test_nonce_matches_csp_header() {
+        let html = inject_custom_code(
+            PAGE.to_string(),
+            Some("<style>body { margin: 0 }</style>"),
+            Some("<script src=\"https://cdn.example.com/widget.js\" defer></script>"),
+            true,
+        );
+
+        let (body, policy) = with_csp_nonce(&html).unwrap();
+        let nonce = policy
+            .strip_prefix("script-src 'nonce-")
+            .and_then(|rest| rest.split('\'').next())
+            .unwrap();
+        assert_eq!(nonce.len(), 32);
+        assert!(!body.contains(CSP_NONCE_PLACEHOLDER));
+        assert_eq!(body.matches(&format!(r#"nonce="{}""#, nonce)).count(), 3);
+
+        // Each response gets its own nonce; pages without custom code get no policy
+        assert_ne!(with_csp_nonce(&html).unwrap().1, policy);
+        assert_eq!(with_csp_nonce(PAGE), None);
+    }
+}
@@ -1,10 +1,16 @@
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
+use deadpool_postgres::Pool;
 use tokio_postgres::Client;
 use std::sync::Arc;
 
-use crate::services::composition::{PuckComposition, RenderContext, RenderDefaults, composition_to_context};
-use crate::services::template_cache::{TemplateCache, TemplateCacheError};
+use crate::database::postgres::tenant_client;
+use crate::services::composition::{PuckComposition, read_stored_composition, seed_from_default_schema};
+use crate::services::draft_patch::{merge_draft_patch, stamp_all_nodes, DraftConflict, DraftPatchRequest};
+use crate::services::site::SiteService;
+use crate::services::site_validation::site_context;
+use crate::services::template_engine::{PageContext, Template, TemplateEngine};
+use crate::types::TenantId;
 
 /// Page data structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,9 +20,16 @@ pub struct Page {
     pub site_id: Uuid,
     pub slug: String,
     pub title: String,
-    pub template_id: Uuid,
+    /// `None` for pages rendered with the base Puck template
+    pub template_id: Option<Uuid>,
     pub template_version: i32,
     pub draft_composition: PuckComposition,
+    /// The stored draft could not be read and `draft_composition` is empty in its place;
+    /// saving a full draft replaces it
+    #[serde(default)]
+    pub composition_error: bool,
+    pub custom_head: Option<String>,
+    pub custom_body: Option<String>,
     pub published_url: Option<String>,
     pub published_etag: Option<String>,
     pub is_published: bool,
@@ -41,6 +54,14 @@ pub struct SavePageDraftRequest {
 pub struct SwitchTemplateRequest {
     pub template_id: Uuid,
     pub template_version: i32,
+    /// Keep the current composition as-is; when false, the draft is re-seeded from
+    /// the new template's default content with matching blocks carried over
+    #[serde(default = "default_preserve_content")]
+    pub preserve_content: bool,
+}
+
+fn default_preserve_content() -> bool {
+    true
 }
 
 /// Preview link response
@@ -50,25 +71,27 @@ pub struct PreviewLinkResponse {
     pub expires_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Columns read into `Page`
+const PAGE_COLUMNS: &str = "id, tenant_id, site_id, slug, title, template_id, template_version,
+    draft_composition, custom_head, custom_body, published_url, published_etag, is_published,
+    preview_image_url, preview_status, created_at, updated_at";
+
 /// Page service for managing page operations
-#[derive(Debug)]
 pub struct PageService {
-    db_client: Arc<Client>,
-    template_cache: Arc<TemplateCache>,
-    render_defaults: RenderDefaults,
+    db: Pool,
+    template_engine: Arc<TemplateEngine>,
 }
 
 impl PageService {
-    pub fn new(
-        db_client: Arc<Client>,
-        template_cache: Arc<TemplateCache>,
-        render_defaults: RenderDefaults,
-    ) -> Self {
-        Self {
-            db_client,
-            template_cache,
-            render_defaults,
-        }
+    pub fn new(db: Pool, template_engine: Arc<TemplateEngine>) -> Self {
+        Self { db, template_engine }
+    }
+
+    /// A connection scoped to the tenant (see `tenant_client`)
+    async fn client(&self, tenant_id: Uuid) -> Result<deadpool_postgres::Object, PageServiceError> {
+        tenant_client(&self.db, &TenantId::from_uuid(tenant_id))
+            .await
+            .map_err(PageServiceError::ConnectionError)
     }
 
     /// Save page draft (Puck composition JSON)
@@ -78,17 +101,23 @@ impl PageService {
         tenant_id: Uuid,
         request: SavePageDraftRequest,
     ) -> Result<Page, PageServiceError> {
+        // A full save rewrites every node; stamp them so stale auto-saves conflict
+        let now = write_timestamp();
+        let mut draft_composition = request.draft_composition;
+        stamp_all_nodes(&mut draft_composition, now);
+
         // Serialize composition to JSON
-        let composition_json = serde_json::to_value(&request.draft_composition)
+        let composition_json = serde_json::to_value(&draft_composition)
             .map_err(|e| PageServiceError::SerializationError(e.to_string()))?;
 
         // Build update query dynamically based on provided fields
-        let mut query_parts = vec!["draft_composition = $3", "updated_at = now()"];
-        let mut param_count = 3;
+        let mut query_parts = vec!["draft_composition = $3", "updated_at = $4"];
+        let mut param_count = 5;
         let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![
             &page_id,
             &tenant_id,
             &composition_json,
+            &now,
         ];
 
         let mut conditions = Vec::new();
@@ -122,18 +151,13 @@ impl PageService {
         }
 
         let query = format!(
-            r#"
-            UPDATE pages 
-            SET {}
-            WHERE id = $1 AND tenant_id = $2
-            RETURNING id, tenant_id, site_id, slug, title, template_id, template_version,
-                      draft_composition, published_url, published_etag, is_published,
-                      preview_image_url, preview_status, created_at, updated_at
-            "#,
-            query_parts.join(", ")
+            "UPDATE pages SET {} WHERE id = $1 AND tenant_id = $2 RETURNING {}",
+            query_parts.join(", "),
+            PAGE_COLUMNS
         );
 
-        let row = self.db_client
+        let client = self.client(tenant_id).await?;
+        let row = client
             .query_opt(&query, &params)
             .await
             .map_err(PageServiceError::DatabaseError)?
@@ -142,11 +166,66 @@ impl PageService {
         let page = self.row_to_page(row)?;
         
         // Queue preview thumbnail generation
-        self.queue_preview_generation(page_id).await?;
+        queue_preview_generation(&client, page_id).await?;
 
         Ok(page)
     }
 
+    /// Merge an auto-saved partial update into the stored draft.
+    ///
+    /// The read-merge-write is retried if another save lands in between; the
+    /// update only applies while `updated_at` still matches what was read.
+    pub async fn patch_draft(
+        &self,
+        page_id: Uuid,
+        tenant_id: Uuid,
+        request: &DraftPatchRequest,
+    ) -> Result<chrono::DateTime<chrono::Utc>, PageServiceError> {
+        let select = r#"
+            SELECT draft_composition, updated_at
+            FROM pages
+            WHERE id = $1 AND tenant_id = $2
+        "#;
+        let update = r#"
+            UPDATE pages
+            SET draft_composition = $3, updated_at = $4
+            WHERE id = $1 AND tenant_id = $2 AND updated_at = $5
+        "#;
+
+        let client = self.client(tenant_id).await?;
+        for _ in 0..DRAFT_PATCH_ATTEMPTS {
+            let row = client
+                .query_opt(select, &[&page_id, &tenant_id])
+                .await
+                .map_err(PageServiceError::DatabaseError)?
+                .ok_or(PageServiceError::PageNotFound(page_id))?;
+            let read_at: chrono::DateTime<chrono::Utc> = row.get("updated_at");
+            // Merging into an empty stand-in would throw the rest of the page away
+            let (mut draft, malformed) = read_stored_composition(page_id, row.get("draft_composition"));
+            if malformed {
+                return Err(PageServiceError::MalformedComposition(page_id));
+            }
+
+            // Never go backwards, even if this clock is behind the last writer's
+            let now = write_timestamp().max(read_at + chrono::Duration::microseconds(1));
+            merge_draft_patch(&mut draft, request, now).map_err(PageServiceError::DraftConflict)?;
+            let composition_json = serde_json::to_value(&draft)
+                .map_err(|e| PageServiceError::SerializationError(e.to_string()))?;
+
+            let updated = client
+                .execute(update, &[&page_id, &tenant_id, &composition_json, &now, &read_at])
+                .await
+                .map_err(PageServiceError::DatabaseError)?;
+            if updated == 1 {
+                return Ok(now);
+            }
+        }
+
+        // Kept losing the race against other saves; let the client reload
+        let node_ids = request.nodes.iter().map(|node| node.id.clone()).chain(request.removed.iter().cloned()).collect();
+        Err(PageServiceError::DraftConflict(DraftConflict { node_ids }))
+    }
+
     /// Switch page template
     pub async fn switch_template(
         &self,
@@ -154,52 +233,66 @@ impl PageService {
         tenant_id: Uuid,
         request: SwitchTemplateRequest,
     ) -> Result<Page, PageServiceError> {
-        // Verify template exists and is accessible
-        self.template_cache
-            .get_template_by_id(request.template_id)
+        // Verify template exists and is accessible before switching
+        let template = self.template_engine
+            .get_template_by_id(request.template_id, tenant_id)
             .await
-            .map_err(|e| match e {
-                TemplateCacheError::TemplateNotFoundById(_) => {
-                    PageServiceError::TemplateNotFound(request.template_id)
-                }
-                _ => PageServiceError::TemplateCacheError(e),
-            })?;
-
-        let query = r#"
-            UPDATE pages 
-            SET template_id = $3, template_version = $4, updated_at = now()
-            WHERE id = $1 AND tenant_id = $2
-            RETURNING id, tenant_id, site_id, slug, title, template_id, template_version,
-                      draft_composition, published_url, published_etag, is_published,
-                      preview_image_url, preview_status, created_at, updated_at
-        "#;
-
-        let row = self.db_client
-            .query_opt(query, &[&page_id, &tenant_id, &request.template_id, &request.template_version])
-            .await
-            .map_err(PageServiceError::DatabaseError)?
-            .ok_or(PageServiceError::PageNotFound(page_id))?;
+            .map_err(PageServiceError::TemplateError)?
+            .ok_or(PageServiceError::TemplateNotFound(request.template_id))?;
+        ensure_template_switchable(&template, tenant_id, &request)?;
+
+        let client = self.client(tenant_id).await?;
+        let row = if request.preserve_content {
+            let query = format!(
+                "UPDATE pages SET template_id = $3, template_version = $4, updated_at = now()
+                 WHERE id = $1 AND tenant_id = $2 RETURNING {}",
+                PAGE_COLUMNS
+            );
+
+            client
+                .query_opt(&query, &[&page_id, &tenant_id, &request.template_id, &request.template_version])
+                .await
+                .map_err(PageServiceError::DatabaseError)?
+                .ok_or(PageServiceError::PageNotFound(page_id))?
+        } else {
+            let page = self.get_page(page_id, tenant_id).await?;
+            let mut seeded = seed_from_default_schema(&template.default_schema, &page.draft_composition)?;
+            // The whole draft is replaced, like a full save
+            let now = write_timestamp();
+            stamp_all_nodes(&mut seeded, now);
+            let composition_json = serde_json::to_value(&seeded)
+                .map_err(|e| PageServiceError::SerializationError(e.to_string()))?;
+
+            let query = format!(
+                "UPDATE pages SET template_id = $3, template_version = $4, draft_composition = $5, updated_at = $6
+                 WHERE id = $1 AND tenant_id = $2 RETURNING {}",
+                PAGE_COLUMNS
+            );
+
+            client
+                .query_opt(
+                    &query,
+                    &[&page_id, &tenant_id, &request.template_id, &request.template_version, &composition_json, &now],
+                )
+                .await
+                .map_err(PageServiceError::DatabaseError)?
+                .ok_or(PageServiceError::PageNotFound(page_id))?
+        };
 
         let page = self.row_to_page(row)?;
         
         // Queue preview thumbnail generation with new template
-        self.queue_preview_generation(page_id).await?;
+        queue_preview_generation(&client, page_id).await?;
 
         Ok(page)
     }
 
     /// Get page by ID
     pub async fn get_page(&self, page_id: Uuid, tenant_id: Uuid) -> Result<Page, PageServiceError> {
-        let query = r#"
-            SELECT id, tenant_id, site_id, slug, title, template_id, template_version,
-                   draft_composition, published_url, published_etag, is_published,
-                   preview_image_url, preview_status, created_at, updated_at
-            FROM pages 
-            WHERE id = $1 AND tenant_id = $2
-        "#;
+        let query = format!("SELECT {} FROM pages WHERE id = $1 AND tenant_id = $2", PAGE_COLUMNS);
 
-        let row = self.db_client
-            .query_opt(query, &[&page_id, &tenant_id])
+        let row = self.client(tenant_id).await?
+            .query_opt(&query, &[&page_id, &tenant_id])
             .await
             .map_err(PageServiceError::DatabaseError)?
             .ok_or(PageServiceError::PageNotFound(page_id))?;
@@ -207,40 +300,58 @@ impl PageService {
         self.row_to_page(row)
     }
 
-    /// Render page for preview (SSR from draft)
-    pub async fn render_preview(
-        &self,
-        page_id: Uuid,
-        tenant_id: Uuid,
-        site_slug: Option<&str>,
-    ) -> Result<String, PageServiceError> {
-        // Get page data
+    /// Render page for preview (SSR from draft), with its own template and the site's
+    /// context, as publishing it would
+    pub async fn render_preview(&self, page_id: Uuid, tenant_id: Uuid) -> Result<String, PageServiceError> {
         let page = self.get_page(page_id, tenant_id).await?;
-        
-        // Get template
-        let cached_template = self.template_cache
-            .get_template_by_id(page.template_id)
+        let template_name = match page.template_id {
+            Some(template_id) => Some(
+                self.template_engine
+                    .get_template_by_id(template_id, tenant_id)
+                    .await
+                    .map_err(PageServiceError::TemplateError)?
+                    .ok_or(PageServiceError::TemplateNotFound(template_id))?
+                    .name,
+            ),
+            None => None,
+        };
+
+        let sites = SiteService::new(self.db.clone());
+        let tenant = TenantId::from_uuid(tenant_id);
+        let site = sites
+            .get_site(&tenant, page.site_id)
             .await
-            .map_err(PageServiceError::TemplateCacheError)?;
-
-        // Transform composition to render context
-        let context = composition_to_context(
-            &page.draft_composition,
-            &self.render_defaults,
-            site_slug,
-        ).map_err(PageServiceError::CompositionError)?;
-
-        // Render with MiniJinja
-        let html = cached_template.environment
-            .get_template(&cached_template.template.main_name)
-            .map_err(|e| PageServiceError::TemplateRenderError(e.to_string()))?
-            .render(&context)
-            .map_err(|e| PageServiceError::TemplateRenderError(e.to_string()))?;
-
-        // Add preview meta tags
-        let html_with_meta = self.add_preview_meta_tags(html);
-
-        Ok(html_with_meta)
+            .map_err(PageServiceError::TemplateError)?
+            .ok_or(PageServiceError::PageNotFound(page_id))?;
+        let theme = sites.tenant_theme(&tenant).await.map_err(PageServiceError::TemplateError)?;
+
+        let puck_data = serde_json::to_value(&page.draft_composition)
+            .map_err(|e| PageServiceError::SerializationError(e.to_string()))?;
+        let page_context = PageContext {
+            id: page.id,
+            slug: page.slug,
+            title: page.title,
+            meta_description: None,
+            meta_keywords: None,
+            is_published: page.is_published,
+            published_at: None,
+        };
+        let site_context = site_context(&site, &theme);
+        let rendered = match template_name {
+            Some(name) => {
+                self.template_engine
+                    .render_puck_template(&name, &puck_data, &site_context, &page_context, tenant_id, None)
+                    .await
+            }
+            None => {
+                self.template_engine
+                    .render_puck_page(&puck_data, &site_context, &page_context, tenant_id, None)
+                    .await
+            }
+        };
+        let html = rendered.map_err(|e| PageServiceError::TemplateRenderError(format!("{:#}", e)))?;
+
+        Ok(self.add_preview_meta_tags(html))
     }
 
     /// Generate preview link token
@@ -267,7 +378,7 @@ impl PageService {
     }
 
     /// Parse preview token
-    pub fn parse_preview_token(&self, token: &str) -> Result<(Uuid, Uuid, chrono::DateTime<chrono::Utc>), PageServiceError> {
+    pub fn parse_preview_token(token: &str) -> Result<(Uuid, Uuid, chrono::DateTime<chrono::Utc>), PageServiceError> {
         let decoded = base64::decode(token)
             .map_err(|_| PageServiceError::InvalidPreviewToken)?;
         
@@ -299,25 +410,6 @@ impl PageService {
         Ok((tenant_id, page_id, expires_at))
     }
 
-    /// Queue preview thumbnail generation
-    async fn queue_preview_generation(&self, page_id: Uuid) -> Result<(), PageServiceError> {
-        let query = r#"
-            UPDATE pages 
-            SET preview_status = 'queued', updated_at = now()
-            WHERE id = $1
-        "#;
-
-        self.db_client
-            .execute(query, &[&page_id])
-            .await
-            .map_err(PageServiceError::DatabaseError)?;
-
-        // TODO: Send to actual queue (Redis, SQS, etc.)
-        tracing::info!("Queued preview generation for page {}", page_id);
-
-        Ok(())
-    }
-
     /// Add preview-specific meta tags
     fn add_preview_meta_tags(&self, html: String) -> String {
         let preview_meta = r#"<meta name="robots" content="noindex,nofollow">
@@ -344,12 +436,11 @@ body { margin-top: 40px !important; }
 
     /// Convert database row to Page struct
     fn row_to_page(&self, row: tokio_postgres::Row) -> Result<Page, PageServiceError> {
-        let composition_json: serde_json::Value = row.get("draft_composition");
-        let draft_composition: PuckComposition = serde_json::from_value(composition_json)
-            .map_err(|e| PageServiceError::SerializationError(e.to_string()))?;
+        let page_id: Uuid = row.get("id");
+        let (draft_composition, composition_error) = read_stored_composition(page_id, row.get("draft_composition"));
 
         Ok(Page {
-            id: row.get("id"),
+            id: page_id,
             tenant_id: row.get("tenant_id"),
             site_id: row.get("site_id"),
             slug: row.get("slug"),
@@ -357,6 +448,9 @@ body { margin-top: 40px !important; }
             template_id: row.get("template_id"),
             template_version: row.get("template_version"),
             draft_composition,
+            composition_error,
+            custom_head: row.get("custom_head"),
+            custom_body: row.get("custom_body"),
             published_url: row.get("published_url"),
             published_etag: row.get("published_etag"),
             is_published: row.get("is_published"),
@@ -368,6 +462,47 @@ body { margin-top: 40px !important; }
     }
 }
 
+/// Queue preview thumbnail generation
+async fn queue_preview_generation(client: &Client, page_id: Uuid) -> Result<(), PageServiceError> {
+    let query = r#"
+        UPDATE pages 
+        SET preview_status = 'queued', updated_at = now()
+        WHERE id = $1
+    "#;
+
+    client
+        .execute(query, &[&page_id])
+        .await
+        .map_err(PageServiceError::DatabaseError)?;
+
+    // TODO: Send to actual queue (Redis, SQS, etc.)
+    tracing::info!("Queued preview generation for page {}", page_id);
+
+    Ok(())
+}
+
+/// Auto-save read-merge-write attempts before reporting a conflict
+const DRAFT_PATCH_ATTEMPTS: usize = 3;
+
+/// Current time at the precision Postgres stores, so timestamps round-trip exactly
+fn write_timestamp() -> chrono::DateTime<chrono::Utc> {
+    use chrono::SubsecRound;
+    chrono::Utc::now().trunc_subsecs(6)
+}
+
+/// Reject switching to another tenant's template or to a version that doesn't exist
+fn ensure_template_switchable(
+    template: &Template,
+    tenant_id: Uuid,
+    request: &SwitchTemplateRequest,
+) -> Result<(), PageServiceError> {
+    let accessible = template.is_public || template.tenant_id.is_none_or(|owner| owner == tenant_id);
+    if !accessible || template.version != request.template_version {
+        return Err(PageServiceError::TemplateNotFound(request.template_id));
+    }
+    Ok(())
+}
+
 /// Page service errors
 #[derive(Debug, thiserror::Error)]
 pub enum PageServiceError {
@@ -380,8 +515,11 @@ pub enum PageServiceError {
     #[error("Database error: {0}")]
     DatabaseError(#[from] tokio_postgres::Error),
     
-    #[error("Template cache error: {0}")]
-    TemplateCacheError(#[from] TemplateCacheError),
+    #[error("Database connection error: {0}")]
+    ConnectionError(anyhow::Error),
+
+    #[error("Template error: {0}")]
+    TemplateError(anyhow::Error),
     
     #[error("Composition error: {0}")]
     CompositionError(#[from] crate::services::composition::CompositionError),
@@ -397,6 +535,12 @@ pub enum PageServiceError {
     
     #[error("Preview token expired")]
     PreviewTokenExpired,
+
+    #[error("Draft changed since last save: {0:?}")]
+    DraftConflict(DraftConflict),
+
+    #[error("Stored draft of page {0} is malformed; save a full draft to replace it")]
+    MalformedComposition(Uuid),
 }
 
 #[cfg(test)]
@@ -406,12 +550,6 @@ mod tests {
 
     #[test]
     fn test_preview_token_parsing() {
-        let service = PageService::new(
-            Arc::new(unsafe { std::mem::zeroed() }), // Mock for test
-            Arc::new(unsafe { std::mem::zeroed() }), // Mock for test
-            RenderDefaults::default(),
-        );
-
         let tenant_id = Uuid::new_v4();
         let page_id = Uuid::new_v4();
         let expires_at = chrono::Utc::now() + chrono::Duration::minutes(30);
@@ -419,11 +557,52 @@ mod tests {
         let token = format!("{}:{}:{}", tenant_id, page_id, expires_at.timestamp());
         let encoded_token = base64::encode(&token);
 
-        let result = service.parse_preview_token(&encoded_token);
+        let result = PageService::parse_preview_token(&encoded_token);
         assert!(result.is_ok());
         
         let (parsed_tenant_id, parsed_page_id, _) = result.unwrap();
         assert_eq!(parsed_tenant_id, tenant_id);
         assert_eq!(parsed_page_id, page_id);
     }
+
+    fn template_owned_by(tenant_id: Option<Uuid>) -> Template {
+        Template {
+            id: Uuid::new_v4(),
+            tenant_id,
+            name: "literary-classic".to_string(),
+            description: None,
+            category: "author".to_string(),
+            html_source: "<html></html>".to_string(),
+            default_schema: json!({}),
+            preview_image_url: None,
+            is_public: false,
+            version: 2,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_switch_to_unavailable_template_rejected() {
+        let tenant_id = Uuid::new_v4();
+        let template = template_owned_by(Some(tenant_id));
+        let request = |version| SwitchTemplateRequest {
+            template_id: template.id,
+            template_version: version,
+            preserve_content: true,
+        };
+
+        assert!(ensure_template_switchable(&template, tenant_id, &request(2)).is_ok());
+        assert!(ensure_template_switchable(&template_owned_by(None), tenant_id, &request(2)).is_ok());
+
+        // Nonexistent version, and another tenant's template, are both treated as missing
+        assert!(matches!(
+            ensure_template_switchable(&template, tenant_id, &request(3)),
+            Err(PageServiceError::TemplateNotFound(_))
+        ));
+        assert!(matches!(
+            ensure_template_switchable(&template, Uuid::new_v4(), &request(2)),
+            Err(PageServiceError::TemplateNotFound(_))
+        ));
+    }
 }
@@ -0,0 +1,262 @@
+use crate::config::{PlanLimits, PlansConfig};
+use crate::database::postgres::tenant_client;
+use crate::types::TenantId;
+use anyhow::{Context, Result};
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// A tenant's plan with any per-tenant overrides applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TenantPlan {
+    pub plan: String,
+    pub limits: PlanLimits,
+}
+
+/// What is about to be created or changed
+#[derive(Debug, Clone, Copy)]
+pub enum PlanCheck {
+    NewSite,
+    NewPage { site_id: Uuid },
+    CustomDomain,
+    /// `None` asks for the platform default retention
+    AnalyticsRetention { days: Option<u32> },
+}
+
+/// Which limit a refused request ran into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanLimit {
+    MaxSites,
+    MaxPagesPerSite,
+    CustomDomains,
+    AnalyticsRetentionDays,
+}
+
+/// Returned to the client (as 402) when the tenant's plan does not allow a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlanLimitExceeded {
+    pub plan: String,
+    pub limit: PlanLimit,
+    /// The plan's value for the limit; absent for on/off features
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed: Option<u32>,
+}
+
+/// Plan enforcement errors
+#[derive(Debug, thiserror::Error)]
+pub enum PlanError {
+    #[error("Plan limit reached: {:?}", .0.limit)]
+    LimitExceeded(PlanLimitExceeded),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// Resolve a tenant's plan from its settings: `plan` picks the tier, and
+/// `plan_limits` replaces individual limits (`null` meaning unlimited)
+pub fn resolve_plan(config: &PlansConfig, tenant_settings: &Value) -> TenantPlan {
+    let plan = tenant_settings
+        .get("plan")
+        .and_then(Value::as_str)
+        .unwrap_or(&config.default_plan)
+        .to_string();
+    let base = config.tiers.get(&plan).cloned().unwrap_or_default();
+
+    let limits = match tenant_settings.get("plan_limits").and_then(Value::as_object) {
+        Some(overrides) => {
+            let mut merged = serde_json::to_value(&base).unwrap_or_default();
+            for (key, value) in overrides {
+                merged[key.as_str()] = value.clone();
+            }
+            // An unusable override is ignored rather than locking the tenant out
+            serde_json::from_value(merged).unwrap_or(base)
+        }
+        None => base,
+    };
+
+    TenantPlan { plan, limits }
+}
+
+/// Check a request against the plan. `current` is how many sites or pages the
+/// tenant already has, for the checks that count.
+pub fn check_plan_limit(
+    plan: &TenantPlan,
+    check: PlanCheck,
+    current: i64,
+    platform_retention_days: u32,
+) -> Result<(), PlanLimitExceeded> {
+    let limits = &plan.limits;
+    let (limit, allowed, permitted) = match check {
+        PlanCheck::NewSite => (
+            PlanLimit::MaxSites,
+            limits.max_sites,
+            limits.max_sites.is_none_or(|max| current < max as i64),
+        ),
+        PlanCheck::NewPage { .. } => (
+            PlanLimit::MaxPagesPerSite,
+            limits.max_pages_per_site,
+            limits.max_pages_per_site.is_none_or(|max| current < max as i64),
+        ),
+        PlanCheck::CustomDomain => (PlanLimit::CustomDomains, None, limits.custom_domains),
+        PlanCheck::AnalyticsRetention { days } => (
+            PlanLimit::AnalyticsRetentionDays,
+            limits.analytics_retention_days,
+            limits
+                .analytics_retention_days
+                .is_none_or(|max| days.unwrap_or(platform_retention_days) <= max),
+        ),
+    };
+
+    if permitted {
+        Ok(())
+    } else {
+        Err(PlanLimitExceeded { plan: plan.plan.clone(), limit, allowed })
+    }
+}
+
+/// Service looking up tenants' plans and enforcing their limits
+pub struct PlanService {
+    db: Pool,
+    config: PlansConfig,
+}
+
+impl PlanService {
+    pub fn new(db: Pool, config: PlansConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// The tenant's plan and effective limits
+    pub async fn tenant_plan(&self, tenant_id: &TenantId) -> Result<TenantPlan> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let settings: Value = client
+            .query_opt("SELECT settings FROM tenants WHERE id = $1", &[tenant_id.as_uuid()])
+            .await
+            .context("Failed to read tenant settings")?
+            .map_or(Value::Null, |row| row.get("settings"));
+
+        Ok(resolve_plan(&self.config, &settings))
+    }
+
+    /// Refuse `check` if the tenant's plan does not allow it
+    pub async fn enforce_plan_limit(
+        &self,
+        tenant_id: &TenantId,
+        check: PlanCheck,
+        platform_retention_days: u32,
+    ) -> Result<(), PlanError> {
+        let plan = self.tenant_plan(tenant_id).await?;
+        let current = match check {
+            PlanCheck::NewSite if plan.limits.max_sites.is_some() => self.count_sites(tenant_id).await?,
+            PlanCheck::NewPage { site_id } if plan.limits.max_pages_per_site.is_some() => {
+                self.count_pages(tenant_id, site_id).await?
+            }
+            _ => 0,
+        };
+
+        check_plan_limit(&plan, check, current, platform_retention_days).map_err(PlanError::LimitExceeded)
+    }
+
+    async fn count_sites(&self, tenant_id: &TenantId) -> Result<i64> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let count: i64 = client
+            .query_one("SELECT COUNT(*) FROM sites WHERE tenant_id = $1", &[tenant_id.as_uuid()])
+            .await
+            .context("Failed to count sites")?
+            .get(0);
+
+        Ok(count)
+    }
+
+    async fn count_pages(&self, tenant_id: &TenantId, site_id: Uuid) -> Result<i64> {
+        let client = tenant_client(&self.db, tenant_id).await?;
+
+        let count: i64 = client
+            .query_one(
+                "SELECT COUNT(*) FROM pages p JOIN sites s ON s.id = p.site_id WHERE p.site_id = $1 AND s.tenant_id = $2",
+                &[&site_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to count pages")?
+            .get(0);
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn plans() -> PlansConfig {
+        let mut config = PlansConfig::default();
+        config.tiers.insert(
+            "free".to_string(),
+            PlanLimits {
+                max_sites: Some(1),
+                max_pages_per_site: Some(10),
+                custom_domains: false,
+                analytics_retention_days: Some(30),
+            },
+        );
+        config.tiers.insert(
+            "author".to_string(),
+            PlanLimits {
+                max_sites: Some(3),
+                max_pages_per_site: Some(50),
+                custom_domains: true,
+                analytics_retention_days: Some(365),
+            },
+        );
+        config
+    }
+
+    #[test]
+    fn test_free_plan_blocked_at_site_limit() {
+        let plan = resolve_plan(&plans(), &json!({}));
+        assert_eq!(plan.plan, "free");
+
+        assert!(check_plan_limit(&plan, PlanCheck::NewSite, 0, 730).is_ok());
+        let refused = check_plan_limit(&plan, PlanCheck::NewSite, 1, 730).unwrap_err();
+        assert_eq!(refused, PlanLimitExceeded { plan: "free".to_string(), limit: PlanLimit::MaxSites, allowed: Some(1) });
+        assert_eq!(
+            serde_json::to_value(&refused).unwrap(),
+            json!({ "plan": "free", "limit": "max_sites", "allowed": 1 })
+        );
+    }
+
+    #[test]
+    fn test_upgraded_plan_not_blocked() {
+        let plan = resolve_plan(&plans(), &json!({ "plan": "author" }));
+
+        assert!(check_plan_limit(&plan, PlanCheck::NewSite, 1, 730).is_ok());
+        assert!(check_plan_limit(&plan, PlanCheck::CustomDomain, 0, 730).is_ok());
+        assert!(check_plan_limit(&plan, PlanCheck::NewSite, 3, 730).is_err());
+    }
+
+    #[test]
+    fn test_tenant_overrides_plan_limits() {
+        let settings = json!({ "plan": "free", "plan_limits": { "max_sites": 5, "max_pages_per_site": null } });
+        let plan = resolve_plan(&plans(), &settings);
+
+        assert_eq!(plan.limits.max_sites, Some(5));
+        assert_eq!(plan.limits.max_pages_per_site, None);
+        assert!(!plan.limits.custom_domains);
+        assert!(check_plan_limit(&plan, PlanCheck::NewSite, 4, 730).is_ok());
+        assert!(check_plan_limit(&plan, PlanCheck::NewPage { site_id: Uuid::new_v4() }, 1000, 730).is_ok());
+    }
+
+    #[test]
+    fn test_retention_capped_by_plan() {
+        let plan = resolve_plan(&plans(), &json!({ "plan": "free" }));
+
+        assert!(check_plan_limit(&plan, PlanCheck::AnalyticsRetention { days: Some(30) }, 0, 730).is_ok());
+        assert!(check_plan_limit(&plan, PlanCheck::AnalyticsRetention { days: Some(90) }, 0, 730).is_err());
+        // Falling back to the platform default is longer than the plan allows
+        assert!(check_plan_limit(&plan, PlanCheck::AnalyticsRetention { days: None }, 0, 730).is_err());
+    }
+}
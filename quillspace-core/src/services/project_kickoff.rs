@@ -1,8 +1,10 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use crate::database::Database;
+use deadpool_postgres::Pool;
+use crate::services::clock::{Clock, IdGenerator, RandomIds, SystemClock};
 use anyhow::Result;
+use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProjectKickoff {
@@ -150,12 +152,18 @@ pub enum UpdateFrequency {
 }
 
 pub struct ProjectKickoffService {
-    db: Database,
+    db: Pool,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
 }
 
 impl ProjectKickoffService {
-    pub fn new(db: Database) -> Self {
-        Self { db }
+    pub fn new(db: Pool) -> Self {
+        Self::with_clock(db, Arc::new(SystemClock), Arc::new(RandomIds))
+    }
+
+    pub fn with_clock(db: Pool, clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Self {
+        Self { db, clock, ids }
     }
 
     /// Initialize project kickoff after proposal acceptance
@@ -163,36 +171,7 @@ impl ProjectKickoffService {
         // Get proposal and consultation details
         let (proposal, consultation) = self.get_proposal_details(proposal_id).await?;
         
-        // Create project phases based on proposal scope
-        let phases = self.create_project_phases(&proposal).await?;
-        
-        // Create initial deliverables
-        let deliverables = self.create_initial_deliverables(&proposal, &phases).await?;
-        
-        let project = ProjectKickoff {
-            id: Uuid::new_v4(),
-            consultation_booking_id: consultation.id,
-            proposal_id,
-            project_name: proposal.title.clone(),
-            client_user_id: consultation.user_id,
-            assigned_designer_id: None, // Will be assigned later
-            assigned_developer_id: None,
-            project_status: ProjectStatus::KickoffScheduled,
-            kickoff_date: Utc::now() + chrono::Duration::days(2), // Schedule kickoff in 2 days
-            estimated_completion: Utc::now() + chrono::Duration::weeks(proposal.timeline_weeks as i64),
-            project_phases: phases,
-            deliverables,
-            client_assets: Vec::new(),
-            communication_preferences: CommunicationPreferences {
-                primary_contact_email: consultation.guest_email.clone(),
-                preferred_meeting_times: vec!["Morning".to_string(), "Afternoon".to_string()],
-                update_frequency: UpdateFrequency::Weekly,
-                slack_channel: None,
-                phone_number: None,
-            },
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+        let project = new_project(self.ids.as_ref(), self.clock.now(), proposal_id, &proposal, &consultation);
 
         // Save to database
         self.create_project_record(&project).await?;
@@ -203,160 +182,6 @@ impl ProjectKickoffService {
         Ok(project)
     }
 
-    /// Create project phases based on proposal scope
-    async fn create_project_phases(&self, proposal: &ProposalDetails) -> Result<Vec<ProjectPhase>> {
-        let mut phases = Vec::new();
-        
-        // Phase 1: Discovery & Planning
-        phases.push(ProjectPhase {
-            id: Uuid::new_v4(),
-            name: "Discovery & Planning".to_string(),
-            description: "Gather requirements, create project plan, and set up infrastructure".to_string(),
-            estimated_duration_days: 3,
-            start_date: None,
-            completion_date: None,
-            status: PhaseStatus::NotStarted,
-            deliverables: vec![
-                "Project kickoff meeting".to_string(),
-                "Content strategy document".to_string(),
-                "Technical specification".to_string(),
-            ],
-            dependencies: Vec::new(),
-        });
-
-        // Phase 2: Design
-        phases.push(ProjectPhase {
-            id: Uuid::new_v4(),
-            name: "Design & Wireframing".to_string(),
-            description: "Create wireframes, design mockups, and establish visual identity".to_string(),
-            estimated_duration_days: 7,
-            start_date: None,
-            completion_date: None,
-            status: PhaseStatus::NotStarted,
-            deliverables: vec![
-                "Site wireframes".to_string(),
-                "Visual design mockups".to_string(),
-                "Style guide".to_string(),
-            ],
-            dependencies: vec![phases[0].id],
-        });
-
-        // Phase 3: Development
-        phases.push(ProjectPhase {
-            id: Uuid::new_v4(),
-            name: "Development".to_string(),
-            description: "Build the website using QuillSpace's platform".to_string(),
-            estimated_duration_days: 10,
-            start_date: None,
-            completion_date: None,
-            status: PhaseStatus::NotStarted,
-            deliverables: vec![
-                "Development environment setup".to_string(),
-                "Core pages implementation".to_string(),
-                "Content integration".to_string(),
-                "Testing and optimization".to_string(),
-            ],
-            dependencies: vec![phases[1].id],
-        });
-
-        // Phase 4: Review & Launch
-        phases.push(ProjectPhase {
-            id: Uuid::new_v4(),
-            name: "Review & Launch".to_string(),
-            description: "Client review, revisions, and website launch".to_string(),
-            estimated_duration_days: 5,
-            start_date: None,
-            completion_date: None,
-            status: PhaseStatus::NotStarted,
-            deliverables: vec![
-                "Client review session".to_string(),
-                "Revisions implementation".to_string(),
-                "Website launch".to_string(),
-                "Training materials".to_string(),
-            ],
-            dependencies: vec![phases[2].id],
-        });
-
-        Ok(phases)
-    }
-
-    /// Create initial deliverables
-    async fn create_initial_deliverables(&self, proposal: &ProposalDetails, phases: &[ProjectPhase]) -> Result<Vec<Deliverable>> {
-        let mut deliverables = Vec::new();
-        let base_date = Utc::now();
-
-        // Discovery deliverables
-        deliverables.push(Deliverable {
-            id: Uuid::new_v4(),
-            name: "Project Kickoff Meeting".to_string(),
-            description: "Initial project kickoff call to align on goals and timeline".to_string(),
-            deliverable_type: DeliverableType::ContentStrategy,
-            due_date: base_date + chrono::Duration::days(2),
-            status: DeliverableStatus::NotStarted,
-            file_url: None,
-            approval_required: false,
-            approved_at: None,
-            feedback: None,
-        });
-
-        // Design deliverables
-        deliverables.push(Deliverable {
-            id: Uuid::new_v4(),
-            name: "Website Wireframes".to_string(),
-            description: "Structural layout of all website pages".to_string(),
-            deliverable_type: DeliverableType::Wireframes,
-            due_date: base_date + chrono::Duration::days(7),
-            status: DeliverableStatus::NotStarted,
-            file_url: None,
-            approval_required: true,
-            approved_at: None,
-            feedback: None,
-        });
-
-        deliverables.push(Deliverable {
-            id: Uuid::new_v4(),
-            name: "Visual Design Mockups".to_string(),
-            description: "High-fidelity design mockups of key pages".to_string(),
-            deliverable_type: DeliverableType::DesignMockups,
-            due_date: base_date + chrono::Duration::days(12),
-            status: DeliverableStatus::NotStarted,
-            file_url: None,
-            approval_required: true,
-            approved_at: None,
-            feedback: None,
-        });
-
-        // Development deliverables
-        deliverables.push(Deliverable {
-            id: Uuid::new_v4(),
-            name: "Testing Website".to_string(),
-            description: "Fully functional website on staging environment".to_string(),
-            deliverable_type: DeliverableType::TestingSite,
-            due_date: base_date + chrono::Duration::days(20),
-            status: DeliverableStatus::NotStarted,
-            file_url: None,
-            approval_required: true,
-            approved_at: None,
-            feedback: None,
-        });
-
-        // Final deliverable
-        deliverables.push(Deliverable {
-            id: Uuid::new_v4(),
-            name: "Live Website".to_string(),
-            description: "Final website launched and live".to_string(),
-            deliverable_type: DeliverableType::FinalWebsite,
-            due_date: base_date + chrono::Duration::days(25),
-            status: DeliverableStatus::NotStarted,
-            file_url: None,
-            approval_required: true,
-            approved_at: None,
-            feedback: None,
-        });
-
-        Ok(deliverables)
-    }
-
     /// Trigger kickoff workflow
     async fn trigger_kickoff_workflow(&self, project: &ProjectKickoff) -> Result<()> {
         // 1. Send kickoff email to client
@@ -451,8 +276,8 @@ impl ProjectKickoffService {
                 timeline_weeks: 4,
             },
             ConsultationDetails {
-                id: Uuid::new_v4(),
-                user_id: Uuid::new_v4(),
+                id: self.ids.new_id(),
+                user_id: self.ids.new_id(),
                 guest_email: "client@example.com".to_string(),
             }
         ))
@@ -520,3 +345,231 @@ pub struct TeamMember {
     pub role: String,
     pub avatar_url: Option<String>,
 }
+
+/// A new project from an accepted proposal: kickoff in two days, completion after the
+/// proposal's timeline, both counted from `now`
+fn new_project(
+    ids: &dyn IdGenerator,
+    now: DateTime<Utc>,
+    proposal_id: Uuid,
+    proposal: &ProposalDetails,
+    consultation: &ConsultationDetails,
+) -> ProjectKickoff {
+    // Create project phases based on proposal scope
+    let phases = create_project_phases(ids, proposal);
+
+    // Create initial deliverables
+    let deliverables = create_initial_deliverables(ids, now, proposal, &phases);
+
+    ProjectKickoff {
+        id: ids.new_id(),
+        consultation_booking_id: consultation.id,
+        proposal_id,
+        project_name: proposal.title.clone(),
+        client_user_id: consultation.user_id,
+        assigned_designer_id: None, // Will be assigned later
+        assigned_developer_id: None,
+        project_status: ProjectStatus::KickoffScheduled,
+        kickoff_date: now + chrono::Duration::days(2), // Schedule kickoff in 2 days
+        estimated_completion: now + chrono::Duration::weeks(proposal.timeline_weeks as i64),
+        project_phases: phases,
+        deliverables,
+        client_assets: Vec::new(),
+        communication_preferences: CommunicationPreferences {
+            primary_contact_email: consultation.guest_email.clone(),
+            preferred_meeting_times: vec!["Morning".to_string(), "Afternoon".to_string()],
+            update_frequency: UpdateFrequency::Weekly,
+            slack_channel: None,
+            phone_number: None,
+        },
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+/// Create project phases based on proposal scope
+fn create_project_phases(ids: &dyn IdGenerator, proposal: &ProposalDetails) -> Vec<ProjectPhase> {
+    let phase_ids: Vec<Uuid> = (0..4).map(|_| ids.new_id()).collect();
+
+    vec![
+        // Phase 1: Discovery & Planning
+        ProjectPhase {
+            id: phase_ids[0],
+            name: "Discovery & Planning".to_string(),
+            description: "Gather requirements, create project plan, and set up infrastructure".to_string(),
+            estimated_duration_days: 3,
+            start_date: None,
+            completion_date: None,
+            status: PhaseStatus::NotStarted,
+            deliverables: vec![
+                "Project kickoff meeting".to_string(),
+                "Content strategy document".to_string(),
+                "Technical specification".to_string(),
+            ],
+            dependencies: Vec::new(),
+        },
+        // Phase 2: Design
+        ProjectPhase {
+            id: phase_ids[1],
+            name: "Design & Wireframing".to_string(),
+            description: "Create wireframes, design mockups, and establish visual identity".to_string(),
+            estimated_duration_days: 7,
+            start_date: None,
+            completion_date: None,
+            status: PhaseStatus::NotStarted,
+            deliverables: vec![
+                "Site wireframes".to_string(),
+                "Visual design mockups".to_string(),
+                "Style guide".to_string(),
+            ],
+            dependencies: vec![phase_ids[0]],
+        },
+        // Phase 3: Development
+        ProjectPhase {
+            id: phase_ids[2],
+            name: "Development".to_string(),
+            description: "Build the website using QuillSpace's platform".to_string(),
+            estimated_duration_days: 10,
+            start_date: None,
+            completion_date: None,
+            status: PhaseStatus::NotStarted,
+            deliverables: vec![
+                "Development environment setup".to_string(),
+                "Core pages implementation".to_string(),
+                "Content integration".to_string(),
+                "Testing and optimization".to_string(),
+            ],
+            dependencies: vec![phase_ids[1]],
+        },
+        // Phase 4: Review & Launch
+        ProjectPhase {
+            id: phase_ids[3],
+            name: "Review & Launch".to_string(),
+            description: "Client review, revisions, and website launch".to_string(),
+            estimated_duration_days: 5,
+            start_date: None,
+            completion_date: None,
+            status: PhaseStatus::NotStarted,
+            deliverables: vec![
+                "Client review session".to_string(),
+                "Revisions implementation".to_string(),
+                "Website launch".to_string(),
+                "Training materials".to_string(),
+            ],
+            dependencies: vec![phase_ids[2]],
+        },
+    ]
+}
+
+/// Create initial deliverables
+fn create_initial_deliverables(ids: &dyn IdGenerator, now: DateTime<Utc>, proposal: &ProposalDetails, phases: &[ProjectPhase]) -> Vec<Deliverable> {
+    let mut deliverables = Vec::new();
+    let base_date = now;
+
+    // Discovery deliverables
+    deliverables.push(Deliverable {
+        id: ids.new_id(),
+        name: "Project Kickoff Meeting".to_string(),
+        description: "Initial project kickoff call to align on goals and timeline".to_string(),
+        deliverable_type: DeliverableType::ContentStrategy,
+        due_date: base_date + chrono::Duration::days(2),
+        status: DeliverableStatus::NotStarted,
+        file_url: None,
+        approval_required: false,
+        approved_at: None,
+        feedback: None,
+    });
+
+    // Design deliverables
+    deliverables.push(Deliverable {
+        id: ids.new_id(),
+        name: "Website Wireframes".to_string(),
+        description: "Structural layout of all website pages".to_string(),
+        deliverable_type: DeliverableType::Wireframes,
+        due_date: base_date + chrono::Duration::days(7),
+        status: DeliverableStatus::NotStarted,
+        file_url: None,
+        approval_required: true,
+        approved_at: None,
+        feedback: None,
+    });
+
+    deliverables.push(Deliverable {
+        id: ids.new_id(),
+        name: "Visual Design Mockups".to_string(),
+        description: "High-fidelity design mockups of key pages".to_string(),
+        deliverable_type: DeliverableType::DesignMockups,
+        due_date: base_date + chrono::Duration::days(12),
+        status: DeliverableStatus::NotStarted,
+        file_url: None,
+        approval_required: true,
+        approved_at: None,
+        feedback: None,
+    });
+
+    // Development deliverables
+    deliverables.push(Deliverable {
+        id: ids.new_id(),
+        name: "Testing Website".to_string(),
+        description: "Fully functional website on staging environment".to_string(),
+        deliverable_type: DeliverableType::TestingSite,
+        due_date: base_date + chrono::Duration::days(20),
+        status: DeliverableStatus::NotStarted,
+        file_url: None,
+        approval_required: true,
+        approved_at: None,
+        feedback: None,
+    });
+
+    // Final deliverable
+    deliverables.push(Deliverable {
+        id: ids.new_id(),
+        name: "Live Website".to_string(),
+        description: "Final website launched and live".to_string(),
+        deliverable_type: DeliverableType::FinalWebsite,
+        due_date: base_date + chrono::Duration::days(25),
+        status: DeliverableStatus::NotStarted,
+        file_url: None,
+        approval_required: true,
+        approved_at: None,
+        feedback: None,
+    });
+
+    deliverables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::clock::SequentialIds;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_new_project_dated_and_numbered_from_injected_clock_and_ids() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap();
+        let ids = SequentialIds::default();
+        let proposal = ProposalDetails { title: "Author Website Project".to_string(), timeline_weeks: 4 };
+        let consultation = ConsultationDetails {
+            id: Uuid::from_u128(100),
+            user_id: Uuid::from_u128(200),
+            guest_email: "author@example.com".to_string(),
+        };
+
+        let project = new_project(&ids, now, Uuid::from_u128(300), &proposal, &consultation);
+
+        // Four phases, then five deliverables, then the project itself
+        let phase_ids: Vec<_> = project.project_phases.iter().map(|phase| phase.id).collect();
+        assert_eq!(phase_ids, (1..=4).map(SequentialIds::nth).collect::<Vec<_>>());
+        assert_eq!(project.project_phases[1].dependencies, vec![SequentialIds::nth(1)]);
+        let deliverable_ids: Vec<_> = project.deliverables.iter().map(|deliverable| deliverable.id).collect();
+        assert_eq!(deliverable_ids, (5..=9).map(SequentialIds::nth).collect::<Vec<_>>());
+        assert_eq!(project.id, SequentialIds::nth(10));
+
+        assert_eq!(project.kickoff_date, Utc.with_ymd_and_hms(2024, 3, 3, 10, 0, 0).unwrap());
+        assert_eq!(project.estimated_completion, Utc.with_ymd_and_hms(2024, 3, 29, 10, 0, 0).unwrap());
+        let due: Vec<_> = project.deliverables.iter().map(|deliverable| deliverable.due_date).collect();
+        assert_eq!(due.first(), Some(&Utc.with_ymd_and_hms(2024, 3, 3, 10, 0, 0).unwrap()));
+        assert_eq!(due.last(), Some(&Utc.with_ymd_and_hms(2024, 3, 26, 10, 0, 0).unwrap()));
+        assert_eq!((project.created_at, project.updated_at), (now, now));
+    }
+}
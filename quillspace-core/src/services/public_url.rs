@@ -0,0 +1,140 @@
+use crate::config::{ProxyConfig, PublicUrlConfig};
+use axum::http::HeaderMap;
+use std::net::IpAddr;
+
+/// Domain every site is served under as `<subdomain>.quillspace.app`
+pub const PLATFORM_SITE_DOMAIN: &str = "quillspace.app";
+
+/// The host and scheme a request was addressed to, as reported by a trusted proxy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestOrigin {
+    pub host: Option<String>,
+    pub scheme: Option<String>,
+}
+
+impl RequestOrigin {
+    /// Read `X-Forwarded-Host` (else `Host`) and `X-Forwarded-Proto`, but only when
+    /// `peer` is a trusted proxy; anyone else could name any host.
+    pub fn from_request(proxies: &ProxyConfig, headers: &HeaderMap, peer: Option<IpAddr>) -> Self {
+        if !peer.is_some_and(|ip| proxies.is_trusted(ip)) {
+            return Self::default();
+        }
+
+        // Proxies append to these headers, so the first value is the client-facing one
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        Self {
+            host: header("x-forwarded-host").or_else(|| header("host")).filter(|host| is_valid_host(host)),
+            scheme: header("x-forwarded-proto")
+                .map(|scheme| scheme.to_ascii_lowercase())
+                .filter(|scheme| scheme == "http" || scheme == "https"),
+        }
+    }
+}
+
+/// A hostname, optionally with a port; no paths, credentials or whitespace
+fn is_valid_host(host: &str) -> bool {
+    host.len() <= 255
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+}
+
+/// Origin (`scheme://host`, no trailing slash) for absolute links: the site's custom
+/// domain, else the host a trusted proxy reported, else `public_url.base_url`
+pub fn resolve_base_url(config: &PublicUrlConfig, site_domain: Option<&str>, origin: &RequestOrigin) -> String {
+    if let Some(domain) = site_domain.map(str::trim).filter(|domain| !domain.is_empty()) {
+        return format!("https://{}", domain);
+    }
+    if let Some(host) = &origin.host {
+        return format!("{}://{}", origin.scheme.as_deref().unwrap_or("https"), host);
+    }
+    config.base_url.trim_end_matches('/').to_string()
+}
+
+/// Origin a site is served from: its custom domain, else its platform subdomain
+pub fn site_origin(subdomain: &str, custom_domain: Option<&str>) -> String {
+    match custom_domain.filter(|domain| !domain.is_empty()) {
+        Some(domain) => format!("https://{}", domain),
+        None => format!("https://{}.{}", subdomain, PLATFORM_SITE_DOMAIN),
+    }
+}
+
+/// Join an origin and a path with exactly one slash between them
+pub fn absolute_url(base_url: &str, path: &str) -> String {
+    format!("{}/{}", base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROXY: [u8; 4] = [10, 0, 0, 2];
+
+    fn config() -> PublicUrlConfig {
+        PublicUrlConfig {
+            base_url: "https://app.quillspace.com/".to_string(),
+        }
+    }
+
+    fn proxies() -> ProxyConfig {
+        ProxyConfig {
+            trusted_proxies: vec!["10.0.0.2".parse().unwrap()],
+        }
+    }
+
+    fn forwarded_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("host", "internal:3000".parse().unwrap());
+        headers.insert("x-forwarded-host", "studio.example.com, internal".parse().unwrap());
+        headers.insert("x-forwarded-proto", "https".parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_preview_link_uses_site_domain() {
+        let config = config();
+        let origin = RequestOrigin::from_request(&proxies(), &forwarded_headers(), Some(IpAddr::from(PROXY)));
+
+        let base = resolve_base_url(&config, Some("janeausten.com"), &origin);
+        assert_eq!(absolute_url(&base, "/preview/abc"), "https://janeausten.com/preview/abc");
+
+        // Without a custom domain the forwarded host, then the configured base URL, is used
+        assert_eq!(resolve_base_url(&config, Some(""), &origin), "https://studio.example.com");
+        assert_eq!(resolve_base_url(&config, None, &RequestOrigin::default()), "https://app.quillspace.com");
+    }
+
+    #[test]
+    fn test_forwarded_host_only_trusted_from_proxies() {
+        let config = config();
+        let headers = forwarded_headers();
+
+        let trusted = RequestOrigin::from_request(&proxies(), &headers, Some(IpAddr::from(PROXY)));
+        assert_eq!(trusted.host.as_deref(), Some("studio.example.com"));
+        assert_eq!(trusted.scheme.as_deref(), Some("https"));
+
+        for peer in [Some(IpAddr::from([203, 0, 113, 9])), None] {
+            let origin = RequestOrigin::from_request(&proxies(), &headers, peer);
+            assert_eq!(origin, RequestOrigin::default());
+            assert_eq!(resolve_base_url(&config, None, &origin), "https://app.quillspace.com");
+        }
+
+        let mut spoofed = headers.clone();
+        spoofed.insert("x-forwarded-host", "evil.example/phish?".parse().unwrap());
+        let origin = RequestOrigin::from_request(&proxies(), &spoofed, Some(IpAddr::from(PROXY)));
+        assert_eq!(origin.host, None);
+    }
+
+    #[test]
+    fn test_site_origin() {
+        assert_eq!(site_origin("jane", None), "https://jane.quillspace.app");
+        assert_eq!(site_origin("jane", Some("janeausten.com")), "https://janeausten.com");
+    }
+}
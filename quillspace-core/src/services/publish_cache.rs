@@ -0,0 +1,199 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// A rendered public response along with its validator
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedPage {
+    pub body: String,
+    /// Strong ETag (quoted) derived from the body
+    pub etag: String,
+}
+
+impl CachedPage {
+    pub fn new(body: String) -> Self {
+        let etag = format!("\"{}\"", &hex::encode(Sha256::digest(body.as_bytes()))[..32]);
+        Self { body, etag }
+    }
+
+    /// Whether an `If-None-Match` header already names this version
+    pub fn matches(&self, if_none_match: Option<&str>) -> bool {
+        if_none_match.is_some_and(|header| {
+            header
+                .split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == self.etag)
+        })
+    }
+}
+
+/// In-process cache of everything served publicly for a site: page HTML and
+/// site-wide documents such as the sitemap or feed, keyed by site and path.
+///
+/// Publishing or unpublishing drops every entry for the site synchronously, so
+/// the next public fetch is rendered from the database.
+#[derive(Debug, Default)]
+pub struct PublishCache {
+    inner: RwLock<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<(Uuid, String), CachedPage>,
+    /// Bumped on every invalidation so loads started before it are not cached
+    generations: HashMap<Uuid, u64>,
+}
+
+impl PublishCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, site_id: Uuid, path: &str) -> Option<CachedPage> {
+        self.inner.read().ok()?.entries.get(&(site_id, path.to_string())).cloned()
+    }
+
+    pub fn insert(&self, site_id: Uuid, path: &str, body: String) -> CachedPage {
+        let page = CachedPage::new(body);
+        if let Ok(mut state) = self.inner.write() {
+            state.entries.insert((site_id, path.to_string()), page.clone());
+        }
+        page
+    }
+
+    /// Return the cached entry or load and cache it; `None` from `load` is not cached,
+    /// nor is a result loaded while the site was being invalidated
+    pub async fn get_or_load<F, Fut, E>(&self, site_id: Uuid, path: &str, load: F) -> Result<Option<CachedPage>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<String>, E>>,
+    {
+        if let Some(page) = self.get(site_id, path) {
+            return Ok(Some(page));
+        }
+
+        let generation = self.generation(site_id);
+        let Some(body) = load().await? else {
+            return Ok(None);
+        };
+        let page = CachedPage::new(body);
+        if let Ok(mut state) = self.inner.write() {
+            if state.generations.get(&site_id).copied().unwrap_or(0) == generation {
+                state.entries.insert((site_id, path.to_string()), page.clone());
+            }
+        }
+        Ok(Some(page))
+    }
+
+    /// Drop every cached entry of a site
+    pub fn invalidate_site(&self, site_id: Uuid) {
+        if let Ok(mut state) = self.inner.write() {
+            state.entries.retain(|(cached_site_id, _), _| *cached_site_id != site_id);
+            *state.generations.entry(site_id).or_default() += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().map(|state| state.entries.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn generation(&self, site_id: Uuid) -> u64 {
+        self.inner
+            .read()
+            .map(|state| state.generations.get(&site_id).copied().unwrap_or(0))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::sync::Mutex;
+
+    /// Stand-in for the pages table
+    struct FakePages(Mutex<HashMap<String, String>>);
+
+    impl FakePages {
+        fn publish(&self, path: &str, html: &str) {
+            self.0.lock().unwrap().insert(path.to_string(), html.to_string());
+        }
+
+        async fn load(&self, path: &str) -> Result<Option<String>, Infallible> {
+            Ok(self.0.lock().unwrap().get(path).cloned())
+        }
+    }
+
+    #[test]
+    fn test_publish_invalidates_site_entries() {
+        let cache = PublishCache::new();
+        let site_id = Uuid::new_v4();
+        let other_site_id = Uuid::new_v4();
+        cache.insert(site_id, "/about", "<p>v1</p>".to_string());
+        cache.insert(site_id, "/sitemap.xml", "<urlset/>".to_string());
+        cache.insert(other_site_id, "/about", "<p>other</p>".to_string());
+
+        cache.invalidate_site(site_id);
+
+        assert_eq!(cache.get(site_id, "/about"), None);
+        assert_eq!(cache.get(site_id, "/sitemap.xml"), None);
+        assert!(cache.get(other_site_id, "/about").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_after_publish_returns_new_content() {
+        let cache = PublishCache::new();
+        let pages = FakePages(Mutex::new(HashMap::new()));
+        let site_id = Uuid::new_v4();
+
+        pages.publish("/about", "<p>v1</p>");
+        let first = cache.get_or_load(site_id, "/about", || pages.load("/about")).await.unwrap().unwrap();
+        assert_eq!(first.body, "<p>v1</p>");
+
+        // Without invalidation the stale copy is served
+        pages.publish("/about", "<p>v2</p>");
+        let stale = cache.get_or_load(site_id, "/about", || pages.load("/about")).await.unwrap().unwrap();
+        assert_eq!(stale, first);
+
+        cache.invalidate_site(site_id);
+        let fresh = cache.get_or_load(site_id, "/about", || pages.load("/about")).await.unwrap().unwrap();
+        assert_eq!(fresh.body, "<p>v2</p>");
+        assert_ne!(fresh.etag, first.etag);
+        assert!(!fresh.matches(Some(&first.etag)));
+        assert!(fresh.matches(Some(&format!("W/{}", fresh.etag))));
+    }
+
+    #[tokio::test]
+    async fn test_missing_pages_not_cached() {
+        let cache = PublishCache::new();
+        let pages = FakePages(Mutex::new(HashMap::new()));
+
+        let missing = cache.get_or_load(Uuid::new_v4(), "/draft", || pages.load("/draft")).await.unwrap();
+        assert_eq!(missing, None);
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_racing_invalidation_not_cached() {
+        let cache = PublishCache::new();
+        let site_id = Uuid::new_v4();
+
+        let page = cache
+            .get_or_load(site_id, "/about", || async {
+                // The author publishes while this visitor's read is in flight
+                cache.invalidate_site(site_id);
+                Ok::<_, Infallible>(Some("<p>v1</p>".to_string()))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(page.map(|page| page.body).as_deref(), Some("<p>v1</p>"));
+        assert_eq!(cache.get(site_id, "/about"), None);
+    }
+}
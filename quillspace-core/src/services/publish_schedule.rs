@@ -0,0 +1,379 @@
+//! Publishing windows: a page can be published at one time and unpublished at another,
+//! and content, whose publish is scheduled through review (`ContentService`), can be
+//! given an expiry. The scheduler applies both through the owner-run
+//! `apply_publishing_windows()` from 028_publishing_windows.sql, which records each
+//! change in `publish_schedule_events`.
+
+use crate::database::postgres::tenant_transaction;
+use crate::services::content::{content_status_to_string, ContentService};
+use crate::types::{ContentStatus, TenantId};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PublishScheduleError {
+    #[error("Not found")]
+    NotFound,
+
+    #[error("Already published")]
+    AlreadyPublished,
+
+    #[error("Unpublish time must be after the publish time")]
+    InvalidWindow,
+
+    #[error("A scheduled page publish needs the rendered HTML to publish")]
+    RenderedHtmlRequired,
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleTarget {
+    Content,
+    Page,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleAction {
+    Publish,
+    Unpublish,
+}
+
+/// A publish or unpublish the scheduler made
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScheduledChange {
+    pub tenant_id: Uuid,
+    pub target: ScheduleTarget,
+    pub target_id: Uuid,
+    /// The page's site; `None` for content
+    pub site_id: Option<Uuid>,
+    pub action: ScheduleAction,
+    pub scheduled_for: DateTime<Utc>,
+}
+
+/// When a page goes live and when it comes down again (UTC); either may be left out
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageWindow {
+    pub publish_at: Option<DateTime<Utc>>,
+    pub unpublish_at: Option<DateTime<Utc>>,
+    /// What to publish at `publish_at`, rendered as for an immediate publish
+    pub rendered_html: Option<String>,
+}
+
+/// An item's publishing state after its window is set
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowState {
+    pub id: Uuid,
+    pub is_published: bool,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub unpublish_at: Option<DateTime<Utc>>,
+    /// Changes made straight away because a time had already passed
+    pub applied: Vec<ScheduledChange>,
+}
+
+fn check_window(publish_at: Option<DateTime<Utc>>, unpublish_at: Option<DateTime<Utc>>) -> Result<(), PublishScheduleError> {
+    match (publish_at, unpublish_at) {
+        (Some(publish_at), Some(unpublish_at)) if unpublish_at <= publish_at => Err(PublishScheduleError::InvalidWindow),
+        _ => Ok(()),
+    }
+}
+
+pub struct PublishScheduleService {
+    db: Pool,
+}
+
+impl PublishScheduleService {
+    pub fn new(db: Pool) -> Self {
+        Self { db }
+    }
+
+    /// Set a page's publishing window. Times already passed at `now` take effect at
+    /// once, so a past expiry unpublishes the page immediately.
+    pub async fn set_page_window(
+        &self,
+        tenant_id: &TenantId,
+        page_id: Uuid,
+        window: PageWindow,
+        now: DateTime<Utc>,
+    ) -> Result<WindowState, PublishScheduleError> {
+        check_window(window.publish_at, window.unpublish_at)?;
+        if window.publish_at.is_some() && window.rendered_html.is_none() {
+            return Err(PublishScheduleError::RenderedHtmlRequired);
+        }
+
+        let mut client = self.db.get().await.context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let is_published: bool = transaction
+            .query_opt("SELECT is_published FROM pages WHERE id = $1 AND tenant_id = $2 FOR UPDATE", &[&page_id, tenant_id.as_uuid()])
+            .await
+            .context("Failed to load page")?
+            .ok_or(PublishScheduleError::NotFound)?
+            .get(0);
+        if is_published && window.publish_at.is_some() {
+            return Err(PublishScheduleError::AlreadyPublished);
+        }
+        transaction
+            .execute(
+                "UPDATE pages SET scheduled_publish_at = $2, unpublish_at = $3,
+                     published_html = COALESCE($4, published_html), updated_at = $5
+                 WHERE id = $1",
+                &[&page_id, &window.publish_at, &window.unpublish_at, &window.rendered_html, &now],
+            )
+            .await
+            .context("Failed to schedule page")?;
+        transaction.commit().await.context("Failed to commit page schedule")?;
+
+        let applied = self.apply_due(now, Some(page_id)).await?;
+        let row = self
+            .tenant_query_one(tenant_id, "SELECT is_published, scheduled_publish_at, unpublish_at FROM pages WHERE id = $1", page_id)
+            .await?;
+        Ok(WindowState {
+            id: page_id,
+            is_published: row.get(0),
+            publish_at: row.get(1),
+            unpublish_at: row.get(2),
+            applied,
+        })
+    }
+
+    /// Set when content comes down again, or with `None` clear it. It must be after any
+    /// scheduled publish; an expiry already passed at `now` unpublishes at once.
+    pub async fn set_content_expiry(
+        &self,
+        tenant_id: &TenantId,
+        content_id: Uuid,
+        unpublish_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<WindowState, PublishScheduleError> {
+        let mut client = self.db.get().await.context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let publish_at: Option<DateTime<Utc>> = transaction
+            .query_opt(
+                "SELECT scheduled_publish_at FROM content WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+                &[&content_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to load content")?
+            .ok_or(PublishScheduleError::NotFound)?
+            .get(0);
+        check_window(publish_at, unpublish_at)?;
+        transaction
+            .execute(
+                "UPDATE content SET unpublish_at = $2, updated_at = $3 WHERE id = $1",
+                &[&content_id, &unpublish_at, &now],
+            )
+            .await
+            .context("Failed to set content expiry")?;
+        transaction.commit().await.context("Failed to commit content expiry")?;
+
+        let applied = self.apply_due(now, Some(content_id)).await?;
+        let row = self
+            .tenant_query_one(
+                tenant_id,
+                "SELECT lower(status) = 'published', scheduled_publish_at, unpublish_at FROM content WHERE id = $1",
+                content_id,
+            )
+            .await?;
+        Ok(WindowState {
+            id: content_id,
+            is_published: row.get(0),
+            publish_at: row.get(1),
+            unpublish_at: row.get(2),
+            applied,
+        })
+    }
+
+    /// Publish pages and unpublish pages and content whose time has come at `now`,
+    /// across all tenants, or only the item `only`
+    pub async fn apply_due(&self, now: DateTime<Utc>, only: Option<Uuid>) -> anyhow::Result<Vec<ScheduledChange>> {
+        let client = self.db.get().await.context("Failed to get database connection")?;
+        let draft = content_status_to_string(&ContentStatus::Draft);
+        let rows = client
+            .query("SELECT * FROM apply_publishing_windows($1, $2, $3)", &[&now, &draft, &only])
+            .await
+            .context("Failed to apply publishing windows")?;
+        Ok(rows
+            .iter()
+            .map(|row| ScheduledChange {
+                tenant_id: row.get("tenant_id"),
+                target: match row.get::<_, &str>("target_type") {
+                    "page" => ScheduleTarget::Page,
+                    _ => ScheduleTarget::Content,
+                },
+                target_id: row.get("target_id"),
+                site_id: row.get("site_id"),
+                action: match row.get::<_, &str>("action") {
+                    "publish" => ScheduleAction::Publish,
+                    _ => ScheduleAction::Unpublish,
+                },
+                scheduled_for: row.get("scheduled_for"),
+            })
+            .collect())
+    }
+
+    /// Every `period` for the lifetime of the process: publish due content, then apply
+    /// the publishing windows, handing what changed to `on_change`
+    pub fn spawn_scheduler<F>(self, period: Duration, on_change: F) -> JoinHandle<()>
+    where
+        F: Fn(&[ScheduledChange]) + Send + 'static,
+    {
+        let content = ContentService::new(self.db.clone());
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let now = Utc::now();
+                match content.publish_due(now).await {
+                    Ok(0) => {}
+                    Ok(count) => info!(count, "Published scheduled content"),
+                    Err(e) => error!("Scheduled publishing failed: {:#}", e),
+                }
+                match self.apply_due(now, None).await {
+                    Ok(changes) if changes.is_empty() => {}
+                    Ok(changes) => {
+                        info!(count = changes.len(), "Applied publishing windows");
+                        on_change(&changes);
+                    }
+                    Err(e) => error!("Publishing windows failed: {:#}", e),
+                }
+            }
+        })
+    }
+
+    async fn tenant_query_one(&self, tenant_id: &TenantId, sql: &str, id: Uuid) -> anyhow::Result<tokio_postgres::Row> {
+        let mut client = self.db.get().await.context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let row = transaction.query_one(sql, &[&id]).await.context("Failed to load publishing window")?;
+        transaction.commit().await.context("Failed to commit")?;
+        Ok(row)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    async fn events(app: &crate::test_harness::TestApp, target_id: Uuid) -> Vec<(String, DateTime<Utc>)> {
+        let admin = app.admin_pool.get().await.unwrap();
+        admin
+            .query(
+                "SELECT action, scheduled_for FROM publish_schedule_events WHERE target_id = $1 ORDER BY occurred_at, action",
+                &[&target_id],
+            )
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect()
+    }
+
+    async fn seed_page(app: &crate::test_harness::TestApp, published: bool) -> Uuid {
+        let admin = app.admin_pool.get().await.unwrap();
+        let tenant_id = app.tenant_a.id.as_uuid();
+        let site_id: Uuid = admin
+            .query_one(
+                "INSERT INTO sites (tenant_id, name, subdomain) VALUES ($1, 'Sale', $2) RETURNING id",
+                &[tenant_id, &format!("sale-{}", Uuid::new_v4().simple())],
+            )
+            .await
+            .unwrap()
+            .get(0);
+        admin
+            .query_one(
+                "INSERT INTO pages (tenant_id, site_id, slug, title, is_published, published_html)
+                 VALUES ($1, $2, 'spring-sale', 'Spring sale', $3, CASE WHEN $3 THEN '<h1>Sale</h1>' END)
+                 RETURNING id",
+                &[tenant_id, &site_id, &published],
+            )
+            .await
+            .unwrap()
+            .get(0)
+    }
+
+    #[tokio::test]
+    async fn test_page_live_only_within_its_window() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let page_id = seed_page(&app, false).await;
+        let service = PublishScheduleService::new(app.state.db.postgres().clone());
+        let at = |hour: u32| Utc.with_ymd_and_hms(2026, 5, 1, hour, 0, 0).unwrap();
+
+        let window = PageWindow { publish_at: Some(at(10)), unpublish_at: Some(at(12)), rendered_html: Some("<h1>Sale</h1>".to_string()) };
+        let state = service.set_page_window(&app.tenant_a.id, page_id, window, at(9)).await.unwrap();
+        assert!(!state.is_published && state.applied.is_empty());
+
+        let changes = service.apply_due(at(11), None).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].target_id, changes[0].action), (page_id, ScheduleAction::Publish));
+        assert!(service.apply_due(at(11), None).await.unwrap().is_empty());
+
+        let changes = service.apply_due(at(13), None).await.unwrap();
+        assert_eq!((changes[0].target_id, changes[0].action), (page_id, ScheduleAction::Unpublish));
+        let admin = app.admin_pool.get().await.unwrap();
+        let row = admin
+            .query_one("SELECT is_published, scheduled_publish_at, unpublish_at FROM pages WHERE id = $1", &[&page_id])
+            .await
+            .unwrap();
+        assert!(!row.get::<_, bool>(0));
+        assert_eq!(row.get::<_, Option<DateTime<Utc>>>(2), None);
+        assert_eq!(events(&app, page_id).await, vec![("publish".to_string(), at(10)), ("unpublish".to_string(), at(12))]);
+
+        let backwards = PageWindow { publish_at: Some(at(14)), unpublish_at: Some(at(14)), rendered_html: Some(String::new()) };
+        assert!(matches!(
+            service.set_page_window(&app.tenant_a.id, page_id, backwards, at(13)).await,
+            Err(PublishScheduleError::InvalidWindow)
+        ));
+        // Another tenant's page is not found
+        let window = PageWindow { unpublish_at: Some(at(14)), ..PageWindow::default() };
+        assert!(matches!(
+            service.set_page_window(&app.tenant_b.id, page_id, window, at(13)).await,
+            Err(PublishScheduleError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_past_expiry_unpublishes_immediately() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let page_id = seed_page(&app, true).await;
+        let service = PublishScheduleService::new(app.state.db.postgres().clone());
+        let now = Utc.with_ymd_and_hms(2026, 5, 1, 9, 0, 0).unwrap();
+        let expired = now - chrono::Duration::minutes(5);
+
+        let window = PageWindow { unpublish_at: Some(expired), ..PageWindow::default() };
+        let state = service.set_page_window(&app.tenant_a.id, page_id, window, now).await.unwrap();
+        assert!(!state.is_published);
+        assert_eq!(state.applied.len(), 1);
+        assert_eq!(events(&app, page_id).await, vec![("unpublish".to_string(), expired)]);
+
+        let admin = app.admin_pool.get().await.unwrap();
+        let content_id: Uuid = admin
+            .query_one(
+                "INSERT INTO content (tenant_id, author_id, title, slug, body, status, locale, published_at, translation_group_id)
+                 VALUES ($1, $2, 'Flash sale', 'flash-sale', '', 'Published', 'en-US', $3, uuid_generate_v4())
+                 RETURNING id",
+                &[app.tenant_a.id.as_uuid(), &app.tenant_a.admin.id, &expired],
+            )
+            .await
+            .unwrap()
+            .get(0);
+        let state = service.set_content_expiry(&app.tenant_a.id, content_id, Some(expired), now).await.unwrap();
+        assert!(!state.is_published);
+        let status: String = admin.query_one("SELECT status FROM content WHERE id = $1", &[&content_id]).await.unwrap().get(0);
+        assert_eq!(status, "Draft");
+        assert_eq!(events(&app, content_id).await, vec![("unpublish".to_string(), expired)]);
+    }
+}
@@ -0,0 +1,378 @@
+use crate::database::postgres::tenant_transaction;
+use crate::types::TenantId;
+use anyhow::Context;
+use deadpool_postgres::Pool;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// Longest source path or destination accepted
+const MAX_PATH_LENGTH: usize = 2048;
+
+/// A redirect rule of a site. `source_path` is either an exact path (`/old-post`)
+/// or a prefix ending in `*` (`/blog/*`); see `resolve_redirect` for precedence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SiteRedirect {
+    pub id: Uuid,
+    pub site_id: Uuid,
+    pub source_path: String,
+    /// A path on the site or an absolute `http(s)` URL. For prefix rules a trailing `*`
+    /// is replaced by the rest of the requested path.
+    pub destination: String,
+    pub status_code: i16,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Create or replace a redirect rule
+#[derive(Debug, Deserialize)]
+pub struct RedirectRuleRequest {
+    pub source_path: String,
+    pub destination: String,
+    /// 301 (permanent, the default) or 302 (temporary)
+    pub status_code: Option<u16>,
+}
+
+/// Where a request is sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectTarget {
+    pub location: String,
+    pub status_code: u16,
+}
+
+/// Redirect rule errors
+#[derive(Debug, thiserror::Error)]
+pub enum RedirectError {
+    #[error("Invalid source path: {0}")]
+    InvalidSource(&'static str),
+
+    #[error("Invalid destination: {0}")]
+    InvalidDestination(&'static str),
+
+    #[error("Redirect status must be 301 or 302, got {0}")]
+    InvalidStatus(u16),
+
+    #[error("A redirect for this source path already exists")]
+    DuplicateSource,
+
+    #[error("Site not found")]
+    SiteNotFound,
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// A validated rule, ready to store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectRule {
+    pub source_path: String,
+    pub destination: String,
+    pub status_code: i16,
+}
+
+impl RedirectRuleRequest {
+    /// Check and normalize the rule: paths lose a trailing slash, `*` may only end a
+    /// source, and a rule may not point at itself
+    pub fn validate(&self) -> Result<RedirectRule, RedirectError> {
+        let source = self.source_path.trim();
+        if !source.starts_with('/') {
+            return Err(RedirectError::InvalidSource("must start with '/'"));
+        }
+        if source.len() > MAX_PATH_LENGTH || source.contains(|c: char| c.is_whitespace() || c == '?' || c == '#') {
+            return Err(RedirectError::InvalidSource("must be a plain path without query or fragment"));
+        }
+        let prefix = source.strip_suffix('*');
+        if prefix.unwrap_or(source).contains('*') {
+            return Err(RedirectError::InvalidSource("'*' is only allowed at the end"));
+        }
+        let source_path = match prefix {
+            Some(prefix) => format!("{}*", prefix),
+            None => normalize_path(source).to_string(),
+        };
+
+        let destination = self.destination.trim();
+        let absolute = destination.starts_with("https://") || destination.starts_with("http://");
+        if !(absolute || destination.starts_with('/')) || destination.starts_with("//") {
+            return Err(RedirectError::InvalidDestination("must be a path or an http(s) URL"));
+        }
+        if destination.len() > MAX_PATH_LENGTH || destination.contains(char::is_whitespace) {
+            return Err(RedirectError::InvalidDestination("must not contain whitespace"));
+        }
+        if destination.strip_suffix('*').unwrap_or(destination).contains('*')
+            || (destination.ends_with('*') && prefix.is_none())
+        {
+            return Err(RedirectError::InvalidDestination("'*' is only allowed at the end of a prefix rule's destination"));
+        }
+        let loops = match prefix {
+            Some(prefix) => destination.starts_with(prefix) || normalize_path(destination) == normalize_path(prefix),
+            None => normalize_path(destination) == source_path,
+        };
+        if loops && !absolute {
+            return Err(RedirectError::InvalidDestination("redirects to itself"));
+        }
+
+        let status_code = match self.status_code.unwrap_or(301) {
+            status @ (301 | 302) => status as i16,
+            status => return Err(RedirectError::InvalidStatus(status)),
+        };
+
+        Ok(RedirectRule { source_path, destination: destination.to_string(), status_code })
+    }
+}
+
+/// Find where `path` redirects to. An exact rule beats any prefix rule, and among
+/// prefix rules the longest prefix wins, so `/blog/2019/*` overrides `/blog/*`.
+pub fn resolve_redirect(rules: &[SiteRedirect], path: &str) -> Option<RedirectTarget> {
+    let path = normalize_path(path);
+
+    if let Some(rule) = rules.iter().find(|rule| !rule.source_path.ends_with('*') && rule.source_path == path) {
+        return Some(RedirectTarget { location: rule.destination.clone(), status_code: rule.status_code as u16 });
+    }
+
+    rules
+        .iter()
+        .filter_map(|rule| {
+            let prefix = rule.source_path.strip_suffix('*')?;
+            // `/blog/*` also catches `/blog` itself
+            let rest = path.strip_prefix(prefix).or_else(|| (path == prefix.trim_end_matches('/')).then_some(""))?;
+            Some((prefix.len(), rule, rest))
+        })
+        .max_by_key(|(length, _, _)| *length)
+        .map(|(_, rule, rest)| RedirectTarget {
+            location: match rule.destination.strip_suffix('*') {
+                Some(destination) => format!("{}{}", destination, rest),
+                None => rule.destination.clone(),
+            },
+            status_code: rule.status_code as u16,
+        })
+}
+
+/// Compare paths without their trailing slash
+fn normalize_path(path: &str) -> &str {
+    match path.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    }
+}
+
+/// Service managing per-site redirect rules
+#[derive(Clone)]
+pub struct RedirectService {
+    db: Pool,
+}
+
+impl RedirectService {
+    pub fn new(db: Pool) -> Self {
+        Self { db }
+    }
+
+    /// All redirect rules of a site, in source path order
+    pub async fn list_redirects(&self, tenant_id: &TenantId, site_id: Uuid) -> Result<Vec<SiteRedirect>, RedirectError> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let rows = transaction
+            .query(
+                "SELECT * FROM site_redirects WHERE tenant_id = $1 AND site_id = $2 ORDER BY source_path",
+                &[tenant_id.as_uuid(), &site_id],
+            )
+            .await
+            .context("Failed to load redirects")?;
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+
+        Ok(rows.iter().map(row_to_redirect).collect())
+    }
+
+    /// Add a rule; a rule for the same source path must be updated instead
+    pub async fn create_redirect(
+        &self,
+        tenant_id: &TenantId,
+        site_id: Uuid,
+        rule: &RedirectRule,
+    ) -> Result<SiteRedirect, RedirectError> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        // The site must belong to the tenant
+        transaction
+            .query_opt("SELECT id FROM sites WHERE id = $1 AND tenant_id = $2", &[&site_id, tenant_id.as_uuid()])
+            .await
+            .context("Failed to look up site")?
+            .ok_or(RedirectError::SiteNotFound)?;
+
+        let row = transaction
+            .query_opt(
+                "INSERT INTO site_redirects (tenant_id, site_id, source_path, destination, status_code)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (site_id, source_path) DO NOTHING
+                 RETURNING *",
+                &[tenant_id.as_uuid(), &site_id, &rule.source_path, &rule.destination, &rule.status_code],
+            )
+            .await
+            .context("Failed to create redirect")?
+            .ok_or(RedirectError::DuplicateSource)?;
+
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+        Ok(row_to_redirect(&row))
+    }
+
+    /// Replace a rule. Returns `None` when it does not exist.
+    pub async fn update_redirect(
+        &self,
+        tenant_id: &TenantId,
+        site_id: Uuid,
+        redirect_id: Uuid,
+        rule: &RedirectRule,
+    ) -> Result<Option<SiteRedirect>, RedirectError> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let taken = transaction
+            .query_opt(
+                "SELECT id FROM site_redirects WHERE site_id = $1 AND source_path = $2 AND id <> $3",
+                &[&site_id, &rule.source_path, &redirect_id],
+            )
+            .await
+            .context("Failed to check redirect source")?;
+        if taken.is_some() {
+            return Err(RedirectError::DuplicateSource);
+        }
+
+        let row = transaction
+            .query_opt(
+                "UPDATE site_redirects SET source_path = $4, destination = $5, status_code = $6, updated_at = NOW()
+                 WHERE id = $1 AND tenant_id = $2 AND site_id = $3
+                 RETURNING *",
+                &[&redirect_id, tenant_id.as_uuid(), &site_id, &rule.source_path, &rule.destination, &rule.status_code],
+            )
+            .await
+            .context("Failed to update redirect")?;
+
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+        Ok(row.as_ref().map(row_to_redirect))
+    }
+
+    /// Delete a rule. Returns whether it existed.
+    pub async fn delete_redirect(&self, tenant_id: &TenantId, site_id: Uuid, redirect_id: Uuid) -> Result<bool, RedirectError> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let deleted = transaction
+            .execute(
+                "DELETE FROM site_redirects WHERE id = $1 AND tenant_id = $2 AND site_id = $3",
+                &[&redirect_id, tenant_id.as_uuid(), &site_id],
+            )
+            .await
+            .context("Failed to delete redirect")?;
+
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+        Ok(deleted > 0)
+    }
+
+    /// Where a public request for `path` on the site redirects to, if anywhere
+    pub async fn find_redirect(&self, tenant_id: &TenantId, site_id: Uuid, path: &str) -> Result<Option<RedirectTarget>, RedirectError> {
+        let rules = self.list_redirects(tenant_id, site_id).await?;
+        Ok(resolve_redirect(&rules, path))
+    }
+}
+
+
+fn row_to_redirect(row: &Row) -> SiteRedirect {
+    SiteRedirect {
+        id: row.get("id"),
+        site_id: row.get("site_id"),
+        source_path: row.get("source_path"),
+        destination: row.get("destination"),
+        status_code: row.get("status_code"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(source_path: &str, destination: &str, status_code: u16) -> SiteRedirect {
+        let rule = RedirectRuleRequest {
+            source_path: source_path.to_string(),
+            destination: destination.to_string(),
+            status_code: Some(status_code),
+        }
+        .validate()
+        .unwrap();
+        SiteRedirect {
+            id: Uuid::new_v4(),
+            site_id: Uuid::nil(),
+            source_path: rule.source_path,
+            destination: rule.destination,
+            status_code: rule.status_code,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn target(location: &str, status_code: u16) -> Option<RedirectTarget> {
+        Some(RedirectTarget { location: location.to_string(), status_code })
+    }
+
+    #[test]
+    fn test_exact_redirect() {
+        let rules = vec![rule("/about-me/", "/about", 301)];
+
+        assert_eq!(resolve_redirect(&rules, "/about-me"), target("/about", 301));
+        assert_eq!(resolve_redirect(&rules, "/about-me/"), target("/about", 301));
+        assert_eq!(resolve_redirect(&rules, "/about-me/team"), None);
+    }
+
+    #[test]
+    fn test_prefix_redirect() {
+        let rules = vec![
+            rule("/blog/*", "/articles/*", 301),
+            rule("/old-shop/*", "https://shop.example.com/", 302),
+        ];
+
+        assert_eq!(resolve_redirect(&rules, "/blog/my-first-post"), target("/articles/my-first-post", 301));
+        assert_eq!(resolve_redirect(&rules, "/blog"), target("/articles/", 301));
+        assert_eq!(resolve_redirect(&rules, "/old-shop/cart"), target("https://shop.example.com/", 302));
+        assert_eq!(resolve_redirect(&rules, "/blogroll"), None);
+    }
+
+    #[test]
+    fn test_exact_beats_prefix_and_longest_prefix_wins() {
+        let rules = vec![
+            rule("/blog/*", "/articles/*", 301),
+            rule("/blog/2019/*", "/archive/*", 301),
+            rule("/blog/2019/launch", "/launch", 302),
+        ];
+
+        assert_eq!(resolve_redirect(&rules, "/blog/2019/launch"), target("/launch", 302));
+        assert_eq!(resolve_redirect(&rules, "/blog/2019/recap"), target("/archive/recap", 301));
+        assert_eq!(resolve_redirect(&rules, "/blog/2020/plans"), target("/articles/2020/plans", 301));
+    }
+
+    #[test]
+    fn test_invalid_rules_rejected() {
+        let request = |source: &str, destination: &str, status: u16| RedirectRuleRequest {
+            source_path: source.to_string(),
+            destination: destination.to_string(),
+            status_code: Some(status),
+        };
+
+        assert!(matches!(request("old", "/new", 301).validate(), Err(RedirectError::InvalidSource(_))));
+        assert!(matches!(request("/a*/b", "/new", 301).validate(), Err(RedirectError::InvalidSource(_))));
+        assert!(matches!(request("/old", "//evil.example", 301).validate(), Err(RedirectError::InvalidDestination(_))));
+        assert!(matches!(request("/old", "/new/*", 301).validate(), Err(RedirectError::InvalidDestination(_))));
+        assert!(matches!(request("/same/", "/same", 301).validate(), Err(RedirectError::InvalidDestination(_))));
+        assert!(matches!(request("/blog/*", "/blog/new/*", 301).validate(), Err(RedirectError::InvalidDestination(_))));
+        assert!(matches!(request("/old", "/new", 307).validate(), Err(RedirectError::InvalidStatus(307))));
+    }
+}
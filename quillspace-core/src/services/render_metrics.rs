@@ -0,0 +1,273 @@
+use crate::services::metrics_registry::MetricsRegistry;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Histogram of render times, labeled by `tenant` and `category`
+pub const RENDER_DURATION_METRIC: &str = "template_render_duration_seconds";
+/// Counter of failed renders, labeled by `tenant`, `category` and `error_type`
+pub const RENDER_ERRORS_METRIC: &str = "template_render_errors_total";
+
+/// Label for tenants and categories beyond the tracked set
+pub const OTHER_LABEL: &str = "other";
+
+/// Categories that get their own label; free-form categories are reported as `other`
+const CATEGORY_LABELS: &[&str] = &["page", "layout", "blog", "landing", "email", "email_text", "text"];
+
+/// Tenants labeled by id before the rest are folded into `other`
+const MAX_TENANT_LABELS: usize = 200;
+
+/// Templates kept for the slowest-templates report
+const MAX_TRACKED_TEMPLATES: usize = 2000;
+
+/// Why a render failed, as reported in the `error_type` label
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderErrorType {
+    /// The template source does not parse
+    Syntax,
+    /// The template used a value the context does not have
+    Undefined,
+    /// Any other failure while evaluating the template
+    Render,
+    /// The template could not be loaded
+    Database,
+    Other,
+}
+
+impl RenderErrorType {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RenderErrorType::Syntax => "syntax",
+            RenderErrorType::Undefined => "undefined",
+            RenderErrorType::Render => "render",
+            RenderErrorType::Database => "database",
+            RenderErrorType::Other => "other",
+        }
+    }
+
+    /// Classify a render error by the first error in its chain we recognise
+    pub fn classify(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(e) = cause.downcast_ref::<minijinja::Error>() {
+                return match e.kind() {
+                    minijinja::ErrorKind::SyntaxError => RenderErrorType::Syntax,
+                    minijinja::ErrorKind::UndefinedError => RenderErrorType::Undefined,
+                    _ => RenderErrorType::Render,
+                };
+            }
+            if cause.is::<tokio_postgres::Error>() || cause.is::<deadpool_postgres::PoolError>() {
+                return RenderErrorType::Database;
+            }
+        }
+        RenderErrorType::Other
+    }
+}
+
+/// Render timings for one template, as shown in the slowest-templates report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateRenderStats {
+    pub tenant_id: Uuid,
+    pub template_name: String,
+    pub category: String,
+    pub renders: u64,
+    pub errors: u64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Default)]
+struct TemplateTiming {
+    category: String,
+    renders: u64,
+    errors: u64,
+    total: Duration,
+    max: Duration,
+}
+
+/// Records template render times as metrics and keeps per-template timings in memory.
+///
+/// Metric labels stay bounded: only the first `MAX_TENANT_LABELS` tenants seen and the
+/// well-known categories get a label of their own, and template names are never labels.
+#[derive(Debug, Default)]
+pub struct RenderMetrics {
+    registry: MetricsRegistry,
+    labeled_tenants: Mutex<HashSet<Uuid>>,
+    templates: Mutex<HashMap<(Uuid, String), TemplateTiming>>,
+}
+
+impl RenderMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send metrics to `registry` rather than the current recorder
+    pub fn with_registry(registry: MetricsRegistry) -> Self {
+        Self {
+            registry,
+            ..Self::default()
+        }
+    }
+
+    /// Record one render of `template_name`, failed if `error` is set
+    pub fn record(
+        &self,
+        tenant_id: Uuid,
+        template_name: &str,
+        category: &str,
+        elapsed: Duration,
+        error: Option<&anyhow::Error>,
+    ) {
+        let tenant = self.tenant_label(tenant_id);
+        let category_label = category_label(category);
+
+        self.registry.record(|| {
+            metrics::histogram!(RENDER_DURATION_METRIC, "tenant" => tenant.clone(), "category" => category_label)
+                .record(elapsed.as_secs_f64());
+            if let Some(error) = error {
+                let error_type = RenderErrorType::classify(error).label();
+                metrics::counter!(
+                    RENDER_ERRORS_METRIC,
+                    "tenant" => tenant,
+                    "category" => category_label,
+                    "error_type" => error_type
+                )
+                .increment(1);
+            }
+        });
+
+        if let Ok(mut templates) = self.templates.lock() {
+            let key = (tenant_id, template_name.to_string());
+            if !templates.contains_key(&key) && templates.len() >= MAX_TRACKED_TEMPLATES {
+                return;
+            }
+            let timing = templates.entry(key).or_default();
+            timing.category = category.to_string();
+            timing.renders += 1;
+            timing.errors += u64::from(error.is_some());
+            timing.total += elapsed;
+            timing.max = timing.max.max(elapsed);
+        }
+    }
+
+    /// The tenant's templates with the highest average render time, slowest first
+    pub fn slowest(&self, tenant_id: Uuid, limit: usize) -> Vec<TemplateRenderStats> {
+        let Ok(templates) = self.templates.lock() else {
+            return Vec::new();
+        };
+        let mut stats: Vec<TemplateRenderStats> = templates
+            .iter()
+            .filter(|((tenant, _), _)| *tenant == tenant_id)
+            .map(|((tenant, name), timing)| TemplateRenderStats {
+                tenant_id: *tenant,
+                template_name: name.clone(),
+                category: timing.category.clone(),
+                renders: timing.renders,
+                errors: timing.errors,
+                avg_ms: timing.total.as_secs_f64() * 1000.0 / timing.renders as f64,
+                max_ms: timing.max.as_secs_f64() * 1000.0,
+            })
+            .collect();
+        stats.sort_by(|a, b| b.avg_ms.total_cmp(&a.avg_ms));
+        stats.truncate(limit);
+        stats
+    }
+
+    fn tenant_label(&self, tenant_id: Uuid) -> String {
+        let Ok(mut tenants) = self.labeled_tenants.lock() else {
+            return OTHER_LABEL.to_string();
+        };
+        if tenants.contains(&tenant_id) || tenants.len() < MAX_TENANT_LABELS {
+            tenants.insert(tenant_id);
+            tenant_id.to_string()
+        } else {
+            OTHER_LABEL.to_string()
+        }
+    }
+}
+
+/// Metric label for a template category
+pub fn category_label(category: &str) -> &'static str {
+    CATEGORY_LABELS
+        .iter()
+        .find(|label| **label == category)
+        .copied()
+        .unwrap_or(OTHER_LABEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    /// Run `f` with a local Prometheus recorder and return what it recorded
+    fn recorded(f: impl FnOnce()) -> String {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, f);
+        handle.render()
+    }
+
+    #[test]
+    fn test_render_records_duration_histogram() {
+        let metrics = RenderMetrics::new();
+        let tenant_id = Uuid::new_v4();
+
+        let output = recorded(|| metrics.record(tenant_id, "home", "page", Duration::from_millis(12), None));
+
+        let count = format!("{}_count{{", RENDER_DURATION_METRIC);
+        let tenant = format!("tenant=\"{}\"", tenant_id);
+        assert!(
+            output.lines().any(|line| line.starts_with(&count)
+                && line.contains(&tenant)
+                && line.contains("category=\"page\"")
+                && line.ends_with(" 1")),
+            "{}",
+            output
+        );
+        assert!(!output.contains(RENDER_ERRORS_METRIC));
+
+        let slowest = metrics.slowest(tenant_id, 10);
+        assert_eq!(slowest.len(), 1);
+        assert_eq!((slowest[0].template_name.as_str(), slowest[0].renders, slowest[0].errors), ("home", 1, 0));
+        assert!(metrics.slowest(Uuid::new_v4(), 10).is_empty());
+    }
+
+    #[test]
+    fn test_render_error_counted_by_type() {
+        let metrics = RenderMetrics::new();
+        let tenant_id = Uuid::new_v4();
+        let syntax_error = Err::<(), _>(minijinja::Error::new(minijinja::ErrorKind::SyntaxError, "unexpected end of input"))
+            .context("Failed to add template to environment")
+            .unwrap_err();
+        assert_eq!(RenderErrorType::classify(&syntax_error), RenderErrorType::Syntax);
+
+        let output = recorded(|| {
+            metrics.record(tenant_id, "broken", "page", Duration::from_millis(3), Some(&syntax_error))
+        });
+
+        let tenant = format!("tenant=\"{}\"", tenant_id);
+        assert!(
+            output.lines().any(|line| line.starts_with(RENDER_ERRORS_METRIC)
+                && line.contains(&tenant)
+                && line.contains("error_type=\"syntax\"")
+                && line.ends_with(" 1")),
+            "{}",
+            output
+        );
+        assert_eq!(metrics.slowest(tenant_id, 10)[0].errors, 1);
+    }
+
+    #[test]
+    fn test_label_cardinality_bounded() {
+        let metrics = RenderMetrics::new();
+        for _ in 0..MAX_TENANT_LABELS {
+            assert_ne!(metrics.tenant_label(Uuid::new_v4()), OTHER_LABEL);
+        }
+        assert_eq!(metrics.tenant_label(Uuid::new_v4()), OTHER_LABEL);
+        assert_eq!(category_label("page"), "page");
+        assert_eq!(category_label("my-custom-category"), OTHER_LABEL);
+    }
+}
@@ -0,0 +1,204 @@
+//! Field-level validation of create and update request bodies.
+//!
+//! Each request type implements [`Validate`], checking every field rather than stopping
+//! at the first problem, and a handler answers a failed check with
+//! [`ValidationErrors::into_response`]: a 422 whose `data.fields` maps each offending
+//! field to what is wrong with it:
+//!
+//! ```json
+//! { "success": false, "error": "Validation failed",
+//!   "data": { "fields": { "slug": [{ "code": "slug_format", "message": "..." }] } } }
+//! ```
+
+use crate::services::slug::{SlugRules, MAX_SLUG_LEN};
+use crate::types::ApiResponse;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Longest page or content title, in characters
+pub const MAX_TITLE_LEN: usize = 200;
+
+/// Longest site, template or user name, in characters
+pub const MAX_NAME_LEN: usize = 100;
+
+/// Longest description or meta text, in characters
+pub const MAX_DESCRIPTION_LEN: usize = 1000;
+
+/// Longest email address or custom domain
+const MAX_ADDRESS_LEN: usize = 254;
+
+/// What is wrong with one field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// Stable identifier clients can match on, such as `too_long`
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// Every failed check of a request, by field
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ValidationErrors {
+    pub fields: BTreeMap<&'static str, Vec<FieldError>>,
+}
+
+/// A request body that can be checked before it is acted on
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: &'static str, code: &'static str, message: impl Into<String>) {
+        self.fields.entry(field).or_default().push(FieldError { code, message: message.into() });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// The errors recorded for `field`
+    pub fn field(&self, field: &str) -> &[FieldError] {
+        self.fields.get(field).map_or(&[], Vec::as_slice)
+    }
+
+    /// `Ok` when no check failed
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// A field that must have something other than whitespace
+    pub fn required(&mut self, field: &'static str, value: &str) {
+        if value.trim().is_empty() {
+            self.add(field, "required", format!("{} is required", field));
+        }
+    }
+
+    pub fn max_length(&mut self, field: &'static str, value: &str, max: usize) {
+        if value.chars().count() > max {
+            self.add(field, "too_long", format!("{} must be at most {} characters", field, max));
+        }
+    }
+
+    /// A stored slug: lowercase letters and digits separated by single hyphens
+    pub fn slug(&mut self, field: &'static str, value: &str) {
+        if value.is_empty() {
+            self.add(field, "required", format!("{} is required", field));
+        } else if value.len() > MAX_SLUG_LEN {
+            self.add(field, "too_long", format!("{} must be at most {} characters", field, MAX_SLUG_LEN));
+        } else if SlugRules::default().slugify(value) != value {
+            self.add(
+                field,
+                "slug_format",
+                format!("{} must be lowercase letters and digits separated by single hyphens", field),
+            );
+        }
+    }
+
+    /// A page slug as requested, which is normalized before it is stored, so it only
+    /// needs something to keep
+    pub fn page_slug(&mut self, field: &'static str, value: &str) {
+        if value.trim().is_empty() {
+            self.add(field, "required", format!("{} is required", field));
+        } else if SlugRules::default().slugify(value).is_empty() {
+            self.add(field, "slug_format", format!("{} must contain letters or digits", field));
+        } else {
+            self.max_length(field, value, MAX_SLUG_LEN);
+        }
+    }
+
+    /// A bare host name such as `www.example.com`, without scheme, port or path
+    pub fn domain(&mut self, field: &'static str, value: &str) {
+        let valid = value.len() <= MAX_ADDRESS_LEN
+            && value.contains('.')
+            && value.split('.').all(|label| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        if !valid {
+            self.add(field, "domain_format", format!("{} must be a host name such as www.example.com", field));
+        }
+    }
+
+    pub fn email(&mut self, field: &'static str, value: &str) {
+        let valid = value.len() <= MAX_ADDRESS_LEN
+            && !value.chars().any(char::is_whitespace)
+            && matches!(value.split_once('@'), Some((local, host))
+                if !local.is_empty() && host.contains('.') && !host.starts_with('.') && !host.ends_with('.') && !host.contains('@'));
+        if !valid {
+            self.add(field, "email_format", format!("{} must be an email address", field));
+        }
+    }
+
+    /// The 422 answering a request that failed validation
+    pub fn into_response(self, request_id: Uuid) -> Response {
+        let response = ApiResponse {
+            success: false,
+            error: Some("Validation failed".to_string()),
+            data: Some(self),
+            request_id,
+        };
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(response)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_pass_valid_values() {
+        let mut errors = ValidationErrors::new();
+        errors.required("title", "Hello");
+        errors.max_length("title", "Hello", MAX_TITLE_LEN);
+        errors.slug("slug", "hello-world-2");
+        errors.page_slug("slug", "About Us");
+        errors.domain("custom_domain", "www.example.com");
+        errors.email("email", "ada@example.com");
+        assert_eq!(errors.into_result(), Ok(()));
+    }
+
+    #[test]
+    fn test_each_failed_check_is_keyed_by_field() {
+        let mut errors = ValidationErrors::new();
+        errors.required("title", "  ");
+        errors.slug("slug", "Hello World");
+        errors.domain("custom_domain", "https://example.com/");
+        errors.email("email", "ada");
+
+        assert_eq!(errors.field("title")[0].code, "required");
+        assert_eq!(errors.field("slug")[0].code, "slug_format");
+        assert_eq!(errors.field("custom_domain")[0].code, "domain_format");
+        assert_eq!(errors.field("email")[0].code, "email_format");
+        assert!(errors.field("body").is_empty());
+    }
+
+    #[test]
+    fn test_response_lists_fields() {
+        let mut errors = ValidationErrors::new();
+        errors.max_length("title", &"x".repeat(MAX_TITLE_LEN + 1), MAX_TITLE_LEN);
+        let value = serde_json::to_value(ApiResponse {
+            success: false,
+            error: None,
+            data: Some(errors),
+            request_id: Uuid::nil(),
+        })
+        .unwrap();
+        assert_eq!(value["data"]["fields"]["title"][0]["code"], "too_long");
+    }
+}
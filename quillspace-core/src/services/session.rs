@@ -0,0 +1,276 @@
+use crate::database::postgres::tenant_client;
+use crate::types::TenantId;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use deadpool_postgres::Pool;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio_postgres::Row;
+use uuid::Uuid;
+
+/// Prefix marking QuillSpace refresh tokens
+pub const REFRESH_TOKEN_PREFIX: &str = "qsr_";
+const REFRESH_TOKEN_SECRET_LEN: usize = 48;
+
+/// A login's refresh-token session (never includes the token itself)
+#[derive(Debug, Clone, Serialize)]
+pub struct Session {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub user_id: Uuid,
+    #[serde(skip)]
+    pub token_version: i32,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+/// Device details recorded when a session starts
+#[derive(Debug, Clone, Default)]
+pub struct SessionClient {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+/// Newly created session; `refresh_token` is only ever returned here
+#[derive(Debug)]
+pub struct CreatedSession {
+    pub session: Session,
+    pub refresh_token: String,
+}
+
+/// Service for refresh-token sessions, so users can see and revoke their logins
+pub struct SessionService {
+    db: Pool,
+}
+
+impl SessionService {
+    pub fn new(db: Pool) -> Self {
+        Self { db }
+    }
+
+    /// Start a session for a user who just logged in
+    pub async fn create_session(
+        &self,
+        tenant_id: Uuid,
+        user_id: Uuid,
+        token_version: i32,
+        client: &SessionClient,
+        ttl: Duration,
+    ) -> Result<CreatedSession> {
+        let db = tenant_client(&self.db, &TenantId::from_uuid(tenant_id)).await?;
+
+        let refresh_token = generate_refresh_token();
+        let expires_at = Utc::now() + ttl;
+        let row = db
+            .query_one(
+                "INSERT INTO refresh_sessions (tenant_id, user_id, token_hash, token_version, user_agent, ip_address, expires_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 RETURNING *",
+                &[
+                    &tenant_id,
+                    &user_id,
+                    &hash_refresh_token(&refresh_token),
+                    &token_version,
+                    &client.user_agent,
+                    &client.ip_address,
+                    &expires_at,
+                ],
+            )
+            .await
+            .context("Failed to create session")?;
+
+        Ok(CreatedSession {
+            session: row_to_session(&row),
+            refresh_token,
+        })
+    }
+
+    /// Active sessions of a user, most recently used first
+    pub async fn list_sessions(&self, tenant_id: &TenantId, user_id: Uuid) -> Result<Vec<Session>> {
+        let db = tenant_client(&self.db, tenant_id).await?;
+
+        let rows = db
+            .query(
+                "SELECT * FROM refresh_sessions
+                 WHERE tenant_id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+                 ORDER BY last_used_at DESC",
+                &[tenant_id.as_uuid(), &user_id],
+            )
+            .await
+            .context("Failed to list sessions")?;
+
+        Ok(rows.iter().map(row_to_session).collect())
+    }
+
+    /// Revoke one of a user's sessions. Returns false if no active session matched.
+    pub async fn revoke_session(&self, tenant_id: &TenantId, user_id: Uuid, session_id: Uuid) -> Result<bool> {
+        let db = tenant_client(&self.db, tenant_id).await?;
+
+        let rows_affected = db
+            .execute(
+                "UPDATE refresh_sessions SET revoked_at = NOW()
+                 WHERE id = $1 AND tenant_id = $2 AND user_id = $3 AND revoked_at IS NULL",
+                &[&session_id, tenant_id.as_uuid(), &user_id],
+            )
+            .await
+            .context("Failed to revoke session")?;
+
+        Ok(rows_affected > 0)
+    }
+
+    /// Revoke every session of a user except `keep`; returns how many were revoked
+    pub async fn revoke_other_sessions(&self, tenant_id: &TenantId, user_id: Uuid, keep: Option<Uuid>) -> Result<u64> {
+        let db = tenant_client(&self.db, tenant_id).await?;
+
+        db.execute(
+            "UPDATE refresh_sessions SET revoked_at = NOW()
+             WHERE tenant_id = $1 AND user_id = $2 AND revoked_at IS NULL
+               AND ($3::uuid IS NULL OR id <> $3)",
+            &[tenant_id.as_uuid(), &user_id, &keep],
+        )
+        .await
+        .context("Failed to revoke sessions")
+    }
+
+    /// Resolve a presented refresh token to its session, if still active, and mark it used
+    pub async fn use_refresh_token(&self, refresh_token: &str) -> Result<Option<Session>> {
+        if !refresh_token.starts_with(REFRESH_TOKEN_PREFIX) {
+            return Ok(None);
+        }
+
+        let db = self.db.get().await
+            .context("Failed to get database connection")?;
+
+        let row = db
+            .query_opt(
+                "SELECT * FROM authenticate_refresh_token($1)",
+                &[&hash_refresh_token(refresh_token)],
+            )
+            .await
+            .context("Failed to look up refresh token")?;
+
+        let session = match row.map(|row| row_to_session(&row)) {
+            Some(session) if session.is_active(Utc::now()) => session,
+            _ => return Ok(None),
+        };
+
+        drop(db);
+
+        // Conditional so a revocation racing this refresh still wins
+        let updated = tenant_client(&self.db, &TenantId::from_uuid(session.tenant_id))
+            .await?
+            .execute(
+                "UPDATE refresh_sessions SET last_used_at = NOW() WHERE id = $1 AND revoked_at IS NULL",
+                &[&session.id],
+            )
+            .await
+            .context("Failed to record session usage")?;
+
+        Ok((updated > 0).then_some(session))
+    }
+}
+
+fn generate_refresh_token() -> String {
+    let secret: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(REFRESH_TOKEN_SECRET_LEN)
+        .map(char::from)
+        .collect();
+    format!("{}{}", REFRESH_TOKEN_PREFIX, secret)
+}
+
+/// Tokens are long random strings, so a fast digest is sufficient for storage
+fn hash_refresh_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Convert database row to Session struct
+fn row_to_session(row: &Row) -> Session {
+    Session {
+        id: row.get("id"),
+        tenant_id: row.get("tenant_id"),
+        user_id: row.get("user_id"),
+        token_version: row.get("token_version"),
+        user_agent: row.get("user_agent"),
+        ip_address: row.get("ip_address"),
+        created_at: row.get("created_at"),
+        last_used_at: row.get("last_used_at"),
+        expires_at: row.get("expires_at"),
+        revoked_at: row.get("revoked_at"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::postgres::create_pool;
+
+    #[test]
+    fn test_refresh_token_format() {
+        let token = generate_refresh_token();
+        assert!(token.starts_with(REFRESH_TOKEN_PREFIX));
+        assert_eq!(token.len(), REFRESH_TOKEN_PREFIX.len() + REFRESH_TOKEN_SECRET_LEN);
+        assert_ne!(token, generate_refresh_token());
+        assert!(!hash_refresh_token(&token).contains(&token[REFRESH_TOKEN_PREFIX.len()..]));
+    }
+
+    #[tokio::test]
+    async fn test_revoked_session_cannot_refresh_while_others_continue() {
+        let Ok(url) = std::env::var("QUILLSPACE_TEST_DATABASE_URL") else {
+            return;
+        };
+        let pool = create_pool(&url, &Default::default()).await.expect("Failed to connect to test database");
+        let client = pool.get().await.expect("Failed to get connection");
+        let tenant_id = TenantId::from_uuid(Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap());
+        let user_id: Uuid = client
+            .query_one("SELECT id FROM users WHERE tenant_id = $1 LIMIT 1", &[tenant_id.as_uuid()])
+            .await
+            .expect("Seed user missing")
+            .get(0);
+        let service = SessionService::new(pool.clone());
+        service.revoke_other_sessions(&tenant_id, user_id, None).await.expect("Failed to reset sessions");
+
+        let device = |agent: &str| SessionClient {
+            user_agent: Some(agent.to_string()),
+            ip_address: Some("203.0.113.7".to_string()),
+        };
+        let laptop = service
+            .create_session(*tenant_id.as_uuid(), user_id, 0, &device("Firefox on Linux"), Duration::days(7))
+            .await
+            .expect("Failed to create session");
+        let phone = service
+            .create_session(*tenant_id.as_uuid(), user_id, 0, &device("Safari on iOS"), Duration::days(7))
+            .await
+            .expect("Failed to create session");
+
+        let listed = service.list_sessions(&tenant_id, user_id).await.expect("Failed to list sessions");
+        let mut listed_ids: Vec<_> = listed.iter().map(|session| session.id).collect();
+        listed_ids.sort();
+        let mut expected = vec![laptop.session.id, phone.session.id];
+        expected.sort();
+        assert_eq!(listed_ids, expected);
+        assert!(listed.iter().any(|session| session.user_agent.as_deref() == Some("Safari on iOS")));
+
+        assert!(service.revoke_session(&tenant_id, user_id, phone.session.id).await.expect("Failed to revoke"));
+        assert!(service.use_refresh_token(&phone.refresh_token).await.expect("Refresh failed").is_none());
+        let still_active = service.use_refresh_token(&laptop.refresh_token).await.expect("Refresh failed");
+        assert_eq!(still_active.map(|session| session.id), Some(laptop.session.id));
+
+        // Another user's session id cannot be revoked through this user
+        assert!(!service.revoke_session(&tenant_id, Uuid::new_v4(), laptop.session.id).await.expect("Failed to revoke"));
+        assert_eq!(service.revoke_other_sessions(&tenant_id, user_id, Some(laptop.session.id)).await.unwrap(), 0);
+        assert_eq!(service.list_sessions(&tenant_id, user_id).await.unwrap().len(), 1);
+    }
+}
@@ -1,3 +1,11 @@
+use crate::database::postgres::{tenant_client, tenant_transaction};
+use crate::services::locale;
+use crate::services::request_validation::{Validate, ValidationErrors, MAX_DESCRIPTION_LEN, MAX_NAME_LEN};
+use crate::services::site_access::SiteAccess;
+use crate::services::site_analytics::SiteAnalyticsSettings;
+use crate::services::site_error_pages::SiteErrorPages;
+use crate::services::site_theme::load_tenant_theme;
+use crate::services::subdomain::{SubdomainError, SubdomainGenerator};
 use crate::types::{TenantId, UserId};
 use anyhow::{Context, Result};
 use deadpool_postgres::Pool;
@@ -6,6 +14,10 @@ use serde_json::Value;
 use tokio_postgres::Row;
 use uuid::Uuid;
 
+/// Subdomains kept for the platform. Subdomains are one namespace across tenants, so
+/// tenant-reserved words (`settings.slugs.reserved`) apply to page slugs only.
+pub const RESERVED_SUBDOMAINS: &[&str] = &["www", "api", "admin", "app", "mail", "ftp", "blog", "shop", "store"];
+
 /// Site entity representing an author's (website-builder)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Site {
@@ -20,6 +32,14 @@ pub struct Site {
     pub seo_settings: Value,
     pub build_status: String,
     pub theme_config: Value,
+    /// Locale every visitor sees, overriding their `Accept-Language`
+    pub locale: Option<String>,
+    /// Locale a multilingual site falls back to
+    pub default_locale: Option<String>,
+    /// Who may view the published site; holds password and token hashes, so it is
+    /// never serialized
+    #[serde(skip_serializing, default)]
+    pub access_protection: SiteAccess,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -34,6 +54,8 @@ pub struct CreateSiteRequest {
     pub subdomain: Option<String>, // If not provided, will be auto-generated
     pub seo_settings: Option<Value>,
     pub theme_config: Option<Value>,
+    pub locale: Option<String>,
+    pub default_locale: Option<String>,
 }
 
 /// Site update request
@@ -45,9 +67,104 @@ pub struct UpdateSiteRequest {
     pub custom_domain: Option<String>,
     pub seo_settings: Option<Value>,
     pub theme_config: Option<Value>,
+    /// An empty locale clears it
+    pub locale: Option<String>,
+    pub default_locale: Option<String>,
     pub is_published: Option<bool>,
 }
 
+impl Validate for CreateSiteRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required("name", &self.name);
+        errors.max_length("name", &self.name, MAX_NAME_LEN);
+        if let Some(description) = &self.description {
+            errors.max_length("description", description, MAX_DESCRIPTION_LEN);
+        }
+        validate_custom_domain(&mut errors, self.custom_domain.as_deref());
+        if let Some(subdomain) = &self.subdomain {
+            if let Err(e) = SiteService::validate_subdomain(subdomain) {
+                errors.add("subdomain", "subdomain_format", e.to_string());
+            }
+        }
+        validate_locales(&mut errors, self.locale.as_deref(), self.default_locale.as_deref());
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdateSiteRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if let Some(name) = &self.name {
+            errors.required("name", name);
+            errors.max_length("name", name, MAX_NAME_LEN);
+        }
+        if let Some(description) = &self.description {
+            errors.max_length("description", description, MAX_DESCRIPTION_LEN);
+        }
+        validate_custom_domain(&mut errors, self.custom_domain.as_deref());
+        validate_locales(&mut errors, self.locale.as_deref(), self.default_locale.as_deref());
+        errors.into_result()
+    }
+}
+
+/// An empty locale means none
+fn validate_locales(errors: &mut ValidationErrors, locale: Option<&str>, default_locale: Option<&str>) {
+    for (field, tag) in [("locale", locale), ("default_locale", default_locale)] {
+        if let Some(tag) = tag.map(str::trim).filter(|tag| !tag.is_empty()) {
+            if !locale::is_locale_tag(tag) {
+                errors.add(field, "locale_format", format!("'{}' is not a locale tag such as en-US", tag));
+            }
+        }
+    }
+}
+
+/// An empty custom domain means none
+fn validate_custom_domain(errors: &mut ValidationErrors, custom_domain: Option<&str>) {
+    if let Some(domain) = custom_domain.map(str::trim).filter(|domain| !domain.is_empty()) {
+        errors.domain("custom_domain", domain);
+    }
+}
+
+/// Site service errors
+#[derive(Debug, thiserror::Error)]
+pub enum SiteError {
+    #[error("{0}")]
+    InvalidSubdomain(&'static str),
+
+    #[error("Subdomain '{0}' is reserved")]
+    ReservedSubdomain(String),
+
+    #[error("Subdomain '{0}' is already taken")]
+    SubdomainTaken(String),
+
+    #[error("No free subdomain for '{0}'")]
+    NoFreeSubdomain(String),
+
+    #[error("{0}")]
+    InvalidSettings(String),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+impl From<SubdomainError> for SiteError {
+    fn from(e: SubdomainError) -> Self {
+        match e {
+            SubdomainError::Taken(subdomain) => SiteError::SubdomainTaken(subdomain),
+            SubdomainError::Exhausted(name) => SiteError::NoFreeSubdomain(name),
+            SubdomainError::Database(e) => SiteError::Database(e),
+        }
+    }
+}
+
+/// Analytics and error page settings must parse before they are stored
+fn check_site_settings(seo_settings: &Value) -> Result<(), SiteError> {
+    SiteAnalyticsSettings::from_site_settings(seo_settings).map_err(|e| SiteError::InvalidSettings(e.to_string()))?;
+    SiteErrorPages::from_site_settings(seo_settings).map_err(|e| SiteError::InvalidSettings(e.to_string()))?;
+    Ok(())
+}
+
 /// Site service for managing author (website-builder)
 pub struct SiteService {
     db: Pool,
@@ -63,69 +180,64 @@ impl SiteService {
         &self,
         tenant_id: &TenantId,
         request: CreateSiteRequest,
-    ) -> Result<Site> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
-
-        // No RLS context needed - using application-level filtering
-
-        // Generate subdomain if not provided
-        let subdomain = if let Some(subdomain) = request.subdomain {
-            // Validate the provided subdomain
-            self.validate_subdomain(&subdomain)?;
-            subdomain
-        } else {
-            // Generate unique subdomain from site name
-            let generated: String = client
-                .query_one(
-                    "SELECT generate_unique_subdomain($1)",
-                    &[&request.name],
-                )
-                .await
-                .context("Failed to generate unique subdomain")?
-                .get(0);
-            generated
-        };
-
-        // Check if subdomain is already taken
-        let exists = client
-            .query_opt("SELECT id FROM sites WHERE subdomain = $1", &[&subdomain])
-            .await
-            .context("Failed to check subdomain availability")?;
-
-        if exists.is_some() {
-            return Err(anyhow::anyhow!("Subdomain '{}' is already taken", subdomain));
+    ) -> Result<Site, SiteError> {
+        if let Some(seo_settings) = &request.seo_settings {
+            check_site_settings(seo_settings)?;
         }
 
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        // One transaction, so the tenant context set for RLS applies to the inserts
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
 
         let seo_settings = request.seo_settings.unwrap_or_else(|| serde_json::json!({}));
         let theme_config = request.theme_config.unwrap_or_else(|| serde_json::json!({}));
 
-        let row = client
-            .query_one(
-                "INSERT INTO sites (tenant_id, name, description, template_id, custom_domain, subdomain, seo_settings, theme_config) 
-                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8) 
-                 RETURNING *",
-                &[
-                    tenant_id.as_uuid(),
-                    &request.name,
-                    &request.description,
-                    &request.template_id,
-                    &request.custom_domain,
-                    &subdomain,
-                    &seo_settings,
-                    &theme_config,
-                ],
-            )
-            .await
-            .context("Failed to create site")?;
+        // The insert claims the subdomain: it does nothing if another site holds it,
+        // even one created at the same moment
+        let insert = |subdomain: String| {
+            let client = &transaction;
+            let (name, description, template_id, custom_domain) =
+                (&request.name, &request.description, &request.template_id, &request.custom_domain);
+            let (seo_settings, theme_config) = (&seo_settings, &theme_config);
+            let (locale, default_locale) = (&request.locale, &request.default_locale);
+            async move {
+                client
+                    .query_opt(
+                        "INSERT INTO sites (tenant_id, name, description, template_id, custom_domain, subdomain, seo_settings, theme_config, locale, default_locale)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NULLIF(TRIM($9), ''), NULLIF(TRIM($10), ''))
+                         ON CONFLICT (subdomain) DO NOTHING
+                         RETURNING *",
+                        &[
+                            tenant_id.as_uuid(),
+                            name,
+                            description,
+                            template_id,
+                            custom_domain,
+                            &subdomain,
+                            seo_settings,
+                            theme_config,
+                            locale,
+                            default_locale,
+                        ],
+                    )
+                    .await
+                    .context("Failed to create site")
+            }
+        };
 
-        Ok(row_to_site(&row)?)
+        let row = match &request.subdomain {
+            Some(subdomain) => {
+                Self::validate_subdomain(subdomain)?;
+                insert(subdomain.clone()).await?.ok_or_else(|| SiteError::SubdomainTaken(subdomain.clone()))?
+            }
+            None => SubdomainGenerator::default().reserve(&request.name, insert).await?,
+        };
+        let site = row_to_site(&row)?;
+        transaction.commit().await
+            .context("Failed to commit site")?;
+
+        Ok(site)
     }
 
     /// Get site by ID with tenant isolation
@@ -134,8 +246,7 @@ impl SiteService {
         tenant_id: &TenantId,
         site_id: Uuid,
     ) -> Result<Option<Site>> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         // Use application-level filtering for tenant isolation
         let row = client
@@ -149,10 +260,36 @@ impl SiteService {
         }
     }
 
-    /// Get site by subdomain
-    pub async fn get_site_by_subdomain(&self, subdomain: &str) -> Result<Option<Site>> {
+    /// The tenant theme the tenant's sites inherit (see `site_theme`)
+    pub async fn tenant_theme(&self, tenant_id: &TenantId) -> Result<Value> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let theme = load_tenant_theme(&transaction, tenant_id.as_uuid()).await?;
+        transaction.commit().await.context("Failed to commit")?;
+        Ok(theme)
+    }
+
+    /// The tenant owning the site served at a subdomain or custom domain. Public requests
+    /// carry no tenant, so `site_tenant_for_host()` runs as its owner to find one before
+    /// the RLS-scoped read.
+    async fn host_tenant(&self, host: &str) -> Result<Option<TenantId>> {
         let client = self.db.get().await
             .context("Failed to get database connection")?;
+        let tenant_id: Option<Uuid> = client
+            .query_one("SELECT site_tenant_for_host($1)", &[&host])
+            .await
+            .context("Failed to resolve site tenant")?
+            .get(0);
+        Ok(tenant_id.map(TenantId::from_uuid))
+    }
+
+    /// Get site by subdomain
+    pub async fn get_site_by_subdomain(&self, subdomain: &str) -> Result<Option<Site>> {
+        let Some(tenant_id) = self.host_tenant(subdomain).await? else {
+            return Ok(None);
+        };
+        let client = tenant_client(&self.db, &tenant_id).await?;
 
         let row = client
             .query_opt("SELECT * FROM sites WHERE subdomain = $1", &[&subdomain])
@@ -167,8 +304,10 @@ impl SiteService {
 
     /// Get site by custom domain
     pub async fn get_site_by_domain(&self, domain: &str) -> Result<Option<Site>> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
+        let Some(tenant_id) = self.host_tenant(domain).await? else {
+            return Ok(None);
+        };
+        let client = tenant_client(&self.db, &tenant_id).await?;
 
         let row = client
             .query_opt("SELECT * FROM sites WHERE custom_domain = $1", &[&domain])
@@ -192,14 +331,8 @@ impl SiteService {
             .context("Failed to get database connection")?;
 
         // Use a transaction to ensure RLS context persists
-        let transaction = client.transaction().await
-            .context("Failed to start transaction")?;
-
         // Set RLS context within transaction
-        transaction
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
 
         let rows = transaction
             .query(
@@ -221,15 +354,14 @@ impl SiteService {
         Ok(sites)
     }
 
-    /// List sites for a tenant without RLS (application-level filtering)
+    /// List sites for a tenant, filtering on the tenant in the query as well as through RLS
     pub async fn list_sites_without_rls(
         &self,
         tenant_id: &TenantId,
         limit: i64,
         offset: i64,
     ) -> Result<Vec<Site>> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
+        let client = tenant_client(&self.db, tenant_id).await?;
         let rows = client
             .query(
                 "SELECT * FROM sites WHERE tenant_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3",
@@ -290,15 +422,12 @@ impl SiteService {
         tenant_id: &TenantId,
         site_id: Uuid,
         request: UpdateSiteRequest,
-    ) -> Result<Option<Site>> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
+    ) -> Result<Option<Site>, SiteError> {
+        if let Some(seo_settings) = &request.seo_settings {
+            check_site_settings(seo_settings)?;
+        }
 
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         // Build dynamic update query
         let mut set_clauses = Vec::new();
@@ -341,6 +470,18 @@ impl SiteService {
             params.push(theme_config);
         }
 
+        if let Some(locale) = &request.locale {
+            param_count += 1;
+            set_clauses.push(format!("locale = NULLIF(TRIM(${}), '')", param_count));
+            params.push(locale);
+        }
+
+        if let Some(default_locale) = &request.default_locale {
+            param_count += 1;
+            set_clauses.push(format!("default_locale = NULLIF(TRIM(${}), '')", param_count));
+            params.push(default_locale);
+        }
+
         if let Some(is_published) = &request.is_published {
             param_count += 1;
             set_clauses.push(format!("is_published = ${}", param_count));
@@ -349,7 +490,7 @@ impl SiteService {
 
         if set_clauses.is_empty() {
             // No updates requested, just return the current site
-            return self.get_site(tenant_id, site_id).await;
+            return Ok(self.get_site(tenant_id, site_id).await?);
         }
 
         let query = format!(
@@ -374,14 +515,7 @@ impl SiteService {
         tenant_id: &TenantId,
         site_id: Uuid,
     ) -> Result<bool> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
-
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         let rows_affected = client
             .execute("DELETE FROM sites WHERE id = $1", &[&site_id])
@@ -397,14 +531,7 @@ impl SiteService {
         tenant_id: &TenantId,
         site_id: Uuid,
     ) -> Result<Option<Site>> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
-
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         let row = client
             .query_opt(
@@ -421,20 +548,37 @@ impl SiteService {
         }
     }
 
-    /// Unpublish site
-    pub async fn unpublish_site(
+    /// Replace who may view the published site
+    pub async fn set_access_protection(
         &self,
         tenant_id: &TenantId,
         site_id: Uuid,
+        access: &SiteAccess,
     ) -> Result<Option<Site>> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
+        let row = client
+            .query_opt(
+                "UPDATE sites SET access_protection = $3, updated_at = NOW()
+                 WHERE id = $1 AND tenant_id = $2 RETURNING *",
+                &[&site_id, tenant_id.as_uuid(), &serde_json::to_value(access)?],
+            )
             .await
-            .context("Failed to set RLS tenant context")?;
+            .context("Failed to update site access protection")?;
+
+        match row {
+            Some(row) => Ok(Some(row_to_site(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Unpublish site
+    pub async fn unpublish_site(
+        &self,
+        tenant_id: &TenantId,
+        site_id: Uuid,
+    ) -> Result<Option<Site>> {
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         let row = client
             .query_opt(
@@ -453,13 +597,7 @@ impl SiteService {
 
     /// Count sites for a tenant
     pub async fn count_sites(&self, tenant_id: &TenantId) -> Result<i64> {
-        let client = self.db.get().await
-            .context("Failed to get database connection")?;
-        // Set RLS context
-        client
-            .execute("SELECT set_config('quillspace.tenant_id', $1, true)", &[&tenant_id.to_string()])
-            .await
-            .context("Failed to set RLS tenant context")?;
+        let client = tenant_client(&self.db, tenant_id).await?;
 
         let count: i64 = client
             .query_one("SELECT COUNT(*) FROM sites", &[])
@@ -472,50 +610,28 @@ impl SiteService {
 
     /// Check if subdomain is available (global check across all tenants)
     pub async fn is_subdomain_available(&self, subdomain: &str) -> Result<bool> {
-        let mut client = self.db.get().await
+        let client = self.db.get().await
             .context("Failed to get database connection")?;
 
-        // Use a transaction to temporarily disable FORCE RLS for global subdomain check
-        let transaction = client.transaction().await
-            .context("Failed to start transaction")?;
-
-        // Temporarily disable FORCE RLS for this global check
-        transaction
-            .execute("ALTER TABLE sites NO FORCE ROW LEVEL SECURITY", &[])
-            .await
-            .context("Failed to disable FORCE RLS for subdomain check")?;
-
-        transaction
-            .execute("SET LOCAL row_security = off", &[])
+        // Subdomains are globally unique; subdomain_taken() runs as its owner so the
+        // lookup sees every tenant's sites without relaxing RLS on the table
+        let taken: bool = client
+            .query_one("SELECT subdomain_taken($1)", &[&subdomain])
             .await
-            .context("Failed to disable RLS for subdomain check")?;
-
-        let exists = transaction
-            .query_opt("SELECT id FROM sites WHERE subdomain = $1", &[&subdomain])
-            .await
-            .context("Failed to check subdomain availability")?;
-
-        // Re-enable FORCE RLS
-        transaction
-            .execute("ALTER TABLE sites FORCE ROW LEVEL SECURITY", &[])
-            .await
-            .context("Failed to re-enable FORCE RLS")?;
-
-        // Commit transaction
-        transaction.commit().await
-            .context("Failed to commit transaction")?;
+            .context("Failed to check subdomain availability")?
+            .get(0);
 
-        Ok(exists.is_none())
+        Ok(!taken)
     }
 
     /// Validate subdomain format
-    fn validate_subdomain(&self, subdomain: &str) -> Result<()> {
+    pub(crate) fn validate_subdomain(subdomain: &str) -> Result<(), SiteError> {
         if subdomain.is_empty() {
-            return Err(anyhow::anyhow!("Subdomain cannot be empty"));
+            return Err(SiteError::InvalidSubdomain("Subdomain cannot be empty"));
         }
 
         if subdomain.len() > 63 {
-            return Err(anyhow::anyhow!("Subdomain cannot be longer than 63 characters"));
+            return Err(SiteError::InvalidSubdomain("Subdomain cannot be longer than 63 characters"));
         }
 
         // Check if subdomain matches the pattern: alphanumeric, can contain hyphens but not at start/end
@@ -523,15 +639,13 @@ impl SiteService {
             .context("Failed to compile subdomain regex")?;
 
         if !re.is_match(subdomain) {
-            return Err(anyhow::anyhow!(
+            return Err(SiteError::InvalidSubdomain(
                 "Subdomain must contain only lowercase letters, numbers, and hyphens (not at start/end)"
             ));
         }
 
-        // Check for reserved subdomains
-        let reserved = ["www", "api", "admin", "app", "mail", "ftp", "blog", "shop", "store"];
-        if reserved.contains(&subdomain) {
-            return Err(anyhow::anyhow!("Subdomain '{}' is reserved", subdomain));
+        if RESERVED_SUBDOMAINS.contains(&subdomain) {
+            return Err(SiteError::ReservedSubdomain(subdomain.to_string()));
         }
 
         Ok(())
@@ -552,7 +666,61 @@ fn row_to_site(row: &Row) -> Result<Site> {
         seo_settings: row.get("seo_settings"),
         build_status: row.get("build_status"),
         theme_config: row.get("theme_config"),
+        locale: row.get("locale"),
+        default_locale: row.get("default_locale"),
+        access_protection: serde_json::from_value(row.get("access_protection"))
+            .context("Site has invalid access protection")?,
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::postgres::{create_pool, rls_statuses};
+
+    /// Postgres pool for tests, if `QUILLSPACE_TEST_DATABASE_URL` is set
+    async fn test_pool() -> Option<Pool> {
+        let url = std::env::var("QUILLSPACE_TEST_DATABASE_URL").ok()?;
+        Some(create_pool(&url, &Default::default()).await.expect("Failed to connect to test database"))
+    }
+
+    async fn sites_force_rls(pool: &Pool) -> bool {
+        rls_statuses(pool)
+            .await
+            .expect("Failed to read RLS state")
+            .into_iter()
+            .find(|status| status.table == "sites")
+            .map(|status| status.force_rls)
+            .expect("sites table missing")
+    }
+
+    #[tokio::test]
+    async fn test_failed_subdomain_check_keeps_force_rls() {
+        let Some(pool) = test_pool().await else {
+            return;
+        };
+        assert!(sites_force_rls(&pool).await);
+
+        // Abort a transaction right after the lookup, as an error mid-check would
+        {
+            let mut client = pool.get().await.expect("Failed to get connection");
+            let transaction = client.transaction().await.expect("Failed to start transaction");
+            transaction
+                .query_one("SELECT subdomain_taken($1)", &[&"rls-check"])
+                .await
+                .expect("Subdomain lookup failed");
+            assert!(transaction.execute("SELECT 1 / 0", &[]).await.is_err());
+            transaction.rollback().await.expect("Failed to roll back");
+        }
+
+        let service = SiteService::new(pool.clone());
+        service
+            .is_subdomain_available("rls-check")
+            .await
+            .expect("Subdomain check failed");
+
+        assert!(sites_force_rls(&pool).await);
+    }
+}
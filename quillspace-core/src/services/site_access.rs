@@ -0,0 +1,300 @@
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Cookie remembering that a visitor unlocked a site; suffixed with the site id
+pub const SITE_ACCESS_COOKIE_PREFIX: &str = "qs_site_access_";
+
+/// How long an unlocked site stays unlocked for the visitor
+pub const ACCESS_COOKIE_TTL: Duration = Duration::days(7);
+
+const MIN_PASSWORD_LEN: usize = 8;
+const MIN_TOKEN_LEN: usize = 16;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SiteAccessError {
+    #[error("Site password must be at least {MIN_PASSWORD_LEN} characters")]
+    WeakPassword,
+
+    #[error("Token protection needs at least one token")]
+    NoTokens,
+
+    #[error("Access tokens must be at least {MIN_TOKEN_LEN} characters")]
+    WeakToken,
+
+    #[error("Failed to hash site password: {0}")]
+    Hash(#[from] bcrypt::BcryptError),
+}
+
+/// Who may view a published site, stored in the site's `access_protection` column.
+/// Secrets are kept hashed: the password with bcrypt, tokens with SHA-256.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SiteAccess {
+    /// Anyone
+    #[default]
+    None,
+    /// Visitors who enter the site password
+    Password { password_hash: String },
+    /// Visitors holding one of the tokens, as `?access_token=` or entered in the form
+    Tokens { token_hashes: Vec<String> },
+}
+
+/// Protection requested through the API, with secrets in the clear
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SiteAccessRequest {
+    None,
+    Password { password: String },
+    Tokens { tokens: Vec<String> },
+}
+
+impl SiteAccessRequest {
+    /// The protection to store, with its secrets hashed
+    pub fn into_access(self) -> Result<SiteAccess, SiteAccessError> {
+        match self {
+            Self::None => Ok(SiteAccess::None),
+            Self::Password { password } => {
+                if password.chars().count() < MIN_PASSWORD_LEN {
+                    return Err(SiteAccessError::WeakPassword);
+                }
+                Ok(SiteAccess::Password { password_hash: bcrypt::hash(password, bcrypt::DEFAULT_COST)? })
+            }
+            Self::Tokens { tokens } => {
+                if tokens.is_empty() {
+                    return Err(SiteAccessError::NoTokens);
+                }
+                if tokens.iter().any(|token| token.chars().count() < MIN_TOKEN_LEN) {
+                    return Err(SiteAccessError::WeakToken);
+                }
+                Ok(SiteAccess::Tokens { token_hashes: tokens.iter().map(|token| hash_token(token)).collect() })
+            }
+        }
+    }
+}
+
+/// What a site's protection allows for a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessDecision {
+    /// Not protected
+    Public,
+    /// Protected, and the request carries a valid cookie or token
+    Granted,
+    /// Protected, and the visitor has to unlock it first
+    Denied,
+}
+
+impl SiteAccess {
+    pub fn is_protected(&self) -> bool {
+        !matches!(self, Self::None)
+    }
+
+    /// The mode as shown in API responses, which never include the secrets
+    pub fn mode(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Password { .. } => "password",
+            Self::Tokens { .. } => "tokens",
+        }
+    }
+
+    /// Whether a password or token entered in the login form unlocks the site
+    pub fn accepts_secret(&self, secret: &str) -> bool {
+        match self {
+            Self::None => true,
+            Self::Password { password_hash } => bcrypt::verify(secret, password_hash).unwrap_or(false),
+            Self::Tokens { token_hashes } => {
+                let hash = hash_token(secret);
+                token_hashes.iter().any(|stored| stored == &hash)
+            }
+        }
+    }
+
+    /// Decide a request from its `Cookie` header and `access_token` query parameter.
+    /// Cookies are signed with `key`; `password` protection ignores tokens.
+    pub fn check(
+        &self,
+        site_id: Uuid,
+        cookie_header: Option<&str>,
+        access_token: Option<&str>,
+        key: &[u8],
+        now: DateTime<Utc>,
+    ) -> AccessDecision {
+        if !self.is_protected() {
+            return AccessDecision::Public;
+        }
+        let token_valid = matches!(self, Self::Tokens { .. }) && access_token.is_some_and(|token| self.accepts_secret(token));
+        let cookie_valid = access_cookie_value(site_id, cookie_header)
+            .is_some_and(|value| self.verify_cookie(site_id, value, key, now));
+        if token_valid || cookie_valid {
+            AccessDecision::Granted
+        } else {
+            AccessDecision::Denied
+        }
+    }
+
+    /// `Set-Cookie` value unlocking the site for [`ACCESS_COOKIE_TTL`], marked `Secure`
+    /// when the site is served over https. Changing the password or tokens invalidates
+    /// cookies issued before.
+    pub fn access_cookie(&self, site_id: Uuid, key: &[u8], now: DateTime<Utc>, secure: bool) -> String {
+        let expires = (now + ACCESS_COOKIE_TTL).timestamp();
+        format!(
+            "{}={}.{}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax{}",
+            cookie_name(site_id),
+            expires,
+            self.cookie_signature(site_id, expires, key),
+            ACCESS_COOKIE_TTL.num_seconds(),
+            if secure { "; Secure" } else { "" },
+        )
+    }
+
+    fn verify_cookie(&self, site_id: Uuid, value: &str, key: &[u8], now: DateTime<Utc>) -> bool {
+        let Some((expires, signature)) = value.split_once('.') else {
+            return false;
+        };
+        let (Ok(expires), Ok(signature)) = (expires.parse::<i64>(), hex::decode(signature)) else {
+            return false;
+        };
+        expires > now.timestamp() && self.cookie_mac(site_id, expires, key).verify_slice(&signature).is_ok()
+    }
+
+    fn cookie_signature(&self, site_id: Uuid, expires: i64, key: &[u8]) -> String {
+        hex::encode(self.cookie_mac(site_id, expires, key).finalize().into_bytes())
+    }
+
+    fn cookie_mac(&self, site_id: Uuid, expires: i64, key: &[u8]) -> HmacSha256 {
+        let fingerprint = match self {
+            Self::None => String::new(),
+            Self::Password { password_hash } => password_hash.clone(),
+            Self::Tokens { token_hashes } => token_hashes.join(","),
+        };
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes keys of any length");
+        mac.update(format!("{}:{}:{}", site_id, expires, fingerprint).as_bytes());
+        mac
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn cookie_name(site_id: Uuid) -> String {
+    format!("{}{}", SITE_ACCESS_COOKIE_PREFIX, site_id.simple())
+}
+
+fn access_cookie_value(site_id: Uuid, cookie_header: Option<&str>) -> Option<&str> {
+    let name = cookie_name(site_id);
+    cookie_header
+        .into_iter()
+        .flat_map(|header| header.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(cookie, _)| *cookie == name)
+        .map(|(_, value)| value.trim_matches('"'))
+}
+
+/// Page shown instead of a protected site, posting the password (or token) to `action`
+pub fn login_page(site_name: &str, action: &str, failed: bool) -> String {
+    let error = if failed { "\n        <p role=\"alert\">That didn't work. Please try again.</p>" } else { "" };
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <meta name="robots" content="noindex,nofollow">
+    <title>{name}</title>
+</head>
+<body>
+    <main>
+        <h1>{name}</h1>
+        <p>This site is not public yet. Enter the password to continue.</p>{error}
+        <form method="post" action="{action}">
+            <input type="password" name="password" autocomplete="current-password" required autofocus>
+            <button type="submit">Continue</button>
+        </form>
+    </main>
+</body>
+</html>"#,
+        name = escape_html(site_name),
+        action = escape_html(action),
+        error = error,
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"test-secret-key-of-at-least-32-bytes";
+
+    fn password_protected() -> SiteAccess {
+        SiteAccessRequest::Password { password: "launch-day".to_string() }.into_access().unwrap()
+    }
+
+    #[test]
+    fn test_protected_site_needs_password_then_cookie_grants_access() {
+        let site_id = Uuid::new_v4();
+        let access = password_protected();
+        let now = Utc::now();
+
+        assert_eq!(SiteAccess::None.check(site_id, None, None, KEY, now), AccessDecision::Public);
+        assert_eq!(access.check(site_id, None, None, KEY, now), AccessDecision::Denied);
+        assert!(!access.accepts_secret("wrong-password"));
+        assert!(access.accepts_secret("launch-day"));
+
+        // The cookie set after unlocking grants access to this site only, until it expires
+        let set_cookie = access.access_cookie(site_id, KEY, now, true);
+        assert!(set_cookie.ends_with("; Secure"));
+        assert!(!access.access_cookie(site_id, KEY, now, false).contains("Secure"));
+        let cookie = set_cookie.split(';').next().unwrap();
+        let header = format!("qs_analytics_consent=granted; {}", cookie);
+        assert_eq!(access.check(site_id, Some(&header), None, KEY, now), AccessDecision::Granted);
+        assert_eq!(access.check(Uuid::new_v4(), Some(&header), None, KEY, now), AccessDecision::Denied);
+        assert_eq!(
+            access.check(site_id, Some(&header), None, KEY, now + ACCESS_COOKIE_TTL),
+            AccessDecision::Denied
+        );
+
+        // Forged or stale cookies don't
+        let forged = format!("{}{}={}.{}", SITE_ACCESS_COOKIE_PREFIX, site_id.simple(), now.timestamp() + 60, "00");
+        assert_eq!(access.check(site_id, Some(&forged), None, KEY, now), AccessDecision::Denied);
+        let new_password = SiteAccessRequest::Password { password: "launch-day".to_string() }.into_access().unwrap();
+        assert_eq!(new_password.check(site_id, Some(&header), None, KEY, now), AccessDecision::Denied);
+    }
+
+    #[test]
+    fn test_token_protection() {
+        let site_id = Uuid::new_v4();
+        let access = SiteAccessRequest::Tokens { tokens: vec!["reviewer-token-0001".to_string()] }
+            .into_access()
+            .unwrap();
+        let now = Utc::now();
+
+        assert_eq!(access.check(site_id, None, Some("reviewer-token-0001"), KEY, now), AccessDecision::Granted);
+        assert_eq!(access.check(site_id, None, Some("reviewer-token-0002"), KEY, now), AccessDecision::Denied);
+        // Tokens are only stored hashed
+        assert!(!serde_json::to_string(&access).unwrap().contains("reviewer-token-0001"));
+
+        // Password protection ignores tokens
+        assert_eq!(password_protected().check(site_id, None, Some("launch-day"), KEY, now), AccessDecision::Denied);
+        assert!(matches!(
+            SiteAccessRequest::Tokens { tokens: vec!["short".to_string()] }.into_access(),
+            Err(SiteAccessError::WeakToken)
+        ));
+    }
+
+    #[test]
+    fn test_login_page_escapes_site_name() {
+        let page = login_page("<script>alert(1)</script>", "/api/public/demo/_access", true);
+        assert!(!page.contains("<script>"));
+        assert!(page.contains("role=\"alert\""));
+    }
+}
@@ -0,0 +1,154 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Analytics provider an author can enable for a site
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyticsProvider {
+    Ga4,
+    Plausible,
+    #[default]
+    None,
+}
+
+/// The `analytics` object in a site's settings, e.g.
+/// `{"provider": "ga4", "tracking_id": "G-ABC123", "require_consent": true}`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SiteAnalyticsSettings {
+    pub provider: AnalyticsProvider,
+    /// GA4 measurement id, or the domain registered with Plausible
+    pub tracking_id: String,
+    /// Emit the scripts inert (`type="text/plain"`) so the site's cookie-consent
+    /// banner activates them once the visitor agrees
+    pub require_consent: bool,
+}
+
+impl SiteAnalyticsSettings {
+    /// Read the analytics settings of a site; absent means no analytics
+    pub fn from_site_settings(site_settings: &Value) -> anyhow::Result<Self> {
+        let settings: Self = match site_settings.get("analytics") {
+            Some(Value::Null) | None => return Ok(Self::default()),
+            Some(analytics) => serde_json::from_value(analytics.clone())
+                .map_err(|e| anyhow::anyhow!("Invalid analytics settings: {}", e))?,
+        };
+
+        let valid = match settings.provider {
+            AnalyticsProvider::Ga4 => is_ga4_id(&settings.tracking_id),
+            AnalyticsProvider::Plausible => is_domain(&settings.tracking_id),
+            AnalyticsProvider::None => true,
+        };
+        if !valid {
+            anyhow::bail!("Invalid analytics settings: bad tracking id '{}'", settings.tracking_id);
+        }
+        Ok(settings)
+    }
+
+    /// Script tags for the page `<head>`; empty when analytics is off
+    pub fn snippet(&self) -> String {
+        // Ids are validated to plain `[A-Za-z0-9.-]`, so they are safe to interpolate
+        let script_type = if self.require_consent {
+            r#" type="text/plain" data-consent="analytics""#
+        } else {
+            ""
+        };
+        let id = &self.tracking_id;
+
+        match self.provider {
+            AnalyticsProvider::Ga4 => format!(
+                r#"<script async{script_type} src="https://www.googletagmanager.com/gtag/js?id={id}"></script>
+<script{script_type}>window.dataLayer = window.dataLayer || []; function gtag(){{dataLayer.push(arguments);}} gtag('js', new Date()); gtag('config', '{id}');</script>"#
+            ),
+            AnalyticsProvider::Plausible => format!(
+                r#"<script defer{script_type} data-domain="{id}" src="https://plausible.io/js/script.js"></script>"#
+            ),
+            AnalyticsProvider::None => String::new(),
+        }
+    }
+}
+
+/// Analytics snippet for a site's settings. Invalid settings inject nothing rather than
+/// failing the page render; they are rejected when the site is saved.
+pub fn analytics_snippet(site_settings: &Value) -> String {
+    match SiteAnalyticsSettings::from_site_settings(site_settings) {
+        Ok(settings) => settings.snippet(),
+        Err(e) => {
+            tracing::warn!("Skipping analytics snippet: {:#}", e);
+            String::new()
+        }
+    }
+}
+
+/// Insert `snippet` right before the closing `</head>` tag; pages without one are left alone
+pub fn inject_into_head(html: String, snippet: &str) -> String {
+    if snippet.is_empty() {
+        return html;
+    }
+    match html.to_ascii_lowercase().find("</head>") {
+        Some(index) => {
+            let mut injected = String::with_capacity(html.len() + snippet.len() + 1);
+            injected.push_str(&html[..index]);
+            injected.push_str(snippet);
+            injected.push('\n');
+            injected.push_str(&html[index..]);
+            injected
+        }
+        None => html,
+    }
+}
+
+fn is_ga4_id(id: &str) -> bool {
+    id.strip_prefix("G-")
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()))
+}
+
+fn is_domain(domain: &str) -> bool {
+    domain.contains('.')
+        && !domain.starts_with(['.', '-'])
+        && domain.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PAGE: &str = "<html><head><title>Post</title></head><body></body></html>";
+
+    #[test]
+    fn test_ga4_injects_script_tag() {
+        let settings = json!({ "analytics": { "provider": "ga4", "tracking_id": "G-ABC123" } });
+        let html = inject_into_head(PAGE.to_string(), &analytics_snippet(&settings));
+
+        assert!(html.contains(
+            r#"<script async src="https://www.googletagmanager.com/gtag/js?id=G-ABC123"></script>"#
+        ));
+        assert!(html.contains("gtag('config', 'G-ABC123');"));
+        assert!(html.find("gtag/js").unwrap() < html.find("</head>").unwrap());
+    }
+
+    #[test]
+    fn test_none_injects_nothing() {
+        for settings in [json!({}), json!({ "analytics": { "provider": "none", "tracking_id": "G-ABC123" } })] {
+            assert_eq!(inject_into_head(PAGE.to_string(), &analytics_snippet(&settings)), PAGE);
+        }
+    }
+
+    #[test]
+    fn test_consent_defers_loading() {
+        let settings = json!({
+            "analytics": { "provider": "plausible", "tracking_id": "author.example.com", "require_consent": true }
+        });
+        assert_eq!(
+            analytics_snippet(&settings),
+            r#"<script defer type="text/plain" data-consent="analytics" data-domain="author.example.com" src="https://plausible.io/js/script.js"></script>"#
+        );
+    }
+
+    #[test]
+    fn test_invalid_tracking_id_rejected() {
+        let settings = json!({ "analytics": { "provider": "ga4", "tracking_id": "G-1');alert(1);//" } });
+        assert!(SiteAnalyticsSettings::from_site_settings(&settings).is_err());
+        assert_eq!(analytics_snippet(&settings), "");
+    }
+}
@@ -0,0 +1,347 @@
+use crate::database::postgres::tenant_transaction;
+use crate::config::BackupConfig;
+use crate::services::object_store::{ObjectStore, ObjectStoreError};
+use crate::services::site_export::{ImportedSite, SiteExport, SiteTransferError, SiteTransferService};
+use crate::types::TenantId;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Pool, Transaction};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_postgres::Row;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Object store prefix backups are written under, as `backups/<tenant_id>/<site_id>/<backup_id>.json`
+pub const BACKUP_KEY_PREFIX: &str = "backups";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SiteBackupError {
+    #[error("Backup not found")]
+    NotFound,
+
+    #[error(transparent)]
+    Transfer(#[from] SiteTransferError),
+
+    #[error(transparent)]
+    Store(#[from] ObjectStoreError),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// A stored snapshot of a site
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteBackup {
+    pub id: Uuid,
+    pub site_id: Uuid,
+    pub site_name: String,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub size_bytes: i64,
+    pub page_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where a backup is restored to
+#[derive(Debug, Clone)]
+pub enum RestoreTarget {
+    /// A new, unpublished site, as an import would create
+    NewSite { subdomain: Option<String> },
+    /// An existing site of the tenant, whose pages are replaced
+    ExistingSite(Uuid),
+}
+
+pub fn backup_key(tenant_id: &TenantId, site_id: Uuid, backup_id: Uuid) -> String {
+    format!("{}/{}/{}/{}.json", BACKUP_KEY_PREFIX, tenant_id, site_id, backup_id)
+}
+
+/// Store `export` under `key`, returning its size in bytes
+pub async fn write_snapshot(store: &dyn ObjectStore, key: &str, export: &SiteExport) -> Result<i64, SiteBackupError> {
+    let bytes = serde_json::to_vec(export).context("Failed to serialize site backup")?;
+    let size = bytes.len() as i64;
+    store.put(key, bytes, "application/json").await?;
+    Ok(size)
+}
+
+/// The snapshot stored under `key`
+pub async fn read_snapshot(store: &dyn ObjectStore, key: &str) -> Result<SiteExport, SiteBackupError> {
+    let bytes = store.get(key).await?;
+    let export: SiteExport = serde_json::from_slice(&bytes).context("Failed to parse site backup")?;
+    export.validate()?;
+    Ok(export)
+}
+
+/// Scheduled snapshots of every site in the export format, with retention, and restores
+/// from them
+pub struct SiteBackupService {
+    db: Pool,
+    transfer: SiteTransferService,
+    store: Arc<dyn ObjectStore>,
+    config: BackupConfig,
+}
+
+impl SiteBackupService {
+    pub fn new(db: Pool, transfer: SiteTransferService, store: Arc<dyn ObjectStore>, config: BackupConfig) -> Self {
+        Self { db, transfer, store, config }
+    }
+
+    /// Snapshot a site as it is now, then drop the tenant's backups past retention
+    pub async fn backup_site(&self, tenant_id: &TenantId, site_id: Uuid) -> Result<SiteBackup, SiteBackupError> {
+        let export = self.transfer.export_site(tenant_id, site_id).await?;
+        let backup_id = Uuid::new_v4();
+        let key = backup_key(tenant_id, site_id, backup_id);
+        let size = write_snapshot(self.store.as_ref(), &key, &export).await?;
+
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let row = transaction
+            .query_one(
+                "INSERT INTO site_backups (id, tenant_id, site_id, site_name, storage_key, size_bytes, page_count, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                 RETURNING *",
+                &[
+                    &backup_id,
+                    tenant_id.as_uuid(),
+                    &site_id,
+                    &export.site.name,
+                    &key,
+                    &size,
+                    &(export.pages.len() as i32),
+                    &export.exported_at,
+                ],
+            )
+            .await
+            .context("Failed to record site backup")?;
+        let expired = self.prune(&transaction, tenant_id, export.exported_at).await?;
+        transaction.commit().await
+            .context("Failed to commit site backup")?;
+
+        // The index no longer points at them, so a failed delete only leaves an orphaned file
+        for key in expired {
+            if let Err(e) = self.store.delete(&key).await {
+                warn!("Failed to delete expired backup {}: {}", key, e);
+            }
+        }
+
+        Ok(row_to_backup(&row))
+    }
+
+    /// Remove the tenant's backups beyond `keep_per_site` for their site or older than
+    /// `max_age_days`, including those of deleted sites. Returns their storage keys.
+    async fn prune(
+        &self,
+        transaction: &Transaction<'_>,
+        tenant_id: &TenantId,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<String>> {
+        let cutoff = now - chrono::Duration::days(self.config.max_age_days as i64);
+        let keep = self.config.keep_per_site.max(1) as i64;
+        let rows = transaction
+            .query(
+                "DELETE FROM site_backups WHERE id IN (
+                     SELECT id FROM (
+                         SELECT id, created_at,
+                                row_number() OVER (PARTITION BY site_id ORDER BY created_at DESC) AS position
+                         FROM site_backups WHERE tenant_id = $1
+                     ) ranked
+                     WHERE position > $2 OR created_at < $3
+                 )
+                 RETURNING storage_key",
+                &[tenant_id.as_uuid(), &keep, &cutoff],
+            )
+            .await
+            .context("Failed to prune site backups")?;
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    /// The tenant's backups, newest first, optionally of one site only
+    pub async fn list_backups(&self, tenant_id: &TenantId, site_id: Option<Uuid>) -> Result<Vec<SiteBackup>, SiteBackupError> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let rows = transaction
+            .query(
+                "SELECT * FROM site_backups
+                 WHERE tenant_id = $1 AND ($2::uuid IS NULL OR site_id = $2)
+                 ORDER BY created_at DESC",
+                &[tenant_id.as_uuid(), &site_id],
+            )
+            .await
+            .context("Failed to list site backups")?;
+        Ok(rows.iter().map(row_to_backup).collect())
+    }
+
+    /// Restore a backup. Either way the restore is one transaction: it applies completely
+    /// or not at all.
+    pub async fn restore(
+        &self,
+        tenant_id: &TenantId,
+        backup_id: Uuid,
+        target: RestoreTarget,
+    ) -> Result<ImportedSite, SiteBackupError> {
+        let backup = self.get_backup(tenant_id, backup_id).await?.ok_or(SiteBackupError::NotFound)?;
+        let export = read_snapshot(self.store.as_ref(), &backup.storage_key).await?;
+
+        let restored = match target {
+            RestoreTarget::NewSite { subdomain } => self.transfer.import_site(tenant_id, &export, subdomain).await?,
+            RestoreTarget::ExistingSite(site_id) => self.transfer.restore_into_site(tenant_id, site_id, &export).await?,
+        };
+        Ok(restored)
+    }
+
+    async fn get_backup(&self, tenant_id: &TenantId, backup_id: Uuid) -> Result<Option<SiteBackup>, SiteBackupError> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+        let row = transaction
+            .query_opt(
+                "SELECT * FROM site_backups WHERE id = $1 AND tenant_id = $2",
+                &[&backup_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to read site backup")?;
+        Ok(row.as_ref().map(row_to_backup))
+    }
+
+    /// Back up every site of every tenant. A site that fails is logged and skipped.
+    /// Returns how many were backed up and how many failed.
+    pub async fn backup_all(&self) -> anyhow::Result<(usize, usize)> {
+        let client = self.db.get().await
+            .context("Failed to get database connection")?;
+        // Runs across tenants, so it goes through the owner-run site_backup_targets()
+        let targets: Vec<(Uuid, Uuid)> = client
+            .query("SELECT tenant_id, site_id FROM site_backup_targets()", &[])
+            .await
+            .context("Failed to list sites to back up")?
+            .iter()
+            .map(|row| (row.get("tenant_id"), row.get("site_id")))
+            .collect();
+        drop(client);
+
+        let (mut backed_up, mut failed) = (0, 0);
+        for (tenant_id, site_id) in targets {
+            match self.backup_site(&TenantId::from_uuid(tenant_id), site_id).await {
+                Ok(_) => backed_up += 1,
+                Err(e) => {
+                    failed += 1;
+                    error!("Failed to back up site {} of tenant {}: {:#}", site_id, tenant_id, e);
+                }
+            }
+        }
+        Ok((backed_up, failed))
+    }
+
+    /// Back up every site each `interval_hours` for the lifetime of the process
+    pub fn spawn_backup_task(self) -> JoinHandle<()> {
+        let period = Duration::from_secs(self.config.interval_hours.max(1) * 3600);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            // The first tick is immediate; wait a full period so restarts don't back up again
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                match self.backup_all().await {
+                    Ok((backed_up, failed)) => info!(backed_up, failed, "Site backups completed"),
+                    Err(e) => error!("Site backups failed: {:#}", e),
+                }
+            }
+        })
+    }
+}
+
+
+fn row_to_backup(row: &Row) -> SiteBackup {
+    SiteBackup {
+        id: row.get("id"),
+        site_id: row.get("site_id"),
+        site_name: row.get("site_name"),
+        storage_key: row.get("storage_key"),
+        size_bytes: row.get("size_bytes"),
+        page_count: row.get("page_count"),
+        created_at: row.get("created_at"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::object_store::MemoryObjectStore;
+    use crate::services::{page::Page, site::Site};
+    use serde_json::json;
+
+    fn site() -> Site {
+        Site {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            name: "Jane Austen".to_string(),
+            description: None,
+            template_id: None,
+            custom_domain: None,
+            subdomain: "jane".to_string(),
+            is_published: true,
+            seo_settings: json!({}),
+            build_status: "published".to_string(),
+            theme_config: json!({ "primary": "#224466" }),
+            locale: None,
+            default_locale: None,
+            access_protection: Default::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn page(site: &Site, slug: &str, title: &str) -> Page {
+        Page {
+            id: Uuid::new_v4(),
+            site_id: site.id,
+            slug: slug.to_string(),
+            title: title.to_string(),
+            meta_description: None,
+            meta_keywords: None,
+            puck_data: json!({ "content": [{ "type": "Hero", "props": { "title": title } }] }),
+            composition_error: false,
+            custom_head: None,
+            custom_body: None,
+            is_published: true,
+            published_html: None,
+            published_at: None,
+            sort_order: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backup_captures_state_at_the_time() {
+        let store = MemoryObjectStore::new();
+        let site = site();
+        let mut pages = vec![page(&site, "home", "Welcome"), page(&site, "books", "Books")];
+        let tenant_id = TenantId::from_uuid(site.tenant_id);
+        let key = backup_key(&tenant_id, site.id, Uuid::new_v4());
+        assert!(key.starts_with(&format!("backups/{}/{}/", tenant_id, site.id)));
+
+        let snapshot = SiteExport::new(&site, pages.clone(), Vec::new(), Utc::now());
+        let size = write_snapshot(&store, &key, &snapshot).await.unwrap();
+        assert!(size > 0);
+
+        // Edits after the backup don't reach it
+        pages[0].title = "Edited".to_string();
+        pages.pop();
+
+        let restored = read_snapshot(&store, &key).await.unwrap();
+        assert_eq!(restored, snapshot);
+        let titles: Vec<&str> = restored.pages.iter().map(|page| page.title.as_str()).collect();
+        assert_eq!(titles, vec!["Welcome", "Books"]);
+        assert_eq!(restored.site.theme_config, json!({ "primary": "#224466" }));
+
+        assert!(matches!(
+            read_snapshot(&store, "backups/missing.json").await,
+            Err(SiteBackupError::Store(ObjectStoreError::NotFound(_)))
+        ));
+    }
+}
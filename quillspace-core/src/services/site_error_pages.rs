@@ -0,0 +1,150 @@
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Error pages a site can brand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPageKind {
+    NotFound,
+    ServerError,
+}
+
+impl ErrorPageKind {
+    pub fn status_code(&self) -> u16 {
+        match self {
+            ErrorPageKind::NotFound => 404,
+            ErrorPageKind::ServerError => 500,
+        }
+    }
+
+    /// Key of the rendered page in the publish cache; never a page path, which starts with `/`
+    pub fn cache_path(&self) -> &'static str {
+        match self {
+            ErrorPageKind::NotFound => "#not_found",
+            ErrorPageKind::ServerError => "#server_error",
+        }
+    }
+
+    fn default_html(&self) -> &'static str {
+        match self {
+            ErrorPageKind::NotFound => DEFAULT_NOT_FOUND_PAGE,
+            ErrorPageKind::ServerError => DEFAULT_SERVER_ERROR_PAGE,
+        }
+    }
+}
+
+/// Served when a site has no 404 page of its own
+const DEFAULT_NOT_FOUND_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Page not found</title>
+</head>
+<body>
+    <main>
+        <h1>Page not found</h1>
+        <p>The page you are looking for does not exist or has moved.</p>
+        <p><a href="/">Go to the home page</a></p>
+    </main>
+</body>
+</html>"#;
+
+/// Served when a site has no 500 page of its own, or it cannot be loaded either
+const DEFAULT_SERVER_ERROR_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Something went wrong</title>
+</head>
+<body>
+    <main>
+        <h1>Something went wrong</h1>
+        <p>Please try again in a moment.</p>
+    </main>
+</body>
+</html>"#;
+
+/// The `error_pages` object in a site's settings, naming pages of the site, e.g.
+/// `{"not_found": "<page id>", "server_error": "<page id>"}`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiteErrorPages {
+    pub not_found: Option<Uuid>,
+    pub server_error: Option<Uuid>,
+}
+
+impl SiteErrorPages {
+    /// Read the error pages of a site; absent means the defaults
+    pub fn from_site_settings(site_settings: &Value) -> anyhow::Result<Self> {
+        match site_settings.get("error_pages") {
+            Some(Value::Null) | None => Ok(Self::default()),
+            Some(error_pages) => serde_json::from_value(error_pages.clone())
+                .map_err(|e| anyhow::anyhow!("Invalid error page settings: {}", e)),
+        }
+    }
+
+    pub fn page_for(&self, kind: ErrorPageKind) -> Option<Uuid> {
+        match kind {
+            ErrorPageKind::NotFound => self.not_found,
+            ErrorPageKind::ServerError => self.server_error,
+        }
+    }
+}
+
+/// What the public handler sends for an error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorPage {
+    pub status_code: u16,
+    pub body: String,
+}
+
+/// The site's designated page for `kind` when it has a published one, otherwise the
+/// default. The status is the error's either way, so a branded 404 is still a 404.
+pub fn error_page(kind: ErrorPageKind, designated_html: Option<String>) -> ErrorPage {
+    ErrorPage {
+        status_code: kind.status_code(),
+        body: designated_html.unwrap_or_else(|| kind.default_html().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_unknown_path_renders_designated_not_found_page() {
+        let page_id = Uuid::new_v4();
+        let settings = json!({ "error_pages": { "not_found": page_id } });
+        let error_pages = SiteErrorPages::from_site_settings(&settings).unwrap();
+        assert_eq!(error_pages.page_for(ErrorPageKind::NotFound), Some(page_id));
+        assert_eq!(error_pages.page_for(ErrorPageKind::ServerError), None);
+
+        let branded = "<html><body><h1>Lost in the stacks</h1></body></html>".to_string();
+        let page = error_page(ErrorPageKind::NotFound, Some(branded.clone()));
+        assert_eq!(page, ErrorPage { status_code: 404, body: branded });
+    }
+
+    #[test]
+    fn test_site_without_error_pages_gets_default() {
+        let error_pages = SiteErrorPages::from_site_settings(&json!({ "analytics": { "provider": "none" } })).unwrap();
+        assert_eq!(error_pages, SiteErrorPages::default());
+
+        let page = error_page(ErrorPageKind::NotFound, None);
+        assert_eq!(page.status_code, 404);
+        assert!(page.body.contains("Page not found"));
+
+        let page = error_page(ErrorPageKind::ServerError, None);
+        assert_eq!(page.status_code, 500);
+        assert!(page.body.contains("Something went wrong"));
+    }
+
+    #[test]
+    fn test_invalid_error_page_settings_rejected() {
+        for error_pages in [json!({ "not_found": "about-us" }), json!({ "forbidden": Uuid::new_v4() }), json!("404")] {
+            assert!(SiteErrorPages::from_site_settings(&json!({ "error_pages": error_pages })).is_err());
+        }
+    }
+}
@@ -0,0 +1,793 @@
+use crate::database::postgres::tenant_transaction;
+use crate::services::asset::{row_to_asset, Asset, AssetService, AssetServiceError};
+use crate::services::clock::{IdGenerator, RandomIds};
+use crate::services::page::{Page, PageService};
+use crate::services::site::{Site, SiteService};
+use crate::services::subdomain::SubdomainGenerator;
+use crate::types::TenantId;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{GenericClient, Pool, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Version of the export format written by `export_site`. Bump it when a field
+/// changes meaning or is removed; adding an optional field does not need a bump.
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SiteTransferError {
+    #[error("Site not found")]
+    SiteNotFound,
+
+    #[error("Export schema version {0} is not supported (newest is {EXPORT_SCHEMA_VERSION})")]
+    UnsupportedVersion(u32),
+
+    #[error("Invalid export: {0}")]
+    Invalid(String),
+
+    #[error("Subdomain '{0}' is already taken")]
+    SubdomainTaken(String),
+
+    #[error(transparent)]
+    Asset(#[from] AssetServiceError),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// A site with its pages and the assets they use, independent of any tenant.
+/// Ids are the source environment's; importing assigns new ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SiteExport {
+    pub schema_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub site: ExportedSite,
+    pub pages: Vec<ExportedPage>,
+    pub assets: Vec<ExportedAsset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedSite {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub template_id: Option<Uuid>,
+    pub custom_domain: Option<String>,
+    pub subdomain: String,
+    pub seo_settings: Value,
+    pub theme_config: Value,
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub default_locale: Option<String>,
+}
+
+impl ExportedSite {
+    /// Exports made before sites had locale columns keep their locales in `seo_settings`
+    fn lift_legacy_locales(&mut self) {
+        let Some(settings) = self.seo_settings.as_object_mut() else { return };
+        for (key, field) in [("locale", &mut self.locale), ("default_locale", &mut self.default_locale)] {
+            if let Some(legacy) = settings.remove(key) {
+                if field.is_none() {
+                    *field = legacy.as_str().map(str::trim).filter(|tag| !tag.is_empty()).map(str::to_string);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedPage {
+    pub id: Uuid,
+    pub slug: String,
+    pub title: String,
+    pub meta_description: Option<String>,
+    pub meta_keywords: Option<String>,
+    pub puck_data: Value,
+    pub custom_head: Option<String>,
+    pub custom_body: Option<String>,
+    pub is_published: bool,
+    pub sort_order: i32,
+}
+
+/// An asset record. The file stays where it is; `storage_path` and `cdn_url` point at it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedAsset {
+    pub id: Uuid,
+    /// Whether the asset belongs to the site rather than the tenant's shared library
+    pub site_scoped: bool,
+    pub filename: String,
+    pub original_filename: String,
+    pub mime_type: String,
+    pub file_size: i64,
+    pub storage_path: String,
+    pub cdn_url: Option<String>,
+    pub alt_text: Option<String>,
+    pub content_hash: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+impl SiteExport {
+    pub fn new(site: &Site, pages: Vec<Page>, assets: Vec<Asset>, exported_at: DateTime<Utc>) -> Self {
+        Self {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            exported_at,
+            site: ExportedSite {
+                id: site.id,
+                name: site.name.clone(),
+                description: site.description.clone(),
+                template_id: site.template_id,
+                custom_domain: site.custom_domain.clone(),
+                subdomain: site.subdomain.clone(),
+                seo_settings: site.seo_settings.clone(),
+                theme_config: site.theme_config.clone(),
+                locale: site.locale.clone(),
+                default_locale: site.default_locale.clone(),
+            },
+            pages: pages
+                .into_iter()
+                .map(|page| ExportedPage {
+                    id: page.id,
+                    slug: page.slug,
+                    title: page.title,
+                    meta_description: page.meta_description,
+                    meta_keywords: page.meta_keywords,
+                    puck_data: page.puck_data,
+                    custom_head: page.custom_head,
+                    custom_body: page.custom_body,
+                    is_published: page.is_published,
+                    sort_order: page.sort_order,
+                })
+                .collect(),
+            assets: assets
+                .into_iter()
+                .map(|asset| ExportedAsset {
+                    id: asset.id,
+                    site_scoped: asset.site_id == Some(site.id),
+                    filename: asset.filename,
+                    original_filename: asset.original_filename,
+                    mime_type: asset.mime_type,
+                    file_size: asset.file_size,
+                    storage_path: asset.storage_path,
+                    cdn_url: asset.cdn_url,
+                    alt_text: asset.alt_text,
+                    content_hash: asset.content_hash,
+                    width: asset.width,
+                    height: asset.height,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), SiteTransferError> {
+        if self.schema_version == 0 || self.schema_version > EXPORT_SCHEMA_VERSION {
+            return Err(SiteTransferError::UnsupportedVersion(self.schema_version));
+        }
+        if self.site.name.trim().is_empty() {
+            return Err(SiteTransferError::Invalid("site has no name".to_string()));
+        }
+
+        let mut ids = HashSet::from([self.site.id]);
+        let mut slugs = HashSet::new();
+        for page in &self.pages {
+            if !slugs.insert(page.slug.as_str()) {
+                return Err(SiteTransferError::Invalid(format!("page slug '{}' is repeated", page.slug)));
+            }
+            if !ids.insert(page.id) {
+                return Err(SiteTransferError::Invalid(format!("id {} is repeated", page.id)));
+            }
+        }
+        for asset in &self.assets {
+            if !ids.insert(asset.id) {
+                return Err(SiteTransferError::Invalid(format!("id {} is repeated", asset.id)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What an import writes: the export with new ids, and references to them rewritten
+#[derive(Debug, Clone)]
+pub struct ImportPlan {
+    pub site: ExportedSite,
+    pub pages: Vec<ExportedPage>,
+    /// Assets to create; ones the tenant already has are reused instead
+    pub assets: Vec<ExportedAsset>,
+    pub reused_assets: usize,
+    /// Exported id to imported id
+    pub id_map: HashMap<Uuid, Uuid>,
+}
+
+/// Assign new ids to everything in `export`. Assets whose content hash matches one of
+/// the target tenant's (`existing_assets`, hash to id) resolve to that asset. Every
+/// mention of an exported id in page data, e.g. an asset id in a composition or a
+/// page id in a link, is rewritten to the new id.
+pub fn plan_import(
+    export: &SiteExport,
+    existing_assets: &HashMap<String, Uuid>,
+    ids: &dyn IdGenerator,
+) -> Result<ImportPlan, SiteTransferError> {
+    export.validate()?;
+
+    let mut id_map = HashMap::new();
+    id_map.insert(export.site.id, ids.new_id());
+    for page in &export.pages {
+        id_map.insert(page.id, ids.new_id());
+    }
+
+    let mut assets = Vec::new();
+    let mut reused_assets = 0;
+    for asset in &export.assets {
+        let existing = asset.content_hash.as_ref().and_then(|hash| existing_assets.get(hash));
+        match existing {
+            Some(existing) => {
+                id_map.insert(asset.id, *existing);
+                reused_assets += 1;
+            }
+            None => {
+                let id = ids.new_id();
+                id_map.insert(asset.id, id);
+                assets.push(ExportedAsset { id, ..asset.clone() });
+            }
+        }
+    }
+
+    let pages = export
+        .pages
+        .iter()
+        .map(|page| ExportedPage {
+            id: id_map[&page.id],
+            puck_data: remap_ids(&page.puck_data, &id_map),
+            ..page.clone()
+        })
+        .collect();
+
+    let mut site = ExportedSite {
+        id: id_map[&export.site.id],
+        seo_settings: remap_ids(&export.site.seo_settings, &id_map),
+        ..export.site.clone()
+    };
+    site.lift_legacy_locales();
+
+    Ok(ImportPlan {
+        site,
+        pages,
+        assets,
+        reused_assets,
+        id_map,
+    })
+}
+
+/// [`plan_import`] for restoring into the existing site `site_id`: the exported site's id,
+/// and references to it in page data, resolve to `site_id` instead of a new id
+pub fn plan_restore(
+    export: &SiteExport,
+    existing_assets: &HashMap<String, Uuid>,
+    site_id: Uuid,
+    ids: &dyn IdGenerator,
+) -> Result<ImportPlan, SiteTransferError> {
+    let mut plan = plan_import(export, existing_assets, ids)?;
+    let to_site = HashMap::from([(plan.site.id, site_id)]);
+    for page in &mut plan.pages {
+        page.puck_data = remap_ids(&page.puck_data, &to_site);
+    }
+    plan.site.seo_settings = remap_ids(&plan.site.seo_settings, &to_site);
+    plan.site.id = site_id;
+    plan.id_map.insert(export.site.id, site_id);
+    Ok(plan)
+}
+
+/// Replace every exported id inside strings of `value` with its imported id
+fn remap_ids(value: &Value, id_map: &HashMap<Uuid, Uuid>) -> Value {
+    match value {
+        Value::String(text) => {
+            let mut text = text.clone();
+            for (from, to) in id_map {
+                let from = from.to_string();
+                if text.contains(&from) {
+                    text = text.replace(&from, &to.to_string());
+                }
+            }
+            Value::String(text)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| remap_ids(item, id_map)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, field)| (key.clone(), remap_ids(field, id_map)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Summary of an import
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedSite {
+    pub site_id: Uuid,
+    pub subdomain: String,
+    pub pages: usize,
+    pub assets_created: usize,
+    pub assets_reused: usize,
+}
+
+/// Export and import of whole sites, for backups and moving sites between environments
+pub struct SiteTransferService {
+    db: Pool,
+    assets: AssetService,
+    ids: Arc<dyn IdGenerator>,
+}
+
+impl SiteTransferService {
+    pub fn new(db: Pool, assets: AssetService) -> Self {
+        Self {
+            db,
+            assets,
+            ids: Arc::new(RandomIds),
+        }
+    }
+
+    /// The site, all its pages, and every asset that is the site's own or that a page mentions
+    pub async fn export_site(&self, tenant_id: &TenantId, site_id: Uuid) -> Result<SiteExport, SiteTransferError> {
+        let client = self.db.get().await
+            .context("Failed to get database connection")?;
+        client
+            .execute(
+                "SELECT set_config('quillspace.tenant_id', $1, true)",
+                &[&tenant_id.to_string()],
+            )
+            .await
+            .context("Failed to set RLS tenant context")?;
+
+        let site = SiteService::new(self.db.clone())
+            .get_site(tenant_id, site_id)
+            .await?
+            .ok_or(SiteTransferError::SiteNotFound)?;
+        let pages = PageService::new(self.db.clone())
+            .get_site_pages(tenant_id, site_id)
+            .await?;
+
+        let asset_rows = client
+            .query(
+                "SELECT a.* FROM assets a
+                 WHERE a.tenant_id = $1
+                   AND (a.site_id = $2 OR EXISTS (
+                       SELECT 1 FROM pages p
+                       WHERE p.site_id = $2 AND p.puck_data::text LIKE '%' || a.id::text || '%'
+                   ))
+                 ORDER BY a.created_at",
+                &[tenant_id.as_uuid(), &site_id],
+            )
+            .await
+            .context("Failed to load site assets")?;
+        let assets = asset_rows
+            .iter()
+            .map(row_to_asset)
+            .collect::<anyhow::Result<Vec<Asset>>>()?;
+
+        Ok(SiteExport::new(&site, pages, assets, Utc::now()))
+    }
+
+    /// Create a new site in `tenant_id` from an export, all or nothing. The site starts
+    /// unpublished; `subdomain` overrides the exported one, which is replaced by a
+    /// generated one if taken. A custom domain already in use is dropped.
+    pub async fn import_site(
+        &self,
+        tenant_id: &TenantId,
+        export: &SiteExport,
+        subdomain: Option<String>,
+    ) -> Result<ImportedSite, SiteTransferError> {
+        export.validate()?;
+        if let Some(subdomain) = &subdomain {
+            SiteService::validate_subdomain(subdomain).map_err(|e| SiteTransferError::Invalid(e.to_string()))?;
+        }
+
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let existing_assets = existing_assets(&transaction, tenant_id, export).await?;
+        let plan = plan_import(export, &existing_assets, self.ids.as_ref())?;
+
+        let custom_domain = match plan.site.custom_domain.as_deref().filter(|domain| !domain.is_empty()) {
+            Some(domain) => {
+                let in_use = transaction
+                    .query_opt("SELECT id FROM sites WHERE custom_domain = $1", &[&domain])
+                    .await
+                    .context("Failed to check custom domain")?
+                    .is_some();
+                (!in_use).then(|| domain.to_string())
+            }
+            None => None,
+        };
+
+        // The insert claims the subdomain, doing nothing if another site holds it
+        let insert_site = |subdomain: String| {
+            let (transaction, plan, custom_domain) = (&transaction, &plan, &custom_domain);
+            async move {
+                let row = transaction
+                    .query_opt(
+                        "INSERT INTO sites (id, tenant_id, name, description, template_id, custom_domain, subdomain, seo_settings, theme_config, locale, default_locale)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                         ON CONFLICT (subdomain) DO NOTHING
+                         RETURNING subdomain",
+                        &[
+                            &plan.site.id,
+                            tenant_id.as_uuid(),
+                            &plan.site.name,
+                            &plan.site.description,
+                            &plan.site.template_id,
+                            custom_domain,
+                            &subdomain,
+                            &plan.site.seo_settings,
+                            &plan.site.theme_config,
+                            &plan.site.locale,
+                            &plan.site.default_locale,
+                        ],
+                    )
+                    .await
+                    .context("Failed to create imported site")?;
+                anyhow::Ok(row.map(|row| row.get::<_, String>(0)))
+            }
+        };
+        let subdomain = match subdomain {
+            Some(subdomain) => insert_site(subdomain.clone())
+                .await?
+                .ok_or(SiteTransferError::SubdomainTaken(subdomain))?,
+            None => match insert_site(plan.site.subdomain.clone()).await? {
+                Some(subdomain) => subdomain,
+                None => SubdomainGenerator::default()
+                    .reserve(&plan.site.name, insert_site)
+                    .await
+                    .map_err(anyhow::Error::from)?,
+            },
+        };
+
+        self.insert_assets(&transaction, tenant_id, &plan).await?;
+        insert_pages(&transaction, plan.site.id, &plan.pages).await?;
+
+        transaction.commit().await
+            .context("Failed to commit site import")?;
+
+        Ok(ImportedSite {
+            site_id: plan.site.id,
+            subdomain,
+            pages: plan.pages.len(),
+            assets_created: plan.assets.len(),
+            assets_reused: plan.reused_assets,
+        })
+    }
+
+    /// Replace the pages of an existing site with those of an export, all or nothing.
+    /// The site's name, description, template, SEO settings and theme are restored too;
+    /// its subdomain, custom domain, publish state and access protection are kept.
+    pub async fn restore_into_site(
+        &self,
+        tenant_id: &TenantId,
+        site_id: Uuid,
+        export: &SiteExport,
+    ) -> Result<ImportedSite, SiteTransferError> {
+        export.validate()?;
+
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let subdomain: String = transaction
+            .query_opt(
+                "SELECT subdomain FROM sites WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+                &[&site_id, tenant_id.as_uuid()],
+            )
+            .await
+            .context("Failed to lock site")?
+            .ok_or(SiteTransferError::SiteNotFound)?
+            .get(0);
+
+        let existing_assets = existing_assets(&transaction, tenant_id, export).await?;
+        let plan = plan_restore(export, &existing_assets, site_id, self.ids.as_ref())?;
+
+        transaction
+            .execute(
+                "UPDATE sites SET name = $3, description = $4, template_id = $5, seo_settings = $6, theme_config = $7,
+                     locale = $8, default_locale = $9, updated_at = NOW()
+                 WHERE id = $1 AND tenant_id = $2",
+                &[
+                    &site_id,
+                    tenant_id.as_uuid(),
+                    &plan.site.name,
+                    &plan.site.description,
+                    &plan.site.template_id,
+                    &plan.site.seo_settings,
+                    &plan.site.theme_config,
+                    &plan.site.locale,
+                    &plan.site.default_locale,
+                ],
+            )
+            .await
+            .context("Failed to restore site settings")?;
+        transaction
+            .execute("DELETE FROM pages WHERE site_id = $1", &[&site_id])
+            .await
+            .context("Failed to remove current pages")?;
+
+        self.insert_assets(&transaction, tenant_id, &plan).await?;
+        insert_pages(&transaction, site_id, &plan.pages).await?;
+
+        transaction.commit().await
+            .context("Failed to commit site restore")?;
+
+        Ok(ImportedSite {
+            site_id,
+            subdomain,
+            pages: plan.pages.len(),
+            assets_created: plan.assets.len(),
+            assets_reused: plan.reused_assets,
+        })
+    }
+
+    /// Create the plan's new assets, counting them against the tenant's storage quota
+    async fn insert_assets(
+        &self,
+        transaction: &Transaction<'_>,
+        tenant_id: &TenantId,
+        plan: &ImportPlan,
+    ) -> Result<(), SiteTransferError> {
+        let new_bytes: i64 = plan.assets.iter().map(|asset| asset.file_size).sum();
+        self.assets.reserve_storage(transaction, tenant_id, new_bytes).await?;
+        for asset in &plan.assets {
+            let site_id = asset.site_scoped.then_some(plan.site.id);
+            transaction
+                .execute(
+                    "INSERT INTO assets (id, tenant_id, site_id, filename, original_filename, mime_type, file_size, storage_path, cdn_url, alt_text, content_hash, width, height)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+                    &[
+                        &asset.id,
+                        tenant_id.as_uuid(),
+                        &site_id,
+                        &asset.filename,
+                        &asset.original_filename,
+                        &asset.mime_type,
+                        &asset.file_size,
+                        &asset.storage_path,
+                        &asset.cdn_url,
+                        &asset.alt_text,
+                        &asset.content_hash,
+                        &asset.width,
+                        &asset.height,
+                    ],
+                )
+                .await
+                .context("Failed to create imported asset")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The tenant's assets with the same content as ones in the export, by content hash
+async fn existing_assets(
+    client: &impl GenericClient,
+    tenant_id: &TenantId,
+    export: &SiteExport,
+) -> anyhow::Result<HashMap<String, Uuid>> {
+    let hashes: Vec<String> = export.assets.iter().filter_map(|asset| asset.content_hash.clone()).collect();
+    let rows = client
+        .query(
+            "SELECT DISTINCT ON (content_hash) content_hash, id FROM assets
+             WHERE tenant_id = $1 AND content_hash = ANY($2)
+             ORDER BY content_hash, created_at",
+            &[tenant_id.as_uuid(), &hashes],
+        )
+        .await
+        .context("Failed to look up existing assets")?;
+    Ok(rows.iter().map(|row| (row.get("content_hash"), row.get("id"))).collect())
+}
+
+async fn insert_pages(client: &impl GenericClient, site_id: Uuid, pages: &[ExportedPage]) -> anyhow::Result<()> {
+    for page in pages {
+        client
+            .execute(
+                "INSERT INTO pages (id, site_id, slug, title, meta_description, meta_keywords, puck_data, custom_head, custom_body, is_published, sort_order)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+                &[
+                    &page.id,
+                    &site_id,
+                    &page.slug,
+                    &page.title,
+                    &page.meta_description,
+                    &page.meta_keywords,
+                    &page.puck_data,
+                    &page.custom_head,
+                    &page.custom_body,
+                    &page.is_published,
+                    &page.sort_order,
+                ],
+            )
+            .await
+            .context("Failed to create imported page")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::clock::SequentialIds;
+    use serde_json::json;
+
+    const ASSET_ID: &str = "aaaaaaaa-0000-0000-0000-000000000001";
+    const HOME_ID: &str = "bbbbbbbb-0000-0000-0000-000000000001";
+
+    fn site() -> Site {
+        Site {
+            id: Uuid::parse_str("cccccccc-0000-0000-0000-000000000001").unwrap(),
+            tenant_id: Uuid::new_v4(),
+            name: "Jane Austen".to_string(),
+            description: Some("Novels".to_string()),
+            template_id: None,
+            custom_domain: Some("janeausten.com".to_string()),
+            subdomain: "jane".to_string(),
+            is_published: true,
+            seo_settings: json!({ "error_pages": { "not_found": HOME_ID } }),
+            build_status: "published".to_string(),
+            theme_config: json!({ "primary": "#223344" }),
+            locale: None,
+            default_locale: None,
+            access_protection: Default::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn page(id: &str, slug: &str, sort_order: i32, puck_data: Value) -> Page {
+        Page {
+            id: Uuid::parse_str(id).unwrap(),
+            site_id: site().id,
+            slug: slug.to_string(),
+            title: slug.to_uppercase(),
+            meta_description: None,
+            meta_keywords: Some("regency".to_string()),
+            puck_data,
+            composition_error: false,
+            custom_head: None,
+            custom_body: None,
+            is_published: true,
+            published_html: Some("<p>cached</p>".to_string()),
+            published_at: Some(Utc::now()),
+            sort_order,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn asset(content_hash: &str) -> Asset {
+        Asset {
+            id: Uuid::parse_str(ASSET_ID).unwrap(),
+            tenant_id: Uuid::new_v4(),
+            site_id: Some(site().id),
+            filename: "cover.jpg".to_string(),
+            original_filename: "Pride and Prejudice.jpg".to_string(),
+            mime_type: "image/jpeg".to_string(),
+            file_size: 2048,
+            storage_path: "tenant/cover.jpg".to_string(),
+            cdn_url: Some("https://cdn.quillspace.com/tenant/cover.jpg".to_string()),
+            alt_text: Some("Cover".to_string()),
+            is_optimized: true,
+            content_hash: Some(content_hash.to_string()),
+            width: Some(600),
+            height: Some(900),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn export() -> SiteExport {
+        let pages = vec![
+            page(HOME_ID, "home", 0, json!({ "content": [{ "type": "Image", "props": { "assetId": ASSET_ID } }] })),
+            page(
+                "bbbbbbbb-0000-0000-0000-000000000002",
+                "books",
+                1,
+                json!({ "content": [{ "type": "Link", "props": { "href": format!("/pages/{}", HOME_ID) } }] }),
+            ),
+            page("bbbbbbbb-0000-0000-0000-000000000003", "about", 2, json!({ "content": [] })),
+        ];
+        SiteExport::new(&site(), pages, vec![asset(&"ab".repeat(32))], Utc::now())
+    }
+
+    /// Undo the id rewrite, so imported data can be compared with the original
+    fn restore_ids(value: &Value, plan: &ImportPlan) -> Value {
+        let reverse = plan.id_map.iter().map(|(from, to)| (*to, *from)).collect();
+        remap_ids(value, &reverse)
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let export = export();
+        let json = serde_json::to_string(&export).unwrap();
+        let parsed: SiteExport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, export);
+        assert_eq!(parsed.schema_version, EXPORT_SCHEMA_VERSION);
+
+        let plan = plan_import(&parsed, &HashMap::new(), &SequentialIds::default()).unwrap();
+
+        assert_eq!(plan.pages.len(), export.pages.len());
+        let slugs = |pages: &[ExportedPage]| pages.iter().map(|page| page.slug.clone()).collect::<Vec<_>>();
+        assert_eq!(slugs(&plan.pages), slugs(&export.pages));
+        for (imported, original) in plan.pages.iter().zip(&export.pages) {
+            assert_ne!(imported.id, original.id);
+            assert_eq!(restore_ids(&imported.puck_data, &plan), original.puck_data);
+        }
+
+        // References follow the new ids
+        let new_asset = plan.assets[0].id;
+        assert_eq!(plan.pages[0].puck_data["content"][0]["props"]["assetId"], new_asset.to_string());
+        let new_home = plan.pages[0].id;
+        assert_eq!(plan.pages[1].puck_data["content"][0]["props"]["href"], format!("/pages/{}", new_home));
+        assert_eq!(plan.site.seo_settings["error_pages"]["not_found"], new_home.to_string());
+        assert_eq!(plan.site.id, SequentialIds::nth(1));
+    }
+
+    #[test]
+    fn test_import_reuses_assets_the_tenant_already_has() {
+        let export = export();
+        let existing = Uuid::new_v4();
+        let hashes = HashMap::from([("ab".repeat(32), existing)]);
+
+        let plan = plan_import(&export, &hashes, &SequentialIds::default()).unwrap();
+
+        assert!(plan.assets.is_empty());
+        assert_eq!(plan.reused_assets, 1);
+        assert_eq!(plan.pages[0].puck_data["content"][0]["props"]["assetId"], existing.to_string());
+    }
+
+    #[test]
+    fn test_restore_recreates_pages_in_existing_site() {
+        let mut snapshot = export();
+        snapshot.pages[2].puck_data = json!({ "content": [{ "type": "Form", "props": { "siteId": site().id } }] });
+        let target = Uuid::new_v4();
+
+        let plan = plan_restore(&snapshot, &HashMap::new(), target, &SequentialIds::default()).unwrap();
+
+        assert_eq!(plan.site.id, target);
+        assert_eq!(plan.site.theme_config, snapshot.site.theme_config);
+        assert_eq!(plan.pages.len(), snapshot.pages.len());
+        for (restored, original) in plan.pages.iter().zip(&snapshot.pages) {
+            assert_eq!((&restored.slug, &restored.title), (&original.slug, &original.title));
+            assert_eq!(restore_ids(&restored.puck_data, &plan), original.puck_data);
+        }
+        let new_home = plan.pages[0].id;
+        assert_eq!(plan.pages[1].puck_data["content"][0]["props"]["href"], format!("/pages/{}", new_home));
+        assert_eq!(plan.pages[2].puck_data["content"][0]["props"]["siteId"], target.to_string());
+    }
+
+    #[test]
+    fn test_import_lifts_locales_out_of_older_exports() {
+        let mut older = export();
+        older.site.seo_settings["locale"] = json!("de-DE");
+        older.site.seo_settings["default_locale"] = json!("");
+        let older: SiteExport = serde_json::from_value(serde_json::to_value(&older).unwrap()).unwrap();
+
+        let plan = plan_import(&older, &HashMap::new(), &SequentialIds::default()).unwrap();
+
+        assert_eq!((plan.site.locale.as_deref(), plan.site.default_locale.as_deref()), (Some("de-DE"), None));
+        assert!(plan.site.seo_settings.get("locale").is_none() && plan.site.seo_settings.get("default_locale").is_none());
+        assert!(plan.site.seo_settings.get("error_pages").is_some());
+    }
+
+    #[test]
+    fn test_invalid_exports_rejected() {
+        let newer = SiteExport { schema_version: EXPORT_SCHEMA_VERSION + 1, ..export() };
+        assert!(matches!(newer.validate(), Err(SiteTransferError::UnsupportedVersion(_))));
+
+        let mut repeated = export();
+        repeated.pages[1].slug = "home".to_string();
+        assert!(matches!(repeated.validate(), Err(SiteTransferError::Invalid(_))));
+    }
+}
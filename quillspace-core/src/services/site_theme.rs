@@ -0,0 +1,113 @@
+//! A tenant-wide default theme that every site inherits.
+//!
+//! The tenant's theme is kept under `settings.theme`, and a site's `theme_config` holds
+//! only its overrides:
+//!
+//! ```json
+//! { "theme": { "colors": { "primary": "#224466", "accent": "#cc8800" }, "font": "Lora" } }
+//! ```
+//!
+//! The effective theme is resolved when a site is rendered, so a change to the tenant
+//! theme shows on every site that does not override the field. Objects are merged field
+//! by field, the site's value winning; any other value, arrays included, replaces the
+//! inherited one whole. A site value of `null` inherits the tenant's.
+
+use anyhow::{Context, Result};
+use deadpool_postgres::GenericClient;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+/// Key of the tenant theme in tenant settings
+pub const THEME_SETTING: &str = "theme";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ThemeError {
+    #[error("Invalid tenant theme: expected an object")]
+    NotAnObject,
+}
+
+/// The tenant theme in `settings`, an empty object when it has none
+pub fn tenant_theme(settings: &Value) -> Value {
+    match settings.get(THEME_SETTING) {
+        Some(theme @ Value::Object(_)) => theme.clone(),
+        _ => Value::Object(Map::new()),
+    }
+}
+
+/// The theme a site renders with: `overrides` laid over `inherited`
+pub fn resolve_theme(inherited: &Value, overrides: &Value) -> Value {
+    match (inherited, overrides) {
+        (_, Value::Null) => inherited.clone(),
+        (Value::Object(inherited), Value::Object(overrides)) => {
+            let mut theme = inherited.clone();
+            for (key, value) in overrides {
+                let resolved = match inherited.get(key) {
+                    Some(base) => resolve_theme(base, value),
+                    None if value.is_null() => continue,
+                    None => value.clone(),
+                };
+                theme.insert(key.clone(), resolved);
+            }
+            Value::Object(theme)
+        }
+        _ => overrides.clone(),
+    }
+}
+
+/// Reject a settings update whose `theme` is not an object
+pub fn validate_settings(settings: &Value) -> Result<(), ThemeError> {
+    match settings.get(THEME_SETTING) {
+        None | Some(Value::Null) | Some(Value::Object(_)) => Ok(()),
+        Some(_) => Err(ThemeError::NotAnObject),
+    }
+}
+
+/// The tenant's theme, read from its settings
+pub async fn load_tenant_theme(client: &impl GenericClient, tenant_id: &Uuid) -> Result<Value> {
+    let settings: Option<Value> = client
+        .query_opt("SELECT settings FROM tenants WHERE id = $1", &[tenant_id])
+        .await
+        .context("Failed to load tenant settings")?
+        .and_then(|row| row.get(0));
+    Ok(settings.map_or_else(|| Value::Object(Map::new()), |settings| tenant_theme(&settings)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_site_without_overrides_follows_tenant_theme() {
+        let overrides = json!({});
+        let mut settings = json!({ "theme": { "colors": { "primary": "#224466" }, "font": "Lora" } });
+        assert_eq!(resolve_theme(&tenant_theme(&settings), &overrides), settings["theme"]);
+
+        settings["theme"]["colors"]["primary"] = json!("#aa0000");
+        assert_eq!(resolve_theme(&tenant_theme(&settings), &overrides)["colors"]["primary"], "#aa0000");
+        assert_eq!(resolve_theme(&tenant_theme(&json!({})), &json!({ "font": "Lora" })), json!({ "font": "Lora" }));
+    }
+
+    #[test]
+    fn test_override_shadows_inherited_value() {
+        let inherited = json!({ "colors": { "primary": "#224466", "accent": "#cc8800" }, "font": "Lora", "links": ["a", "b"] });
+        let overrides = json!({ "colors": { "accent": "#00aa00" }, "links": ["c"], "font": null, "logo": "/logo.png" });
+
+        assert_eq!(
+            resolve_theme(&inherited, &overrides),
+            json!({
+                "colors": { "primary": "#224466", "accent": "#00aa00" },
+                "font": "Lora",
+                "links": ["c"],
+                "logo": "/logo.png",
+            })
+        );
+    }
+
+    #[test]
+    fn test_theme_setting_must_be_an_object() {
+        assert!(validate_settings(&json!({})).is_ok());
+        assert!(validate_settings(&json!({ "theme": { "font": "Lora" } })).is_ok());
+        assert_eq!(validate_settings(&json!({ "theme": "dark" })), Err(ThemeError::NotAnObject));
+    }
+}
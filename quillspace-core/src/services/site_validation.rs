@@ -0,0 +1,154 @@
+use crate::services::page::Page;
+use crate::services::site::Site;
+use crate::services::site_theme::resolve_theme;
+use crate::services::template_engine::{PageContext, SiteContext, TemplateEngine};
+use anyhow::Result;
+use serde::Serialize;
+use std::future::Future;
+use uuid::Uuid;
+
+/// Result of rendering one page of a site
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PageValidation {
+    pub page_id: Uuid,
+    pub slug: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Whether every page of a site renders, and why the ones that don't fail
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SiteValidationReport {
+    pub valid: bool,
+    pub checked: usize,
+    /// Pages that failed to render, in page order
+    pub failures: Vec<PageValidation>,
+}
+
+impl SiteValidationReport {
+    pub fn from_results(results: Vec<PageValidation>) -> Self {
+        let checked = results.len();
+        let failures: Vec<PageValidation> = results.into_iter().filter(|page| page.error.is_some()).collect();
+        Self {
+            valid: failures.is_empty(),
+            checked,
+            failures,
+        }
+    }
+}
+
+/// Render context for a site's pages, its theme inherited from `tenant_theme`
+pub fn site_context(site: &Site, tenant_theme: &serde_json::Value) -> SiteContext {
+    SiteContext {
+        id: site.id,
+        name: site.name.clone(),
+        description: site.description.clone(),
+        subdomain: site.subdomain.clone(),
+        custom_domain: site.custom_domain.clone(),
+        seo_settings: site.seo_settings.clone(),
+        locale: site.locale.clone(),
+        default_locale: site.default_locale.clone(),
+        theme: resolve_theme(tenant_theme, &site.theme_config),
+    }
+}
+
+/// Render a page the way publishing it would, without storing the result
+pub async fn render_for_publish(
+    template_engine: &TemplateEngine,
+    site_context: &SiteContext,
+    tenant_id: Uuid,
+    page: Page,
+) -> Result<String> {
+    let page_context = PageContext {
+        id: page.id,
+        slug: page.slug,
+        title: page.title,
+        meta_description: page.meta_description,
+        meta_keywords: page.meta_keywords,
+        is_published: true,
+        published_at: Some(chrono::Utc::now()),
+    };
+    template_engine
+        .render_puck_page(&page.puck_data, site_context, &page_context, tenant_id, None)
+        .await
+}
+
+/// Render every page with `render` and report the ones that fail
+pub async fn validate_pages<F, Fut>(pages: Vec<Page>, render: F) -> SiteValidationReport
+where
+    F: Fn(Page) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let mut results = Vec::with_capacity(pages.len());
+    for page in pages {
+        let (page_id, slug, title) = (page.id, page.slug.clone(), page.title.clone());
+        let error = render(page).await.err().map(|e| format!("{:#}", e));
+        results.push(PageValidation { page_id, slug, title, error });
+    }
+    SiteValidationReport::from_results(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use serde_json::json;
+
+    fn page(slug: &str) -> Page {
+        Page {
+            id: Uuid::new_v4(),
+            site_id: Uuid::nil(),
+            slug: slug.to_string(),
+            title: slug.to_uppercase(),
+            meta_description: None,
+            meta_keywords: None,
+            puck_data: json!({ "content": [] }),
+            composition_error: false,
+            custom_head: None,
+            custom_body: None,
+            is_published: false,
+            published_html: None,
+            published_at: None,
+            sort_order: 0,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    async fn render(page: Page) -> Result<String> {
+        if page.slug == "books" {
+            Err(anyhow!("Template 'book-grid' not found"))
+        } else {
+            Ok(format!("<h1>{}</h1>", page.title))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_site_with_broken_page_fails_validation() {
+        let pages = vec![page("home"), page("books"), page("about")];
+        let broken = pages[1].id;
+
+        let report = validate_pages(pages, render).await;
+
+        assert!(!report.valid);
+        assert_eq!(report.checked, 3);
+        assert_eq!(
+            report.failures,
+            vec![PageValidation {
+                page_id: broken,
+                slug: "books".to_string(),
+                title: "BOOKS".to_string(),
+                error: Some("Template 'book-grid' not found".to_string()),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_site_whose_pages_all_render_is_valid() {
+        let report = validate_pages(vec![page("home"), page("about")], render).await;
+        assert!(report.valid);
+        assert_eq!(report.checked, 2);
+        assert!(report.failures.is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+use crate::services::{cdn::page_urls, page::Page, site::Site};
+
+/// `sitemap.xml` listing a site's published pages under its canonical domain (the
+/// custom domain when set). `None` for sites search engines shouldn't see: unpublished
+/// ones, and published ones behind access protection.
+pub fn site_sitemap(site: &Site, pages: &[Page]) -> Option<String> {
+    if !site.is_published || site.access_protection.is_protected() {
+        return None;
+    }
+
+    let mut sitemap = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">",
+    );
+    for page in pages.iter().filter(|page| page.is_published && page.site_id == site.id) {
+        let Some(url) = page_urls(&site.subdomain, site.custom_domain.as_deref(), &page.slug).pop() else {
+            continue;
+        };
+        let modified = page.published_at.unwrap_or(page.updated_at).date_naive();
+        sitemap.push_str(&format!(
+            "\n  <url><loc>{}</loc><lastmod>{}</lastmod></url>",
+            escape_xml(&url),
+            modified
+        ));
+    }
+    sitemap.push_str("\n</urlset>\n");
+    Some(sitemap)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::site_access::SiteAccessRequest;
+    use chrono::Utc;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn site() -> Site {
+        Site {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            name: "Jane Austen".to_string(),
+            description: None,
+            template_id: None,
+            custom_domain: None,
+            subdomain: "jane".to_string(),
+            is_published: true,
+            seo_settings: json!({}),
+            build_status: "published".to_string(),
+            theme_config: json!({}),
+            locale: None,
+            default_locale: None,
+            access_protection: Default::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn page(site: &Site, slug: &str) -> Page {
+        Page {
+            id: Uuid::new_v4(),
+            site_id: site.id,
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            meta_description: None,
+            meta_keywords: None,
+            puck_data: json!({}),
+            composition_error: false,
+            custom_head: None,
+            custom_body: None,
+            is_published: true,
+            published_html: Some("<p>Hi</p>".to_string()),
+            published_at: Some(Utc::now()),
+            sort_order: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_protected_site_absent_from_sitemap() {
+        let mut site = site();
+        let pages = vec![page(&site, "books"), page(&site, "about")];
+
+        let sitemap = site_sitemap(&site, &pages).expect("public site has a sitemap");
+        assert!(sitemap.contains("<loc>https://jane.quillspace.app/books</loc>"));
+        assert!(sitemap.contains("<loc>https://jane.quillspace.app/about</loc>"));
+
+        site.access_protection = SiteAccessRequest::Tokens { tokens: vec!["reviewer-token-0001".to_string()] }
+            .into_access()
+            .unwrap();
+        assert_eq!(site_sitemap(&site, &pages), None);
+    }
+}
@@ -0,0 +1,215 @@
+//! Page slugs, generated with the tenant's transliteration rules and kept clear of
+//! reserved paths.
+//!
+//! Tenants configure both under `settings.slugs`:
+//!
+//! ```json
+//! { "reserved": ["login", "members"], "transliterations": { "ä": "ae", "&": "and" } }
+//! ```
+//!
+//! A slug is lowercase ASCII letters and digits separated by single hyphens. Each
+//! character is replaced by the tenant's transliteration if it has one, otherwise by
+//! the built-in one for common Latin letters; anything left that is not a letter or
+//! digit becomes a separator.
+
+use anyhow::{Context, Result};
+use deadpool_postgres::GenericClient;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Key of the slug rules in tenant settings
+pub const SLUG_SETTING: &str = "slugs";
+
+/// Longest slug generated, in characters
+pub const MAX_SLUG_LEN: usize = 100;
+
+/// Top-level platform routes, which a page served from the site root would shadow.
+/// `sitemap.xml` and `_access` need no entry: no slug can contain `.` or `_`.
+pub const SYSTEM_PAGE_SLUGS: &[&str] = &["api", "health", "ready", "ping", "info", "metrics", "preview", "public"];
+
+const MAX_RESERVED: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SlugError {
+    #[error("Slug '{0}' has no letters or digits")]
+    Empty(String),
+
+    #[error("Slug '{0}' is reserved")]
+    Reserved(String),
+
+    #[error("Invalid slug settings: {0}")]
+    InvalidSettings(String),
+}
+
+/// A tenant's slug preferences; the default has no reserved words of its own and
+/// only the built-in transliterations
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlugRules {
+    /// Slugs the tenant keeps for its own routes, on top of [`SYSTEM_PAGE_SLUGS`]
+    pub reserved: Vec<String>,
+    /// Replacement for a character, taking precedence over the built-in ones
+    pub transliterations: BTreeMap<char, String>,
+}
+
+impl SlugRules {
+    /// The rules in `settings`, or the defaults when it has none
+    pub fn from_settings(settings: &serde_json::Value) -> Result<Self, SlugError> {
+        let rules: Self = match settings.get(SLUG_SETTING) {
+            None | Some(serde_json::Value::Null) => return Ok(Self::default()),
+            Some(rules) => serde_json::from_value(rules.clone())
+                .map_err(|e| SlugError::InvalidSettings(e.to_string()))?,
+        };
+
+        if rules.reserved.len() > MAX_RESERVED {
+            return Err(SlugError::InvalidSettings(format!("at most {} reserved slugs", MAX_RESERVED)));
+        }
+        if let Some((from, _)) = rules.transliterations.iter().find(|(_, to)| !to.is_ascii()) {
+            return Err(SlugError::InvalidSettings(format!("transliteration of '{}' must be ASCII", from)));
+        }
+        if let Some(word) = rules.reserved.iter().find(|word| rules.slugify(word).is_empty()) {
+            return Err(SlugError::InvalidSettings(format!("reserved slug '{}' has no letters or digits", word)));
+        }
+        Ok(rules)
+    }
+
+    /// `input` as a slug; empty if it has nothing to keep
+    pub fn slugify(&self, input: &str) -> String {
+        let mut slug = String::new();
+        let mut separate = false;
+        let mut push = |c: char| {
+            if !c.is_ascii_alphanumeric() {
+                separate = true;
+                return;
+            }
+            if separate && !slug.is_empty() {
+                slug.push('-');
+            }
+            separate = false;
+            slug.push(c.to_ascii_lowercase());
+        };
+        for c in input.chars() {
+            match self.transliterations.get(&c).map(String::as_str).or_else(|| builtin_transliteration(c)) {
+                Some(replacement) => replacement.chars().for_each(&mut push),
+                None => push(c),
+            }
+        }
+        slug.truncate(MAX_SLUG_LEN);
+        slug.trim_end_matches('-').to_string()
+    }
+
+    /// Whether `slug` would take a system path or one the tenant reserved
+    pub fn is_reserved(&self, slug: &str) -> bool {
+        SYSTEM_PAGE_SLUGS.contains(&slug) || self.reserved.iter().any(|word| self.slugify(word) == slug)
+    }
+
+    /// The slug stored for a page requested as `requested`
+    pub fn page_slug(&self, requested: &str) -> Result<String, SlugError> {
+        let slug = self.slugify(requested);
+        if slug.is_empty() {
+            return Err(SlugError::Empty(requested.to_string()));
+        }
+        if self.is_reserved(&slug) {
+            return Err(SlugError::Reserved(slug));
+        }
+        Ok(slug)
+    }
+}
+
+/// ASCII spelling of common accented Latin letters
+fn builtin_transliteration(c: char) -> Option<&'static str> {
+    let ascii = match c.to_lowercase().next().unwrap_or(c) {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' => "i",
+        'ł' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ř' => "r",
+        'ś' | 'š' => "s",
+        'ß' => "ss",
+        'ť' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    };
+    Some(ascii)
+}
+
+/// Reject a settings update whose `slugs` are not valid rules
+pub fn validate_settings(settings: &serde_json::Value) -> Result<(), SlugError> {
+    SlugRules::from_settings(settings).map(|_| ())
+}
+
+/// The tenant's slug rules, read from its settings
+pub async fn load_slug_rules(client: &impl GenericClient, tenant_id: &Uuid) -> Result<SlugRules> {
+    let settings: Option<serde_json::Value> = client
+        .query_opt("SELECT settings FROM tenants WHERE id = $1", &[tenant_id])
+        .await
+        .context("Failed to load tenant settings")?
+        .and_then(|row| row.get(0));
+    match settings {
+        Some(settings) => SlugRules::from_settings(&settings).context("Tenant has invalid slug settings"),
+        None => Ok(SlugRules::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_slugify_defaults() {
+        let rules = SlugRules::default();
+        assert_eq!(rules.slugify("  Über die Brontës!  "), "uber-die-brontes");
+        assert_eq!(rules.slugify("Books & Events -- 2024"), "books-events-2024");
+        assert_eq!(rules.slugify("Œuvres complètes"), "oeuvres-completes");
+        assert_eq!(rules.slugify("???"), "");
+        assert_eq!(rules.slugify(&"word ".repeat(40)).len(), MAX_SLUG_LEN - 1);
+        assert!(!rules.slugify(&"word ".repeat(40)).ends_with('-'));
+    }
+
+    #[test]
+    fn test_transliteration_settings_change_the_slug() {
+        let settings = json!({ "slugs": { "transliterations": { "ü": "ue", "Ü": "Ue", "&": "and" } } });
+        let rules = SlugRules::from_settings(&settings).unwrap();
+
+        assert_eq!(SlugRules::default().slugify("Über Bücher & Mehr"), "uber-bucher-mehr");
+        assert_eq!(rules.slugify("Über Bücher & Mehr"), "ueber-buecher-and-mehr");
+    }
+
+    #[test]
+    fn test_reserved_slugs_rejected() {
+        let rules = SlugRules::from_settings(&json!({ "slugs": { "reserved": ["Login", "members"] } })).unwrap();
+
+        assert_eq!(rules.page_slug("Login"), Err(SlugError::Reserved("login".to_string())));
+        assert_eq!(rules.page_slug("members"), Err(SlugError::Reserved("members".to_string())));
+        assert_eq!(rules.page_slug("API"), Err(SlugError::Reserved("api".to_string())));
+        assert_eq!(rules.page_slug("Members Area"), Ok("members-area".to_string()));
+        assert_eq!(SlugRules::default().page_slug("login"), Ok("login".to_string()));
+        assert!(matches!(rules.page_slug("—"), Err(SlugError::Empty(_))));
+    }
+
+    #[test]
+    fn test_invalid_settings_rejected() {
+        assert!(validate_settings(&json!({})).is_ok());
+        assert!(validate_settings(&json!({ "slugs": null })).is_ok());
+        for slugs in [
+            json!({ "reserved": "login" }),
+            json!({ "reserved": ["!!"] }),
+            json!({ "transliterations": { "ab": "x" } }),
+            json!({ "transliterations": { "ä": "ä" } }),
+            json!({ "separator": "_" }),
+        ] {
+            assert!(matches!(validate_settings(&json!({ "slugs": slugs })), Err(SlugError::InvalidSettings(_))), "{}", slugs);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Subdomains generated for sites created without one.
+//!
+//! A site named "Brontë Press" gets `bronte-press`, or when that is taken (or
+//! reserved) `bronte-press-1`, `bronte-press-2` and so on up to `max_suffix`. Past that
+//! it gets a random suffix such as `bronte-press-x7k2q9`, tried `random_attempts` times.
+//!
+//! Each candidate is reserved by inserting the site with it, relying on the unique
+//! index on `sites.subdomain`, so of two sites created at once with the same name only
+//! one gets a candidate and the other moves on to the next.
+
+use crate::services::site::RESERVED_SUBDOMAINS;
+use crate::services::slug::SlugRules;
+use rand::Rng;
+use std::future::Future;
+
+/// Longest subdomain, the DNS label limit
+pub const MAX_SUBDOMAIN_LEN: usize = 63;
+
+/// Base for names without letters or digits
+const FALLBACK_BASE: &str = "site";
+
+const RANDOM_SUFFIX_LEN: usize = 6;
+const RANDOM_SUFFIX_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SubdomainError {
+    #[error("Subdomain '{0}' is already taken")]
+    Taken(String),
+
+    #[error("No free subdomain for '{0}'")]
+    Exhausted(String),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// How many candidates are tried before giving up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubdomainGenerator {
+    /// Highest numbered suffix tried
+    pub max_suffix: u32,
+    /// Random suffixes tried once the numbered ones are exhausted
+    pub random_attempts: u32,
+}
+
+impl Default for SubdomainGenerator {
+    fn default() -> Self {
+        Self {
+            max_suffix: 20,
+            random_attempts: 5,
+        }
+    }
+}
+
+impl SubdomainGenerator {
+    /// The subdomain a site named `name` gets when it is free
+    pub fn base(name: &str) -> String {
+        let base = truncate(&SlugRules::default().slugify(name), MAX_SUBDOMAIN_LEN);
+        if base.is_empty() {
+            FALLBACK_BASE.to_string()
+        } else {
+            base
+        }
+    }
+
+    /// The numbered candidates for `name`, in the order they are tried. The bare base
+    /// is left out when it is reserved.
+    pub fn candidates(&self, name: &str) -> Vec<String> {
+        let base = Self::base(name);
+        let bare = (!RESERVED_SUBDOMAINS.contains(&base.as_str())).then(|| base.clone());
+        bare.into_iter()
+            .chain((1..=self.max_suffix).map(|suffix| with_suffix(&base, &suffix.to_string())))
+            .collect()
+    }
+
+    /// Reserve a subdomain for `name`: `try_reserve` is called with each candidate in
+    /// turn, numbered ones first, until it returns `Some` for one it could claim
+    pub async fn reserve<T, F, Fut>(&self, name: &str, mut try_reserve: F) -> Result<T, SubdomainError>
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = anyhow::Result<Option<T>>>,
+    {
+        for candidate in self.candidates(name) {
+            if let Some(reserved) = try_reserve(candidate).await? {
+                return Ok(reserved);
+            }
+        }
+
+        let base = Self::base(name);
+        for _ in 0..self.random_attempts {
+            if let Some(reserved) = try_reserve(with_suffix(&base, &random_suffix())).await? {
+                return Ok(reserved);
+            }
+        }
+        Err(SubdomainError::Exhausted(name.to_string()))
+    }
+}
+
+/// `base-suffix`, with `base` shortened to keep it a valid subdomain
+fn with_suffix(base: &str, suffix: &str) -> String {
+    format!("{}-{}", truncate(base, MAX_SUBDOMAIN_LEN - suffix.len() - 1), suffix)
+}
+
+/// At most `len` bytes of a slug, without a trailing hyphen
+fn truncate(slug: &str, len: usize) -> String {
+    slug[..slug.len().min(len)].trim_end_matches('-').to_string()
+}
+
+fn random_suffix() -> String {
+    let mut rng = rand::thread_rng();
+    (0..RANDOM_SUFFIX_LEN)
+        .map(|_| RANDOM_SUFFIX_CHARS[rng.gen_range(0..RANDOM_SUFFIX_CHARS.len())] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::site::SiteService;
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+
+    /// Claims a subdomain if no one has, like the insert into `sites`
+    fn claim(taken: &Arc<Mutex<HashSet<String>>>, candidate: String) -> impl Future<Output = anyhow::Result<Option<String>>> {
+        let taken = taken.clone();
+        async move {
+            tokio::task::yield_now().await;
+            Ok(taken.lock().unwrap().insert(candidate.clone()).then_some(candidate))
+        }
+    }
+
+    #[test]
+    fn test_candidates_derived_from_name() {
+        let generator = SubdomainGenerator { max_suffix: 3, random_attempts: 0 };
+        assert_eq!(
+            generator.candidates("Brontë Press"),
+            vec!["bronte-press", "bronte-press-1", "bronte-press-2", "bronte-press-3"]
+        );
+        assert_eq!(generator.candidates("Blog"), vec!["blog-1", "blog-2", "blog-3"]);
+        assert_eq!(SubdomainGenerator::base("!!!"), "site");
+
+        let long = generator.candidates(&"word ".repeat(30));
+        assert!(long.iter().all(|candidate| SiteService::validate_subdomain(candidate).is_ok()), "{:?}", long);
+        assert!(long.iter().all(|candidate| candidate.len() <= MAX_SUBDOMAIN_LEN));
+    }
+
+    #[tokio::test]
+    async fn test_same_named_sites_get_distinct_subdomains() {
+        let generator = SubdomainGenerator::default();
+        let taken = Arc::new(Mutex::new(HashSet::new()));
+
+        let creates = (0..20).map(|_| {
+            let taken = taken.clone();
+            tokio::spawn(async move { generator.reserve("Brontë Press", |candidate| claim(&taken, candidate)).await })
+        });
+        let mut subdomains = HashSet::new();
+        for create in creates {
+            assert!(subdomains.insert(create.await.unwrap().unwrap()));
+        }
+
+        assert_eq!(subdomains.len(), 20);
+        assert!(subdomains.contains("bronte-press"));
+        assert!(subdomains.contains("bronte-press-19"));
+    }
+
+    #[tokio::test]
+    async fn test_random_fallback_after_numbered_suffixes() {
+        let generator = SubdomainGenerator { max_suffix: 3, random_attempts: 2 };
+        let taken = Arc::new(Mutex::new(generator.candidates("Brontë Press").into_iter().collect::<HashSet<_>>()));
+
+        let subdomain = generator.reserve("Brontë Press", |candidate| claim(&taken, candidate)).await.unwrap();
+        let suffix = subdomain.strip_prefix("bronte-press-").expect("random suffix");
+        assert_eq!(suffix.len(), RANDOM_SUFFIX_LEN);
+        assert!(suffix.parse::<u32>().map_or(true, |number| number > 3));
+        assert!(SiteService::validate_subdomain(&subdomain).is_ok());
+
+        let tries = Arc::new(Mutex::new(0));
+        let never = generator
+            .reserve("Brontë Press", |_| {
+                *tries.lock().unwrap() += 1;
+                async { anyhow::Ok(None::<String>) }
+            })
+            .await;
+        assert!(matches!(never, Err(SubdomainError::Exhausted(_))));
+        assert_eq!(*tries.lock().unwrap(), 4 + 2);
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use minijinja::Environment;
+use crate::services::template_engine::truncate_at_boundary;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use tokio_postgres::Client;
@@ -18,6 +19,9 @@ pub struct Template {
     pub html_main: String,
     pub html_partials: HashMap<String, String>,
     pub manifest: serde_json::Value,
+    /// Editor defaults, including the default page content seeded on template switch
+    #[serde(default)]
+    pub default_schema: serde_json::Value,
 }
 
 /// Cache key for templates
@@ -165,7 +169,7 @@ impl TemplateCache {
     ) -> Result<Template, TemplateCacheError> {
         let query = r#"
             SELECT id, tenant_id, name, version, display_name, description, 
-                   main_name, html_main, html_partials, manifest
+                   main_name, html_main, html_partials, manifest, default_schema
             FROM templates 
             WHERE (tenant_id IS NULL OR tenant_id = $1) 
               AND name = $2 
@@ -196,6 +200,7 @@ impl TemplateCache {
             html_main: row.get("html_main"),
             html_partials,
             manifest: row.get("manifest"),
+            default_schema: row.get("default_schema"),
         })
     }
 
@@ -203,7 +208,7 @@ impl TemplateCache {
     async fn load_template_by_id_from_db(&self, template_id: Uuid) -> Result<Template, TemplateCacheError> {
         let query = r#"
             SELECT id, tenant_id, name, version, display_name, description, 
-                   main_name, html_main, html_partials, manifest
+                   main_name, html_main, html_partials, manifest, default_schema
             FROM templates 
             WHERE id = $1 AND is_active = true
         "#;
@@ -229,6 +234,7 @@ impl TemplateCache {
             html_main: row.get("html_main"),
             html_partials,
             manifest: row.get("manifest"),
+            default_schema: row.get("default_schema"),
         })
     }
 
@@ -252,11 +258,8 @@ impl TemplateCache {
         
         // env.set_source(source); // TODO: Fix minijinja source loading
         
-        // Configure environment
-        // env.set_auto_escape_callback(|name| {
-        //     // Auto-escape HTML files
-        //     name.ends_with(".html") || name.ends_with(".htm")
-        // }); // TODO: Fix minijinja auto escape
+        // Page templates are HTML whatever their name, so escape every value by default
+        env.set_auto_escape_callback(|_| minijinja::AutoEscape::Html);
         
         // Add custom filters if needed
         env.add_filter("truncate", truncate_filter);
@@ -268,10 +271,13 @@ impl TemplateCache {
 
 /// Custom MiniJinja filters
 fn truncate_filter(value: String, length: usize) -> String {
-    if value.len() <= length {
-        value
-    } else {
-        format!("{}...", &value[..length.saturating_sub(3)])
+    // `length` includes the ellipsis
+    match truncate_at_boundary(&value, length) {
+        Some(_) => {
+            let truncated = truncate_at_boundary(&value, length.saturating_sub(3)).unwrap_or(&value);
+            format!("{}...", truncated)
+        }
+        None => value,
     }
 }
 
@@ -0,0 +1,254 @@
+//! Tenant data templates can look up while rendering:
+//!
+//! - `recent_content(limit)`: the tenant's latest published content, newest first
+//! - `site_setting(key)`: a value from the site's effective theme (the tenant theme with
+//!   the site's `theme_config` overrides, see `site_theme`), by dotted path such as
+//!   `"contact.email"`
+//!
+//! Templates render synchronously, so the data is loaded before the render, and only
+//! when the template source calls the helper. The queries are fixed, filter on the
+//! rendering tenant and run with its RLS context set; a template only picks a limit or
+//! a key, so it can neither reach another tenant's rows nor shape the SQL.
+
+use crate::database::postgres::tenant_transaction;
+use crate::services::content::content_status_to_string;
+use crate::services::site_theme::{load_tenant_theme, resolve_theme};
+use crate::types::{ContentStatus, TenantId};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use minijinja::{Environment, Error as TemplateError, ErrorKind, Value as TemplateValue};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Most items one `recent_content` call returns
+pub const MAX_RECENT_CONTENT: usize = 20;
+
+/// Deepest `site_setting` path, in segments
+const MAX_SETTING_DEPTH: usize = 5;
+
+/// A published content item as `recent_content` lists it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecentContent {
+    pub id: Uuid,
+    pub title: String,
+    pub slug: String,
+    pub locale: String,
+    pub tags: Vec<String>,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// The data behind the helpers for one render
+#[derive(Debug, Clone, Default)]
+pub struct TemplateData {
+    recent_content: Vec<RecentContent>,
+    site_settings: Value,
+}
+
+impl TemplateData {
+    pub fn new(recent_content: Vec<RecentContent>, site_settings: Value) -> Self {
+        Self { recent_content, site_settings }
+    }
+
+    /// Load what `source` uses, for `tenant_id` rendering `site_id`
+    pub async fn load(pool: &Pool, tenant_id: Uuid, site_id: Uuid, source: &str) -> Result<Self> {
+        let needs_content = source.contains("recent_content");
+        let needs_settings = source.contains("site_setting");
+        if !needs_content && !needs_settings {
+            return Ok(Self::default());
+        }
+
+        let mut client = pool.get().await.context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, &TenantId::from_uuid(tenant_id)).await?;
+
+        let mut data = Self::default();
+        if needs_content {
+            let published = content_status_to_string(&ContentStatus::Published);
+            let limit = MAX_RECENT_CONTENT as i64;
+            data.recent_content = transaction
+                .query(
+                    "SELECT id, title, slug, locale, tags, published_at
+                     FROM content
+                     WHERE tenant_id = $1 AND status = $2
+                     ORDER BY published_at DESC NULLS LAST, created_at DESC
+                     LIMIT $3",
+                    &[&tenant_id, &published, &limit],
+                )
+                .await
+                .context("Failed to load recent content")?
+                .iter()
+                .map(|row| RecentContent {
+                    id: row.get("id"),
+                    title: row.get("title"),
+                    slug: row.get::<_, Option<String>>("slug").unwrap_or_default(),
+                    locale: row.get("locale"),
+                    tags: row.get("tags"),
+                    published_at: row.get("published_at"),
+                })
+                .collect();
+        }
+        if needs_settings {
+            let overrides = transaction
+                .query_opt("SELECT theme_config FROM sites WHERE id = $1 AND tenant_id = $2", &[&site_id, &tenant_id])
+                .await
+                .context("Failed to load site settings")?
+                .map_or(Value::Null, |row| row.get("theme_config"));
+            let tenant_theme = load_tenant_theme(&transaction, &tenant_id).await?;
+            data.site_settings = resolve_theme(&tenant_theme, &overrides);
+        }
+        transaction.commit().await.context("Failed to commit template data")?;
+        Ok(data)
+    }
+
+    /// The `limit` latest items; asking for more than [`MAX_RECENT_CONTENT`] is an error
+    pub fn recent_content(&self, limit: usize) -> Result<&[RecentContent], TemplateError> {
+        if limit > MAX_RECENT_CONTENT {
+            return Err(TemplateError::new(
+                ErrorKind::InvalidOperation,
+                format!("recent_content returns at most {} items", MAX_RECENT_CONTENT),
+            ));
+        }
+        Ok(&self.recent_content[..limit.min(self.recent_content.len())])
+    }
+
+    /// The setting at a dotted `key`, `None` when the site does not set it
+    pub fn site_setting(&self, key: &str) -> Result<Option<&Value>, TemplateError> {
+        let segments: Vec<&str> = key.split('.').collect();
+        let valid = segments.len() <= MAX_SETTING_DEPTH
+            && segments.iter().all(|segment| {
+                !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+            });
+        if !valid {
+            return Err(TemplateError::new(ErrorKind::InvalidOperation, format!("invalid site setting key '{}'", key)));
+        }
+        Ok(segments.iter().try_fold(&self.site_settings, |value, segment| value.get(segment)))
+    }
+
+    /// Add `recent_content` and `site_setting` to `env`
+    pub fn register(self: Arc<Self>, env: &mut Environment<'static>) {
+        let data = Arc::clone(&self);
+        env.add_function("recent_content", move |limit: Option<usize>| -> Result<TemplateValue, TemplateError> {
+            data.recent_content(limit.unwrap_or(5)).map(TemplateValue::from_serialize)
+        });
+        env.add_function("site_setting", move |key: String| -> Result<TemplateValue, TemplateError> {
+            Ok(self.site_setting(&key)?.map_or(TemplateValue::UNDEFINED, TemplateValue::from_serialize))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(data: TemplateData, source: &str) -> Result<String, TemplateError> {
+        let mut env = Environment::new();
+        Arc::new(data).register(&mut env);
+        env.render_str(source, ())
+    }
+
+    #[test]
+    fn test_site_setting_reads_dotted_paths() {
+        let data = TemplateData::new(Vec::new(), json!({ "contact": { "email": "hello@example.com" } }));
+
+        assert_eq!(
+            render(data.clone(), "{{ site_setting('contact.email') }} {{ site_setting('contact.phone')|default('-') }}")
+                .unwrap(),
+            "hello@example.com -"
+        );
+        for key in ["", "contact..email", "contact.e mail", "a.b.c.d.e.f"] {
+            assert!(data.site_setting(key).is_err(), "{}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recent_content_lists_tenants_latest_published() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let admin = app.admin_pool.get().await.unwrap();
+        let at = |day: u32| chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 3, day, 9, 0, 0).unwrap();
+        let seed = |tenant: Uuid, author: Uuid, title: &'static str, status: &'static str, day: u32| {
+            let admin = &admin;
+            async move {
+                admin
+                    .execute(
+                        "INSERT INTO content (tenant_id, author_id, title, slug, body, status, locale, published_at, translation_group_id)
+                         VALUES ($1, $2, $3, $4, '', $5, 'en-US', $6, uuid_generate_v4())",
+                        &[&tenant, &author, &title, &title.to_lowercase().replace(' ', "-"), &status, &at(day)],
+                    )
+                    .await
+                    .expect("Failed to seed content");
+            }
+        };
+        let (tenant_a, tenant_b) = (*app.tenant_a.id.as_uuid(), *app.tenant_b.id.as_uuid());
+        seed(tenant_a, app.tenant_a.admin.id, "Emma", "Published", 1).await;
+        seed(tenant_a, app.tenant_a.admin.id, "Persuasion", "Published", 3).await;
+        seed(tenant_a, app.tenant_a.admin.id, "Sanditon", "Draft", 4).await;
+        seed(tenant_a, app.tenant_a.admin.id, "Mansfield Park", "Published", 2).await;
+        seed(tenant_b, app.tenant_b.admin.id, "Jane Eyre", "Published", 5).await;
+
+        let source = "{% for item in recent_content(2) %}{{ item.title }};{% endfor %}";
+        let data = TemplateData::load(app.state.db.postgres(), tenant_a, Uuid::new_v4(), source).await.unwrap();
+        assert_eq!(render(data.clone(), source).unwrap(), "Persuasion;Mansfield Park;");
+        assert_eq!(data.recent_content(10).unwrap().len(), 3);
+        assert!(render(data, "{{ recent_content(100) }}").is_err());
+
+        // Nothing is loaded for a template that does not use the helpers
+        let data = TemplateData::load(app.state.db.postgres(), tenant_a, Uuid::new_v4(), "<h1></h1>").await.unwrap();
+        assert!(data.recent_content(5).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_site_setting_inherits_tenant_theme() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let admin = app.admin_pool.get().await.unwrap();
+        let tenant_id = *app.tenant_a.id.as_uuid();
+        let set_tenant_theme = |theme: Value| {
+            let admin = &admin;
+            async move {
+                admin
+                    .execute(
+                        "UPDATE tenants SET settings = jsonb_set(COALESCE(settings, '{}'::jsonb), '{theme}', $2) WHERE id = $1",
+                        &[&tenant_id, &theme],
+                    )
+                    .await
+                    .expect("Failed to set tenant theme");
+            }
+        };
+        let add_site = |theme_config: Value| {
+            let admin = &admin;
+            async move {
+                admin
+                    .query_one(
+                        "INSERT INTO sites (tenant_id, name, subdomain, theme_config) VALUES ($1, 'Press', $2, $3) RETURNING id",
+                        &[&tenant_id, &format!("press-{}", Uuid::new_v4().simple()), &theme_config],
+                    )
+                    .await
+                    .expect("Failed to seed site")
+                    .get::<_, Uuid>(0)
+            }
+        };
+        let inheriting = add_site(serde_json::json!({})).await;
+        let overriding = add_site(serde_json::json!({ "colors": { "primary": "#00aa00" } })).await;
+        let source = "{{ site_setting('colors.primary') }}/{{ site_setting('font') }}";
+        let pool = app.state.db.postgres();
+        let rendered = |site_id: Uuid| async move {
+            let data = TemplateData::load(pool, tenant_id, site_id, source).await.unwrap();
+            render(data, source).unwrap()
+        };
+
+        set_tenant_theme(serde_json::json!({ "colors": { "primary": "#224466" }, "font": "Lora" })).await;
+        assert_eq!(rendered(inheriting).await, "#224466/Lora");
+        assert_eq!(rendered(overriding).await, "#00aa00/Lora");
+
+        // A tenant theme change shows on the site that does not override it
+        set_tenant_theme(serde_json::json!({ "colors": { "primary": "#aa0000" }, "font": "Lora" })).await;
+        assert_eq!(rendered(inheriting).await, "#aa0000/Lora");
+        assert_eq!(rendered(overriding).await, "#00aa00/Lora");
+    }
+}
@@ -1,27 +1,97 @@
 use anyhow::{Context, Result};
-use minijinja::{Environment, context};
+use minijinja::{AutoEscape, Environment, HtmlEscape, State, Value as TemplateValue, context};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio_postgres::Row;
 use tracing::{error, info, warn};
+use unicode_segmentation::UnicodeSegmentation;
 use uuid::Uuid;
 
-use crate::database::{DatabaseConnections, rls_helper::RlsHelper};
+use crate::config::HtmlMinifyConfig;
+use crate::database::{postgres::tenant_client, DatabaseConnections};
+use crate::services::dev_templates::DevTemplateLoader;
+use crate::services::html_minify::minify_for_site;
+use crate::services::html_sanitize::SanitizedHtml;
+use crate::services::locale::{self, DEFAULT_LOCALE};
+use crate::services::page::PageService;
+use crate::services::public_url::{absolute_url, site_origin};
+use crate::services::metrics_registry::MetricsRegistry;
+use crate::services::render_metrics::RenderMetrics;
+use crate::services::site_analytics::{analytics_snippet, inject_into_head};
+use crate::services::template_source_cache::{TemplateCacheStats, TemplateSourceCache, TenantCacheEntries};
+use crate::services::template_data::TemplateData;
+use crate::services::template_syntax::TemplateSyntax;
+use crate::services::translation::{resolve_translation, TranslationService, Translations};
+use crate::services::content_related::RelatedContent;
+use crate::types::{ContentAuthor, TenantId};
+
+/// Base template every Puck page is rendered with
+pub const PUCK_BASE_TEMPLATE: &str = "puck-base";
+
+/// Name used for the built-in template when no configured fallback exists either
+pub const BUILTIN_FALLBACK_TEMPLATE_NAME: &str = "__builtin_fallback__.html";
+
+/// Last-resort template so a page with a missing template still renders
+const BUILTIN_FALLBACK_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{{ page.title }} | {{ site.name }}</title>
+    {% if page.meta_description %}<meta name="description" content="{{ page.meta_description }}">{% endif %}
+</head>
+<body>
+    <main id="puck-root">
+        <h1>{{ page.title }}</h1>
+    </main>
+</body>
+</html>"#;
+
+/// Template categories rendered as plain text (e.g. the text part of an email).
+/// Every other category is treated as HTML and auto-escaped.
+pub const TEXT_TEMPLATE_CATEGORIES: &[&str] = &["email_text", "text"];
+
+/// Escaping applied to a template's output, decided by its category rather than its name
+pub fn auto_escape_for_category(category: &str) -> AutoEscape {
+    if TEXT_TEMPLATE_CATEGORIES.contains(&category) {
+        AutoEscape::None
+    } else {
+        AutoEscape::Html
+    }
+}
+
+/// Template source along with the category that decides how it is escaped and the
+/// delimiters it is written with
+#[derive(Debug, Clone)]
+struct TemplateSource {
+    html_source: String,
+    category: String,
+    syntax: TemplateSyntax,
+}
 
 /// Template engine service with database loader for MiniJinja templates
 pub struct TemplateEngine {
     env: Environment<'static>,
     db: Arc<DatabaseConnections>,
-    template_cache: std::sync::RwLock<HashMap<String, String>>,
+    template_cache: TemplateSourceCache<TemplateSource>,
+    /// Navigation per site, dropped when the site's pages are published or reordered
+    navigation_cache: std::sync::RwLock<HashMap<Uuid, Vec<NavigationItem>>>,
+    default_template: String,
+    minify: HtmlMinifyConfig,
+    render_metrics: RenderMetrics,
+    /// Templates read from disk ahead of the database, in development only
+    dev_templates: Option<DevTemplateLoader>,
 }
 
 /// Template data structure matching database schema
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Template {
     pub id: Uuid,
-    pub tenant_id: Uuid,
+    /// `None` for platform templates, which every tenant can use
+    pub tenant_id: Option<Uuid>,
     pub name: String,
     pub description: Option<String>,
     pub category: String,
@@ -42,6 +112,17 @@ pub struct TemplateContext {
     pub puck_data: Option<Value>,
     pub puck_content: String,
     pub user: Option<UserContext>,
+    /// The site's published pages in menu order, for `{% for item in navigation %}`
+    pub navigation: Vec<NavigationItem>,
+    /// The content item being rendered, when the page shows one
+    pub content: Option<ContentContext>,
+    /// Sanitized HTML blocks output unescaped, as `{{ trusted.sidebar }}`
+    pub trusted: BTreeMap<String, SanitizedHtml>,
+    /// Locale for the `number_format`, `currency` and `date` filters (see `locale::resolve_locale`)
+    pub locale: String,
+    /// Site strings looked up by `t(key)`
+    #[serde(skip)]
+    pub translations: Translations,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +133,15 @@ pub struct SiteContext {
     pub subdomain: String,
     pub custom_domain: Option<String>,
     pub seo_settings: Value,
+    /// Locale every visitor sees (see `locale::resolve_locale`)
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Locale a multilingual site falls back to
+    #[serde(default)]
+    pub default_locale: Option<String>,
+    /// The tenant theme with the site's `theme_config` overrides applied
+    #[serde(default)]
+    pub theme: Value,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +155,81 @@ pub struct PageContext {
     pub published_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// A published page as listed in the site navigation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NavigationItem {
+    pub title: String,
+    pub slug: String,
+    pub sort_order: i32,
+}
+
+/// A content item with its full byline, for `{% for author in content.authors %}`
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentContext {
+    pub id: Uuid,
+    pub title: String,
+    pub slug: String,
+    /// The body, sanitized, so `{{ content.body }}` outputs its markup
+    pub body: SanitizedHtml,
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Primary author first, then co-authors in byline order
+    pub authors: Vec<ContentAuthor>,
+    /// Author names joined for display, e.g. "Jane Austen, Anne Brontë and Mary Shelley"
+    pub byline: String,
+    pub tags: Vec<String>,
+    /// Related posts, most related first, for `{% for post in content.related %}`
+    pub related: Vec<RelatedPost>,
+}
+
+/// A related post as linked from the one being rendered
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RelatedPost {
+    pub id: Uuid,
+    pub title: String,
+    pub slug: String,
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ContentContext {
+    pub fn new(content: &crate::types::Content, authors: Vec<ContentAuthor>) -> Self {
+        Self {
+            id: content.id,
+            title: content.title.clone(),
+            slug: content.slug.clone(),
+            body: SanitizedHtml::new(&content.body),
+            published_at: content.published_at,
+            byline: byline(&authors),
+            authors,
+            tags: content.tags.clone(),
+            related: Vec::new(),
+        }
+    }
+
+    /// Add the posts from `ContentService::related`
+    pub fn with_related(mut self, related: &[RelatedContent]) -> Self {
+        self.related = related
+            .iter()
+            .map(|related| RelatedPost {
+                id: related.content.id,
+                title: related.content.title.clone(),
+                slug: related.content.slug.clone(),
+                published_at: related.content.published_at,
+            })
+            .collect();
+        self
+    }
+}
+
+/// Join author names as "A", "A and B" or "A, B and C"
+pub fn byline(authors: &[ContentAuthor]) -> String {
+    let names: Vec<String> = authors.iter().map(ContentAuthor::display_name).collect();
+    match names.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct UserContext {
     pub id: Uuid,
@@ -77,24 +242,12 @@ impl TemplateEngine {
     pub fn new(db: Arc<DatabaseConnections>) -> Result<Self> {
         let mut env = Environment::new();
         
-        // Configure MiniJinja environment
-        env.set_auto_escape_callback(|name| {
-            // Auto-escape HTML files, but not text files
-            if name.ends_with(".html") || name.ends_with(".htm") {
-                minijinja::AutoEscape::Html
-            } else {
-                minijinja::AutoEscape::None
-            }
-        });
-        
-        // Add custom filters
-        env.add_filter("markdown", markdown_filter);
-        env.add_filter("truncate", truncate_filter);
-        env.add_filter("date", date_filter);
+        // Template names carry no extension, so escape everything as HTML by default;
+        // text templates opt out per render through their category
+        env.set_auto_escape_callback(|_| AutoEscape::Html);
         
-        // Add custom functions
-        env.add_function("asset_url", asset_url_function);
-        env.add_function("url", url_function);
+        // Add custom filters and functions
+        register_helpers(&mut env);
         
         // Add global variables
         // Add global variables - skip the now variable for now to avoid compilation issues
@@ -113,32 +266,156 @@ impl TemplateEngine {
         Ok(Self {
             env,
             db,
-            template_cache: std::sync::RwLock::new(HashMap::new()),
+            template_cache: TemplateSourceCache::new(),
+            navigation_cache: std::sync::RwLock::new(HashMap::new()),
+            default_template: crate::config::TemplateConfig::default().default_template,
+            minify: HtmlMinifyConfig::default(),
+            render_metrics: RenderMetrics::new(),
+            dev_templates: None,
         })
     }
     
+    /// Set the platform default template used when a page's template is missing
+    pub fn with_default_template(mut self, default_template: impl Into<String>) -> Self {
+        self.default_template = default_template.into();
+        self
+    }
+    
+    /// Set the global HTML minification settings applied to rendered pages
+    pub fn with_minify(mut self, minify: HtmlMinifyConfig) -> Self {
+        self.minify = minify;
+        self
+    }
+    
+    /// Record render metrics into the app's registry
+    pub fn with_metrics(mut self, registry: MetricsRegistry) -> Self {
+        self.render_metrics = RenderMetrics::with_registry(registry);
+        self
+    }
+    
+    /// Load templates from files in `dir` before the database, re-reading a file
+    /// whenever it changes. For theme development; see `AppConfig::dev_template_dir`.
+    pub fn with_dev_templates(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        let loader = DevTemplateLoader::new(dir);
+        warn!(dir = %loader.dir().display(), "Development template loading is on; templates on disk override the database");
+        self.dev_templates = Some(loader);
+        self
+    }
+    
+    /// Render timings, for the slowest-templates report
+    pub fn render_metrics(&self) -> &RenderMetrics {
+        &self.render_metrics
+    }
+    
+    /// Drop the tenant's cached template sources so the next render reloads them
+    pub fn invalidate_tenant_templates(&self, tenant_id: Uuid) {
+        self.template_cache.clear_tenant(tenant_id);
+    }
+    
+    /// Template cache hit/miss counts and size
+    pub fn template_cache_stats(&self) -> TemplateCacheStats {
+        self.template_cache.stats()
+    }
+    
+    /// Cached template names per tenant, or only `tenant_id`'s
+    pub fn template_cache_keys(&self, tenant_id: Option<Uuid>) -> Vec<TenantCacheEntries> {
+        self.template_cache.keys(tenant_id)
+    }
+    
+    /// Drop the tenant's cached templates, or every tenant's; returns how many were dropped
+    pub fn clear_template_cache(&self, tenant_id: Option<Uuid>) -> usize {
+        match tenant_id {
+            Some(tenant_id) => self.template_cache.clear_tenant(tenant_id),
+            None => self.template_cache.clear_all(),
+        }
+    }
+    
+    /// Drop a site's cached navigation so the next render reloads it
+    pub fn invalidate_navigation(&self, site_id: Uuid) {
+        if let Ok(mut cache) = self.navigation_cache.write() {
+            cache.remove(&site_id);
+        }
+    }
+    
+    /// The site's navigation, loaded with a single query and cached per site
+    pub async fn navigation(&self, tenant_id: Uuid, site_id: Uuid) -> Result<Vec<NavigationItem>> {
+        if let Ok(cache) = self.navigation_cache.read() {
+            if let Some(navigation) = cache.get(&site_id) {
+                return Ok(navigation.clone());
+            }
+        }
+        
+        let navigation = PageService::new(self.db.postgres().clone())
+            .get_navigation(&TenantId::from_uuid(tenant_id), site_id)
+            .await?;
+        if let Ok(mut cache) = self.navigation_cache.write() {
+            cache.insert(site_id, navigation.clone());
+        }
+        Ok(navigation)
+    }
+    
     /// Load template from database with caching
     pub async fn load_template(&self, name: &str, tenant_id: Uuid) -> Result<String> {
-        let cache_key = format!("{}:{}", tenant_id, name);
+        match self.find_template_source(name, tenant_id).await? {
+            Some(template) => Ok(template.html_source),
+            None => {
+                error!("Template '{}' not found for tenant {}", name, tenant_id);
+                Err(anyhow::anyhow!("Template '{}' not found", name))
+            }
+        }
+    }
+    
+    /// Check whether a template is available to the tenant
+    pub async fn template_exists(&self, name: &str, tenant_id: Uuid) -> Result<bool> {
+        Ok(self.find_template_source(name, tenant_id).await?.is_some())
+    }
+    
+    /// Load templates into the cache ahead of their first render, returning how many
+    /// exist. Missing ones are skipped; rendering them falls back as usual.
+    pub async fn warm_templates(&self, tenant_id: Uuid, names: &[&str]) -> Result<usize> {
+        let mut found = 0;
+        for name in names {
+            if self.find_template_source(name, tenant_id).await?.is_some() {
+                found += 1;
+            }
+        }
+        Ok(found)
+    }
+    
+    /// Look up a template's source, returning `None` if it does not exist
+    async fn find_template_source(&self, name: &str, tenant_id: Uuid) -> Result<Option<TemplateSource>> {
+        // Files being worked on in development win over the database and its cache
+        if let Some(dev_templates) = &self.dev_templates {
+            let template = dev_templates
+                .load(name)
+                .with_context(|| format!("Failed to read template '{}' from disk", name))?;
+            if let Some(template) = template {
+                return Ok(Some(TemplateSource {
+                    html_source: template.html_source,
+                    category: template.category,
+                    syntax: TemplateSyntax::default(),
+                }));
+            }
+        }
         
         // Check cache first
-        if let Ok(cache) = self.template_cache.read() {
-            if let Some(cached_template) = cache.get(&cache_key) {
-                return Ok(cached_template.clone());
-            }
+        if let Some(cached_template) = self.template_cache.get(tenant_id, name) {
+            return Ok(Some(cached_template));
         }
         
-        // Load from database
+        // Load from database; the tenant's delimiters apply to its own templates only
         let query = "
-            SELECT html_source 
+            SELECT html_source, category,
+                   CASE WHEN tenant_id = $2
+                        THEN (SELECT settings -> 'template_syntax' FROM tenants WHERE id = $2)
+                   END AS template_syntax
             FROM templates 
             WHERE name = $1 AND (tenant_id = $2 OR is_public = true)
             ORDER BY tenant_id = $2 DESC, version DESC
             LIMIT 1
         ";
         
-        let client = self.db.postgres().get().await
-            .context("Failed to get database connection")?;
+        let client = tenant_client(self.db.postgres(), &TenantId::from_uuid(tenant_id)).await?;
         let row = client
             .query_opt(query, &[&name, &tenant_id])
             .await
@@ -146,71 +423,145 @@ impl TemplateEngine {
         
         match row {
             Some(row) => {
-                let html_source: String = row.get("html_source");
+                let syntax_setting: Option<Value> = row.get("template_syntax");
+                let template = TemplateSource {
+                    html_source: row.get("html_source"),
+                    category: row.get("category"),
+                    syntax: TemplateSyntax::resolve(&syntax_setting.unwrap_or(Value::Null), name)
+                        .with_context(|| format!("Tenant has an invalid syntax for template '{}'", name))?,
+                };
                 
                 // Cache the template
-                if let Ok(mut cache) = self.template_cache.write() {
-                    cache.insert(cache_key, html_source.clone());
-                }
+                self.template_cache.insert(tenant_id, name, template.clone());
                 
-                Ok(html_source)
+                Ok(Some(template))
             }
-            None => {
-                error!("Template '{}' not found for tenant {}", name, tenant_id);
-                Err(anyhow::anyhow!("Template '{}' not found", name))
+            None => Ok(None),
+        }
+    }
+    
+    /// Get the tenant's configured fallback template, if any
+    async fn tenant_fallback_template(&self, tenant_id: Uuid) -> Result<Option<String>> {
+        let client = tenant_client(self.db.postgres(), &TenantId::from_uuid(tenant_id)).await?;
+        let row = client
+            .query_opt("SELECT fallback_template FROM tenants WHERE id = $1", &[&tenant_id])
+            .await
+            .context("Failed to query tenant fallback template")?;
+        
+        Ok(row.and_then(|row| row.get::<_, Option<String>>("fallback_template")))
+    }
+    
+    /// Resolve the template to render, falling back when the requested one is missing.
+    /// Returns the name actually used along with its source.
+    async fn resolve_template_source(&self, template_name: &str, tenant_id: Uuid) -> Result<(String, TemplateSource)> {
+        if let Some(source) = self.find_template_source(template_name, tenant_id).await? {
+            return Ok((template_name.to_string(), source));
+        }
+        
+        let tenant_fallback = self.tenant_fallback_template(tenant_id).await?;
+        for candidate in fallback_candidates(template_name, tenant_fallback.as_deref(), &self.default_template) {
+            if let Some(source) = self.find_template_source(candidate, tenant_id).await? {
+                warn!(
+                    requested = template_name,
+                    fallback = candidate,
+                    tenant_id = %tenant_id,
+                    "Template not found, rendering fallback template"
+                );
+                return Ok((candidate.to_string(), source));
             }
         }
+        
+        warn!(
+            requested = template_name,
+            tenant_id = %tenant_id,
+            "Template and configured fallbacks not found, rendering built-in fallback"
+        );
+        Ok((
+            BUILTIN_FALLBACK_TEMPLATE_NAME.to_string(),
+            TemplateSource {
+                html_source: BUILTIN_FALLBACK_TEMPLATE.to_string(),
+                category: "page".to_string(),
+                syntax: TemplateSyntax::default(),
+            },
+        ))
     }
     
-    /// Render template with context
+    /// Render template with context. If the template no longer exists, the tenant's
+    /// fallback template, the platform default or a built-in page is rendered instead.
+    /// Every render, including `render_puck_page`'s, is timed in `render_metrics`.
     pub async fn render_template(
         &self,
         template_name: &str,
         tenant_id: Uuid,
         context: &TemplateContext,
     ) -> Result<String> {
-        // Load template source from database
-        let template_source = self.load_template(template_name, tenant_id).await?;
+        let started = Instant::now();
         
-        // Create a new environment for this render to avoid lifetime issues
-        let mut env = Environment::new();
-        
-        // Configure the environment with the same settings as the main one
-        env.set_auto_escape_callback(|name| {
-            if name.ends_with(".html") || name.ends_with(".htm") {
-                minijinja::AutoEscape::Html
-            } else {
-                minijinja::AutoEscape::None
+        // Load template source from database, falling back if it has been deleted
+        let (template_name, category, rendered) = match self.resolve_template_source(template_name, tenant_id).await {
+            Ok((resolved_name, template)) => {
+                let data = TemplateData::load(self.db.postgres(), tenant_id, context.site.id, &template.html_source)
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!(template = %resolved_name, "Failed to load template data, rendering without it: {:#}", e);
+                        TemplateData::default()
+                    });
+                let escape = auto_escape_for_category(&template.category);
+                let rendered = render_source(
+                    &resolved_name,
+                    template.html_source,
+                    escape,
+                    &template.syntax,
+                    context,
+                    Arc::new(data),
+                );
+                (resolved_name, template.category, rendered)
             }
-        });
-        
-        // Add the template using add_template_owned to avoid lifetime issues
-        env.add_template_owned(template_name.to_string(), template_source)
-            .context("Failed to add template to environment")?;
-        
-        // Get template and render
-        let template = env.get_template(template_name)
-            .context("Failed to get template from environment")?;
-        
-        let rendered = template.render(context! {
-            site => context.site,
-            page => context.page,
-            puck_data => context.puck_data,
-            puck_content => context.puck_content,
-            user => context.user,
-        }).context("Failed to render template")?;
+            Err(e) => (template_name.to_string(), "unknown".to_string(), Err(e)),
+        };
         
-        Ok(rendered)
+        self.render_metrics
+            .record(tenant_id, &template_name, &category, started.elapsed(), rendered.as_ref().err());
+        rendered
     }
     
-    /// Render Puck data to HTML using a base template
+    /// Render Puck data to HTML using a base template. `accept_language` is the visitor's
+    /// `Accept-Language` header, used when the site has no locale configured.
     pub async fn render_puck_page(
         &self,
         puck_data: &Value,
         site_context: &SiteContext,
         page_context: &PageContext,
         tenant_id: Uuid,
+        accept_language: Option<&str>,
     ) -> Result<String> {
+        self.render_puck_template(PUCK_BASE_TEMPLATE, puck_data, site_context, page_context, tenant_id, accept_language)
+            .await
+    }
+
+    /// Render Puck data with the named template, as `render_puck_page` does with the base one
+    pub async fn render_puck_template(
+        &self,
+        template_name: &str,
+        puck_data: &Value,
+        site_context: &SiteContext,
+        page_context: &PageContext,
+        tenant_id: Uuid,
+        accept_language: Option<&str>,
+    ) -> Result<String> {
+        let translations = TranslationService::new(self.db.postgres().clone())
+            .get_translations(&TenantId::from_uuid(tenant_id), site_context.id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(site_id = %site_context.id, "Failed to load translations, rendering keys: {:#}", e);
+                Translations::new()
+            });
+        
+        let navigation = self.navigation(tenant_id, site_context.id).await.unwrap_or_else(|e| {
+            warn!(site_id = %site_context.id, "Failed to load navigation, rendering without it: {:#}", e);
+            Vec::new()
+        });
+        
         // Create context with Puck data
         let context = TemplateContext {
             site: site_context.clone(),
@@ -219,11 +570,21 @@ impl TemplateEngine {
             puck_content: serde_json::to_string_pretty(puck_data)
                 .context("Failed to serialize Puck data")?,
             user: None,
+            navigation,
+            content: None,
+            trusted: BTreeMap::new(),
+            locale: locale::resolve_locale(
+                site_context.locale.as_deref(),
+                site_context.default_locale.as_deref(),
+                accept_language,
+            ),
+            translations,
         };
         
-        // Use a base template that can render Puck data
-        // This template should include the Puck renderer component
-        self.render_template("puck-base", tenant_id, &context).await
+        // The template should include the Puck renderer component
+        let html = self.render_template(template_name, tenant_id, &context).await?;
+        let html = inject_into_head(html, &analytics_snippet(&site_context.seo_settings));
+        Ok(minify_for_site(html, &self.minify, &site_context.seo_settings))
     }
     
     /// Generate static HTML from Puck data for SEO and performance
@@ -237,11 +598,10 @@ impl TemplateEngine {
         // to generate static HTML from the Puck components
         // For now, we'll return a basic HTML structure
         
-        let title = page_context.title.clone();
-        let meta_description = page_context.meta_description
-            .as_ref()
-            .unwrap_or(&"".to_string())
-            .clone();
+        // Everything interpolated below is user-provided, so escape it for its position
+        let title = escape_html(&page_context.title);
+        let meta_description = escape_html(page_context.meta_description.as_deref().unwrap_or(""));
+        let puck_json = serde_json::to_string(puck_data).unwrap_or_default();
         
         let html = format!(
             r#"<!DOCTYPE html>
@@ -254,7 +614,7 @@ impl TemplateEngine {
     <meta property="og:title" content="{}">
     <meta property="og:description" content="{}">
     <meta property="og:site_name" content="{}">
-    <link rel="canonical" href="https://{}.quillspace.app{}">
+    <link rel="canonical" href="{}">
 </head>
 <body>
     <div id="puck-root" data-puck='{}'>
@@ -273,14 +633,17 @@ impl TemplateEngine {
             meta_description,
             title,
             meta_description,
-            site_context.name,
-            site_context.subdomain,
-            page_context.slug,
-            serde_json::to_string(puck_data).unwrap_or_default(),
-            serde_json::to_string(puck_data).unwrap_or_default()
+            escape_html(&site_context.name),
+            escape_html(&absolute_url(
+                &site_origin(&site_context.subdomain, site_context.custom_domain.as_deref()),
+                &page_context.slug,
+            )),
+            escape_html(&puck_json),
+            escape_script_json(&puck_json)
         );
         
-        Ok(html)
+        let html = inject_into_head(html, &analytics_snippet(&site_context.seo_settings));
+        Ok(minify_for_site(html, &self.minify, &site_context.seo_settings))
     }
     
     /// Get template by name and tenant
@@ -295,8 +658,7 @@ impl TemplateEngine {
             LIMIT 1
         ";
         
-        let client = self.db.postgres().get().await
-            .context("Failed to get database connection")?;
+        let client = tenant_client(self.db.postgres(), &TenantId::from_uuid(tenant_id)).await?;
         let row = client
             .query_opt(query, &[&name, &tenant_id])
             .await
@@ -308,6 +670,26 @@ impl TemplateEngine {
         }
     }
     
+    /// Get template by ID if the tenant may see it: its own, or a platform or public one.
+    /// Callers check whether it may also change it.
+    pub async fn get_template_by_id(&self, template_id: Uuid, tenant_id: Uuid) -> Result<Option<Template>> {
+        let query = "
+            SELECT id, tenant_id, name, description, category, html_source, 
+                   default_schema, preview_image_url, is_public, version,
+                   created_at, updated_at
+            FROM templates 
+            WHERE id = $1
+        ";
+
+        let client = tenant_client(self.db.postgres(), &TenantId::from_uuid(tenant_id)).await?;
+        let row = client
+            .query_opt(query, &[&template_id])
+            .await
+            .context("Failed to query template")?;
+
+        row.map(|row| self.row_to_template(row)).transpose()
+    }
+    
     /// List templates for tenant
     pub async fn list_templates(
         &self,
@@ -395,8 +777,9 @@ impl TemplateEngine {
         html_source: &str,
         default_schema: &Value,
     ) -> Result<Template> {
-        // Validate template syntax
-        self.validate_template_syntax(html_source)?;
+        // Validate template syntax, with the delimiters the tenant uses for it
+        let syntax = self.tenant_template_syntax(tenant_id, name).await?;
+        self.validate_template_syntax(html_source, &syntax)?;
         
         let query = "
             INSERT INTO templates (tenant_id, name, description, category, html_source, default_schema)
@@ -406,11 +789,7 @@ impl TemplateEngine {
                       created_at, updated_at
         ";
         
-        let client = self.db.postgres().get().await
-            .context("Failed to get database connection")?;
-        
-        // Set RLS context for tenant isolation
-        RlsHelper::set_tenant_context(&client, &tenant_id).await?;
+        let client = tenant_client(self.db.postgres(), &TenantId::from_uuid(tenant_id)).await?;
         
         let row = client
             .query_one(query, &[&tenant_id, &name, &description, &category, &html_source, &default_schema])
@@ -436,7 +815,17 @@ impl TemplateEngine {
         default_schema: Option<&Value>,
     ) -> Result<Template> {
         if let Some(html) = html_source {
-            self.validate_template_syntax(html)?;
+            let client = tenant_client(self.db.postgres(), &TenantId::from_uuid(tenant_id)).await?;
+            let name: Option<String> = client
+                .query_opt("SELECT name FROM templates WHERE id = $1 AND tenant_id = $2", &[&template_id, &tenant_id])
+                .await
+                .context("Failed to look up template")?
+                .map(|row| row.get(0));
+            let syntax = match name {
+                Some(name) => self.tenant_template_syntax(tenant_id, &name).await?,
+                None => return Err(anyhow::anyhow!("Template not found or access denied")),
+            };
+            self.validate_template_syntax(html, &syntax)?;
         }
         
         let query = "
@@ -451,8 +840,7 @@ impl TemplateEngine {
                       created_at, updated_at
         ";
         
-        let client = self.db.postgres().get().await
-            .context("Failed to get database connection")?;
+        let client = tenant_client(self.db.postgres(), &TenantId::from_uuid(tenant_id)).await?;
         let row = client
             .query_opt(query, &[&template_id, &tenant_id, &html_source, &description, &default_schema])
             .await
@@ -473,8 +861,7 @@ impl TemplateEngine {
     pub async fn delete_template(&self, template_id: Uuid, tenant_id: Uuid) -> Result<()> {
         let query = "DELETE FROM templates WHERE id = $1 AND tenant_id = $2";
         
-        let client = self.db.postgres().get().await
-            .context("Failed to get database connection")?;
+        let client = tenant_client(self.db.postgres(), &TenantId::from_uuid(tenant_id)).await?;
         let rows_affected = client
             .execute(query, &[&template_id, &tenant_id])
             .await
@@ -489,10 +876,25 @@ impl TemplateEngine {
         Ok(())
     }
     
+    /// The delimiters the tenant's template `name` is written with
+    async fn tenant_template_syntax(&self, tenant_id: Uuid, name: &str) -> Result<TemplateSyntax> {
+        let client = tenant_client(self.db.postgres(), &TenantId::from_uuid(tenant_id)).await?;
+        let setting: Option<Value> = client
+            .query_opt("SELECT settings -> 'template_syntax' FROM tenants WHERE id = $1", &[&tenant_id])
+            .await
+            .context("Failed to read tenant template syntax")?
+            .and_then(|row| row.get(0));
+        TemplateSyntax::resolve(&setting.unwrap_or(Value::Null), name)
+            .with_context(|| format!("Tenant has an invalid syntax for template '{}'", name))
+    }
+    
     /// Validate template syntax
-    fn validate_template_syntax(&self, html_source: &str) -> Result<()> {
+    fn validate_template_syntax(&self, html_source: &str, syntax: &TemplateSyntax) -> Result<()> {
         // Create a temporary environment for validation
         let mut temp_env = Environment::new();
+        if !syntax.is_default() {
+            temp_env.set_syntax(syntax.syntax_config()?);
+        }
         
         // Try to parse the template to check for syntax errors
         match temp_env.add_template("__validation__", html_source) {
@@ -506,10 +908,7 @@ impl TemplateEngine {
     
     /// Clear cache for tenant
     fn clear_cache_for_tenant(&self, tenant_id: Uuid) {
-        if let Ok(mut cache) = self.template_cache.write() {
-            let tenant_prefix = format!("{}:", tenant_id);
-            cache.retain(|key, _| !key.starts_with(&tenant_prefix));
-        }
+        self.template_cache.clear_tenant(tenant_id);
     }
     
     /// Convert database row to Template struct
@@ -531,39 +930,251 @@ impl TemplateEngine {
     }
 }
 
+/// Order in which fallback templates are tried when `requested` is missing
+fn fallback_candidates<'a>(requested: &str, tenant_fallback: Option<&'a str>, platform_default: &'a str) -> Vec<&'a str> {
+    let mut candidates = Vec::new();
+    for candidate in tenant_fallback.into_iter().chain(std::iter::once(platform_default)) {
+        if !candidate.is_empty() && candidate != requested && !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+/// Render template source with the given context
+fn render_source(
+    template_name: &str,
+    template_source: String,
+    escape: AutoEscape,
+    syntax: &TemplateSyntax,
+    context: &TemplateContext,
+    data: Arc<TemplateData>,
+) -> Result<String> {
+    // Create a new environment for this render to avoid lifetime issues
+    let mut env = Environment::new();
+    if !syntax.is_default() {
+        env.set_syntax(syntax.syntax_config()?);
+    }
+    
+    // Escaping follows the template's category; user values are escaped unless marked safe
+    env.set_auto_escape_callback(move |_| escape);
+    register_helpers(&mut env);
+    
+    // t(key): the key's text in the render locale, then the site's default locale, then the key
+    let translations = Arc::new(context.translations.clone());
+    let default_locale = locale::default_locale(context.site.locale.as_deref(), context.site.default_locale.as_deref());
+    env.add_function("t", move |state: &State, key: String| -> Result<String, minijinja::Error> {
+        Ok(resolve_translation(&translations, &key, &render_locale(state), &default_locale).to_string())
+    });
+    // recent_content(limit) and site_setting(key), from data loaded for this render
+    data.register(&mut env);
+    
+    // Add the template using add_template_owned to avoid lifetime issues
+    env.add_template_owned(template_name.to_string(), template_source)
+        .context("Failed to add template to environment")?;
+    
+    // Get template and render
+    let template = env.get_template(template_name)
+        .context("Failed to get template from environment")?;
+    
+    let rendered = template.render(context! {
+        site => context.site,
+        page => context.page,
+        puck_data => context.puck_data,
+        puck_content => context.puck_content,
+        user => context.user,
+        navigation => context.navigation,
+        content => context.content,
+        trusted => context.trusted,
+        locale => context.locale,
+    }).context("Failed to render template")?;
+    
+    Ok(rendered)
+}
+
+/// Register the custom filters and functions available to every template
+fn register_helpers(env: &mut Environment<'static>) {
+    env.add_filter("markdown", markdown_filter);
+    env.add_filter("truncate", truncate_filter);
+    env.add_filter("date", date_filter);
+    env.add_filter("number_format", number_format_filter);
+    env.add_filter("currency", currency_filter);
+    env.add_filter("default", default_filter);
+    env.add_filter("safe", safe_filter);
+    env.add_function("asset_url", asset_url_function);
+    env.add_function("url", url_function);
+}
+
+/// Escape text for an HTML body or quoted attribute
+fn escape_html(value: &str) -> String {
+    HtmlEscape(value).to_string()
+}
+
+/// Make JSON safe to embed in a `<script>` block
+fn escape_script_json(json: &str) -> String {
+    json.replace('<', "\\u003c").replace('>', "\\u003e").replace('&', "\\u0026")
+}
+
 // Custom MiniJinja filters
-fn markdown_filter(value: String) -> Result<String, minijinja::Error> {
-    // Simple markdown to HTML conversion (in production, use a proper markdown parser)
-    let html = value
+fn markdown_filter(value: String) -> Result<TemplateValue, minijinja::Error> {
+    // Simple markdown to HTML conversion (in production, use a proper markdown parser).
+    // The input is escaped first so only the markup generated here is trusted.
+    let html = escape_html(&value)
         .replace("\n\n", "</p><p>")
         .replace("**", "<strong>")
         .replace("*", "<em>");
-    Ok(format!("<p>{}</p>", html))
+    Ok(TemplateValue::from_safe_string(format!("<p>{}</p>", html)))
 }
 
-fn truncate_filter(value: String, length: usize) -> Result<String, minijinja::Error> {
-    if value.len() <= length {
-        Ok(value)
+/// `value|default("N/A")`: the fallback (an empty string if none is given) when the value
+/// is undefined, none or empty, the value itself otherwise
+fn default_filter(value: TemplateValue, fallback: Option<TemplateValue>) -> TemplateValue {
+    let empty = value.is_undefined() || value.is_none() || value.len() == Some(0);
+    if empty {
+        fallback.unwrap_or_else(|| TemplateValue::from(""))
     } else {
-        Ok(format!("{}...", &value[..length]))
+        value
+    }
+}
+
+/// `value|safe`: output a string without HTML escaping.
+///
+/// Security: this turns off the XSS protection auto-escaping gives. Use it only on HTML
+/// the template author controls (theme snippets, `escape`d or `markdown` output), never on
+/// page fields, content or anything else a site visitor or tenant user can set. Content
+/// bodies are already safe as `content.body`, and other sanitized HTML can be passed
+/// in `trusted`.
+fn safe_filter(value: TemplateValue) -> Result<TemplateValue, minijinja::Error> {
+    if value.is_safe() || value.is_undefined() {
+        return Ok(value);
+    }
+    match value.as_str() {
+        Some(html) => Ok(TemplateValue::from_safe_string(html.to_string())),
+        None => Err(minijinja::Error::new(
+            minijinja::ErrorKind::InvalidOperation,
+            "safe can only be applied to strings",
+        )),
     }
 }
 
-fn date_filter(value: String, format: Option<String>) -> Result<String, minijinja::Error> {
+fn truncate_filter(value: String, length: usize) -> Result<String, minijinja::Error> {
+    match truncate_at_boundary(&value, length) {
+        Some(truncated) => Ok(format!("{}...", truncated)),
+        None => Ok(value),
+    }
+}
+
+/// Cut `value` to at most `length` user-perceived characters, or `None` if it already fits.
+///
+/// Cuts only between graphemes, so multibyte characters and emoji are never split, and
+/// backs up to the previous whitespace rather than cutting mid-word when that keeps at
+/// least half of the allowed length.
+pub fn truncate_at_boundary(value: &str, length: usize) -> Option<&str> {
+    let (cut, next) = value.grapheme_indices(true).nth(length)?;
+    let prefix = &value[..cut];
+
+    if !next.chars().all(char::is_whitespace) {
+        if let Some((word_end, _)) = prefix.grapheme_indices(true).rev().find(|(_, g)| g.chars().all(char::is_whitespace)) {
+            if prefix[..word_end].graphemes(true).count() * 2 >= length {
+                return Some(prefix[..word_end].trim_end());
+            }
+        }
+    }
+
+    Some(prefix.trim_end())
+}
+
+/// Locale of the current render, from the `locale` context value
+fn render_locale(state: &State) -> String {
+    state
+        .lookup("locale")
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+/// Format a number for the render locale: `{{ sales|number_format(2) }}`
+fn number_format_filter(state: &State, value: f64, decimals: Option<usize>) -> Result<String, minijinja::Error> {
+    Ok(locale::format_number(value, decimals.unwrap_or(0), &render_locale(state)))
+}
+
+/// Format money for the render locale: `{{ price|currency("EUR") }}`
+fn currency_filter(state: &State, value: f64, currency_code: Option<String>) -> Result<String, minijinja::Error> {
+    let currency_code = currency_code.as_deref().unwrap_or("USD");
+    Ok(locale::format_currency(value, currency_code, &render_locale(state)))
+}
+
+/// Format a date: `{{ value|date("%d %B %Y", "Europe/London") }}`.
+///
+/// Accepts RFC 3339, RFC 2822, `%Y-%m-%d`, Unix epoch seconds or milliseconds
+/// (as numbers or numeric strings) and `"now"`. The optional timezone is an IANA
+/// name; without it dates are shown in UTC. Month and weekday names follow the
+/// render locale. Unparseable input is an error rather than passed through, so
+/// template authors notice.
+fn date_filter(
+    state: &State,
+    value: TemplateValue,
+    format: Option<String>,
+    timezone: Option<String>,
+) -> Result<String, minijinja::Error> {
     let format_str = format.as_deref().unwrap_or("%Y-%m-%d");
-    
-    if value == "now" {
-        let now = chrono::Utc::now();
-        Ok(now.format(format_str).to_string())
-    } else {
-        // Try to parse the date string
-        match chrono::DateTime::parse_from_rfc3339(&value) {
-            Ok(dt) => Ok(dt.format(format_str).to_string()),
-            Err(_) => Ok(value), // Return original if parsing fails
+    if chrono::format::StrftimeItems::new(format_str).any(|item| matches!(item, chrono::format::Item::Error)) {
+        return Err(template_error(format!("invalid date format '{}'", format_str)));
+    }
+
+    let timestamp = parse_date_value(&value)
+        .ok_or_else(|| template_error(format!("cannot parse '{}' as a date", value)))?;
+
+    let date_locale = locale::date_locale(&render_locale(state));
+    match timezone {
+        Some(name) => {
+            let tz: chrono_tz::Tz = name
+                .parse()
+                .map_err(|_| template_error(format!("unknown timezone '{}'", name)))?;
+            Ok(timestamp.with_timezone(&tz).format_localized(format_str, date_locale).to_string())
         }
+        None => Ok(timestamp.format_localized(format_str, date_locale).to_string()),
+    }
+}
+
+/// Epoch values at or above this are taken as milliseconds (seconds would be past year 5000)
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+fn parse_date_value(value: &TemplateValue) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(epoch) = i64::try_from(value.clone()) {
+        return from_epoch(epoch);
+    }
+
+    let text = value.as_str()?.trim();
+    if text == "now" {
+        return Some(chrono::Utc::now());
+    }
+    if let Ok(epoch) = text.parse::<i64>() {
+        return from_epoch(epoch);
+    }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Some(dt.with_timezone(&chrono::Utc));
+    }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc2822(text) {
+        return Some(dt.with_timezone(&chrono::Utc));
+    }
+    chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|naive| naive.and_utc())
+}
+
+fn from_epoch(epoch: i64) -> Option<chrono::DateTime<chrono::Utc>> {
+    if epoch.abs() >= EPOCH_MILLIS_THRESHOLD {
+        chrono::DateTime::from_timestamp_millis(epoch)
+    } else {
+        chrono::DateTime::from_timestamp(epoch, 0)
     }
 }
 
+fn template_error(message: String) -> minijinja::Error {
+    minijinja::Error::new(minijinja::ErrorKind::InvalidOperation, message)
+}
+
 // Custom MiniJinja functions
 fn asset_url_function(path: String) -> Result<String, minijinja::Error> {
     // In production, this would use your CDN URL
@@ -578,3 +1189,451 @@ fn url_function(path: String) -> Result<String, minijinja::Error> {
         Ok(format!("/{}", path))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_context() -> TemplateContext {
+        TemplateContext {
+            site: SiteContext {
+                id: Uuid::new_v4(),
+                name: "Jane Austen".to_string(),
+                description: None,
+                subdomain: "jane".to_string(),
+                custom_domain: None,
+                seo_settings: serde_json::json!({}),
+                locale: None,
+                default_locale: None,
+                theme: serde_json::json!({}),
+            },
+            page: PageContext {
+                id: Uuid::new_v4(),
+                slug: "about".to_string(),
+                title: "About <me>".to_string(),
+                meta_description: Some("Author of Emma".to_string()),
+                meta_keywords: None,
+                is_published: true,
+                published_at: None,
+            },
+            puck_data: None,
+            puck_content: String::new(),
+            user: None,
+            navigation: vec![
+                NavigationItem { title: "Home".to_string(), slug: "home".to_string(), sort_order: 0 },
+                NavigationItem { title: "About <me>".to_string(), slug: "about".to_string(), sort_order: 1 },
+                NavigationItem { title: "Books".to_string(), slug: "books".to_string(), sort_order: 2 },
+            ],
+            content: None,
+            trusted: BTreeMap::new(),
+            locale: DEFAULT_LOCALE.to_string(),
+            translations: Translations::new(),
+        }
+    }
+
+    #[test]
+    fn test_fallback_candidates_order() {
+        assert_eq!(fallback_candidates("deleted", Some("tenant-base"), "puck-base"), vec!["tenant-base", "puck-base"]);
+        assert_eq!(fallback_candidates("deleted", None, "puck-base"), vec!["puck-base"]);
+        // The missing template itself and duplicates are never retried
+        assert_eq!(fallback_candidates("puck-base", Some("puck-base"), "puck-base"), Vec::<&str>::new());
+        assert_eq!(fallback_candidates("deleted", Some(""), "puck-base"), vec!["puck-base"]);
+    }
+
+    #[test]
+    fn test_deleted_template_renders_builtin_fallback() {
+        let rendered = render_source(
+            BUILTIN_FALLBACK_TEMPLATE_NAME,
+            BUILTIN_FALLBACK_TEMPLATE.to_string(),
+            AutoEscape::Html,
+            &TemplateSyntax::default(),
+            &test_context(),
+            Arc::default(),
+        ).expect("Built-in fallback failed to render");
+
+        assert!(rendered.contains("<title>About &lt;me&gt; | Jane Austen</title>"));
+        assert!(rendered.contains(r#"<meta name="description" content="Author of Emma">"#));
+    }
+
+    #[test]
+    fn test_script_in_page_content_is_escaped() {
+        let mut context = test_context();
+        context.puck_content = "<script>alert('xss')</script>".to_string();
+
+        let rendered = render_source(
+            "puck-base",
+            "<main>{{ puck_content }}</main>".to_string(),
+            auto_escape_for_category("page"),
+            &TemplateSyntax::default(),
+            &context,
+            Arc::default(),
+        ).expect("Template failed to render");
+
+        assert!(!rendered.contains("<script>"));
+        assert!(rendered.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn test_navigation_lists_published_pages_in_order() {
+        let rendered = render_source(
+            "nav",
+            r#"{% for item in navigation %}<a href="/{{ item.slug }}">{{ item.title }}</a>{% endfor %}"#.to_string(),
+            AutoEscape::Html,
+            &TemplateSyntax::default(),
+            &test_context(),
+            Arc::default(),
+        ).expect("Template failed to render");
+
+        assert_eq!(
+            rendered,
+            r#"<a href="/home">Home</a><a href="/about">About &lt;me&gt;</a><a href="/books">Books</a>"#
+        );
+    }
+
+    #[test]
+    fn test_content_context_exposes_authors_and_related_posts() {
+        let author = |first: &str, last: &str, position: i32| ContentAuthor {
+            user_id: Uuid::new_v4(),
+            first_name: first.to_string(),
+            last_name: last.to_string(),
+            position,
+            is_primary: position == 0,
+        };
+        let now = chrono::Utc::now();
+        let content = crate::types::Content {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            title: "Letters".to_string(),
+            slug: "letters".to_string(),
+            body: String::new(),
+            status: crate::types::ContentStatus::Published,
+            author_id: Uuid::new_v4(),
+            published_at: Some(now),
+            scheduled_publish_at: None,
+            locale: DEFAULT_LOCALE.to_string(),
+            translation_group_id: Uuid::new_v4(),
+            tags: vec!["regency".to_string()],
+            review_decision: None,
+            reviewed_by: None,
+            reviewed_at: None,
+            review_comment: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let mut context = test_context();
+        context.content = Some(ContentContext::new(
+            &content,
+            vec![author("Jane", "Austen", 0), author("Anne", "Brontë", 1), author("Mary", "Shelley", 2)],
+        ));
+
+        let rendered = render_source(
+            "post",
+            "{% for author in content.authors %}[{{ author.first_name }}{% if author.is_primary %}*{% endif %}]{% endfor %} by {{ content.byline }}".to_string(),
+            AutoEscape::Html,
+            &TemplateSyntax::default(),
+            &context,
+            Arc::default(),
+        ).expect("Template failed to render");
+
+        assert_eq!(rendered, "[Jane*][Anne][Mary] by Jane Austen, Anne Brontë and Mary Shelley");
+        assert_eq!(byline(&[author("Jane", "Austen", 0), author("Anne", "Brontë", 1)]), "Jane Austen and Anne Brontë");
+
+        let sequel = crate::types::Content {
+            id: Uuid::new_v4(),
+            title: "More <letters>".to_string(),
+            slug: "more-letters".to_string(),
+            ..content.clone()
+        };
+        context.content = context.content.map(|post| post.with_related(&[RelatedContent { content: sequel, score: 1.0 }]));
+        let rendered = render_source(
+            "post",
+            r#"{{ content.tags | join(", ") }}: {% for post in content.related %}<a href="/{{ post.slug }}">{{ post.title }}</a>{% endfor %}"#.to_string(),
+            AutoEscape::Html,
+            &TemplateSyntax::default(),
+            &context,
+            Arc::default(),
+        ).expect("Template failed to render");
+        assert_eq!(rendered, r#"regency: <a href="/more-letters">More &lt;letters&gt;</a>"#);
+    }
+
+    #[test]
+    fn test_custom_delimiters_leave_vue_syntax_alone() {
+        let syntax = TemplateSyntax::resolve(
+            &serde_json::json!({ "block": ["<%", "%>"], "variable": ["[[", "]]"], "comment": ["<#", "#>"] }),
+            "landing",
+        )
+        .unwrap();
+        let source = r#"<# imported #><div id="app"><% if site.name %><h1>[[ site.name ]]</h1><% endif %><p>{{ message }}</p></div>"#;
+
+        let rendered = render_source("landing", source.to_string(), AutoEscape::Html, &syntax, &test_context(), Arc::default())
+            .expect("Template failed to render");
+        assert_eq!(rendered, r#"<div id="app"><h1>Jane Austen</h1><p>{{ message }}</p></div>"#);
+
+        // The same source with the default delimiters reads the Vue binding as its own
+        assert!(render_source("landing", source.to_string(), AutoEscape::Html, &TemplateSyntax::default(), &test_context(), Arc::default())
+            .is_ok_and(|rendered| !rendered.contains("{{ message }}")));
+    }
+
+    #[tokio::test]
+    async fn test_dev_templates_reload_on_change_without_database() {
+        let dir = std::env::temp_dir().join(format!("quillspace-dev-templates-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("themes")).unwrap();
+        let path = dir.join("themes/classic.html");
+        std::fs::write(&path, "<h1>{{ site.name }}</h1>").unwrap();
+
+        // The database is never connected to, so every render comes from the file
+        let engine = TemplateEngine::new(Arc::new(DatabaseConnections::unconnected()))
+            .unwrap()
+            .with_dev_templates(&dir);
+        let tenant_id = Uuid::new_v4();
+        let rendered = engine.render_template("themes/classic", tenant_id, &test_context()).await.unwrap();
+        assert_eq!(rendered, "<h1>Jane Austen</h1>");
+
+        std::fs::write(&path, "<h2>{{ site.name }}: {{ page.slug }}</h2>").unwrap();
+        let rendered = engine.render_template("themes/classic", tenant_id, &test_context()).await.unwrap();
+        assert_eq!(rendered, "<h2>Jane Austen: about</h2>");
+        assert_eq!(engine.template_cache_stats().entries, 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_navigation_items_carry_only_menu_fields() {
+        let context = serde_json::to_value(test_context()).expect("Failed to serialize context");
+
+        assert_eq!(context["navigation"].as_array().map(Vec::len), Some(3));
+        assert_eq!(context["navigation"][1], serde_json::json!({ "title": "About <me>", "slug": "about", "sort_order": 1 }));
+    }
+
+    #[test]
+    fn test_text_templates_opt_out_of_escaping() {
+        let rendered = render_source(
+            "welcome-email",
+            "Hello {{ page.title }}".to_string(),
+            auto_escape_for_category("email_text"),
+            &TemplateSyntax::default(),
+            &test_context(),
+            Arc::default(),
+        ).expect("Template failed to render");
+
+        assert_eq!(rendered, "Hello About <me>");
+    }
+
+    #[test]
+    fn test_static_html_escapes_page_fields() {
+        let mut context = test_context();
+        context.page.title = "</title><script>alert(1)</script>".to_string();
+        let puck_data = serde_json::json!({ "text": "</script><script>alert(2)</script>" });
+
+        let escaped_title = escape_html(&context.page.title);
+        assert!(!escaped_title.contains('<'));
+        assert!(!escape_script_json(&puck_data.to_string()).contains("</script>"));
+    }
+
+    #[test]
+    fn test_default_filter_replaces_undefined_and_empty() {
+        let rendered = render_source(
+            "defaults",
+            r#"{{ missing|default("TBD") }}|{{ page.meta_keywords|default("TBD") }}|{{ ""|default("TBD") }}|{{ []|default("none")|join }}|{{ page.slug|default("TBD") }}|{{ missing|default }}|{{ 0|default(5) }}"#.to_string(),
+            auto_escape_for_category("page"),
+            &TemplateSyntax::default(),
+            &test_context(),
+            Arc::default(),
+        ).expect("Template failed to render");
+
+        // Autoescaping applies to defaults too, so they avoid characters it rewrites
+        assert_eq!(rendered, "TBD|TBD|TBD|none|about||0");
+    }
+
+    #[test]
+    fn test_safe_filter_prevents_double_escaping() {
+        let mut context = test_context();
+        context.puck_content = "<em>Emma</em> &amp; Persuasion".to_string();
+        let source = "{{ puck_content|safe }}|{{ page.title|escape|safe }}|{{ page.title }}".to_string();
+
+        let rendered = render_source("trusted", source.clone(), auto_escape_for_category("page"), &TemplateSyntax::default(), &context, Arc::default())
+            .expect("Template failed to render");
+        assert_eq!(rendered, "<em>Emma</em> &amp; Persuasion|About &lt;me&gt;|About &lt;me&gt;");
+
+        // Without auto-escaping `safe` changes nothing
+        let rendered = render_source("trusted", "{{ page.title|safe }}".to_string(), auto_escape_for_category("text"), &TemplateSyntax::default(), &context, Arc::default())
+            .expect("Template failed to render");
+        assert_eq!(rendered, "About <me>");
+
+        assert!(render_source("trusted", "{{ navigation|safe }}".to_string(), AutoEscape::Html, &TemplateSyntax::default(), &context, Arc::default()).is_err());
+    }
+
+    #[test]
+    fn test_sanitized_html_renders_unescaped() {
+        let mut context = test_context();
+        context.content = Some(ContentContext {
+            id: Uuid::new_v4(),
+            title: "Emma <draft>".to_string(),
+            slug: "emma".to_string(),
+            body: SanitizedHtml::new("<p><em>Emma</em> &amp; Persuasion<script>alert(1)</script></p>"),
+            published_at: None,
+            authors: Vec::new(),
+            byline: String::new(),
+            tags: Vec::new(),
+            related: Vec::new(),
+        });
+        context.trusted.insert("sidebar".to_string(), SanitizedHtml::new("<a href=\"javascript:x\" onclick=\"x\">Books</a>"));
+        let source = "{{ content.body }}|{{ trusted.sidebar }}|{{ content.title }}|{{ page.title }}".to_string();
+
+        let rendered = render_source("trusted", source, AutoEscape::Html, &TemplateSyntax::default(), &context, Arc::default())
+            .expect("Template failed to render");
+        assert_eq!(rendered, "<p><em>Emma</em> &amp; Persuasion</p>|<a>Books</a>|Emma &lt;draft&gt;|About &lt;me&gt;");
+
+        // Outside templates it is the plain string
+        assert_eq!(serde_json::to_value(SanitizedHtml::new("<b>x</b>")).unwrap(), serde_json::json!("<b>x</b>"));
+    }
+
+    #[test]
+    fn test_truncate_multibyte_boundary() {
+        // Cut falls inside/next to multibyte characters and a family emoji cluster
+        for (value, length) in [
+            ("café crème brûlée", 4),
+            ("naïve", 3),
+            ("👨‍👩‍👧 family", 1),
+            ("日本語のテキスト", 5),
+            ("Zoë🎉", 3),
+        ] {
+            let rendered = truncate_filter(value.to_string(), length).expect("Truncate failed");
+            assert!(std::str::from_utf8(rendered.as_bytes()).is_ok());
+            assert!(value.starts_with(rendered.trim_end_matches("...")));
+        }
+
+        assert_eq!(truncate_filter("👨‍👩‍👧 family".to_string(), 1).unwrap(), "👨‍👩‍👧...");
+        assert_eq!(truncate_filter("Zoë🎉".to_string(), 3).unwrap(), "Zoë...");
+        assert_eq!(truncate_filter("naïve".to_string(), 5).unwrap(), "naïve");
+    }
+
+    #[test]
+    fn test_truncate_avoids_cutting_words() {
+        assert_eq!(truncate_filter("Pride and Prejudice".to_string(), 12).unwrap(), "Pride and...");
+        // A single long word is cut rather than dropped
+        assert_eq!(truncate_filter("Supercalifragilistic".to_string(), 5).unwrap(), "Super...");
+        assert_eq!(truncate_at_boundary("short", 10), None);
+    }
+
+    /// Render a one-line template with the given locale
+    fn render_with_locale(source: &str, locale: &str) -> Result<String> {
+        let mut context = test_context();
+        context.locale = locale.to_string();
+        render_source("inline", source.to_string(), AutoEscape::None, &TemplateSyntax::default(), &context, Arc::default())
+    }
+
+    fn format_date(value: &str, format: &str, timezone: Option<&str>) -> Result<String> {
+        let timezone = timezone.map(|tz| format!(", {:?}", tz)).unwrap_or_default();
+        render_with_locale(&format!("{{{{ {}|date({:?}{}) }}}}", value, format, timezone), DEFAULT_LOCALE)
+    }
+
+    #[test]
+    fn test_date_filter_input_formats() {
+        let expected = "2024-03-05 14:30";
+        for value in [
+            r#""2024-03-05T14:30:00Z""#,
+            r#""2024-03-05T15:30:00+01:00""#,
+            r#""Tue, 05 Mar 2024 14:30:00 +0000""#,
+            "1709649000",
+            "1709649000000",
+            r#""1709649000""#,
+        ] {
+            assert_eq!(format_date(value, "%Y-%m-%d %H:%M", None).unwrap(), expected);
+        }
+
+        assert_eq!(format_date(r#""2024-03-05""#, "%d %B %Y", None).unwrap(), "05 March 2024");
+    }
+
+    #[test]
+    fn test_date_filter_timezone_conversion() {
+        let value = r#""2024-07-01T23:30:00Z""#;
+        assert_eq!(format_date(value, "%Y-%m-%d %H:%M", Some("Europe/Berlin")).unwrap(), "2024-07-02 01:30");
+        assert_eq!(format_date(value, "%H:%M %Z", Some("America/New_York")).unwrap(), "19:30 EDT");
+    }
+
+    #[test]
+    fn test_date_filter_rejects_unparseable_input() {
+        assert!(format_date(r#""next tuesday""#, "%Y", None).is_err());
+        assert!(format_date(r#""2024-03-05""#, "%Y", Some("Mars/Olympus")).is_err());
+        assert!(format_date(r#""2024-03-05""#, "%Q", None).is_err());
+    }
+
+    #[test]
+    fn test_locale_filters_differ_by_locale() {
+        let source = r#"{{ 1234567.891|number_format(2) }} | {{ 1234.5|currency("EUR") }} | {{ "2024-03-05"|date("%d %B %Y") }}"#;
+
+        assert_eq!(
+            render_with_locale(source, "en-US").unwrap(),
+            "1,234,567.89 | €1,234.50 | 05 March 2024"
+        );
+        assert_eq!(
+            render_with_locale(source, "de-DE").unwrap(),
+            "1.234.567,89 | 1.234,50\u{a0}€ | 05 März 2024"
+        );
+    }
+
+    #[test]
+    fn test_t_resolves_render_locale_then_default() {
+        let mut context = test_context();
+        context.site.default_locale = Some("en-US".to_string());
+        context.translations.insert(
+            "welcome".to_string(),
+            HashMap::from([
+                ("en-US".to_string(), "Welcome".to_string()),
+                ("de-DE".to_string(), "Willkommen".to_string()),
+            ]),
+        );
+        let source = r#"{{ t("welcome") }} {{ t("missing") }}"#;
+
+        for (locale, expected) in [("de-DE", "Willkommen missing"), ("fr-FR", "Welcome missing")] {
+            context.locale = locale.to_string();
+            let rendered = render_source("inline", source.to_string(), AutoEscape::Html, &TemplateSyntax::default(), &context, Arc::default()).unwrap();
+            assert_eq!(rendered, expected);
+        }
+    }
+
+    /// Needs `QUILLSPACE_TEST_DATABASE_URL` with the full schema, connecting as a role
+    /// that may add and remove a public template
+    #[tokio::test]
+    async fn test_warmed_templates_render_from_cache() {
+        let Ok(url) = std::env::var("QUILLSPACE_TEST_DATABASE_URL") else {
+            return;
+        };
+        let pool = crate::database::postgres::create_pool(&url, &Default::default())
+            .await
+            .expect("Failed to connect to test database");
+        let name = format!("warm-test-{}", Uuid::new_v4().simple());
+        pool.get()
+            .await
+            .expect("Failed to get connection")
+            .execute(
+                "INSERT INTO templates (name, category, html_source, default_schema, is_public)
+                 VALUES ($1, 'page', '<h1>{{ site.name }}</h1>', '{}', true)",
+                &[&name],
+            )
+            .await
+            .expect("Failed to add template");
+
+        let engine = TemplateEngine::new(Arc::new(DatabaseConnections::with_postgres(pool.clone())))
+            .expect("Failed to build template engine");
+        let tenant_id = Uuid::new_v4();
+        assert_eq!(engine.warm_templates(tenant_id, &[&name, "no-such-template"]).await.unwrap(), 1);
+
+        let cached = engine.template_cache_keys(Some(tenant_id));
+        assert!(cached.iter().any(|entry| entry.templates.contains(&name)));
+
+        let before = engine.template_cache_stats();
+        let rendered = engine.render_template(&name, tenant_id, &test_context()).await.unwrap();
+        let after = engine.template_cache_stats();
+        assert!(rendered.starts_with("<h1>"));
+        assert_eq!((after.hits - before.hits, after.misses - before.misses), (1, 0));
+
+        pool.get()
+            .await
+            .expect("Failed to get connection")
+            .execute("DELETE FROM templates WHERE name = $1", &[&name])
+            .await
+            .expect("Failed to remove template");
+    }
+}
@@ -0,0 +1,354 @@
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::services::template_engine::Template;
+
+/// Puck field types a template may declare for a component prop
+const FIELD_TYPES: &[&str] = &["text", "textarea", "number", "select", "radio", "array", "object"];
+
+/// Errors from reading a template's `default_schema`
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateSchemaError {
+    #[error("Template not found")]
+    NotFound,
+
+    #[error("Invalid default_schema: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+}
+
+/// Check a template's `default_schema` against the editor contract:
+///
+/// ```json
+/// {
+///   "components": { "HeroBlock": { "label": "Hero", "fields": { "title": { "type": "text" } } } },
+///   "root": { "fields": { ... }, "props": { ... } },
+///   "content": [ { "type": "HeroBlock", "props": { "title": "Your Book Title" } } ]
+/// }
+/// ```
+///
+/// Every key is optional. Fields are Puck fields: `select`/`radio` need `options`,
+/// `array` needs `arrayFields` and `object` needs `objectFields`. Default content
+/// may only use declared components once any are declared.
+pub fn validate_default_schema(schema: &Value) -> Result<(), TemplateSchemaError> {
+    let mut errors = Vec::new();
+    let Some(schema) = schema.as_object() else {
+        return Err(TemplateSchemaError::Invalid(vec!["must be an object".to_string()]));
+    };
+
+    let components = match schema.get("components") {
+        None => None,
+        Some(Value::Object(components)) => Some(components),
+        Some(_) => {
+            errors.push("components: must be an object".to_string());
+            None
+        }
+    };
+    for (name, component) in components.into_iter().flatten() {
+        let path = format!("components.{}", name);
+        match component.as_object() {
+            Some(component) => {
+                check_optional_string(component, "label", &path, &mut errors);
+                check_fields(component.get("fields"), &format!("{}.fields", path), &mut errors);
+            }
+            None => errors.push(format!("{}: must be an object", path)),
+        }
+    }
+
+    match schema.get("root") {
+        None => {}
+        Some(Value::Object(root)) => {
+            if root.contains_key("fields") {
+                check_fields(root.get("fields"), "root.fields", &mut errors);
+            }
+            if root.get("props").is_some_and(|props| !props.is_object()) {
+                errors.push("root.props: must be an object".to_string());
+            }
+        }
+        Some(_) => errors.push("root: must be an object".to_string()),
+    }
+
+    match schema.get("content") {
+        None => {}
+        Some(Value::Array(blocks)) => {
+            for (i, block) in blocks.iter().enumerate() {
+                let path = format!("content[{}]", i);
+                match block.get("type").and_then(Value::as_str) {
+                    Some(block_type) => {
+                        let declared = components.is_none_or(|c| c.is_empty() || c.contains_key(block_type));
+                        if !declared {
+                            errors.push(format!("{}.type: unknown component '{}'", path, block_type));
+                        }
+                    }
+                    None => errors.push(format!("{}.type: must be a string", path)),
+                }
+                if !block.get("props").is_some_and(Value::is_object) {
+                    errors.push(format!("{}.props: must be an object", path));
+                }
+            }
+        }
+        Some(_) => errors.push("content: must be an array".to_string()),
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(TemplateSchemaError::Invalid(errors))
+    }
+}
+
+/// JSON Schema (draft 2020-12) for the editor data of a template the tenant can see.
+///
+/// Describes the Puck data shape: `content` holds blocks of the declared
+/// components, each with its typed `props`, and `root.props` the page-level fields.
+pub fn template_json_schema(template: &Template, tenant_id: Uuid) -> Result<Value, TemplateSchemaError> {
+    if template.tenant_id.is_some_and(|owner| owner != tenant_id) && !template.is_public {
+        return Err(TemplateSchemaError::NotFound);
+    }
+    validate_default_schema(&template.default_schema)?;
+
+    let empty = Map::new();
+    let components = template
+        .default_schema
+        .get("components")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    let mut defs = Map::new();
+    for (name, component) in components {
+        let mut def = json!({
+            "type": "object",
+            "properties": {
+                "type": { "const": name },
+                "props": object_schema(component.get("fields")),
+            },
+            "required": ["type", "props"],
+        });
+        if let Some(label) = component.get("label") {
+            def["title"] = label.clone();
+        }
+        defs.insert(name.clone(), def);
+    }
+
+    let mut content = json!({ "type": "array" });
+    if !defs.is_empty() {
+        let refs: Vec<Value> = defs.keys().map(|name| json!({ "$ref": format!("#/$defs/{}", name) })).collect();
+        content["items"] = json!({ "anyOf": refs });
+    }
+    let root_fields = template.default_schema.get("root").and_then(|root| root.get("fields"));
+
+    Ok(json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": template.name,
+        "type": "object",
+        "$defs": defs,
+        "properties": {
+            "content": content,
+            "root": {
+                "type": "object",
+                "properties": { "props": object_schema(root_fields) },
+            },
+        },
+    }))
+}
+
+fn check_optional_string(object: &Map<String, Value>, key: &str, path: &str, errors: &mut Vec<String>) {
+    if object.get(key).is_some_and(|value| !value.is_string()) {
+        errors.push(format!("{}.{}: must be a string", path, key));
+    }
+}
+
+fn check_fields(fields: Option<&Value>, path: &str, errors: &mut Vec<String>) {
+    let Some(fields) = fields.and_then(Value::as_object) else {
+        errors.push(format!("{}: must be an object", path));
+        return;
+    };
+
+    for (name, field) in fields {
+        let path = format!("{}.{}", path, name);
+        let Some(field) = field.as_object() else {
+            errors.push(format!("{}: must be an object", path));
+            continue;
+        };
+        check_optional_string(field, "label", &path, errors);
+        if field.get("required").is_some_and(|required| !required.is_boolean()) {
+            errors.push(format!("{}.required: must be a boolean", path));
+        }
+
+        match field.get("type").and_then(Value::as_str) {
+            Some("select" | "radio") => {
+                let options = field.get("options").and_then(Value::as_array).filter(|o| !o.is_empty());
+                match options {
+                    Some(options) if options.iter().all(|o| option_value(o).is_some()) => {}
+                    Some(_) => errors.push(format!("{}.options: each option needs a value", path)),
+                    None => errors.push(format!("{}.options: must be a non-empty array", path)),
+                }
+            }
+            Some("array") => check_fields(field.get("arrayFields"), &format!("{}.arrayFields", path), errors),
+            Some("object") => check_fields(field.get("objectFields"), &format!("{}.objectFields", path), errors),
+            Some("number") => {
+                for bound in ["min", "max"] {
+                    if field.get(bound).is_some_and(|value| !value.is_number()) {
+                        errors.push(format!("{}.{}: must be a number", path, bound));
+                    }
+                }
+            }
+            Some(field_type) if FIELD_TYPES.contains(&field_type) => {}
+            Some(field_type) => errors.push(format!("{}.type: unknown field type '{}'", path, field_type)),
+            None => errors.push(format!("{}.type: must be a string", path)),
+        }
+    }
+}
+
+/// Options are either plain values or Puck's `{ "label": ..., "value": ... }`
+fn option_value(option: &Value) -> Option<&Value> {
+    match option {
+        Value::Object(option) => option.get("value"),
+        Value::Array(_) | Value::Null => None,
+        value => Some(value),
+    }
+}
+
+fn object_schema(fields: Option<&Value>) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for (name, field) in fields.and_then(Value::as_object).into_iter().flatten() {
+        if field.get("required").and_then(Value::as_bool).unwrap_or(false) {
+            required.push(Value::String(name.clone()));
+        }
+        properties.insert(name.clone(), field_schema(field));
+    }
+    json!({ "type": "object", "properties": properties, "required": required })
+}
+
+fn field_schema(field: &Value) -> Value {
+    let mut schema = match field.get("type").and_then(Value::as_str).unwrap_or_default() {
+        "number" => {
+            let mut schema = json!({ "type": "number" });
+            if let Some(min) = field.get("min") {
+                schema["minimum"] = min.clone();
+            }
+            if let Some(max) = field.get("max") {
+                schema["maximum"] = max.clone();
+            }
+            schema
+        }
+        "select" | "radio" => {
+            let options = field.get("options").and_then(Value::as_array).into_iter().flatten();
+            json!({ "enum": options.filter_map(option_value).cloned().collect::<Vec<_>>() })
+        }
+        "array" => json!({ "type": "array", "items": object_schema(field.get("arrayFields")) }),
+        "object" => object_schema(field.get("objectFields")),
+        _ => json!({ "type": "string" }),
+    };
+    if let Some(label) = field.get("label") {
+        schema["title"] = label.clone();
+    }
+    if let Some(default) = field.get("default") {
+        schema["default"] = default.clone();
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hero_schema() -> Value {
+        json!({
+            "components": {
+                "HeroBlock": {
+                    "label": "Hero",
+                    "fields": {
+                        "title": { "type": "text", "label": "Title", "required": true },
+                        "align": { "type": "radio", "options": [{ "label": "Left", "value": "left" }, "center"] },
+                        "links": { "type": "array", "arrayFields": { "href": { "type": "text" } } }
+                    }
+                }
+            },
+            "root": { "fields": { "title": { "type": "text" } } },
+            "content": [{ "type": "HeroBlock", "props": { "title": "Your Book Title" } }]
+        })
+    }
+
+    fn template(default_schema: Value, tenant_id: Uuid, is_public: bool) -> Template {
+        Template {
+            id: Uuid::new_v4(),
+            tenant_id: Some(tenant_id),
+            name: "literary-classic".to_string(),
+            description: None,
+            category: "author".to_string(),
+            html_source: "<html></html>".to_string(),
+            default_schema,
+            preview_image_url: None,
+            is_public,
+            version: 1,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn errors(schema: Value) -> Vec<String> {
+        match validate_default_schema(&schema) {
+            Err(TemplateSchemaError::Invalid(errors)) => errors,
+            other => panic!("expected invalid schema, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_valid_schema_accepted() {
+        assert!(validate_default_schema(&hero_schema()).is_ok());
+        // Templates created before the contract store an empty object
+        assert!(validate_default_schema(&json!({})).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_schema_rejected() {
+        assert_eq!(errors(json!([])), vec!["must be an object"]);
+        assert_eq!(
+            errors(json!({ "components": { "HeroBlock": { "fields": { "color": { "type": "color" } } } } })),
+            vec!["components.HeroBlock.fields.color.type: unknown field type 'color'"]
+        );
+        assert_eq!(
+            errors(json!({ "components": { "HeroBlock": { "fields": { "align": { "type": "select" } } } } })),
+            vec!["components.HeroBlock.fields.align.options: must be a non-empty array"]
+        );
+
+        let mut undeclared = hero_schema();
+        undeclared["content"] = json!([{ "type": "GalleryBlock", "props": {} }, { "props": [] }]);
+        assert_eq!(
+            errors(undeclared),
+            vec![
+                "content[0].type: unknown component 'GalleryBlock'",
+                "content[1].type: must be a string",
+                "content[1].props: must be an object",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_schema_describes_component_props() {
+        let tenant_id = Uuid::new_v4();
+        let schema = template_json_schema(&template(hero_schema(), tenant_id, false), tenant_id).unwrap();
+
+        let hero = &schema["$defs"]["HeroBlock"];
+        assert_eq!(hero["title"], "Hero");
+        assert_eq!(hero["properties"]["type"], json!({ "const": "HeroBlock" }));
+        let props = &hero["properties"]["props"];
+        assert_eq!(props["required"], json!(["title"]));
+        assert_eq!(props["properties"]["align"]["enum"], json!(["left", "center"]));
+        assert_eq!(props["properties"]["links"]["items"]["properties"]["href"], json!({ "type": "string" }));
+        assert_eq!(schema["properties"]["content"]["items"]["anyOf"], json!([{ "$ref": "#/$defs/HeroBlock" }]));
+    }
+
+    #[test]
+    fn test_schema_returned_for_public_templates() {
+        let owner = Uuid::new_v4();
+        let other_tenant = Uuid::new_v4();
+
+        let public = template(hero_schema(), owner, true);
+        assert!(template_json_schema(&public, other_tenant).is_ok());
+
+        let private = template(hero_schema(), owner, false);
+        assert!(matches!(template_json_schema(&private, other_tenant), Err(TemplateSchemaError::NotFound)));
+    }
+}
@@ -0,0 +1,148 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// Hit and miss counts since startup (or the last clear), with the current size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TemplateCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// Names of the templates cached for one tenant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TenantCacheEntries {
+    pub tenant_id: Uuid,
+    pub templates: Vec<String>,
+}
+
+/// Template sources cached per tenant and template name, with lookup counters
+/// so operators can tell whether a stale render came from the cache.
+#[derive(Debug)]
+pub struct TemplateSourceCache<T> {
+    entries: RwLock<HashMap<(Uuid, String), T>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<T> Default for TemplateSourceCache<T> {
+    fn default() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<T: Clone> TemplateSourceCache<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached value, counting a hit or a miss
+    pub fn get(&self, tenant_id: Uuid, name: &str) -> Option<T> {
+        let cached = self
+            .entries
+            .read()
+            .ok()
+            .and_then(|entries| entries.get(&(tenant_id, name.to_string())).cloned());
+        let counter = if cached.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached
+    }
+
+    pub fn insert(&self, tenant_id: Uuid, name: &str, value: T) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert((tenant_id, name.to_string()), value);
+        }
+    }
+
+    /// Drop the tenant's entries, returning how many there were
+    pub fn clear_tenant(&self, tenant_id: Uuid) -> usize {
+        let Ok(mut entries) = self.entries.write() else {
+            return 0;
+        };
+        let before = entries.len();
+        entries.retain(|(tenant, _), _| *tenant != tenant_id);
+        before - entries.len()
+    }
+
+    /// Drop every entry and reset the counters, returning how many entries there were
+    pub fn clear_all(&self) -> usize {
+        let removed = self.entries.write().map(|mut entries| entries.drain().count()).unwrap_or(0);
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        removed
+    }
+
+    /// Cached template names per tenant, sorted; only `tenant_id`'s when given
+    pub fn keys(&self, tenant_id: Option<Uuid>) -> Vec<TenantCacheEntries> {
+        let Ok(entries) = self.entries.read() else {
+            return Vec::new();
+        };
+        let mut by_tenant: BTreeMap<Uuid, Vec<String>> = BTreeMap::new();
+        for (tenant, name) in entries.keys() {
+            if tenant_id.is_none_or(|wanted| wanted == *tenant) {
+                by_tenant.entry(*tenant).or_default().push(name.clone());
+            }
+        }
+        by_tenant
+            .into_iter()
+            .map(|(tenant_id, mut templates)| {
+                templates.sort();
+                TenantCacheEntries { tenant_id, templates }
+            })
+            .collect()
+    }
+
+    pub fn stats(&self) -> TemplateCacheStats {
+        TemplateCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.read().map(|entries| entries.len()).unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clear_tenant_empties_only_that_tenant() {
+        let cache = TemplateSourceCache::new();
+        let (jane, emily) = (Uuid::new_v4(), Uuid::new_v4());
+        cache.insert(jane, "puck-base", "<main>".to_string());
+        cache.insert(jane, "blog", "<article>".to_string());
+        cache.insert(emily, "puck-base", "<main>".to_string());
+
+        assert_eq!(cache.keys(Some(jane))[0].templates, vec!["blog", "puck-base"]);
+
+        assert_eq!(cache.clear_tenant(jane), 2);
+        assert!(cache.keys(Some(jane)).is_empty());
+        assert_eq!(cache.get(jane, "puck-base"), None);
+        assert_eq!(cache.keys(None), vec![TenantCacheEntries { tenant_id: emily, templates: vec!["puck-base".to_string()] }]);
+
+        assert_eq!(cache.clear_all(), 1);
+        assert_eq!(cache.stats(), TemplateCacheStats { hits: 0, misses: 0, entries: 0 });
+    }
+
+    #[test]
+    fn test_hit_and_miss_counters() {
+        let cache = TemplateSourceCache::new();
+        let tenant_id = Uuid::new_v4();
+
+        assert_eq!(cache.get(tenant_id, "puck-base"), None);
+        cache.insert(tenant_id, "puck-base", "<main>".to_string());
+        assert_eq!(cache.get(tenant_id, "puck-base").as_deref(), Some("<main>"));
+        assert_eq!(cache.get(tenant_id, "puck-base").as_deref(), Some("<main>"));
+        // Another tenant's lookup of the same name is its own miss
+        assert_eq!(cache.get(Uuid::new_v4(), "puck-base"), None);
+
+        assert_eq!(cache.stats(), TemplateCacheStats { hits: 2, misses: 2, entries: 1 });
+    }
+}
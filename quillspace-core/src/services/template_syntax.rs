@@ -0,0 +1,216 @@
+//! Custom MiniJinja delimiters, for tenants whose templates come from systems where
+//! `{{ }}` or `{% %}` already mean something (Vue, Angular, Handlebars).
+//!
+//! Tenants configure them under `settings.template_syntax`, for all of their own
+//! templates and per template name:
+//!
+//! ```json
+//! {
+//!   "variable": ["[[", "]]"],
+//!   "templates": { "landing": { "block": ["<%", "%>"] } }
+//! }
+//! ```
+//!
+//! Delimiters left out keep the default, and a template's own entry overrides the
+//! tenant-wide one. Public templates always use the default syntax.
+
+use minijinja::syntax::SyntaxConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Key of the delimiter settings in tenant settings
+pub const TEMPLATE_SYNTAX_SETTING: &str = "template_syntax";
+
+const MAX_DELIMITER_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TemplateSyntaxError {
+    #[error("Invalid template syntax: {0}")]
+    Invalid(String),
+}
+
+/// Start and end of one kind of tag
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delimiters(pub String, pub String);
+
+impl Delimiters {
+    fn new(start: &str, end: &str) -> Self {
+        Self(start.to_string(), end.to_string())
+    }
+}
+
+/// The delimiters a template is parsed with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TemplateSyntax {
+    pub block: Delimiters,
+    pub variable: Delimiters,
+    pub comment: Delimiters,
+}
+
+impl Default for TemplateSyntax {
+    fn default() -> Self {
+        Self {
+            block: Delimiters::new("{%", "%}"),
+            variable: Delimiters::new("{{", "}}"),
+            comment: Delimiters::new("{#", "#}"),
+        }
+    }
+}
+
+/// Delimiters set at one level of the settings; the rest come from the level below
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SyntaxOverrides {
+    block: Option<Delimiters>,
+    variable: Option<Delimiters>,
+    comment: Option<Delimiters>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TemplateSyntaxSettings {
+    block: Option<Delimiters>,
+    variable: Option<Delimiters>,
+    comment: Option<Delimiters>,
+    templates: BTreeMap<String, SyntaxOverrides>,
+}
+
+impl TemplateSyntax {
+    /// The syntax of the tenant's template `name`, given the tenant's
+    /// `template_syntax` setting (`Null` when it has none)
+    pub fn resolve(setting: &serde_json::Value, name: &str) -> Result<Self, TemplateSyntaxError> {
+        if setting.is_null() {
+            return Ok(Self::default());
+        }
+        let settings: TemplateSyntaxSettings =
+            serde_json::from_value(setting.clone()).map_err(|e| TemplateSyntaxError::Invalid(e.to_string()))?;
+        let tenant_wide = SyntaxOverrides {
+            block: settings.block,
+            variable: settings.variable,
+            comment: settings.comment,
+        };
+        let syntax = Self::default().with(&tenant_wide);
+        let syntax = match settings.templates.get(name) {
+            Some(overrides) => syntax.with(overrides),
+            None => syntax,
+        };
+        syntax.validate()?;
+        Ok(syntax)
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check that every delimiter is short, without whitespace, and that no start
+    /// delimiter is a prefix of another, which would make tags ambiguous
+    pub fn validate(&self) -> Result<(), TemplateSyntaxError> {
+        let kinds = [("block", &self.block), ("variable", &self.variable), ("comment", &self.comment)];
+        for (kind, Delimiters(start, end)) in kinds {
+            for delimiter in [start, end] {
+                if delimiter.is_empty() || delimiter.chars().count() > MAX_DELIMITER_LEN {
+                    return Err(TemplateSyntaxError::Invalid(format!(
+                        "{} delimiters must be 1 to {} characters",
+                        kind, MAX_DELIMITER_LEN
+                    )));
+                }
+                if delimiter.chars().any(char::is_whitespace) {
+                    return Err(TemplateSyntaxError::Invalid(format!("{} delimiters can't contain whitespace", kind)));
+                }
+            }
+        }
+        for (i, (kind, Delimiters(start, _))) in kinds.iter().enumerate() {
+            for (other_kind, Delimiters(other_start, _)) in &kinds[i + 1..] {
+                if start.starts_with(other_start.as_str()) || other_start.starts_with(start.as_str()) {
+                    return Err(TemplateSyntaxError::Invalid(format!(
+                        "{} start '{}' overlaps {} start '{}'",
+                        kind, start, other_kind, other_start
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The MiniJinja syntax for these delimiters
+    pub fn syntax_config(&self) -> Result<SyntaxConfig, TemplateSyntaxError> {
+        self.validate()?;
+        SyntaxConfig::builder()
+            .block_delimiters(self.block.0.clone(), self.block.1.clone())
+            .variable_delimiters(self.variable.0.clone(), self.variable.1.clone())
+            .comment_delimiters(self.comment.0.clone(), self.comment.1.clone())
+            .build()
+            .map_err(|e| TemplateSyntaxError::Invalid(e.to_string()))
+    }
+
+    fn with(self, overrides: &SyntaxOverrides) -> Self {
+        Self {
+            block: overrides.block.clone().unwrap_or(self.block),
+            variable: overrides.variable.clone().unwrap_or(self.variable),
+            comment: overrides.comment.clone().unwrap_or(self.comment),
+        }
+    }
+}
+
+/// Reject a settings update whose `template_syntax` is malformed or, for the tenant
+/// or any template, overlapping
+pub fn validate_settings(settings: &serde_json::Value) -> Result<(), TemplateSyntaxError> {
+    let Some(setting) = settings.get(TEMPLATE_SYNTAX_SETTING) else {
+        return Ok(());
+    };
+    TemplateSyntax::resolve(setting, "")?;
+    let names: Vec<String> = setting
+        .get("templates")
+        .and_then(serde_json::Value::as_object)
+        .map(|templates| templates.keys().cloned().collect())
+        .unwrap_or_default();
+    for name in names {
+        TemplateSyntax::resolve(setting, &name)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_template_overrides_tenant_wide_delimiters() {
+        let setting = json!({
+            "variable": ["[[", "]]"],
+            "templates": { "landing": { "block": ["<%", "%>"], "variable": ["${", "}"] } }
+        });
+
+        let tenant_wide = TemplateSyntax::resolve(&setting, "about").unwrap();
+        assert_eq!(tenant_wide.variable, Delimiters::new("[[", "]]"));
+        assert_eq!(tenant_wide.block, Delimiters::new("{%", "%}"));
+
+        let landing = TemplateSyntax::resolve(&setting, "landing").unwrap();
+        assert_eq!(landing.block, Delimiters::new("<%", "%>"));
+        assert_eq!(landing.variable, Delimiters::new("${", "}"));
+        assert_eq!(landing.comment, Delimiters::new("{#", "#}"));
+
+        assert!(TemplateSyntax::resolve(&serde_json::Value::Null, "about").unwrap().is_default());
+    }
+
+    #[test]
+    fn test_overlapping_delimiters_rejected() {
+        for setting in [
+            json!({ "variable": ["{%", "%}"] }),
+            json!({ "variable": ["{", "}"] }),
+            json!({ "comment": ["{{#", "#}}"] }),
+            json!({ "block": ["", "%}"] }),
+            json!({ "block": ["{ %", "% }"] }),
+            json!({ "block": ["<%"] }),
+            json!({ "delimiters": {} }),
+            json!({ "templates": { "landing": { "block": ["[[", "]]"], "variable": ["[[", "]]"] } } }),
+        ] {
+            let settings = json!({ TEMPLATE_SYNTAX_SETTING: setting });
+            assert!(matches!(validate_settings(&settings), Err(TemplateSyntaxError::Invalid(_))), "{}", setting);
+        }
+
+        assert!(validate_settings(&json!({})).is_ok());
+        assert!(validate_settings(&json!({ TEMPLATE_SYNTAX_SETTING: { "variable": ["[[", "]]"] } })).is_ok());
+    }
+}
@@ -0,0 +1,469 @@
+use crate::services::notification::{enqueue_notification, USER_INVITED};
+use crate::services::public_url::absolute_url;
+use crate::services::site::SiteService;
+use crate::services::slug::SlugRules;
+use crate::services::subdomain::{SubdomainError, SubdomainGenerator};
+use crate::types::{TenantId, UserRole};
+use anyhow::Context;
+use chrono::{Duration, Utc};
+use deadpool_postgres::{GenericClient, Pool};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use tokio_postgres::error::SqlState;
+use uuid::Uuid;
+
+/// How long the first admin's invite stays valid
+pub const INVITE_TTL_DAYS: i64 = 7;
+
+/// Pages a starter site gets when the request names none
+pub const DEFAULT_PAGES: &[(&str, &str)] = &[
+    ("home", "Home"),
+    ("about", "About"),
+    ("books", "Books"),
+    ("contact", "Contact"),
+];
+
+const INVITE_TOKEN_LEN: usize = 48;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TenantBootstrapError {
+    #[error("Invalid bootstrap request: {0}")]
+    Invalid(String),
+
+    #[error("Template not found or not public")]
+    TemplateNotFound,
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// A page of the starter site
+#[derive(Debug, Clone, Deserialize)]
+pub struct StarterPage {
+    pub slug: String,
+    pub title: String,
+}
+
+/// Everything needed to make a tenant usable in one go
+#[derive(Debug, Clone, Deserialize)]
+pub struct BootstrapTenantRequest {
+    pub tenant_name: String,
+    pub tenant_slug: String,
+    pub admin_email: String,
+    pub admin_first_name: String,
+    pub admin_last_name: String,
+    /// Public template the starter site is built from
+    pub template_id: Uuid,
+    /// Defaults to the tenant name
+    pub site_name: Option<String>,
+    /// Generated from the site name when not given
+    pub subdomain: Option<String>,
+    /// Defaults to `DEFAULT_PAGES`
+    pub pages: Option<Vec<StarterPage>>,
+}
+
+impl BootstrapTenantRequest {
+    pub fn validate(&self) -> Result<(), TenantBootstrapError> {
+        let invalid = |reason: &str| Err(TenantBootstrapError::Invalid(reason.to_string()));
+
+        if self.tenant_name.trim().is_empty() || self.tenant_slug.trim().is_empty() {
+            return invalid("tenant_name and tenant_slug are required");
+        }
+        if !self.admin_email.contains('@') {
+            return invalid("admin_email is not an email address");
+        }
+        if self.admin_first_name.trim().is_empty() {
+            return invalid("admin_first_name is required");
+        }
+        if let Some(subdomain) = &self.subdomain {
+            SiteService::validate_subdomain(subdomain)
+                .map_err(|e| TenantBootstrapError::Invalid(e.to_string()))?;
+        }
+
+        let pages = self.starter_pages();
+        if pages.is_empty() {
+            return invalid("the starter site needs at least one page");
+        }
+        let mut slugs = HashSet::new();
+        for page in &pages {
+            if page.title.trim().is_empty() {
+                return invalid("every page needs a title");
+            }
+            // A new tenant has no settings yet, so only the system paths are reserved
+            let slug = SlugRules::default()
+                .page_slug(&page.slug)
+                .map_err(|e| TenantBootstrapError::Invalid(e.to_string()))?;
+            if !slugs.insert(slug) {
+                return Err(TenantBootstrapError::Invalid(format!("page slug '{}' is repeated", page.slug)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn starter_pages(&self) -> Vec<StarterPage> {
+        match &self.pages {
+            Some(pages) => pages.clone(),
+            None => DEFAULT_PAGES
+                .iter()
+                .map(|(slug, title)| StarterPage { slug: slug.to_string(), title: title.to_string() })
+                .collect(),
+        }
+    }
+
+    fn site_name(&self) -> &str {
+        self.site_name.as_deref().unwrap_or(&self.tenant_name)
+    }
+}
+
+/// Ids of everything a bootstrap created
+#[derive(Debug, Clone, Serialize)]
+pub struct BootstrappedTenant {
+    pub tenant_id: Uuid,
+    pub admin_user_id: Uuid,
+    pub invite_id: Uuid,
+    pub site_id: Uuid,
+    pub subdomain: String,
+    /// In the order the pages were requested
+    pub page_ids: Vec<Uuid>,
+}
+
+/// Starter Puck data for a page: the template's default, titled for the page
+pub fn starter_puck_data(default_schema: &serde_json::Value, title: &str) -> serde_json::Value {
+    let mut data = match default_schema {
+        serde_json::Value::Object(_) => default_schema.clone(),
+        _ => serde_json::json!({ "content": [] }),
+    };
+    data["root"]["props"]["title"] = serde_json::json!(title);
+    data
+}
+
+pub(crate) fn generate_invite_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(INVITE_TOKEN_LEN)
+        .map(char::from)
+        .collect()
+}
+
+/// The token is long and random, so a fast digest is sufficient for storage
+pub(crate) fn hash_invite_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn conflict_on_duplicate(e: tokio_postgres::Error, what: &str) -> TenantBootstrapError {
+    if e.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+        TenantBootstrapError::Conflict(format!("{} already exists", what))
+    } else {
+        TenantBootstrapError::Database(anyhow::Error::new(e).context(format!("Failed to create {}", what)))
+    }
+}
+
+/// Creates a tenant with its first admin, a starter site and default pages in one transaction
+pub struct TenantBootstrapService {
+    db: Pool,
+}
+
+impl TenantBootstrapService {
+    pub fn new(db: Pool) -> Self {
+        Self { db }
+    }
+
+    /// Create everything or nothing. The admin has no usable password until they
+    /// accept the invite queued for them, linked under `base_url`.
+    pub async fn bootstrap(
+        &self,
+        request: &BootstrapTenantRequest,
+        base_url: &str,
+    ) -> Result<BootstrappedTenant, TenantBootstrapError> {
+        request.validate()?;
+
+        let invite_token = generate_invite_token();
+        // Nobody knows this password, so the account cannot log in before the invite is
+        // accepted. bcrypt is deliberately slow, so it runs off the async workers.
+        let password_hash = tokio::task::spawn_blocking(|| bcrypt::hash(generate_invite_token(), bcrypt::DEFAULT_COST))
+            .await
+            .context("Password hashing task failed")?
+            .context("Failed to hash placeholder password")?;
+
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = client.transaction().await
+            .context("Failed to start transaction")?;
+
+        let invite_url = absolute_url(base_url, &format!("/invite/{}", invite_token));
+        let created = create_all(&transaction, request, &invite_token, &invite_url, &password_hash).await?;
+
+        transaction.commit().await
+            .context("Failed to commit tenant bootstrap")?;
+        Ok(created)
+    }
+}
+
+/// Every insert of a bootstrap; the caller's transaction makes them atomic
+async fn create_all(
+    client: &impl GenericClient,
+    request: &BootstrapTenantRequest,
+    invite_token: &str,
+    invite_url: &str,
+    password_hash: &str,
+) -> Result<BootstrappedTenant, TenantBootstrapError> {
+    let template = client
+        .query_opt(
+            "SELECT id, default_schema FROM templates WHERE id = $1 AND is_public = true",
+            &[&request.template_id],
+        )
+        .await
+        .context("Failed to look up template")?
+        .ok_or(TenantBootstrapError::TemplateNotFound)?;
+    let default_schema: serde_json::Value = template.get("default_schema");
+
+    let tenant = TenantId::new();
+    let tenant_id = *tenant.as_uuid();
+
+    // Set before creating the tenant, so its insert passes the tenants policy; everything
+    // after this belongs to the new tenant
+    client
+        .execute(
+            "SELECT set_config('quillspace.tenant_id', $1, true)",
+            &[&tenant.to_string()],
+        )
+        .await
+        .context("Failed to set RLS tenant context")?;
+
+    client
+        .execute(
+            "INSERT INTO tenants (id, name, slug, settings, is_active) VALUES ($1, $2, $3, '{}', true)",
+            &[&tenant_id, &request.tenant_name.trim(), &request.tenant_slug.trim()],
+        )
+        .await
+        .map_err(|e| conflict_on_duplicate(e, "tenant slug"))?;
+
+    let admin_user_id: Uuid = client
+        .query_one(
+            "INSERT INTO users (tenant_id, email, password_hash, first_name, last_name, role)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id",
+            &[
+                &tenant_id,
+                &request.admin_email.trim().to_lowercase(),
+                &password_hash,
+                &request.admin_first_name.trim(),
+                &request.admin_last_name.trim(),
+                &UserRole::Admin,
+            ],
+        )
+        .await
+        .map_err(|e| conflict_on_duplicate(e, "user with this email"))?
+        .get(0);
+
+    let expires_at = Utc::now() + Duration::days(INVITE_TTL_DAYS);
+    let invite_id: Uuid = client
+        .query_one(
+            "INSERT INTO user_invites (tenant_id, user_id, token_hash, expires_at) VALUES ($1, $2, $3, $4) RETURNING id",
+            &[&tenant_id, &admin_user_id, &hash_invite_token(invite_token), &expires_at],
+        )
+        .await
+        .context("Failed to create invite")?
+        .get(0);
+
+    // The site insert claims the subdomain, doing nothing if another site holds it
+    let insert_site = |subdomain: String| async move {
+        let row = client
+            .query_opt(
+                "INSERT INTO sites (tenant_id, name, template_id, subdomain) VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (subdomain) DO NOTHING
+                 RETURNING id",
+                &[&tenant_id, &request.site_name(), &request.template_id, &subdomain],
+            )
+            .await
+            .context("Failed to create site")?;
+        anyhow::Ok(row.map(|row| (row.get::<_, Uuid>(0), subdomain)))
+    };
+    let (site_id, subdomain) = match &request.subdomain {
+        Some(subdomain) => insert_site(subdomain.clone())
+            .await?
+            .ok_or_else(|| TenantBootstrapError::Conflict("site with this subdomain already exists".to_string()))?,
+        None => SubdomainGenerator::default()
+            .reserve(request.site_name(), insert_site)
+            .await
+            .map_err(|e| match e {
+                SubdomainError::Database(e) => TenantBootstrapError::Database(e),
+                e => TenantBootstrapError::Conflict(e.to_string()),
+            })?,
+    };
+
+    let mut page_ids = Vec::new();
+    for (sort_order, page) in (0..).zip(request.starter_pages()) {
+        let puck_data = starter_puck_data(&default_schema, &page.title);
+        let page_id: Uuid = client
+            .query_one(
+                "INSERT INTO pages (tenant_id, site_id, slug, title, puck_data, sort_order)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 RETURNING id",
+                &[&tenant_id, &site_id, &SlugRules::default().slugify(&page.slug), &page.title, &puck_data, &sort_order],
+            )
+            .await
+            .map_err(|e| conflict_on_duplicate(e, &format!("page '{}'", page.slug)))?
+            .get(0);
+        page_ids.push(page_id);
+    }
+
+    let payload = serde_json::json!({
+        "tenant_name": request.tenant_name,
+        "site_id": site_id,
+        "subdomain": subdomain,
+        "invite_id": invite_id,
+        "invite_token": invite_token,
+        "invite_url": invite_url,
+        "expires_at": expires_at,
+    });
+    enqueue_notification(client, &tenant, admin_user_id, USER_INVITED, &payload).await?;
+
+    Ok(BootstrappedTenant {
+        tenant_id,
+        admin_user_id,
+        invite_id,
+        site_id,
+        subdomain,
+        page_ids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TestApp;
+
+    fn request(slug: &str) -> BootstrapTenantRequest {
+        BootstrapTenantRequest {
+            tenant_name: "Brontë Press".to_string(),
+            tenant_slug: slug.to_string(),
+            admin_email: format!("admin@{}.example.com", slug),
+            admin_first_name: "Charlotte".to_string(),
+            admin_last_name: "Brontë".to_string(),
+            template_id: Uuid::nil(),
+            site_name: None,
+            subdomain: None,
+            pages: None,
+        }
+    }
+
+    async fn count(client: &impl GenericClient, query: &str, id: &Uuid) -> i64 {
+        client.query_one(query, &[id]).await.expect("Failed to count rows").get(0)
+    }
+
+    #[test]
+    fn test_request_validation() {
+        assert!(request("bronte").validate().is_ok());
+        assert_eq!(request("bronte").starter_pages().len(), DEFAULT_PAGES.len());
+
+        let no_email = BootstrapTenantRequest { admin_email: "charlotte".to_string(), ..request("bronte") };
+        assert!(matches!(no_email.validate(), Err(TenantBootstrapError::Invalid(_))));
+
+        let page = |slug: &str| StarterPage { slug: slug.to_string(), title: "Page".to_string() };
+        let repeated = BootstrapTenantRequest { pages: Some(vec![page("home"), page("home")]), ..request("bronte") };
+        assert!(matches!(repeated.validate(), Err(TenantBootstrapError::Invalid(reason)) if reason.contains("home")));
+
+        let reserved = BootstrapTenantRequest { subdomain: Some("admin".to_string()), ..request("bronte") };
+        assert!(reserved.validate().is_err());
+        let system_path = BootstrapTenantRequest { pages: Some(vec![page("home"), page("API")]), ..request("bronte") };
+        assert!(matches!(system_path.validate(), Err(TenantBootstrapError::Invalid(reason)) if reason.contains("reserved")));
+    }
+
+    #[test]
+    fn test_starter_puck_data_keeps_template_defaults() {
+        let schema = serde_json::json!({ "content": [{ "type": "Hero" }], "root": { "props": { "theme": "dark" } } });
+        let data = starter_puck_data(&schema, "About");
+        assert_eq!(data["content"][0]["type"], "Hero");
+        assert_eq!(data["root"]["props"], serde_json::json!({ "theme": "dark", "title": "About" }));
+
+        assert_eq!(starter_puck_data(&serde_json::Value::Null, "Home")["root"]["props"]["title"], "Home");
+    }
+
+    /// A public template to build starter sites from
+    async fn public_template(app: &TestApp) -> Uuid {
+        app.admin_pool
+            .get()
+            .await
+            .expect("Failed to get connection")
+            .query_one(
+                "INSERT INTO templates (name, html_source, default_schema, is_public)
+                 VALUES ('Author', '<main>{{ page.title }}</main>', '{\"content\": [{\"type\": \"Hero\"}]}', true)
+                 RETURNING id",
+                &[],
+            )
+            .await
+            .expect("Failed to create template")
+            .get(0)
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_creates_tenant_admin_site_and_pages() {
+        let Some(app) = TestApp::start().await else {
+            return;
+        };
+        let template_id = public_template(&app).await;
+        let client = app.admin_pool.get().await.expect("Failed to get connection");
+
+        let slug = format!("bootstrap-{}", Uuid::new_v4().simple());
+        let request = BootstrapTenantRequest { template_id, ..request(&slug) };
+        let created = TenantBootstrapService::new(app.state.db.postgres().clone())
+            .bootstrap(&request, "https://app.quillspace.com")
+            .await
+            .expect("Bootstrap failed");
+
+        assert_eq!(created.page_ids.len(), DEFAULT_PAGES.len());
+        assert_eq!(count(&client, "SELECT COUNT(*) FROM tenants WHERE id = $1", &created.tenant_id).await, 1);
+        assert_eq!(count(&client, "SELECT COUNT(*) FROM sites WHERE tenant_id = $1", &created.tenant_id).await, 1);
+        assert_eq!(count(&client, "SELECT COUNT(*) FROM pages WHERE site_id = $1", &created.site_id).await, 4);
+        assert_eq!(
+            count(&client, "SELECT COUNT(*) FROM users WHERE id = $1 AND role = 'admin'", &created.admin_user_id).await,
+            1
+        );
+        assert_eq!(
+            count(
+                &client,
+                "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND kind = 'user_invited'",
+                &created.admin_user_id
+            )
+            .await,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_bootstrap_rolls_everything_back() {
+        let Some(app) = TestApp::start().await else {
+            return;
+        };
+        let template_id = public_template(&app).await;
+        let client = app.admin_pool.get().await.expect("Failed to get connection");
+        let service = TenantBootstrapService::new(app.state.db.postgres().clone());
+
+        let first = format!("bootstrap-{}", Uuid::new_v4().simple());
+        let first = BootstrapTenantRequest { template_id, ..request(&first) };
+        service.bootstrap(&first, "https://app.quillspace.com").await.expect("First bootstrap failed");
+
+        // The tenant insert succeeds, then the admin's email collides with the first tenant's admin
+        let second = format!("bootstrap-{}", Uuid::new_v4().simple());
+        let second = BootstrapTenantRequest {
+            tenant_slug: second.clone(),
+            admin_email: first.admin_email.clone(),
+            ..first.clone()
+        };
+        let result = service.bootstrap(&second, "https://app.quillspace.com").await;
+        assert!(matches!(result, Err(TenantBootstrapError::Conflict(_))), "{:?}", result);
+
+        let tenants: i64 = client
+            .query_one("SELECT COUNT(*) FROM tenants WHERE slug = $1", &[&second.tenant_slug])
+            .await
+            .expect("Failed to count tenants")
+            .get(0);
+        assert_eq!(tenants, 0);
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use deadpool_postgres::GenericClient;
+use uuid::Uuid;
+
+/// Key of the tenant setting holding its IANA timezone name, e.g. `"Europe/Berlin"`
+pub const TIMEZONE_SETTING: &str = "timezone";
+
+/// Wall-clock formats accepted for scheduling input without an offset
+const LOCAL_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"];
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum TimezoneError {
+    #[error("Unknown timezone '{0}'")]
+    Unknown(String),
+
+    #[error("{0} does not exist in {1}; the clocks skip it for daylight saving")]
+    NonexistentLocalTime(NaiveDateTime, Tz),
+
+    #[error("Cannot parse '{0}' as a date and time")]
+    InvalidDateTime(String),
+}
+
+/// Parse an IANA timezone name
+pub fn parse_timezone(name: &str) -> Result<Tz, TimezoneError> {
+    name.trim().parse().map_err(|_| TimezoneError::Unknown(name.to_string()))
+}
+
+/// The tenant's timezone from its settings; UTC when unset or unrecognised
+pub fn tenant_timezone(settings: &serde_json::Value) -> Tz {
+    settings
+        .get(TIMEZONE_SETTING)
+        .and_then(|value| value.as_str())
+        .and_then(|name| parse_timezone(name).ok())
+        .unwrap_or(Tz::UTC)
+}
+
+/// The timezone a user's wall-clock input is read in: their own if set, else the tenant's
+pub fn effective_timezone(user_timezone: Option<&str>, tenant_settings: &serde_json::Value) -> Tz {
+    user_timezone
+        .and_then(|name| parse_timezone(name).ok())
+        .unwrap_or_else(|| tenant_timezone(tenant_settings))
+}
+
+/// Reject a settings update whose `timezone` is not a known IANA name
+pub fn validate_settings(settings: &serde_json::Value) -> Result<(), TimezoneError> {
+    match settings.get(TIMEZONE_SETTING) {
+        None | Some(serde_json::Value::Null) => Ok(()),
+        Some(serde_json::Value::String(name)) => parse_timezone(name).map(|_| ()),
+        Some(other) => Err(TimezoneError::Unknown(other.to_string())),
+    }
+}
+
+/// The UTC instant of wall-clock time `local` in `tz`.
+///
+/// A time repeated when the clocks go back resolves to its first occurrence; a time
+/// skipped when they go forward is an error rather than silently shifted.
+pub fn local_to_utc(local: NaiveDateTime, tz: Tz) -> Result<DateTime<Utc>, TimezoneError> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .map(|instant| instant.with_timezone(&Utc))
+        .ok_or(TimezoneError::NonexistentLocalTime(local, tz))
+}
+
+/// Parse scheduling input. With an offset (RFC 3339) it is an exact instant; without
+/// one it is wall-clock time in `tz`.
+pub fn parse_schedule_input(input: &str, tz: Tz) -> Result<DateTime<Utc>, TimezoneError> {
+    let input = input.trim();
+    if let Ok(instant) = DateTime::parse_from_rfc3339(input) {
+        return Ok(instant.with_timezone(&Utc));
+    }
+
+    let local = LOCAL_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .ok_or_else(|| TimezoneError::InvalidDateTime(input.to_string()))?;
+    local_to_utc(local, tz)
+}
+
+/// A stored UTC instant as wall-clock time in `tz`, keeping its offset
+pub fn to_local(instant: DateTime<Utc>, tz: Tz) -> DateTime<FixedOffset> {
+    instant.with_timezone(&tz).fixed_offset()
+}
+
+/// The local date `instant` falls on in `tz`, i.e. the daily bucket it is counted in
+pub fn local_date(instant: DateTime<Utc>, tz: Tz) -> NaiveDate {
+    instant.with_timezone(&tz).date_naive()
+}
+
+/// The UTC instant local day `date` starts in `tz`. That is midnight unless the
+/// clocks skip midnight that day, in which case it is the first time that exists.
+pub fn local_day_start(date: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).expect("Midnight is a valid time");
+    (0..=24)
+        .find_map(|hours| tz.from_local_datetime(&(midnight + Duration::hours(hours))).earliest())
+        .map(|start| start.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+/// Start of a daily window covering today and the `days` local days before it
+pub fn daily_window_start(now: DateTime<Utc>, days: u32, tz: Tz) -> DateTime<Utc> {
+    local_day_start(local_date(now, tz) - Duration::days(days as i64), tz)
+}
+
+/// The tenant's timezone, read from its settings
+pub async fn load_tenant_timezone(client: &impl GenericClient, tenant_id: &Uuid) -> Result<Tz> {
+    let settings: Option<serde_json::Value> = client
+        .query_opt("SELECT settings FROM tenants WHERE id = $1", &[tenant_id])
+        .await
+        .context("Failed to load tenant settings")?
+        .and_then(|row| row.get(0));
+    Ok(settings.map_or(Tz::UTC, |settings| tenant_timezone(&settings)))
+}
+
+/// The timezone a user works in: their own setting, falling back to their tenant's
+pub async fn load_user_timezone(client: &impl GenericClient, tenant_id: &Uuid, user_id: &Uuid) -> Result<Tz> {
+    let row = client
+        .query_opt(
+            "SELECT u.timezone, t.settings FROM users u JOIN tenants t ON t.id = u.tenant_id
+             WHERE u.id = $1 AND u.tenant_id = $2",
+            &[user_id, tenant_id],
+        )
+        .await
+        .context("Failed to load user timezone")?;
+
+    Ok(match row {
+        Some(row) => {
+            let user_timezone: Option<String> = row.get("timezone");
+            let settings: Option<serde_json::Value> = row.get("settings");
+            effective_timezone(user_timezone.as_deref(), &settings.unwrap_or_default())
+        }
+        None => Tz::UTC,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).expect("Invalid test instant").with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_nine_am_local_is_stored_as_utc() {
+        let new_york = parse_timezone("America/New_York").unwrap();
+
+        // Standard time (UTC-5) and daylight time (UTC-4)
+        assert_eq!(parse_schedule_input("2026-01-15T09:00", new_york).unwrap(), utc("2026-01-15T14:00:00Z"));
+        assert_eq!(parse_schedule_input("2026-07-15 09:00", new_york).unwrap(), utc("2026-07-15T13:00:00Z"));
+
+        // Presented back as 9am local
+        let stored = parse_schedule_input("2026-07-15T09:00:00", new_york).unwrap();
+        assert_eq!(to_local(stored, new_york).to_rfc3339(), "2026-07-15T09:00:00-04:00");
+
+        // An explicit offset wins over the user's timezone
+        assert_eq!(
+            parse_schedule_input("2026-07-15T09:00:00+02:00", new_york).unwrap(),
+            utc("2026-07-15T07:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_daylight_saving_transitions() {
+        let new_york = parse_timezone("America/New_York").unwrap();
+
+        // 02:30 is skipped on 8 March 2026
+        let skipped = parse_schedule_input("2026-03-08T02:30", new_york);
+        assert!(matches!(skipped, Err(TimezoneError::NonexistentLocalTime(..))));
+
+        // 01:30 happens twice on 1 November 2026; the first (EDT) is taken
+        assert_eq!(parse_schedule_input("2026-11-01T01:30", new_york).unwrap(), utc("2026-11-01T05:30:00Z"));
+    }
+
+    #[test]
+    fn test_daily_buckets_align_to_tenant_timezone() {
+        let los_angeles = tenant_timezone(&json!({ "timezone": "America/Los_Angeles" }));
+        let day = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+
+        // 23:30 PDT on the 9th is already the 10th in UTC
+        assert_eq!(local_date(utc("2026-03-10T06:30:00Z"), los_angeles), day);
+        assert_eq!(local_date(utc("2026-03-10T07:00:00Z"), los_angeles), day.succ_opt().unwrap());
+        assert_eq!(local_day_start(day, los_angeles), utc("2026-03-09T07:00:00Z"));
+
+        // The day the clocks change is 23 hours long
+        let change = NaiveDate::from_ymd_opt(2026, 3, 8).unwrap();
+        assert_eq!(local_day_start(day, los_angeles) - local_day_start(change, los_angeles), Duration::hours(23));
+
+        assert_eq!(
+            daily_window_start(utc("2026-03-10T06:30:00Z"), 1, los_angeles),
+            utc("2026-03-08T08:00:00Z")
+        );
+        assert_eq!(daily_window_start(utc("2026-03-10T06:30:00Z"), 1, Tz::UTC), utc("2026-03-09T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_timezone_resolution() {
+        let settings = json!({ "timezone": "Europe/Berlin" });
+        assert_eq!(tenant_timezone(&settings), chrono_tz::Europe::Berlin);
+        assert_eq!(tenant_timezone(&json!({})), Tz::UTC);
+        assert_eq!(tenant_timezone(&json!({ "timezone": "Mars/Olympus" })), Tz::UTC);
+
+        assert_eq!(effective_timezone(Some("Asia/Tokyo"), &settings), chrono_tz::Asia::Tokyo);
+        assert_eq!(effective_timezone(None, &settings), chrono_tz::Europe::Berlin);
+
+        assert!(validate_settings(&settings).is_ok());
+        assert!(validate_settings(&json!({ "plan": "pro" })).is_ok());
+        assert!(validate_settings(&json!({ "timezone": "Mars/Olympus" })).is_err());
+        assert!(validate_settings(&json!({ "timezone": 5 })).is_err());
+    }
+}
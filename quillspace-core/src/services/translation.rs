@@ -0,0 +1,169 @@
+use crate::database::postgres::tenant_transaction;
+use crate::types::TenantId;
+use anyhow::{Context, Result};
+use deadpool_postgres::Pool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A site's translation strings: key → locale → text
+pub type Translations = HashMap<String, HashMap<String, String>>;
+
+/// Translation service errors
+#[derive(Debug, thiserror::Error)]
+pub enum TranslationError {
+    #[error("Site not found")]
+    SiteNotFound,
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// Service managing per-site translation strings
+#[derive(Clone)]
+pub struct TranslationService {
+    db: Pool,
+}
+
+impl TranslationService {
+    pub fn new(db: Pool) -> Self {
+        Self { db }
+    }
+
+    /// All translations for a site
+    pub async fn get_translations(&self, tenant_id: &TenantId, site_id: Uuid) -> Result<Translations> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let rows = transaction
+            .query(
+                "SELECT key, locale, value FROM site_translations WHERE tenant_id = $1 AND site_id = $2",
+                &[tenant_id.as_uuid(), &site_id],
+            )
+            .await
+            .context("Failed to load translations")?;
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+
+        let mut translations = Translations::new();
+        for row in rows {
+            translations
+                .entry(row.get("key"))
+                .or_default()
+                .insert(row.get("locale"), row.get("value"));
+        }
+        Ok(translations)
+    }
+
+    /// Set a key's text for the given locales; other locales of the key are left as they are
+    pub async fn upsert_translation(
+        &self,
+        tenant_id: &TenantId,
+        site_id: Uuid,
+        key: &str,
+        values: &HashMap<String, String>,
+    ) -> Result<(), TranslationError> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        // The site must belong to the tenant
+        transaction
+            .query_opt("SELECT id FROM sites WHERE id = $1 AND tenant_id = $2", &[&site_id, tenant_id.as_uuid()])
+            .await
+            .context("Failed to look up site")?
+            .ok_or(TranslationError::SiteNotFound)?;
+
+        for (locale, value) in values {
+            transaction
+                .execute(
+                    "INSERT INTO site_translations (tenant_id, site_id, key, locale, value)
+                     VALUES ($1, $2, $3, $4, $5)
+                     ON CONFLICT (site_id, key, locale)
+                     DO UPDATE SET value = EXCLUDED.value, updated_at = NOW()",
+                    &[tenant_id.as_uuid(), &site_id, &key, locale, value],
+                )
+                .await
+                .context("Failed to save translation")?;
+        }
+
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+        Ok(())
+    }
+
+    /// Delete a key in every locale, or only in `locale`. Returns whether anything was deleted.
+    pub async fn delete_translation(
+        &self,
+        tenant_id: &TenantId,
+        site_id: Uuid,
+        key: &str,
+        locale: Option<&str>,
+    ) -> Result<bool> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = tenant_transaction(&mut client, tenant_id).await?;
+
+        let deleted = transaction
+            .execute(
+                "DELETE FROM site_translations
+                 WHERE tenant_id = $1 AND site_id = $2 AND key = $3 AND ($4::text IS NULL OR locale = $4)",
+                &[tenant_id.as_uuid(), &site_id, &key, &locale],
+            )
+            .await
+            .context("Failed to delete translation")?;
+
+        transaction.commit().await
+            .context("Failed to commit transaction")?;
+        Ok(deleted > 0)
+    }
+}
+
+
+/// Text for `key` in `locale`, falling back to the locale's language (`de` for `de-AT`),
+/// then `default_locale`, then the key itself
+pub fn resolve_translation<'a>(translations: &'a Translations, key: &'a str, locale: &str, default_locale: &str) -> &'a str {
+    let Some(values) = translations.get(key) else {
+        return key;
+    };
+    let language = locale.split(['-', '_']).next().unwrap_or(locale);
+
+    [locale, language, default_locale]
+        .iter()
+        .find_map(|candidate| values.get(*candidate))
+        .map(String::as_str)
+        .unwrap_or(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translations() -> Translations {
+        let mut welcome = HashMap::new();
+        welcome.insert("en-US".to_string(), "Welcome".to_string());
+        welcome.insert("de".to_string(), "Willkommen".to_string());
+        welcome.insert("fr-FR".to_string(), "Bienvenue".to_string());
+
+        let mut translations = Translations::new();
+        translations.insert("welcome".to_string(), welcome);
+        translations
+    }
+
+    #[test]
+    fn test_resolve_translation_in_two_locales() {
+        let translations = translations();
+        assert_eq!(resolve_translation(&translations, "welcome", "fr-FR", "en-US"), "Bienvenue");
+        assert_eq!(resolve_translation(&translations, "welcome", "en-US", "en-US"), "Welcome");
+        // Regional variant falls back to its language
+        assert_eq!(resolve_translation(&translations, "welcome", "de-AT", "en-US"), "Willkommen");
+    }
+
+    #[test]
+    fn test_resolve_translation_fallbacks() {
+        let translations = translations();
+        assert_eq!(resolve_translation(&translations, "welcome", "es-ES", "en-US"), "Welcome");
+        assert_eq!(resolve_translation(&translations, "welcome", "es-ES", "it-IT"), "welcome");
+        assert_eq!(resolve_translation(&translations, "missing_key", "fr-FR", "en-US"), "missing_key");
+    }
+}
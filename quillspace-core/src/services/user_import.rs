@@ -0,0 +1,504 @@
+//! Adding a tenant's team from a CSV file.
+//!
+//! The file has a header row naming its columns, `email` and `name` required and `role`
+//! optional (a blank role is `viewer`); other columns are ignored:
+//!
+//! ```text
+//! email,name,role
+//! anne@example.com,Anne Brontë,editor
+//! "emily@example.com","Brontë, Emily",viewer
+//! ```
+//!
+//! Every row is checked before anything is written, and the valid ones are created in a
+//! single transaction, each with an invite like a bootstrapped tenant's first admin. A
+//! row whose email is already registered, or repeats an earlier row, is skipped rather
+//! than failing the import. The report lists what happened to every row.
+
+use crate::database::postgres::tenant_transaction;
+use crate::services::notification::{enqueue_notification, USER_INVITED};
+use crate::services::public_url::absolute_url;
+use crate::services::request_validation::{ValidationErrors, MAX_NAME_LEN};
+use crate::services::tenant_bootstrap::{generate_invite_token, hash_invite_token, INVITE_TTL_DAYS};
+use crate::types::{TenantId, UserRole};
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use deadpool_postgres::{GenericClient, Pool};
+use serde::Serialize;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Most rows one import may have, not counting the header
+pub const MAX_IMPORT_ROWS: usize = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum UserImportError {
+    #[error("Invalid CSV: {0}")]
+    Invalid(String),
+
+    #[error("Too many rows: at most {max} users can be imported at once")]
+    TooManyRows { max: usize },
+
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+/// What happened to one row
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RowOutcome {
+    Created { user_id: Uuid, invite_id: Uuid },
+    /// `already_exists` or `duplicate_in_file`
+    Skipped { reason: &'static str },
+    Invalid { errors: ValidationErrors },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportedRow {
+    /// Line of the file the row starts on; the header is line 1
+    pub line: usize,
+    pub email: String,
+    #[serde(flatten)]
+    pub outcome: RowOutcome,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UserImportReport {
+    pub created: usize,
+    pub skipped: usize,
+    pub invalid: usize,
+    /// In file order
+    pub rows: Vec<ImportedRow>,
+}
+
+/// A row that passed validation
+#[derive(Debug, Clone, PartialEq)]
+struct NewUser {
+    email: String,
+    first_name: String,
+    last_name: String,
+    role: UserRole,
+}
+
+/// A row of the file, validated
+#[derive(Debug, Clone)]
+struct ParsedRow {
+    line: usize,
+    email: String,
+    user: Result<NewUser, ValidationErrors>,
+}
+
+/// Records of `input` with the line each starts on. Fields may be quoted, with `""` for
+/// a quote inside one; blank lines are dropped.
+fn parse_csv(input: &str) -> Result<Vec<(usize, Vec<String>)>, UserImportError> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let (mut line, mut start) = (1, 1);
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            '\n' if quoted => {
+                line += 1;
+                field.push(c);
+            }
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|field| !field.trim().is_empty()) {
+                    records.push((start, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                start = line;
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(UserImportError::Invalid(format!("unterminated quoted field starting on line {}", start)));
+    }
+    record.push(field);
+    if record.iter().any(|field| !field.trim().is_empty()) {
+        records.push((start, record));
+    }
+    Ok(records)
+}
+
+/// First word of a name, and the rest
+pub(crate) fn split_name(name: &str) -> (String, String) {
+    match name.trim().split_once(char::is_whitespace) {
+        Some((first, last)) => (first.to_string(), last.trim().to_string()),
+        None => (name.trim().to_string(), String::new()),
+    }
+}
+
+fn parse_role(role: &str) -> Option<UserRole> {
+    match role.trim().to_lowercase().as_str() {
+        "" | "viewer" => Some(UserRole::Viewer),
+        "editor" => Some(UserRole::Editor),
+        "admin" => Some(UserRole::Admin),
+        _ => None,
+    }
+}
+
+/// Every row of the file after the header
+fn parse_rows(input: &str) -> Result<Vec<ParsedRow>, UserImportError> {
+    let mut records = parse_csv(input)?.into_iter();
+    let (_, header) = records
+        .next()
+        .ok_or_else(|| UserImportError::Invalid("the file is empty".to_string()))?;
+    let column = |name: &str| header.iter().position(|column| column.trim().eq_ignore_ascii_case(name));
+    let (Some(email_column), Some(name_column)) = (column("email"), column("name")) else {
+        return Err(UserImportError::Invalid("the header must name the email and name columns".to_string()));
+    };
+    let role_column = column("role");
+
+    let records: Vec<_> = records.collect();
+    if records.is_empty() {
+        return Err(UserImportError::Invalid("the file has no users".to_string()));
+    }
+    if records.len() > MAX_IMPORT_ROWS {
+        return Err(UserImportError::TooManyRows { max: MAX_IMPORT_ROWS });
+    }
+
+    Ok(records
+        .into_iter()
+        .map(|(line, record)| {
+            let value = |column: Option<usize>| column.and_then(|i| record.get(i)).map_or("", |v| v.trim());
+            let email = value(Some(email_column)).to_lowercase();
+            let name = value(Some(name_column));
+
+            let mut errors = ValidationErrors::new();
+            errors.email("email", &email);
+            errors.required("name", name);
+            errors.max_length("name", name, MAX_NAME_LEN);
+            let role = parse_role(value(role_column));
+            if role.is_none() {
+                errors.add("role", "invalid_role", "role must be admin, editor or viewer");
+            }
+
+            let user = errors.into_result().map(|()| {
+                let (first_name, last_name) = split_name(name);
+                NewUser { email: email.clone(), first_name, last_name, role: role.unwrap_or(UserRole::Viewer) }
+            });
+            ParsedRow { line, email, user }
+        })
+        .collect())
+}
+
+/// Creates the users of a CSV file and invites them
+pub struct UserImportService {
+    db: Pool,
+}
+
+impl UserImportService {
+    pub fn new(db: Pool) -> Self {
+        Self { db }
+    }
+
+    /// Create the file's valid, new users in `tenant_id`, inviting each through a link
+    /// under `base_url`. Nothing is written when a database step fails.
+    pub async fn import(
+        &self,
+        tenant_id: &TenantId,
+        invited_by: Uuid,
+        csv: &str,
+        base_url: &str,
+    ) -> Result<UserImportReport, UserImportError> {
+        let rows = parse_rows(csv)?;
+
+        // One placeholder hash serves the whole file
+        let password_hash = placeholder_password_hash().await?;
+
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = client.transaction().await
+            .context("Failed to start transaction")?;
+        let tenant_name = begin_invites(&transaction, tenant_id).await?;
+
+        let mut report = UserImportReport::default();
+        let mut seen = HashSet::new();
+        for ParsedRow { line, email, user } in rows {
+            let outcome = match user {
+                Err(errors) => RowOutcome::Invalid { errors },
+                Ok(_) if !seen.insert(email.clone()) => RowOutcome::Skipped { reason: "duplicate_in_file" },
+                Ok(user) => {
+                    let invite = Invite { tenant_id, tenant_name: &tenant_name, invited_by, base_url, password_hash: &password_hash };
+                    match create_user(&transaction, &user, &invite).await? {
+                        Some((user_id, invite_id)) => RowOutcome::Created { user_id, invite_id },
+                        None => RowOutcome::Skipped { reason: "already_exists" },
+                    }
+                }
+            };
+            match outcome {
+                RowOutcome::Created { .. } => report.created += 1,
+                RowOutcome::Skipped { .. } => report.skipped += 1,
+                RowOutcome::Invalid { .. } => report.invalid += 1,
+            }
+            report.rows.push(ImportedRow { line, email, outcome });
+        }
+
+        transaction.commit().await
+            .context("Failed to commit user import")?;
+        Ok(report)
+    }
+
+    /// Create one user in `tenant_id` and invite them as an imported row would be, or
+    /// `None` when the email is already registered
+    pub async fn invite(
+        &self,
+        tenant_id: &TenantId,
+        invited_by: Uuid,
+        email: &str,
+        name: &str,
+        role: UserRole,
+        base_url: &str,
+    ) -> Result<Option<(Uuid, Uuid)>, UserImportError> {
+        let (first_name, last_name) = split_name(name);
+        let user = NewUser { email: email.trim().to_lowercase(), first_name, last_name, role };
+        let password_hash = placeholder_password_hash().await?;
+
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let transaction = client.transaction().await
+            .context("Failed to start transaction")?;
+        let tenant_name = begin_invites(&transaction, tenant_id).await?;
+        let invite = Invite { tenant_id, tenant_name: &tenant_name, invited_by, base_url, password_hash: &password_hash };
+        let created = create_user(&transaction, &user, &invite).await?;
+
+        transaction.commit().await
+            .context("Failed to commit user invite")?;
+        Ok(created)
+    }
+
+    /// Give an invited user the password they chose, using up the invite. `None` when
+    /// the token is unknown, expired or already used.
+    pub async fn accept_invite(&self, token: &str, password_hash: &str) -> Result<Option<Uuid>, UserImportError> {
+        let mut client = self.db.get().await
+            .context("Failed to get database connection")?;
+        let Some(invite) = client
+            .query_opt("SELECT * FROM find_user_invite($1)", &[&hash_invite_token(token)])
+            .await
+            .context("Failed to look up invite")?
+        else {
+            return Ok(None);
+        };
+        let accepted_at: Option<DateTime<Utc>> = invite.get("accepted_at");
+        let expires_at: DateTime<Utc> = invite.get("expires_at");
+        if accepted_at.is_some() || expires_at <= Utc::now() {
+            return Ok(None);
+        }
+        let (invite_id, user_id, tenant_id): (Uuid, Uuid, Uuid) =
+            (invite.get("id"), invite.get("user_id"), invite.get("tenant_id"));
+
+        let transaction = tenant_transaction(&mut client, &TenantId::from_uuid(tenant_id)).await?;
+        // Conditional so two acceptances racing each other use the invite once
+        let accepted = transaction
+            .execute(
+                "UPDATE user_invites SET accepted_at = NOW() WHERE id = $1 AND accepted_at IS NULL",
+                &[&invite_id],
+            )
+            .await
+            .context("Failed to accept invite")?;
+        if accepted == 0 {
+            return Ok(None);
+        }
+        transaction
+            .execute("UPDATE users SET password_hash = $2 WHERE id = $1", &[&user_id, &password_hash])
+            .await
+            .context("Failed to set password")?;
+
+        transaction.commit().await
+            .context("Failed to commit invite acceptance")?;
+        Ok(Some(user_id))
+    }
+}
+
+/// Hash of a password nobody knows, so no invited account can log in before its
+/// invite is accepted. bcrypt is deliberately slow, so it runs off the async workers.
+async fn placeholder_password_hash() -> anyhow::Result<String> {
+    tokio::task::spawn_blocking(|| bcrypt::hash(generate_invite_token(), bcrypt::DEFAULT_COST))
+        .await
+        .context("Password hashing task failed")?
+        .context("Failed to hash placeholder password")
+}
+
+/// Scope the transaction to `tenant_id` and load the tenant's name for the invites
+async fn begin_invites(client: &impl GenericClient, tenant_id: &TenantId) -> anyhow::Result<String> {
+    client
+        .execute(
+            "SELECT set_config('quillspace.tenant_id', $1, true)",
+            &[&tenant_id.to_string()],
+        )
+        .await
+        .context("Failed to set RLS tenant context")?;
+    Ok(client
+        .query_one("SELECT name FROM tenants WHERE id = $1", &[tenant_id.as_uuid()])
+        .await
+        .context("Failed to load tenant")?
+        .get(0))
+}
+
+/// What every invite of an import shares
+struct Invite<'a> {
+    tenant_id: &'a TenantId,
+    tenant_name: &'a str,
+    invited_by: Uuid,
+    base_url: &'a str,
+    password_hash: &'a str,
+}
+
+/// Insert the user and queue its invite, or `None` when the email is already registered
+async fn create_user(
+    client: &impl GenericClient,
+    user: &NewUser,
+    invite: &Invite<'_>,
+) -> anyhow::Result<Option<(Uuid, Uuid)>> {
+    let Some(row) = client
+        .query_opt(
+            "INSERT INTO users (tenant_id, email, password_hash, first_name, last_name, role)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (email) DO NOTHING
+             RETURNING id",
+            &[
+                invite.tenant_id.as_uuid(),
+                &user.email,
+                &invite.password_hash,
+                &user.first_name,
+                &user.last_name,
+                &user.role,
+            ],
+        )
+        .await
+        .context("Failed to create user")?
+    else {
+        return Ok(None);
+    };
+    let user_id: Uuid = row.get(0);
+
+    let token = generate_invite_token();
+    let expires_at = Utc::now() + Duration::days(INVITE_TTL_DAYS);
+    let invite_id: Uuid = client
+        .query_one(
+            "INSERT INTO user_invites (tenant_id, user_id, token_hash, expires_at) VALUES ($1, $2, $3, $4) RETURNING id",
+            &[invite.tenant_id.as_uuid(), &user_id, &hash_invite_token(&token), &expires_at],
+        )
+        .await
+        .context("Failed to create invite")?
+        .get(0);
+
+    let payload = serde_json::json!({
+        "tenant_name": invite.tenant_name,
+        "invited_by": invite.invited_by,
+        "invite_id": invite_id,
+        "invite_token": token,
+        "invite_url": absolute_url(invite.base_url, &format!("/invite/{}", token)),
+        "expires_at": expires_at,
+    });
+    enqueue_notification(client, invite.tenant_id, user_id, USER_INVITED, &payload).await?;
+    Ok(Some((user_id, invite_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_handles_quotes_and_line_endings() {
+        let records = parse_csv("email,name\r\n\"a@example.com\",\"Brontë, \"\"Anne\"\"\"\r\n\r\nb@example.com,\"Emily\nJane\"\nc@example.com,Charlotte").unwrap();
+        assert_eq!(
+            records,
+            vec![
+                (1, vec!["email".to_string(), "name".to_string()]),
+                (2, vec!["a@example.com".to_string(), "Brontë, \"Anne\"".to_string()]),
+                (4, vec!["b@example.com".to_string(), "Emily\nJane".to_string()]),
+                (6, vec!["c@example.com".to_string(), "Charlotte".to_string()]),
+            ]
+        );
+        assert!(matches!(parse_csv("email,name\n\"a@example.com,Anne"), Err(UserImportError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_rows_are_validated_individually() {
+        let rows = parse_rows("Name,Email,Role\nAnne Brontë,ANNE@example.com,Editor\n,emily@example.com,owner\n").unwrap();
+        assert_eq!(
+            rows[0].user,
+            Ok(NewUser {
+                email: "anne@example.com".to_string(),
+                first_name: "Anne".to_string(),
+                last_name: "Brontë".to_string(),
+                role: UserRole::Editor,
+            })
+        );
+        let errors = rows[1].user.clone().unwrap_err();
+        assert_eq!(errors.field("name")[0].code, "required");
+        assert_eq!(errors.field("role")[0].code, "invalid_role");
+
+        assert!(matches!(parse_rows("email,role\na@example.com,viewer"), Err(UserImportError::Invalid(_))));
+        let too_many = format!("email,name\n{}", "a@example.com,Anne\n".repeat(MAX_IMPORT_ROWS + 1));
+        assert!(matches!(parse_rows(&too_many), Err(UserImportError::TooManyRows { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_import_reports_each_row_and_invites_created_users() {
+        let Some(app) = crate::test_harness::TestApp::start().await else {
+            return;
+        };
+        let tenant = &app.tenant_a;
+        let csv = format!(
+            "email,name,role\n\
+             anne@bronte.example.com,Anne Brontë,editor\n\
+             emily@bronte.example.com,Emily Brontë,\n\
+             branwell@bronte.example.com,Branwell,painter\n\
+             {},Someone Else,viewer\n\
+             Anne@bronte.example.com,Anne Again,viewer\n",
+            app.tenant_b.admin.email
+        );
+
+        let report = UserImportService::new(app.state.db.postgres().clone())
+            .import(&tenant.id, tenant.admin.id, &csv, "https://app.quillspace.com")
+            .await
+            .expect("Import failed");
+
+        assert_eq!((report.created, report.skipped, report.invalid), (2, 2, 1));
+        let statuses: Vec<_> = report.rows.iter().map(|row| (row.line, serde_json::to_value(row).unwrap()["status"].clone())).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (2, "created".into()),
+                (3, "created".into()),
+                (4, "invalid".into()),
+                (5, "skipped".into()),
+                (6, "skipped".into()),
+            ]
+        );
+        assert_eq!(report.rows[3].outcome, RowOutcome::Skipped { reason: "already_exists" });
+        assert_eq!(report.rows[4].outcome, RowOutcome::Skipped { reason: "duplicate_in_file" });
+
+        let admin = app.admin_pool.get().await.unwrap();
+        let count = |query: &'static str| {
+            let admin = &admin;
+            async move { admin.query_one(query, &[tenant.id.as_uuid()]).await.unwrap().get::<_, i64>(0) }
+        };
+        assert_eq!(count("SELECT COUNT(*) FROM users WHERE tenant_id = $1 AND email LIKE '%@bronte.example.com'").await, 2);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM users WHERE tenant_id = $1 AND email = 'emily@bronte.example.com' AND role = 'viewer'").await,
+            1
+        );
+        assert_eq!(count("SELECT COUNT(*) FROM user_invites WHERE tenant_id = $1").await, 2);
+        assert_eq!(
+            count("SELECT COUNT(*) FROM notifications WHERE tenant_id = $1 AND kind = 'user_invited' AND emailed_at IS NULL").await,
+            2
+        );
+    }
+}
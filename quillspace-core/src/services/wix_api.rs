@@ -1,6 +1,231 @@
 use serde::{Deserialize, Serialize};
-use anyhow::Result;
-use reqwest::Client;
+use serde_json::Value;
+use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
+use crate::services::http_client;
+
+/// Most items Wix returns for one page of a data query
+pub const MAX_QUERY_PAGE_LIMIT: u32 = 1000;
+
+/// The business details of a Wix site, as held in its site properties
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub business_name: Option<String>,
+    /// Wix address object (`formatted`, `streetAddress`, `city`, `country`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<Value>,
+    /// Wix opening hours (`periods` and `specialHourPeriod`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub business_schedule: Option<Value>,
+}
+
+impl BusinessInfo {
+    /// Field mask naming the fields that are set
+    pub fn field_paths(&self) -> Vec<&'static str> {
+        [
+            ("businessName", self.business_name.is_some()),
+            ("address", self.address.is_some()),
+            ("businessSchedule", self.business_schedule.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(path, set)| set.then_some(path))
+        .collect()
+    }
+}
+
+/// Wix's error body: `{"message", "details": {"applicationError": {"code", "description"}}}`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WixErrorResponse {
+    pub message: Option<String>,
+    pub details: Option<WixErrorDetails>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WixErrorDetails {
+    pub application_error: Option<WixApplicationError>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WixApplicationError {
+    pub code: Option<String>,
+    pub description: Option<String>,
+}
+
+impl WixErrorResponse {
+    /// Wix application error code such as `WDE0025`
+    pub fn code(&self) -> Option<&str> {
+        self.application_error().and_then(|error| error.code.as_deref())
+    }
+
+    /// The first non-empty of `message` and the application error description
+    pub fn message(&self) -> Option<&str> {
+        [self.message.as_deref(), self.application_error().and_then(|error| error.description.as_deref())]
+            .into_iter()
+            .flatten()
+            .find(|message| !message.is_empty())
+    }
+
+    fn application_error(&self) -> Option<&WixApplicationError> {
+        self.details.as_ref().and_then(|details| details.application_error.as_ref())
+    }
+}
+
+/// Errors from the Wix APIs, mapped from their error responses
+#[derive(Debug, thiserror::Error)]
+pub enum WixApiError {
+    #[error("Wix rejected the credentials: {0}")]
+    Unauthorized(String),
+
+    #[error("Wix resource not found: {0}")]
+    NotFound(String),
+
+    #[error("Wix rate limit reached")]
+    RateLimited { retry_after_secs: Option<u64> },
+
+    /// Wix rejected the request itself, e.g. an invalid filter or a missing field
+    #[error("Wix rejected the request: {message}")]
+    Validation { code: Option<String>, message: String },
+
+    #[error("Wix server error {status}: {message}")]
+    Server { status: u16, message: String },
+
+    /// Any other error status
+    #[error("Wix API error {status}: {message}")]
+    Other { status: u16, code: Option<String>, message: String },
+
+    #[error("Unexpected Wix response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Wix did not respond in time")]
+    Timeout,
+
+    #[error(transparent)]
+    Http(reqwest::Error),
+}
+
+impl From<reqwest::Error> for WixApiError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            WixApiError::Timeout
+        } else {
+            WixApiError::Http(error)
+        }
+    }
+}
+
+impl WixApiError {
+    /// Map a failed response by status, with the code and message from Wix's error body
+    pub fn from_response(status: StatusCode, body: &str, retry_after_secs: Option<u64>) -> Self {
+        let parsed: WixErrorResponse = serde_json::from_str(body).unwrap_or_default();
+        let code = parsed.code().map(str::to_string);
+        let message = parsed.message().map(str::to_string).unwrap_or_else(|| body.trim().to_string());
+
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => WixApiError::Unauthorized(message),
+            StatusCode::NOT_FOUND => WixApiError::NotFound(message),
+            StatusCode::TOO_MANY_REQUESTS => WixApiError::RateLimited { retry_after_secs },
+            StatusCode::BAD_REQUEST | StatusCode::CONFLICT | StatusCode::UNPROCESSABLE_ENTITY => {
+                WixApiError::Validation { code, message }
+            }
+            status if status.is_server_error() => WixApiError::Server { status: status.as_u16(), message },
+            _ => WixApiError::Other { status: status.as_u16(), code, message },
+        }
+    }
+
+    /// Whether retrying later may succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            WixApiError::RateLimited { .. } | WixApiError::Server { .. } | WixApiError::Timeout | WixApiError::Http(_)
+        )
+    }
+}
+
+/// Sort direction for a data query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataSort {
+    pub field_name: String,
+    pub order: SortOrder,
+}
+
+/// One page of a Wix Data query
+#[derive(Debug, Clone, Default)]
+pub struct DataQuery {
+    /// Wix Data filter, e.g. `{"status": {"$eq": "published"}}`
+    pub filter: Option<Value>,
+    pub sort: Vec<DataSort>,
+    /// Items per page, capped at `MAX_QUERY_PAGE_LIMIT`; Wix defaults to 50
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page. Wix keeps the filter and sort in the cursor,
+    /// so they are not sent again.
+    pub cursor: Option<String>,
+}
+
+impl DataQuery {
+    /// Request body for `POST /wix-data/v2/items/query`
+    pub fn to_body(&self, collection_id: &str) -> Value {
+        let mut paging = serde_json::Map::new();
+        if let Some(limit) = self.limit {
+            paging.insert("limit".to_string(), Value::from(limit.clamp(1, MAX_QUERY_PAGE_LIMIT)));
+        }
+        let mut query = serde_json::Map::new();
+        match &self.cursor {
+            Some(cursor) => {
+                paging.insert("cursor".to_string(), Value::String(cursor.clone()));
+            }
+            None => {
+                if let Some(filter) = &self.filter {
+                    query.insert("filter".to_string(), filter.clone());
+                }
+                if !self.sort.is_empty() {
+                    query.insert("sort".to_string(), serde_json::json!(self.sort));
+                }
+            }
+        }
+        query.insert("cursorPaging".to_string(), Value::Object(paging));
+
+        serde_json::json!({
+            "dataCollectionId": collection_id,
+            "query": query,
+        })
+    }
+}
+
+/// Items of one page, and the cursor for the next if there is one
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataPage {
+    pub items: Vec<Value>,
+    pub next_cursor: Option<String>,
+}
+
+impl DataPage {
+    fn from_response(response: &Value) -> Result<Self, WixApiError> {
+        let items = response
+            .get("dataItems")
+            .and_then(Value::as_array)
+            .cloned()
+            .ok_or_else(|| WixApiError::InvalidResponse("missing dataItems".to_string()))?;
+        let next_cursor = response
+            .pointer("/pagingMetadata/cursors/next")
+            .and_then(Value::as_str)
+            .filter(|cursor| !cursor.is_empty())
+            .map(str::to_string);
+        Ok(Self { items, next_cursor })
+    }
+}
 
 pub struct WixApiClient {
     client: Client,
@@ -12,13 +237,39 @@ pub struct WixApiClient {
 impl WixApiClient {
     pub fn new(api_key: String, account_id: String) -> Self {
         Self {
-            client: Client::new(),
+            client: http_client::shared_client(),
             api_key,
             account_id,
             base_url: "https://www.wixapis.com".to_string(),
         }
     }
 
+    /// Send requests through `client` instead of the shared one
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Send requests somewhere other than `https://www.wixapis.com`
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Map a failed Wix response to a typed error
+    async fn error_from(response: reqwest::Response) -> WixApiError {
+        let status = response.status();
+        let retry_after_secs = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        match response.text().await {
+            Ok(body) => WixApiError::from_response(status, &body, retry_after_secs),
+            Err(e) => e.into(),
+        }
+    }
+
     fn create_headers(&self, site_id: &str) -> reqwest::header::HeaderMap {
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert("Authorization", self.api_key.parse().unwrap());
@@ -29,7 +280,7 @@ impl WixApiClient {
     }
 
     /// Get items from a Wix Data collection
-    pub async fn get_collection_items(&self, site_id: &str, collection_id: &str) -> Result<serde_json::Value> {
+    pub async fn get_collection_items(&self, site_id: &str, collection_id: &str) -> Result<serde_json::Value, WixApiError> {
         let url = format!("{}/wix-data/v2/items/query", self.base_url);
         let headers = self.create_headers(site_id);
         
@@ -45,16 +296,59 @@ impl WixApiClient {
             .send()
             .await?;
 
-        if response.status().is_success() {
-            Ok(response.json().await?)
-        } else {
-            let error_text = response.text().await?;
-            Err(anyhow::anyhow!("Wix Data API error: {}", error_text))
+        if !response.status().is_success() {
+            return Err(Self::error_from(response).await);
         }
+        Ok(response.json().await?)
+    }
+
+    /// Query one page of a Wix Data collection, with optional filter, sort and cursor paging
+    pub async fn query_data(&self, site_id: &str, collection_id: &str, query: &DataQuery) -> Result<DataPage, WixApiError> {
+        let url = format!("{}/wix-data/v2/items/query", self.base_url);
+
+        let response = self.client
+            .post(&url)
+            .headers(self.create_headers(site_id))
+            .json(&query.to_body(collection_id))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Self::error_from(response).await);
+        }
+        DataPage::from_response(&response.json().await?)
+    }
+
+    /// Every item matching `query`, fetched page by page until Wix has no more or
+    /// `max_items` have been collected. Results past the cap are left out.
+    pub async fn query_all(
+        &self,
+        site_id: &str,
+        collection_id: &str,
+        mut query: DataQuery,
+        max_items: usize,
+    ) -> Result<Vec<Value>, WixApiError> {
+        let mut items = Vec::new();
+        while items.len() < max_items {
+            let page = self.query_data(site_id, collection_id, &query).await?;
+            items.extend(page.items);
+
+            match page.next_cursor {
+                Some(next) if query.cursor.as_deref() == Some(next.as_str()) => {
+                    return Err(WixApiError::InvalidResponse("paging cursor did not advance".to_string()));
+                }
+                Some(next) => query.cursor = Some(next),
+                None => return Ok(items),
+            }
+        }
+
+        items.truncate(max_items);
+        tracing::warn!(collection_id, max_items, "Wix data query stopped at the item cap");
+        Ok(items)
     }
 
     /// Insert item into Wix Data collection
-    pub async fn insert_collection_item(&self, site_id: &str, collection_id: &str, item_data: serde_json::Value) -> Result<serde_json::Value> {
+    pub async fn insert_collection_item(&self, site_id: &str, collection_id: &str, item_data: serde_json::Value) -> Result<serde_json::Value, WixApiError> {
         let url = format!("{}/wix-data/v2/items", self.base_url);
         let headers = self.create_headers(site_id);
         
@@ -70,16 +364,14 @@ impl WixApiClient {
             .send()
             .await?;
 
-        if response.status().is_success() {
-            Ok(response.json().await?)
-        } else {
-            let error_text = response.text().await?;
-            Err(anyhow::anyhow!("Wix Data API error: {}", error_text))
+        if !response.status().is_success() {
+            return Err(Self::error_from(response).await);
         }
+        Ok(response.json().await?)
     }
 
     /// Update item in Wix Data collection
-    pub async fn update_collection_item(&self, site_id: &str, collection_id: &str, item_id: &str, item_data: serde_json::Value) -> Result<serde_json::Value> {
+    pub async fn update_collection_item(&self, site_id: &str, collection_id: &str, item_id: &str, item_data: serde_json::Value) -> Result<serde_json::Value, WixApiError> {
         let url = format!("{}/wix-data/v2/items/{}", self.base_url, item_id);
         let headers = self.create_headers(site_id);
         
@@ -95,16 +387,14 @@ impl WixApiClient {
             .send()
             .await?;
 
-        if response.status().is_success() {
-            Ok(response.json().await?)
-        } else {
-            let error_text = response.text().await?;
-            Err(anyhow::anyhow!("Wix Data API error: {}", error_text))
+        if !response.status().is_success() {
+            return Err(Self::error_from(response).await);
         }
+        Ok(response.json().await?)
     }
 
     /// Create or update collection field with proper type
-    pub async fn ensure_collection_field(&self, site_id: &str, collection_id: &str, field_key: &str, field_type: &str, display_name: &str) -> Result<serde_json::Value> {
+    pub async fn ensure_collection_field(&self, site_id: &str, collection_id: &str, field_key: &str, field_type: &str, display_name: &str) -> Result<serde_json::Value, WixApiError> {
         let url = format!("{}/wix-data/v1/collections/{}/fields", self.base_url, collection_id);
         let headers = self.create_headers(site_id);
         
@@ -140,13 +430,37 @@ impl WixApiClient {
             if response_text.contains("already exists") || response_text.contains("FIELD_ALREADY_EXISTS") {
                 Ok(serde_json::json!({"status": "field_exists"}))
             } else {
-                Err(anyhow::anyhow!("Wix Collection Field API error: {} - {}", status, response_text))
+                Err(WixApiError::from_response(status, &response_text, None))
             }
         }
     }
 
+    /// Check the credentials with the smallest account-level request: the first site of
+    /// the account's site list, rather than every site
+    pub async fn test_connection(&self) -> Result<(), WixApiError> {
+        let url = format!("{}/site-list/v2/sites/query", self.base_url);
+        let response = self.client
+            .post(&url)
+            .header("Authorization", &self.api_key)
+            .header("wix-account-id", &self.account_id)
+            .json(&serde_json::json!({ "query": { "cursorPaging": { "limit": 1 } } }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Self::error_from(response).await);
+        }
+        Ok(())
+    }
+
+    /// Identifies the client's credentials without revealing them
+    pub fn credentials_key(&self) -> String {
+        let digest = Sha256::digest(format!("{}:{}", self.account_id, self.api_key));
+        hex::encode(digest)
+    }
+
     /// Get site properties
-    pub async fn get_site_properties(&self, site_id: &str) -> Result<serde_json::Value> {
+    pub async fn get_site_properties(&self, site_id: &str) -> Result<serde_json::Value, WixApiError> {
         let url = format!("{}/site-properties/v4/properties", self.base_url);
         let headers = self.create_headers(site_id);
         
@@ -156,12 +470,206 @@ impl WixApiClient {
             .send()
             .await?;
 
-        if response.status().is_success() {
-            Ok(response.json().await?)
-        } else {
-            let error_text = response.text().await?;
-            Err(anyhow::anyhow!("Site properties API error: {}", error_text))
+        if !response.status().is_success() {
+            return Err(Self::error_from(response).await);
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Business name, address and opening hours from the site properties
+    pub async fn get_business_info(&self, site_id: &str) -> Result<BusinessInfo, WixApiError> {
+        let url = format!("{}/site-properties/v4/properties", self.base_url);
+
+        let response = self.client
+            .get(&url)
+            .headers(self.create_headers(site_id))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Self::error_from(response).await);
+        }
+        let body: Value = response.json().await?;
+        let properties = body
+            .get("properties")
+            .cloned()
+            .ok_or_else(|| WixApiError::InvalidResponse("missing properties".to_string()))?;
+        serde_json::from_value(properties).map_err(|e| WixApiError::InvalidResponse(e.to_string()))
+    }
+
+    /// Write business info to the site properties; fields left unset are not changed
+    pub async fn update_business_info(&self, site_id: &str, info: &BusinessInfo) -> Result<BusinessInfo, WixApiError> {
+        let url = format!("{}/site-properties/v4/properties", self.base_url);
+        let body = serde_json::json!({
+            "properties": info,
+            "fields": { "paths": info.field_paths() },
+        });
+
+        let response = self.client
+            .patch(&url)
+            .headers(self.create_headers(site_id))
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Self::error_from(response).await);
         }
+        let body: Value = response.json().await?;
+        match body.get("properties") {
+            Some(properties) => serde_json::from_value(properties.clone()).map_err(|e| WixApiError::InvalidResponse(e.to_string())),
+            None => Ok(info.clone()),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode as HttpStatus, routing::post, Json, Router};
+    use std::sync::{Arc, Mutex};
+
+    /// Serves `pages` from a fake `/wix-data/v2/items/query`, chained by cursor, and
+    /// records every request body
+    async fn mock_wix(pages: Vec<Vec<u32>>) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let handler = move |Json(body): Json<Value>| {
+            let (pages, requests) = (pages.clone(), recorded.clone());
+            async move {
+                requests.lock().unwrap().push(body.clone());
+                if body["dataCollectionId"] == "missing" {
+                    let error = serde_json::json!({
+                        "message": "Collection missing does not exist",
+                        "details": { "applicationError": { "code": "WDE0025", "description": "Collection not found" } }
+                    });
+                    return (HttpStatus::NOT_FOUND, Json(error));
+                }
+                let index: usize = body
+                    .pointer("/query/cursorPaging/cursor")
+                    .and_then(Value::as_str)
+                    .map_or(0, |cursor| cursor.trim_start_matches("page-").parse().unwrap());
+                let next = (index + 1 < pages.len()).then(|| format!("page-{}", index + 1));
+                let items: Vec<Value> = pages[index].iter().map(|n| serde_json::json!({ "id": n })).collect();
+                (
+                    HttpStatus::OK,
+                    Json(serde_json::json!({
+                        "dataItems": items,
+                        "pagingMetadata": { "count": items.len(), "cursors": { "next": next } }
+                    })),
+                )
+            }
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind mock Wix");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().route("/wix-data/v2/items/query", post(handler));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (base_url, requests)
+    }
+
+    fn client(base_url: &str) -> WixApiClient {
+        WixApiClient::new("test-key".to_string(), "account".to_string()).with_base_url(base_url)
     }
 
+    fn ids(items: &[Value]) -> Vec<u64> {
+        items.iter().filter_map(|item| item["id"].as_u64()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_query_all_concatenates_pages() {
+        let (base_url, requests) = mock_wix(vec![vec![1, 2, 3], vec![4, 5]]).await;
+        let query = DataQuery {
+            sort: vec![DataSort { field_name: "title".to_string(), order: SortOrder::Asc }],
+            limit: Some(3),
+            ..DataQuery::default()
+        };
+
+        let items = client(&base_url).query_all("site", "books", query, 100).await.expect("Query failed");
+        assert_eq!(ids(&items), vec![1, 2, 3, 4, 5]);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0]["query"]["sort"][0], serde_json::json!({ "fieldName": "title", "order": "ASC" }));
+        // Follow-up pages send only the cursor
+        assert_eq!(requests[1]["query"], serde_json::json!({ "cursorPaging": { "limit": 3, "cursor": "page-1" } }));
+    }
+
+    #[tokio::test]
+    async fn test_query_all_respects_cap() {
+        let (base_url, requests) = mock_wix(vec![vec![1, 2, 3], vec![4, 5, 6], vec![7]]).await;
+
+        let items = client(&base_url).query_all("site", "books", DataQuery::default(), 4).await.expect("Query failed");
+        assert_eq!(ids(&items), vec![1, 2, 3, 4]);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_wix_error_mapped_to_typed_error() {
+        let (base_url, _) = mock_wix(vec![vec![]]).await;
+
+        let error = client(&base_url).query_data("site", "missing", &DataQuery::default()).await.unwrap_err();
+        assert!(matches!(&error, WixApiError::NotFound(message) if message == "Collection missing does not exist"), "{:?}", error);
+
+        let body = r#"{"message":"","details":{"applicationError":{"code":"WDE0080","description":"Invalid filter"}}}"#;
+        match WixApiError::from_response(StatusCode::BAD_REQUEST, body, None) {
+            WixApiError::Validation { code, message } => {
+                assert_eq!((code.as_deref(), message.as_str()), (Some("WDE0080"), "Invalid filter"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(matches!(
+            WixApiError::from_response(StatusCode::TOO_MANY_REQUESTS, "", Some(30)),
+            WixApiError::RateLimited { retry_after_secs: Some(30) }
+        ));
+    }
+
+    #[test]
+    fn test_status_codes_mapped_to_variants() {
+        let body = r#"{"message":"Something happened","details":{"applicationError":{"code":"WDE0001"}}}"#;
+        let map = |status: u16, retry_after| WixApiError::from_response(StatusCode::from_u16(status).unwrap(), body, retry_after);
+
+        assert!(matches!(map(401, None), WixApiError::Unauthorized(_)));
+        assert!(matches!(map(403, None), WixApiError::Unauthorized(_)));
+        assert!(matches!(map(404, None), WixApiError::NotFound(_)));
+        assert!(matches!(map(429, Some(5)), WixApiError::RateLimited { retry_after_secs: Some(5) }));
+        for status in [400, 409, 422] {
+            assert!(matches!(map(status, None), WixApiError::Validation { code: Some(_), .. }), "{}", status);
+        }
+        for status in [500, 502, 503] {
+            assert!(matches!(map(status, None), WixApiError::Server { status: s, .. } if s == status), "{}", status);
+        }
+        assert!(matches!(map(418, None), WixApiError::Other { status: 418, code: Some(_), .. }));
+
+        // A body Wix did not format is kept as the message
+        match WixApiError::from_response(StatusCode::BAD_GATEWAY, "upstream timed out\n", None) {
+            WixApiError::Server { message, .. } => assert_eq!(message, "upstream timed out"),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(map(503, None).is_transient());
+        assert!(!map(401, None).is_transient());
+    }
+
+    #[tokio::test]
+    async fn test_slow_wix_times_out() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind mock Wix");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().route(
+            "/site-properties/v4/properties",
+            axum::routing::get(|| async {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                Json(serde_json::json!({ "properties": {} }))
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let config = crate::config::HttpClientConfig { request_timeout_ms: 200, ..Default::default() };
+        let client = client(&base_url).with_client(http_client::builder_from(&config).build().unwrap());
+
+        let started = std::time::Instant::now();
+        let error = client.get_site_properties("site").await.expect_err("Slow Wix answered in time");
+        assert!(matches!(error, WixApiError::Timeout), "{:?}", error);
+        assert!(error.is_transient());
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    }
 }
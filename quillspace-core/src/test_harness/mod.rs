@@ -0,0 +1,308 @@
+//! Harness for tests that exercise the HTTP API against a real database.
+//!
+//! [`TestApp::start`] gives a test its own Postgres database with every migration and
+//! the row-level security policies applied, two seeded tenants with an admin each, and
+//! the application router with its full middleware stack. The application connects as
+//! the `quillspace` role, which is not a superuser, so the policies apply to it as they
+//! do in production.
+//!
+//! The database lives in a throwaway container, or, when `QUILLSPACE_TEST_POSTGRES_URL`
+//! names a server to use instead (as a superuser), in a fresh database on that server.
+//! With neither available `start` returns `None` and the test is skipped, like the other
+//! database tests.
+
+use crate::{
+    config::{AppConfig, DatabasePoolConfig},
+    create_app,
+    database::postgres::{create_pool, setup_rls},
+    types::{TenantId, UserRole},
+    AppState,
+};
+use axum::{
+    body::{to_bytes, Body},
+    extract::ConnectInfo,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use deadpool_postgres::Pool;
+use std::{net::SocketAddr, sync::Arc};
+use testcontainers_modules::{
+    postgres::Postgres,
+    testcontainers::{runners::AsyncRunner, ContainerAsync},
+};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+/// Superuser URL of a Postgres server to create test databases on instead of a container
+pub const SERVER_URL_ENV: &str = "QUILLSPACE_TEST_POSTGRES_URL";
+
+/// Role the application connects as, as in the docker-compose setup
+const APP_ROLE: &str = "quillspace";
+const APP_PASSWORD: &str = "quillspace";
+
+/// Applied after the first migration; see the file for why
+const WEB_BUILDER_SCHEMA: &str = include_str!("web_builder_schema.sql");
+
+/// A seeded user to make requests as
+#[derive(Debug, Clone)]
+pub struct TestUser {
+    pub id: Uuid,
+    pub tenant_id: TenantId,
+    pub email: String,
+    pub role: UserRole,
+}
+
+/// A seeded tenant and its admin
+#[derive(Debug, Clone)]
+pub struct TestTenant {
+    pub id: TenantId,
+    pub admin: TestUser,
+}
+
+/// Status and JSON body of a response; `Null` when the body is empty or not JSON
+#[derive(Debug)]
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub body: serde_json::Value,
+}
+
+pub struct TestApp {
+    pub state: AppState,
+    pub router: Router,
+    /// Superuser connections to the test database, for arranging data and checking it
+    /// without row-level security in the way
+    pub admin_pool: Pool,
+    pub tenant_a: TestTenant,
+    pub tenant_b: TestTenant,
+    _container: Option<ContainerAsync<Postgres>>,
+}
+
+impl TestApp {
+    /// Start the application on a fresh database, or `None` when no Postgres is available
+    pub async fn start() -> Option<Self> {
+        Self::start_with(|_| {}).await
+    }
+
+    /// Like [`TestApp::start`], with a changed configuration. Unlike [`TestApp::reconfigure`]
+    /// this reaches the services built at startup, such as the webhook handlers.
+    pub async fn start_with(configure: impl FnOnce(&mut AppConfig)) -> Option<Self> {
+        let (server_url, container) = match std::env::var(SERVER_URL_ENV) {
+            Ok(url) => (url, None),
+            Err(_) => match Postgres::default().start().await {
+                Ok(container) => {
+                    let host = container.get_host().await.expect("Failed to get container host");
+                    let port = container.get_host_port_ipv4(5432).await.expect("Failed to get container port");
+                    (format!("postgresql://postgres:postgres@{}:{}/postgres", host, port), Some(container))
+                }
+                Err(e) => {
+                    eprintln!("Skipping: no Postgres container ({}); set {} to use a server", e, SERVER_URL_ENV);
+                    return None;
+                }
+            },
+        };
+
+        let server: tokio_postgres::Config = server_url.parse().expect("Invalid Postgres server URL");
+        let database = format!("quillspace_test_{}", Uuid::new_v4().simple());
+        create_database(&server_url, &database).await;
+
+        let admin_url = database_url(&server, None, &database);
+        let admin_pool = create_pool(&admin_url, &DatabasePoolConfig::default())
+            .await
+            .expect("Failed to connect to test database");
+        migrate(&admin_pool).await;
+
+        let mut config = AppConfig::default();
+        config.database.url = database_url(&server, Some((APP_ROLE, APP_PASSWORD)), &database);
+        configure(&mut config);
+        let state = AppState::new(config).await.expect("Failed to build application state");
+        let router = create_app(state.clone()).await.expect("Failed to build router");
+
+        let tenant_a = seed_tenant(&admin_pool, "tenant-a").await;
+        let tenant_b = seed_tenant(&admin_pool, "tenant-b").await;
+
+        Some(Self { state, router, admin_pool, tenant_a, tenant_b, _container: container })
+    }
+
+    /// Rebuild the router with a changed configuration, keeping the database and tenants.
+    /// Only what handlers read from `state.config` changes; services built at startup keep
+    /// the configuration they were built with.
+    pub async fn reconfigure(mut self, configure: impl FnOnce(&mut AppConfig)) -> Self {
+        let mut config = (*self.state.config).clone();
+        configure(&mut config);
+        self.state.config = Arc::new(config);
+        self.router = create_app(self.state.clone()).await.expect("Failed to build router");
+        self
+    }
+
+    /// Add a user with `role` to a tenant
+    pub async fn add_user(&self, tenant_id: &TenantId, role: UserRole) -> TestUser {
+        insert_user(&self.admin_pool, tenant_id, role).await
+    }
+
+    /// An access token for `user`, as login would issue
+    pub fn token(&self, user: &TestUser) -> String {
+        self.state
+            .jwt_manager
+            .generate_token(
+                &user.id.to_string(),
+                &user.email,
+                "Test",
+                "User",
+                &user.role.to_string(),
+                &user.tenant_id.to_string(),
+            )
+            .expect("Failed to create test token")
+    }
+
+    /// A request authenticated as `user`, with a JSON body when one is given
+    pub fn request(&self, method: Method, uri: &str, user: &TestUser, body: Option<serde_json::Value>) -> Request<Body> {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", self.token(user)));
+        let mut request = match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        }
+        .expect("Invalid test request");
+
+        // Handlers that look at the peer address expect it, as the server provides it
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 0))));
+        request
+    }
+
+    /// Send a request through the router and middleware
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router.clone().oneshot(request).await.expect("Request failed");
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.expect("Failed to read response body");
+        let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+        TestResponse { status, body }
+    }
+
+    pub async fn get(&self, uri: &str, user: &TestUser) -> TestResponse {
+        self.send(self.request(Method::GET, uri, user, None)).await
+    }
+
+    pub async fn post(&self, uri: &str, user: &TestUser, body: serde_json::Value) -> TestResponse {
+        self.send(self.request(Method::POST, uri, user, Some(body))).await
+    }
+}
+
+/// Connection URL for `database` on the server, as the server's superuser unless
+/// `credentials` are given
+fn database_url(server: &tokio_postgres::Config, credentials: Option<(&str, &str)>, database: &str) -> String {
+    let host = match server.get_hosts().first() {
+        Some(tokio_postgres::config::Host::Tcp(host)) => host.clone(),
+        _ => "localhost".to_string(),
+    };
+    let port = server.get_ports().first().copied().unwrap_or(5432);
+    let (user, password) = match credentials {
+        Some((user, password)) => (user.to_string(), password.to_string()),
+        None => (
+            server.get_user().unwrap_or("postgres").to_string(),
+            server.get_password().map(|password| String::from_utf8_lossy(password).into_owned()).unwrap_or_default(),
+        ),
+    };
+    format!("postgresql://{}:{}@{}:{}/{}", user, password, host, port, database)
+}
+
+/// Create the application role if the server lacks it, and an empty database
+async fn create_database(server_url: &str, database: &str) {
+    let (client, connection) = tokio_postgres::connect(server_url, tokio_postgres::NoTls)
+        .await
+        .expect("Failed to connect to Postgres server");
+    tokio::spawn(connection);
+
+    client
+        .batch_execute(&format!(
+            "DO $$ BEGIN
+                 IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = '{role}') THEN
+                     CREATE ROLE {role} LOGIN PASSWORD '{password}';
+                 END IF;
+             END $$",
+            role = APP_ROLE,
+            password = APP_PASSWORD,
+        ))
+        .await
+        .expect("Failed to create application role");
+    // On its own, since CREATE DATABASE can't share an implicit transaction
+    client
+        .batch_execute(&format!("CREATE DATABASE {}", database))
+        .await
+        .expect("Failed to create test database");
+}
+
+/// Apply the migrations in order, then row-level security as startup does, and give the
+/// application role the access it has in production
+async fn migrate(pool: &Pool) {
+    let client = pool.get().await.expect("Failed to get connection");
+
+    let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations");
+    let mut migrations: Vec<_> = std::fs::read_dir(directory)
+        .expect("Failed to read migrations")
+        .map(|entry| entry.expect("Failed to read migration").path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "sql"))
+        .collect();
+    migrations.sort();
+
+    for (index, path) in migrations.iter().enumerate() {
+        let sql = std::fs::read_to_string(path).expect("Failed to read migration");
+        client
+            .batch_execute(&sql)
+            .await
+            .unwrap_or_else(|e| panic!("Migration {} failed: {}", path.display(), e));
+        if index == 0 {
+            client.batch_execute(WEB_BUILDER_SCHEMA).await.expect("Failed to create web builder schema");
+        }
+    }
+    drop(client);
+
+    setup_rls(pool).await.expect("Failed to set up row-level security");
+
+    pool.get()
+        .await
+        .expect("Failed to get connection")
+        .batch_execute(&format!(
+            "GRANT USAGE ON SCHEMA public TO {role};
+             GRANT SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA public TO {role};
+             GRANT USAGE, SELECT ON ALL SEQUENCES IN SCHEMA public TO {role};",
+            role = APP_ROLE,
+        ))
+        .await
+        .expect("Failed to grant access to the application role");
+}
+
+async fn seed_tenant(pool: &Pool, slug: &str) -> TestTenant {
+    let id = TenantId::new();
+    pool.get()
+        .await
+        .expect("Failed to get connection")
+        .execute(
+            "INSERT INTO tenants (id, name, slug) VALUES ($1, $2, $2)",
+            &[id.as_uuid(), &slug],
+        )
+        .await
+        .expect("Failed to seed tenant");
+
+    let admin = insert_user(pool, &id, UserRole::Admin).await;
+    TestTenant { id, admin }
+}
+
+async fn insert_user(pool: &Pool, tenant_id: &TenantId, role: UserRole) -> TestUser {
+    let id = Uuid::new_v4();
+    let email = format!("{}@example.test", id.simple());
+    pool.get()
+        .await
+        .expect("Failed to get connection")
+        .execute(
+            "INSERT INTO users (id, tenant_id, email, password_hash, first_name, last_name, role)
+             VALUES ($1, $2, $3, '!', 'Test', 'User', $4::text::user_role)",
+            &[&id, tenant_id.as_uuid(), &email, &role.to_string()],
+        )
+        .await
+        .expect("Failed to seed user");
+    TestUser { id, tenant_id: tenant_id.clone(), email, role }
+}
@@ -0,0 +1,158 @@
+-- Web builder schema the server relies on but the numbered migrations do not create.
+-- Deployed databases get it from the web builder setup; the test harness applies it
+-- right after 001_complete_setup.sql so the later migrations that reference it apply.
+
+-- Page templates; platform templates have no tenant
+CREATE TABLE IF NOT EXISTS templates (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID REFERENCES tenants(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    category VARCHAR(100) NOT NULL DEFAULT 'page',
+    html_source TEXT NOT NULL,
+    default_schema JSONB NOT NULL DEFAULT '{}',
+    preview_image_url TEXT,
+    is_public BOOLEAN NOT NULL DEFAULT false,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_templates_tenant_id ON templates(tenant_id);
+
+CREATE TABLE IF NOT EXISTS sites (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    template_id UUID,
+    custom_domain VARCHAR(255),
+    subdomain VARCHAR(100) NOT NULL UNIQUE,
+    is_published BOOLEAN NOT NULL DEFAULT false,
+    seo_settings JSONB NOT NULL DEFAULT '{}',
+    build_status VARCHAR(50) NOT NULL DEFAULT 'draft',
+    theme_config JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_sites_tenant_id ON sites(tenant_id);
+
+CREATE TABLE IF NOT EXISTS pages (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    site_id UUID NOT NULL REFERENCES sites(id) ON DELETE CASCADE,
+    slug VARCHAR(255) NOT NULL,
+    title VARCHAR(500) NOT NULL,
+    meta_description TEXT,
+    meta_keywords TEXT,
+    puck_data JSONB DEFAULT '{}',
+    template_id UUID,
+    template_version INTEGER NOT NULL DEFAULT 1,
+    draft_composition JSONB,
+    published_url TEXT,
+    published_etag TEXT,
+    is_published BOOLEAN NOT NULL DEFAULT false,
+    published_html TEXT,
+    published_at TIMESTAMPTZ,
+    preview_image_url TEXT,
+    preview_status VARCHAR(50) NOT NULL DEFAULT 'pending',
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (site_id, slug)
+);
+
+CREATE INDEX IF NOT EXISTS idx_pages_site_id ON pages(site_id);
+
+-- Uploaded files and media
+CREATE TABLE IF NOT EXISTS assets (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    site_id UUID REFERENCES sites(id) ON DELETE SET NULL,
+    filename VARCHAR(255) NOT NULL,
+    original_filename VARCHAR(255) NOT NULL,
+    mime_type VARCHAR(255) NOT NULL,
+    file_size BIGINT NOT NULL,
+    storage_path TEXT NOT NULL,
+    cdn_url TEXT,
+    alt_text TEXT,
+    is_optimized BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_assets_tenant_id ON assets(tenant_id);
+
+-- Client websites QuillSpace builds and manages on Wix
+CREATE TABLE IF NOT EXISTS user_wix_sites (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    wix_site_id VARCHAR(255) NOT NULL UNIQUE,
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    display_name VARCHAR(255),
+    custom_domain VARCHAR(255),
+    project_status VARCHAR(50) NOT NULL DEFAULT 'active',
+    service_type VARCHAR(50) NOT NULL DEFAULT 'full_service',
+    client_can_edit BOOLEAN NOT NULL DEFAULT true,
+    client_can_publish BOOLEAN NOT NULL DEFAULT false,
+    metadata JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_wix_sites_user_id ON user_wix_sites(user_id);
+
+-- Sites users connected to QuillSpace themselves, with their own builder credentials
+CREATE TABLE IF NOT EXISTS connected_websites (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    credentials_id UUID,
+    builder_type VARCHAR(50) NOT NULL,
+    external_site_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    url TEXT,
+    domain VARCHAR(255),
+    status VARCHAR(50) NOT NULL DEFAULT 'active',
+    last_sync TIMESTAMPTZ,
+    metadata JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, builder_type, external_site_id)
+);
+
+-- Content columns the server reads and writes, which 001_complete_setup.sql predates
+ALTER TABLE content ADD COLUMN IF NOT EXISTS body TEXT;
+ALTER TABLE content ADD COLUMN IF NOT EXISTS slug VARCHAR(255);
+ALTER TABLE content ADD COLUMN IF NOT EXISTS published_at TIMESTAMPTZ;
+
+-- Session-level tenant and user context read by the row-level security policies
+CREATE OR REPLACE FUNCTION set_tenant_context(p_tenant_id UUID) RETURNS VOID
+LANGUAGE sql AS $$
+    SELECT set_config('quillspace.tenant_id', p_tenant_id::text, false);
+$$;
+
+CREATE OR REPLACE FUNCTION set_user_context(p_user_id UUID) RETURNS VOID
+LANGUAGE sql AS $$
+    SELECT set_config('quillspace.user_id', p_user_id::text, false);
+$$;
+
+CREATE OR REPLACE FUNCTION generate_unique_subdomain(p_name TEXT) RETURNS TEXT
+LANGUAGE plpgsql AS $$
+DECLARE
+    base TEXT := trim(both '-' from regexp_replace(lower(p_name), '[^a-z0-9]+', '-', 'g'));
+    candidate TEXT;
+    suffix INTEGER := 0;
+BEGIN
+    IF base = '' THEN
+        base := 'site';
+    END IF;
+    candidate := base;
+    WHILE subdomain_taken(candidate) LOOP
+        suffix := suffix + 1;
+        candidate := base || '-' || suffix;
+    END LOOP;
+    RETURN candidate;
+END;
+$$;
@@ -103,14 +103,17 @@ pub struct User {
 }
 
 impl User {
-    /// Create User from database row
+    /// Create User from database row. The role is the `user_role` column itself, or
+    /// text where a function such as `authenticate_user` casts it.
     pub fn from_row(row: &tokio_postgres::Row) -> Result<Self, Box<dyn std::error::Error>> {
-        let role_str: String = row.try_get("role")?;
-        let role = match role_str.as_str() {
-            "admin" => UserRole::Admin,
-            "editor" => UserRole::Editor,
-            "viewer" => UserRole::Viewer,
-            _ => UserRole::Viewer, // Default fallback
+        let role = match row.try_get::<_, UserRole>("role") {
+            Ok(role) => role,
+            Err(_) => match row.try_get::<_, String>("role")?.as_str() {
+                "admin" => UserRole::Admin,
+                "editor" => UserRole::Editor,
+                "viewer" => UserRole::Viewer,
+                _ => UserRole::Viewer, // Default fallback
+            },
         };
 
         Ok(User {
@@ -160,21 +163,88 @@ pub struct Content {
     pub status: ContentStatus,
     pub author_id: Uuid,
     pub published_at: Option<DateTime<Utc>>,
+    /// When the content will be published automatically, if scheduled (UTC)
+    #[serde(default)]
+    pub scheduled_publish_at: Option<DateTime<Utc>>,
+    /// Language of this variant, e.g. `en-US`
+    pub locale: String,
+    /// Shared by all translations of the same post
+    pub translation_group_id: Uuid,
+    /// Lowercase keywords, used to find related posts
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Outcome of the latest review; cleared when the content is resubmitted or edited
+    pub review_decision: Option<ReviewDecision>,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_comment: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// One name in a piece of content's byline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentAuthor {
+    pub user_id: Uuid,
+    pub first_name: String,
+    pub last_name: String,
+    /// Byline position; the primary author (`Content.author_id`) is 0
+    pub position: i32,
+    pub is_primary: bool,
+}
+
+impl ContentAuthor {
+    pub fn display_name(&self) -> String {
+        format!("{} {}", self.first_name, self.last_name).trim().to_string()
+    }
+}
+
+/// Content together with its byline, as returned by the content API
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentWithAuthors {
+    #[serde(flatten)]
+    pub content: Content,
+    pub authors: Vec<ContentAuthor>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSql, FromSql)]
 #[postgres(name = "content_status")]
 pub enum ContentStatus {
     #[postgres(name = "draft")]
     Draft,
+    #[postgres(name = "pending_review")]
+    PendingReview,
     #[postgres(name = "published")]
     Published,
     #[postgres(name = "archived")]
     Archived,
 }
 
+/// A reviewer's verdict on content submitted for review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewDecision {
+    Approved,
+    Rejected,
+}
+
+impl ReviewDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewDecision::Approved => "approved",
+            ReviewDecision::Rejected => "rejected",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "approved" => Some(ReviewDecision::Approved),
+            "rejected" => Some(ReviewDecision::Rejected),
+            _ => None,
+        }
+    }
+}
+
 /// Analytics event for ClickHouse
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalyticsEvent {
@@ -189,6 +259,54 @@ pub struct AnalyticsEvent {
     pub user_agent: Option<String>,
 }
 
+impl AnalyticsEvent {
+    /// Build a page view event. With `anonymize` set (visitor has not consented)
+    /// the IP, user agent and user are dropped and the session id is replaced by
+    /// a tenant-salted hash so visits can still be counted without identifying anyone.
+    pub fn page_view(
+        tenant_id: &TenantId,
+        user_id: Option<Uuid>,
+        page_path: &str,
+        session_id: Option<String>,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+        anonymize: bool,
+    ) -> Self {
+        let now = Utc::now();
+        let mut event = Self {
+            event_id: Uuid::new_v4(),
+            tenant_id: *tenant_id.as_uuid(),
+            user_id,
+            event_type: "page_view".to_string(),
+            event_data: serde_json::json!({
+                "page_path": page_path,
+                "timestamp": now,
+                "anonymized": anonymize
+            }),
+            timestamp: now,
+            session_id,
+            ip_address,
+            user_agent,
+        };
+
+        if anonymize {
+            use sha2::{Digest, Sha256};
+
+            event.user_id = None;
+            event.ip_address = None;
+            event.user_agent = None;
+            event.session_id = event.session_id.map(|session_id| {
+                let mut hasher = Sha256::new();
+                hasher.update(tenant_id.as_uuid().as_bytes());
+                hasher.update(session_id.as_bytes());
+                hex::encode(hasher.finalize())
+            });
+        }
+
+        event
+    }
+}
+
 /// Request context containing tenant and user information
 #[derive(Debug, Clone)]
 pub struct RequestContext {
@@ -269,3 +387,71 @@ pub struct PaginatedResponse<T> {
     pub limit: u32,
     pub total_pages: u32,
 }
+
+/// One field of a partial update. A field left out of the request keeps its stored value,
+/// `null` clears it and any other value replaces it. Declare the field with
+/// `#[serde(default)]` so that leaving it out deserializes as `Keep`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Patch<T> {
+    #[default]
+    Keep,
+    Clear,
+    Set(T),
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patch<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Only called when the field is present, so `None` here was an explicit null
+        Ok(match Option::<T>::deserialize(deserializer)? {
+            Some(value) => Patch::Set(value),
+            None => Patch::Clear,
+        })
+    }
+}
+
+impl<T> Patch<T> {
+    /// The new column value, or `None` to leave the column alone
+    pub fn update(&self) -> Option<Option<&T>> {
+        match self {
+            Patch::Keep => None,
+            Patch::Clear => Some(None),
+            Patch::Set(value) => Some(Some(value)),
+        }
+    }
+
+    /// The value being set, if any
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Patch::Set(value) => Some(value),
+            Patch::Keep | Patch::Clear => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Update {
+        #[serde(default)]
+        meta_description: Patch<String>,
+    }
+
+    fn parse(json: &str) -> Patch<String> {
+        serde_json::from_str::<Update>(json).expect("Invalid update").meta_description
+    }
+
+    #[test]
+    fn test_patch_absent_null_and_value() {
+        assert_eq!(parse("{}"), Patch::Keep);
+        assert_eq!(parse("{}").update(), None);
+
+        assert_eq!(parse(r#"{"meta_description": null}"#), Patch::Clear);
+        assert_eq!(parse(r#"{"meta_description": null}"#).update(), Some(None));
+
+        let set = parse(r#"{"meta_description": "Author of Emma"}"#);
+        assert_eq!(set.update(), Some(Some(&"Author of Emma".to_string())));
+        assert_eq!(set.value().map(String::as_str), Some("Author of Emma"));
+    }
+}
@@ -30,7 +30,7 @@ SECURITY DEFINER
 AS $$
 BEGIN
     -- Set tenant context (required)
-    PERFORM set_config('app.current_tenant_id', tenant_id_param::text, true);
+    PERFORM set_config('quillspace.tenant_id', tenant_id_param::text, true);
     
     -- Set user context (optional)
     IF user_id_param IS NOT NULL THEN
@@ -49,7 +49,7 @@ LANGUAGE plpgsql
 SECURITY DEFINER
 AS $$
 BEGIN
-    PERFORM set_config('app.current_tenant_id', NULL, true);
+    PERFORM set_config('quillspace.tenant_id', NULL, true);
     PERFORM set_config('rls.user_id', NULL, true);
     RAISE DEBUG 'RLS context cleared';
 END;
@@ -62,7 +62,7 @@ LANGUAGE plpgsql
 STABLE
 AS $$
 BEGIN
-    RETURN current_setting('app.current_tenant_id', true)::UUID;
+    RETURN current_setting('quillspace.tenant_id', true)::UUID;
 EXCEPTION
     WHEN OTHERS THEN
         RETURN NULL;
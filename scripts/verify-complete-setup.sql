@@ -67,7 +67,7 @@ SELECT
     get_tenant_isolation_mode() as isolation_mode;
 
 -- Set Yasin's context and test
-SELECT set_config('app.current_tenant_id', '11111111-1111-1111-1111-111111111111', true);
+SELECT set_config('quillspace.tenant_id', '11111111-1111-1111-1111-111111111111', true);
 SELECT set_config('rls.user_id', 'bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb', true);
 
 SELECT 